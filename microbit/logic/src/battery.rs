@@ -0,0 +1,71 @@
+//! How full the batteries are, going by the supply voltage, and when it's
+//! time to warn that they're running out.
+//!
+//! Two fresh alkaline AAA cells make about 3.2 V, and most of their life is
+//! spent on the way down to about 2.2 V, after which they go down fast.
+//! Somewhere below 2 V the LEDs go dim, and at about 1.7 V the nRF52833
+//! resets itself, which looks as if the program had crashed. Over USB, the
+//! board's regulator makes 3.3 V, which counts as full.
+
+/// The supply voltage of full batteries, in millivolts.
+pub const FULL_MV: u32 = 3_000;
+/// The voltage of batteries that have nothing left worth mentioning.
+pub const EMPTY_MV: u32 = 2_200;
+/// How many bars a battery icon has, for [`bars`].
+pub const BARS: u32 = 4;
+
+/// How many of the [`BARS`] of a battery icon a supply of `mv` fills. Any
+/// voltage above [`EMPTY_MV`] fills at least one, so the icon only shows
+/// empty when the batteries are.
+pub fn bars(mv: u32) -> u32 {
+    if mv <= EMPTY_MV {
+        return 0;
+    }
+    let above = (mv - EMPTY_MV).min(FULL_MV - EMPTY_MV);
+    (above * BARS).div_ceil(FULL_MV - EMPTY_MV)
+}
+
+/// Decides when the batteries are low.
+///
+/// The voltage wobbles by a few millivolts from one measurement to the next,
+/// and drops for a moment whenever something draws a lot of current, like the
+/// speaker. A single threshold would turn the warning on and off over and
+/// over while the voltage is close to it. So the warning only goes off again
+/// once the voltage is back above the threshold by the *hysteresis*, and that
+/// takes new batteries.
+pub struct LowBattery {
+    threshold_mv: u32,
+    hysteresis_mv: u32,
+    low: bool,
+}
+
+impl LowBattery {
+    pub const fn new(threshold_mv: u32, hysteresis_mv: u32) -> LowBattery {
+        LowBattery {
+            threshold_mv,
+            hysteresis_mv,
+            low: false,
+        }
+    }
+
+    /// Take in the latest measurement, and say whether the batteries are low.
+    pub fn update(&mut self, mv: u32) -> bool {
+        if mv < self.threshold_mv {
+            self.low = true;
+        } else if mv >= self.threshold_mv + self.hysteresis_mv {
+            self.low = false;
+        }
+        self.low
+    }
+
+    pub fn is_low(&self) -> bool {
+        self.low
+    }
+}
+
+impl Default for LowBattery {
+    /// Warn at 2.4 V, which leaves a while to find new batteries.
+    fn default() -> LowBattery {
+        LowBattery::new(2_400, 200)
+    }
+}
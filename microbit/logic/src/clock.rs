@@ -0,0 +1,190 @@
+//! Telling the time of day with a clock that only counts up from start up.
+//!
+//! A [`WallClock`] remembers the time of day it was last set to, and the
+//! reading of the monotonic clock at that moment. The time right now is then
+//! just the time it was set to, plus however long it has been since.
+//!
+//! No clock is perfect. One that is 20 ppm (parts per million) fast gains
+//! about 1.7 seconds a day. Setting the clock again after a while tells how
+//! far off it had got, the [`Drift`], and from that how many ppm it's off.
+//! Giving the clock that number as its trim makes it count that much slower
+//! or faster from then on.
+
+use core::fmt;
+
+pub const SECS_PER_DAY: u32 = 24 * 60 * 60;
+const MS_PER_DAY: i64 = SECS_PER_DAY as i64 * 1_000;
+
+/// A time between 00:00:00 and 23:59:59.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TimeOfDay {
+    secs: u32,
+}
+
+impl TimeOfDay {
+    pub fn new(hours: u32, minutes: u32, seconds: u32) -> Option<TimeOfDay> {
+        if hours >= 24 || minutes >= 60 || seconds >= 60 {
+            return None;
+        }
+        Some(TimeOfDay {
+            secs: (hours * 60 + minutes) * 60 + seconds,
+        })
+    }
+
+    /// The time `secs` after midnight, going around to the next day if there
+    /// are more than a day's worth.
+    pub fn from_secs(secs: u32) -> TimeOfDay {
+        TimeOfDay {
+            secs: secs % SECS_PER_DAY,
+        }
+    }
+
+    /// Read `HH:MM` or `HH:MM:SS`.
+    pub fn parse(text: &str) -> Option<TimeOfDay> {
+        let mut fields = text.split(':').map(|field| {
+            if field.is_empty() || field.len() > 2 {
+                return None;
+            }
+            field.parse::<u32>().ok()
+        });
+        let hours = fields.next()??;
+        let minutes = fields.next()??;
+        let seconds = fields.next().unwrap_or(Some(0))?;
+        if fields.next().is_some() {
+            return None;
+        }
+        TimeOfDay::new(hours, minutes, seconds)
+    }
+
+    pub fn as_secs(self) -> u32 {
+        self.secs
+    }
+
+    pub fn hours(self) -> u32 {
+        self.secs / 3600
+    }
+
+    pub fn minutes(self) -> u32 {
+        self.secs / 60 % 60
+    }
+
+    pub fn seconds(self) -> u32 {
+        self.secs % 60
+    }
+}
+
+/// `HH:MM:SS`
+impl fmt::Display for TimeOfDay {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:02}:{:02}:{:02}",
+            self.hours(),
+            self.minutes(),
+            self.seconds()
+        )
+    }
+}
+
+/// How far off the clock was when it was set again.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Drift {
+    /// How far ahead the clock was, in milliseconds. Negative if it was
+    /// behind.
+    pub error_ms: i64,
+    /// How long it had been running since it was set before.
+    pub over_ms: u64,
+}
+
+impl Drift {
+    /// How far off the clock is, in ppm. Positive if it runs fast.
+    pub fn ppm(&self) -> i32 {
+        if self.over_ms == 0 {
+            return 0;
+        }
+        (self.error_ms * 1_000_000 / self.over_ms as i64) as i32
+    }
+}
+
+/// The time of day, worked out from a clock that counts milliseconds since
+/// start up.
+pub struct WallClock {
+    /// The monotonic clock when the time was set.
+    set_at_ms: u64,
+    set_to: TimeOfDay,
+    /// Whether anybody set it yet, or it's just counting from midnight.
+    set: bool,
+    trim_ppm: i32,
+}
+
+impl WallClock {
+    /// A clock that starts at midnight, untrimmed.
+    pub const fn new() -> WallClock {
+        WallClock {
+            set_at_ms: 0,
+            set_to: TimeOfDay { secs: 0 },
+            set: false,
+            trim_ppm: 0,
+        }
+    }
+
+    /// The time of day when the monotonic clock reads `now_ms`.
+    pub fn now(&self, now_ms: u64) -> TimeOfDay {
+        TimeOfDay::from_secs((self.ms_of_day(now_ms) / 1_000) as u32)
+    }
+
+    /// The clock says `time` at `now_ms`. If it had been set before, this
+    /// tells how far off it had got since then.
+    pub fn set(&mut self, now_ms: u64, time: TimeOfDay) -> Option<Drift> {
+        let drift = if self.set {
+            let mut error_ms = self.ms_of_day(now_ms) - i64::from(time.secs) * 1_000;
+            // Minutes too fast just before midnight, not a day behind
+            if error_ms > MS_PER_DAY / 2 {
+                error_ms -= MS_PER_DAY;
+            } else if error_ms < -MS_PER_DAY / 2 {
+                error_ms += MS_PER_DAY;
+            }
+            Some(Drift {
+                error_ms,
+                over_ms: now_ms.saturating_sub(self.set_at_ms),
+            })
+        } else {
+            None
+        };
+        self.set_at_ms = now_ms;
+        self.set_to = time;
+        self.set = true;
+        drift
+    }
+
+    /// How many ppm faster the clock counts than the monotonic one, or
+    /// slower if negative.
+    pub fn trim_ppm(&self) -> i32 {
+        self.trim_ppm
+    }
+
+    /// Count `ppm` faster from now on. A clock that turned out to be 20 ppm
+    /// fast needs 20 less than the trim it had.
+    ///
+    /// The time it shows right now stays the same.
+    pub fn set_trim_ppm(&mut self, now_ms: u64, ppm: i32) {
+        let ms_of_day = self.ms_of_day(now_ms);
+        self.set_to = TimeOfDay::from_secs((ms_of_day / 1_000) as u32);
+        // Keep the part of a second that had passed, too
+        self.set_at_ms = now_ms.saturating_sub((ms_of_day % 1_000) as u64);
+        self.trim_ppm = ppm;
+    }
+
+    /// Milliseconds since midnight, at `now_ms`.
+    fn ms_of_day(&self, now_ms: u64) -> i64 {
+        let elapsed = now_ms.saturating_sub(self.set_at_ms) as i64;
+        let trimmed = elapsed + elapsed * i64::from(self.trim_ppm) / 1_000_000;
+        (i64::from(self.set_to.secs) * 1_000 + trimmed).rem_euclid(MS_PER_DAY)
+    }
+}
+
+impl Default for WallClock {
+    fn default() -> Self {
+        WallClock::new()
+    }
+}
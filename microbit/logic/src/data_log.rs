@@ -0,0 +1,79 @@
+//! The samples of the data logger, and turning them into CSV.
+//!
+//! Every sample is kept as one record of the flash log: the milliseconds
+//! since the recording started, and the acceleration along the three axes,
+//! in mg. The timestamps start over with every recording, and every time the
+//! logger comes back from a reset or a flat battery and takes up the
+//! recording again. [`rows`] uses that to tell the recordings, the *runs*,
+//! apart: a timestamp that's no later than the one before starts a new run.
+
+use core::fmt;
+
+/// The rates the logger can record at, in samples per second. These are the
+/// ones the accelerometer can measure at, up to the point where erasing a
+/// page of flash, which takes up to 85 ms, stalls the logger for less than a
+/// handful of samples.
+pub const RATES_HZ: [u32; 4] = [1, 10, 25, 50];
+
+/// The first line of the CSV, naming the columns of [`Row`].
+pub const CSV_HEADER: &str = "run,ms,x,y,z";
+
+/// One measurement of the accelerometer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Sample {
+    /// Since the recording started.
+    pub ms: u32,
+    pub x: i16,
+    pub y: i16,
+    pub z: i16,
+}
+
+impl Sample {
+    /// The sample as a record of the flash log.
+    pub fn to_record(self) -> [u32; 4] {
+        [self.ms, self.x as u32, self.y as u32, self.z as u32]
+    }
+
+    pub fn from_record(record: [u32; 4]) -> Sample {
+        let [ms, x, y, z] = record;
+        Sample {
+            ms,
+            x: x as i16,
+            y: y as i16,
+            z: z as i16,
+        }
+    }
+}
+
+/// A line of the CSV, a sample and the run it belongs to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Row {
+    /// Counting from 1, the oldest run still in the log.
+    pub run: u32,
+    pub sample: Sample,
+}
+
+/// `run,ms,x,y,z`, without a line ending.
+impl fmt::Display for Row {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Sample { ms, x, y, z } = self.sample;
+        write!(f, "{},{},{},{},{}", self.run, ms, x, y, z)
+    }
+}
+
+/// The records of the flash log, from the oldest to the newest, as rows of
+/// the CSV.
+pub fn rows(records: impl Iterator<Item = [u32; 4]>) -> impl Iterator<Item = Row> {
+    records
+        .map(Sample::from_record)
+        .scan(None, |last: &mut Option<Row>, sample| {
+            let run = match *last {
+                Some(row) if sample.ms > row.sample.ms => row.run,
+                Some(row) => row.run + 1,
+                None => 1,
+            };
+            let row = Row { run, sample };
+            *last = Some(row);
+            Some(row)
+        })
+}
@@ -0,0 +1,355 @@
+//! Updating a program over the serial port, *device firmware update*: what
+//! the computer and the bootloader say to each other, and what the bootloader
+//! does with it.
+//!
+//! The computer sends a [`Request`] and waits for the [`Response`] before it
+//! sends the next one. First comes [`Request::Start`], with the length of the
+//! new program and its CRC, then the program itself, [`CHUNK_LEN`] bytes at a
+//! time, and last [`Request::Finish`]. Every chunk is answered with the
+//! offset of the next one the bootloader wants, so a chunk or an answer that
+//! got lost on the way is simply sent again. While the flash is being erased
+//! or written the CPU stops, and bytes that arrive just then would be lost,
+//! but since the computer waits for the answer, nothing arrives.
+//!
+//! Each request and response goes in the payload of a [`DFU`](crate::packet::types::DFU)
+//! packet, so a garbled one is caught by the packet's CRC and dropped, and
+//! the computer sends it again after a while.
+//!
+//! A program that's only half written, because the cable came out or the
+//! computer stopped, mustn't be started. So [`Updater`] keeps back the first
+//! two words of it, the initial stack pointer and the reset vector, and only
+//! writes them once the whole program is in the flash and its CRC checks
+//! out. Until then, the first word reads as erased, and that's how the
+//! bootloader knows there's no program to start.
+
+use core::convert::TryInto;
+use heapless::Vec;
+
+/// How many bytes of the program go in a chunk. Every chunk is this long,
+/// but the last one, which has what's left.
+pub const CHUNK_LEN: usize = 128;
+
+/// The longest request, a whole chunk with its tag and offset.
+pub const MAX_REQUEST_LEN: usize = 1 + 4 + CHUNK_LEN;
+/// The longest response.
+pub const MAX_RESPONSE_LEN: usize = 1 + 4;
+
+/// What erased flash reads as.
+const ERASED: u32 = 0xffff_ffff;
+/// The words at the start of the program that are written last.
+const HEAD_WORDS: usize = 2;
+
+/// What the computer asks for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Request<'a> {
+    /// A new program of `len` bytes is coming, with `crc` as its
+    /// [`crc32`].
+    Start { len: u32, crc: u32 },
+    /// The bytes of the program from `offset` on.
+    Chunk { offset: u32, data: &'a [u8] },
+    /// That was all of it, check it and start it.
+    Finish,
+}
+
+/// What the bootloader answers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Response {
+    /// Send the chunk at this offset next.
+    Next(u32),
+    /// The program is all there and checks out.
+    Done,
+    /// Something went wrong, and the update has to start over.
+    Failed(Failure),
+}
+
+/// Why an update failed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Failure {
+    /// The program doesn't fit into the flash, or is too short to be one.
+    BadLength,
+    /// A chunk or the finish came without a start.
+    NotStarted,
+    /// A chunk that's longer or shorter than it should be.
+    BadChunk,
+    /// The finish came before all of the program.
+    Incomplete,
+    /// The program in the flash isn't the one the start promised.
+    BadCrc,
+}
+
+impl Failure {
+    const ALL: [Failure; 5] = [
+        Failure::BadLength,
+        Failure::NotStarted,
+        Failure::BadChunk,
+        Failure::Incomplete,
+        Failure::BadCrc,
+    ];
+}
+
+impl Request<'_> {
+    const START: u8 = b'S';
+    const CHUNK: u8 = b'C';
+    const FINISH: u8 = b'F';
+
+    /// The request as bytes, with the numbers little endian.
+    pub fn encode(&self) -> Vec<u8, MAX_REQUEST_LEN> {
+        let mut bytes = Vec::new();
+        let parts: &[&[u8]] = match self {
+            Request::Start { len, crc } => {
+                &[&[Request::START], &len.to_le_bytes(), &crc.to_le_bytes()]
+            }
+            Request::Chunk { offset, data } => &[&[Request::CHUNK], &offset.to_le_bytes(), data],
+            Request::Finish => &[&[Request::FINISH]],
+        };
+        for part in parts {
+            // Only a chunk longer than `CHUNK_LEN` wouldn't fit, and that
+            // isn't a request the other end would take anyway
+            let _ = bytes.extend_from_slice(part);
+        }
+        bytes
+    }
+
+    /// The request in `bytes`, or `None` if they don't make sense.
+    pub fn decode(bytes: &[u8]) -> Option<Request<'_>> {
+        let (&kind, rest) = bytes.split_first()?;
+        match kind {
+            Request::START if rest.len() == 8 => Some(Request::Start {
+                len: u32::from_le_bytes(rest[..4].try_into().ok()?),
+                crc: u32::from_le_bytes(rest[4..].try_into().ok()?),
+            }),
+            Request::CHUNK if (4..=4 + CHUNK_LEN).contains(&rest.len()) => {
+                let (offset, data) = rest.split_at(4);
+                Some(Request::Chunk {
+                    offset: u32::from_le_bytes(offset.try_into().ok()?),
+                    data,
+                })
+            }
+            Request::FINISH if rest.is_empty() => Some(Request::Finish),
+            _ => None,
+        }
+    }
+}
+
+impl Response {
+    const NEXT: u8 = b'N';
+    const DONE: u8 = b'D';
+    const FAILED: u8 = b'X';
+
+    pub fn encode(&self) -> Vec<u8, MAX_RESPONSE_LEN> {
+        let mut bytes = Vec::new();
+        let parts: &[&[u8]] = match self {
+            Response::Next(offset) => &[&[Response::NEXT], &offset.to_le_bytes()],
+            Response::Done => &[&[Response::DONE]],
+            Response::Failed(failure) => &[&[Response::FAILED, *failure as u8]],
+        };
+        for part in parts {
+            // They're all short enough
+            let _ = bytes.extend_from_slice(part);
+        }
+        bytes
+    }
+
+    /// The response in `bytes`, or `None` if they don't make sense.
+    pub fn decode(bytes: &[u8]) -> Option<Response> {
+        let (&kind, rest) = bytes.split_first()?;
+        match (kind, rest) {
+            (Response::NEXT, _) => Some(Response::Next(u32::from_le_bytes(rest.try_into().ok()?))),
+            (Response::DONE, []) => Some(Response::Done),
+            (Response::FAILED, &[failure]) => Failure::ALL
+                .iter()
+                .copied()
+                .find(|&known| known as u8 == failure)
+                .map(Response::Failed),
+            _ => None,
+        }
+    }
+}
+
+/// CRC-32, the one zip files and Ethernet use: the polynomial 0x04C11DB7,
+/// with the bits reversed, starting from all ones and inverted at the end.
+/// It's computed a piece at a time, since the bootloader never has the whole
+/// program in RAM.
+#[derive(Clone, Copy, Debug)]
+pub struct Crc32 {
+    value: u32,
+}
+
+impl Crc32 {
+    pub const fn new() -> Crc32 {
+        Crc32 { value: 0xffff_ffff }
+    }
+
+    pub fn update(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.value ^= u32::from(byte);
+            for _ in 0..8 {
+                self.value = if self.value & 1 != 0 {
+                    self.value >> 1 ^ 0xedb8_8320
+                } else {
+                    self.value >> 1
+                };
+            }
+        }
+    }
+
+    /// The CRC of all the bytes so far.
+    pub fn finish(&self) -> u32 {
+        !self.value
+    }
+}
+
+impl Default for Crc32 {
+    fn default() -> Crc32 {
+        Crc32::new()
+    }
+}
+
+/// The CRC-32 of `bytes`, all at once.
+pub fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = Crc32::new();
+    crc.update(bytes);
+    crc.finish()
+}
+
+/// The part of the flash the program goes into, with offsets from its start.
+/// The bootloader gets at it through the `NVMC`, the tests with an array.
+pub trait ImageFlash {
+    /// Erase the page that starts at `offset`.
+    fn erase_page(&mut self, offset: usize);
+    /// Write `words` from `offset` on, which has to be erased.
+    fn write(&mut self, offset: usize, words: &[u32]);
+    /// Read the word at `offset`.
+    fn read(&self, offset: usize) -> u32;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum State {
+    Idle,
+    /// A program of `len` bytes is coming, and `written` of them are in the
+    /// flash, or held back.
+    Receiving {
+        len: u32,
+        crc: u32,
+        written: u32,
+    },
+}
+
+/// The bootloader's side of an update: takes the requests, and writes the
+/// program into an [`ImageFlash`].
+pub struct Updater {
+    capacity: u32,
+    page_size: u32,
+    state: State,
+    /// The first words of the program, until it checks out.
+    head: [u32; HEAD_WORDS],
+}
+
+impl Updater {
+    /// An updater for a flash that has room for `capacity` bytes, in pages
+    /// of `page_size`.
+    pub const fn new(capacity: u32, page_size: u32) -> Updater {
+        Updater {
+            capacity,
+            page_size,
+            state: State::Idle,
+            head: [ERASED; HEAD_WORDS],
+        }
+    }
+
+    /// Do what `request` asks for, and say what to send back. A
+    /// [`Request::Start`] starts over at any time.
+    pub fn handle(&mut self, request: &Request, flash: &mut impl ImageFlash) -> Response {
+        let response = match (*request, self.state) {
+            (Request::Start { len, crc }, _) => {
+                if len < (HEAD_WORDS * 4) as u32 || len > self.capacity {
+                    Response::Failed(Failure::BadLength)
+                } else {
+                    self.state = State::Receiving {
+                        len,
+                        crc,
+                        written: 0,
+                    };
+                    self.head = [ERASED; HEAD_WORDS];
+                    Response::Next(0)
+                }
+            }
+            (Request::Chunk { offset, data }, State::Receiving { len, crc, written }) => {
+                // A chunk sent again, because its answer got lost, or one
+                // after a lost one: either way, ask for the one that's next
+                if offset != written {
+                    return Response::Next(written);
+                }
+                let expected = (len - written).min(CHUNK_LEN as u32);
+                if data.len() as u32 != expected {
+                    Response::Failed(Failure::BadChunk)
+                } else {
+                    self.write_chunk(offset, data, flash);
+                    let written = written + expected;
+                    self.state = State::Receiving { len, crc, written };
+                    Response::Next(written)
+                }
+            }
+            (Request::Finish, State::Receiving { len, crc, written }) => {
+                if written != len {
+                    Response::Failed(Failure::Incomplete)
+                } else if self.crc(len, flash) != crc {
+                    Response::Failed(Failure::BadCrc)
+                } else {
+                    flash.write(0, &self.head);
+                    Response::Done
+                }
+            }
+            (_, State::Idle) => Response::Failed(Failure::NotStarted),
+        };
+        if let Response::Failed(_) | Response::Done = response {
+            self.state = State::Idle;
+        }
+        response
+    }
+
+    fn write_chunk(&mut self, offset: u32, data: &[u8], flash: &mut impl ImageFlash) {
+        // Chunks are whole words, but the last one, which is padded with
+        // what erased flash reads as
+        let mut words = [ERASED; CHUNK_LEN / 4];
+        for (word, bytes) in words.iter_mut().zip(data.chunks(4)) {
+            let mut padded = [0xff; 4];
+            padded[..bytes.len()].copy_from_slice(bytes);
+            *word = u32::from_le_bytes(padded);
+        }
+        let words = &words[..data.len().div_ceil(4)];
+
+        // Every page is erased when the first chunk that goes in it arrives,
+        // the first one before anything else, so that from then on there's
+        // no program to start
+        let end = offset + data.len() as u32;
+        let mut page = offset.div_ceil(self.page_size) * self.page_size;
+        while page < end {
+            flash.erase_page(page as usize);
+            page += self.page_size;
+        }
+
+        let mut start = 0;
+        while offset as usize + start * 4 < HEAD_WORDS * 4 && start < words.len() {
+            self.head[offset as usize / 4 + start] = words[start];
+            start += 1;
+        }
+        flash.write(offset as usize + start * 4, &words[start..]);
+    }
+
+    /// The CRC of the program as it is in the flash, with the head that's
+    /// held back.
+    fn crc(&self, len: u32, flash: &impl ImageFlash) -> u32 {
+        let mut crc = Crc32::new();
+        for offset in (0..len as usize).step_by(4) {
+            let word = if offset < HEAD_WORDS * 4 {
+                self.head[offset / 4]
+            } else {
+                flash.read(offset)
+            };
+            let bytes = word.to_le_bytes();
+            crc.update(&bytes[..(len as usize - offset).min(4)]);
+        }
+        crc.finish()
+    }
+}
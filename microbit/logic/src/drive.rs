@@ -0,0 +1,92 @@
+//! Driving a robot with two wheels by tilting the micro:bit, and getting the
+//! motors up to speed gently.
+//!
+//! A robot like that steers by turning its wheels at different speeds, with
+//! a motor for each side. Both forward at the same speed go straight ahead,
+//! one forward and one back turn it on the spot. [`from_tilt`] makes the two
+//! speeds out of how far the board leans: forward and back for driving,
+//! left and right for steering.
+//!
+//! A motor that's started at full speed from standing still draws a lot more
+//! current than while it's running, for a moment. The batteries' voltage
+//! drops, and if it drops far enough, the micro:bit resets. A [`Ramp`] makes
+//! the speed change a step at a time instead, so the motors speed up over a
+//! fraction of a second.
+
+/// The speed of a motor going forward as fast as it can. Going back as fast
+/// as it can is `-MAX_SPEED`.
+pub const MAX_SPEED: i16 = 1_000;
+
+/// How far the board has to lean, in mg, before the robot starts to move, so
+/// that it stands still while the board lies more or less flat.
+pub const DEAD_ZONE_MG: i32 = 150;
+/// How far it has to lean to go at full speed. That's about 35°.
+pub const FULL_TILT_MG: i32 = 600;
+
+/// The speed for a lean of `mg`, with the sign of it.
+fn lean(mg: i32) -> i32 {
+    let beyond = mg.abs() - DEAD_ZONE_MG;
+    if beyond <= 0 {
+        return 0;
+    }
+    let speed = beyond.min(FULL_TILT_MG - DEAD_ZONE_MG) * i32::from(MAX_SPEED)
+        / (FULL_TILT_MG - DEAD_ZONE_MG);
+    speed * mg.signum()
+}
+
+/// The speeds of the left and the right motor, for a board that leans `x_mg`
+/// to the right and `y_mg` forward, as the accelerometer measures them.
+///
+/// Steering takes speed from one side and gives it to the other. Where that
+/// would be faster than [`MAX_SPEED`], both slow down by the same factor, so
+/// the robot still turns as tightly as it was told to.
+pub fn from_tilt(x_mg: i32, y_mg: i32) -> (i16, i16) {
+    let forward = lean(y_mg);
+    let turn = lean(x_mg);
+    let (left, right) = (forward + turn, forward - turn);
+    let fastest = left.abs().max(right.abs());
+    let max = i32::from(MAX_SPEED);
+    if fastest <= max {
+        (left as i16, right as i16)
+    } else {
+        (
+            (left * max / fastest) as i16,
+            (right * max / fastest) as i16,
+        )
+    }
+}
+
+/// Changes a speed towards a target by at most a step at a time.
+pub struct Ramp {
+    step: i16,
+    speed: i16,
+}
+
+impl Ramp {
+    /// A ramp that starts at standing still, and changes the speed by at most
+    /// `step` for every [`Ramp::update`].
+    pub const fn new(step: i16) -> Ramp {
+        Ramp { step, speed: 0 }
+    }
+
+    /// Go one step towards `target`, and say what the speed is now. Going
+    /// from forward to back slows down to standing still on the way, since
+    /// reversing a motor that's running draws even more current than
+    /// starting it.
+    pub fn update(&mut self, target: i16) -> i16 {
+        let target = target.clamp(-MAX_SPEED, MAX_SPEED);
+        let change = (target - self.speed).clamp(-self.step, self.step);
+        self.speed += change;
+        self.speed
+    }
+
+    pub fn speed(&self) -> i16 {
+        self.speed
+    }
+
+    /// Stand still right away, for when the motors have been stopped some
+    /// other way.
+    pub fn reset(&mut self) {
+        self.speed = 0;
+    }
+}
@@ -0,0 +1,88 @@
+//! What the fault status registers of a Cortex-M4 say about a HardFault.
+//!
+//! When the CPU can't carry on, it raises a fault, and notes down why in the
+//! bits of a few registers of the `SCB`. `CFSR`, the *configurable fault
+//! status register*, has the reasons of the three kinds of fault that can be
+//! switched on and off: the memory management fault, the bus fault, and the
+//! usage fault. They're all off after reset, so each of them ends up as a
+//! HardFault instead, and `HFSR`, the *HardFault status register*, says so
+//! with its `FORCED` bit. For some faults, `BFAR` or `MMFAR` hold the address
+//! that was read or written, but only if the bit in `CFSR` that says so is
+//! set.
+//!
+//! The Cortex-M0 of the micro:bit v1 has none of these registers.
+
+/// A bit of `CFSR` or `HFSR`: its name in Arm's manuals, and what it means.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Flag {
+    pub bit: u32,
+    pub name: &'static str,
+    pub meaning: &'static str,
+}
+
+const fn flag(bit: u32, name: &'static str, meaning: &'static str) -> Flag {
+    Flag { bit, name, meaning }
+}
+
+/// The bits of `CFSR` that mean something. Dividing by zero and unaligned
+/// accesses only fault once they've been switched on in `CCR`.
+#[rustfmt::skip]
+pub const CFSR_FLAGS: [Flag; 19] = [
+    flag(0, "IACCVIOL", "fetched an instruction from where the MPU forbids it"),
+    flag(1, "DACCVIOL", "read or wrote where the MPU forbids it"),
+    flag(3, "MUNSTKERR", "the MPU stopped unstacking after an exception"),
+    flag(4, "MSTKERR", "the MPU stopped stacking for an exception"),
+    flag(5, "MLSPERR", "the MPU stopped stacking the FPU registers"),
+    flag(7, "MMARVALID", "MMFAR holds the address"),
+    flag(8, "IBUSERR", "fetched an instruction from where there's no memory"),
+    flag(9, "PRECISERR", "read or wrote where there's no memory"),
+    flag(10, "IMPRECISERR", "wrote where there's no memory, a few instructions before the pc"),
+    flag(11, "UNSTKERR", "unstacking after an exception hit a bus error"),
+    flag(12, "STKERR", "stacking for an exception hit a bus error, the stack may be full"),
+    flag(13, "LSPERR", "stacking the FPU registers hit a bus error"),
+    flag(15, "BFARVALID", "BFAR holds the address"),
+    flag(16, "UNDEFINSTR", "ran into something that isn't an instruction"),
+    flag(17, "INVSTATE", "jumped to an address without the Thumb bit"),
+    flag(18, "INVPC", "returned from an exception to a bad address"),
+    flag(19, "NOCP", "used the FPU while it's switched off"),
+    flag(24, "UNALIGNED", "read or wrote a word that isn't aligned"),
+    flag(25, "DIVBYZERO", "divided by zero"),
+];
+
+/// The bits of `HFSR` that mean something.
+#[rustfmt::skip]
+pub const HFSR_FLAGS: [Flag; 3] = [
+    flag(1, "VECTTBL", "reading the vector table failed"),
+    flag(30, "FORCED", "a fault that's switched off, see CFSR"),
+    flag(31, "DEBUGEVT", "a breakpoint with no debugger attached"),
+];
+
+const MMARVALID: u32 = 1 << 7;
+const BFARVALID: u32 = 1 << 15;
+
+/// The flags that are set in `cfsr`.
+pub fn cfsr_flags(cfsr: u32) -> impl Iterator<Item = Flag> {
+    set_in(&CFSR_FLAGS, cfsr)
+}
+
+/// The flags that are set in `hfsr`.
+pub fn hfsr_flags(hfsr: u32) -> impl Iterator<Item = Flag> {
+    set_in(&HFSR_FLAGS, hfsr)
+}
+
+fn set_in(flags: &'static [Flag], register: u32) -> impl Iterator<Item = Flag> {
+    flags
+        .iter()
+        .copied()
+        .filter(move |flag| register & 1 << flag.bit != 0)
+}
+
+/// Whether `BFAR` holds the address of the bus fault that `cfsr` is about.
+pub fn bfar_valid(cfsr: u32) -> bool {
+    cfsr & BFARVALID != 0
+}
+
+/// Whether `MMFAR` holds the address of the memory management fault.
+pub fn mmfar_valid(cfsr: u32) -> bool {
+    cfsr & MMARVALID != 0
+}
@@ -0,0 +1,63 @@
+//! Turning the counts of a rotary encoder into a value that goes up and down
+//! with the knob, one step for every click.
+//!
+//! A rotary encoder has two switches, A and B, that open and close a quarter
+//! of a cycle apart while the knob turns, and which one goes first says which
+//! way the knob is going. The `QDEC` of the nRF52833 watches the two for us,
+//! and counts up or down at every change. Most knobs have a *detent*, a click
+//! you can feel, every so many of those counts, and nobody wants the value to
+//! change in between two clicks.
+
+/// A value between two limits that a knob turns up and down.
+pub struct Knob {
+    min: i32,
+    max: i32,
+    counts_per_detent: i32,
+    value: i32,
+    /// The counts towards the next step, either way.
+    partial: i32,
+}
+
+impl Knob {
+    /// A knob for values from `min` to `max`, starting at `min`, that takes
+    /// `counts_per_detent` counts of the encoder for a step.
+    pub const fn new(min: i32, max: i32, counts_per_detent: i32) -> Knob {
+        Knob {
+            min,
+            max,
+            counts_per_detent,
+            value: min,
+            partial: 0,
+        }
+    }
+
+    /// Take in the counts since the last time, up for clockwise, and say
+    /// whether that changed the value.
+    ///
+    /// Turning on past either end does nothing, and the counts it takes
+    /// aren't kept either, so turning back changes the value right away,
+    /// however far the knob had been turned on.
+    pub fn turn(&mut self, counts: i32) -> bool {
+        self.partial += counts;
+        let steps = self.partial / self.counts_per_detent;
+        self.partial %= self.counts_per_detent;
+
+        let value = (self.value + steps).clamp(self.min, self.max);
+        if value != self.value + steps {
+            self.partial = 0;
+        }
+        let changed = value != self.value;
+        self.value = value;
+        changed
+    }
+
+    pub fn value(&self) -> i32 {
+        self.value
+    }
+
+    /// Set the value, for when something other than the knob changes it.
+    pub fn set_value(&mut self, value: i32) {
+        self.value = value.clamp(self.min, self.max);
+        self.partial = 0;
+    }
+}
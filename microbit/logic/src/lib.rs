@@ -0,0 +1,45 @@
+//! The parts of the chapters that don't need a micro:bit.
+//!
+//! Nothing in here touches the hardware, so it builds for the host just as
+//! well as for the micro:bit, and `cargo test -p discovery-logic` runs its
+//! tests on your computer. The chapters keep the hardware side, and hand
+//! anything that only computes something to this crate.
+
+#![cfg_attr(not(test), no_std)]
+
+pub mod battery;
+pub mod clock;
+pub mod data_log;
+pub mod dfu;
+pub mod drive;
+pub mod fault_status;
+pub mod knob;
+pub mod life;
+pub mod lsm303_registers;
+pub mod maze;
+pub mod metal_detector;
+pub mod morse;
+pub mod ndef;
+pub mod packet;
+pub mod pairing;
+pub mod pong;
+pub mod prbs;
+pub mod pulse;
+pub mod register_map;
+pub mod roulette;
+pub mod snake;
+pub mod sniffer;
+pub mod stopwatch;
+pub mod temperature_log;
+pub mod type2_tag;
+
+// This one stays in its chapter, where the book walks through it and other
+// programs include it by path. It's only built here to be tested.
+#[cfg(test)]
+#[path = "../../src/07-uart/src/line_reader.rs"]
+// It has a `v2` feature to sleep between keystrokes, which this crate
+// doesn't need
+#[allow(unexpected_cfgs)]
+mod line_reader;
+#[cfg(test)]
+mod tests;
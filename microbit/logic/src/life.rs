@@ -0,0 +1,58 @@
+//! Conway's Game of Life, on a grid the size of the LED matrix.
+//!
+//! Every cell is alive or dead. From one generation to the next, a live cell
+//! with two or three live neighbours stays alive, a dead cell with exactly
+//! three comes to life, and every other cell is dead. The grid wraps around:
+//! the cells on the right edge are neighbours of the ones on the left edge,
+//! and the top row of the bottom one, so every cell has eight neighbours.
+
+pub const SIZE: usize = 5;
+
+/// Which cells are alive, by row and column, the same way round as the LEDs.
+pub type Grid = [[bool; SIZE]; SIZE];
+
+/// How many of the eight cells around `row` and `col` are alive.
+pub fn neighbours(grid: &Grid, row: usize, col: usize) -> usize {
+    let mut alive = 0;
+    // Adding `SIZE - 1` is going back one, without going below 0
+    for row_step in [SIZE - 1, 0, 1] {
+        for col_step in [SIZE - 1, 0, 1] {
+            if (row_step, col_step) != (0, 0)
+                && grid[(row + row_step) % SIZE][(col + col_step) % SIZE]
+            {
+                alive += 1;
+            }
+        }
+    }
+    alive
+}
+
+/// The generation after `grid`.
+pub fn step(grid: &Grid) -> Grid {
+    let mut next = [[false; SIZE]; SIZE];
+    for (row, cells) in next.iter_mut().enumerate() {
+        for (col, cell) in cells.iter_mut().enumerate() {
+            *cell = matches!(
+                (grid[row][col], neighbours(grid, row, col)),
+                (true, 2) | (_, 3)
+            );
+        }
+    }
+    next
+}
+
+/// How many cells are alive.
+pub fn population(grid: &Grid) -> usize {
+    grid.iter().flatten().filter(|&&cell| cell).count()
+}
+
+/// The grid as LEDs for the display, 1 for a live cell, 0 for a dead one.
+pub fn to_leds(grid: &Grid) -> [[u8; SIZE]; SIZE] {
+    let mut leds = [[0; SIZE]; SIZE];
+    for (leds, cells) in leds.iter_mut().zip(grid) {
+        for (led, &cell) in leds.iter_mut().zip(cells) {
+            *led = cell as u8;
+        }
+    }
+    leds
+}
@@ -0,0 +1,348 @@
+//! The control and status registers of the LSM303AGR, with their fields.
+//!
+//! When the accelerometer measures at a rate nobody asked for, or the
+//! numbers come out twice as big as they should, the driver has usually been
+//! told something different from what the program meant. The registers say
+//! what the sensor was really told. A register is only a byte, though, and
+//! `0x57` doesn't say "100 Hz" to anybody who doesn't have the datasheet
+//! open, so the tables here take every register apart into its fields, with
+//! names for the values that have one.
+//!
+//! The names are the datasheet's, so that they can be looked up there.
+
+use core::fmt::{self, Write};
+
+/// Which half of the LSM303AGR a register belongs to. The two are separate
+/// devices on the bus, with addresses of their own.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Device {
+    Accelerometer,
+    Magnetometer,
+}
+
+impl Device {
+    /// The I2C address.
+    pub fn address(self) -> u8 {
+        match self {
+            Device::Accelerometer => 0x19,
+            Device::Magnetometer => 0x1E,
+        }
+    }
+}
+
+/// Some bits of a register that belong together.
+pub struct Field {
+    pub name: &'static str,
+    /// The lowest bit.
+    pub shift: u8,
+    pub width: u8,
+    /// What the values mean, indexed by value. Without any, it's a number.
+    pub values: &'static [&'static str],
+}
+
+impl Field {
+    /// The field's value in `register`.
+    pub fn get(&self, register: u8) -> u8 {
+        // Shifted as a `u16`, so that a field of all 8 bits works too
+        let mask = (1u16 << self.width) - 1;
+        (u16::from(register) >> self.shift & mask) as u8
+    }
+}
+
+pub struct Register {
+    pub name: &'static str,
+    pub device: Device,
+    pub address: u8,
+    pub fields: &'static [Field],
+}
+
+/// A field of a single bit, which is on or off.
+const fn flag(name: &'static str, bit: u8) -> Field {
+    Field {
+        name,
+        shift: bit,
+        width: 1,
+        values: &[],
+    }
+}
+
+const fn field(name: &'static str, shift: u8, width: u8, values: &'static [&'static str]) -> Field {
+    Field {
+        name,
+        shift,
+        width,
+        values,
+    }
+}
+
+const fn accel(name: &'static str, address: u8, fields: &'static [Field]) -> Register {
+    Register {
+        name,
+        device: Device::Accelerometer,
+        address,
+        fields,
+    }
+}
+
+const fn mag(name: &'static str, address: u8, fields: &'static [Field]) -> Register {
+    Register {
+        name,
+        device: Device::Magnetometer,
+        address,
+        fields,
+    }
+}
+
+/// The threshold and duration registers of the interrupt generators are 7
+/// bit numbers.
+const SEVEN_BITS: &[Field] = &[field("value", 0, 7, &[])];
+
+const INT_CFG: &[Field] = &[
+    flag("AOI", 7),
+    flag("6D", 6),
+    flag("ZHIE", 5),
+    flag("ZLIE", 4),
+    flag("YHIE", 3),
+    flag("YLIE", 2),
+    flag("XHIE", 1),
+    flag("XLIE", 0),
+];
+
+const INT_SRC: &[Field] = &[
+    flag("IA", 6),
+    flag("ZH", 5),
+    flag("ZL", 4),
+    flag("YH", 3),
+    flag("YL", 2),
+    flag("XH", 1),
+    flag("XL", 0),
+];
+
+const STATUS: &[Field] = &[
+    flag("ZYXOR", 7),
+    flag("ZOR", 6),
+    flag("YOR", 5),
+    flag("XOR", 4),
+    flag("ZYXDA", 3),
+    flag("ZDA", 2),
+    flag("YDA", 1),
+    flag("XDA", 0),
+];
+
+/// Every register worth looking at, in the order of their addresses, the
+/// accelerometer's first.
+pub static REGISTERS: &[Register] = &[
+    accel("STATUS_REG_AUX_A", 0x07, &[flag("TOR", 6), flag("TDA", 2)]),
+    accel("WHO_AM_I_A", 0x0F, &[field("value", 0, 8, &[])]),
+    accel(
+        "TEMP_CFG_REG_A",
+        0x1F,
+        &[field("TEMP_EN", 6, 2, &["off", "?", "?", "on"])],
+    ),
+    accel(
+        "CTRL_REG1_A",
+        0x20,
+        &[
+            field(
+                "ODR",
+                4,
+                4,
+                &[
+                    "power-down",
+                    "1 Hz",
+                    "10 Hz",
+                    "25 Hz",
+                    "50 Hz",
+                    "100 Hz",
+                    "200 Hz",
+                    "400 Hz",
+                    "1620 Hz (low-power)",
+                    "1344 Hz, 5376 Hz (low-power)",
+                ],
+            ),
+            flag("LPen", 3),
+            flag("Zen", 2),
+            flag("Yen", 1),
+            flag("Xen", 0),
+        ],
+    ),
+    accel(
+        "CTRL_REG2_A",
+        0x21,
+        &[
+            field("HPM", 6, 2, &["normal", "reference", "normal", "autoreset"]),
+            field("HPCF", 4, 2, &[]),
+            flag("FDS", 3),
+            flag("HPCLICK", 2),
+            flag("HPIS2", 1),
+            flag("HPIS1", 0),
+        ],
+    ),
+    accel(
+        "CTRL_REG3_A",
+        0x22,
+        &[
+            flag("I1_CLICK", 7),
+            flag("I1_AOI1", 6),
+            flag("I1_AOI2", 5),
+            flag("I1_DRDY1", 4),
+            flag("I1_DRDY2", 3),
+            flag("I1_WTM", 2),
+            flag("I1_OVERRUN", 1),
+        ],
+    ),
+    accel(
+        "CTRL_REG4_A",
+        0x23,
+        &[
+            flag("BDU", 7),
+            flag("BLE", 6),
+            field("FS", 4, 2, &["2g", "4g", "8g", "16g"]),
+            flag("HR", 3),
+            field("ST", 1, 2, &["off", "self-test 0", "self-test 1", "?"]),
+            flag("SPI_ENABLE", 0),
+        ],
+    ),
+    accel(
+        "CTRL_REG5_A",
+        0x24,
+        &[
+            flag("BOOT", 7),
+            flag("FIFO_EN", 6),
+            flag("LIR_INT1", 3),
+            flag("D4D_INT1", 2),
+            flag("LIR_INT2", 1),
+            flag("D4D_INT2", 0),
+        ],
+    ),
+    accel(
+        "CTRL_REG6_A",
+        0x25,
+        &[
+            flag("I2_CLICKEN", 7),
+            flag("I2_INT1", 6),
+            flag("I2_INT2", 5),
+            flag("BOOT_I2", 4),
+            flag("P2_ACT", 3),
+            flag("H_LACTIVE", 1),
+        ],
+    ),
+    accel("STATUS_REG_A", 0x27, STATUS),
+    accel(
+        "FIFO_CTRL_REG_A",
+        0x2E,
+        &[
+            field("FM", 6, 2, &["bypass", "FIFO", "stream", "stream-to-FIFO"]),
+            flag("TR", 5),
+            field("FTH", 0, 5, &[]),
+        ],
+    ),
+    accel(
+        "FIFO_SRC_REG_A",
+        0x2F,
+        &[
+            flag("WTM", 7),
+            flag("OVRN_FIFO", 6),
+            flag("EMPTY", 5),
+            field("FSS", 0, 5, &[]),
+        ],
+    ),
+    accel("INT1_CFG_A", 0x30, INT_CFG),
+    accel("INT1_SRC_A", 0x31, INT_SRC),
+    accel("INT1_THS_A", 0x32, SEVEN_BITS),
+    accel("INT1_DURATION_A", 0x33, SEVEN_BITS),
+    accel("INT2_CFG_A", 0x34, INT_CFG),
+    accel("INT2_SRC_A", 0x35, INT_SRC),
+    accel("INT2_THS_A", 0x36, SEVEN_BITS),
+    accel("INT2_DURATION_A", 0x37, SEVEN_BITS),
+    accel("ACT_THS_A", 0x3E, SEVEN_BITS),
+    accel("ACT_DUR_A", 0x3F, &[field("value", 0, 8, &[])]),
+    mag("WHO_AM_I_M", 0x4F, &[field("value", 0, 8, &[])]),
+    mag(
+        "CFG_REG_A_M",
+        0x60,
+        &[
+            flag("COMP_TEMP_EN", 7),
+            flag("REBOOT", 6),
+            flag("SOFT_RST", 5),
+            flag("LP", 4),
+            field("ODR", 2, 2, &["10 Hz", "20 Hz", "50 Hz", "100 Hz"]),
+            field("MD", 0, 2, &["continuous", "single", "idle", "idle"]),
+        ],
+    ),
+    mag(
+        "CFG_REG_B_M",
+        0x61,
+        &[
+            flag("OFF_CANC_ONE_SHOT", 4),
+            flag("INT_on_DataOFF", 3),
+            flag("Set_FREQ", 2),
+            flag("OFF_CANC", 1),
+            flag("LPF", 0),
+        ],
+    ),
+    mag(
+        "CFG_REG_C_M",
+        0x62,
+        &[
+            flag("INT_MAG_PIN", 6),
+            flag("I2C_DIS", 5),
+            flag("BDU", 4),
+            flag("BLE", 3),
+            flag("Self_test", 1),
+            flag("INT_MAG", 0),
+        ],
+    ),
+    mag(
+        "INT_CTRL_REG_M",
+        0x63,
+        &[
+            flag("XIEN", 7),
+            flag("YIEN", 6),
+            flag("ZIEN", 5),
+            flag("IEA", 2),
+            flag("IEL", 1),
+            flag("IEN", 0),
+        ],
+    ),
+    mag(
+        "INT_SOURCE_REG_M",
+        0x64,
+        &[
+            flag("P_TH_S_X", 7),
+            flag("P_TH_S_Y", 6),
+            flag("P_TH_S_Z", 5),
+            flag("N_TH_S_X", 4),
+            flag("N_TH_S_Y", 3),
+            flag("N_TH_S_Z", 2),
+            flag("MROI", 1),
+            flag("INT", 0),
+        ],
+    ),
+    mag("STATUS_REG_M", 0x67, STATUS),
+];
+
+/// The register called `name`, ignoring case.
+pub fn find(name: &str) -> Option<&'static Register> {
+    REGISTERS
+        .iter()
+        .find(|register| register.name.eq_ignore_ascii_case(name))
+}
+
+/// Write a line about `register` holding `value` to `out`: its name,
+/// address and value, and then every field, like `FS=4g`.
+pub fn describe(register: &Register, value: u8, out: &mut dyn Write) -> fmt::Result {
+    write!(
+        out,
+        "{:<17} 0x{:02X} = 0x{:02X}",
+        register.name, register.address, value
+    )?;
+    for field in register.fields {
+        let raw = field.get(value);
+        match field.values.get(usize::from(raw)) {
+            Some(meaning) => write!(out, " {}={}", field.name, meaning)?,
+            None => write!(out, " {}={}", field.name, raw)?,
+        }
+    }
+    out.write_str("\r\n")
+}
@@ -0,0 +1,212 @@
+//! A maze for a ball to roll through, bigger than the display.
+//!
+//! A level is a grid of walls and open tiles, some number of screens wide and
+//! high, and the display shows the screen the ball is on. Tilting the board
+//! rolls the ball one tile at a time, and walls stop it. Hitting a wall at an
+//! angle doesn't stop it altogether, though: it slides along the wall, on
+//! whichever axis is still free.
+
+/// How many tiles a screen is wide and high, the size of the display.
+pub const SCREEN: usize = 5;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Tile {
+    Open,
+    Wall,
+    /// Where the ball starts. Open, otherwise.
+    Start,
+    /// Where the ball has to get to.
+    Goal,
+}
+
+/// A maze, written out one row of bytes at a time: `#` is a wall, `S` the
+/// start, `G` the goal and anything else open. All rows have to be just as
+/// long, and the width and height have to be a whole number of screens.
+pub struct Level {
+    rows: &'static [&'static [u8]],
+}
+
+impl Level {
+    pub const fn new(rows: &'static [&'static [u8]]) -> Level {
+        Level { rows }
+    }
+
+    pub fn width(&self) -> usize {
+        self.rows.first().map_or(0, |row| row.len())
+    }
+
+    pub fn height(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Whether every row is just as long, and the level a whole number of
+    /// screens wide and high.
+    pub fn fits_screens(&self) -> bool {
+        let width = self.width();
+        width > 0
+            && width.is_multiple_of(SCREEN)
+            && self.height().is_multiple_of(SCREEN)
+            && self.rows.iter().all(|row| row.len() == width)
+    }
+
+    /// The tile at `row` and `col`. Everything outside of the level is a
+    /// wall, so the ball can't roll off the edge.
+    pub fn tile(&self, row: usize, col: usize) -> Tile {
+        match self.rows.get(row).and_then(|line| line.get(col)) {
+            None | Some(b'#') => Tile::Wall,
+            Some(b'S') => Tile::Start,
+            Some(b'G') => Tile::Goal,
+            Some(_) => Tile::Open,
+        }
+    }
+
+    /// Where the ball starts, as `(row, col)`, the top left corner if the
+    /// level doesn't say.
+    pub fn start(&self) -> (usize, usize) {
+        self.find(Tile::Start).unwrap_or((0, 0))
+    }
+
+    /// The first tile of the given kind, as `(row, col)`.
+    pub fn find(&self, tile: Tile) -> Option<(usize, usize)> {
+        (0..self.height())
+            .flat_map(|row| (0..self.width()).map(move |col| (row, col)))
+            .find(|&(row, col)| self.tile(row, col) == tile)
+    }
+}
+
+/// The levels, easiest first. Being `static`, they stay in flash, and don't
+/// take up any of the RAM.
+pub static LEVELS: [Level; 3] = [
+    // Two screens side by side
+    Level::new(&[
+        b"##########",
+        b"#S..#....#",
+        b"#.#.#.##.#",
+        b"#.#...#.G#",
+        b"##########",
+    ]),
+    // Two screens on top of each other
+    Level::new(&[
+        b"#####", b"#S..#", b"##..#", b"#..##", b"#.#.#", b"#.#.#", b"#...#", b"##.##", b"#G..#",
+        b"#####",
+    ]),
+    // Four screens, and the way to the goal goes through all of them
+    Level::new(&[
+        b"##########",
+        b"#S...#...#",
+        b"####.#.#.#",
+        b"#....#.#.#",
+        b"#.####.#.#",
+        b"#.#G...#.#",
+        b"#.######.#",
+        b"#........#",
+        b"##########",
+        b"##########",
+    ]),
+];
+
+/// What happened to the ball on a [`Maze::roll`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Roll {
+    /// The board is level, the ball stays where it is.
+    Still,
+    Moved,
+    /// There's a wall in the way, every way the ball could go.
+    Blocked,
+    /// The ball made it to the goal.
+    Goal,
+}
+
+/// A level with a ball rolling through it.
+pub struct Maze {
+    level: &'static Level,
+    /// The tile the ball is on, as `(row, col)`.
+    ball: (usize, usize),
+}
+
+impl Maze {
+    pub fn new(level: &'static Level) -> Maze {
+        Maze {
+            level,
+            ball: level.start(),
+        }
+    }
+
+    pub fn ball(&self) -> (usize, usize) {
+        self.ball
+    }
+
+    pub fn is_solved(&self) -> bool {
+        self.level.tile(self.ball.0, self.ball.1) == Tile::Goal
+    }
+
+    /// Roll the ball by one tile, `d_row` down and `d_col` to the right, each
+    /// -1, 0 or 1.
+    ///
+    /// Diagonally, the ball only fits through if one of the two tiles it
+    /// passes by is open too, it doesn't squeeze between two walls that touch
+    /// at their corners. If it can't go diagonally, it tries going just
+    /// sideways, and then just up or down.
+    pub fn roll(&mut self, d_row: i8, d_col: i8) -> Roll {
+        let (d_row, d_col) = (d_row.signum(), d_col.signum());
+        if (d_row, d_col) == (0, 0) {
+            return Roll::Still;
+        }
+
+        let target = if d_row != 0 && d_col != 0 {
+            let sideways = self.neighbour(0, d_col);
+            let up_down = self.neighbour(d_row, 0);
+            if sideways.is_none() && up_down.is_none() {
+                None
+            } else {
+                self.neighbour(d_row, d_col).or(sideways).or(up_down)
+            }
+        } else {
+            self.neighbour(d_row, d_col)
+        };
+
+        match target {
+            Some(ball) => {
+                self.ball = ball;
+                if self.is_solved() {
+                    Roll::Goal
+                } else {
+                    Roll::Moved
+                }
+            }
+            None => Roll::Blocked,
+        }
+    }
+
+    /// The tile next to the ball, if it's not a wall.
+    fn neighbour(&self, d_row: i8, d_col: i8) -> Option<(usize, usize)> {
+        let row = self.ball.0.checked_add_signed(d_row as isize)?;
+        let col = self.ball.1.checked_add_signed(d_col as isize)?;
+        match self.level.tile(row, col) {
+            Tile::Wall => None,
+            _ => Some((row, col)),
+        }
+    }
+
+    /// The top left tile of the screen the ball is on, as `(row, col)`.
+    pub fn screen(&self) -> (usize, usize) {
+        (self.ball.0 / SCREEN * SCREEN, self.ball.1 / SCREEN * SCREEN)
+    }
+
+    /// The tiles of the screen the ball is on, for the display.
+    pub fn view(&self) -> [[Tile; SCREEN]; SCREEN] {
+        let (top, left) = self.screen();
+        let mut view = [[Tile::Open; SCREEN]; SCREEN];
+        for (row, tiles) in view.iter_mut().enumerate() {
+            for (col, tile) in tiles.iter_mut().enumerate() {
+                *tile = self.level.tile(top + row, left + col);
+            }
+        }
+        view
+    }
+
+    /// Where the ball is in [`Maze::view`], as `(row, col)`.
+    pub fn ball_in_view(&self) -> (usize, usize) {
+        (self.ball.0 % SCREEN, self.ball.1 % SCREEN)
+    }
+}
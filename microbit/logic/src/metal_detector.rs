@@ -0,0 +1,140 @@
+//! A metal detector, out of the magnetometer.
+//!
+//! Anything made of iron or steel bends the magnetic field around it, so
+//! bringing the board close to a screw or a nail changes what the
+//! magnetometer measures. The interesting part isn't the field itself, the
+//! earth's is much stronger than the change, but how far it has moved away
+//! from what it was before:
+//!
+//! - smooth the measurements a little to get rid of the noise,
+//! - compare them to a *baseline*, the field with no metal around, and take
+//!   the length of the difference,
+//! - and let the baseline follow the field slowly, but only while the
+//!   difference is small. That takes care of the magnetometer drifting with
+//!   the temperature, without learning the metal as the new normal.
+//!
+//! Turning the board changes the field it measures just as much as metal
+//! does, so it has to keep pointing the same way. After turning it,
+//! [`MetalDetector::rebaseline`] starts over from the field as it is.
+//!
+//! Like the pedometer, this works in integers, with the averages kept as
+//! fixed point numbers with 4 fractional bits.
+
+/// Number of fractional bits of the averages.
+const FRACTION_BITS: u32 = 4;
+/// A difference below this, in nT, is noise and drift. The magnetometer's
+/// noise is a few hundred nT.
+pub const QUIET_NT: u32 = 1_000;
+/// A difference of this much, in nT, fills the whole display. The earth's
+/// field is about 50 000 nT.
+pub const FULL_NT: u32 = 20_000;
+/// The pitch goes up from the lowest to the highest as the difference goes
+/// from [`QUIET_NT`] to [`FULL_NT`].
+pub const LOWEST_HZ: u32 = 200;
+pub const HIGHEST_HZ: u32 = 2_000;
+/// How many LEDs a bar graph has.
+pub const LEDS: usize = 25;
+
+pub struct MetalDetector {
+    /// The smoothed field, averaged over roughly 4 measurements, once there
+    /// was one.
+    smoothed: Option<[i32; 3]>,
+    /// The field without any metal, following drift over roughly 64
+    /// measurements.
+    baseline: [i32; 3],
+}
+
+impl MetalDetector {
+    /// A detector that takes its first measurement as the baseline.
+    pub const fn new() -> MetalDetector {
+        MetalDetector {
+            smoothed: None,
+            baseline: [0; 3],
+        }
+    }
+
+    /// Take the next measurement as the baseline, like for the very first.
+    pub fn rebaseline(&mut self) {
+        self.smoothed = None;
+    }
+
+    /// Feed a new measurement, in nT, and get how far the field is from the
+    /// baseline, in nT.
+    pub fn update(&mut self, field_nt: [i32; 3]) -> u32 {
+        let field = field_nt.map(|axis| axis << FRACTION_BITS);
+        let smoothed = match self.smoothed.as_mut() {
+            Some(smoothed) => smoothed,
+            None => {
+                self.baseline = field;
+                self.smoothed.insert(field)
+            }
+        };
+
+        // Exponential moving averages: move a fraction of the way towards
+        // the new value
+        for (smoothed, field) in smoothed.iter_mut().zip(field) {
+            *smoothed += (field - *smoothed) >> 2;
+        }
+        let deviation = distance(smoothed, &self.baseline) >> FRACTION_BITS;
+        if deviation < QUIET_NT {
+            for (baseline, smoothed) in self.baseline.iter_mut().zip(*smoothed) {
+                *baseline += (smoothed - *baseline) >> 6;
+            }
+        }
+        deviation
+    }
+}
+
+impl Default for MetalDetector {
+    fn default() -> MetalDetector {
+        MetalDetector::new()
+    }
+}
+
+/// The length of the difference between `a` and `b`.
+fn distance(a: &[i32; 3], b: &[i32; 3]) -> u32 {
+    let squares: u64 = a
+        .iter()
+        .zip(b)
+        .map(|(a, b)| {
+            let difference = u64::from(a.abs_diff(*b));
+            difference * difference
+        })
+        .sum();
+    squares.isqrt() as u32
+}
+
+/// How far `deviation_nt` is from [`QUIET_NT`] towards [`FULL_NT`], scaled to
+/// `0..=full`.
+fn scale(deviation_nt: u32, full: u32) -> u32 {
+    let above = deviation_nt.clamp(QUIET_NT, FULL_NT) - QUIET_NT;
+    above * full / (FULL_NT - QUIET_NT)
+}
+
+/// How many LEDs of a bar graph to light for `deviation_nt`, from 0 to
+/// [`LEDS`].
+pub fn bar_leds(deviation_nt: u32) -> usize {
+    if deviation_nt < QUIET_NT {
+        return 0;
+    }
+    // Anything that isn't quiet lights at least one
+    (scale(deviation_nt, LEDS as u32) as usize).max(1)
+}
+
+/// The display with `leds` of them lit, filled up row by row from the bottom,
+/// left to right.
+pub fn bar_graph(leds: usize) -> [[u8; 5]; 5] {
+    let mut display = [[0; 5]; 5];
+    for (i, led) in display.iter_mut().rev().flatten().enumerate() {
+        *led = u8::from(i < leds);
+    }
+    display
+}
+
+/// The pitch to beep at for `deviation_nt`, in Hz, or 0 for silence.
+pub fn pitch_hz(deviation_nt: u32) -> u32 {
+    if deviation_nt < QUIET_NT {
+        return 0;
+    }
+    LOWEST_HZ + scale(deviation_nt, HIGHEST_HZ - LOWEST_HZ)
+}
@@ -0,0 +1,159 @@
+//! Morse code.
+//!
+//! Every letter is a short pattern of dots and dashes. Morse code is all
+//! about timing, and everything is measured in *units*:
+//!
+//! - a dot lasts one unit and a dash three,
+//! - between the dots and dashes of a letter there's one unit of silence,
+//! - between letters three units,
+//! - and between words seven.
+//!
+//! How long a unit is depends on the speed, in words per minute. By
+//! convention a word is "PARIS", which takes exactly 50 units.
+
+/// Letters can't be longer than this.
+const MAX_SYMBOLS: usize = 7;
+
+const CODES: [(char, &str); 46] = [
+    ('A', ".-"),
+    ('B', "-..."),
+    ('C', "-.-."),
+    ('D', "-.."),
+    ('E', "."),
+    ('F', "..-."),
+    ('G', "--."),
+    ('H', "...."),
+    ('I', ".."),
+    ('J', ".---"),
+    ('K', "-.-"),
+    ('L', ".-.."),
+    ('M', "--"),
+    ('N', "-."),
+    ('O', "---"),
+    ('P', ".--."),
+    ('Q', "--.-"),
+    ('R', ".-."),
+    ('S', "..."),
+    ('T', "-"),
+    ('U', "..-"),
+    ('V', "...-"),
+    ('W', ".--"),
+    ('X', "-..-"),
+    ('Y', "-.--"),
+    ('Z', "--.."),
+    ('0', "-----"),
+    ('1', ".----"),
+    ('2', "..---"),
+    ('3', "...--"),
+    ('4', "....-"),
+    ('5', "....."),
+    ('6', "-...."),
+    ('7', "--..."),
+    ('8', "---.."),
+    ('9', "----."),
+    ('.', ".-.-.-"),
+    (',', "--..--"),
+    ('?', "..--.."),
+    ('\'', ".----."),
+    ('!', "-.-.--"),
+    ('/', "-..-."),
+    ('(', "-.--."),
+    (')', "-.--.-"),
+    ('=', "-...-"),
+    ('@', ".--.-."),
+];
+
+/// How long a unit lasts at `wpm` words per minute.
+pub const fn unit_ms(wpm: u32) -> u32 {
+    60_000 / (50 * wpm)
+}
+
+/// The dots and dashes of `c`, as a string of `.` and `-`. Lowercase letters
+/// are the same as uppercase ones.
+pub fn encode(c: char) -> Option<&'static str> {
+    let c = c.to_ascii_uppercase();
+    CODES
+        .iter()
+        .find(|&&(letter, _)| letter == c)
+        .map(|&(_, code)| code)
+}
+
+/// The character for `code`, a string of `.` and `-`.
+pub fn decode(code: &str) -> Option<char> {
+    CODES
+        .iter()
+        .find(|&&(_, known)| known == code)
+        .map(|&(letter, _)| letter)
+}
+
+/// What a [`Decoder`] made of the key presses.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Decoded {
+    /// A whole letter. Patterns that aren't a letter turn into `?`.
+    Char(char),
+    /// The pause after a word.
+    Space,
+}
+
+/// Turns someone keying Morse code, like with a button, back into text.
+///
+/// Real people don't keep time perfectly, so anything held for less than two
+/// units is a dot, and anything longer a dash. Likewise, a pause of more than
+/// two units ends a letter, and one of more than five units a word.
+pub struct Decoder {
+    unit_ms: u32,
+    symbols: [u8; MAX_SYMBOLS],
+    len: usize,
+    /// Whether the letter is too long to be one.
+    overflow: bool,
+    /// Whether a word has been started since the last space.
+    in_word: bool,
+}
+
+impl Decoder {
+    pub fn new(wpm: u32) -> Decoder {
+        Decoder {
+            unit_ms: unit_ms(wpm),
+            symbols: [0; MAX_SYMBOLS],
+            len: 0,
+            overflow: false,
+            in_word: false,
+        }
+    }
+
+    /// The key was held down for `ms`.
+    pub fn press(&mut self, ms: u32) {
+        let symbol = if ms < 2 * self.unit_ms { b'.' } else { b'-' };
+        match self.symbols.get_mut(self.len) {
+            Some(slot) => {
+                *slot = symbol;
+                self.len += 1;
+            }
+            None => self.overflow = true,
+        }
+    }
+
+    /// The key has been up for `ms` since it was last released. Every letter
+    /// and every space is only handed out once, however often this is called
+    /// during the same pause.
+    pub fn pause(&mut self, ms: u32) -> Option<Decoded> {
+        if self.len > 0 && ms > 2 * self.unit_ms {
+            // Only dots and dashes ever make it into `symbols`
+            let code = core::str::from_utf8(&self.symbols[..self.len]).unwrap();
+            let c = if self.overflow {
+                '?'
+            } else {
+                decode(code).unwrap_or('?')
+            };
+            self.len = 0;
+            self.overflow = false;
+            self.in_word = true;
+            return Some(Decoded::Char(c));
+        }
+        if self.in_word && ms > 5 * self.unit_ms {
+            self.in_word = false;
+            return Some(Decoded::Space);
+        }
+        None
+    }
+}
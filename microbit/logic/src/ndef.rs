@@ -0,0 +1,77 @@
+//! NDEF, the NFC Data Exchange Format, which is what a phone understands on
+//! any kind of NFC tag.
+//!
+//! An NDEF *message* is a list of *records*, and each record says what type
+//! of thing it holds, followed by the thing itself. We only need a message
+//! with a single URI record, which makes a phone open a web page:
+//!
+//! ```text
+//! | header | type length | payload length | type | prefix | the rest of the URI |
+//! | 0xD1   | 1           | 1 + rest       | 'U'  | 1 byte |                     |
+//! ```
+//!
+//! The header says that this record is both the first and the last of the
+//! message, that its payload length fits into a single byte, and that its
+//! type is one the NFC Forum defined, `U` for URI. To save a few bytes, the
+//! common starts of URIs, like `https://`, are sent as a single prefix byte.
+
+use heapless::Vec;
+
+/// The longest message [`uri_message`] makes.
+pub const MAX_MESSAGE_LEN: usize = 128;
+/// The longest URI that fits into that, once its prefix has been taken off.
+pub const MAX_URI_LEN: usize = MAX_MESSAGE_LEN - 5;
+
+/// The first record of a message, and the last, with a short payload, of a
+/// type the NFC Forum defined.
+const HEADER: u8 = 0b1101_0001;
+const URI_TYPE: u8 = b'U';
+
+/// The starts of URIs that have a prefix byte, a few of the 35 there are.
+/// The byte is where they are in this list, plus 1, and 0 means no prefix at
+/// all.
+const PREFIXES: [&str; 6] = [
+    "http://www.",
+    "https://www.",
+    "http://",
+    "https://",
+    "tel:",
+    "mailto:",
+];
+
+pub type MessageBytes = Vec<u8, MAX_MESSAGE_LEN>;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The URI is longer than [`MAX_URI_LEN`], without its prefix.
+    TooLong,
+}
+
+/// A message with a single record, which opens `uri`. Phones handle
+/// `https://` and `tel:` URIs, among others.
+pub fn uri_message(uri: &str) -> Result<MessageBytes, Error> {
+    let (prefix, rest) = split_prefix(uri);
+    if rest.len() > MAX_URI_LEN {
+        return Err(Error::TooLong);
+    }
+
+    let mut message = Vec::new();
+    let header = [HEADER, 1, 1 + rest.len() as u8, URI_TYPE, prefix];
+    message.extend_from_slice(&header).unwrap();
+    message.extend_from_slice(rest.as_bytes()).unwrap();
+    Ok(message)
+}
+
+/// The prefix byte for `uri`, and what's left of it. The longest prefix
+/// wins, `https://www.` rather than `https://`.
+fn split_prefix(uri: &str) -> (u8, &str) {
+    let mut best = (0, uri);
+    for (index, prefix) in PREFIXES.iter().enumerate() {
+        if let Some(rest) = uri.strip_prefix(prefix) {
+            if rest.len() < best.1.len() {
+                best = (index as u8 + 1, rest);
+            }
+        }
+    }
+    best
+}
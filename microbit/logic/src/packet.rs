@@ -0,0 +1,133 @@
+//! Packets with a header and a checksum, the same over every link.
+//!
+//! ```text
+//! | type   | length | sequence | payload        | CRC     |
+//! | 1 byte | 1 byte | 1 byte   | 0 - 255 bytes  | 2 bytes |
+//! ```
+//!
+//! The type says what the payload is, see [`types`], and the length how long
+//! it is. The sequence number goes up by one with every packet the sender
+//! sends, so the receiver can tell when one got lost, or arrived twice. The
+//! CRC covers everything before it, and is sent big endian.
+//!
+//! The radio checks a CRC of its own already, the serial port doesn't. With
+//! this one on top, a garbled packet is caught and reported the same way no
+//! matter how it came in.
+
+/// The header in front of the payload: type, length and sequence number.
+pub const HEADER_LEN: usize = 3;
+/// The CRC after the payload.
+pub const CRC_LEN: usize = 2;
+/// The length is a single byte.
+pub const MAX_PAYLOAD_LEN: usize = 255;
+
+/// What the payloads are. The receiver goes by this to know how to read one,
+/// so two kinds of packets that use the same type would be mistaken for each
+/// other.
+pub mod types {
+    /// A `discovery_messages::Message`, serialized with `postcard`.
+    pub const MESSAGE: u8 = 1;
+    /// The five rows of an image for the LED display, one byte each.
+    pub const IMAGE: u8 = 2;
+    /// A single byte command, from a remote control.
+    pub const COMMAND: u8 = 3;
+    /// A question for the other end, with a payload that's up to the program.
+    pub const REQUEST: u8 = 4;
+    /// The answer to a `REQUEST`, with the request's sequence number.
+    pub const RESPONSE: u8 = 5;
+    /// A request to the bootloader, or its response, see `dfu`.
+    pub const DFU: u8 = 6;
+}
+
+/// A packet, as it's handed to [`encode`] and comes out of [`decode`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Packet<'a> {
+    pub packet_type: u8,
+    pub sequence: u8,
+    pub payload: &'a [u8],
+}
+
+/// Why a packet could not be encoded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EncodeError {
+    /// The payload is longer than [`MAX_PAYLOAD_LEN`].
+    PayloadTooLong,
+    /// The buffer doesn't have room for the packet, which takes `needed`
+    /// bytes.
+    BufferTooSmall { needed: usize },
+}
+
+/// Why some bytes are not a packet.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    /// There aren't even enough bytes for the header and the CRC.
+    TooShort,
+    /// The header promises a payload of `declared` bytes, but `actual` bytes
+    /// came with it.
+    LengthMismatch { declared: usize, actual: usize },
+    /// The CRC the packet brought along isn't the one of its bytes, they got
+    /// garbled on the way.
+    BadCrc { received: u16, computed: u16 },
+}
+
+/// Put `packet` into `buffer`, and return the part of `buffer` it takes.
+pub fn encode<'b>(packet: &Packet, buffer: &'b mut [u8]) -> Result<&'b [u8], EncodeError> {
+    let len = packet.payload.len();
+    if len > MAX_PAYLOAD_LEN {
+        return Err(EncodeError::PayloadTooLong);
+    }
+    let needed = HEADER_LEN + len + CRC_LEN;
+    if buffer.len() < needed {
+        return Err(EncodeError::BufferTooSmall { needed });
+    }
+
+    buffer[0] = packet.packet_type;
+    buffer[1] = len as u8;
+    buffer[2] = packet.sequence;
+    buffer[HEADER_LEN..HEADER_LEN + len].copy_from_slice(packet.payload);
+    let crc = crc16(&buffer[..HEADER_LEN + len]);
+    buffer[HEADER_LEN + len..needed].copy_from_slice(&crc.to_be_bytes());
+    Ok(&buffer[..needed])
+}
+
+/// Check that `bytes` are a whole packet, and take it apart.
+pub fn decode(bytes: &[u8]) -> Result<Packet<'_>, DecodeError> {
+    if bytes.len() < HEADER_LEN + CRC_LEN {
+        return Err(DecodeError::TooShort);
+    }
+    let declared = usize::from(bytes[1]);
+    let actual = bytes.len() - HEADER_LEN - CRC_LEN;
+    if declared != actual {
+        return Err(DecodeError::LengthMismatch { declared, actual });
+    }
+
+    let (data, crc) = bytes.split_at(HEADER_LEN + actual);
+    let received = u16::from_be_bytes([crc[0], crc[1]]);
+    let computed = crc16(data);
+    if received != computed {
+        return Err(DecodeError::BadCrc { received, computed });
+    }
+
+    Ok(Packet {
+        packet_type: bytes[0],
+        sequence: bytes[2],
+        payload: &data[HEADER_LEN..],
+    })
+}
+
+/// CRC-16/CCITT-FALSE: the polynomial 0x1021, starting from 0xFFFF. The same
+/// one the radio chapter sets the RADIO peripheral up with.
+pub fn crc16(bytes: &[u8]) -> u16 {
+    let mut crc = 0xffff_u16;
+    for &byte in bytes {
+        crc ^= u16::from(byte) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                crc << 1 ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
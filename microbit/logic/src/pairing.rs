@@ -0,0 +1,356 @@
+//! Making sure a message comes from a board that knows our key, and only
+//! counts once.
+//!
+//! Anybody with a micro:bit can send whatever they like to our address, and
+//! anybody who heard one of our packets can send it again later. Both boards
+//! know the same secret [`Key`], which the others don't, so they prove that
+//! to each other instead:
+//!
+//! ```text
+//! A                                           B
+//! | --- Challenge(a) -----------------------> |
+//! | <-- Response(b, tag of a, b) ------------ |  B knows the key
+//! | --- Confirm(tag of a, b) ---------------> |  A knows the key
+//! | <-> Data(counter, payload, tag of ...) <-> |
+//! ```
+//!
+//! `a` and `b` are *nonces*, random numbers that are only ever used once.
+//! The *tag* is an HMAC over the key and the message: without the key there's
+//! no way to work it out, and any change to the message changes it. Since
+//! every tag covers both nonces, an old response doesn't answer a new
+//! challenge, and a message from an earlier pairing doesn't fit this one.
+//!
+//! Within a pairing, every message counts up, and a message that doesn't
+//! count higher than the last one is a replay. The tag says which of the two
+//! boards sent it as well, or a message could be sent back to the board it
+//! came from.
+//!
+//! What this doesn't do is keep the messages secret: anybody can read them,
+//! they just can't make up their own. Anybody can also interrupt with a
+//! challenge of their own, which ends the pairing.
+
+use core::convert::TryInto;
+use heapless::Vec;
+use sha2::{Digest, Sha256};
+
+pub const KEY_LEN: usize = 16;
+pub const NONCE_LEN: usize = 8;
+/// The first bytes of the HMAC, which is 32 bytes long. 8 of them still
+/// take a forger about 2^64 tries, and leave room for a payload.
+pub const TAG_LEN: usize = 8;
+/// The longest message, which fits into a packet of the radio chapter.
+pub const MESSAGE_LEN: usize = 32;
+/// The longest payload a [`Message::Data`] carries.
+pub const MAX_PAYLOAD_LEN: usize = MESSAGE_LEN - 1 - 4 - TAG_LEN;
+
+const BLOCK_LEN: usize = 64;
+
+pub type Key = [u8; KEY_LEN];
+pub type Nonce = [u8; NONCE_LEN];
+pub type Tag = [u8; TAG_LEN];
+/// An encoded [`Message`], ready to send.
+pub type MessageBytes = Vec<u8, MESSAGE_LEN>;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// It doesn't look like any message.
+    Malformed,
+    /// The tag doesn't match: the sender doesn't know the key, or the
+    /// message was changed on the way.
+    Forged,
+    /// A message we've already had.
+    Replayed,
+    /// A message that doesn't fit in, like a response while we didn't
+    /// challenge anybody.
+    Unexpected,
+    /// We aren't paired, so there's nobody to send to.
+    NotPaired,
+    /// The payload is longer than [`MAX_PAYLOAD_LEN`].
+    TooLong,
+}
+
+/// What the boards tell each other.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Message<'a> {
+    Challenge {
+        nonce: Nonce,
+    },
+    /// Answers a challenge, and challenges back.
+    Response {
+        nonce: Nonce,
+        tag: Tag,
+    },
+    /// Answers the challenge of a response.
+    Confirm {
+        tag: Tag,
+    },
+    Data {
+        counter: u32,
+        payload: &'a [u8],
+        tag: Tag,
+    },
+}
+
+impl Message<'_> {
+    const CHALLENGE: u8 = b'C';
+    const RESPONSE: u8 = b'R';
+    const CONFIRM: u8 = b'K';
+    const DATA: u8 = b'D';
+
+    pub fn encode(&self) -> MessageBytes {
+        let mut bytes = Vec::new();
+        let parts: &[&[u8]] = match self {
+            Message::Challenge { nonce } => &[&[Message::CHALLENGE], nonce],
+            Message::Response { nonce, tag } => &[&[Message::RESPONSE], nonce, tag],
+            Message::Confirm { tag } => &[&[Message::CONFIRM], tag],
+            Message::Data {
+                counter,
+                payload,
+                tag,
+            } => &[&[Message::DATA], &counter.to_le_bytes(), payload, tag],
+        };
+        for part in parts {
+            bytes.extend_from_slice(part).unwrap();
+        }
+        bytes
+    }
+
+    /// The message in `bytes`, or `None` if they don't make sense.
+    pub fn decode(bytes: &[u8]) -> Option<Message<'_>> {
+        let (&kind, rest) = bytes.split_first()?;
+        match kind {
+            Message::CHALLENGE => Some(Message::Challenge {
+                nonce: rest.try_into().ok()?,
+            }),
+            Message::RESPONSE if rest.len() == NONCE_LEN + TAG_LEN => {
+                let (nonce, tag) = rest.split_at(NONCE_LEN);
+                Some(Message::Response {
+                    nonce: nonce.try_into().ok()?,
+                    tag: tag.try_into().ok()?,
+                })
+            }
+            Message::CONFIRM => Some(Message::Confirm {
+                tag: rest.try_into().ok()?,
+            }),
+            Message::DATA
+                if (4 + TAG_LEN..=4 + MAX_PAYLOAD_LEN + TAG_LEN).contains(&rest.len()) =>
+            {
+                let (counter, rest) = rest.split_at(4);
+                let (payload, tag) = rest.split_at(rest.len() - TAG_LEN);
+                Some(Message::Data {
+                    counter: u32::from_le_bytes(counter.try_into().ok()?),
+                    payload,
+                    tag: tag.try_into().ok()?,
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// HMAC-SHA256 of `parts`, one after the other, with `key`, which must not
+/// be longer than 64 bytes.
+///
+/// Hashing the key and the message together isn't enough: SHA-256 lets
+/// anybody who knows the hash of a message work out the hash of a longer one,
+/// without knowing the start. HMAC hashes twice to get around that.
+pub fn hmac(key: &[u8], parts: &[&[u8]]) -> [u8; 32] {
+    let mut block = [0; BLOCK_LEN];
+    block[..key.len()].copy_from_slice(key);
+
+    let mut inner = Sha256::new();
+    inner.update(block.map(|byte| byte ^ 0x36));
+    for part in parts {
+        inner.update(part);
+    }
+    let mut outer = Sha256::new();
+    outer.update(block.map(|byte| byte ^ 0x5c));
+    outer.update(inner.finalize());
+    outer.finalize().into()
+}
+
+/// Compare all of the bytes, even after the first that differs. Stopping
+/// early would take longer the more bytes of a guess are right, and a forger
+/// with a stopwatch could work out a tag one byte at a time.
+fn same(a: &Tag, b: &Tag) -> bool {
+    a.iter().zip(b).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum State {
+    Unpaired,
+    /// We sent a challenge with `ours`.
+    Challenged {
+        ours: Nonce,
+    },
+    /// We answered the challenge `theirs` with a challenge of our own.
+    Responded {
+        theirs: Nonce,
+        ours: Nonce,
+    },
+    Paired(Session),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Session {
+    /// The nonces of the challenge and of the response.
+    nonces: [Nonce; 2],
+    /// Whether we sent the challenge.
+    challenged: bool,
+    /// The counter of the last message we sent, and of the last one we had.
+    sent: u32,
+    received: u32,
+}
+
+/// What came of a message.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Event<'a> {
+    /// Send this back, we're halfway there.
+    Reply(MessageBytes),
+    /// We're paired. Send the reply, if there is one, so that the other
+    /// board is as well.
+    Paired(Option<MessageBytes>),
+    /// This came from the board we're paired with.
+    Data(&'a [u8]),
+}
+
+/// One board's side of the pairing.
+pub struct Pairing {
+    key: Key,
+    state: State,
+}
+
+impl Pairing {
+    pub fn new(key: Key) -> Pairing {
+        Pairing {
+            key,
+            state: State::Unpaired,
+        }
+    }
+
+    pub fn is_paired(&self) -> bool {
+        matches!(self.state, State::Paired(_))
+    }
+
+    /// Start pairing over with a challenge to send. `nonce` must be random,
+    /// and never used before.
+    pub fn challenge(&mut self, nonce: Nonce) -> MessageBytes {
+        self.state = State::Challenged { ours: nonce };
+        Message::Challenge { nonce }.encode()
+    }
+
+    /// Make sense of a message that arrived. `nonce` is a fresh one like for
+    /// [`Pairing::challenge`], in case we have to challenge back.
+    pub fn receive<'a>(&mut self, bytes: &'a [u8], nonce: Nonce) -> Result<Event<'a>, Error> {
+        match (Message::decode(bytes).ok_or(Error::Malformed)?, self.state) {
+            // A new challenge always wins, the other board may have been reset
+            (Message::Challenge { nonce: theirs }, _) => {
+                self.state = State::Responded {
+                    theirs,
+                    ours: nonce,
+                };
+                let tag = self.tag(Message::RESPONSE, &[theirs, nonce], &[]);
+                Ok(Event::Reply(Message::Response { nonce, tag }.encode()))
+            }
+            (Message::Response { nonce: theirs, tag }, State::Challenged { ours }) => {
+                let nonces = [ours, theirs];
+                self.check(&tag, Message::RESPONSE, &nonces, &[])?;
+                self.state = State::Paired(Session::new(nonces, true));
+                let tag = self.tag(Message::CONFIRM, &nonces, &[]);
+                Ok(Event::Paired(Some(Message::Confirm { tag }.encode())))
+            }
+            (Message::Confirm { tag }, State::Responded { theirs, ours }) => {
+                let nonces = [theirs, ours];
+                self.check(&tag, Message::CONFIRM, &nonces, &[])?;
+                self.state = State::Paired(Session::new(nonces, false));
+                Ok(Event::Paired(None))
+            }
+            (
+                Message::Data {
+                    counter,
+                    payload,
+                    tag,
+                },
+                State::Paired(session),
+            ) => {
+                let sender = [u8::from(!session.challenged)];
+                self.check(
+                    &tag,
+                    Message::DATA,
+                    &session.nonces,
+                    &[&sender, &counter.to_le_bytes(), payload],
+                )?;
+                if counter <= session.received {
+                    return Err(Error::Replayed);
+                }
+                self.state = State::Paired(Session {
+                    received: counter,
+                    ..session
+                });
+                Ok(Event::Data(payload))
+            }
+            _ => Err(Error::Unexpected),
+        }
+    }
+
+    /// Wrap `payload` up for the board we're paired with.
+    pub fn send(&mut self, payload: &[u8]) -> Result<MessageBytes, Error> {
+        let session = match self.state {
+            State::Paired(session) => session,
+            _ => return Err(Error::NotPaired),
+        };
+        if payload.len() > MAX_PAYLOAD_LEN {
+            return Err(Error::TooLong);
+        }
+        let counter = session.sent + 1;
+        let sender = [u8::from(session.challenged)];
+        let tag = self.tag(
+            Message::DATA,
+            &session.nonces,
+            &[&sender, &counter.to_le_bytes(), payload],
+        );
+        self.state = State::Paired(Session {
+            sent: counter,
+            ..session
+        });
+        Ok(Message::Data {
+            counter,
+            payload,
+            tag,
+        }
+        .encode())
+    }
+
+    /// The tag of a message of `kind` in the pairing with `nonces`, which
+    /// covers `rest` as well.
+    fn tag(&self, kind: u8, nonces: &[Nonce; 2], rest: &[&[u8]]) -> Tag {
+        let kind = [kind];
+        let mut parts: Vec<&[u8], 6> = Vec::new();
+        parts
+            .extend_from_slice(&[&kind, &nonces[0], &nonces[1]])
+            .unwrap();
+        parts.extend_from_slice(rest).unwrap();
+        let mut tag = [0; TAG_LEN];
+        tag.copy_from_slice(&hmac(&self.key, &parts)[..TAG_LEN]);
+        tag
+    }
+
+    fn check(&self, tag: &Tag, kind: u8, nonces: &[Nonce; 2], rest: &[&[u8]]) -> Result<(), Error> {
+        if same(tag, &self.tag(kind, nonces, rest)) {
+            Ok(())
+        } else {
+            Err(Error::Forged)
+        }
+    }
+}
+
+impl Session {
+    fn new(nonces: [Nonce; 2], challenged: bool) -> Session {
+        Session {
+            nonces,
+            challenged,
+            sent: 0,
+            received: 0,
+        }
+    }
+}
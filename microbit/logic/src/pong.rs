@@ -0,0 +1,272 @@
+//! Pong for two micro:bits, one half of the field on each.
+//!
+//! The boards lie top edge to top edge, with the players facing each other,
+//! so the field is 5 columns wide and 10 rows long. Every board shows its own
+//! half, with its paddle on the bottom row, and only it knows where its paddle
+//! is. The ball is on one half at a time, and the board it's on moves it.
+//! Once the ball goes over the top edge, that board hands it over with a
+//! [`Message::Ball`], and the other board takes over.
+//!
+//! The other board is turned around, so its columns go the other way: what's
+//! on the left here is on the right there. [`Message::Ball`] says where the
+//! ball goes in the coordinates of the board that receives it.
+
+use heapless::Vec;
+
+pub const WIDTH: i8 = 5;
+pub const HEIGHT: i8 = 5;
+/// How many LEDs wide the paddle is.
+pub const PADDLE_WIDTH: i8 = 2;
+/// The row the paddle moves along, the bottom one.
+pub const PADDLE_ROW: i8 = HEIGHT - 1;
+/// The first to get this many points wins.
+pub const WINNING_SCORE: u8 = 5;
+/// The longest message, in bytes.
+pub const MESSAGE_LEN: usize = 5;
+
+/// Where the ball is, and which way it goes, one row and column per step.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Ball {
+    pub row: i8,
+    pub col: i8,
+    /// -1 is towards the other board, 1 towards our paddle.
+    pub d_row: i8,
+    /// -1, 0 or 1.
+    pub d_col: i8,
+}
+
+impl Ball {
+    /// Bounce off the left and right edges.
+    fn bounce_off_sides(&mut self) {
+        if !(0..WIDTH).contains(&(self.col + self.d_col)) {
+            self.d_col = -self.d_col;
+        }
+    }
+}
+
+/// What the boards tell each other.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Message {
+    /// A new game starts, and the sender has the ball. If both boards start
+    /// one at the same time, the higher `ticket` gets to serve.
+    Serve { ticket: u32 },
+    /// The ball comes over the top edge in column `col`, going sideways by
+    /// `d_col`, in the coordinates of the receiver.
+    Ball { col: i8, d_col: i8 },
+    /// The sender missed the ball, that's a point for the receiver.
+    Point,
+}
+
+impl Message {
+    const SERVE: u8 = b'S';
+    const BALL: u8 = b'B';
+    const POINT: u8 = b'P';
+
+    pub fn encode(&self) -> Vec<u8, MESSAGE_LEN> {
+        let mut bytes = Vec::new();
+        match *self {
+            Message::Serve { ticket } => {
+                bytes.push(Message::SERVE).unwrap();
+                bytes.extend_from_slice(&ticket.to_le_bytes()).unwrap();
+            }
+            Message::Ball { col, d_col } => {
+                bytes
+                    .extend_from_slice(&[Message::BALL, col as u8, d_col as u8])
+                    .unwrap();
+            }
+            Message::Point => bytes.push(Message::POINT).unwrap(),
+        }
+        bytes
+    }
+
+    /// The message in `bytes`, or `None` if they don't make sense.
+    pub fn decode(bytes: &[u8]) -> Option<Message> {
+        match *bytes {
+            [Message::SERVE, a, b, c, d] => Some(Message::Serve {
+                ticket: u32::from_le_bytes([a, b, c, d]),
+            }),
+            [Message::BALL, col, d_col] => {
+                let (col, d_col) = (col as i8, d_col as i8);
+                if (0..WIDTH).contains(&col) && (-1..=1).contains(&d_col) {
+                    Some(Message::Ball { col, d_col })
+                } else {
+                    None
+                }
+            }
+            [Message::POINT] => Some(Message::Point),
+            _ => None,
+        }
+    }
+}
+
+/// Our half of the field.
+pub struct Pong {
+    /// The leftmost column of the paddle.
+    paddle: i8,
+    /// `None` while the ball is on the other half.
+    ball: Option<Ball>,
+    pub score: u8,
+    pub their_score: u8,
+    /// Whether we serve next, which the one who lost the last point does.
+    serves_next: bool,
+}
+
+impl Default for Pong {
+    fn default() -> Pong {
+        Pong::new()
+    }
+}
+
+impl Pong {
+    pub fn new() -> Pong {
+        Pong {
+            paddle: (WIDTH - PADDLE_WIDTH) / 2,
+            ball: None,
+            score: 0,
+            their_score: 0,
+            serves_next: false,
+        }
+    }
+
+    pub fn ball(&self) -> Option<Ball> {
+        self.ball
+    }
+
+    /// The leftmost column of the paddle.
+    pub fn paddle(&self) -> i8 {
+        self.paddle
+    }
+
+    pub fn move_paddle_left(&mut self) {
+        self.paddle = (self.paddle - 1).max(0);
+    }
+
+    pub fn move_paddle_right(&mut self) {
+        self.paddle = (self.paddle + 1).min(WIDTH - PADDLE_WIDTH);
+    }
+
+    /// Start the ball from just above the paddle, going towards the other
+    /// board and sideways by `d_col`.
+    pub fn serve(&mut self, d_col: i8) {
+        self.ball = Some(Ball {
+            row: PADDLE_ROW - 1,
+            col: self.paddle,
+            d_row: -1,
+            d_col: d_col.clamp(-1, 1),
+        });
+        self.serves_next = false;
+    }
+
+    /// Whether we lost the last point, and serve the next one.
+    pub fn serves_next(&self) -> bool {
+        self.serves_next
+    }
+
+    /// Forget the ball, because the other board serves after all.
+    pub fn drop_ball(&mut self) {
+        self.ball = None;
+    }
+
+    /// Start a new game, both scores back at 0.
+    pub fn reset(&mut self) {
+        *self = Pong {
+            paddle: self.paddle,
+            ..Pong::new()
+        };
+    }
+
+    /// Whether somebody has won.
+    pub fn is_over(&self) -> bool {
+        self.score >= WINNING_SCORE || self.their_score >= WINNING_SCORE
+    }
+
+    /// Move the ball on by one step, if it's on our half. Returns what to
+    /// tell the other board, if anything: that the ball is coming over, or
+    /// that we missed it.
+    pub fn step(&mut self) -> Option<Message> {
+        let mut ball = self.ball?;
+        ball.bounce_off_sides();
+
+        if ball.d_row > 0 && ball.row + 1 == PADDLE_ROW {
+            let col = ball.col + ball.d_col;
+            if !(self.paddle..self.paddle + PADDLE_WIDTH).contains(&col) {
+                self.ball = None;
+                self.their_score += 1;
+                self.serves_next = true;
+                return Some(Message::Point);
+            }
+            // Off the left half of the paddle the ball goes left, off the
+            // right half it goes right
+            ball.d_row = -1;
+            ball.d_col = if col == self.paddle { -1 } else { 1 };
+            ball.bounce_off_sides();
+        }
+
+        if ball.row + ball.d_row < 0 {
+            self.ball = None;
+            return Some(Message::Ball {
+                col: WIDTH - 1 - (ball.col + ball.d_col),
+                d_col: -ball.d_col,
+            });
+        }
+        ball.row += ball.d_row;
+        ball.col += ball.d_col;
+        self.ball = Some(ball);
+        None
+    }
+
+    /// The other board didn't take the ball that [`Pong::step`] handed over,
+    /// so it bounces back off the top edge instead.
+    pub fn bounce_back(&mut self, message: Message) {
+        if let Message::Ball { col, d_col } = message {
+            self.ball = Some(Ball {
+                row: 0,
+                col: WIDTH - 1 - col,
+                d_row: 1,
+                d_col: -d_col,
+            });
+        }
+    }
+
+    /// Take in what the other board told us. [`Message::Serve`] is up to the
+    /// program, it knows whether it's started a game itself.
+    pub fn receive(&mut self, message: Message) {
+        match message {
+            Message::Ball { col, d_col } => {
+                self.ball = Some(Ball {
+                    row: 0,
+                    col,
+                    d_row: 1,
+                    d_col,
+                })
+            }
+            Message::Point => self.score += 1,
+            Message::Serve { .. } => {}
+        }
+    }
+
+    /// The paddle and the ball, for the display.
+    pub fn leds(&self) -> [[u8; 5]; 5] {
+        let mut leds = [[0; 5]; 5];
+        for led in &mut leds[PADDLE_ROW as usize][self.paddle as usize..][..PADDLE_WIDTH as usize] {
+            *led = 1;
+        }
+        if let Some(ball) = self.ball {
+            leds[ball.row as usize][ball.col as usize] = 1;
+        }
+        leds
+    }
+
+    /// Both scores, for the display: ours in the left column from the bottom
+    /// up, theirs in the right column from the top down.
+    pub fn score_leds(&self) -> [[u8; 5]; 5] {
+        let mut leds = [[0; 5]; 5];
+        for row in leds.iter_mut().rev().take(self.score as usize) {
+            row[0] = 1;
+        }
+        for row in leds.iter_mut().take(self.their_score as usize) {
+            row[4] = 1;
+        }
+        leds
+    }
+}
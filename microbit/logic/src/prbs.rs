@@ -0,0 +1,122 @@
+//! A test pattern for a serial line, and the check for it at the other end.
+//!
+//! To find out how fast a line really is, and what it loses on the way, both
+//! ends send each other data as fast as they can, and check what arrives.
+//! Counting the bytes says how fast. For checking them, the data shouldn't be
+//! anything regular, like counting up, that hides a byte going missing now
+//! and then. It has to look random, but the other end still has to know
+//! what's coming.
+//!
+//! A *pseudo-random binary sequence* does both. PRBS-15 makes every new bit
+//! out of two of the last 15: the one 14 bits back, XOR the one 15 bits back.
+//! That goes through every 15 bit pattern but zero before it repeats, which
+//! is why it's the usual pattern for testing lines. And because the next bit
+//! only depends on the last 15, [`Checker`] doesn't need to know where the
+//! sender started: it predicts every byte from the two before it, as they
+//! arrived. A byte that gets lost or garbled only spoils the prediction of it
+//! and the two after it, and from then on the check is back in step.
+
+/// The 15 bits that go into the next ones.
+const MASK: u16 = 0x7FFF;
+
+/// The next 8 bits of the sequence after `state`, the first of them the
+/// highest, and the state after them.
+fn step(mut state: u16) -> (u8, u16) {
+    let mut byte = 0;
+    for _ in 0..8 {
+        let bit = (state >> 14 ^ state >> 13) & 1;
+        state = (state << 1 | bit) & MASK;
+        byte = byte << 1 | bit as u8;
+    }
+    (byte, state)
+}
+
+/// Makes the sequence, one byte after the other.
+pub struct Prbs {
+    state: u16,
+}
+
+impl Prbs {
+    /// The sequence that starts after the 15 bits of `seed`. A seed of 0
+    /// would only ever make zeroes, so that one starts after all ones.
+    pub const fn new(seed: u16) -> Prbs {
+        let state = seed & MASK;
+        Prbs {
+            state: if state == 0 { MASK } else { state },
+        }
+    }
+
+    pub fn next_byte(&mut self) -> u8 {
+        let (byte, state) = step(self.state);
+        self.state = state;
+        byte
+    }
+
+    /// Fill all of `bytes` with the next ones.
+    pub fn fill(&mut self, bytes: &mut [u8]) {
+        for byte in bytes {
+            *byte = self.next_byte();
+        }
+    }
+}
+
+impl Default for Prbs {
+    fn default() -> Prbs {
+        Prbs::new(0)
+    }
+}
+
+/// Checks bytes that should be the sequence, from whatever seed.
+pub struct Checker {
+    /// The last 15 bits that arrived.
+    state: u16,
+    /// How many more bytes it takes to know the state, at the start.
+    warming_up: u8,
+    received: u32,
+    errors: u32,
+}
+
+impl Checker {
+    pub const fn new() -> Checker {
+        Checker {
+            state: 0,
+            warming_up: 2,
+            received: 0,
+            errors: 0,
+        }
+    }
+
+    /// Check the next byte that arrived, and whether it was the one that
+    /// should have. The first two are only taken in as they are.
+    pub fn check(&mut self, byte: u8) -> bool {
+        let (expected, _) = step(self.state);
+        self.state = (self.state << 8 | u16::from(byte)) & MASK;
+        self.received = self.received.wrapping_add(1);
+        if self.warming_up > 0 {
+            self.warming_up -= 1;
+            return true;
+        }
+        let ok = byte == expected;
+        if !ok {
+            self.errors = self.errors.wrapping_add(1);
+        }
+        ok
+    }
+
+    /// How many bytes it's checked.
+    pub fn received(&self) -> u32 {
+        self.received
+    }
+
+    /// How many of them weren't the ones that should have arrived. Every
+    /// byte that got lost or garbled makes up to 3 of these.
+    pub fn errors(&self) -> u32 {
+        self.errors
+    }
+}
+
+impl Default for Checker {
+    fn default() -> Checker {
+        Checker::new()
+    }
+}
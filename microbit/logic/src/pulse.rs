@@ -0,0 +1,73 @@
+//! The frequency and duty cycle of a square wave, from how long it stays high
+//! and low.
+//!
+//! Whatever does the measuring hands every phase it sees to
+//! [`PulseStats::add`], in ticks of its own clock. It doesn't have to see all
+//! of them: one it missed only means one less to average over, as long as the
+//! wave doesn't change in the meantime.
+
+/// The high and low phases of a signal, added up.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PulseStats {
+    high_ticks: u64,
+    highs: u32,
+    low_ticks: u64,
+    lows: u32,
+}
+
+impl PulseStats {
+    /// No phases at all.
+    pub const fn new() -> PulseStats {
+        PulseStats {
+            high_ticks: 0,
+            highs: 0,
+            low_ticks: 0,
+            lows: 0,
+        }
+    }
+
+    /// Add a phase that was `high` or low for `ticks`.
+    pub fn add(&mut self, high: bool, ticks: u32) {
+        if high {
+            self.high_ticks += u64::from(ticks);
+            self.highs += 1;
+        } else {
+            self.low_ticks += u64::from(ticks);
+            self.lows += 1;
+        }
+    }
+
+    /// How many phases were added, high and low.
+    pub fn phases(&self) -> u32 {
+        self.highs + self.lows
+    }
+
+    /// How long the signal stays high and low on average, in ticks, once
+    /// there was at least one phase of each.
+    fn averages(&self) -> Option<(f32, f32)> {
+        if self.highs == 0 || self.lows == 0 {
+            return None;
+        }
+        Some((
+            self.high_ticks as f32 / self.highs as f32,
+            self.low_ticks as f32 / self.lows as f32,
+        ))
+    }
+
+    /// How long a whole period of high and low takes, in ticks.
+    pub fn period_ticks(&self) -> Option<f32> {
+        self.averages().map(|(high, low)| high + low)
+    }
+
+    /// How many periods there are in a second, for a clock that ticks
+    /// `tick_hz` times a second.
+    pub fn frequency_hz(&self, tick_hz: u32) -> Option<f32> {
+        self.period_ticks().map(|period| tick_hz as f32 / period)
+    }
+
+    /// How much of a period the signal is high, from 0 to 100.
+    pub fn duty_percent(&self) -> Option<f32> {
+        self.averages()
+            .map(|(high, low)| 100.0 * high / (high + low))
+    }
+}
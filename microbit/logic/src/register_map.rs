@@ -0,0 +1,104 @@
+//! The registers of a micro:bit that pretends to be an I2C sensor.
+//!
+//! Sensors like the LSM303AGR all work the same way: a write from the
+//! controller starts with the number of a register, and whatever follows goes
+//! into that register and the ones after it. A read starts at the register
+//! the last write chose, and goes on to the ones after it, one register per
+//! byte. That's how a single read gets all three axes of the accelerometer at
+//! once.
+//!
+//! Some sensors remember where a read stopped, and carry on from there with
+//! the next one. Ours doesn't, so a controller chooses the register before
+//! every read, which is what `write_read` does anyway.
+//!
+//! The registers are these, with multi-byte values least significant byte
+//! first:
+//!
+//! | Register | Name          | Access | Holds                                  |
+//! |----------|---------------|--------|----------------------------------------|
+//! | `0x00`   | `WHO_AM_I`    | read   | always [`WHO_AM_I_VALUE`]              |
+//! | `0x01`   | `BUTTONS`     | read   | bit 0 for button A, bit 1 for B        |
+//! | `0x02`   | `TEMPERATURE` | read   | 2 bytes, quarters of a degree Celsius  |
+//! | `0x04`   | `LEDS`        | write  | 5 bytes, a row each, bit 4 on the left |
+
+/// Where the controller finds us, unless it's told otherwise. Nothing else on
+/// the micro:bit's own bus uses it.
+pub const DEFAULT_ADDRESS: u8 = 0x42;
+pub const WHO_AM_I_VALUE: u8 = 0x4D;
+/// How many registers there are.
+pub const LEN: usize = 9;
+
+pub mod registers {
+    pub const WHO_AM_I: u8 = 0x00;
+    pub const BUTTONS: u8 = 0x01;
+    pub const TEMPERATURE: u8 = 0x02;
+    pub const LEDS: u8 = 0x04;
+}
+
+/// The registers the controller can write to.
+const WRITABLE: core::ops::Range<usize> = registers::LEDS as usize..LEN;
+
+pub struct RegisterMap {
+    bytes: [u8; LEN],
+    /// The register the last write chose.
+    pointer: usize,
+}
+
+impl Default for RegisterMap {
+    fn default() -> RegisterMap {
+        RegisterMap::new()
+    }
+}
+
+impl RegisterMap {
+    pub fn new() -> RegisterMap {
+        let mut bytes = [0; LEN];
+        bytes[registers::WHO_AM_I as usize] = WHO_AM_I_VALUE;
+        RegisterMap { bytes, pointer: 0 }
+    }
+
+    pub fn set_buttons(&mut self, a: bool, b: bool) {
+        self.bytes[registers::BUTTONS as usize] = u8::from(a) | u8::from(b) << 1;
+    }
+
+    pub fn set_temperature(&mut self, quarters: i16) {
+        let at = registers::TEMPERATURE as usize;
+        self.bytes[at..at + 2].copy_from_slice(&quarters.to_le_bytes());
+    }
+
+    /// The rows of the LEDs, as the controller last wrote them.
+    pub fn leds(&self) -> [[u8; 5]; 5] {
+        let mut leds = [[0; 5]; 5];
+        for (row, &bits) in leds.iter_mut().zip(&self.bytes[WRITABLE]) {
+            for (col, led) in row.iter_mut().enumerate() {
+                *led = bits >> (4 - col) & 1;
+            }
+        }
+        leds
+    }
+
+    /// The controller wrote `bytes`. Writes to registers that can only be
+    /// read, or that don't exist, are ignored, like a real sensor does.
+    /// Says whether any register changed.
+    pub fn write(&mut self, bytes: &[u8]) -> bool {
+        let (&register, values) = match bytes.split_first() {
+            Some(split) => split,
+            None => return false,
+        };
+        self.pointer = usize::from(register);
+        let mut changed = false;
+        for (at, &value) in (self.pointer..).zip(values) {
+            if WRITABLE.contains(&at) && self.bytes[at] != value {
+                self.bytes[at] = value;
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    /// What a controller that reads now gets, from the register it chose up to
+    /// the last one. Anything it reads past that end is up to the peripheral.
+    pub fn to_read(&self) -> &[u8] {
+        self.bytes.get(self.pointer..).unwrap_or(&[])
+    }
+}
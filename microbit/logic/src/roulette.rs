@@ -0,0 +1,32 @@
+//! Where the LED roulette goes next.
+
+/// The row and column of every LED around the edge of the display, clockwise
+/// from the top left corner.
+pub const PIXELS: [(usize, usize); 16] = [
+    (0, 0),
+    (0, 1),
+    (0, 2),
+    (0, 3),
+    (0, 4),
+    (1, 4),
+    (2, 4),
+    (3, 4),
+    (4, 4),
+    (4, 3),
+    (4, 2),
+    (4, 1),
+    (4, 0),
+    (3, 0),
+    (2, 0),
+    (1, 0),
+];
+
+/// The LED that lights up after LED `current` of [`PIXELS`], going round
+/// `clockwise` or the other way.
+pub fn next_pixel(current: usize, clockwise: bool) -> usize {
+    if clockwise {
+        (current + 1) % PIXELS.len()
+    } else {
+        (current + PIXELS.len() - 1) % PIXELS.len()
+    }
+}
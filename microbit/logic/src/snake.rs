@@ -1,15 +1,17 @@
-// Imports we will use later on
-use heapless::FnvIndexSet;
+//! The snake game of chapter 11, everything but the buttons and the display.
+
+use core::fmt::Write;
 use heapless::spsc::Queue;
+use heapless::{FnvIndexSet, String};
 
 /// A basic pseudo-random number generator.
 struct Prng {
-    value: u32
+    value: u32,
 }
 
 impl Prng {
     fn new(seed: u32) -> Self {
-        Self {value: seed}
+        Self { value: seed }
     }
 
     /// Basic xorshift PRNG function: see https://en.wikipedia.org/wiki/Xorshift
@@ -33,24 +35,21 @@ struct Coords {
     // Signed ints to allow negative values (handy when checking if we have gone off the top or left
     // of the grid)
     row: i8,
-    col: i8
+    col: i8,
 }
 
 impl Coords {
     /// Get random coordinates within a grid. `exclude` is an optional set of coordinates which
     /// should be excluded from the output.
-    fn random(
-        rng: &mut Prng,
-        exclude: Option<&FnvIndexSet<Coords, 32>>
-    ) -> Self {
+    fn random(rng: &mut Prng, exclude: Option<&FnvIndexSet<Coords, 32>>) -> Self {
         let mut coords = Coords {
             row: ((rng.random_u32() as usize) % 5) as i8,
-            col: ((rng.random_u32() as usize) % 5) as i8
+            col: ((rng.random_u32() as usize) % 5) as i8,
         };
         while exclude.is_some_and(|exc| exc.contains(&coords)) {
             coords = Coords {
                 row: ((rng.random_u32() as usize) % 5) as i8,
-                col: ((rng.random_u32() as usize) % 5) as i8
+                col: ((rng.random_u32() as usize) % 5) as i8,
             }
         }
         coords
@@ -67,7 +66,7 @@ enum Direction {
     Up,
     Down,
     Left,
-    Right
+    Right,
 }
 
 /// What direction the snake should turn.
@@ -75,17 +74,19 @@ enum Direction {
 pub enum Turn {
     Left,
     Right,
-    None
+    None,
 }
 
 /// The current status of the game.
 pub enum GameStatus {
     Won,
     Lost,
-    Ongoing
+    Ongoing,
 }
 
 /// The outcome of a single move/step.
+// Where a game ends isn't needed by the game itself, only by whoever wants to show it
+#[allow(dead_code)]
 enum StepOutcome {
     /// Grid full (player wins)
     Full(Coords),
@@ -94,7 +95,7 @@ enum StepOutcome {
     /// Snake has eaten some food
     Eat(Coords),
     /// Snake has moved (and nothing else has happened)
-    Move(Coords)
+    Move(Coords),
 }
 
 struct Snake {
@@ -106,7 +107,7 @@ struct Snake {
     /// checking).
     coord_set: FnvIndexSet<Coords, 32>,
     /// The direction the snake is currently moving in.
-    direction: Direction
+    direction: Direction,
 }
 
 impl Snake {
@@ -145,7 +146,7 @@ impl Snake {
             Direction::Up => Direction::Right,
             Direction::Down => Direction::Left,
             Direction::Left => Direction::Up,
-            Direction::Right => Direction::Down
+            Direction::Right => Direction::Down,
         }
     }
 
@@ -154,7 +155,7 @@ impl Snake {
             Direction::Up => Direction::Left,
             Direction::Down => Direction::Right,
             Direction::Left => Direction::Down,
-            Direction::Right => Direction::Up
+            Direction::Right => Direction::Up,
         }
     }
 
@@ -162,24 +163,33 @@ impl Snake {
         match direction {
             Turn::Left => self.turn_left(),
             Turn::Right => self.turn_right(),
-            Turn::None => ()
+            Turn::None => (),
         }
     }
 }
 
+/// How long a step takes at the start of the game, in milliseconds.
+pub const START_STEP_MS: u32 = 1000;
+/// How much quicker every step gets with every piece of food the snake eats.
+pub const SPEED_UP_MS: u32 = 50;
+/// The snake doesn't get any quicker than this, or nobody could keep up.
+pub const MIN_STEP_MS: u32 = 200;
+
 /// Struct to hold game state and associated behaviour
-pub(crate) struct Game {
+pub struct Game {
     rng: Prng,
     snake: Snake,
     food_coords: Coords,
-    speed: u8,
-    pub(crate) status: GameStatus,
-    score: u8
+    pub status: GameStatus,
+    pub score: u8,
+    /// The best score so far, of this game or of any before it.
+    high_score: u8,
+    /// Whether this game beat the high score, once it's over.
+    new_high_score: bool,
 }
 
 impl Game {
-
-    pub(crate) fn new(rng_seed: u32) -> Self {
+    pub fn new(rng_seed: u32) -> Self {
         let mut rng = Prng::new(rng_seed);
         let mut tail: FnvIndexSet<Coords, 32> = FnvIndexSet::new();
         tail.insert(Coords { row: 2, col: 1 }).unwrap();
@@ -189,19 +199,38 @@ impl Game {
             rng,
             snake,
             food_coords,
-            speed: 1,
             status: GameStatus::Ongoing,
-            score: 0
+            score: 0,
+            high_score: 0,
+            new_high_score: false,
         }
     }
 
     /// Reset the game state to start a new game.
-    pub(crate) fn reset(&mut self) {
+    pub fn reset(&mut self) {
         self.snake = Snake::new();
         self.place_food();
-        self.speed = 1;
         self.status = GameStatus::Ongoing;
         self.score = 0;
+        self.new_high_score = false;
+    }
+
+    /// The best score so far. It starts at 0, unless [`Game::set_high_score`]
+    /// says otherwise.
+    pub fn high_score(&self) -> u8 {
+        self.high_score
+    }
+
+    /// Carry on from the best score of an earlier game, such as one that was
+    /// saved before the last reset.
+    pub fn set_high_score(&mut self, high_score: u8) {
+        self.high_score = high_score;
+    }
+
+    /// Whether the game is over, and beat the high score doing so. The high
+    /// score is this game's score by then.
+    pub fn is_new_high_score(&self) -> bool {
+        self.new_high_score
     }
 
     /// Randomly place food on the grid.
@@ -230,10 +259,22 @@ impl Game {
     fn get_next_move(&self) -> Coords {
         let head = &self.snake.head;
         let next_move = match self.snake.direction {
-            Direction::Up => Coords { row: head.row - 1, col: head.col },
-            Direction::Down => Coords { row: head.row + 1, col: head.col },
-            Direction::Left => Coords { row: head.row, col: head.col - 1 },
-            Direction::Right => Coords { row: head.row, col: head.col + 1 },
+            Direction::Up => Coords {
+                row: head.row - 1,
+                col: head.col,
+            },
+            Direction::Down => Coords {
+                row: head.row + 1,
+                col: head.col,
+            },
+            Direction::Left => Coords {
+                row: head.row,
+                col: head.col - 1,
+            },
+            Direction::Right => Coords {
+                row: head.row,
+                col: head.col + 1,
+            },
         };
         if next_move.is_out_of_bounds() {
             self.wraparound(next_move)
@@ -267,50 +308,48 @@ impl Game {
 
     /// Handle the outcome of a step, updating the game's internal state.
     fn handle_step_outcome(&mut self, outcome: StepOutcome) {
-        self.status = match outcome {
+        let status = match outcome {
             StepOutcome::Collision(_) => GameStatus::Lost,
             StepOutcome::Full(_) => GameStatus::Won,
             StepOutcome::Eat(c) => {
                 self.snake.move_snake(c, true);
                 self.place_food();
                 self.score += 1;
-                if self.score % 5 == 0 {
-                    self.speed += 1
-                }
                 GameStatus::Ongoing
-            },
+            }
             StepOutcome::Move(c) => {
                 self.snake.move_snake(c, false);
                 GameStatus::Ongoing
             }
+        };
+        if !matches!(status, GameStatus::Ongoing) && self.score > self.high_score {
+            self.high_score = self.score;
+            self.new_high_score = true;
         }
+        self.status = status;
     }
 
-    pub(crate) fn step(&mut self, turn: Turn) {
+    pub fn step(&mut self, turn: Turn) {
         self.snake.turn(turn);
         let outcome = self.get_step_outcome();
         self.handle_step_outcome(outcome);
     }
 
-    /// Calculate the length of time to wait between game steps, in milliseconds. Generally this
-    /// will get lower as the player's score increases, but need to be careful it cannot result in a
-    /// value below zero.
-    pub(crate) fn step_len_ms(&self) -> u32 {
-        let result = 1000 - (200 * ((self.speed as i32) - 1));
-        if result < 200 {
-            200u32
-        } else {
-            result as u32
-        }
+    /// Calculate the length of time to wait between game steps, in milliseconds. It gets a little
+    /// shorter every time the snake grows, down to [`MIN_STEP_MS`].
+    pub fn step_len_ms(&self) -> u32 {
+        START_STEP_MS
+            .saturating_sub(SPEED_UP_MS * self.score as u32)
+            .max(MIN_STEP_MS)
     }
 
     /// Return an array representing the game state, which can be used to display the state on the
     /// microbit's LED matrix. Each `_brightness` parameter should be a value between 0 and 9.
-    pub(crate) fn game_matrix(
+    pub fn game_matrix(
         &self,
         head_brightness: u8,
         tail_brightness: u8,
-        food_brightness: u8
+        food_brightness: u8,
     ) -> [[u8; 5]; 5] {
         let mut values = [[0u8; 5]; 5];
         values[self.snake.head.row as usize][self.snake.head.col as usize] = head_brightness;
@@ -324,15 +363,27 @@ impl Game {
     /// Return an array representing the game score, which can be used to display the score on the
     /// microbit's LED matrix (by illuminating the equivalent number of LEDs, going left->right and
     /// top->bottom).
-    pub(crate) fn score_matrix(&self) -> [[u8; 5]; 5] {
+    pub fn score_matrix(&self) -> [[u8; 5]; 5] {
         let mut values = [[0u8; 5]; 5];
         let full_rows = (self.score as usize) / 5;
-        for r in 0..full_rows {
-            values[r] = [1; 5];
+        for row in values.iter_mut().take(full_rows) {
+            *row = [1; 5];
         }
-        for c in 0..(self.score as usize) % 5 {
-            values[full_rows][c] = 1;
+        for led in values[full_rows].iter_mut().take((self.score as usize) % 5) {
+            *led = 1;
         }
         values
     }
-}
\ No newline at end of file
+
+    /// What to scroll across the display once the game is over: the score, and either the news
+    /// that it's the best yet, or what the best is.
+    pub fn score_text(&self) -> String<32> {
+        let mut text = String::new();
+        if self.new_high_score {
+            write!(text, "{} HIGH SCORE!", self.score).unwrap();
+        } else {
+            write!(text, "{} BEST {}", self.score, self.high_score).unwrap();
+        }
+        text
+    }
+}
@@ -0,0 +1,160 @@
+//! Making sense of what the radio picks up while sniffing.
+//!
+//! The RADIO peripheral only hands over packets sent to an address it
+//! listens on, and the group of the micro:bit radio protocol is part of the
+//! address, as its last byte on air. A sniffer that wants to hear every group
+//! listens on the four bytes before it instead. The radio then takes the
+//! group for the first byte of the packet, and has no idea where the packet
+//! really starts, so it can't undo the whitening or check the CRC either. It
+//! just hands over a fixed number of bytes, [`CAPTURE_LEN`], and [`decode`]
+//! does the rest:
+//!
+//! ```text
+//! | group  | length | payload      | CRC     | whatever came after |
+//! | 1 byte | 1 byte | 0 - 32 bytes | 2 bytes |                     |
+//! ```
+//!
+//! Everything after the group is whitened: the sender XORs it with a
+//! pseudo-random sequence, so that long runs of 0s or 1s don't confuse the
+//! receiver. XORing with the same sequence again gets the bits back.
+//!
+//! The CRC covers the whole address, the length and the payload, in the
+//! order the bits go over the air, least significant bit first. The CRC
+//! itself goes out the other way around, most significant bit first.
+
+use core::fmt;
+
+/// The longest packet the micro:bit radio protocol sends, without the length
+/// byte.
+pub const MAX_PACKET_LEN: usize = 32;
+/// How many bytes to capture after the address: the group, the length, the
+/// longest packet and the CRC.
+pub const CAPTURE_LEN: usize = 2 + MAX_PACKET_LEN + 2;
+
+const CRC_POLY: u16 = 0x1021;
+const CRC_INIT: u16 = 0xFFFF;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The length is longer than any packet, so this was noise that happened
+    /// to look like the address. It says how long.
+    TooLong(u8),
+}
+
+/// A packet the way it came in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Capture<'a> {
+    pub group: u8,
+    /// Everything after the length byte.
+    pub packet: &'a [u8],
+    /// Whether the CRC matched. A packet that doesn't is still shown, since
+    /// a garbled packet is worth knowing about when debugging.
+    pub crc_ok: bool,
+}
+
+/// XOR `data` with the whitening sequence that starts from `iv`, the value
+/// of the radio's DATAWHITEIV register, which the radio always reads with
+/// bit 6 set. Doing it twice gets the data back.
+pub fn whiten(data: &mut [u8], iv: u8) {
+    // Bit 7 of `lfsr` is the position the sequence comes out of
+    let mut lfsr = (iv | 0x40).reverse_bits();
+    for byte in data {
+        for bit in 0..8 {
+            if lfsr & 0x80 != 0 {
+                lfsr ^= 0x11;
+                *byte ^= 1 << bit;
+            }
+            lfsr <<= 1;
+        }
+    }
+}
+
+/// Carry on the CRC `crc` over the bits of `bytes`, least significant bit of
+/// every byte first, the way they go over the air.
+pub fn crc16_air(mut crc: u16, bytes: &[u8]) -> u16 {
+    for &byte in bytes {
+        for bit in 0..8 {
+            let feedback = (crc >> 15) ^ u16::from(byte >> bit & 1);
+            crc <<= 1;
+            if feedback & 1 != 0 {
+                crc ^= CRC_POLY;
+            }
+        }
+    }
+    crc
+}
+
+/// Turn what the radio captured after `address`, the four bytes of the
+/// address before the group in the order they went over the air, into a
+/// packet. `iv` is the sender's whitening value.
+pub fn decode(
+    address: [u8; 4],
+    iv: u8,
+    capture: &mut [u8; CAPTURE_LEN],
+) -> Result<Capture<'_>, Error> {
+    whiten(&mut capture[1..], iv);
+    let len = capture[1];
+    if usize::from(len) > MAX_PACKET_LEN {
+        return Err(Error::TooLong(len));
+    }
+    let end = 2 + usize::from(len);
+
+    let crc = crc16_air(crc16_air(CRC_INIT, &address), &capture[..end]);
+    let sent =
+        u16::from(capture[end].reverse_bits()) << 8 | u16::from(capture[end + 1].reverse_bits());
+    Ok(Capture {
+        group: capture[0],
+        packet: &capture[2..end],
+        crc_ok: crc == sent,
+    })
+}
+
+/// What a sender puts on the air after `address` for `packet` in `group`,
+/// the opposite of [`decode`].
+pub fn encode(address: [u8; 4], iv: u8, group: u8, packet: &[u8], capture: &mut [u8; CAPTURE_LEN]) {
+    let end = 2 + packet.len();
+    capture[0] = group;
+    capture[1] = packet.len() as u8;
+    capture[2..end].copy_from_slice(packet);
+    let crc = crc16_air(crc16_air(CRC_INIT, &address), &capture[..end]);
+    capture[end] = ((crc >> 8) as u8).reverse_bits();
+    capture[end + 1] = (crc as u8).reverse_bits();
+    whiten(&mut capture[1..end + 2], iv);
+}
+
+/// Bytes shown 16 to a line, as hex and as text.
+pub fn hexdump(bytes: &[u8]) -> impl Iterator<Item = HexLine<'_>> {
+    bytes.chunks(16).enumerate().map(|(line, bytes)| HexLine {
+        offset: line * 16,
+        bytes,
+    })
+}
+
+/// A line of a [`hexdump`]: `0010  48 65 6c 6c 6f  Hello`.
+pub struct HexLine<'a> {
+    offset: usize,
+    bytes: &'a [u8],
+}
+
+impl fmt::Display for HexLine<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04x} ", self.offset)?;
+        for byte in self.bytes {
+            write!(f, " {:02x}", byte)?;
+        }
+        // Line the text up, on a short last line too
+        for _ in self.bytes.len()..16 {
+            write!(f, "   ")?;
+        }
+        write!(f, "  ")?;
+        for &byte in self.bytes {
+            let c = if byte.is_ascii_graphic() || byte == b' ' {
+                byte as char
+            } else {
+                '.'
+            };
+            write!(f, "{}", c)?;
+        }
+        Ok(())
+    }
+}
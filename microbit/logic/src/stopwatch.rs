@@ -0,0 +1,137 @@
+//! A stopwatch with laps.
+//!
+//! The stopwatch doesn't keep time itself, every call gets handed the time
+//! right now, in milliseconds from whatever clock the program has. That keeps
+//! it just as happy with the micro:bit's RTC as with a test that makes up its
+//! own times.
+//!
+//! While it's running, a lap remembers the time the stopwatch shows at that
+//! moment, the *split*. The time of the lap itself is how long it has been
+//! since the split before.
+
+use core::fmt;
+use heapless::Vec;
+
+/// How many laps the stopwatch remembers.
+pub const MAX_LAPS: usize = 16;
+
+/// What pressing lap/reset did.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LapOrReset {
+    /// A new lap, the `number`th, counting from 1.
+    Lap {
+        number: usize,
+        lap_ms: u64,
+        split_ms: u64,
+    },
+    /// The stopwatch is running, but there's no room for another lap.
+    Full,
+    /// The stopwatch was stopped, and is back at 0 now.
+    Reset,
+}
+
+pub struct Stopwatch {
+    /// When it was last started, if it's running.
+    started_ms: Option<u64>,
+    /// The time it had shown by then.
+    before_ms: u64,
+    splits: Vec<u64, MAX_LAPS>,
+}
+
+impl Stopwatch {
+    /// A stopwatch at 0, that isn't running.
+    pub const fn new() -> Stopwatch {
+        Stopwatch {
+            started_ms: None,
+            before_ms: 0,
+            splits: Vec::new(),
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.started_ms.is_some()
+    }
+
+    /// The time the stopwatch shows at `now_ms`.
+    pub fn elapsed_ms(&self, now_ms: u64) -> u64 {
+        match self.started_ms {
+            Some(started_ms) => self.before_ms + now_ms.saturating_sub(started_ms),
+            None => self.before_ms,
+        }
+    }
+
+    /// Start the stopwatch if it's stopped, or stop it if it's running, and
+    /// return whether it's running now. A stopwatch that is started again
+    /// goes on from where it stopped.
+    pub fn start_stop(&mut self, now_ms: u64) -> bool {
+        match self.started_ms {
+            Some(_) => {
+                self.before_ms = self.elapsed_ms(now_ms);
+                self.started_ms = None;
+                false
+            }
+            None => {
+                self.started_ms = Some(now_ms);
+                true
+            }
+        }
+    }
+
+    /// Take a lap if the stopwatch is running, or set it back to 0 and forget
+    /// its laps if it isn't.
+    pub fn lap_or_reset(&mut self, now_ms: u64) -> LapOrReset {
+        if !self.is_running() {
+            self.before_ms = 0;
+            self.splits.clear();
+            return LapOrReset::Reset;
+        }
+
+        let split_ms = self.elapsed_ms(now_ms);
+        let lap_ms = split_ms - self.splits.last().copied().unwrap_or(0);
+        if self.splits.push(split_ms).is_err() {
+            return LapOrReset::Full;
+        }
+        LapOrReset::Lap {
+            number: self.splits.len(),
+            lap_ms,
+            split_ms,
+        }
+    }
+
+    /// Every lap so far, as the time of the lap and its split.
+    pub fn laps(&self) -> impl Iterator<Item = (u64, u64)> + '_ {
+        self.splits.iter().scan(0, |previous, &split| {
+            let lap = split - *previous;
+            *previous = split;
+            Some((lap, split))
+        })
+    }
+}
+
+impl Default for Stopwatch {
+    fn default() -> Self {
+        Stopwatch::new()
+    }
+}
+
+/// Shows a number of milliseconds the way stopwatches do, to the hundredth
+/// of a second: `1:23.45`, or `1:02:03.45` after an hour.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Elapsed(pub u64);
+
+impl fmt::Display for Elapsed {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let hundredths = self.0 / 10 % 100;
+        let secs = self.0 / 1_000;
+        let (hours, minutes, seconds) = (secs / 3600, secs / 60 % 60, secs % 60);
+        if hours > 0 {
+            write!(
+                f,
+                "{}:{:02}:{:02}.{:02}",
+                hours, minutes, seconds, hundredths
+            )
+        } else {
+            write!(f, "{}:{:02}.{:02}", minutes, seconds, hundredths)
+        }
+    }
+}
@@ -0,0 +1,47 @@
+//! The lines of a log of temperatures, one measurement to a line, as CSV
+//! that a spreadsheet opens as it is.
+//!
+//! Every line says when the temperature was measured, in milliseconds since
+//! start up and as a time of day, and the temperature in degrees Celsius:
+//!
+//! ```text
+//! ms,time,celsius
+//! 1000,00:00:01,23.25
+//! 2000,00:00:02,23.50
+//! ```
+
+use crate::clock::TimeOfDay;
+use core::fmt::Write;
+use heapless::String;
+
+/// The name of the file on the card. FAT only knows names of up to 8
+/// letters, with up to 3 after the dot, in capitals.
+pub const FILE_NAME: &str = "TEMPS.CSV";
+
+/// The first line of the file, with the names of the columns.
+pub const HEADER: &str = "ms,time,celsius\r\n";
+
+/// The longest line [`line()`] makes: a `u64`, a time of day, and a
+/// temperature in hundredths of a degree, with the commas and the line end.
+pub const MAX_LINE_LEN: usize = 20 + 1 + 8 + 1 + 12 + 2;
+
+/// The line for a temperature of `hundredths` of a degree, measured `ms`
+/// after start up, at `time`.
+pub fn line(ms: u64, time: TimeOfDay, hundredths: i32) -> String<MAX_LINE_LEN> {
+    let mut line = String::new();
+    // -0.25 has 0 whole degrees, and 0 has no sign, so the sign goes in by
+    // itself
+    let sign = if hundredths < 0 { "-" } else { "" };
+    let degrees = hundredths.unsigned_abs();
+    // It's long enough for any numbers
+    let _ = write!(
+        line,
+        "{},{},{}{}.{:02}\r\n",
+        ms,
+        time,
+        sign,
+        degrees / 100,
+        degrees % 100
+    );
+    line
+}
@@ -0,0 +1,2268 @@
+mod roulette {
+    use crate::roulette::{next_pixel, PIXELS};
+
+    #[test]
+    fn wraps_around_both_ways() {
+        assert_eq!(next_pixel(PIXELS.len() - 1, true), 0);
+        assert_eq!(next_pixel(0, false), PIXELS.len() - 1);
+    }
+
+    #[test]
+    fn anticlockwise_undoes_clockwise() {
+        for current in 0..PIXELS.len() {
+            assert_eq!(next_pixel(next_pixel(current, true), false), current);
+        }
+    }
+
+    #[test]
+    fn every_step_is_to_a_neighbouring_led() {
+        for current in 0..PIXELS.len() {
+            let (row, col) = PIXELS[current];
+            let (next_row, next_col) = PIXELS[next_pixel(current, true)];
+            assert_eq!(
+                row.abs_diff(next_row) + col.abs_diff(next_col),
+                1,
+                "from {}",
+                current
+            );
+        }
+    }
+}
+
+mod morse {
+    use crate::morse::{decode, encode, unit_ms, Decoded, Decoder};
+
+    #[test]
+    fn paris_takes_a_minute_at_one_wpm() {
+        assert_eq!(unit_ms(1) * 50, 60_000);
+        assert_eq!(unit_ms(20), 60);
+    }
+
+    #[test]
+    fn encodes_either_case() {
+        assert_eq!(encode('s'), Some("..."));
+        assert_eq!(encode('S'), Some("..."));
+        assert_eq!(encode('#'), None);
+    }
+
+    #[test]
+    fn decodes_what_it_encodes() {
+        for c in "ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789.,?'!/()=@".chars() {
+            assert_eq!(decode(encode(c).unwrap()), Some(c));
+        }
+        assert_eq!(decode("........"), None);
+    }
+
+    #[test]
+    fn decoder_hears_letters_and_words() {
+        // A unit is 60 ms at 20 WPM
+        let mut decoder = Decoder::new(20);
+        for &ms in &[50, 70, 50] {
+            decoder.press(ms);
+            assert_eq!(decoder.pause(60), None);
+        }
+        assert_eq!(decoder.pause(150), Some(Decoded::Char('S')));
+        // Still the same pause, the letter isn't handed out twice
+        assert_eq!(decoder.pause(200), None);
+        assert_eq!(decoder.pause(350), Some(Decoded::Space));
+        assert_eq!(decoder.pause(1000), None);
+    }
+
+    #[test]
+    fn decoder_turns_nonsense_into_question_marks() {
+        let mut decoder = Decoder::new(20);
+        for _ in 0..8 {
+            decoder.press(200);
+        }
+        assert_eq!(decoder.pause(150), Some(Decoded::Char('?')));
+    }
+}
+
+mod line_reader {
+    use crate::line_reader::LineReader;
+    use core::convert::Infallible;
+    use embedded_hal::serial;
+
+    /// A serial port that has "received" `input` already, and keeps whatever
+    /// is written to it.
+    struct FakeSerial {
+        input: std::collections::VecDeque<u8>,
+        output: Vec<u8>,
+    }
+
+    impl FakeSerial {
+        fn new(input: &[u8]) -> FakeSerial {
+            FakeSerial {
+                input: input.iter().copied().collect(),
+                output: Vec::new(),
+            }
+        }
+    }
+
+    impl serial::Read<u8> for FakeSerial {
+        type Error = Infallible;
+
+        fn read(&mut self) -> nb::Result<u8, Infallible> {
+            self.input.pop_front().ok_or(nb::Error::WouldBlock)
+        }
+    }
+
+    impl serial::Write<u8> for FakeSerial {
+        type Error = Infallible;
+
+        fn write(&mut self, byte: u8) -> nb::Result<(), Infallible> {
+            self.output.push(byte);
+            Ok(())
+        }
+
+        fn flush(&mut self) -> nb::Result<(), Infallible> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn echoes_and_edits() {
+        let mut serial = FakeSerial::new(b"ab\x08c\r");
+        let mut reader: LineReader<32> = LineReader::new();
+        assert_eq!(reader.read_line(&mut serial), Ok("ac"));
+        assert_eq!(serial.output, b"ab\x08 \x08c\r\n");
+    }
+
+    #[test]
+    fn beeps_when_full() {
+        let mut serial = FakeSerial::new(b"abcd\r");
+        let mut reader: LineReader<3> = LineReader::new();
+        assert_eq!(reader.read_line(&mut serial), Ok("abc"));
+        assert_eq!(serial.output, b"abc\x07\r\n");
+    }
+
+    #[test]
+    fn polls_until_the_line_is_done() {
+        let mut serial = FakeSerial::new(b"he");
+        let mut reader: LineReader<32> = LineReader::new();
+        assert_eq!(reader.poll_line(&mut serial), Ok(None));
+        serial.input.extend(b"llo\r\nagain\n");
+        assert_eq!(reader.poll_line(&mut serial), Ok(Some("hello")));
+        assert_eq!(reader.poll_line(&mut serial), Ok(Some("again")));
+        assert_eq!(reader.poll_line(&mut serial), Ok(None));
+    }
+}
+
+/// The same, but checking every single read, write and flush the line reader
+/// does, in order.
+mod line_reader_mock {
+    use crate::line_reader::LineReader;
+    use embedded_hal_mock::serial::{Mock, Transaction};
+    use embedded_hal_mock::MockError;
+    use std::io::ErrorKind;
+
+    /// Typing `byte`, and what should be echoed for it. Every key is followed
+    /// by a flush, whether it's echoed or not.
+    fn key(byte: u8, echo: &[u8]) -> Vec<Transaction<u8>> {
+        let mut transactions = vec![Transaction::read(byte)];
+        if !echo.is_empty() {
+            transactions.push(Transaction::write_many(echo));
+        }
+        transactions.push(Transaction::flush());
+        transactions
+    }
+
+    /// Ending the line with `byte`, which moves the terminal to the next one.
+    fn enter(byte: u8) -> Vec<Transaction<u8>> {
+        vec![
+            Transaction::read(byte),
+            Transaction::write_many(b"\r\n"),
+            Transaction::flush(),
+        ]
+    }
+
+    /// Nothing more has arrived yet.
+    fn nothing() -> Vec<Transaction<u8>> {
+        vec![Transaction::read_error(nb::Error::WouldBlock)]
+    }
+
+    /// What the serial port fails with.
+    const FAILURE: MockError = MockError::Io(ErrorKind::InvalidData);
+
+    /// The serial port failed to receive.
+    fn failure() -> Vec<Transaction<u8>> {
+        vec![Transaction::read_error(nb::Error::Other(FAILURE))]
+    }
+
+    fn serial(keys: Vec<Vec<Transaction<u8>>>) -> Mock<u8> {
+        let transactions: Vec<_> = keys.into_iter().flatten().collect();
+        Mock::new(&transactions)
+    }
+
+    #[test]
+    fn echoes_what_is_typed() {
+        let mut serial = serial(vec![key(b'h', b"h"), key(b'i', b"i"), enter(b'\r')]);
+        let mut reader: LineReader<32> = LineReader::new();
+        assert_eq!(reader.read_line(&mut serial), Ok("hi"));
+        serial.done();
+    }
+
+    #[test]
+    fn backspace_and_delete_erase_on_the_terminal() {
+        let mut serial = serial(vec![
+            key(b'a', b"a"),
+            key(b'b', b"b"),
+            key(0x08, b"\x08 \x08"),
+            key(b'c', b"c"),
+            key(0x7f, b"\x08 \x08"),
+            key(b'd', b"d"),
+            enter(b'\r'),
+        ]);
+        let mut reader: LineReader<32> = LineReader::new();
+        assert_eq!(reader.read_line(&mut serial), Ok("ad"));
+        serial.done();
+    }
+
+    #[test]
+    fn backspace_on_an_empty_line_echoes_nothing() {
+        let mut serial = serial(vec![
+            key(0x08, b""),
+            key(0x7f, b""),
+            key(b'a', b"a"),
+            enter(b'\n'),
+        ]);
+        let mut reader: LineReader<32> = LineReader::new();
+        assert_eq!(reader.read_line(&mut serial), Ok("a"));
+        serial.done();
+    }
+
+    #[test]
+    fn overflow_beeps_until_there_is_room_again() {
+        let mut serial = serial(vec![
+            key(b'a', b"a"),
+            key(b'b', b"b"),
+            key(b'c', b"\x07"),
+            key(0x08, b"\x08 \x08"),
+            key(b'c', b"c"),
+            key(b'd', b"\x07"),
+            enter(b'\r'),
+        ]);
+        let mut reader: LineReader<2> = LineReader::new();
+        assert_eq!(reader.read_line(&mut serial), Ok("ac"));
+        serial.done();
+    }
+
+    #[test]
+    fn control_characters_are_not_echoed() {
+        let mut serial = serial(vec![
+            key(0x1b, b""),
+            key(b'\t', b""),
+            key(b'x', b"x"),
+            enter(b'\r'),
+        ]);
+        let mut reader: LineReader<32> = LineReader::new();
+        assert_eq!(reader.read_line(&mut serial), Ok("x"));
+        serial.done();
+    }
+
+    #[test]
+    fn up_replaces_the_line_with_the_one_before() {
+        let mut serial = serial(vec![
+            key(b'a', b"a"),
+            enter(b'\r'),
+            key(b'b', b"b"),
+            key(0x1b, b""),
+            key(b'[', b""),
+            key(b'A', b"\x08\x1b[Ka"),
+            // There's nothing before that
+            key(0x1b, b""),
+            key(b'[', b""),
+            key(b'A', b"\x07"),
+            enter(b'\r'),
+        ]);
+        let mut reader: LineReader<32> = LineReader::new();
+        assert_eq!(reader.read_line(&mut serial), Ok("a"));
+        assert_eq!(reader.read_line(&mut serial), Ok("a"));
+        serial.done();
+    }
+
+    #[test]
+    fn partial_lines_are_kept_between_polls() {
+        let mut serial = serial(vec![
+            key(b'o', b"o"),
+            nothing(),
+            nothing(),
+            key(b'k', b"k"),
+            enter(b'\r'),
+            // The `\n` of a `\r\n` doesn't end another, empty, line
+            key(b'\n', b""),
+            nothing(),
+            key(b'n', b"n"),
+            enter(b'\n'),
+        ]);
+        let mut reader: LineReader<32> = LineReader::new();
+        assert_eq!(reader.poll_line(&mut serial), Ok(None));
+        assert_eq!(reader.poll_line(&mut serial), Ok(None));
+        assert_eq!(reader.poll_line(&mut serial), Ok(Some("ok")));
+        assert_eq!(reader.poll_line(&mut serial), Ok(None));
+        assert_eq!(reader.poll_line(&mut serial), Ok(Some("n")));
+        serial.done();
+    }
+
+    #[test]
+    fn an_error_drops_the_line_typed_so_far() {
+        let mut serial = serial(vec![
+            key(b'a', b"a"),
+            failure(),
+            key(b'b', b"b"),
+            enter(b'\r'),
+            key(b'c', b"c"),
+            failure(),
+            key(b'd', b"d"),
+            enter(b'\r'),
+        ]);
+        let mut reader: LineReader<32> = LineReader::new();
+        let error = Err(MockError::Io(ErrorKind::InvalidData));
+        assert_eq!(reader.read_line(&mut serial), error);
+        assert_eq!(reader.read_line(&mut serial), Ok("b"));
+        assert_eq!(
+            reader.poll_line(&mut serial),
+            error.clone().map(|_: &str| None)
+        );
+        assert_eq!(reader.poll_line(&mut serial), Ok(Some("d")));
+        serial.done();
+    }
+}
+
+mod snake {
+    use crate::snake::{Game, GameStatus, Turn, MIN_STEP_MS, START_STEP_MS};
+
+    /// Where the value `brightness` is on the display, which for the snake's
+    /// head is only ever in one place.
+    fn find(matrix: [[u8; 5]; 5], brightness: u8) -> Vec<(usize, usize)> {
+        let mut found = Vec::new();
+        for (row, values) in matrix.iter().enumerate() {
+            for (col, &value) in values.iter().enumerate() {
+                if value == brightness {
+                    found.push((row, col));
+                }
+            }
+        }
+        found
+    }
+
+    fn head(game: &Game) -> (usize, usize) {
+        find(game.game_matrix(2, 1, 3), 2)[0]
+    }
+
+    #[test]
+    fn starts_in_the_middle_going_right() {
+        for seed in 1..100 {
+            let mut game = Game::new(seed);
+            let matrix = game.game_matrix(2, 1, 3);
+            assert_eq!(find(matrix, 2), [(2, 2)]);
+            assert_eq!(find(matrix, 1), [(2, 1)]);
+            assert_eq!(find(matrix, 3).len(), 1);
+            assert_eq!(game.step_len_ms(), 1000);
+
+            game.step(Turn::None);
+            assert_eq!(head(&game), (2, 3));
+        }
+    }
+
+    #[test]
+    fn turns_relative_to_where_it_goes() {
+        let mut game = Game::new(1);
+        game.step(Turn::Left);
+        assert_eq!(head(&game), (1, 2));
+        game.step(Turn::Right);
+        assert_eq!(head(&game), (1, 3));
+    }
+
+    #[test]
+    fn wraps_around_the_edges() {
+        for seed in 1..100 {
+            let mut game = Game::new(seed);
+            for _ in 0..3 {
+                game.step(Turn::None);
+            }
+            assert_eq!(head(&game), (2, 0));
+            assert!(matches!(game.status, GameStatus::Ongoing));
+        }
+    }
+
+    #[test]
+    fn shows_the_score_in_rows_of_five() {
+        let mut game = Game::new(1);
+        game.score = 7;
+        let matrix = game.score_matrix();
+        assert_eq!(matrix[0], [1; 5]);
+        assert_eq!(matrix[1], [1, 1, 0, 0, 0]);
+        assert_eq!(matrix[2], [0; 5]);
+    }
+
+    #[test]
+    fn speeds_up_as_the_snake_grows() {
+        let mut game = Game::new(1);
+        assert_eq!(game.step_len_ms(), START_STEP_MS);
+        let mut last = START_STEP_MS;
+        for score in 1..=23 {
+            game.score = score;
+            assert!(game.step_len_ms() <= last, "at {}", score);
+            last = game.step_len_ms();
+        }
+        assert_eq!(last, MIN_STEP_MS);
+    }
+
+    /// Play a game to its end, heading straight for the food until the snake
+    /// is long enough to bite itself by going around in a tight circle.
+    /// `None` if it never ends.
+    fn play(game: &mut Game) -> Option<u8> {
+        // Where the snake is going, as a row and column step. It starts off
+        // going right.
+        let mut direction = (0, 1);
+        for _ in 0..500 {
+            if !matches!(game.status, GameStatus::Ongoing) {
+                return Some(game.score);
+            }
+            let (row, col) = head(game);
+            let food = find(game.game_matrix(2, 1, 3), 3)[0];
+            let turn = if game.score >= 4 {
+                Turn::Left
+            } else if direction.0 == 0 {
+                // Going along a row: stay on it if that's where the food is
+                if row == food.0 {
+                    Turn::None
+                } else {
+                    Turn::Left
+                }
+            } else if col == food.1 || row != food.0 {
+                Turn::None
+            } else {
+                Turn::Left
+            };
+            if let Turn::Left = turn {
+                direction = (-direction.1, direction.0);
+            }
+            game.step(turn);
+        }
+        None
+    }
+
+    #[test]
+    fn keeps_the_best_score() {
+        let mut game = Game::new(7);
+        let mut best = 0;
+        let mut beaten = 0;
+        for _ in 0..20 {
+            if let Some(score) = play(&mut game) {
+                assert_eq!(
+                    game.is_new_high_score(),
+                    score > best,
+                    "{} after {}",
+                    score,
+                    best
+                );
+                if score > best {
+                    beaten += 1;
+                }
+                best = best.max(score);
+                assert_eq!(game.high_score(), best);
+            }
+            game.reset();
+            assert!(!game.is_new_high_score());
+            assert_eq!(game.high_score(), best);
+        }
+        assert!(beaten > 0);
+    }
+
+    #[test]
+    fn carries_on_from_a_saved_high_score() {
+        let mut game = Game::new(7);
+        game.set_high_score(u8::MAX);
+        if play(&mut game).is_some() {
+            assert!(!game.is_new_high_score());
+        }
+        assert_eq!(game.high_score(), u8::MAX);
+        assert!(game.score_text().ends_with(" BEST 255"));
+    }
+
+    #[test]
+    fn tells_about_a_new_high_score() {
+        let mut game = Game::new(7);
+        for _ in 0..20 {
+            if play(&mut game).unwrap_or(0) > 0 {
+                break;
+            }
+            game.reset();
+        }
+        assert!(game.is_new_high_score());
+        assert_eq!(game.score_text().as_str().split(' ').nth(1), Some("HIGH"));
+    }
+}
+
+mod life {
+    use crate::life::{neighbours, population, step, to_leds, Grid};
+
+    /// A grid drawn as five rows of `#` for live cells and `.` for dead ones.
+    fn grid(rows: [&str; 5]) -> Grid {
+        let mut grid = [[false; 5]; 5];
+        for (cells, row) in grid.iter_mut().zip(rows) {
+            for (cell, c) in cells.iter_mut().zip(row.chars()) {
+                *cell = c == '#';
+            }
+        }
+        grid
+    }
+
+    #[test]
+    fn counts_neighbours_around_the_edges() {
+        let corners = grid(["#...#", ".....", ".....", ".....", "#...#"]);
+        assert_eq!(neighbours(&corners, 0, 0), 3);
+        assert_eq!(neighbours(&corners, 2, 2), 0);
+        assert_eq!(neighbours(&corners, 0, 2), 0);
+        assert_eq!(neighbours(&corners, 4, 0), 3);
+    }
+
+    #[test]
+    fn a_lonely_cell_dies() {
+        let lonely = grid([".....", ".....", "..#..", ".....", "....."]);
+        assert_eq!(population(&step(&lonely)), 0);
+    }
+
+    #[test]
+    fn a_block_stays_as_it_is() {
+        let block = grid([".....", ".##..", ".##..", ".....", "....."]);
+        assert_eq!(step(&block), block);
+    }
+
+    #[test]
+    fn a_blinker_blinks() {
+        let across = grid([".....", ".....", ".###.", ".....", "....."]);
+        let down = grid([".....", "..#..", "..#..", "..#..", "....."]);
+        assert_eq!(step(&across), down);
+        assert_eq!(step(&down), across);
+    }
+
+    #[test]
+    fn a_blinker_blinks_across_the_edge() {
+        let across = grid([".....", ".....", "##..#", ".....", "....."]);
+        let down = grid([".....", "#....", "#....", "#....", "....."]);
+        assert_eq!(step(&across), down);
+        assert_eq!(step(&down), across);
+    }
+
+    #[test]
+    fn a_glider_goes_all_the_way_round() {
+        let glider = grid([".#...", "..#..", "###..", ".....", "....."]);
+        // One row down and one column right every four generations
+        let moved = grid([".....", "..#..", "...#.", ".###.", "....."]);
+        let mut grid = glider;
+        for generation in 1..=20 {
+            grid = step(&grid);
+            assert_eq!(population(&grid), 5, "generation {}", generation);
+            if generation == 4 {
+                assert_eq!(grid, moved);
+            }
+        }
+        assert_eq!(grid, glider);
+    }
+
+    #[test]
+    fn lights_the_live_cells() {
+        let cells = grid(["#....", ".....", ".....", ".....", "....#"]);
+        let leds = to_leds(&cells);
+        assert_eq!(leds[0], [1, 0, 0, 0, 0]);
+        assert_eq!(leds[4], [0, 0, 0, 0, 1]);
+        assert_eq!(leds.iter().flatten().sum::<u8>(), 2);
+    }
+}
+
+mod pong {
+    use crate::pong::{Ball, Message, Pong, PADDLE_ROW, WINNING_SCORE};
+
+    #[test]
+    fn messages_survive_the_trip() {
+        for message in [
+            Message::Serve {
+                ticket: 0xdead_beef,
+            },
+            Message::Ball { col: 4, d_col: -1 },
+            Message::Ball { col: 0, d_col: 0 },
+            Message::Point,
+        ] {
+            assert_eq!(Message::decode(&message.encode()), Some(message));
+        }
+    }
+
+    #[test]
+    fn ignores_garbage() {
+        assert_eq!(Message::decode(b""), None);
+        assert_eq!(Message::decode(b"X"), None);
+        assert_eq!(Message::decode(&[b'B', 5, 0]), None);
+        assert_eq!(Message::decode(&[b'B', 0, 2]), None);
+        assert_eq!(Message::decode(b"SS"), None);
+    }
+
+    #[test]
+    fn keeps_the_paddle_on_the_field() {
+        let mut pong = Pong::new();
+        for _ in 0..10 {
+            pong.move_paddle_left();
+        }
+        assert_eq!(pong.paddle(), 0);
+        for _ in 0..10 {
+            pong.move_paddle_right();
+        }
+        assert_eq!(pong.paddle(), 3);
+        assert_eq!(pong.leds()[PADDLE_ROW as usize], [0, 0, 0, 1, 1]);
+    }
+
+    /// Step until the ball leaves our half.
+    fn play(pong: &mut Pong) -> Message {
+        for _ in 0..100 {
+            if let Some(message) = pong.step() {
+                return message;
+            }
+            let ball = pong.ball().unwrap();
+            assert!(
+                (0..5).contains(&ball.row) && (0..5).contains(&ball.col),
+                "{:?}",
+                ball
+            );
+        }
+        panic!("the ball never left");
+    }
+
+    #[test]
+    fn hands_the_ball_over_mirrored() {
+        let mut pong = Pong::new();
+        pong.serve(0);
+        let served = pong.ball().unwrap();
+        assert_eq!(
+            play(&mut pong),
+            Message::Ball {
+                col: 4 - served.col,
+                d_col: 0
+            }
+        );
+        assert_eq!(pong.ball(), None);
+
+        // Off the left edge on the way
+        pong.serve(-1);
+        assert_eq!(served.col, 1);
+        assert_eq!(pong.step(), None);
+        assert_eq!(pong.step(), None);
+        let ball = pong.ball().unwrap();
+        assert_eq!((ball.row, ball.col, ball.d_col), (1, 1, 1));
+        assert_eq!(play(&mut pong), Message::Ball { col: 1, d_col: -1 });
+    }
+
+    #[test]
+    fn bounces_off_the_paddle() {
+        let mut pong = Pong::new();
+        let paddle = pong.paddle();
+        pong.receive(Message::Ball {
+            col: paddle + 1,
+            d_col: 0,
+        });
+        // Down to just above the paddle, and back up
+        for _ in 0..3 {
+            assert_eq!(pong.step(), None);
+        }
+        let ball = pong.ball().unwrap();
+        assert_eq!((ball.row, ball.d_row), (PADDLE_ROW - 1, 1));
+        assert_eq!(pong.step(), None);
+        let ball = pong.ball().unwrap();
+        assert_eq!((ball.row, ball.d_row, ball.d_col), (PADDLE_ROW - 2, -1, 1));
+    }
+
+    #[test]
+    fn a_miss_is_a_point_for_them() {
+        let mut pong = Pong::new();
+        let paddle = pong.paddle();
+        pong.receive(Message::Ball {
+            col: (paddle + 3) % 5,
+            d_col: 0,
+        });
+        assert_eq!(play(&mut pong), Message::Point);
+        assert_eq!((pong.score, pong.their_score), (0, 1));
+        assert!(pong.serves_next());
+        pong.serve(1);
+        assert!(!pong.serves_next());
+    }
+
+    #[test]
+    fn a_ball_nobody_takes_comes_back() {
+        let mut pong = Pong::new();
+        pong.serve(1);
+        let message = play(&mut pong);
+        pong.bounce_back(message);
+        let ball: Ball = pong.ball().unwrap();
+        assert_eq!((ball.row, ball.d_row), (0, 1));
+    }
+
+    #[test]
+    fn the_first_to_five_wins() {
+        let mut pong = Pong::new();
+        for _ in 0..WINNING_SCORE {
+            assert!(!pong.is_over());
+            pong.receive(Message::Point);
+        }
+        assert!(pong.is_over());
+        let leds = pong.score_leds();
+        assert!(leds.iter().all(|row| row[0] == 1 && row[4] == 0));
+
+        pong.reset();
+        assert!(!pong.is_over());
+        assert_eq!(pong.score, 0);
+    }
+}
+
+mod maze {
+    use crate::maze::{Level, Maze, Roll, Tile, LEVELS, SCREEN};
+
+    static ROOM: Level = Level::new(&[b"#####", b"#S..#", b"#...#", b"#..G#", b"#####"]);
+
+    static PILLAR: Level = Level::new(&[b"#####", b"#S..#", b"#.#.#", b"#..G#", b"#####"]);
+
+    static CORNERS: Level = Level::new(&[b"#####", b"#S#G#", b"##..#", b"#...#", b"#####"]);
+
+    static NO_WALLS: Level = Level::new(&[b"S....", b".....", b".....", b".....", b"....G"]);
+
+    static CORRIDOR: Level = Level::new(&[
+        b"##########",
+        b"#S.......#",
+        b"##########",
+        b"##########",
+        b"##########",
+    ]);
+
+    #[test]
+    fn starts_on_the_start() {
+        let maze = Maze::new(&ROOM);
+        assert_eq!(maze.ball(), (1, 1));
+        assert!(!maze.is_solved());
+    }
+
+    #[test]
+    fn stays_put_while_level() {
+        let mut maze = Maze::new(&ROOM);
+        assert_eq!(maze.roll(0, 0), Roll::Still);
+        assert_eq!(maze.ball(), (1, 1));
+    }
+
+    #[test]
+    fn walls_stop_the_ball() {
+        let mut maze = Maze::new(&ROOM);
+        assert_eq!(maze.roll(-1, 0), Roll::Blocked);
+        assert_eq!(maze.roll(0, -1), Roll::Blocked);
+        assert_eq!(maze.roll(-1, -1), Roll::Blocked);
+        assert_eq!(maze.ball(), (1, 1));
+    }
+
+    #[test]
+    fn rolls_one_tile_whatever_the_tilt() {
+        let mut maze = Maze::new(&ROOM);
+        assert_eq!(maze.roll(0, 100), Roll::Moved);
+        assert_eq!(maze.ball(), (1, 2));
+    }
+
+    #[test]
+    fn rolls_diagonally_to_the_goal() {
+        let mut maze = Maze::new(&ROOM);
+        assert_eq!(maze.roll(1, 1), Roll::Moved);
+        assert_eq!(maze.ball(), (2, 2));
+        assert_eq!(maze.roll(1, 1), Roll::Goal);
+        assert!(maze.is_solved());
+    }
+
+    #[test]
+    fn slides_along_a_wall() {
+        // Up and to the right along the top wall goes right
+        let mut maze = Maze::new(&ROOM);
+        assert_eq!(maze.roll(-1, 1), Roll::Moved);
+        assert_eq!(maze.ball(), (1, 2));
+
+        // Down and to the left into the left wall goes down
+        let mut maze = Maze::new(&ROOM);
+        assert_eq!(maze.roll(1, -1), Roll::Moved);
+        assert_eq!(maze.ball(), (2, 1));
+    }
+
+    #[test]
+    fn goes_sideways_round_a_pillar_first() {
+        let mut maze = Maze::new(&PILLAR);
+        assert_eq!(maze.roll(1, 1), Roll::Moved);
+        assert_eq!(maze.ball(), (1, 2));
+    }
+
+    #[test]
+    fn does_not_squeeze_between_corners() {
+        let mut maze = Maze::new(&CORNERS);
+        assert_eq!(maze.roll(1, 1), Roll::Blocked);
+        assert_eq!(maze.ball(), (1, 1));
+    }
+
+    #[test]
+    fn does_not_roll_off_the_edge() {
+        let mut maze = Maze::new(&NO_WALLS);
+        assert_eq!(maze.roll(-1, -1), Roll::Blocked);
+        assert_eq!(maze.roll(-1, 0), Roll::Blocked);
+        assert_eq!(maze.roll(0, -1), Roll::Blocked);
+        assert_eq!(maze.ball(), (0, 0));
+    }
+
+    #[test]
+    fn the_view_follows_the_ball_to_the_next_screen() {
+        let mut maze = Maze::new(&CORRIDOR);
+        assert_eq!(maze.screen(), (0, 0));
+        for _ in 0..4 {
+            assert_eq!(maze.roll(0, 1), Roll::Moved);
+        }
+        assert_eq!(maze.ball(), (1, 5));
+        assert_eq!(maze.screen(), (0, 5));
+        assert_eq!(maze.ball_in_view(), (1, 0));
+
+        let view = maze.view();
+        assert_eq!(view[0], [Tile::Wall; SCREEN]);
+        assert_eq!(
+            view[1],
+            [Tile::Open, Tile::Open, Tile::Open, Tile::Open, Tile::Wall]
+        );
+    }
+
+    #[test]
+    fn every_level_fits_and_can_be_solved() {
+        for (number, level) in LEVELS.iter().enumerate() {
+            assert!(level.fits_screens(), "level {}", number);
+            assert_eq!(level.tile(level.start().0, level.start().1), Tile::Start);
+
+            // Flood the maze from the start, one straight step at a time
+            let (width, height) = (level.width(), level.height());
+            let mut seen = vec![vec![false; width]; height];
+            let mut todo = vec![level.start()];
+            while let Some((row, col)) = todo.pop() {
+                if level.tile(row, col) == Tile::Wall || seen[row][col] {
+                    continue;
+                }
+                seen[row][col] = true;
+                // Going off the top or left edge wraps around to a wall
+                let (up, left) = (row.wrapping_sub(1), col.wrapping_sub(1));
+                todo.extend([(row + 1, col), (up, col), (row, col + 1), (row, left)]);
+            }
+            let goal = level.find(Tile::Goal).unwrap();
+            assert!(seen[goal.0][goal.1], "level {} can't be solved", number);
+        }
+    }
+}
+
+mod packet {
+    use crate::packet::{
+        crc16, decode, encode, types, DecodeError, EncodeError, Packet, MAX_PAYLOAD_LEN,
+    };
+
+    const PACKET: Packet = Packet {
+        packet_type: types::IMAGE,
+        sequence: 7,
+        payload: &[0b10001, 0b01010, 0b00100, 0b01010, 0b10001],
+    };
+
+    #[test]
+    fn crc_is_ccitt_false() {
+        // The check value every CRC catalogue lists for it
+        assert_eq!(crc16(b"123456789"), 0x29b1);
+        assert_eq!(crc16(&[]), 0xffff);
+    }
+
+    #[test]
+    fn decodes_what_it_encodes() {
+        let mut buffer = [0; 16];
+        let bytes = encode(&PACKET, &mut buffer).unwrap();
+        assert_eq!(bytes.len(), 3 + 5 + 2);
+        assert_eq!(&bytes[..3], &[types::IMAGE, 5, 7]);
+        assert_eq!(decode(bytes), Ok(PACKET));
+    }
+
+    #[test]
+    fn an_empty_payload_is_fine() {
+        let packet = Packet {
+            packet_type: types::COMMAND,
+            sequence: 255,
+            payload: &[],
+        };
+        let mut buffer = [0; 5];
+        assert_eq!(decode(encode(&packet, &mut buffer).unwrap()), Ok(packet));
+    }
+
+    #[test]
+    fn encode_checks_the_sizes() {
+        let mut buffer = [0; 9];
+        assert_eq!(
+            encode(&PACKET, &mut buffer),
+            Err(EncodeError::BufferTooSmall { needed: 10 })
+        );
+
+        let payload = [0; MAX_PAYLOAD_LEN + 1];
+        let packet = Packet {
+            payload: &payload,
+            ..PACKET
+        };
+        let mut buffer = [0; 300];
+        assert_eq!(
+            encode(&packet, &mut buffer),
+            Err(EncodeError::PayloadTooLong)
+        );
+    }
+
+    #[test]
+    fn decode_checks_the_length() {
+        assert_eq!(decode(&[1, 0, 0, 0]), Err(DecodeError::TooShort));
+
+        let mut buffer = [0; 16];
+        let bytes = encode(&PACKET, &mut buffer).unwrap();
+        assert_eq!(
+            decode(&bytes[1..]),
+            Err(DecodeError::LengthMismatch {
+                declared: 7,
+                actual: 4
+            })
+        );
+        assert_eq!(
+            decode(&bytes[..bytes.len() - 1]),
+            Err(DecodeError::LengthMismatch {
+                declared: 5,
+                actual: 4
+            })
+        );
+    }
+
+    #[test]
+    fn every_flipped_bit_is_caught() {
+        let mut buffer = [0; 16];
+        let len = encode(&PACKET, &mut buffer).unwrap().len();
+        for bit in 0..len * 8 {
+            let mut garbled = buffer;
+            garbled[bit / 8] ^= 1 << (bit % 8);
+            // A flip in the length byte is a length mismatch, any other one
+            // a bad CRC
+            assert!(decode(&garbled[..len]).is_err(), "bit {}", bit);
+            if bit / 8 != 1 {
+                assert!(matches!(
+                    decode(&garbled[..len]),
+                    Err(DecodeError::BadCrc { .. })
+                ));
+            }
+        }
+    }
+}
+
+mod clock {
+    use crate::clock::{Drift, TimeOfDay, WallClock};
+
+    const HOUR_MS: u64 = 60 * 60 * 1_000;
+    const DAY_MS: u64 = 24 * HOUR_MS;
+
+    #[test]
+    fn parses_hours_and_minutes_with_or_without_seconds() {
+        assert_eq!(TimeOfDay::parse("07:05"), TimeOfDay::new(7, 5, 0));
+        assert_eq!(TimeOfDay::parse("23:59:30"), TimeOfDay::new(23, 59, 30));
+        assert_eq!(TimeOfDay::parse("7:5"), TimeOfDay::new(7, 5, 0));
+        for text in [
+            "24:00",
+            "12:60",
+            "12",
+            "12:",
+            ":30",
+            "12:30:00:00",
+            "012:30",
+            "ab:cd",
+        ] {
+            assert_eq!(TimeOfDay::parse(text), None, "{}", text);
+        }
+    }
+
+    #[test]
+    fn shows_the_time_with_leading_zeros() {
+        assert_eq!(TimeOfDay::new(9, 3, 7).unwrap().to_string(), "09:03:07");
+    }
+
+    #[test]
+    fn counts_on_from_the_time_it_was_set_to() {
+        let mut clock = WallClock::new();
+        assert_eq!(clock.now(5_000), TimeOfDay::new(0, 0, 5).unwrap());
+
+        assert_eq!(clock.set(10_000, TimeOfDay::new(23, 0, 0).unwrap()), None);
+        assert_eq!(
+            clock.now(10_000 + HOUR_MS / 2),
+            TimeOfDay::new(23, 30, 0).unwrap()
+        );
+        // Into the next day
+        assert_eq!(
+            clock.now(10_000 + 2 * HOUR_MS),
+            TimeOfDay::new(1, 0, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn setting_it_again_measures_the_drift() {
+        let mut clock = WallClock::new();
+        clock.set(0, TimeOfDay::new(12, 0, 0).unwrap());
+        // A day later it's 12:00:02 by our clock, but really only noon
+        let drift = clock.set(DAY_MS + 2_000, TimeOfDay::new(12, 0, 0).unwrap());
+        assert_eq!(
+            drift,
+            Some(Drift {
+                error_ms: 2_000,
+                over_ms: DAY_MS + 2_000
+            })
+        );
+        assert_eq!(drift.unwrap().ppm(), 23);
+    }
+
+    #[test]
+    fn drift_across_midnight_is_small() {
+        let mut clock = WallClock::new();
+        clock.set(0, TimeOfDay::new(23, 59, 0).unwrap());
+        // Our clock says 00:00:30, the right time is still 23:59:50
+        let drift = clock
+            .set(90_000, TimeOfDay::new(23, 59, 50).unwrap())
+            .unwrap();
+        assert_eq!(drift.error_ms, 40_000);
+
+        // And the other way around, our clock is behind at 23:59:50
+        let mut clock = WallClock::new();
+        clock.set(0, TimeOfDay::new(23, 59, 40).unwrap());
+        let drift = clock
+            .set(10_000, TimeOfDay::new(0, 0, 10).unwrap())
+            .unwrap();
+        assert_eq!(drift.error_ms, -20_000);
+    }
+
+    #[test]
+    fn trim_corrects_a_fast_clock() {
+        let mut clock = WallClock::new();
+        clock.set(0, TimeOfDay::new(0, 0, 0).unwrap());
+        // 50 ppm slower loses 4.32 seconds a day
+        clock.set_trim_ppm(0, -50);
+        assert_eq!(clock.trim_ppm(), -50);
+        assert_eq!(clock.now(DAY_MS), TimeOfDay::new(23, 59, 55).unwrap());
+    }
+
+    #[test]
+    fn changing_the_trim_keeps_the_time() {
+        let mut clock = WallClock::new();
+        clock.set(0, TimeOfDay::new(8, 0, 0).unwrap());
+        clock.set_trim_ppm(HOUR_MS + 500, 100_000);
+        assert_eq!(clock.now(HOUR_MS + 500), TimeOfDay::new(9, 0, 0).unwrap());
+        // 10% fast from here on
+        assert_eq!(
+            clock.now(HOUR_MS + 500 + 10_000),
+            TimeOfDay::new(9, 0, 11).unwrap()
+        );
+    }
+}
+
+mod stopwatch {
+    use crate::stopwatch::{Elapsed, LapOrReset, Stopwatch, MAX_LAPS};
+
+    #[test]
+    fn only_counts_while_running() {
+        let mut stopwatch = Stopwatch::new();
+        assert_eq!(stopwatch.elapsed_ms(1_000), 0);
+
+        assert!(stopwatch.start_stop(1_000));
+        assert_eq!(stopwatch.elapsed_ms(3_500), 2_500);
+        assert!(!stopwatch.start_stop(4_000));
+        assert_eq!(stopwatch.elapsed_ms(10_000), 3_000);
+
+        // Going on from where it stopped
+        assert!(stopwatch.start_stop(20_000));
+        assert_eq!(stopwatch.elapsed_ms(21_000), 4_000);
+    }
+
+    #[test]
+    fn laps_while_running_and_resets_when_stopped() {
+        let mut stopwatch = Stopwatch::new();
+        stopwatch.start_stop(0);
+        assert_eq!(
+            stopwatch.lap_or_reset(10_000),
+            LapOrReset::Lap {
+                number: 1,
+                lap_ms: 10_000,
+                split_ms: 10_000
+            }
+        );
+        assert_eq!(
+            stopwatch.lap_or_reset(25_000),
+            LapOrReset::Lap {
+                number: 2,
+                lap_ms: 15_000,
+                split_ms: 25_000
+            }
+        );
+        assert_eq!(
+            stopwatch.laps().collect::<Vec<_>>(),
+            [(10_000, 10_000), (15_000, 25_000)]
+        );
+
+        stopwatch.start_stop(30_000);
+        assert_eq!(stopwatch.lap_or_reset(31_000), LapOrReset::Reset);
+        assert_eq!(stopwatch.elapsed_ms(31_000), 0);
+        assert_eq!(stopwatch.laps().count(), 0);
+    }
+
+    #[test]
+    fn a_lap_after_a_pause_leaves_the_pause_out() {
+        let mut stopwatch = Stopwatch::new();
+        stopwatch.start_stop(0);
+        stopwatch.lap_or_reset(1_000);
+        stopwatch.start_stop(2_000);
+        stopwatch.start_stop(60_000);
+        assert_eq!(
+            stopwatch.lap_or_reset(61_000),
+            LapOrReset::Lap {
+                number: 2,
+                lap_ms: 2_000,
+                split_ms: 3_000
+            }
+        );
+    }
+
+    #[test]
+    fn stops_taking_laps_when_full() {
+        let mut stopwatch = Stopwatch::new();
+        stopwatch.start_stop(0);
+        for lap in 1..=MAX_LAPS as u64 {
+            assert!(matches!(
+                stopwatch.lap_or_reset(lap * 1_000),
+                LapOrReset::Lap { .. }
+            ));
+        }
+        assert_eq!(stopwatch.lap_or_reset(100_000), LapOrReset::Full);
+        assert_eq!(stopwatch.laps().count(), MAX_LAPS);
+        // It keeps running all the same
+        assert_eq!(stopwatch.elapsed_ms(200_000), 200_000);
+    }
+
+    #[test]
+    fn shows_hundredths_and_hours_only_when_there_are_any() {
+        assert_eq!(Elapsed(0).to_string(), "0:00.00");
+        assert_eq!(Elapsed(83_459).to_string(), "1:23.45");
+        assert_eq!(
+            Elapsed((62 * 60 + 3) * 1_000 + 450).to_string(),
+            "1:02:03.45"
+        );
+    }
+}
+
+mod data_log {
+    use crate::data_log::{rows, Row, Sample};
+
+    fn sample(ms: u32) -> Sample {
+        Sample {
+            ms,
+            x: -24,
+            y: 12,
+            z: 1008,
+        }
+    }
+
+    #[test]
+    fn a_sample_survives_being_a_record() {
+        let sample = Sample {
+            ms: 123_456,
+            x: -16_000,
+            y: 0,
+            z: 16_000,
+        };
+        assert_eq!(Sample::from_record(sample.to_record()), sample);
+    }
+
+    #[test]
+    fn a_timestamp_going_back_starts_a_new_run() {
+        let records = [0, 100, 200, 0, 100, 40, 40].map(|ms| sample(ms).to_record());
+        let runs: Vec<_> = rows(records.iter().copied()).map(|row| row.run).collect();
+        assert_eq!(runs, [1, 1, 1, 2, 2, 3, 4]);
+    }
+
+    #[test]
+    fn rows_are_comma_separated() {
+        let row = Row {
+            run: 2,
+            sample: sample(1_000),
+        };
+        assert_eq!(row.to_string(), "2,1000,-24,12,1008");
+    }
+}
+
+mod pairing {
+    use crate::pairing::{hmac, Error, Event, Message, Pairing, MAX_PAYLOAD_LEN};
+
+    const KEY: [u8; 16] = *b"sixteen byte key";
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    /// Board `a` challenges `b`, and both end up paired. Every `nonce` makes
+    /// for another pairing.
+    fn paired(nonce: u8) -> (Pairing, Pairing) {
+        let (mut a, mut b) = (Pairing::new(KEY), Pairing::new(KEY));
+        let challenge = a.challenge([nonce; 8]);
+        let response = match b.receive(&challenge, [!nonce; 8]) {
+            Ok(Event::Reply(response)) => response,
+            other => panic!("{:?}", other),
+        };
+        let confirm = match a.receive(&response, [0; 8]) {
+            Ok(Event::Paired(Some(confirm))) => confirm,
+            other => panic!("{:?}", other),
+        };
+        assert_eq!(b.receive(&confirm, [0; 8]), Ok(Event::Paired(None)));
+        assert!(a.is_paired() && b.is_paired());
+        (a, b)
+    }
+
+    #[test]
+    fn hmac_matches_rfc_4231() {
+        assert_eq!(
+            hex(&hmac(&[0x0b; 20], &[b"Hi There"])),
+            "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7"
+        );
+        assert_eq!(
+            hex(&hmac(b"Jefe", &[b"what do ya want ", b"for nothing?"])),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn messages_round_trip() {
+        let messages = [
+            Message::Challenge { nonce: [1; 8] },
+            Message::Response {
+                nonce: [2; 8],
+                tag: [3; 8],
+            },
+            Message::Confirm { tag: [4; 8] },
+            Message::Data {
+                counter: 5,
+                payload: b"",
+                tag: [6; 8],
+            },
+            Message::Data {
+                counter: u32::MAX,
+                payload: &[7; MAX_PAYLOAD_LEN],
+                tag: [8; 8],
+            },
+        ];
+        for message in messages {
+            assert_eq!(Message::decode(&message.encode()), Some(message));
+        }
+        assert_eq!(Message::decode(b""), None);
+        assert_eq!(Message::decode(b"C1234567"), None);
+        assert_eq!(Message::decode(b"X12345678"), None);
+    }
+
+    #[test]
+    fn paired_boards_talk_both_ways() {
+        let (mut a, mut b) = paired(1);
+        let message = a.send(b"hello").unwrap();
+        assert_eq!(b.receive(&message, [0; 8]), Ok(Event::Data(b"hello")));
+        let message = b.send(b"hi").unwrap();
+        assert_eq!(a.receive(&message, [0; 8]), Ok(Event::Data(b"hi")));
+    }
+
+    #[test]
+    fn a_replay_is_rejected() {
+        let (mut a, mut b) = paired(1);
+        let first = a.send(b"1").unwrap();
+        let second = a.send(b"2").unwrap();
+        assert_eq!(b.receive(&second, [0; 8]), Ok(Event::Data(b"2")));
+        assert_eq!(b.receive(&second, [0; 8]), Err(Error::Replayed));
+        // Came too late, but that can't be told apart from a replay
+        assert_eq!(b.receive(&first, [0; 8]), Err(Error::Replayed));
+    }
+
+    #[test]
+    fn a_message_sent_back_is_rejected() {
+        let (mut a, _) = paired(1);
+        let message = a.send(b"echo").unwrap();
+        assert_eq!(a.receive(&message, [0; 8]), Err(Error::Forged));
+    }
+
+    #[test]
+    fn a_message_from_an_earlier_pairing_is_rejected() {
+        let (mut a, _) = paired(1);
+        let old = a.send(b"old").unwrap();
+        let (_, mut b) = paired(2);
+        assert_eq!(b.receive(&old, [0; 8]), Err(Error::Forged));
+    }
+
+    #[test]
+    fn a_changed_message_is_rejected() {
+        let (mut a, mut b) = paired(1);
+        let mut message = a.send(b"pay 1").unwrap();
+        message[9] = b'9';
+        assert_eq!(b.receive(&message, [0; 8]), Err(Error::Forged));
+    }
+
+    #[test]
+    fn a_board_with_another_key_never_pairs() {
+        let (mut a, mut b) = (Pairing::new(KEY), Pairing::new(*b"another key, 16b"));
+        let challenge = a.challenge([1; 8]);
+        let response = match b.receive(&challenge, [2; 8]) {
+            Ok(Event::Reply(response)) => response,
+            other => panic!("{:?}", other),
+        };
+        assert_eq!(a.receive(&response, [3; 8]), Err(Error::Forged));
+        assert!(!a.is_paired());
+        assert_eq!(a.send(b"hello"), Err(Error::NotPaired));
+    }
+
+    #[test]
+    fn an_old_response_doesnt_answer_a_new_challenge() {
+        let (mut a, mut b) = (Pairing::new(KEY), Pairing::new(KEY));
+        let challenge = a.challenge([1; 8]);
+        let old = match b.receive(&challenge, [2; 8]) {
+            Ok(Event::Reply(response)) => response,
+            other => panic!("{:?}", other),
+        };
+        a.challenge([9; 8]);
+        assert_eq!(a.receive(&old, [3; 8]), Err(Error::Forged));
+        // And a confirm nobody asked for doesn't fit in at all
+        let (mut c, _) = paired(1);
+        let message = c.send(b"x").unwrap();
+        assert_eq!(
+            Pairing::new(KEY).receive(&message, [0; 8]),
+            Err(Error::Unexpected)
+        );
+    }
+
+    #[test]
+    fn a_long_payload_doesnt_fit() {
+        let (mut a, _) = paired(1);
+        assert_eq!(a.send(&[0; MAX_PAYLOAD_LEN + 1]), Err(Error::TooLong));
+    }
+}
+
+mod register_map {
+    use crate::register_map::{registers, RegisterMap, WHO_AM_I_VALUE};
+
+    #[test]
+    fn reads_start_at_the_chosen_register() {
+        let mut map = RegisterMap::new();
+        map.set_buttons(false, true);
+        map.set_temperature(-6);
+
+        // Nothing chosen yet, so it's from the start
+        assert_eq!(map.to_read()[..2], [WHO_AM_I_VALUE, 0b10]);
+
+        assert!(!map.write(&[registers::TEMPERATURE]));
+        assert_eq!(map.to_read()[..2], (-6i16).to_le_bytes());
+        assert_eq!(map.to_read().len(), 7);
+        // Again, however much was read
+        assert_eq!(map.to_read()[..2], (-6i16).to_le_bytes());
+
+        assert!(!map.write(&[registers::LEDS]));
+        assert_eq!(map.to_read(), [0; 5]);
+    }
+
+    #[test]
+    fn only_the_leds_can_be_written() {
+        let mut map = RegisterMap::new();
+        assert!(!map.write(&[registers::WHO_AM_I, 1, 2, 3, 4]));
+        map.write(&[registers::WHO_AM_I]);
+        assert_eq!(map.to_read(), RegisterMap::new().to_read());
+
+        assert!(map.write(&[registers::LEDS, 0b10000, 0, 0b00100, 0, 0b00001, 0xAA]));
+        assert_eq!(
+            map.leds(),
+            [
+                [1, 0, 0, 0, 0],
+                [0, 0, 0, 0, 0],
+                [0, 0, 1, 0, 0],
+                [0, 0, 0, 0, 0],
+                [0, 0, 0, 0, 1],
+            ]
+        );
+        // The same again changes nothing
+        assert!(!map.write(&[registers::LEDS, 0b10000]));
+        assert!(!map.write(&[]));
+    }
+
+    #[test]
+    fn a_register_past_the_end_reads_as_nothing() {
+        let mut map = RegisterMap::new();
+        assert!(!map.write(&[0x80, 1]));
+        assert!(map.to_read().is_empty());
+    }
+}
+
+mod sniffer {
+    use crate::packet::crc16;
+    use crate::sniffer::{
+        crc16_air, decode, encode, hexdump, whiten, Capture, Error, CAPTURE_LEN, MAX_PACKET_LEN,
+    };
+
+    /// `"ubit"`, least significant byte first.
+    const ADDRESS: [u8; 4] = [0x74, 0x69, 0x62, 0x75];
+    const IV: u8 = 0x18;
+
+    #[test]
+    fn whitening_twice_gets_the_data_back() {
+        let data = *b"The quick brown fox";
+        let mut whitened = data;
+        whiten(&mut whitened, IV);
+        assert_ne!(whitened, data);
+        whiten(&mut whitened, IV);
+        assert_eq!(whitened, data);
+    }
+
+    #[test]
+    fn whitening_depends_on_the_iv_only_below_bit_6() {
+        let (mut a, mut b, mut c) = ([0; 8], [0; 8], [0; 8]);
+        whiten(&mut a, 7);
+        whiten(&mut b, 7 | 0x40);
+        whiten(&mut c, 8);
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn the_air_crc_is_the_usual_one_over_mirrored_bytes() {
+        let bytes = b"123456789";
+        let mirrored: Vec<u8> = bytes.iter().map(|byte| byte.reverse_bits()).collect();
+        assert_eq!(crc16_air(0xffff, bytes), crc16(&mirrored));
+    }
+
+    #[test]
+    fn decodes_what_was_sent_in_any_group() {
+        for group in [0, 1, 0x21, 255] {
+            let mut capture = [0x5a; CAPTURE_LEN];
+            encode(ADDRESS, IV, group, &[1, 0, 1, b'h', b'i'], &mut capture);
+            assert_eq!(
+                decode(ADDRESS, IV, &mut capture),
+                Ok(Capture {
+                    group,
+                    packet: &[1, 0, 1, b'h', b'i'],
+                    crc_ok: true
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn a_flipped_bit_fails_the_crc() {
+        let mut capture = [0; CAPTURE_LEN];
+        encode(ADDRESS, IV, 1, &[1, 2, 3], &mut capture);
+        capture[3] ^= 0x10;
+        assert!(!decode(ADDRESS, IV, &mut capture).unwrap().crc_ok);
+
+        // So does hearing it from another address
+        encode(ADDRESS, IV, 1, &[1, 2, 3], &mut capture);
+        assert!(
+            !decode([0x74, 0x69, 0x62, 0x76], IV, &mut capture)
+                .unwrap()
+                .crc_ok
+        );
+    }
+
+    #[test]
+    fn a_full_packet_fits() {
+        let mut capture = [0; CAPTURE_LEN];
+        encode(ADDRESS, IV, 1, &[0xee; MAX_PACKET_LEN], &mut capture);
+        assert_eq!(
+            decode(ADDRESS, IV, &mut capture).unwrap().packet.len(),
+            MAX_PACKET_LEN
+        );
+    }
+
+    #[test]
+    fn noise_is_too_long() {
+        let mut capture = [0; CAPTURE_LEN];
+        capture[1] = 200;
+        whiten(&mut capture[1..2], IV);
+        assert_eq!(decode(ADDRESS, IV, &mut capture), Err(Error::TooLong(200)));
+    }
+
+    #[test]
+    fn dumps_hex_and_text_16_bytes_a_line() {
+        let bytes: Vec<u8> = (0..18).map(|i| b'@' + i).collect();
+        let lines: Vec<String> = hexdump(&bytes).map(|line| line.to_string()).collect();
+        assert_eq!(
+            lines,
+            [
+                "0000  40 41 42 43 44 45 46 47 48 49 4a 4b 4c 4d 4e 4f  @ABCDEFGHIJKLMNO",
+                "0010  50 51                                            PQ",
+            ]
+        );
+        assert_eq!(
+            hexdump(&[0, b'a']).next().unwrap().to_string().trim_end(),
+            format!("0000  00 61{}  .a", " ".repeat(42))
+        );
+    }
+}
+
+mod ndef {
+    use crate::ndef::{uri_message, Error, MAX_URI_LEN};
+
+    #[test]
+    fn the_longest_prefix_is_used() {
+        assert_eq!(
+            &uri_message("https://www.rust-lang.org").unwrap()[..],
+            b"\xd1\x01\x0eU\x02rust-lang.org"
+        );
+        assert_eq!(
+            &uri_message("https://docs.rs").unwrap()[..],
+            b"\xd1\x01\x08U\x04docs.rs"
+        );
+    }
+
+    #[test]
+    fn a_uri_without_a_known_start_is_kept_whole() {
+        assert_eq!(
+            &uri_message("geo:0,0").unwrap()[..],
+            b"\xd1\x01\x08U\x00geo:0,0"
+        );
+    }
+
+    #[test]
+    fn a_long_uri_is_refused() {
+        let uri = format!("https://{}", "a".repeat(MAX_URI_LEN));
+        assert!(uri_message(&uri).is_ok());
+        let uri = format!("https://{}", "a".repeat(MAX_URI_LEN + 1));
+        assert_eq!(uri_message(&uri), Err(Error::TooLong));
+    }
+}
+
+mod type2_tag {
+    use crate::ndef::{uri_message, MAX_MESSAGE_LEN};
+    use crate::type2_tag::{commands, Response, Type2Tag, PAGES};
+
+    const UID: [u8; 7] = [0x5f, 1, 2, 3, 4, 5, 6];
+
+    fn read(tag: &Type2Tag, page: u8) -> [u8; 16] {
+        match tag.respond(&[commands::READ, page]) {
+            Response::Data(data) => data,
+            response => panic!("{:?}", response),
+        }
+    }
+
+    #[test]
+    fn starts_with_the_uid_and_the_capabilities() {
+        let tag = Type2Tag::new(UID, b"").unwrap();
+        let data = read(&tag, 0);
+        assert_eq!(data[..4], [0x5f, 1, 2, 0x88 ^ 0x5f ^ 1 ^ 2]);
+        assert_eq!(data[4..8], [3, 4, 5, 6]);
+        assert_eq!(data[8..12], [3 ^ 4 ^ 5 ^ 6, 0x48, 0xff, 0xff]);
+        assert_eq!(data[12..], [0xe1, 0x10, 17, 0x0f]);
+    }
+
+    #[test]
+    fn holds_the_message_in_a_tlv() {
+        let message = uri_message("https://docs.rs").unwrap();
+        let tag = Type2Tag::new(UID, &message).unwrap();
+        let data = read(&tag, 4);
+        assert_eq!(data[..2], [0x03, message.len() as u8]);
+        assert_eq!(data[2..14], message[..]);
+        assert_eq!(data[14], 0xfe);
+    }
+
+    #[test]
+    fn reads_past_the_end_start_over() {
+        let tag = Type2Tag::new(UID, b"").unwrap();
+        let last = (PAGES - 1) as u8;
+        assert_eq!(read(&tag, last)[4..], read(&tag, 0)[..12]);
+        assert_eq!(tag.respond(&[commands::READ, PAGES as u8]), Response::Nak);
+    }
+
+    #[test]
+    fn writes_are_refused_and_halt_sleeps() {
+        let tag = Type2Tag::new(UID, b"").unwrap();
+        assert_eq!(
+            tag.respond(&[commands::WRITE, 4, 1, 2, 3, 4]),
+            Response::Nak
+        );
+        assert_eq!(tag.respond(&[commands::HALT, 0]), Response::Sleep);
+    }
+
+    #[test]
+    fn the_longest_message_fits() {
+        assert!(Type2Tag::new(UID, &[0; MAX_MESSAGE_LEN]).is_ok());
+        assert!(Type2Tag::new(UID, &[0; MAX_MESSAGE_LEN + 1]).is_err());
+    }
+}
+
+mod pulse {
+    use crate::pulse::PulseStats;
+
+    #[test]
+    fn needs_a_high_and_a_low() {
+        let mut stats = PulseStats::new();
+        assert_eq!(stats.frequency_hz(16_000_000), None);
+        stats.add(true, 100);
+        stats.add(true, 100);
+        assert_eq!(stats.duty_percent(), None);
+        stats.add(false, 300);
+        assert_eq!(stats.phases(), 3);
+        assert_eq!(stats.period_ticks(), Some(400.0));
+        assert_eq!(stats.duty_percent(), Some(25.0));
+    }
+
+    #[test]
+    fn a_1khz_square_wave() {
+        let mut stats = PulseStats::new();
+        for _ in 0..1000 {
+            stats.add(true, 8000);
+            stats.add(false, 8000);
+        }
+        assert_eq!(stats.frequency_hz(16_000_000), Some(1000.0));
+        assert_eq!(stats.duty_percent(), Some(50.0));
+    }
+
+    #[test]
+    fn missed_phases_only_average_over_fewer() {
+        let mut stats = PulseStats::new();
+        // Three lows went missing
+        for _ in 0..4 {
+            stats.add(true, 160);
+        }
+        stats.add(false, 480);
+        assert_eq!(stats.frequency_hz(16_000_000), Some(25_000.0));
+        assert_eq!(stats.duty_percent(), Some(25.0));
+    }
+}
+
+mod metal_detector {
+    use crate::metal_detector::{
+        bar_graph, bar_leds, pitch_hz, MetalDetector, FULL_NT, HIGHEST_HZ, LEDS, LOWEST_HZ,
+        QUIET_NT,
+    };
+
+    const EARTH: [i32; 3] = [20_000, -5_000, 40_000];
+
+    /// Feed `field` to `detector` a couple of times, for the smoothing to
+    /// catch up, and return the last deviation.
+    fn settle(detector: &mut MetalDetector, field: [i32; 3]) -> u32 {
+        (0..20).map(|_| detector.update(field)).last().unwrap()
+    }
+
+    #[test]
+    fn the_first_measurement_is_the_baseline() {
+        let mut detector = MetalDetector::new();
+        assert_eq!(detector.update(EARTH), 0);
+        assert_eq!(settle(&mut detector, EARTH), 0);
+    }
+
+    #[test]
+    fn metal_shows_up_and_stays() {
+        let mut detector = MetalDetector::new();
+        settle(&mut detector, EARTH);
+        let near = [EARTH[0] + 3_000, EARTH[1], EARTH[2] + 4_000];
+        let deviation = settle(&mut detector, near);
+        assert!((4_900..=5_000).contains(&deviation), "{}", deviation);
+        // Far above the quiet level, the baseline doesn't follow
+        for _ in 0..1_000 {
+            detector.update(near);
+        }
+        assert!(settle(&mut detector, near) >= 4_900);
+        assert!(settle(&mut detector, EARTH) < 100);
+    }
+
+    #[test]
+    fn the_baseline_follows_drift() {
+        let mut detector = MetalDetector::new();
+        settle(&mut detector, EARTH);
+        let mut field = EARTH;
+        for _ in 0..100 {
+            field[2] += 100;
+            assert!(settle(&mut detector, field) < QUIET_NT);
+        }
+    }
+
+    #[test]
+    fn rebaselining_starts_over() {
+        let mut detector = MetalDetector::new();
+        settle(&mut detector, EARTH);
+        let turned = [-EARTH[0], EARTH[1], EARTH[2]];
+        assert!(settle(&mut detector, turned) > FULL_NT);
+        detector.rebaseline();
+        assert_eq!(settle(&mut detector, turned), 0);
+    }
+
+    #[test]
+    fn bars_and_pitch_ramp_up() {
+        assert_eq!(bar_leds(QUIET_NT - 1), 0);
+        assert_eq!(pitch_hz(QUIET_NT - 1), 0);
+        assert_eq!(bar_leds(QUIET_NT), 1);
+        assert_eq!(pitch_hz(QUIET_NT), LOWEST_HZ);
+        assert_eq!(bar_leds(FULL_NT), LEDS);
+        assert_eq!(pitch_hz(10 * FULL_NT), HIGHEST_HZ);
+        let mut last = (0, 0);
+        for deviation in (0..=FULL_NT).step_by(100) {
+            let now = (bar_leds(deviation), pitch_hz(deviation));
+            assert!(now.0 >= last.0 && now.1 >= last.1);
+            last = now;
+        }
+    }
+
+    #[test]
+    fn bars_fill_from_the_bottom_left() {
+        assert_eq!(bar_graph(0), [[0; 5]; 5]);
+        assert_eq!(bar_graph(7)[4], [1; 5]);
+        assert_eq!(bar_graph(7)[3], [1, 1, 0, 0, 0]);
+        assert_eq!(bar_graph(7)[2], [0; 5]);
+        assert_eq!(bar_graph(LEDS), [[1; 5]; 5]);
+    }
+}
+
+mod lsm303_registers {
+    use crate::lsm303_registers::{describe, find, Device, REGISTERS};
+
+    fn described(name: &str, value: u8) -> String {
+        let mut out = String::new();
+        describe(find(name).unwrap(), value, &mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn fields_get_their_names() {
+        assert_eq!(
+            described("CTRL_REG1_A", 0x57),
+            "CTRL_REG1_A       0x20 = 0x57 ODR=100 Hz LPen=0 Zen=1 Yen=1 Xen=1\r\n"
+        );
+        assert!(described("CTRL_REG4_A", 0x90).contains(" BDU=1 BLE=0 FS=4g HR=0 ST=off "));
+        assert!(described("CFG_REG_A_M", 0x8c).contains(" ODR=100 Hz MD=continuous"));
+    }
+
+    #[test]
+    fn values_without_a_name_are_numbers() {
+        assert!(described("CTRL_REG1_A", 0xf0).contains(" ODR=15 "));
+        assert!(described("INT1_THS_A", 0x96).ends_with(" value=22\r\n"));
+        assert!(described("WHO_AM_I_A", 0x33).ends_with(" value=51\r\n"));
+    }
+
+    #[test]
+    fn names_are_found_ignoring_case() {
+        let register = find("cfg_reg_a_m").unwrap();
+        assert_eq!(register.address, 0x60);
+        assert_eq!(register.device, Device::Magnetometer);
+        assert_eq!(register.device.address(), 0x1E);
+        assert!(find("CTRL_REG7_A").is_none());
+    }
+
+    #[test]
+    fn the_tables_are_consistent() {
+        for (i, register) in REGISTERS.iter().enumerate() {
+            assert!(
+                REGISTERS[..i]
+                    .iter()
+                    .all(|other| other.name != register.name),
+                "{} twice",
+                register.name
+            );
+            let mut bits = 0u16;
+            for field in register.fields {
+                let mask = ((1u16 << field.width) - 1) << field.shift;
+                assert!(mask <= 0xff, "{} is too wide", field.name);
+                assert_eq!(bits & mask, 0, "{} overlaps", field.name);
+                assert!(field.values.len() <= 1 << field.width, "{}", field.name);
+                bits |= mask;
+            }
+        }
+        let addresses: Vec<_> = REGISTERS.iter().map(|register| register.address).collect();
+        assert!(addresses.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+}
+
+mod prbs {
+    use crate::prbs::{Checker, Prbs};
+
+    fn bytes(prbs: &mut Prbs, count: usize) -> Vec<u8> {
+        (0..count).map(|_| prbs.next_byte()).collect()
+    }
+
+    #[test]
+    fn the_sequence_repeats_after_all_15_bit_patterns() {
+        // 32767 bits is 4095 bytes and 7 bits, so the bytes only repeat
+        // after 8 times around
+        let mut prbs = Prbs::new(1);
+        let start = bytes(&mut prbs, 4);
+        let mut again = Prbs::new(1);
+        let _ = bytes(&mut again, 32_767);
+        assert_eq!(bytes(&mut again, 4), start);
+        assert!(start.iter().any(|&byte| byte != 0));
+    }
+
+    #[test]
+    fn a_seed_of_zero_still_makes_a_sequence() {
+        let mut prbs = Prbs::new(0);
+        assert!(bytes(&mut prbs, 8).iter().any(|&byte| byte != 0));
+    }
+
+    #[test]
+    fn the_checker_follows_any_seed() {
+        let mut prbs = Prbs::new(0x1234);
+        let mut checker = Checker::new();
+        let mut buffer = [0; 1000];
+        prbs.fill(&mut buffer);
+        assert!(buffer.iter().all(|&byte| checker.check(byte)));
+        assert_eq!(checker.received(), 1000);
+        assert_eq!(checker.errors(), 0);
+    }
+
+    #[test]
+    fn a_lost_byte_spoils_only_a_few() {
+        let mut prbs = Prbs::default();
+        let mut checker = Checker::default();
+        for (i, byte) in bytes(&mut prbs, 100).into_iter().enumerate() {
+            if i != 50 {
+                checker.check(byte);
+            }
+        }
+        assert_eq!(checker.received(), 99);
+        assert!((1..=3).contains(&checker.errors()), "{}", checker.errors());
+    }
+
+    #[test]
+    fn a_garbled_byte_spoils_only_a_few() {
+        let mut prbs = Prbs::default();
+        let mut checker = Checker::new();
+        for (i, byte) in bytes(&mut prbs, 100).into_iter().enumerate() {
+            checker.check(if i == 50 { byte ^ 0x10 } else { byte });
+        }
+        assert!((1..=3).contains(&checker.errors()), "{}", checker.errors());
+    }
+}
+
+mod dfu {
+    use crate::dfu::{crc32, Crc32, Failure, ImageFlash, Request, Response, Updater, CHUNK_LEN};
+
+    const PAGE_SIZE: usize = 1024;
+
+    /// Flash that checks it's only ever written where it's erased.
+    struct FakeFlash {
+        words: Vec<u32>,
+        erased_pages: usize,
+    }
+
+    impl FakeFlash {
+        fn new(pages: usize) -> FakeFlash {
+            // Whatever the last program left behind
+            FakeFlash {
+                words: vec![0x1234_5678; pages * PAGE_SIZE / 4],
+                erased_pages: 0,
+            }
+        }
+
+        fn bytes(&self, len: usize) -> Vec<u8> {
+            let bytes: Vec<u8> = self.words.iter().flat_map(|w| w.to_le_bytes()).collect();
+            bytes[..len].to_vec()
+        }
+    }
+
+    impl ImageFlash for FakeFlash {
+        fn erase_page(&mut self, offset: usize) {
+            assert_eq!(offset % PAGE_SIZE, 0);
+            let page = offset / 4..(offset + PAGE_SIZE) / 4;
+            self.words[page].iter_mut().for_each(|w| *w = 0xffff_ffff);
+            self.erased_pages += 1;
+        }
+
+        fn write(&mut self, offset: usize, words: &[u32]) {
+            for (i, &word) in words.iter().enumerate() {
+                let slot = &mut self.words[offset / 4 + i];
+                assert_eq!(*slot, 0xffff_ffff, "written twice at {}", offset + i * 4);
+                *slot = word;
+            }
+        }
+
+        fn read(&self, offset: usize) -> u32 {
+            self.words[offset / 4]
+        }
+    }
+
+    fn program(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i * 7 + i / 251) as u8).collect()
+    }
+
+    /// Send all of `image` but the finish, and return the last response.
+    fn send(updater: &mut Updater, flash: &mut FakeFlash, image: &[u8]) -> Response {
+        let start = Request::Start {
+            len: image.len() as u32,
+            crc: crc32(image),
+        };
+        let mut response = updater.handle(&start, flash);
+        while let Response::Next(offset) = response {
+            if offset as usize == image.len() {
+                break;
+            }
+            let end = (offset as usize + CHUNK_LEN).min(image.len());
+            let chunk = Request::Chunk {
+                offset,
+                data: &image[offset as usize..end],
+            };
+            response = updater.handle(&chunk, flash);
+        }
+        response
+    }
+
+    #[test]
+    fn the_crc_is_the_usual_crc_32() {
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        let mut crc = Crc32::new();
+        crc.update(b"1234");
+        crc.update(b"56789");
+        assert_eq!(crc.finish(), 0xcbf4_3926);
+    }
+
+    #[test]
+    fn requests_and_responses_come_back_the_same() {
+        let data = program(CHUNK_LEN);
+        let requests = [
+            Request::Start {
+                len: 5000,
+                crc: 0xdead_beef,
+            },
+            Request::Chunk {
+                offset: 256,
+                data: &data,
+            },
+            Request::Finish,
+        ];
+        for request in &requests {
+            assert_eq!(Request::decode(&request.encode()), Some(*request));
+        }
+        let responses = [
+            Response::Next(128),
+            Response::Done,
+            Response::Failed(Failure::BadCrc),
+        ];
+        for response in &responses {
+            assert_eq!(Response::decode(&response.encode()), Some(*response));
+        }
+    }
+
+    #[test]
+    fn nonsense_is_not_a_request() {
+        assert_eq!(Request::decode(b""), None);
+        assert_eq!(Request::decode(b"S1234"), None);
+        assert_eq!(Request::decode(b"F!"), None);
+        assert_eq!(Request::decode(&[b'C'; 4 + CHUNK_LEN + 2]), None);
+        assert_eq!(Response::decode(&[b'X', 99]), None);
+    }
+
+    #[test]
+    fn a_whole_program_ends_up_in_the_flash() {
+        let image = program(2999);
+        let mut flash = FakeFlash::new(4);
+        let mut updater = Updater::new(4 * PAGE_SIZE as u32, PAGE_SIZE as u32);
+        assert_eq!(send(&mut updater, &mut flash, &image), Response::Next(2999));
+        assert_eq!(updater.handle(&Request::Finish, &mut flash), Response::Done);
+        assert_eq!(flash.bytes(2999), image);
+        // The last word is padded with what erased flash reads as
+        assert_eq!(flash.bytes(3000)[2999], 0xff);
+        // Only the pages the program takes are erased
+        assert_eq!(flash.erased_pages, 3);
+        assert_eq!(flash.words[3 * PAGE_SIZE / 4], 0x1234_5678);
+    }
+
+    #[test]
+    fn the_start_is_only_written_once_the_crc_checks_out() {
+        let image = program(500);
+        let mut flash = FakeFlash::new(1);
+        let mut updater = Updater::new(PAGE_SIZE as u32, PAGE_SIZE as u32);
+        send(&mut updater, &mut flash, &image);
+        assert_eq!(flash.words[..2], [0xffff_ffff; 2]);
+        assert_eq!(flash.bytes(500)[8..], image[8..]);
+    }
+
+    #[test]
+    fn a_garbled_program_is_not_started() {
+        let image = program(500);
+        let mut flash = FakeFlash::new(1);
+        let mut updater = Updater::new(PAGE_SIZE as u32, PAGE_SIZE as u32);
+        let start = Request::Start {
+            len: 500,
+            crc: crc32(&image) ^ 1,
+        };
+        updater.handle(&start, &mut flash);
+        for offset in (0..500).step_by(CHUNK_LEN) {
+            let chunk = Request::Chunk {
+                offset: offset as u32,
+                data: &image[offset..(offset + CHUNK_LEN).min(500)],
+            };
+            updater.handle(&chunk, &mut flash);
+        }
+        assert_eq!(
+            updater.handle(&Request::Finish, &mut flash),
+            Response::Failed(Failure::BadCrc)
+        );
+        assert_eq!(flash.words[0], 0xffff_ffff);
+        // And it's over, the next finish needs a start first
+        assert_eq!(
+            updater.handle(&Request::Finish, &mut flash),
+            Response::Failed(Failure::NotStarted)
+        );
+    }
+
+    #[test]
+    fn a_chunk_sent_again_is_answered_again() {
+        let image = program(400);
+        let mut flash = FakeFlash::new(1);
+        let mut updater = Updater::new(PAGE_SIZE as u32, PAGE_SIZE as u32);
+        let start = Request::Start {
+            len: 400,
+            crc: crc32(&image),
+        };
+        updater.handle(&start, &mut flash);
+        let first = Request::Chunk {
+            offset: 0,
+            data: &image[..CHUNK_LEN],
+        };
+        assert_eq!(updater.handle(&first, &mut flash), Response::Next(128));
+        assert_eq!(updater.handle(&first, &mut flash), Response::Next(128));
+        // One that skips ahead gets the same answer
+        let third = Request::Chunk {
+            offset: 256,
+            data: &image[256..384],
+        };
+        assert_eq!(updater.handle(&third, &mut flash), Response::Next(128));
+        assert_eq!(
+            updater.handle(&Request::Finish, &mut flash),
+            Response::Failed(Failure::Incomplete)
+        );
+    }
+
+    #[test]
+    fn what_does_not_fit_is_refused() {
+        let mut flash = FakeFlash::new(1);
+        let mut updater = Updater::new(PAGE_SIZE as u32, PAGE_SIZE as u32);
+        for len in [0, 4, PAGE_SIZE as u32 + 1] {
+            assert_eq!(
+                updater.handle(&Request::Start { len, crc: 0 }, &mut flash),
+                Response::Failed(Failure::BadLength)
+            );
+        }
+        updater.handle(&Request::Start { len: 200, crc: 0 }, &mut flash);
+        let short = Request::Chunk {
+            offset: 0,
+            data: &[0; 100],
+        };
+        assert_eq!(
+            updater.handle(&short, &mut flash),
+            Response::Failed(Failure::BadChunk)
+        );
+        assert_eq!(flash.erased_pages, 0);
+    }
+}
+
+mod battery {
+    use crate::battery::{bars, LowBattery, BARS, EMPTY_MV, FULL_MV};
+
+    #[test]
+    fn the_bars_go_from_empty_to_full() {
+        assert_eq!(bars(1_800), 0);
+        assert_eq!(bars(EMPTY_MV), 0);
+        assert_eq!(bars(EMPTY_MV + 1), 1);
+        assert_eq!(bars(2_600), BARS / 2);
+        assert_eq!(bars(FULL_MV), BARS);
+        // USB
+        assert_eq!(bars(3_300), BARS);
+    }
+
+    #[test]
+    fn the_bars_never_go_up_as_the_voltage_goes_down() {
+        let levels: Vec<u32> = (1_500..3_500).rev().map(bars).collect();
+        assert!(levels.windows(2).all(|pair| pair[1] <= pair[0]));
+    }
+
+    #[test]
+    fn the_warning_stays_on_while_the_voltage_wobbles() {
+        let mut battery = LowBattery::new(2_400, 200);
+        assert!(!battery.update(2_500));
+        assert!(battery.update(2_399));
+        assert!(battery.update(2_450));
+        assert!(battery.update(2_599));
+        assert!(battery.is_low());
+        // New batteries
+        assert!(!battery.update(3_100));
+    }
+
+    #[test]
+    fn the_default_warns_before_empty() {
+        let mut battery = LowBattery::default();
+        assert!(!battery.update(FULL_MV));
+        assert!(battery.update(EMPTY_MV + 100));
+    }
+}
+
+mod knob {
+    use crate::knob::Knob;
+
+    #[test]
+    fn one_step_per_detent() {
+        let mut knob = Knob::new(0, 9, 4);
+        assert!(!knob.turn(3));
+        assert!(knob.turn(1));
+        assert_eq!(knob.value(), 1);
+        assert!(knob.turn(9));
+        assert_eq!(knob.value(), 3);
+        // The one count left over counts towards the way back, too
+        assert!(!knob.turn(-4));
+        assert_eq!(knob.value(), 3);
+        assert!(knob.turn(-1));
+        assert_eq!(knob.value(), 2);
+    }
+
+    #[test]
+    fn it_stops_at_the_ends() {
+        let mut knob = Knob::new(0, 9, 4);
+        assert!(!knob.turn(-100));
+        assert_eq!(knob.value(), 0);
+        assert!(knob.turn(100));
+        assert_eq!(knob.value(), 9);
+        // However far it went past the end, one click back is one step
+        assert!(knob.turn(-4));
+        assert_eq!(knob.value(), 8);
+    }
+
+    #[test]
+    fn setting_the_value_forgets_partial_steps() {
+        let mut knob = Knob::new(0, 10, 4);
+        knob.turn(3);
+        knob.set_value(20);
+        assert_eq!(knob.value(), 10);
+        assert!(knob.turn(-4));
+        assert_eq!(knob.value(), 9);
+    }
+}
+
+mod temperature_log {
+    use crate::clock::TimeOfDay;
+    use crate::temperature_log::{line, HEADER, MAX_LINE_LEN};
+
+    #[test]
+    fn a_line_has_the_columns_of_the_header() {
+        let time = TimeOfDay::new(12, 3, 4).unwrap();
+        assert_eq!(line(1_500, time, 2_325), "1500,12:03:04,23.25\r\n");
+        assert_eq!(
+            line(0, time, 0).split(',').count(),
+            HEADER.split(',').count()
+        );
+    }
+
+    #[test]
+    fn temperatures_below_zero_keep_their_sign() {
+        let time = TimeOfDay::default();
+        assert_eq!(line(0, time, -25), "0,00:00:00,-0.25\r\n");
+        assert_eq!(line(0, time, -1_205), "0,00:00:00,-12.05\r\n");
+    }
+
+    #[test]
+    fn the_longest_line_fits() {
+        let line = line(u64::MAX, TimeOfDay::default(), i32::MIN);
+        assert!(line.ends_with("\r\n"));
+        assert!(line.len() <= MAX_LINE_LEN);
+    }
+}
+
+mod drive {
+    use crate::drive::{from_tilt, Ramp, DEAD_ZONE_MG, FULL_TILT_MG, MAX_SPEED};
+
+    #[test]
+    fn flat_stands_still() {
+        assert_eq!(from_tilt(0, 0), (0, 0));
+        assert_eq!(from_tilt(DEAD_ZONE_MG, -DEAD_ZONE_MG), (0, 0));
+    }
+
+    #[test]
+    fn leaning_forward_and_back_drives_straight() {
+        assert_eq!(from_tilt(0, FULL_TILT_MG), (MAX_SPEED, MAX_SPEED));
+        assert_eq!(from_tilt(0, 1_000), (MAX_SPEED, MAX_SPEED));
+        assert_eq!(from_tilt(0, -FULL_TILT_MG), (-MAX_SPEED, -MAX_SPEED));
+        let (left, right) = from_tilt(0, (DEAD_ZONE_MG + FULL_TILT_MG) / 2);
+        assert_eq!(left, right);
+        assert_eq!(left, MAX_SPEED / 2);
+    }
+
+    #[test]
+    fn leaning_sideways_turns_on_the_spot() {
+        assert_eq!(from_tilt(FULL_TILT_MG, 0), (MAX_SPEED, -MAX_SPEED));
+        assert_eq!(from_tilt(-FULL_TILT_MG, 0), (-MAX_SPEED, MAX_SPEED));
+    }
+
+    #[test]
+    fn turning_at_full_speed_slows_both_sides_down() {
+        let (left, right) = from_tilt((DEAD_ZONE_MG + FULL_TILT_MG) / 2, FULL_TILT_MG);
+        assert_eq!(left, MAX_SPEED);
+        assert!(right > 0 && right < left);
+        // Steering half as hard as it goes takes a third off the inner side
+        assert_eq!(right, MAX_SPEED / 3);
+    }
+
+    #[test]
+    fn the_ramp_speeds_up_a_step_at_a_time() {
+        let mut ramp = Ramp::new(100);
+        let speeds: Vec<i16> = (0..12).map(|_| ramp.update(MAX_SPEED)).collect();
+        assert_eq!(speeds[0], 100);
+        assert_eq!(speeds[9], MAX_SPEED);
+        assert_eq!(speeds[11], MAX_SPEED);
+        assert_eq!(Ramp::new(100).update(30), 30);
+    }
+
+    #[test]
+    fn reversing_goes_through_standing_still() {
+        let mut ramp = Ramp::new(400);
+        for _ in 0..3 {
+            ramp.update(MAX_SPEED);
+        }
+        assert_eq!(ramp.update(-MAX_SPEED), 600);
+        assert_eq!(ramp.update(-MAX_SPEED), 200);
+        assert_eq!(ramp.update(-MAX_SPEED), -200);
+        ramp.reset();
+        assert_eq!(ramp.speed(), 0);
+    }
+}
+
+mod fault_status {
+    use crate::fault_status::{
+        bfar_valid, cfsr_flags, hfsr_flags, mmfar_valid, CFSR_FLAGS, HFSR_FLAGS,
+    };
+
+    fn names(flags: impl Iterator<Item = crate::fault_status::Flag>) -> Vec<&'static str> {
+        flags.map(|flag| flag.name).collect()
+    }
+
+    #[test]
+    fn a_read_from_nowhere() {
+        // What reading past the end of RAM leaves behind
+        let cfsr = 0x0000_8200;
+        assert_eq!(names(cfsr_flags(cfsr)), ["PRECISERR", "BFARVALID"]);
+        assert!(bfar_valid(cfsr));
+        assert!(!mmfar_valid(cfsr));
+        assert_eq!(names(hfsr_flags(0x4000_0000)), ["FORCED"]);
+    }
+
+    #[test]
+    fn usage_faults() {
+        assert_eq!(
+            names(cfsr_flags(1 << 16 | 1 << 25)),
+            ["UNDEFINSTR", "DIVBYZERO"]
+        );
+        assert!(!bfar_valid(1 << 16));
+    }
+
+    #[test]
+    fn nothing_set_means_no_flags() {
+        assert_eq!(cfsr_flags(0).count(), 0);
+        assert_eq!(hfsr_flags(0).count(), 0);
+        // Bits that are reserved don't turn into flags either
+        assert_eq!(cfsr_flags(1 << 2 | 1 << 14 | 1 << 31).count(), 0);
+    }
+
+    #[test]
+    fn every_flag_has_a_bit_of_its_own() {
+        for flags in [&CFSR_FLAGS[..], &HFSR_FLAGS[..]] {
+            for (i, flag) in flags.iter().enumerate() {
+                assert!(flag.bit < 32);
+                assert!(flags[i + 1..].iter().all(|other| other.bit != flag.bit));
+            }
+        }
+    }
+}
@@ -0,0 +1,117 @@
+//! The memory of an NFC Forum Type 2 tag, and the commands a reader uses on
+//! it.
+//!
+//! Type 2 is the simplest kind of NFC tag, the one in stickers and in the
+//! cards of many public transport systems. Its memory is divided into
+//! *pages* of 4 bytes:
+//!
+//! | Page   | Holds                                                     |
+//! |--------|-----------------------------------------------------------|
+//! | 0 - 1  | the 7 byte UID, with a check byte after the first 3 bytes |
+//! | 2      | the check byte of the last 4 bytes of the UID, and locks  |
+//! | 3      | the capability container, which says what the tag can do |
+//! | 4 -    | data: the NDEF message, wrapped in a TLV                  |
+//!
+//! The capability container starts with `0xE1`, which marks the tag as one
+//! that holds NDEF, then comes the version of the spec, the size of the data
+//! area in units of 8 bytes, and whether it can be written. Ours can't.
+//!
+//! The data area is a list of TLVs, a type, a length and a value. The NDEF
+//! message goes in one of type `0x03`, and `0xFE` marks the end.
+//!
+//! A reader reads 16 bytes, four pages, at a time, and starts over from page
+//! 0 if it reads past the end.
+
+use crate::ndef::MAX_MESSAGE_LEN;
+
+/// How many bytes a page has.
+pub const PAGE_LEN: usize = 4;
+/// How many bytes a read gets.
+pub const READ_LEN: usize = 4 * PAGE_LEN;
+/// Room for the longest NDEF message, its TLV and the terminator, in whole
+/// units of 8 bytes.
+pub const DATA_LEN: usize = (MAX_MESSAGE_LEN + 3).div_ceil(8) * 8;
+pub const MEMORY_LEN: usize = 4 * PAGE_LEN + DATA_LEN;
+pub const PAGES: usize = MEMORY_LEN / PAGE_LEN;
+
+pub mod commands {
+    /// `READ page`, answered with 16 bytes.
+    pub const READ: u8 = 0x30;
+    /// `WRITE page b0 b1 b2 b3`, which a tag that can be written answers with
+    /// an ACK, the 4 bits `0xA`.
+    pub const WRITE: u8 = 0xA2;
+    /// `HALT 0x00`, which isn't answered at all.
+    pub const HALT: u8 = 0x50;
+}
+
+/// What the UID starts over with after 3 bytes, the *cascade tag*. A 7 byte
+/// UID doesn't fit into a single round of anti-collision.
+const CASCADE_TAG: u8 = 0x88;
+/// NDEF, version 1.0, and no writing.
+const CAPABILITIES: [u8; 4] = [0xE1, 0x10, (DATA_LEN / 8) as u8, 0x0F];
+const NDEF_TLV: u8 = 0x03;
+const TERMINATOR_TLV: u8 = 0xFE;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The message is longer than [`MAX_MESSAGE_LEN`].
+    TooLong,
+}
+
+/// What to answer a command with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Response {
+    /// Send these bytes, with a CRC.
+    Data([u8; READ_LEN]),
+    /// Send 4 bits, `0x0`, to refuse the command.
+    Nak,
+    /// Send nothing, and don't answer again until the reader wakes us up.
+    Sleep,
+}
+
+pub struct Type2Tag {
+    memory: [u8; MEMORY_LEN],
+}
+
+impl Type2Tag {
+    /// A read-only tag with `uid` that holds the NDEF `message`.
+    pub fn new(uid: [u8; 7], message: &[u8]) -> Result<Type2Tag, Error> {
+        if message.len() > MAX_MESSAGE_LEN {
+            return Err(Error::TooLong);
+        }
+
+        let mut memory = [0; MEMORY_LEN];
+        memory[..3].copy_from_slice(&uid[..3]);
+        memory[3] = CASCADE_TAG ^ uid[0] ^ uid[1] ^ uid[2];
+        memory[4..8].copy_from_slice(&uid[3..]);
+        memory[8] = uid[3] ^ uid[4] ^ uid[5] ^ uid[6];
+        // What NXP's tags have there, and both lock bytes set: pages 3 to 15
+        // can't be written
+        memory[9..12].copy_from_slice(&[0x48, 0xFF, 0xFF]);
+        memory[12..16].copy_from_slice(&CAPABILITIES);
+
+        let end = 18 + message.len();
+        memory[16] = NDEF_TLV;
+        memory[17] = message.len() as u8;
+        memory[18..end].copy_from_slice(message);
+        memory[end] = TERMINATOR_TLV;
+        Ok(Type2Tag { memory })
+    }
+
+    /// How to answer `command`, which came without its CRC.
+    pub fn respond(&self, command: &[u8]) -> Response {
+        match *command {
+            [commands::READ, page] if usize::from(page) < PAGES => {
+                let mut data = [0; READ_LEN];
+                let start = usize::from(page) * PAGE_LEN;
+                for (offset, byte) in data.iter_mut().enumerate() {
+                    *byte = self.memory[(start + offset) % MEMORY_LEN];
+                }
+                Response::Data(data)
+            }
+            [commands::HALT, 0x00] => Response::Sleep,
+            // Writes too, the tag is read-only
+            _ => Response::Nak,
+        }
+    }
+}
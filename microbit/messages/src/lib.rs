@@ -0,0 +1,80 @@
+//! Messages exchanged between the micro:bit and the host.
+//!
+//! Both sides depend on this crate, so they always agree on what the bytes on
+//! the wire mean. Sending and receiving them over a serial port is handled by
+//! `discovery_support::wire` on the micro:bit.
+//!
+//! The bytes are whatever `postcard` makes of these types. The tests pin them
+//! down, so that a change to a type that would leave an older host tool unable
+//! to read the firmware, or the other way round, doesn't go unnoticed.
+
+#![no_std]
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(test)]
+mod tests;
+
+/// An accelerometer reading, in milli-g.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccelSample {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+
+/// A magnetometer reading, in nanotesla.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MagSample {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+
+/// Which way north is, after compensating for the tilt of the board.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CompassHeading {
+    /// The angle from the board's x axis to north, counterclockwise, from
+    /// -180 to 180 degrees.
+    pub degrees: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Button {
+    A,
+    B,
+}
+
+/// A button was pressed or let go of.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ButtonEvent {
+    pub button: Button,
+    pub pressed: bool,
+}
+
+// `postcard` numbers the variants in the order they are declared here, new
+// ones go at the end
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Message {
+    Acceleration(AccelSample),
+    MagneticField(MagSample),
+    /// The temperature of the chip, in degrees Celsius.
+    Temperature(i16),
+    /// Sent by the host to check that the micro:bit is there, answered with
+    /// `Pong`.
+    Ping,
+    Pong,
+    /// A compass calibration, sent by the host once it has fitted one to the
+    /// `MagneticField` readings: the hard iron offset, how much to scale every
+    /// axis by, in 1024ths, and the radius of the sphere that leaves the
+    /// readings on. The micro:bit saves it and answers with
+    /// `CalibrationSaved`.
+    Calibration {
+        center: [i32; 3],
+        scale: [i32; 3],
+        radius: u32,
+    },
+    CalibrationSaved,
+    Heading(CompassHeading),
+    Button(ButtonEvent),
+}
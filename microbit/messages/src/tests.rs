@@ -0,0 +1,87 @@
+use crate::{AccelSample, Button, ButtonEvent, CompassHeading, MagSample, Message};
+
+/// Check that `message` turns into the bytes `discovery_support::wire` puts
+/// into a packet for it.
+fn assert_bytes(message: &Message, expected: &[u8]) {
+    let mut buf = [0; 32];
+    let bytes = postcard::to_slice(message, &mut buf).unwrap();
+    assert_eq!(bytes, expected, "{:?}", message);
+}
+
+fn decode(bytes: &[u8]) -> Message {
+    postcard::from_bytes(bytes).unwrap()
+}
+
+// What the firmware sends, byte for byte. The variant comes first, then the
+// fields, signed ones zigzag encoded: 1 is 2, -1 is 1
+#[test]
+fn acceleration() {
+    let message = Message::Acceleration(AccelSample {
+        x: 1,
+        y: -1,
+        z: 1000,
+    });
+    assert_bytes(&message, &[0, 2, 1, 0xd0, 0x0f]);
+    assert_eq!(decode(&[0, 2, 1, 0xd0, 0x0f]), message);
+}
+
+#[test]
+fn magnetic_field() {
+    let message = Message::MagneticField(MagSample {
+        x: -64,
+        y: 0,
+        z: 63,
+    });
+    assert_bytes(&message, &[1, 0x7f, 0, 0x7e]);
+}
+
+#[test]
+fn temperature() {
+    assert_bytes(&Message::Temperature(-3), &[2, 5]);
+}
+
+#[test]
+fn messages_without_data_are_a_single_byte() {
+    assert_bytes(&Message::Ping, &[3]);
+    assert_bytes(&Message::Pong, &[4]);
+    assert_bytes(&Message::CalibrationSaved, &[6]);
+}
+
+#[test]
+fn calibration() {
+    let message = Message::Calibration {
+        center: [1, 2, 3],
+        scale: [1024; 3],
+        radius: 500,
+    };
+    // `radius` is unsigned, so it isn't zigzag encoded
+    let bytes = [5, 2, 4, 6, 0x80, 0x10, 0x80, 0x10, 0x80, 0x10, 0xf4, 0x03];
+    assert_bytes(&message, &bytes);
+    assert_eq!(decode(&bytes), message);
+}
+
+#[test]
+fn heading_is_a_little_endian_float() {
+    let message = Message::Heading(CompassHeading { degrees: 90. });
+    assert_bytes(&message, &[7, 0, 0, 0xb4, 0x42]);
+}
+
+#[test]
+fn button_event() {
+    let message = Message::Button(ButtonEvent {
+        button: Button::B,
+        pressed: true,
+    });
+    assert_bytes(&message, &[8, 1, 1]);
+    assert_eq!(decode(&[8, 1, 1]), message);
+}
+
+#[test]
+fn unknown_variants_are_rejected() {
+    assert!(postcard::from_bytes::<Message>(&[9]).is_err());
+}
+
+#[test]
+fn truncated_messages_are_rejected() {
+    assert!(postcard::from_bytes::<Message>(&[0, 2, 1]).is_err());
+}
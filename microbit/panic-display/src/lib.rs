@@ -0,0 +1,90 @@
+//! A panic handler you can see without a debugger.
+//!
+//! Like `panic-rtt-target` the panic message is printed over RTT, so with a
+//! probe attached nothing changes. Without one nobody empties the RTT buffer,
+//! so the message is cut off where the buffer is full, rather than wait for
+//! room that never comes. Once that is done the LED matrix takes over: a sad
+//! face, followed by the line the program panicked on blinked out one digit
+//! at a time in the middle LED (ten blinks stand for a 0). That way a
+//! micro:bit running from a battery can still tell you where it went wrong.
+//!
+//! To use it, replace `use panic_rtt_target as _;` with
+//! `use panic_display as _;` and keep calling `rtt_init_print!()` as before.
+
+#![no_std]
+
+use core::{fmt::Write, panic::PanicInfo};
+use microbit::{
+    board::Board,
+    display::blocking::Display,
+    hal::{prelude::*, Timer},
+    pac,
+};
+use rtt_target::{ChannelMode, UpChannel};
+
+const SAD_FACE: [[u8; 5]; 5] = [
+    [0, 0, 0, 0, 0],
+    [0, 1, 0, 1, 0],
+    [0, 0, 0, 0, 0],
+    [0, 1, 1, 1, 0],
+    [1, 0, 0, 0, 1],
+];
+
+const DOT: [[u8; 5]; 5] = [
+    [0, 0, 0, 0, 0],
+    [0, 0, 0, 0, 0],
+    [0, 0, 1, 0, 0],
+    [0, 0, 0, 0, 0],
+    [0, 0, 0, 0, 0],
+];
+
+#[inline(never)]
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    cortex_m::interrupt::disable();
+
+    if let Some(mut channel) = unsafe { UpChannel::conjure(0) } {
+        // Waiting for room in the buffer would keep the display dark forever
+        channel.set_mode(ChannelMode::NoBlockTrim);
+
+        writeln!(channel, "{}", info).ok();
+    }
+
+    // Whoever owned the peripherals before isn't going to run again, so it is
+    // safe to take them back and start from scratch.
+    let board = Board::new(unsafe { pac::Peripherals::steal() }, unsafe {
+        pac::CorePeripherals::steal()
+    });
+    let mut timer = Timer::new(board.TIMER0);
+    let mut display = Display::new(board.display_pins);
+
+    let line = info.location().map_or(0, |location| location.line());
+
+    loop {
+        display.show(&mut timer, SAD_FACE, 2_000);
+        display.clear();
+        timer.delay_ms(500u32);
+
+        blink_number(&mut display, &mut timer, line);
+    }
+}
+
+/// Blink `number` in decimal, most significant digit first.
+fn blink_number(display: &mut Display, timer: &mut Timer<pac::TIMER0>, number: u32) {
+    let mut divisor = 1;
+    while number / divisor >= 10 {
+        divisor *= 10;
+    }
+
+    while divisor > 0 {
+        let digit = number / divisor % 10;
+        let blinks = if digit == 0 { 10 } else { digit };
+        for _ in 0..blinks {
+            display.show(timer, DOT, 250);
+            display.clear();
+            timer.delay_ms(250u32);
+        }
+        timer.delay_ms(1_000u32);
+        divisor /= 10;
+    }
+}
@@ -0,0 +1,54 @@
+//! The Morse code example from chapter 7: every line typed gets sent in Morse
+//! code. Instead of beeping, the dots and dashes show up in the terminal, at
+//! the right speed.
+
+#![no_main]
+#![no_std]
+
+use cortex_m_rt::entry;
+use cortex_m_semihosting::{hprint, hprintln};
+use discovery_logic::morse::{encode, unit_ms};
+use panic_semihosting as _;
+use qemu::serial::{prompt, Stdin};
+use qemu::{exit, Delay};
+
+const WPM: u32 = 15;
+
+#[entry]
+fn main() -> ! {
+    let core = cortex_m::Peripherals::take().unwrap();
+    let mut delay = Delay::new(core.SYST);
+    let mut stdin = Stdin::open();
+    let unit = unit_ms(WPM);
+
+    hprintln!("Type a line to send it in Morse code, Ctrl-D to stop");
+    prompt();
+    while let Some(line) = stdin.read_line::<64>() {
+        for c in line.chars() {
+            if c == ' ' {
+                // Seven units between words, three of them already came after
+                // the last letter
+                hprint!("  ");
+                delay.delay_ms(4 * unit);
+                continue;
+            }
+            let code = match encode(c) {
+                Some(code) => code,
+                None => {
+                    hprint!("[{}?]", c);
+                    continue;
+                }
+            };
+            for symbol in code.chars() {
+                hprint!("{}", symbol);
+                let units = if symbol == '.' { 1 } else { 3 };
+                delay.delay_ms((units + 1) * unit);
+            }
+            hprint!(" ");
+            delay.delay_ms(2 * unit);
+        }
+        hprintln!();
+        prompt();
+    }
+    exit()
+}
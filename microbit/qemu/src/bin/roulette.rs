@@ -0,0 +1,30 @@
+//! The LED roulette from chapter 5, going round the display in the terminal.
+
+#![no_main]
+#![no_std]
+
+use cortex_m_rt::entry;
+use discovery_logic::roulette::{next_pixel, PIXELS};
+use panic_semihosting as _;
+use qemu::display::{display_leds, init_display};
+use qemu::{exit, Delay};
+
+#[entry]
+fn main() -> ! {
+    let core = cortex_m::Peripherals::take().unwrap();
+    let mut delay = Delay::new(core.SYST);
+    init_display();
+
+    let mut leds = [[0; 5]; 5];
+    let mut current = 0;
+    // Three times round, and then QEMU stops
+    for _ in 0..3 * PIXELS.len() {
+        let (row, col) = PIXELS[current];
+        leds[row][col] = 1;
+        display_leds(leds);
+        delay.delay_ms(100);
+        leds[row][col] = 0;
+        current = next_pixel(current, true);
+    }
+    exit()
+}
@@ -0,0 +1,21 @@
+//! The LED display, drawn in the terminal.
+
+use cortex_m_semihosting::hprint;
+
+/// Shows `leds` like `display_leds` does on the micro:bit, as five lines of
+/// text, one character per LED. Every call draws over the last one.
+pub fn display_leds(leds: [[u8; 5]; 5]) {
+    // Move the cursor back up to where the last image started
+    hprint!("\x1b[5A");
+    for row in leds.iter() {
+        for &led in row.iter() {
+            hprint!("{}", if led > 0 { " #" } else { " ." });
+        }
+        hprint!("\n");
+    }
+}
+
+/// Make room for the display, once, before the first image.
+pub fn init_display() {
+    hprint!("\n\n\n\n\n");
+}
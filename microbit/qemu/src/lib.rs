@@ -0,0 +1,52 @@
+//! Stand-ins for the micro:bit's peripherals, for running the chapters'
+//! logic under QEMU.
+//!
+//! QEMU can't pretend to be a micro:bit v2, but it can pretend to be another
+//! board with a Cortex-M. The programs in `src/bin` run on that, with the
+//! algorithms of the chapters from `discovery-logic`, and these stand-ins
+//! for the hardware they would use on the micro:bit. Everything goes through
+//! *semihosting*, which QEMU passes on to the terminal it was started from.
+
+#![no_std]
+
+pub mod display;
+pub mod serial;
+
+use cortex_m::peripheral::{syst::SystClkSource, SYST};
+use cortex_m_semihosting::debug;
+
+/// The clock QEMU's LM3S6965 runs at, in Hz.
+const CLOCK_HZ: u32 = 12_000_000;
+
+/// Waits with the `SysTick` timer, the one timer every Cortex-M has, in place
+/// of the micro:bit's `Timer`.
+pub struct Delay {
+    syst: SYST,
+}
+
+impl Delay {
+    pub fn new(mut syst: SYST) -> Delay {
+        syst.set_clock_source(SystClkSource::Core);
+        Delay { syst }
+    }
+
+    pub fn delay_ms(&mut self, ms: u32) {
+        // The counter is 24 bits wide, which lasts a little over a second
+        for _ in 0..ms {
+            self.syst.set_reload(CLOCK_HZ / 1_000 - 1);
+            self.syst.clear_current();
+            self.syst.enable_counter();
+            while !self.syst.has_wrapped() {}
+            self.syst.disable_counter();
+        }
+    }
+}
+
+/// Stops QEMU, which has no reset button to press.
+pub fn exit() -> ! {
+    debug::exit(debug::EXIT_SUCCESS);
+    // Only a debugger that doesn't know how to exit gets here
+    loop {
+        cortex_m::asm::wfi();
+    }
+}
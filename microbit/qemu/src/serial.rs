@@ -0,0 +1,48 @@
+//! The serial port, read from the terminal's standard input.
+//!
+//! Unlike a real serial port, the terminal only hands over what was typed
+//! once Enter is pressed, and echoes it itself.
+
+use cortex_m_semihosting::{hprint, nr, syscall};
+use heapless::String;
+
+/// The terminal's standard input, opened the semihosting way.
+pub struct Stdin {
+    handle: usize,
+}
+
+impl Stdin {
+    pub fn open() -> Stdin {
+        // `:tt` is how semihosting names the terminal
+        let handle = unsafe { syscall!(OPEN, ":tt\0".as_ptr(), nr::open::R, 3) };
+        Stdin { handle }
+    }
+
+    /// Read one line, without its line ending. Returns `None` once there's
+    /// nothing more to read.
+    pub fn read_line<const N: usize>(&mut self) -> Option<String<N>> {
+        let mut line = String::new();
+        loop {
+            let mut byte = 0;
+            // The number of bytes it didn't read, so anything but 0 means that
+            // the input has ended
+            let left = unsafe { syscall!(READ, self.handle, &mut byte as *mut u8, 1) };
+            if left != 0 {
+                return if line.is_empty() { None } else { Some(line) };
+            }
+            match byte {
+                b'\n' => return Some(line),
+                b'\r' => {}
+                // Like the micro:bit's line reader, keep what fits
+                _ => {
+                    line.push(byte as char).ok();
+                }
+            }
+        }
+    }
+}
+
+/// Ask for the next line with a prompt.
+pub fn prompt() {
+    hprint!("> ");
+}
@@ -2,7 +2,7 @@
 #![no_main]
 
 use panic_rtt_target as _;
-use rtt_target::{rtt_init_print, rprintln};
+use rtt_target::{rprintln, rtt_init_print};
 
 use cortex_m_rt::entry;
 
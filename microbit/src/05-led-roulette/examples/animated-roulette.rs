@@ -0,0 +1,54 @@
+#![deny(unsafe_code)]
+#![no_main]
+#![no_std]
+
+use cortex_m_rt::entry;
+use discovery_support::animation::{self, Animation, Frame, Mode};
+use discovery_support::delay::LowPowerDelay;
+use discovery_support::display::init_display;
+use microbit::{board::Board, hal::clocks::Clocks};
+use panic_rtt_target as _;
+use rtt_target::rtt_init_print;
+
+const PIXELS: [(usize, usize); 16] = [
+    (0, 0),
+    (0, 1),
+    (0, 2),
+    (0, 3),
+    (0, 4),
+    (1, 4),
+    (2, 4),
+    (3, 4),
+    (4, 4),
+    (4, 3),
+    (4, 2),
+    (4, 1),
+    (4, 0),
+    (3, 0),
+    (2, 0),
+    (1, 0),
+];
+
+#[entry]
+fn main() -> ! {
+    rtt_init_print!();
+
+    let mut board = Board::take().unwrap();
+    // Sleep between steps instead of keeping the CPU busy
+    let clocks = Clocks::new(board.CLOCK).start_lfclk();
+    let mut delay = LowPowerDelay::new(board.RTC0, &clocks, &mut board.SCB);
+    init_display(board.TIMER1, board.display_pins);
+
+    // The roulette is just an animation with one frame per lit LED
+    let mut frames = [Frame::new([[0; 5]; 5], 30); 16];
+    for (frame, &(row, col)) in frames.iter_mut().zip(PIXELS.iter()) {
+        frame.leds[row][col] = 9;
+    }
+    let roulette = Animation::new(&frames, Mode::Loop);
+
+    // Clear the screen with a wipe first, then spin forever
+    animation::play(&animation::WIPE, &mut delay);
+    loop {
+        animation::play(&roulette, &mut delay);
+    }
+}
@@ -0,0 +1,95 @@
+//! The roulette once more, written with `async` and Embassy instead of a
+//! main loop that checks on everything in turn. Spinning the roulette and
+//! watching the two buttons are three tasks that each wait for their own
+//! thing, and the executor puts the CPU to sleep whenever all of them are
+//! waiting. `embassy-nrf` has its own drivers and its own peripherals, so
+//! this program doesn't use the `microbit` crate or `discovery-support`.
+//!
+//! Button A turns the roulette around, button B makes it faster, until it
+//! starts over at the slowest speed.
+
+#![no_main]
+#![no_std]
+
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use discovery_logic::roulette::{next_pixel, PIXELS};
+use embassy_executor::Spawner;
+use embassy_nrf::gpio::{Input, Level, Output, OutputDrive, Pull};
+use embassy_time::Timer;
+use panic_rtt_target as _;
+use rtt_target::{rprintln, rtt_init_print};
+
+/// How long every step takes in milliseconds, from the slowest to the fastest.
+const SPEEDS_MS: [u64; 4] = [120, 60, 30, 15];
+const DEFAULT_SPEED: usize = 2;
+/// How long a button's contacts take to stop bouncing.
+const DEBOUNCE_MS: u64 = 20;
+
+static CLOCKWISE: AtomicBool = AtomicBool::new(true);
+static SPEED: AtomicUsize = AtomicUsize::new(DEFAULT_SPEED);
+
+/// Wait until `button` has been pressed, and let go of it again.
+async fn click(button: &mut Input<'static>) {
+    // The buttons have pull-ups, pressing one pulls its pin low
+    button.wait_for_falling_edge().await;
+    Timer::after_millis(DEBOUNCE_MS).await;
+    button.wait_for_high().await;
+    Timer::after_millis(DEBOUNCE_MS).await;
+}
+
+#[embassy_executor::task]
+async fn turn_around(mut button_a: Input<'static>) {
+    loop {
+        click(&mut button_a).await;
+        CLOCKWISE.fetch_xor(true, Ordering::Relaxed);
+    }
+}
+
+#[embassy_executor::task]
+async fn speed_up(mut button_b: Input<'static>) {
+    loop {
+        click(&mut button_b).await;
+        let speed = (SPEED.load(Ordering::Relaxed) + 1) % SPEEDS_MS.len();
+        SPEED.store(speed, Ordering::Relaxed);
+        rprintln!("{} ms a step", SPEEDS_MS[speed]);
+    }
+}
+
+#[embassy_executor::main]
+async fn main(spawner: Spawner) {
+    rtt_init_print!();
+    let p = embassy_nrf::init(Default::default());
+
+    // An LED lights up where its row is high and its column is low. Only one
+    // is lit at a time, so unlike the display driver this needn't go through
+    // the rows one after the other.
+    let mut rows = [
+        Output::new(p.P0_21, Level::Low, OutputDrive::Standard),
+        Output::new(p.P0_22, Level::Low, OutputDrive::Standard),
+        Output::new(p.P0_15, Level::Low, OutputDrive::Standard),
+        Output::new(p.P0_24, Level::Low, OutputDrive::Standard),
+        Output::new(p.P0_19, Level::Low, OutputDrive::Standard),
+    ];
+    let mut cols = [
+        Output::new(p.P0_28, Level::High, OutputDrive::Standard),
+        Output::new(p.P0_11, Level::High, OutputDrive::Standard),
+        Output::new(p.P0_31, Level::High, OutputDrive::Standard),
+        Output::new(p.P1_05, Level::High, OutputDrive::Standard),
+        Output::new(p.P0_30, Level::High, OutputDrive::Standard),
+    ];
+
+    spawner.spawn(turn_around(Input::new(p.P0_14, Pull::None)).unwrap());
+    spawner.spawn(speed_up(Input::new(p.P0_23, Pull::None)).unwrap());
+
+    let mut current = 0;
+    loop {
+        let (row, col) = PIXELS[current];
+        rows[row].set_high();
+        cols[col].set_low();
+        // The other tasks run while this one waits
+        Timer::after_millis(SPEEDS_MS[SPEED.load(Ordering::Relaxed)]).await;
+        rows[row].set_low();
+        cols[col].set_high();
+        current = next_pixel(current, CLOCKWISE.load(Ordering::Relaxed));
+    }
+}
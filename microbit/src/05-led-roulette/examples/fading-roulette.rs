@@ -0,0 +1,57 @@
+#![deny(unsafe_code)]
+#![no_main]
+#![no_std]
+
+use cortex_m_rt::entry;
+use discovery_support::delay::LowPowerDelay;
+use discovery_support::display::{fade, init_display, FrameBuffer, MAX_BRIGHTNESS};
+use microbit::{
+    board::Board,
+    hal::{clocks::Clocks, prelude::*},
+};
+use panic_rtt_target as _;
+use rtt_target::rtt_init_print;
+
+const PIXELS: [(usize, usize); 16] = [
+    (0, 0),
+    (0, 1),
+    (0, 2),
+    (0, 3),
+    (0, 4),
+    (1, 4),
+    (2, 4),
+    (3, 4),
+    (4, 4),
+    (4, 3),
+    (4, 2),
+    (4, 1),
+    (4, 0),
+    (3, 0),
+    (2, 0),
+    (1, 0),
+];
+
+#[entry]
+fn main() -> ! {
+    rtt_init_print!();
+
+    let mut board = Board::take().unwrap();
+    // Sleep between steps instead of keeping the CPU busy
+    let clocks = Clocks::new(board.CLOCK).start_lfclk();
+    let mut delay = LowPowerDelay::new(board.RTC0, &clocks, &mut board.SCB);
+    init_display(board.TIMER1, board.display_pins);
+
+    let mut frame = FrameBuffer::new();
+
+    loop {
+        for &(row, col) in PIXELS.iter() {
+            // Rather than switching the previous LED off, let every LED we
+            // already passed get a little darker so the light leaves a trail.
+            // Nothing shows up until the new frame is complete and committed.
+            fade(frame.leds_mut());
+            frame.set(row, col, MAX_BRIGHTNESS);
+            frame.commit();
+            delay.delay_ms(50u32);
+        }
+    }
+}
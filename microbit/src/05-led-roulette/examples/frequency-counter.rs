@@ -0,0 +1,70 @@
+#![no_main]
+#![no_std]
+
+use core::fmt::Write;
+use cortex_m_rt::entry;
+use discovery_logic::pulse::PulseStats;
+use discovery_support::pulse_capture::{PulseCapture, TICKS_PER_SECOND};
+use discovery_support::serial::{FlowControl, UartePort};
+use microbit::{
+    board::Board,
+    hal::{
+        gpiote::Gpiote,
+        ppi,
+        prelude::*,
+        uarte::{self, Baudrate, Parity},
+        Timer,
+    },
+    pac,
+};
+use panic_rtt_target as _;
+use rtt_target::rtt_init_print;
+
+#[entry]
+fn main() -> ! {
+    rtt_init_print!();
+
+    let board = Board::take().unwrap();
+    // `Board` doesn't hand out the PPI, so nobody else can be using it
+    let ppi = ppi::Parts::new(unsafe { pac::Peripherals::steal() }.PPI);
+    let gpiote = Gpiote::new(board.GPIOTE);
+
+    let serial = uarte::Uarte::new(
+        board.UARTE0,
+        board.uart.into(),
+        Parity::EXCLUDED,
+        Baudrate::BAUD115200,
+    );
+    let mut serial = UartePort::new(serial, FlowControl::Disabled);
+
+    // The signal goes into the big ring 0 of the edge connector
+    let pin = board.pins.p0_02.into_floating_input().degrade();
+    let mut capture = PulseCapture::new(board.TIMER1, ppi.ppi0, gpiote.channel0(), pin);
+
+    let mut timer = Timer::periodic(board.TIMER0);
+    let mut stats = PulseStats::new();
+    write!(serial, "Measuring the signal on ring 0\r\n").unwrap();
+
+    // Report once a second, the timer ticks at 1MHz
+    timer.start(1_000_000u32);
+    loop {
+        if let Some((high, ticks)) = capture.poll() {
+            stats.add(high, ticks);
+        }
+
+        if timer.wait().is_ok() {
+            match (stats.frequency_hz(TICKS_PER_SECOND), stats.duty_percent()) {
+                (Some(frequency), Some(duty)) => write!(
+                    serial,
+                    "{:.1} Hz, {:.1}% high, from {} phases\r\n",
+                    frequency,
+                    duty,
+                    stats.phases()
+                )
+                .unwrap(),
+                _ => write!(serial, "No signal\r\n").unwrap(),
+            }
+            stats = PulseStats::new();
+        }
+    }
+}
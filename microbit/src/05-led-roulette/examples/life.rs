@@ -0,0 +1,76 @@
+#![no_main]
+#![no_std]
+
+use cortex_m_rt::entry;
+use discovery_logic::life::{population, step, to_leds, Grid, SIZE};
+use discovery_support::buttons::{init_buttons, Button, ButtonEvent};
+use discovery_support::display::{display_leds, init_display};
+use discovery_support::random::Random;
+use microbit::{
+    board::Board,
+    hal::{prelude::*, Timer},
+};
+use panic_rtt_target as _;
+use rtt_target::{rprintln, rtt_init_print};
+
+/// How long every generation stays on the display.
+const GENERATION_MS: u32 = 400;
+/// How many cells out of a hundred are alive in a new grid.
+const ALIVE_PERCENT: u32 = 40;
+
+/// A new grid, with cells alive at random.
+fn seed(random: &mut Random) -> Grid {
+    let mut grid = [[false; SIZE]; SIZE];
+    for cell in grid.iter_mut().flatten() {
+        *cell = random.chance(ALIVE_PERCENT);
+    }
+    grid
+}
+
+#[entry]
+fn main() -> ! {
+    rtt_init_print!();
+
+    let board = Board::take().unwrap();
+    let mut timer = Timer::new(board.TIMER0);
+    init_display(board.TIMER1, board.display_pins);
+    let mut events = init_buttons(board.GPIOTE, board.TIMER2, board.buttons);
+    let mut random = Random::new(board.RNG);
+
+    let mut grid = seed(&mut random);
+    // The generation before, to tell when the grid blinks back and forth
+    let mut previous = grid;
+    let mut paused = false;
+
+    loop {
+        display_leds(to_leds(&grid));
+        timer.delay_ms(GENERATION_MS);
+
+        while let Some(event) = events.recv() {
+            match event {
+                (Button::A, ButtonEvent::Click) => {
+                    grid = seed(&mut random);
+                    previous = grid;
+                    rprintln!("New grid, {} alive", population(&grid));
+                }
+                (Button::B, ButtonEvent::Click) => paused = !paused,
+                _ => {}
+            }
+        }
+        if paused {
+            continue;
+        }
+
+        let next = step(&grid);
+        // A grid that died out, stopped changing, or blinks between two
+        // generations doesn't have much more to show. Start over.
+        if population(&next) == 0 || next == grid || next == previous {
+            grid = seed(&mut random);
+            previous = grid;
+            rprintln!("Starting over, {} alive", population(&grid));
+        } else {
+            previous = grid;
+            grid = next;
+        }
+    }
+}
@@ -0,0 +1,62 @@
+#![deny(unsafe_code)]
+#![no_main]
+#![no_std]
+
+use cortex_m_rt::entry;
+use discovery_support::display::{display_greyscale, init_display, light_level, MAX_BRIGHTNESS};
+use microbit::{
+    board::Board,
+    hal::{
+        prelude::*,
+        saadc::{Saadc, SaadcConfig},
+        Timer,
+    },
+};
+use panic_rtt_target as _;
+use rtt_target::{rprintln, rtt_init_print};
+
+const PIXELS: [(usize, usize); 16] = [
+    (0, 0),
+    (0, 1),
+    (0, 2),
+    (0, 3),
+    (0, 4),
+    (1, 4),
+    (2, 4),
+    (3, 4),
+    (4, 4),
+    (4, 3),
+    (4, 2),
+    (4, 1),
+    (4, 0),
+    (3, 0),
+    (2, 0),
+    (1, 0),
+];
+
+#[entry]
+fn main() -> ! {
+    rtt_init_print!();
+
+    let board = Board::take().unwrap();
+    let mut timer = Timer::new(board.TIMER0);
+    let mut saadc = Saadc::new(board.SAADC, SaadcConfig::default());
+    init_display(board.TIMER1, board.display_pins);
+
+    let mut leds = [[0; 5]; 5];
+
+    loop {
+        // Measure once per lap, the display flickers a little every time
+        let light = light_level(&mut saadc);
+        // Never go all the way dark, or there would be nothing to see
+        let brightness = 1 + (light as u32 * (MAX_BRIGHTNESS as u32 - 1) / 255) as u8;
+        rprintln!("light level {}, brightness {}", light, brightness);
+
+        for &(row, col) in PIXELS.iter() {
+            leds[row][col] = brightness;
+            display_greyscale(leds);
+            timer.delay_ms(30u32);
+            leds[row][col] = 0;
+        }
+    }
+}
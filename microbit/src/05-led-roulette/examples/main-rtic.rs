@@ -0,0 +1,118 @@
+//! led-roulette on top of RTIC.
+//!
+//! The LED matrix is refreshed by a hardware task bound to the display timer's
+//! interrupt, while moving the lit LED along is left to a software task that a
+//! second timer spawns every 30ms. The refresh task has the higher priority, so
+//! it preempts `advance` whenever the display needs attention, and `advance`
+//! has to `lock` the shared display to hand it a new image.
+
+#![no_main]
+#![no_std]
+
+use panic_rtt_target as _;
+
+#[rtic::app(device = microbit::pac, dispatchers = [SWI0_EGU0])]
+mod app {
+    use microbit::{
+        display::nonblocking::{BitImage, Display},
+        display_pins,
+        hal::{
+            gpio::{p0, p1},
+            prelude::*,
+            timer::{Periodic, Timer},
+        },
+        pac::{TIMER0, TIMER1},
+    };
+    use rtt_target::{rprintln, rtt_init_print};
+
+    const PIXELS: [(usize, usize); 16] = [
+        (0, 0),
+        (0, 1),
+        (0, 2),
+        (0, 3),
+        (0, 4),
+        (1, 4),
+        (2, 4),
+        (3, 4),
+        (4, 4),
+        (4, 3),
+        (4, 2),
+        (4, 1),
+        (4, 0),
+        (3, 0),
+        (2, 0),
+        (1, 0),
+    ];
+
+    #[shared]
+    struct Shared {
+        display: Display<TIMER1>,
+    }
+
+    #[local]
+    struct Local {
+        timer: Timer<TIMER0, Periodic>,
+        current: usize,
+    }
+
+    #[init]
+    fn init(cx: init::Context) -> (Shared, Local, init::Monotonics) {
+        rtt_init_print!();
+
+        // RTIC has already taken the peripherals, so instead of going through
+        // `Board::take` we build the display pins ourselves.
+        let p0 = p0::Parts::new(cx.device.P0);
+        let p1 = p1::Parts::new(cx.device.P1);
+        let display = Display::new(cx.device.TIMER1, display_pins!(p0, p1));
+
+        // 30ms per step, the timer ticks at 1MHz
+        let mut timer = Timer::periodic(cx.device.TIMER0);
+        timer.enable_interrupt();
+        timer.start(30_000u32);
+
+        rprintln!("roulette started");
+        (
+            Shared { display },
+            Local { timer, current: 0 },
+            init::Monotonics(),
+        )
+    }
+
+    #[idle]
+    fn idle(_: idle::Context) -> ! {
+        loop {
+            cortex_m::asm::wfi();
+        }
+    }
+
+    /// Keep the LED matrix going, this runs many times per frame.
+    #[task(binds = TIMER1, priority = 2, shared = [display])]
+    fn refresh(mut cx: refresh::Context) {
+        cx.shared
+            .display
+            .lock(|display| display.handle_display_event());
+    }
+
+    /// Fires every 30ms and schedules the next roulette step.
+    #[task(binds = TIMER0, local = [timer])]
+    fn tick(cx: tick::Context) {
+        // Acknowledge the compare event so the interrupt doesn't fire again
+        // straight away.
+        cx.local.timer.wait().ok();
+        advance::spawn().ok();
+    }
+
+    /// Move on to the next LED of the roulette.
+    #[task(shared = [display], local = [current])]
+    fn advance(mut cx: advance::Context) {
+        let current = cx.local.current;
+        *current = (*current + 1) % PIXELS.len();
+
+        let (row, col) = PIXELS[*current];
+        let mut leds = [[0; 5]; 5];
+        leds[row][col] = 1;
+        let image = BitImage::new(&leds);
+
+        cx.shared.display.lock(|display| display.show(&image));
+    }
+}
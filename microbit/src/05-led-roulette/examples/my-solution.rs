@@ -3,17 +3,27 @@
 #![no_std]
 
 use cortex_m_rt::entry;
-use rtt_target::rtt_init_print;
+use microbit::{board::Board, display::blocking::Display, hal::Timer};
 use panic_rtt_target as _;
-use microbit::{
-    board::Board,
-    display::blocking::Display,
-    hal::Timer,
-};
+use rtt_target::rtt_init_print;
 
 const PIXELS: [(usize, usize); 16] = [
-    (0,0), (0,1), (0,2), (0,3), (0,4), (1,4), (2,4), (3,4), (4,4),
-    (4,3), (4,2), (4,1), (4,0), (3,0), (2,0), (1,0)
+    (0, 0),
+    (0, 1),
+    (0, 2),
+    (0, 3),
+    (0, 4),
+    (1, 4),
+    (2, 4),
+    (3, 4),
+    (4, 4),
+    (4, 3),
+    (4, 2),
+    (4, 1),
+    (4, 0),
+    (3, 0),
+    (2, 0),
+    (1, 0),
 ];
 
 #[entry]
@@ -31,7 +41,7 @@ fn main() -> ! {
         [0, 0, 0, 0, 0],
     ];
 
-    let mut last_led = (0,0);
+    let mut last_led = (0, 0);
 
     loop {
         for current_led in PIXELS.iter() {
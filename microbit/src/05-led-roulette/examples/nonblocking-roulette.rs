@@ -0,0 +1,68 @@
+#![deny(unsafe_code)]
+#![no_main]
+#![no_std]
+
+use cortex_m_rt::entry;
+use discovery_support::display::{display_leds, init_display};
+use microbit::{
+    board::Board,
+    hal::{prelude::*, Timer},
+};
+use panic_rtt_target as _;
+use rtt_target::{rprintln, rtt_init_print};
+
+const PIXELS: [(usize, usize); 16] = [
+    (0, 0),
+    (0, 1),
+    (0, 2),
+    (0, 3),
+    (0, 4),
+    (1, 4),
+    (2, 4),
+    (3, 4),
+    (4, 4),
+    (4, 3),
+    (4, 2),
+    (4, 1),
+    (4, 0),
+    (3, 0),
+    (2, 0),
+    (1, 0),
+];
+
+#[entry]
+fn main() -> ! {
+    rtt_init_print!();
+
+    let board = Board::take().unwrap();
+    let mut timer = Timer::periodic(board.TIMER0);
+    init_display(board.TIMER1, board.display_pins);
+
+    let mut leds = [[0; 5]; 5];
+    let mut current = 0;
+    let mut spins: u32 = 0;
+
+    // 30ms per step, the timer ticks at 1MHz
+    timer.start(30_000u32);
+    loop {
+        // Instead of blocking inside `display.show` we only check whether it is
+        // time to move on to the next LED, the refresh happens in the TIMER1
+        // interrupt.
+        if timer.wait().is_ok() {
+            let (last_row, last_col) = PIXELS[current];
+            leds[last_row][last_col] = 0;
+            current = (current + 1) % PIXELS.len();
+            let (row, col) = PIXELS[current];
+            leds[row][col] = 1;
+            display_leds(leds);
+
+            if current == 0 {
+                rprintln!("main loop spun {} times during the last lap", spins);
+                spins = 0;
+            }
+        }
+
+        // Stand-in for any other work main wants to get done
+        spins += 1;
+    }
+}
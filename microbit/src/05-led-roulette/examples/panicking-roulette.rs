@@ -0,0 +1,50 @@
+#![deny(unsafe_code)]
+#![no_main]
+#![no_std]
+
+use cortex_m_rt::entry;
+use microbit::{board::Board, display::blocking::Display, hal::Timer};
+use panic_display as _;
+use rtt_target::{rprintln, rtt_init_print};
+
+const PIXELS: [(usize, usize); 16] = [
+    (0, 0),
+    (0, 1),
+    (0, 2),
+    (0, 3),
+    (0, 4),
+    (1, 4),
+    (2, 4),
+    (3, 4),
+    (4, 4),
+    (4, 3),
+    (4, 2),
+    (4, 1),
+    (4, 0),
+    (3, 0),
+    (2, 0),
+    (1, 0),
+];
+
+#[entry]
+fn main() -> ! {
+    rtt_init_print!();
+
+    let board = Board::take().unwrap();
+    let mut timer = Timer::new(board.TIMER0);
+    let mut display = Display::new(board.display_pins);
+    let mut leds = [[0; 5]; 5];
+
+    for lap in 0..3 {
+        rprintln!("lap {}", lap);
+        for &(row, col) in PIXELS.iter() {
+            leds[row][col] = 1;
+            display.show(&mut timer, leds, 30);
+            leds[row][col] = 0;
+        }
+    }
+
+    // Three laps are all you get, after that the panic handler takes over the
+    // display and blinks out the line number of this panic
+    panic!("out of laps");
+}
@@ -0,0 +1,40 @@
+#![deny(unsafe_code)]
+#![no_main]
+#![no_std]
+
+use cortex_m_rt::entry;
+use discovery_support::ppi_blink::ppi_blink;
+use microbit::{
+    board::Board,
+    hal::{gpiote::Gpiote, ppi, prelude::*},
+    pac,
+};
+use panic_rtt_target as _;
+use rtt_target::{rprintln, rtt_init_print};
+
+#[entry]
+fn main() -> ! {
+    rtt_init_print!();
+
+    let board = Board::take().unwrap();
+    // `Board` doesn't hand out the PPI, so nobody else can be using it
+    #[allow(unsafe_code)]
+    let ppi = ppi::Parts::new(unsafe { pac::Peripherals::steal() }.PPI);
+    let gpiote = Gpiote::new(board.GPIOTE);
+
+    // An LED lights up when its row is high and its column is low. The
+    // column stays low, and the blinking row does the rest.
+    let mut col1 = board.display_pins.col1;
+    col1.set_low().unwrap();
+    let row1 = board.display_pins.row1.degrade();
+
+    // Half a second on, half a second off
+    let _blink = ppi_blink(board.TIMER0, ppi.ppi0, &gpiote.channel0(), row1, 500_000);
+    rprintln!("Blinking, and the CPU is going to sleep");
+
+    loop {
+        // There's nothing that could wake us up, and nothing to do if
+        // something did
+        cortex_m::asm::wfi();
+    }
+}
@@ -0,0 +1,58 @@
+#![deny(unsafe_code)]
+#![no_main]
+#![no_std]
+
+use cortex_m_rt::entry;
+use discovery_support::buttons::{init_buttons, init_rings, Button, ButtonEvent, Pull};
+use discovery_support::display::{display_leds, init_display};
+use discovery_support::idle;
+use microbit::board::Board;
+use panic_rtt_target as _;
+use rtt_target::{rprintln, rtt_init_print};
+
+/// The column of the display that lights up while each input is active.
+fn column(button: Button) -> usize {
+    match button {
+        Button::A => 0,
+        Button::P0 => 1,
+        Button::P1 => 2,
+        Button::P2 => 3,
+        Button::B => 4,
+    }
+}
+
+#[entry]
+fn main() -> ! {
+    rtt_init_print!();
+
+    let mut board = Board::take().unwrap();
+    idle::init(&mut board.SCB);
+    init_display(board.TIMER1, board.display_pins);
+    let mut events = init_buttons(board.GPIOTE, board.TIMER2, board.buttons);
+    // Pulled up, so a wire, or a switch, from a ring to GND makes it active.
+    // For the output of a motion sensor, use `Pull::Down` instead.
+    init_rings(
+        board.pins.p0_02.degrade(),
+        board.pins.p0_03.degrade(),
+        board.pins.p0_04.degrade(),
+        Pull::Up,
+    );
+
+    let mut leds = [[0; 5]; 5];
+    loop {
+        while let Some((button, event)) = events.recv() {
+            rprintln!("{:?} {:?}", button, event);
+            // The buttons click, the rings are pressed and released
+            let lit = match event {
+                ButtonEvent::Pressed | ButtonEvent::LongPress => 1,
+                ButtonEvent::Released => 0,
+                ButtonEvent::Click | ButtonEvent::DoubleClick => 1 - leds[0][column(button)],
+            };
+            for row in leds.iter_mut() {
+                row[column(button)] = lit;
+            }
+            display_leds(leds);
+        }
+        idle::sleep();
+    }
+}
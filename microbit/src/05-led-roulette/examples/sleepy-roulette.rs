@@ -0,0 +1,133 @@
+#![deny(unsafe_code)]
+#![no_main]
+#![no_std]
+
+use cortex_m_rt::entry;
+use discovery_support::display::{display_leds, free_display, init_display};
+use discovery_support::idle;
+use discovery_support::power::Power;
+use discovery_support::profiling::Marker;
+use microbit::{
+    board::Board,
+    gpio::DisplayPins,
+    hal::{prelude::*, Timer},
+};
+use panic_rtt_target as _;
+use rtt_target::{rprintln, rtt_init_print};
+
+const PIXELS: [(usize, usize); 16] = [
+    (0, 0),
+    (0, 1),
+    (0, 2),
+    (0, 3),
+    (0, 4),
+    (1, 4),
+    (2, 4),
+    (3, 4),
+    (4, 4),
+    (4, 3),
+    (4, 2),
+    (4, 1),
+    (4, 0),
+    (3, 0),
+    (2, 0),
+    (1, 0),
+];
+
+const STEP_MS: u32 = 30;
+/// Without a press of button A for this long, it's time for bed.
+const SLEEP_AFTER_MS: u32 = 30_000;
+
+/// How the roulette is kept in the retained byte while the chip is off: the
+/// LED it was at in the low 4 bits, and which way it was going. After the
+/// power has been off the byte is 0, so `SAVED` tells that there's something
+/// to restore.
+const SAVED: u8 = 1 << 7;
+const CLOCKWISE: u8 = 1 << 6;
+const POSITION: u8 = 0x0f;
+
+#[entry]
+fn main() -> ! {
+    rtt_init_print!();
+
+    let mut board = Board::take().unwrap();
+    let mut power = Power::take().unwrap();
+
+    let mut current = 0;
+    let mut clockwise = true;
+    let saved = power.retained();
+    if power.woke_up() && saved & SAVED != 0 {
+        current = usize::from(saved & POSITION);
+        clockwise = saved & CLOCKWISE != 0;
+        rprintln!("Good morning, carrying on from LED {}", current);
+    }
+    power.retain(0);
+
+    let mut timer = Timer::periodic(board.TIMER0);
+    idle::init(&mut board.SCB);
+    idle::wake_on_timer(&mut timer);
+    init_display(board.TIMER1, board.display_pins);
+    let button_a = board.buttons.button_a.degrade();
+    // Ring 2 of the edge connector is high while the CPU is awake, for a
+    // power profiler to show next to the current
+    let marker = Marker::new(board.pins.p0_04.degrade());
+
+    let mut leds = [[0; 5]; 5];
+    // The press that woke us up doesn't count as one to turn around
+    let mut was_pressed = button_a.is_low().unwrap();
+    let mut idle_ms = 0;
+
+    // The timer ticks at 1MHz
+    timer.start(STEP_MS * 1_000);
+    while idle_ms < SLEEP_AFTER_MS {
+        if !idle::timer_fired(&mut timer) {
+            idle::sleep();
+            continue;
+        }
+        let _awake = marker.region();
+
+        let pressed = button_a.is_low().unwrap();
+        if pressed {
+            if !was_pressed {
+                clockwise = !clockwise;
+            }
+            idle_ms = 0;
+        }
+        was_pressed = pressed;
+
+        let (last_row, last_col) = PIXELS[current];
+        leds[last_row][last_col] = 0;
+        current = if clockwise {
+            (current + 1) % PIXELS.len()
+        } else {
+            (current + PIXELS.len() - 1) % PIXELS.len()
+        };
+        let (row, col) = PIXELS[current];
+        leds[row][col] = 1;
+        display_leds(leds);
+
+        idle_ms += STEP_MS;
+    }
+
+    rprintln!("Going to sleep, press button A to wake up");
+    let mut saved = SAVED | current as u8;
+    if clockwise {
+        saved |= CLOCKWISE;
+    }
+    power.retain(saved);
+
+    let (_, mut display_pins) = free_display().unwrap();
+    lights_out(&mut display_pins);
+    power.wake_on_low(&button_a);
+    power.system_off()
+}
+
+/// The pins stay the way they are while the chip is off, so make sure that
+/// no LED stays lit.
+fn lights_out(pins: &mut DisplayPins) {
+    pins.row1.set_low().unwrap();
+    pins.row2.set_low().unwrap();
+    pins.row3.set_low().unwrap();
+    pins.row4.set_low().unwrap();
+    pins.row5.set_low().unwrap();
+}
@@ -0,0 +1,42 @@
+#![no_main]
+#![no_std]
+
+use cortex_m_rt::entry;
+use discovery_support::delay::LowPowerDelay;
+use discovery_support::display::{fade, init_display, FrameBuffer, MAX_BRIGHTNESS};
+use discovery_support::random::Random;
+use microbit::{
+    board::Board,
+    hal::{clocks::Clocks, prelude::*},
+};
+use panic_rtt_target as _;
+use rtt_target::rtt_init_print;
+
+#[entry]
+fn main() -> ! {
+    rtt_init_print!();
+
+    let mut board = Board::take().unwrap();
+    let clocks = Clocks::new(board.CLOCK).start_lfclk();
+    let mut delay = LowPowerDelay::new(board.RTC0, &clocks, &mut board.SCB);
+    init_display(board.TIMER1, board.display_pins);
+    // The pool fills up while we sleep, so there's never any waiting for the
+    // next sparkle
+    let mut random = Random::new_interrupt_fed(board.RNG);
+
+    let mut frame = FrameBuffer::new();
+
+    loop {
+        // Every sparkle fades away, a new one lights up somewhere else now
+        // and then
+        fade(frame.leds_mut());
+        if random.chance(30) {
+            let row = random.below(5) as usize;
+            let col = random.below(5) as usize;
+            let brightness = random.between(2, u32::from(MAX_BRIGHTNESS) + 1) as u8;
+            frame.set(row, col, brightness);
+        }
+        frame.commit();
+        delay.delay_ms(random.between(40, 120));
+    }
+}
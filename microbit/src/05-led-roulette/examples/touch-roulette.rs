@@ -0,0 +1,105 @@
+#![deny(unsafe_code)]
+#![no_main]
+#![no_std]
+
+use cortex_m_rt::entry;
+use discovery_support::buttons::{init_buttons, Button, ButtonEvent};
+use discovery_support::display::{display_leds, init_display};
+use discovery_support::idle;
+use discovery_support::touch::{TouchEvent, TouchLogo};
+use microbit::{
+    board::Board,
+    hal::{prelude::*, Timer},
+};
+use panic_rtt_target as _;
+use rtt_target::{rprintln, rtt_init_print};
+
+const PIXELS: [(usize, usize); 16] = [
+    (0, 0),
+    (0, 1),
+    (0, 2),
+    (0, 3),
+    (0, 4),
+    (1, 4),
+    (2, 4),
+    (3, 4),
+    (4, 4),
+    (4, 3),
+    (4, 2),
+    (4, 1),
+    (4, 0),
+    (3, 0),
+    (2, 0),
+    (1, 0),
+];
+
+/// How long every step takes in milliseconds, from the slowest to the fastest.
+const SPEEDS_MS: [u32; 4] = [120, 60, 30, 15];
+const DEFAULT_SPEED: usize = 2;
+
+#[entry]
+fn main() -> ! {
+    rtt_init_print!();
+
+    let mut board = Board::take().unwrap();
+    let mut timer = Timer::periodic(board.TIMER0);
+    idle::init(&mut board.SCB);
+    idle::wake_on_timer(&mut timer);
+    init_display(board.TIMER1, board.display_pins);
+    let mut events = init_buttons(board.GPIOTE, board.TIMER2, board.buttons);
+    // Hands off the logo while it calibrates!
+    let mut logo = TouchLogo::new(board.pins.p1_04, board.TIMER3);
+
+    let mut leds = [[0; 5]; 5];
+    let mut current = 0;
+    let mut clockwise = true;
+    let mut speed = DEFAULT_SPEED;
+    let mut paused = false;
+
+    // The timer ticks at 1MHz
+    timer.start(SPEEDS_MS[speed] * 1_000);
+    loop {
+        if let Some((button, event)) = events.recv() {
+            rprintln!("{:?} {:?}", button, event);
+            match (button, event) {
+                (Button::A, ButtonEvent::Click) => clockwise = !clockwise,
+                (Button::B, ButtonEvent::Click) => speed = (speed + 1) % SPEEDS_MS.len(),
+                (Button::B, ButtonEvent::DoubleClick) => {
+                    speed = (speed + SPEEDS_MS.len() - 1) % SPEEDS_MS.len()
+                }
+                (_, ButtonEvent::LongPress) => {
+                    clockwise = true;
+                    speed = DEFAULT_SPEED;
+                }
+                _ => {}
+            }
+            timer.start(SPEEDS_MS[speed] * 1_000);
+        }
+
+        if !idle::timer_fired(&mut timer) {
+            idle::sleep();
+            continue;
+        }
+
+        // The wheel stands still for as long as the logo is touched. Nothing
+        // tells us when it is touched, so it is measured once every step.
+        match logo.poll_event() {
+            Some(TouchEvent::Touched) => paused = true,
+            Some(TouchEvent::Released) => paused = false,
+            None => {}
+        }
+
+        if !paused {
+            let (last_row, last_col) = PIXELS[current];
+            leds[last_row][last_col] = 0;
+            current = if clockwise {
+                (current + 1) % PIXELS.len()
+            } else {
+                (current + PIXELS.len() - 1) % PIXELS.len()
+            };
+            let (row, col) = PIXELS[current];
+            leds[row][col] = 1;
+            display_leds(leds);
+        }
+    }
+}
@@ -0,0 +1,69 @@
+//! The LED roulette, steered with the buttons.
+//!
+//! It lives in a library, rather than in an example of its own, so that
+//! other programs can run it too, like the launcher that bundles several
+//! chapters into one.
+
+#![deny(unsafe_code)]
+#![no_std]
+
+use discovery_logic::roulette::{next_pixel, PIXELS};
+use discovery_support::buttons::{init_buttons, Button, ButtonEvent};
+use discovery_support::display::{display_leds, init_display};
+use discovery_support::idle;
+use microbit::{
+    board::Board,
+    hal::{prelude::*, Timer},
+};
+
+/// How long every step takes in milliseconds, from the slowest to the fastest.
+const SPEEDS_MS: [u32; 4] = [120, 60, 30, 15];
+const DEFAULT_SPEED: usize = 2;
+
+/// Spin the roulette, with button A to reverse it and button B to change its
+/// speed.
+pub fn run(mut board: Board) -> ! {
+    let mut timer = Timer::periodic(board.TIMER0);
+    idle::init(&mut board.SCB);
+    idle::wake_on_timer(&mut timer);
+    init_display(board.TIMER1, board.display_pins);
+    let mut events = init_buttons(board.GPIOTE, board.TIMER2, board.buttons);
+
+    let mut leds = [[0; 5]; 5];
+    let mut current = 0;
+    let mut clockwise = true;
+    let mut speed = DEFAULT_SPEED;
+
+    // The timer ticks at 1MHz
+    timer.start(SPEEDS_MS[speed] * 1_000);
+    loop {
+        if let Some((button, event)) = events.recv() {
+            match (button, event) {
+                (Button::A, ButtonEvent::Click) => clockwise = !clockwise,
+                (Button::B, ButtonEvent::Click) => speed = (speed + 1) % SPEEDS_MS.len(),
+                (Button::B, ButtonEvent::DoubleClick) => {
+                    speed = (speed + SPEEDS_MS.len() - 1) % SPEEDS_MS.len()
+                }
+                (_, ButtonEvent::LongPress) => {
+                    clockwise = true;
+                    speed = DEFAULT_SPEED;
+                }
+                _ => {}
+            }
+            timer.start(SPEEDS_MS[speed] * 1_000);
+        }
+
+        if idle::timer_fired(&mut timer) {
+            let (last_row, last_col) = PIXELS[current];
+            leds[last_row][last_col] = 0;
+            current = next_pixel(current, clockwise);
+            let (row, col) = PIXELS[current];
+            leds[row][col] = 1;
+            display_leds(leds);
+        }
+
+        // Nothing else to do until the timer, a button or the display wakes us
+        // up again
+        idle::sleep();
+    }
+}
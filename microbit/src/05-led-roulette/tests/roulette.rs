@@ -0,0 +1,45 @@
+//! Tests that run on the micro:bit itself. `cargo test` flashes them with
+//! `probe-rs`, which prints what `defmt-test` reports, see `.cargo/config.toml`.
+
+#![no_main]
+#![no_std]
+
+use defmt_rtt as _;
+
+// Not used, but it has the interrupt vectors the linker needs
+use microbit as _;
+
+use panic_probe as _;
+
+#[defmt_test::tests]
+mod tests {
+    use discovery_logic::roulette::{next_pixel, PIXELS};
+
+    #[test]
+    fn goes_round_clockwise() {
+        defmt::assert_eq!(next_pixel(0, true), 1);
+        defmt::assert_eq!(next_pixel(PIXELS.len() - 1, true), 0);
+    }
+
+    #[test]
+    fn goes_round_anticlockwise() {
+        defmt::assert_eq!(next_pixel(1, false), 0);
+        defmt::assert_eq!(next_pixel(0, false), PIXELS.len() - 1);
+    }
+
+    #[test]
+    fn every_step_is_to_a_neighbouring_led() {
+        for current in 0..PIXELS.len() {
+            let (row, col) = PIXELS[current];
+            let (next_row, next_col) = PIXELS[next_pixel(current, true)];
+            defmt::assert_eq!(row.abs_diff(next_row) + col.abs_diff(next_col), 1);
+        }
+    }
+
+    #[test]
+    fn every_led_is_on_the_edge() {
+        for &(row, col) in PIXELS.iter() {
+            defmt::assert!(row == 0 || row == 4 || col == 0 || col == 4);
+        }
+    }
+}
@@ -1,30 +1,23 @@
-//! This build script copies the `memory.x` file from the crate root into
-//! a directory where the linker can always find it at build time.
-//! For many projects this is optional, as the linker always searches the
-//! project root directory (wherever `Cargo.toml` is). However, if you
-//! are using a workspace or have a more complicated build setup, this
-//! build script becomes required. Additionally, by requesting that
-//! Cargo re-run the build script whenever `memory.x` is changed,
-//! a rebuild of the application with new memory settings is ensured after updating `memory.x`.
+//! This build script writes the `memory.x` for the board the chapter is
+//! built for into a directory where the linker can always find it. Which
+//! layout that is depends on the features, see `memory_x.rs`. With the
+//! `embassy` feature, it writes a `device.x` as well, see `device_x.rs`. It
+//! also tells the programs which commit they're built from, and when, for the
+//! shell's `info`, see `build_info.rs`.
 
-use std::env;
-use std::fs::File;
-use std::io::Write;
-use std::path::PathBuf;
+#[path = "../../support/build/build_info.rs"]
+mod build_info;
+#[path = "../../support/build/device_x.rs"]
+mod device_x;
+#[path = "../../support/build/memory_x.rs"]
+mod memory_x;
 
 fn main() {
-    // Put `memory.x` in our output directory and ensure it's
-    // on the linker search path.
-    let out = &PathBuf::from(env::var_os("OUT_DIR").unwrap());
-    File::create(out.join("memory.x"))
-        .unwrap()
-        .write_all(include_bytes!("memory.x"))
-        .unwrap();
-    println!("cargo:rustc-link-search={}", out.display());
+    memory_x::write();
+    device_x::write();
+    build_info::write();
 
-    // By default, Cargo will re-run a build script whenever
-    // any file in the project changes. By specifying `memory.x`
-    // here, we ensure the build script is only re-run when
-    // `memory.x` is changed.
-    println!("cargo:rerun-if-changed=memory.x");
+    // The tests report through `defmt`, which keeps its format strings in a
+    // section of their own that only its linker script knows about
+    println!("cargo:rustc-link-arg-tests=-Tdefmt.x");
 }
@@ -0,0 +1,47 @@
+#![no_main]
+#![no_std]
+
+use cortex_m_rt::entry;
+use panic_rtt_target as _;
+use rtt_target::rtt_init_print;
+
+use microbit::{
+    hal::uarte::{self, Baudrate, Error, Parity},
+    pac::UARTE0,
+};
+
+use discovery_support::executor::block_on;
+use discovery_support::serial::{AsyncPort, FlowControl, UartePort};
+
+/// Send back whatever arrives, in capitals.
+async fn shout(serial: &mut AsyncPort<UARTE0>) -> Result<(), Error> {
+    serial
+        .write_all(b"Type something, and it comes back in capitals\r\n")
+        .await?;
+    let mut buf = [0; 32];
+    loop {
+        // The CPU sleeps in here until a byte arrives
+        let count = serial.read(&mut buf).await?;
+        buf[..count].make_ascii_uppercase();
+        serial.write_all(&buf[..count]).await?;
+    }
+}
+
+#[entry]
+fn main() -> ! {
+    rtt_init_print!();
+    let board = microbit::Board::take().unwrap();
+
+    let serial = uarte::Uarte::new(
+        board.UARTE0,
+        board.uart.into(),
+        Parity::EXCLUDED,
+        Baudrate::BAUD115200,
+    );
+    let mut serial = AsyncPort::new(UartePort::new(serial, FlowControl::Disabled));
+
+    block_on(shout(&mut serial)).unwrap();
+    loop {
+        cortex_m::asm::wfi();
+    }
+}
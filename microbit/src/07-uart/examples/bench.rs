@@ -0,0 +1,90 @@
+#![no_main]
+#![no_std]
+
+use cortex_m_rt::entry;
+use embedded_hal::timer::CountDown;
+use panic_rtt_target as _;
+use rtt_target::{rprintln, rtt_init_print};
+
+use microbit::{
+    hal::uarte,
+    hal::uarte::{Baudrate, Parity},
+    hal::Timer,
+};
+
+use discovery_logic::prbs::{Checker, Prbs};
+use discovery_support::serial::{FlowControl, SerialStats, UartePort};
+
+/// Receive with two DMA buffers in turn, which takes an interrupt every 16
+/// bytes. Turn it off to take one for every byte instead, and compare.
+const RX_DMA: bool = true;
+/// How many bytes go out with each DMA transfer. Sending stops the main loop
+/// for as long as they take, about 5.5ms at 115200 baud.
+const TX_CHUNK: usize = 64;
+
+#[entry]
+fn main() -> ! {
+    rtt_init_print!();
+    let board = microbit::Board::take().unwrap();
+
+    let serial = uarte::Uarte::new(
+        board.UARTE0,
+        board.uart.into(),
+        Parity::EXCLUDED,
+        Baudrate::BAUD115200,
+    );
+    let mut serial = UartePort::new(serial, FlowControl::Disabled);
+    if RX_DMA {
+        serial.enable_rx_dma();
+    } else {
+        serial.enable_rx_interrupt();
+    }
+
+    let mut prbs = Prbs::new(0x5eed);
+    let mut checker = Checker::new();
+    let mut chunk = [0; TX_CHUNK];
+    let mut sent = 0;
+    let mut received = 0;
+    // What went wrong up to the last report
+    let mut last = SerialStats::default();
+    let mut last_wrong = 0;
+
+    rprintln!("Benchmarking, run `serial-client --bench` on the computer");
+    // Report once a second, the timer ticks at 1MHz
+    let mut timer = Timer::periodic(board.TIMER0);
+    timer.start(1_000_000u32);
+    loop {
+        prbs.fill(&mut chunk);
+        serial.write_all(&chunk).unwrap();
+        sent += chunk.len();
+
+        // Everything that arrived while we were sending
+        while serial.rx_available() > 0 {
+            let mut incoming = [0; 64];
+            // There are bytes waiting, so this doesn't block
+            let count = embedded_io::Read::read(&mut serial, &mut incoming).unwrap();
+            for &byte in &incoming[..count] {
+                checker.check(byte);
+            }
+            received += count;
+        }
+
+        if timer.wait().is_ok() {
+            let stats = serial.stats();
+            rprintln!(
+                "tx {} B/s, rx {} B/s, {} wrong | overruns {} dropped {} framing {} parity {}",
+                sent,
+                received,
+                checker.errors() - last_wrong,
+                stats.overruns - last.overruns,
+                stats.dropped - last.dropped,
+                stats.framing_errors - last.framing_errors,
+                stats.parity_errors - last.parity_errors,
+            );
+            sent = 0;
+            received = 0;
+            last = stats;
+            last_wrong = checker.errors();
+        }
+    }
+}
@@ -0,0 +1,118 @@
+#![no_main]
+#![no_std]
+
+use cortex_m::singleton;
+use cortex_m_rt::entry;
+use panic_rtt_target as _;
+use rtt_target::{rprintln, rtt_init_print};
+
+use microbit::{
+    hal::gpio::Level,
+    hal::prelude::*,
+    hal::uarte::{self, Baudrate, Instance, Parity},
+    hal::Timer,
+};
+
+use discovery_support::serial::{Buffers, FlowControl, UartePort};
+
+/// How many bytes each direction can fall behind by, at 115200 baud that's
+/// almost 90ms worth.
+const QUEUE_LEN: usize = 1024;
+
+/// What went through in one direction since the last report.
+#[derive(Default)]
+struct Stats {
+    bytes: usize,
+    /// The most bytes that were waiting at once.
+    peak: usize,
+}
+
+/// Pass on whatever `from` has received so far.
+fn forward<A: Instance, B: Instance>(
+    from: &mut UartePort<A>,
+    to: &mut UartePort<B>,
+    stats: &mut Stats,
+) {
+    let waiting = from.rx_available();
+    if waiting == 0 {
+        return;
+    }
+    stats.peak = stats.peak.max(waiting);
+
+    let mut chunk = [0; 64];
+    // There are bytes waiting, so this doesn't block
+    let count = embedded_io::Read::read(from, &mut chunk).unwrap();
+    to.write_all(&chunk[..count]).unwrap();
+    stats.bytes += count;
+}
+
+fn report(name: &str, stats: &Stats) {
+    rprintln!(
+        "{}: {} bytes, at most {} waiting",
+        name,
+        stats.bytes,
+        stats.peak
+    );
+    // The queue holds one byte less than its length
+    if stats.peak >= QUEUE_LEN - 1 {
+        rprintln!("{}: the queue was full, some bytes were lost", name);
+    }
+}
+
+#[entry]
+fn main() -> ! {
+    rtt_init_print!();
+    let board = microbit::Board::take().unwrap();
+
+    // The one the interface chip turns into a serial port on the computer
+    let usb = uarte::Uarte::new(
+        board.UARTE0,
+        board.uart.into(),
+        Parity::EXCLUDED,
+        Baudrate::BAUD115200,
+    );
+    // And one on the big rings of the edge connector: we send on 0 and
+    // receive on 1
+    let edge_pins = uarte::Pins {
+        txd: board
+            .pins
+            .p0_02
+            .into_push_pull_output(Level::High)
+            .degrade(),
+        rxd: board.pins.p0_03.into_floating_input().degrade(),
+        cts: None,
+        rts: None,
+    };
+    let edge = uarte::Uarte::new(
+        board.UARTE1,
+        edge_pins,
+        Parity::EXCLUDED,
+        Baudrate::BAUD115200,
+    );
+
+    let usb_buffers = singleton!(: Buffers<1, QUEUE_LEN> = Buffers::new()).unwrap();
+    let edge_buffers = singleton!(: Buffers<1, QUEUE_LEN> = Buffers::new()).unwrap();
+    let mut usb = UartePort::with_buffers(usb, FlowControl::Disabled, usb_buffers);
+    let mut edge = UartePort::with_buffers(edge, FlowControl::Disabled, edge_buffers);
+    usb.enable_rx_interrupt();
+    edge.enable_rx_interrupt();
+
+    let mut timer = Timer::periodic(board.TIMER0);
+    let mut to_edge = Stats::default();
+    let mut to_usb = Stats::default();
+
+    rprintln!("Bridging the USB serial port and rings 0 (TX) and 1 (RX)");
+    // Report once a second, the timer ticks at 1MHz
+    timer.start(1_000_000u32);
+    loop {
+        forward(&mut usb, &mut edge, &mut to_edge);
+        forward(&mut edge, &mut usb, &mut to_usb);
+
+        if timer.wait().is_ok() && (to_edge.bytes != 0 || to_usb.bytes != 0) {
+            report("to the edge", &to_edge);
+            report("to USB", &to_usb);
+            to_edge = Stats::default();
+            to_usb = Stats::default();
+        }
+    }
+}
@@ -0,0 +1,156 @@
+#![no_main]
+#![no_std]
+
+use core::fmt::Write;
+use cortex_m_rt::entry;
+use embedded_hal::digital::v2::InputPin;
+use heapless::String;
+use panic_rtt_target as _;
+use rtt_target::rtt_init_print;
+
+use discovery_logic::clock::{TimeOfDay, WallClock};
+use discovery_support::clocks::init_clocks;
+use discovery_support::display::init_display;
+use discovery_support::monotonic::{init_monotonic, Instant};
+use discovery_support::scroll::scroll_text_nonblocking;
+use microbit::{hal::Timer, pac::TIMER0};
+
+#[cfg(feature = "v1")]
+use microbit::hal::uart::{self, Baudrate, Parity};
+
+#[cfg(feature = "v2")]
+use microbit::hal::uarte::{self, Baudrate, Parity};
+
+#[cfg(feature = "v2")]
+use discovery_support::serial::{FlowControl, UartePort};
+
+#[path = "../src/line_reader.rs"]
+mod line_reader;
+use line_reader::LineReader;
+
+#[path = "../src/shell.rs"]
+#[allow(dead_code)]
+mod shell;
+use shell::{Args, Command, Error, Shell};
+
+/// Below this, the clock hasn't been running for long enough to tell how far
+/// off it is, when it can only be set to the second.
+const MIN_DRIFT_MS: u64 = 60 * 60 * 1_000;
+
+fn now_ms() -> u64 {
+    Instant::now().as_micros() / 1_000
+}
+
+fn time(clock: &mut WallClock, mut args: Args, out: &mut dyn Write) -> Result<(), Error> {
+    match args.word() {
+        Err(Error::MissingArgument) => {}
+        Ok("set") => {
+            let time = TimeOfDay::parse(args.word()?).ok_or(Error::InvalidArgument)?;
+            args.end()?;
+            if let Some(drift) = clock.set(now_ms(), time) {
+                write!(out, "it was {} ms off", drift.error_ms)?;
+                if drift.over_ms >= MIN_DRIFT_MS {
+                    write!(
+                        out,
+                        " after {} minutes, {} ppm, try `time trim {}`",
+                        drift.over_ms / 60_000,
+                        drift.ppm(),
+                        clock.trim_ppm() - drift.ppm()
+                    )?;
+                }
+                write!(out, "\r\n")?;
+            }
+        }
+        Ok("trim") => {
+            let ppm = args.parse()?;
+            args.end()?;
+            clock.set_trim_ppm(now_ms(), ppm);
+        }
+        _ => return Err(Error::InvalidArgument),
+    }
+    write!(
+        out,
+        "{}, trimmed by {} ppm\r\n",
+        clock.now(now_ms()),
+        clock.trim_ppm()
+    )?;
+    Ok(())
+}
+
+const COMMANDS: &[Command<WallClock>] = &[Command {
+    name: "time",
+    usage: "time [set HH:MM[:SS] | trim <ppm>]",
+    run: time,
+}];
+
+/// Scroll the hours and minutes across the display.
+fn show_time(clock: &WallClock, timer: &mut Timer<TIMER0>) {
+    let now = clock.now(now_ms());
+    let mut text: String<5> = String::new();
+    write!(text, "{:02}:{:02}", now.hours(), now.minutes()).unwrap();
+    scroll_text_nonblocking(timer, &text);
+}
+
+#[entry]
+fn main() -> ! {
+    rtt_init_print!();
+    let board = microbit::Board::take().unwrap();
+
+    #[cfg(feature = "v1")]
+    let mut serial = {
+        uart::Uart::new(
+            board.UART0,
+            board.uart.into(),
+            Parity::EXCLUDED,
+            Baudrate::BAUD115200,
+        )
+    };
+
+    #[cfg(feature = "v2")]
+    let mut serial = {
+        let serial = uarte::Uarte::new(
+            board.UARTE0,
+            board.uart.into(),
+            Parity::EXCLUDED,
+            Baudrate::BAUD115200,
+        );
+        let mut serial = UartePort::new(serial, FlowControl::Disabled);
+        serial.enable_rx_interrupt();
+        serial
+    };
+
+    // The time of day comes from the RTC, which counts a clock divided down
+    // from the crystal
+    let clocks = init_clocks(board.CLOCK);
+    init_monotonic(&clocks);
+    init_display(board.TIMER1, board.display_pins);
+    let mut timer = Timer::new(board.TIMER0);
+    let button_a = board.buttons.button_a;
+
+    let mut clock = WallClock::new();
+    let shell = Shell::new(COMMANDS);
+    let mut reader: LineReader<32> = LineReader::new();
+    write!(
+        serial,
+        "Set the time with `time set HH:MM`, press A to see it\r\n> "
+    )
+    .unwrap();
+
+    let mut pressed = false;
+    loop {
+        match reader.poll_line(&mut serial) {
+            Ok(Some(line)) => {
+                shell.run(&mut clock, line, &mut serial).unwrap();
+                write!(serial, "> ").unwrap();
+            }
+            Ok(None) => {}
+            Err(error) => write!(serial, "\r\nserial error: {:?}\r\n> ", error).unwrap(),
+        }
+
+        let now_pressed = button_a.is_low().unwrap();
+        if now_pressed && !pressed {
+            show_time(&clock, &mut timer);
+        }
+        pressed = now_pressed;
+    }
+}
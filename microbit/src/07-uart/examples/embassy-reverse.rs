@@ -0,0 +1,119 @@
+//! The string reverser once more, written with `async` and Embassy. One task
+//! serves the lines that come in over the serial port while another one
+//! blinks an LED, each written as if it had the CPU to itself, and the
+//! executor puts the CPU to sleep whenever both are waiting. `embassy-nrf` has
+//! its own drivers and its own peripherals, so this program doesn't use the
+//! `microbit` crate or `discovery-support`.
+//!
+//! The replies are the same as those of the solution without the `async`, so
+//! `serial-client` can check them.
+
+#![no_main]
+#![no_std]
+
+use core::fmt::Write;
+use core::str;
+use embassy_executor::Spawner;
+use embassy_nrf::gpio::{Level, Output, OutputDrive};
+use embassy_nrf::uarte::{self, UarteRxWithIdle, UarteTx};
+use embassy_nrf::{bind_interrupts, peripherals};
+use embassy_time::Timer;
+use heapless::{String, Vec};
+use panic_rtt_target as _;
+use rtt_target::{rprintln, rtt_init_print};
+
+bind_interrupts!(struct Irqs {
+    UARTE0 => uarte::InterruptHandler<peripherals::UARTE0>;
+});
+
+/// How many bytes of a line are kept, like the buffer of the solution.
+const LINE_LEN: usize = 32;
+
+/// Send back `line` the other way round, after the carriage return that ended
+/// it and followed by `"\n\r"`.
+async fn reply(tx: &mut UarteTx<'static>, line: &[u8]) -> Result<(), uarte::Error> {
+    let line = match str::from_utf8(line) {
+        Ok(line) => line,
+        Err(_) => return tx.write(b"error: not valid UTF-8\r\n").await,
+    };
+    // The carriage return and the `"\n\r"` on top of the line always fit
+    let mut reversed: String<{ LINE_LEN + 3 }> = String::new();
+    reversed.push('\r').ok();
+    for c in line.chars().rev() {
+        reversed.push(c).ok();
+    }
+    reversed.push_str("\n\r").ok();
+    // The UARTE sends from RAM, and so does `write`, which copies whatever
+    // isn't there yet
+    tx.write(reversed.as_bytes()).await
+}
+
+#[embassy_executor::task]
+async fn serve(mut tx: UarteTx<'static>, mut rx: UarteRxWithIdle<'static>) {
+    let mut line: Vec<u8, LINE_LEN> = Vec::new();
+    let mut full = false;
+    let mut chunk = [0; 16];
+    loop {
+        // Returns once the chunk is full, or the line has been quiet for as
+        // long as two bytes take, so that typing gets an answer right away
+        let count = match rx.read_until_idle(&mut chunk).await {
+            Ok(count) => count,
+            Err(error) => {
+                // That line is lost, but the server carries on with the next one
+                let mut report: String<48> = String::new();
+                write!(report, "error: could not receive ({:?})\r\n", error).ok();
+                tx.write(report.as_bytes()).await.ok();
+                line.clear();
+                full = false;
+                continue;
+            }
+        };
+        for &byte in &chunk[..count] {
+            if byte != b'\r' {
+                if line.push(byte).is_err() {
+                    full = true;
+                }
+                continue;
+            }
+            let served = if full {
+                tx.write(b"error: buffer full\r\n").await
+            } else {
+                reply(&mut tx, &line).await
+            };
+            if let Err(error) = served {
+                rprintln!("could not send: {:?}", error);
+            }
+            line.clear();
+            full = false;
+        }
+    }
+}
+
+/// Blink the top left LED, to show that the server isn't all the CPU does.
+#[embassy_executor::task]
+async fn heartbeat(mut row: Output<'static>, mut col: Output<'static>) {
+    col.set_low();
+    loop {
+        row.set_high();
+        Timer::after_millis(100).await;
+        row.set_low();
+        Timer::after_millis(900).await;
+    }
+}
+
+#[embassy_executor::main]
+async fn main(spawner: Spawner) {
+    rtt_init_print!();
+    let p = embassy_nrf::init(Default::default());
+
+    // The pins that go to the interface chip, at the default 115200 baud
+    let serial = uarte::Uarte::new(p.UARTE0, p.P1_08, p.P0_06, Irqs, uarte::Config::default());
+    // Telling when the line has gone quiet takes a timer and two PPI channels
+    let (tx, rx) = serial.split_with_idle(p.TIMER0, p.PPI_CH0, p.PPI_CH1);
+
+    let row = Output::new(p.P0_21, Level::Low, OutputDrive::Standard);
+    let col = Output::new(p.P0_28, Level::High, OutputDrive::Standard);
+
+    spawner.spawn(serve(tx, rx).unwrap());
+    spawner.spawn(heartbeat(row, col).unwrap());
+}
@@ -0,0 +1,50 @@
+#![no_main]
+#![no_std]
+
+use core::str;
+use cortex_m_rt::entry;
+use panic_rtt_target as _;
+use rtt_target::{rprintln, rtt_init_print};
+
+use microbit::{
+    hal::ppi,
+    hal::uarte,
+    hal::uarte::{Baudrate, Parity},
+    hal::Timer,
+    pac,
+};
+
+use discovery_support::serial::{FlowControl, IdleTimeout, UartePort};
+
+#[entry]
+fn main() -> ! {
+    rtt_init_print!();
+    let board = microbit::Board::take().unwrap();
+
+    let serial = uarte::Uarte::new(
+        board.UARTE0,
+        board.uart.into(),
+        Parity::EXCLUDED,
+        Baudrate::BAUD115200,
+    );
+    let mut serial = UartePort::new(serial, FlowControl::Disabled);
+
+    // `Board` doesn't hand out the PPI, so nobody else can be using it
+    let ppi = ppi::Parts::new(unsafe { pac::Peripherals::steal() }.PPI);
+    // At 115200 baud a byte takes less than 100us, so 10ms without one means
+    // whoever was sending is done
+    let mut idle = IdleTimeout::new(Timer::new(board.TIMER0), ppi.ppi0, ppi.ppi1, 10_000);
+
+    let mut buffer = [0; 64];
+    loop {
+        let count = serial.read_until_idle(&mut buffer, &mut idle).unwrap();
+        if count == 0 {
+            continue;
+        }
+
+        match str::from_utf8(&buffer[..count]) {
+            Ok(message) => rprintln!("received {} bytes: {:?}", count, message),
+            Err(_) => rprintln!("received {} bytes: {:?}", count, &buffer[..count]),
+        }
+    }
+}
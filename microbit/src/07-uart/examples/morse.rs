@@ -0,0 +1,200 @@
+#![no_main]
+#![no_std]
+
+use core::fmt::Write;
+use cortex_m_rt::entry;
+use embedded_hal::blocking::delay::DelayMs;
+use embedded_hal::digital::v2::InputPin;
+use panic_rtt_target as _;
+use rtt_target::rtt_init_print;
+
+use discovery_logic::morse::{self, Decoded, Decoder};
+use discovery_support::display::{display_leds, init_display};
+use discovery_support::sound::Speaker;
+use microbit::{
+    board::Buttons,
+    hal::uarte,
+    hal::uarte::{Baudrate, Parity},
+    hal::Timer,
+    pac::{TIMER0, TIMER2},
+};
+
+use discovery_support::serial::{FlowControl, UartePort};
+
+#[path = "../src/line_reader.rs"]
+mod line_reader;
+use line_reader::LineReader;
+
+#[path = "../src/shell.rs"]
+mod shell;
+use shell::{Args, Command, Error, Shell};
+
+/// The pitch of the beeps.
+const TONE_HZ: u32 = 700;
+const DEFAULT_WPM: u32 = 12;
+/// Slower than 1 word per minute nobody has the patience for, and faster
+/// than 40 only experts can follow.
+const WPM_RANGE: core::ops::RangeInclusive<u32> = 1..=40;
+/// Just the LED in the middle of the display.
+const CENTER: [[u8; 5]; 5] = [
+    [0, 0, 0, 0, 0],
+    [0, 0, 0, 0, 0],
+    [0, 0, 1, 0, 0],
+    [0, 0, 0, 0, 0],
+    [0, 0, 0, 0, 0],
+];
+
+struct Context {
+    speaker: Speaker<TIMER0>,
+    timer: Timer<TIMER2>,
+    buttons: Buttons,
+    wpm: u32,
+}
+
+impl Context {
+    /// Light the LED and beep, or stop doing so.
+    fn key(&mut self, down: bool) {
+        if down {
+            display_leds(CENTER);
+            self.speaker.start_tone(TONE_HZ);
+        } else {
+            display_leds([[0; 5]; 5]);
+            self.speaker.stop();
+        }
+    }
+}
+
+fn send(context: &mut Context, args: Args, out: &mut dyn Write) -> Result<(), Error> {
+    let unit = morse::unit_ms(context.wpm);
+    for (i, word) in args.rest().enumerate() {
+        if i > 0 {
+            // Seven units between words, three of which the last letter has
+            // already waited
+            context.timer.delay_ms(4 * unit);
+        }
+        for c in word.chars() {
+            let code = match morse::encode(c) {
+                Some(code) => code,
+                None => {
+                    write!(out, "skipping {:?}, it has no Morse code\r\n", c)?;
+                    continue;
+                }
+            };
+            for symbol in code.chars() {
+                context.key(true);
+                context
+                    .timer
+                    .delay_ms(if symbol == '.' { unit } else { 3 * unit });
+                context.key(false);
+                context.timer.delay_ms(unit);
+            }
+            // Three units between letters, one of which the last symbol has
+            // already waited
+            context.timer.delay_ms(2 * unit);
+        }
+    }
+    Ok(())
+}
+
+fn wpm(context: &mut Context, mut args: Args, out: &mut dyn Write) -> Result<(), Error> {
+    match args.parse() {
+        Ok(wpm) if WPM_RANGE.contains(&wpm) => context.wpm = wpm,
+        Ok(_) => return Err(Error::InvalidArgument),
+        Err(Error::MissingArgument) => {}
+        Err(error) => return Err(error),
+    }
+    args.end()?;
+    write!(out, "{} words per minute\r\n", context.wpm)?;
+    Ok(())
+}
+
+fn listen(context: &mut Context, args: Args, out: &mut dyn Write) -> Result<(), Error> {
+    args.end()?;
+    write!(out, "key with button A, press B to stop\r\n")?;
+
+    let mut decoder = Decoder::new(context.wpm);
+    let mut was_down = false;
+    // How long the key has been held down, or up, in milliseconds
+    let mut held = 0;
+    while context.buttons.button_b.is_high().unwrap() {
+        let down = context.buttons.button_a.is_low().unwrap();
+        if down != was_down {
+            context.key(down);
+            if !down {
+                decoder.press(held);
+            }
+            was_down = down;
+            held = 0;
+        }
+        if !down {
+            match decoder.pause(held) {
+                Some(Decoded::Char(c)) => write!(out, "{}", c)?,
+                Some(Decoded::Space) => write!(out, " ")?,
+                None => {}
+            }
+        }
+
+        context.timer.delay_ms(1_u32);
+        held += 1;
+    }
+    context.key(false);
+    write!(out, "\r\n")?;
+    Ok(())
+}
+
+const COMMANDS: &[Command<Context>] = &[
+    Command {
+        name: "send",
+        usage: "send <text>",
+        run: send,
+    },
+    Command {
+        name: "wpm",
+        usage: "wpm [<words per minute>]",
+        run: wpm,
+    },
+    Command {
+        name: "listen",
+        usage: "listen",
+        run: listen,
+    },
+];
+
+#[entry]
+fn main() -> ! {
+    rtt_init_print!();
+    let board = microbit::Board::take().unwrap();
+
+    let serial = uarte::Uarte::new(
+        board.UARTE0,
+        board.uart.into(),
+        Parity::EXCLUDED,
+        Baudrate::BAUD115200,
+    );
+    let mut serial = UartePort::new(serial, FlowControl::Disabled);
+    // `read_line` sleeps until a byte arrives, which takes the interrupt
+    serial.enable_rx_interrupt();
+
+    init_display(board.TIMER1, board.display_pins);
+    let mut context = Context {
+        speaker: Speaker::new(board.PWM0, board.speaker_pin, board.TIMER0),
+        timer: Timer::new(board.TIMER2),
+        buttons: board.buttons,
+        wpm: DEFAULT_WPM,
+    };
+
+    let shell = Shell::new(COMMANDS);
+    let mut reader: LineReader<64> = LineReader::new();
+
+    loop {
+        write!(serial, "> ").unwrap();
+        let line = match reader.read_line(&mut serial) {
+            Ok(line) => line,
+            Err(error) => {
+                write!(serial, "\r\nserial error: {:?}\r\n", error).unwrap();
+                continue;
+            }
+        };
+        shell.run(&mut context, line, &mut serial).unwrap();
+    }
+}
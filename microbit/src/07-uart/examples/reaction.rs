@@ -0,0 +1,122 @@
+#![no_main]
+#![no_std]
+
+use core::fmt::Write;
+use cortex_m_rt::entry;
+use embedded_hal::digital::v2::InputPin;
+use panic_rtt_target as _;
+use rtt_target::rtt_init_print;
+
+use discovery_support::clocks::init_clocks;
+use discovery_support::display::{clear_display, display_image, display_leds, init_display};
+use discovery_support::image::Image5x5;
+use discovery_support::monotonic::{init_monotonic, Duration, Instant};
+use discovery_support::random::Random;
+use discovery_support::settings::{keys, Settings};
+
+#[cfg(feature = "v1")]
+use microbit::hal::uart::{self, Baudrate, Parity};
+
+#[cfg(feature = "v2")]
+use microbit::hal::uarte::{self, Baudrate, Parity};
+
+/// How long to wait before lighting up, at random, in milliseconds.
+const MIN_WAIT_MS: u32 = 1_000;
+const MAX_WAIT_MS: u32 = 4_000;
+/// How long the result stays up before the next round.
+const PAUSE: Duration = Duration::from_secs(2);
+
+/// Just the LED in the middle of the display.
+const CENTER: [[u8; 5]; 5] = [
+    [0, 0, 0, 0, 0],
+    [0, 0, 0, 0, 0],
+    [0, 0, 1, 0, 0],
+    [0, 0, 0, 0, 0],
+    [0, 0, 0, 0, 0],
+];
+const CROSS: Image5x5 = Image5x5::from_rows([0b10001, 0b01010, 0b00100, 0b01010, 0b10001]);
+const TICK: Image5x5 = Image5x5::from_rows([0b00000, 0b00001, 0b00010, 0b10100, 0b01000]);
+
+/// Wait until `until`, or until `pressed` says yes. Returns whether it did.
+fn wait_until(until: Instant, mut pressed: impl FnMut() -> bool) -> bool {
+    while Instant::now() < until {
+        if pressed() {
+            return true;
+        }
+    }
+    false
+}
+
+#[entry]
+fn main() -> ! {
+    rtt_init_print!();
+    let board = microbit::Board::take().unwrap();
+
+    #[cfg(feature = "v1")]
+    let mut serial = uart::Uart::new(
+        board.UART0,
+        board.uart.into(),
+        Parity::EXCLUDED,
+        Baudrate::BAUD115200,
+    );
+
+    #[cfg(feature = "v2")]
+    let mut serial = uarte::Uarte::new(
+        board.UARTE0,
+        board.uart.into(),
+        Parity::EXCLUDED,
+        Baudrate::BAUD115200,
+    );
+
+    // Reaction times are measured with the RTC, which is only as accurate as
+    // the clock it counts
+    let clocks = init_clocks(board.CLOCK);
+    init_monotonic(&clocks);
+    init_display(board.TIMER1, board.display_pins);
+    let mut random = Random::new(board.RNG);
+    let button_a = board.buttons.button_a;
+    let mut pressed = || button_a.is_low().unwrap();
+
+    let mut settings = Settings::take().unwrap();
+    let mut best_ms = match settings.get(keys::REACTION_TIME).as_deref() {
+        Some(&[a, b, c, d]) => Some(u32::from_le_bytes([a, b, c, d])),
+        _ => None,
+    };
+    if let Some(best_ms) = best_ms {
+        write!(serial, "Best time so far: {} ms\r\n", best_ms).unwrap();
+    }
+
+    loop {
+        // Let go of the button first, or it counts as pressed right away
+        while pressed() {}
+        clear_display();
+        write!(serial, "Get ready...\r\n").unwrap();
+
+        let wait = Duration::from_millis(random.between(MIN_WAIT_MS, MAX_WAIT_MS).into());
+        if wait_until(Instant::now() + wait, &mut pressed) {
+            write!(serial, "Too soon!\r\n").unwrap();
+            display_image(&CROSS);
+            wait_until(Instant::now() + PAUSE, || false);
+            continue;
+        }
+
+        display_leds(CENTER);
+        let lit = Instant::now();
+        while !pressed() {}
+        let time_ms = lit.elapsed().as_millis() as u32;
+
+        write!(serial, "{} ms", time_ms).unwrap();
+        if best_ms.is_none_or(|best_ms| time_ms < best_ms) {
+            write!(serial, ", a new best time!").unwrap();
+            settings
+                .set(keys::REACTION_TIME, &time_ms.to_le_bytes())
+                .unwrap();
+            best_ms = Some(time_ms);
+            display_image(&TICK);
+        } else {
+            clear_display();
+        }
+        write!(serial, "\r\n").unwrap();
+        wait_until(Instant::now() + PAUSE, || false);
+    }
+}
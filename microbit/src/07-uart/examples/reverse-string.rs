@@ -0,0 +1,112 @@
+#![no_main]
+#![no_std]
+
+use core::fmt::{self, Write};
+use core::str;
+use cortex_m_rt::entry;
+use embedded_hal::serial;
+use heapless::Vec;
+use panic_rtt_target as _;
+use rtt_target::rtt_init_print;
+
+#[cfg(feature = "v1")]
+use microbit::{
+    hal::uart,
+    hal::uart::{Baudrate, Parity},
+};
+
+#[cfg(feature = "v2")]
+use microbit::{
+    hal::uarte,
+    hal::uarte::{Baudrate, Parity},
+};
+
+#[cfg(feature = "v2")]
+use discovery_support::serial::{FlowControl, UartePort};
+
+#[path = "../src/error.rs"]
+mod error;
+use error::Error;
+
+/// Receive bytes into `buffer` until ENTER is pressed.
+///
+/// When the buffer is full, the rest of the line is still read, just not
+/// kept, so that it isn't taken for the next line.
+fn read_line<S, const N: usize>(serial: &mut S, buffer: &mut Vec<u8, N>) -> Result<(), Error>
+where
+    S: serial::Read<u8>,
+    Error: From<S::Error>,
+{
+    let mut full = false;
+    loop {
+        let byte = nb::block!(serial.read())?;
+        if byte == b'\r' {
+            return if full { Err(Error::BufferFull) } else { Ok(()) };
+        }
+        if buffer.push(byte).is_err() {
+            full = true;
+        }
+    }
+}
+
+/// Send back `line` the other way round, one character at a time.
+///
+/// The carriage return that ended the line comes first, being its last byte,
+/// and then `"\n\r"`: the same reply as the solution without any error
+/// handling, which is what the `serial-client` tool checks for.
+fn reply<W: Write>(serial: &mut W, line: &[u8]) -> Result<(), Error> {
+    let line = str::from_utf8(line)?;
+    serial.write_char('\r')?;
+    for c in line.chars().rev() {
+        serial.write_char(c)?;
+    }
+    serial.write_str("\n\r")?;
+    Ok(())
+}
+
+/// Tell the client what went wrong with its line.
+fn report<W: Write>(serial: &mut W, error: &Error) -> fmt::Result {
+    write!(serial, "error: {}\r\n", error)
+}
+
+#[entry]
+fn main() -> ! {
+    rtt_init_print!();
+    let board = microbit::Board::take().unwrap();
+
+    #[cfg(feature = "v1")]
+    let mut serial = {
+        uart::Uart::new(
+            board.UART0,
+            board.uart.into(),
+            Parity::EXCLUDED,
+            Baudrate::BAUD115200,
+        )
+    };
+
+    #[cfg(feature = "v2")]
+    let mut serial = {
+        let serial = uarte::Uarte::new(
+            board.UARTE0,
+            board.uart.into(),
+            Parity::EXCLUDED,
+            Baudrate::BAUD115200,
+        );
+        UartePort::new(serial, FlowControl::Disabled)
+    };
+
+    // A buffer with 32 bytes of capacity
+    let mut buffer: Vec<u8, 32> = Vec::new();
+
+    loop {
+        buffer.clear();
+
+        let served = read_line(&mut serial, &mut buffer).and_then(|()| reply(&mut serial, &buffer));
+        if let Err(error) = served {
+            // That line is lost, but the server carries on with the next one.
+            // If not even the report gets through, there's nobody left to
+            // tell.
+            report(&mut serial, &error).ok();
+        }
+    }
+}
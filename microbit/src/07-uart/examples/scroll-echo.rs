@@ -0,0 +1,86 @@
+#![no_main]
+#![no_std]
+
+use core::fmt::Write;
+use cortex_m_rt::entry;
+use discovery_support::scroll::scroll_text;
+use panic_rtt_target as _;
+use rtt_target::rtt_init_print;
+
+use microbit::{display::blocking::Display, hal::Timer};
+
+#[cfg(feature = "v1")]
+use microbit::{
+    hal::uart,
+    hal::uart::{Baudrate, Parity},
+};
+
+#[cfg(feature = "v2")]
+use microbit::{
+    hal::uarte,
+    hal::uarte::{Baudrate, Parity},
+};
+
+#[cfg(feature = "v2")]
+use discovery_support::serial::{Buffers, FlowControl, UartePort};
+
+#[path = "../src/line_reader.rs"]
+mod line_reader;
+use line_reader::LineReader;
+
+#[entry]
+fn main() -> ! {
+    rtt_init_print!();
+    let board = microbit::Board::take().unwrap();
+
+    #[cfg(feature = "v1")]
+    let mut serial = {
+        uart::Uart::new(
+            board.UART0,
+            board.uart.into(),
+            Parity::EXCLUDED,
+            Baudrate::BAUD115200,
+        )
+    };
+
+    #[cfg(feature = "v2")]
+    let mut serial = {
+        let serial = uarte::Uarte::new(
+            board.UARTE0,
+            board.uart.into(),
+            Parity::EXCLUDED,
+            Baudrate::BAUD115200,
+        );
+        // Scrolling a line takes seconds, long enough for pasted text to
+        // bring in hundreds of bytes. Room for 1023 of them, rather than the
+        // default 255.
+        let buffers = cortex_m::singleton!(: Buffers<1, 1024> = Buffers::new()).unwrap();
+        let mut serial = UartePort::with_buffers(serial, FlowControl::Disabled, buffers);
+        // Keep receiving while the text is scrolling
+        serial.enable_rx_interrupt();
+        serial
+    };
+
+    let mut timer = Timer::new(board.TIMER0);
+    let mut display = Display::new(board.display_pins);
+
+    // Lines of up to 32 characters, the default
+    let mut reader: LineReader = LineReader::new();
+
+    loop {
+        // Echoes every character back as it is typed, and lets backspace take
+        // back mistakes
+        let line = match reader.read_line(&mut serial) {
+            Ok(line) => line,
+            Err(error) => {
+                write!(serial, "\r\nserial error: {:?}\r\n", error).unwrap();
+                continue;
+            }
+        };
+
+        // Show the whole line on the LED matrix as well. Nothing is read from
+        // the serial port while the text is scrolling, on the micro:bit v2 the
+        // UARTE interrupt keeps what is typed in the meantime for later.
+        scroll_text(&mut display, &mut timer, line);
+    }
+}
@@ -0,0 +1,265 @@
+#![no_main]
+#![no_std]
+
+use core::fmt::Write;
+use core::str;
+use cortex_m_rt::entry;
+use discovery_support::display::{display_leds, init_display};
+use discovery_support::scroll::scroll_text_nonblocking;
+use discovery_support::settings::{keys, Settings, MAX_VALUE_LEN};
+use heapless::String;
+use panic_rtt_target as _;
+use rtt_target::rtt_init_print;
+
+use microbit::{
+    hal::{Temp, Timer},
+    pac::TIMER0,
+};
+
+#[cfg(feature = "v1")]
+use microbit::{
+    hal::uart,
+    hal::uart::{Baudrate, Parity},
+};
+
+#[cfg(feature = "v2")]
+use microbit::{
+    hal::uarte,
+    hal::uarte::{Baudrate, Parity},
+};
+
+#[cfg(feature = "v2")]
+use discovery_support::serial::{FlowControl, SerialConfig, SerialStats, UartePort};
+
+#[path = "../src/line_reader.rs"]
+mod line_reader;
+use line_reader::LineReader;
+
+#[path = "../src/shell.rs"]
+mod shell;
+use shell::{Args, Command, Error, Shell};
+
+/// Whether the last command is kept in flash, so that the up arrow still
+/// finds it after a reset. Turn it off to spare the flash: every command that
+/// differs from the one before is written to it, and a page of flash only
+/// survives so many erases.
+const KEEP_LAST_COMMAND: bool = true;
+/// The longest line the shell takes.
+const LINE_LEN: usize = 64;
+
+/// Everything the commands get to play with.
+struct Context {
+    leds: [[u8; 5]; 5],
+    timer: Timer<TIMER0>,
+    temp: Temp,
+    /// New line settings, applied once the command's output has been sent
+    #[cfg(feature = "v2")]
+    reconfigure: Option<SerialConfig>,
+    /// What the port counted, as of the start of the command
+    #[cfg(feature = "v2")]
+    serial_stats: SerialStats,
+}
+
+fn led(context: &mut Context, mut args: Args, _out: &mut dyn Write) -> Result<(), Error> {
+    let lit = match args.word()? {
+        "on" => 1,
+        "off" => 0,
+        _ => return Err(Error::InvalidArgument),
+    };
+    let row: usize = args.parse()?;
+    let col: usize = args.parse()?;
+    args.end()?;
+
+    if row > 4 || col > 4 {
+        return Err(Error::InvalidArgument);
+    }
+    context.leds[row][col] = lit;
+    display_leds(context.leds);
+    Ok(())
+}
+
+fn scroll(context: &mut Context, args: Args, _out: &mut dyn Write) -> Result<(), Error> {
+    // The words with one space between them, however many were typed. They
+    // came in a line of at most `LINE_LEN`, so they fit.
+    let mut text: String<LINE_LEN> = String::new();
+    for word in args.rest() {
+        if !text.is_empty() {
+            let _ = text.push(' ');
+        }
+        let _ = text.push_str(word);
+    }
+    scroll_text_nonblocking(&mut context.timer, &text);
+    // Put back whatever the LEDs showed before
+    display_leds(context.leds);
+    Ok(())
+}
+
+fn temp(context: &mut Context, args: Args, out: &mut dyn Write) -> Result<(), Error> {
+    args.end()?;
+    let celsius = context.temp.measure();
+    write!(out, "{} C\r\n", celsius)?;
+    Ok(())
+}
+
+#[cfg(feature = "v2")]
+fn baud(context: &mut Context, mut args: Args, out: &mut dyn Write) -> Result<(), Error> {
+    let bps = args.parse()?;
+    let baudrate = SerialConfig::baudrate_from_bps(bps).ok_or(Error::InvalidArgument)?;
+    let parity = match args.word() {
+        Ok("none") | Err(Error::MissingArgument) => uarte::Parity::EXCLUDED,
+        Ok("even") => uarte::Parity::INCLUDED,
+        _ => return Err(Error::InvalidArgument),
+    };
+    args.end()?;
+
+    write!(
+        out,
+        "switching to {} baud, change your terminal to match\r\n",
+        bps
+    )?;
+    context.reconfigure = Some(SerialConfig { baudrate, parity });
+    Ok(())
+}
+
+#[cfg(feature = "v2")]
+fn stats(context: &mut Context, args: Args, out: &mut dyn Write) -> Result<(), Error> {
+    args.end()?;
+    let stats = context.serial_stats;
+    write!(out, "overruns: {}\r\n", stats.overruns)?;
+    write!(out, "parity errors: {}\r\n", stats.parity_errors)?;
+    write!(out, "framing errors: {}\r\n", stats.framing_errors)?;
+    write!(out, "breaks: {}\r\n", stats.breaks)?;
+    write!(out, "dropped: {}\r\n", stats.dropped)?;
+    Ok(())
+}
+
+fn info(_context: &mut Context, args: Args, out: &mut dyn Write) -> Result<(), Error> {
+    args.end()?;
+    write!(out, "{}\r\n", discovery_support::build_info!())?;
+    Ok(())
+}
+
+const COMMANDS: &[Command<Context>] = &[
+    Command {
+        name: "led",
+        usage: "led on|off <row> <col>",
+        run: led,
+    },
+    Command {
+        name: "scroll",
+        usage: "scroll <text>",
+        run: scroll,
+    },
+    Command {
+        name: "temp",
+        usage: "temp",
+        run: temp,
+    },
+    Command {
+        name: "info",
+        usage: "info",
+        run: info,
+    },
+    #[cfg(feature = "v2")]
+    Command {
+        name: "baud",
+        usage: "baud <rate> [none|even]",
+        run: baud,
+    },
+    #[cfg(feature = "v2")]
+    Command {
+        name: "stats",
+        usage: "stats",
+        run: stats,
+    },
+];
+
+#[entry]
+fn main() -> ! {
+    rtt_init_print!();
+    let board = microbit::Board::take().unwrap();
+
+    #[cfg(feature = "v1")]
+    let mut serial = {
+        uart::Uart::new(
+            board.UART0,
+            board.uart.into(),
+            Parity::EXCLUDED,
+            Baudrate::BAUD115200,
+        )
+    };
+
+    #[cfg(feature = "v2")]
+    let mut serial = {
+        let serial = uarte::Uarte::new(
+            board.UARTE0,
+            board.uart.into(),
+            Parity::EXCLUDED,
+            Baudrate::BAUD115200,
+        );
+        let mut serial = UartePort::new(serial, FlowControl::Disabled);
+        serial.enable_rx_interrupt();
+        serial
+    };
+
+    init_display(board.TIMER1, board.display_pins);
+    let mut context = Context {
+        leds: [[0; 5]; 5],
+        timer: Timer::new(board.TIMER0),
+        temp: Temp::new(board.TEMP),
+        #[cfg(feature = "v2")]
+        reconfigure: None,
+        #[cfg(feature = "v2")]
+        serial_stats: SerialStats::default(),
+    };
+
+    let shell = Shell::new(COMMANDS);
+    let mut reader: LineReader<LINE_LEN> = LineReader::new();
+
+    let mut settings = Settings::take().unwrap();
+    if KEEP_LAST_COMMAND {
+        if let Some(last) = settings.get(keys::SHELL_LAST_COMMAND) {
+            // Anything that isn't text didn't come from us
+            if let Ok(last) = str::from_utf8(&last) {
+                reader.remember(last);
+            }
+        }
+    }
+
+    // Whoever plugs the board in gets told what's on it
+    write!(serial, "{}\r\n", discovery_support::build_info!()).unwrap();
+    loop {
+        write!(serial, "> ").unwrap();
+        let line = match reader.read_line(&mut serial) {
+            Ok(line) => line,
+            Err(error) => {
+                write!(serial, "\r\nserial error: {:?}\r\n", error).unwrap();
+                continue;
+            }
+        };
+        // Longer commands don't fit into a setting, and can't be kept
+        if KEEP_LAST_COMMAND && !line.is_empty() && line.len() <= MAX_VALUE_LEN {
+            settings
+                .set(keys::SHELL_LAST_COMMAND, line.as_bytes())
+                .unwrap();
+        }
+        if line == "history" {
+            // Only the reader knows the history, so it's not one of the
+            // commands
+            for line in reader.history() {
+                write!(serial, "{}\r\n", line).unwrap();
+            }
+            continue;
+        }
+        #[cfg(feature = "v2")]
+        {
+            context.serial_stats = serial.stats();
+        }
+        shell.run(&mut context, line, &mut serial).unwrap();
+
+        #[cfg(feature = "v2")]
+        if let Some(config) = context.reconfigure.take() {
+            serial.reconfigure(config);
+        }
+    }
+}
@@ -0,0 +1,97 @@
+#![no_main]
+#![no_std]
+
+use core::fmt::Write;
+use cortex_m_rt::entry;
+use embedded_hal::blocking::delay::DelayUs;
+use heapless::String;
+use panic_rtt_target as _;
+use rtt_target::{rprintln, rtt_init_print};
+
+use microbit::hal::{prelude::*, Temp, Timer};
+
+use discovery_logic::packet::{types, Packet};
+use discovery_support::half_duplex::{Error, HalfDuplex};
+use discovery_support::random::Random;
+
+/// How long a request waits for its response.
+const RESPONSE_TIMEOUT_US: u32 = 100_000;
+/// How long to listen for requests before looking at the button again.
+const LISTEN_US: u32 = 10_000;
+/// How often to ask before giving up.
+const ATTEMPTS: usize = 5;
+
+#[entry]
+fn main() -> ! {
+    rtt_init_print!();
+    let board = microbit::Board::take().unwrap();
+
+    // Ring 0 of this board goes to ring 0 of the other one
+    let pin = board.pins.p0_02.degrade();
+    let mut link = HalfDuplex::new(board.UARTE1, board.TIMER1, pin, 115_200);
+    let mut delay = Timer::new(board.TIMER0);
+    let mut random = Random::new(board.RNG);
+    let mut temp = Temp::new(board.TEMP);
+    let button_a = board.buttons.button_a;
+
+    let mut sequence = 0u8;
+    let mut was_pressed = false;
+    rprintln!("Press A to ask the other micro:bit for its temperature");
+    loop {
+        let pressed = button_a.is_low().unwrap();
+        if pressed && !was_pressed {
+            sequence = sequence.wrapping_add(1);
+            let request = Packet {
+                packet_type: types::REQUEST,
+                sequence,
+                payload: b"temp",
+            };
+            let mut answered = false;
+            for _ in 0..ATTEMPTS {
+                match link.request(&request, RESPONSE_TIMEOUT_US) {
+                    Ok(response) => {
+                        let answer = core::str::from_utf8(response.payload).unwrap_or("?");
+                        rprintln!("The other one is at {}", answer);
+                        answered = true;
+                        break;
+                    }
+                    // Both started at once, the other one was busy, or it
+                    // wasn't listening just then. Wait for a random while, up
+                    // to 25ms, so that the two don't wait just as long as
+                    // each other, and try again.
+                    Err(e) => {
+                        rprintln!("{:?}, trying again", e);
+                        delay.delay_us(u32::from(random.next_u8()) * 100);
+                    }
+                }
+            }
+            if !answered {
+                rprintln!("No answer");
+            }
+        }
+        was_pressed = pressed;
+
+        match link.receive(LISTEN_US) {
+            Ok(Packet {
+                packet_type: types::REQUEST,
+                sequence,
+                payload: b"temp",
+            }) => {
+                let mut answer: String<16> = String::new();
+                write!(answer, "{} C", temp.measure()).unwrap();
+                let response = Packet {
+                    packet_type: types::RESPONSE,
+                    sequence,
+                    payload: answer.as_bytes(),
+                };
+                if let Err(e) = link.send(&response) {
+                    rprintln!("could not answer: {:?}", e);
+                }
+            }
+            Ok(packet) => rprintln!("unexpected packet: {:?}", packet),
+            // Nobody asked
+            Err(Error::Timeout) => {}
+            Err(e) => rprintln!("garbled request: {:?}", e),
+        }
+    }
+}
@@ -0,0 +1,182 @@
+#![no_main]
+#![no_std]
+
+use core::fmt::Write;
+use cortex_m_rt::entry;
+use embedded_hal::digital::v2::InputPin;
+use heapless::String;
+use panic_rtt_target as _;
+use rtt_target::rtt_init_print;
+
+use discovery_logic::stopwatch::{Elapsed, LapOrReset, Stopwatch};
+use discovery_support::clocks::init_clocks;
+use discovery_support::display::{clear_display, display_leds, init_display};
+use discovery_support::monotonic::{init_monotonic, Instant};
+use discovery_support::scroll::{steps, SCROLL_STEP_MS};
+
+#[cfg(feature = "v1")]
+use microbit::hal::uart::{self, Baudrate, Parity};
+
+#[cfg(feature = "v2")]
+use microbit::hal::uarte::{self, Baudrate, Parity};
+
+#[cfg(feature = "v2")]
+use discovery_support::serial::{FlowControl, UartePort};
+
+/// How often the buttons are looked at, slow enough that they're done
+/// bouncing.
+const BUTTON_POLL_MS: u64 = 10;
+
+/// How often the running time is written out over the serial port.
+const UPDATE_MS: u64 = 100;
+
+fn now_ms() -> u64 {
+    Instant::now().as_micros() / 1_000
+}
+
+/// Text scrolling across the display, one step at a time, so the buttons
+/// still work while it does.
+struct Scroll {
+    text: String<16>,
+    step: usize,
+    next_step_ms: u64,
+}
+
+impl Scroll {
+    fn new() -> Scroll {
+        Scroll {
+            text: String::new(),
+            step: 0,
+            next_step_ms: 0,
+        }
+    }
+
+    /// Start scrolling `elapsed`, in place of whatever was scrolling before.
+    fn show(&mut self, elapsed: Elapsed) {
+        self.text.clear();
+        write!(self.text, "{}", elapsed).unwrap();
+        self.step = 0;
+        self.next_step_ms = now_ms();
+    }
+
+    fn poll(&mut self) {
+        if self.text.is_empty() || now_ms() < self.next_step_ms {
+            return;
+        }
+        let leds = steps(&self.text).nth(self.step);
+        match leds {
+            Some(leds) => display_leds(leds),
+            None => {
+                self.text.clear();
+                clear_display();
+            }
+        }
+        self.step += 1;
+        self.next_step_ms += u64::from(SCROLL_STEP_MS);
+    }
+}
+
+#[entry]
+fn main() -> ! {
+    rtt_init_print!();
+    let board = microbit::Board::take().unwrap();
+
+    #[cfg(feature = "v1")]
+    let mut serial = {
+        uart::Uart::new(
+            board.UART0,
+            board.uart.into(),
+            Parity::EXCLUDED,
+            Baudrate::BAUD115200,
+        )
+    };
+
+    #[cfg(feature = "v2")]
+    let mut serial = {
+        let serial = uarte::Uarte::new(
+            board.UARTE0,
+            board.uart.into(),
+            Parity::EXCLUDED,
+            Baudrate::BAUD115200,
+        );
+        UartePort::new(serial, FlowControl::Disabled)
+    };
+
+    let clocks = init_clocks(board.CLOCK);
+    init_monotonic(&clocks);
+    init_display(board.TIMER1, board.display_pins);
+    let button_a = board.buttons.button_a;
+    let button_b = board.buttons.button_b;
+
+    let mut stopwatch = Stopwatch::new();
+    let mut scroll = Scroll::new();
+    write!(
+        serial,
+        "A starts and stops, B takes a lap, or resets when stopped\r\n"
+    )
+    .unwrap();
+
+    let (mut a_pressed, mut b_pressed) = (false, false);
+    let mut next_poll_ms = 0;
+    let mut next_update_ms = 0;
+    loop {
+        scroll.poll();
+
+        let now = now_ms();
+        if now >= next_update_ms && stopwatch.is_running() {
+            write!(serial, "\r{}", Elapsed(stopwatch.elapsed_ms(now))).unwrap();
+            next_update_ms = now + UPDATE_MS;
+        }
+
+        if now < next_poll_ms {
+            continue;
+        }
+        next_poll_ms = now + BUTTON_POLL_MS;
+
+        let a_now = button_a.is_low().unwrap();
+        if a_now && !a_pressed {
+            if stopwatch.start_stop(now) {
+                write!(serial, "\rstarted\r\n").unwrap();
+            } else {
+                let elapsed = Elapsed(stopwatch.elapsed_ms(now));
+                write!(serial, "\rstopped at {}\r\n", elapsed).unwrap();
+                for (number, (lap_ms, split_ms)) in stopwatch.laps().enumerate() {
+                    write!(
+                        serial,
+                        "  lap {}: {} ({})\r\n",
+                        number + 1,
+                        Elapsed(lap_ms),
+                        Elapsed(split_ms)
+                    )
+                    .unwrap();
+                }
+                scroll.show(elapsed);
+            }
+        }
+        a_pressed = a_now;
+
+        let b_now = button_b.is_low().unwrap();
+        if b_now && !b_pressed {
+            match stopwatch.lap_or_reset(now) {
+                LapOrReset::Lap {
+                    number,
+                    lap_ms,
+                    split_ms,
+                } => {
+                    write!(
+                        serial,
+                        "\rlap {}: {} ({})\r\n",
+                        number,
+                        Elapsed(lap_ms),
+                        Elapsed(split_ms)
+                    )
+                    .unwrap();
+                    scroll.show(Elapsed(lap_ms));
+                }
+                LapOrReset::Full => write!(serial, "\rno room for more laps\r\n").unwrap(),
+                LapOrReset::Reset => write!(serial, "reset\r\n").unwrap(),
+            }
+        }
+        b_pressed = b_now;
+    }
+}
@@ -0,0 +1,66 @@
+use core::fmt;
+use core::str::Utf8Error;
+
+#[cfg(feature = "v1")]
+use microbit::hal::uart;
+
+#[cfg(feature = "v2")]
+use microbit::hal::uarte;
+
+/// Everything that can go wrong while the server deals with a line.
+///
+/// None of it is a reason to stop: the line is lost, but the next one may well
+/// be fine.
+#[derive(Debug)]
+pub enum Error {
+    /// The UARTE couldn't receive a byte, most likely because of a framing
+    /// error: noise on the line, or the other end using a different baud
+    /// rate. The v1's UART never reports an error.
+    #[cfg(feature = "v2")]
+    Receive(uarte::Error),
+    /// The reply could not be sent.
+    Send,
+    /// The line is longer than the buffer.
+    BufferFull,
+    /// The line isn't valid UTF-8, so there are no characters to reverse.
+    InvalidUtf8,
+}
+
+#[cfg(feature = "v1")]
+impl From<uart::Error> for Error {
+    // There are no errors to convert, the enum is empty
+    fn from(error: uart::Error) -> Error {
+        match error {}
+    }
+}
+
+#[cfg(feature = "v2")]
+impl From<uarte::Error> for Error {
+    fn from(error: uarte::Error) -> Error {
+        Error::Receive(error)
+    }
+}
+
+impl From<fmt::Error> for Error {
+    fn from(_: fmt::Error) -> Error {
+        Error::Send
+    }
+}
+
+impl From<Utf8Error> for Error {
+    fn from(_: Utf8Error) -> Error {
+        Error::InvalidUtf8
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            #[cfg(feature = "v2")]
+            Error::Receive(error) => write!(f, "could not receive ({:?})", error),
+            Error::Send => f.write_str("could not send"),
+            Error::BufferFull => f.write_str("buffer full"),
+            Error::InvalidUtf8 => f.write_str("not valid UTF-8"),
+        }
+    }
+}
@@ -0,0 +1,314 @@
+// Not every program that includes this module uses all of it
+#![allow(dead_code)]
+
+use core::fmt::Debug;
+use core::str;
+use embedded_hal::serial;
+use heapless::{Deque, Vec};
+
+const BACKSPACE: u8 = 0x08;
+const DELETE: u8 = 0x7f;
+const BELL: u8 = 0x07;
+const ESCAPE: u8 = 0x1b;
+
+/// How far into an escape sequence we are. That's how a terminal sends the
+/// keys that don't have a character of their own: the arrow keys are ESC `[`
+/// followed by `A` for up, `B` for down, `C` for right and `D` for left. In
+/// between there may be numbers, separated by `;`, for keys like F5 or for
+/// arrows pressed together with Shift.
+#[derive(Clone, Copy, PartialEq)]
+enum Escape {
+    None,
+    /// ESC arrived.
+    Started,
+    /// ESC `[` arrived, or ESC `O`, which some terminals send for the arrows
+    /// instead.
+    Sequence,
+}
+
+/// Reads one line at a time from a serial port, echoing what is typed.
+///
+/// Backspace and delete remove the last character, both on our side and on
+/// the terminal. A line ends with `\r`, `\n` or both. Only printable ASCII is
+/// kept, and once `N` characters have been typed any further ones are
+/// refused with a beep until the line is finished or edited. `N` is 32
+/// unless the program asks for more, or less.
+///
+/// The last `H` lines are kept, 8 unless the program asks otherwise. The up
+/// arrow brings back the one before the line on screen, and the down arrow the
+/// one after it, as in the shell on the computer. Other escape sequences are
+/// ignored.
+pub struct LineReader<const N: usize = 32, const H: usize = 8> {
+    buffer: Vec<u8, N>,
+    last_was_cr: bool,
+    /// Whether `buffer` holds a line that has already been returned.
+    done: bool,
+    escape: Escape,
+    /// Earlier lines, the newest at the back. Empty lines, and lines that are
+    /// the same as the one before, aren't kept.
+    history: Deque<Vec<u8, N>, H>,
+    /// How many lines back the arrows have gone, 0 while typing a new line.
+    recalled: usize,
+}
+
+impl<const N: usize, const H: usize> LineReader<N, H> {
+    pub const fn new() -> LineReader<N, H> {
+        LineReader {
+            buffer: Vec::new(),
+            last_was_cr: false,
+            done: false,
+            escape: Escape::None,
+            history: Deque::new(),
+            recalled: 0,
+        }
+    }
+
+    /// Add `line` to the history, as if it had been typed. That's how a
+    /// program brings back lines from before a reset. Lines that don't fit
+    /// are left out.
+    pub fn remember(&mut self, line: &str) {
+        if let Ok(line) = Vec::from_slice(line.as_bytes()) {
+            self.push_history(line);
+        }
+    }
+
+    /// The lines in the history, the oldest first.
+    pub fn history(&self) -> impl Iterator<Item = &str> {
+        // Nothing but ASCII ever makes it into the history
+        self.history
+            .iter()
+            .map(|line| str::from_utf8(line).unwrap())
+    }
+
+    /// Block until a whole line has been typed and return it, without the line
+    /// ending.
+    ///
+    /// On the micro:bit v2 the CPU sleeps between keystrokes, until an
+    /// interrupt wakes it up. So `serial` has to receive in the background,
+    /// which for a `UartePort` means calling `enable_rx_interrupt` first. The
+    /// v1's UART doesn't, so there the CPU keeps asking it for the next byte.
+    ///
+    /// If `serial` fails to receive, the line typed so far is dropped and the
+    /// error returned, so that the program can report it and read the next
+    /// line.
+    pub fn read_line<S>(&mut self, serial: &mut S) -> Result<&str, <S as serial::Read<u8>>::Error>
+    where
+        S: serial::Read<u8> + serial::Write<u8>,
+        <S as serial::Write<u8>>::Error: Debug,
+    {
+        self.buffer.clear();
+        self.done = false;
+
+        #[cfg(feature = "v2")]
+        {
+            // Wake up for interrupts that become pending as well, so that a
+            // byte arriving right before `wfe` isn't slept through
+            let mut scb = unsafe { cortex_m::Peripherals::steal() }.SCB;
+            scb.set_sevonpend();
+        }
+
+        loop {
+            let byte = match next_byte(serial) {
+                Ok(byte) => byte,
+                Err(error) => return Err(self.drop_line(error)),
+            };
+            if self.handle(byte, serial) {
+                break;
+            }
+        }
+        Ok(self.finish(serial))
+    }
+
+    /// Like `read_line`, but only take the bytes that have already arrived,
+    /// and return `None` if the line isn't finished yet. The next call after
+    /// a line has been returned starts a new one, and so does the next call
+    /// after an error.
+    ///
+    /// This lets a program keep doing its job between keystrokes, as long as
+    /// the serial port receives in the background.
+    pub fn poll_line<S>(
+        &mut self,
+        serial: &mut S,
+    ) -> Result<Option<&str>, <S as serial::Read<u8>>::Error>
+    where
+        S: serial::Read<u8> + serial::Write<u8>,
+        <S as serial::Write<u8>>::Error: Debug,
+    {
+        if self.done {
+            self.buffer.clear();
+            self.done = false;
+        }
+
+        loop {
+            match serial.read() {
+                Ok(byte) if self.handle(byte, serial) => break,
+                Ok(_) => {}
+                Err(nb::Error::WouldBlock) => return Ok(None),
+                Err(nb::Error::Other(error)) => {
+                    self.done = true;
+                    return Err(self.drop_line(error));
+                }
+            }
+        }
+        self.done = true;
+        Ok(Some(self.finish(serial)))
+    }
+
+    /// Forget the line being typed, after `error` cut it short. Whatever
+    /// arrives next starts afresh, not in the middle of an escape sequence.
+    fn drop_line<E>(&mut self, error: E) -> E {
+        self.buffer.clear();
+        self.last_was_cr = false;
+        self.escape = Escape::None;
+        self.recalled = 0;
+        error
+    }
+
+    /// Deal with one typed byte. Returns whether it ended the line.
+    fn handle<S>(&mut self, byte: u8, serial: &mut S) -> bool
+    where
+        S: serial::Write<u8>,
+        S::Error: Debug,
+    {
+        let last_was_cr = self.last_was_cr;
+        self.last_was_cr = byte == b'\r';
+
+        if self.escape_sequence(byte, serial) {
+            nb::block!(serial.flush()).unwrap();
+            return false;
+        }
+
+        match byte {
+            // The second half of a "\r\n" line ending, not an empty line
+            b'\n' if last_was_cr => {}
+            b'\r' | b'\n' => return true,
+            BACKSPACE | DELETE if self.buffer.pop().is_some() => {
+                // Step back, blank out the character and step back again
+                for &b in b"\x08 \x08" {
+                    nb::block!(serial.write(b)).unwrap();
+                }
+            }
+            b' '..=b'~' => {
+                let echo = if self.buffer.push(byte).is_ok() {
+                    byte
+                } else {
+                    BELL
+                };
+                nb::block!(serial.write(echo)).unwrap();
+            }
+            _ => {}
+        }
+        nb::block!(serial.flush()).unwrap();
+        false
+    }
+
+    /// Deal with `byte` if it's part of an escape sequence, and say whether it
+    /// was.
+    fn escape_sequence<S>(&mut self, byte: u8, serial: &mut S) -> bool
+    where
+        S: serial::Write<u8>,
+        S::Error: Debug,
+    {
+        match (self.escape, byte) {
+            (Escape::None, ESCAPE) => self.escape = Escape::Started,
+            (Escape::None, _) => return false,
+            (Escape::Started, b'[' | b'O') => self.escape = Escape::Sequence,
+            // The numbers in between
+            (Escape::Sequence, b'0'..=b'9' | b';') => {}
+            (Escape::Sequence, b'A') => {
+                self.escape = Escape::None;
+                self.recall(self.recalled + 1, serial);
+            }
+            (Escape::Sequence, b'B') => {
+                self.escape = Escape::None;
+                match self.recalled.checked_sub(1) {
+                    Some(back) => self.recall(back, serial),
+                    // Past the newest line there's nothing left to go to
+                    None => nb::block!(serial.write(BELL)).unwrap(),
+                }
+            }
+            // The end of a sequence we don't know, or of an escape sequence
+            // that's just ESC and one more byte
+            (Escape::Started | Escape::Sequence, _) => self.escape = Escape::None,
+        }
+        true
+    }
+
+    /// Replace the line on screen with the one `back` lines back in the
+    /// history, or with an empty line for 0. Beep if there is no such line.
+    fn recall<S>(&mut self, back: usize, serial: &mut S)
+    where
+        S: serial::Write<u8>,
+        S::Error: Debug,
+    {
+        let line = match back {
+            0 => Vec::new(),
+            _ => match self.history.iter().rev().nth(back - 1) {
+                Some(line) => line.clone(),
+                None => return nb::block!(serial.write(BELL)).unwrap(),
+            },
+        };
+        self.recalled = back;
+
+        // Step back to where the line starts, and clear the rest of it
+        for _ in 0..self.buffer.len() {
+            nb::block!(serial.write(BACKSPACE)).unwrap();
+        }
+        for &b in b"\x1b[K".iter().chain(&line) {
+            nb::block!(serial.write(b)).unwrap();
+        }
+        self.buffer = line;
+    }
+
+    fn push_history(&mut self, line: Vec<u8, N>) {
+        if line.is_empty() || self.history.back() == Some(&line) {
+            return;
+        }
+        if self.history.is_full() {
+            self.history.pop_front();
+        }
+        self.history.push_back(line).ok();
+    }
+
+    /// Move the terminal to the next line, keep the finished line in the
+    /// history, and return it.
+    fn finish<S>(&mut self, serial: &mut S) -> &str
+    where
+        S: serial::Write<u8>,
+        S::Error: Debug,
+    {
+        for &b in b"\r\n" {
+            nb::block!(serial.write(b)).unwrap();
+        }
+        nb::block!(serial.flush()).unwrap();
+        self.escape = Escape::None;
+        self.recalled = 0;
+        self.push_history(self.buffer.clone());
+
+        // Nothing but ASCII ever makes it into the buffer
+        str::from_utf8(&self.buffer).unwrap()
+    }
+}
+
+/// Wait for the next byte from `serial`.
+fn next_byte<S>(serial: &mut S) -> Result<u8, S::Error>
+where
+    S: serial::Read<u8>,
+{
+    loop {
+        match serial.read() {
+            Ok(byte) => return Ok(byte),
+            #[cfg(feature = "v2")]
+            Err(nb::Error::WouldBlock) => cortex_m::asm::wfe(),
+            #[cfg(not(feature = "v2"))]
+            Err(nb::Error::WouldBlock) => {}
+            Err(nb::Error::Other(error)) => return Err(error),
+        }
+    }
+}
+
+impl<const N: usize, const H: usize> Default for LineReader<N, H> {
+    fn default() -> Self {
+        LineReader::new()
+    }
+}
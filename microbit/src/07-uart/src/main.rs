@@ -2,8 +2,8 @@
 #![no_std]
 
 use cortex_m_rt::entry;
-use rtt_target::rtt_init_print;
 use panic_rtt_target as _;
+use rtt_target::rtt_init_print;
 
 #[cfg(feature = "v1")]
 use microbit::{
@@ -20,9 +20,7 @@ use microbit::{
 };
 
 #[cfg(feature = "v2")]
-mod serial_setup;
-#[cfg(feature = "v2")]
-use serial_setup::UartePort;
+use discovery_support::serial::{FlowControl, UartePort};
 
 #[entry]
 fn main() -> ! {
@@ -47,7 +45,7 @@ fn main() -> ! {
             Parity::EXCLUDED,
             Baudrate::BAUD115200,
         );
-        UartePort::new(serial)
+        UartePort::new(serial, FlowControl::Disabled)
     };
 
     nb::block!(serial.write(b'X')).unwrap();
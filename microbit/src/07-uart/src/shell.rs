@@ -0,0 +1,116 @@
+use core::fmt::{self, Write};
+use core::str::{FromStr, SplitWhitespace};
+
+/// Why a command could not be run.
+#[derive(Debug)]
+pub enum Error {
+    /// There is no command with that name.
+    UnknownCommand,
+    /// The command needs more arguments than it was given.
+    MissingArgument,
+    /// An argument could not be understood.
+    InvalidArgument,
+    /// Writing the command's output failed.
+    Output,
+}
+
+impl From<fmt::Error> for Error {
+    fn from(_: fmt::Error) -> Error {
+        Error::Output
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::UnknownCommand => f.write_str("unknown command, try `help`"),
+            Error::MissingArgument => f.write_str("missing argument"),
+            Error::InvalidArgument => f.write_str("invalid argument"),
+            Error::Output => f.write_str("could not write output"),
+        }
+    }
+}
+
+/// The words following the command name.
+pub struct Args<'l>(SplitWhitespace<'l>);
+
+impl<'l> Args<'l> {
+    /// The next word.
+    pub fn word(&mut self) -> Result<&'l str, Error> {
+        self.0.next().ok_or(Error::MissingArgument)
+    }
+
+    /// The next word, parsed as a number or anything else that implements
+    /// `FromStr`.
+    pub fn parse<T: FromStr>(&mut self) -> Result<T, Error> {
+        self.word()?.parse().map_err(|_| Error::InvalidArgument)
+    }
+
+    /// All remaining words.
+    pub fn rest(self) -> impl Iterator<Item = &'l str> {
+        self.0
+    }
+
+    /// Fail if there is anything left.
+    pub fn end(mut self) -> Result<(), Error> {
+        match self.0.next() {
+            Some(_) => Err(Error::InvalidArgument),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A command the shell knows about.
+///
+/// `C` is whatever state the commands need to do their job, usually a struct
+/// holding the peripherals they drive. Anything the command wants to tell the
+/// user goes to `out`.
+pub struct Command<C> {
+    pub name: &'static str,
+    pub usage: &'static str,
+    pub run: fn(context: &mut C, args: Args, out: &mut dyn Write) -> Result<(), Error>,
+}
+
+/// Looks up the first word of a line in a list of commands and runs it.
+///
+/// Each program brings its own list of commands, so later chapters can reuse
+/// this with whatever commands make sense there. `help` is always available
+/// and lists the registered commands.
+pub struct Shell<'c, C> {
+    commands: &'c [Command<C>],
+}
+
+impl<'c, C> Shell<'c, C> {
+    pub const fn new(commands: &'c [Command<C>]) -> Shell<'c, C> {
+        Shell { commands }
+    }
+
+    /// Run `line`, reporting any error to `out`. Empty lines are ignored.
+    pub fn run(&self, context: &mut C, line: &str, out: &mut dyn Write) -> fmt::Result {
+        let mut words = line.split_whitespace();
+        let name = match words.next() {
+            Some(name) => name,
+            None => return Ok(()),
+        };
+
+        if name == "help" {
+            return self.help(out);
+        }
+
+        let command = match self.commands.iter().find(|command| command.name == name) {
+            Some(command) => command,
+            None => return write!(out, "{}: {}\r\n", name, Error::UnknownCommand),
+        };
+        match (command.run)(context, Args(words), out) {
+            Ok(()) => Ok(()),
+            Err(error) => write!(out, "{}: {}\r\nusage: {}\r\n", name, error, command.usage),
+        }
+    }
+
+    fn help(&self, out: &mut dyn Write) -> fmt::Result {
+        for command in self.commands {
+            write!(out, "{}\r\n", command.usage)?;
+        }
+        write!(out, "help\r\n")
+    }
+}
@@ -0,0 +1,196 @@
+//! Tests that run on the micro:bit itself. `cargo test` flashes them with
+//! `probe-rs`, which prints what `defmt-test` reports, see `.cargo/config.toml`.
+//!
+//! The serial port is a fake one, so there's no need to type anything: it
+//! hands out bytes that were "typed" beforehand, and keeps whatever is echoed.
+//! A NUL among them stands for a byte the port failed to receive.
+
+#![no_main]
+#![no_std]
+
+#[path = "../src/line_reader.rs"]
+mod line_reader;
+
+use core::convert::Infallible;
+use defmt_rtt as _;
+use embedded_hal::serial;
+use heapless::Vec;
+
+// Not used, but it has the interrupt vectors the linker needs
+use microbit as _;
+
+use panic_probe as _;
+
+/// What the fake serial port fails with.
+#[derive(Debug, PartialEq, defmt::Format)]
+struct Overrun;
+
+struct FakeSerial {
+    input: &'static [u8],
+    output: Vec<u8, 64>,
+}
+
+impl FakeSerial {
+    fn new(input: &'static [u8]) -> FakeSerial {
+        FakeSerial {
+            input,
+            output: Vec::new(),
+        }
+    }
+}
+
+impl serial::Read<u8> for FakeSerial {
+    type Error = Overrun;
+
+    fn read(&mut self) -> nb::Result<u8, Overrun> {
+        let (&byte, rest) = self.input.split_first().ok_or(nb::Error::WouldBlock)?;
+        self.input = rest;
+        match byte {
+            0 => Err(nb::Error::Other(Overrun)),
+            _ => Ok(byte),
+        }
+    }
+}
+
+impl serial::Write<u8> for FakeSerial {
+    type Error = Infallible;
+
+    fn write(&mut self, byte: u8) -> nb::Result<(), Infallible> {
+        self.output.push(byte).unwrap();
+        Ok(())
+    }
+
+    fn flush(&mut self) -> nb::Result<(), Infallible> {
+        Ok(())
+    }
+}
+
+#[defmt_test::tests]
+mod tests {
+    use super::{FakeSerial, Overrun};
+    use crate::line_reader::LineReader;
+
+    #[test]
+    fn reads_a_line() {
+        let mut serial = FakeSerial::new(b"hello\r");
+        let mut reader: LineReader<32> = LineReader::new();
+        defmt::assert_eq!(reader.read_line(&mut serial), Ok("hello"));
+        defmt::assert_eq!(&serial.output[..], b"hello\r\n");
+    }
+
+    #[test]
+    fn backspace_removes_the_last_character() {
+        let mut serial = FakeSerial::new(b"ab\x08c\x7fd\r");
+        let mut reader: LineReader<32> = LineReader::new();
+        defmt::assert_eq!(reader.read_line(&mut serial), Ok("ad"));
+        defmt::assert_eq!(&serial.output[..], b"ab\x08 \x08c\x08 \x08d\r\n");
+    }
+
+    #[test]
+    fn backspace_on_an_empty_line_does_nothing() {
+        let mut serial = FakeSerial::new(b"\x08a\r");
+        let mut reader: LineReader<32> = LineReader::new();
+        defmt::assert_eq!(reader.read_line(&mut serial), Ok("a"));
+        defmt::assert_eq!(&serial.output[..], b"a\r\n");
+    }
+
+    #[test]
+    fn a_full_line_beeps() {
+        let mut serial = FakeSerial::new(b"abcd\r");
+        let mut reader: LineReader<3> = LineReader::new();
+        defmt::assert_eq!(reader.read_line(&mut serial), Ok("abc"));
+        defmt::assert_eq!(&serial.output[..], b"abc\x07\r\n");
+    }
+
+    #[test]
+    fn crlf_is_one_line_ending() {
+        let mut serial = FakeSerial::new(b"a\r\nb\n");
+        let mut reader: LineReader<32> = LineReader::new();
+        defmt::assert_eq!(reader.poll_line(&mut serial), Ok(Some("a")));
+        defmt::assert_eq!(reader.poll_line(&mut serial), Ok(Some("b")));
+        defmt::assert_eq!(reader.poll_line(&mut serial), Ok(None));
+    }
+
+    #[test]
+    fn poll_line_waits_for_the_end_of_the_line() {
+        let mut serial = FakeSerial::new(b"he");
+        let mut reader: LineReader<32> = LineReader::new();
+        defmt::assert_eq!(reader.poll_line(&mut serial), Ok(None));
+        serial.input = b"llo\r";
+        defmt::assert_eq!(reader.poll_line(&mut serial), Ok(Some("hello")));
+    }
+
+    #[test]
+    fn control_characters_are_ignored() {
+        let mut serial = FakeSerial::new(b"a\x1b\tb\r");
+        let mut reader: LineReader<32> = LineReader::new();
+        defmt::assert_eq!(reader.read_line(&mut serial), Ok("ab"));
+    }
+
+    #[test]
+    fn up_brings_back_earlier_lines() {
+        let mut serial = FakeSerial::new(b"one\rtwo\r\x1b[A\x1b[A\r");
+        let mut reader: LineReader<32> = LineReader::new();
+        defmt::assert_eq!(reader.read_line(&mut serial), Ok("one"));
+        defmt::assert_eq!(reader.read_line(&mut serial), Ok("two"));
+        serial.output.clear();
+        defmt::assert_eq!(reader.read_line(&mut serial), Ok("one"));
+        defmt::assert_eq!(&serial.output[..], b"\x1b[Ktwo\x08\x08\x08\x1b[Kone\r\n");
+    }
+
+    #[test]
+    fn down_goes_forward_again() {
+        let mut serial = FakeSerial::new(b"one\rtwo\r\x1b[A\x1b[A\x1b[Bx\r");
+        let mut reader: LineReader<32> = LineReader::new();
+        reader.read_line(&mut serial).unwrap();
+        reader.read_line(&mut serial).unwrap();
+        defmt::assert_eq!(reader.read_line(&mut serial), Ok("twox"));
+    }
+
+    #[test]
+    fn down_past_the_newest_line_beeps() {
+        let mut serial = FakeSerial::new(b"\x1b[Ba\r");
+        let mut reader: LineReader<32> = LineReader::new();
+        defmt::assert_eq!(reader.read_line(&mut serial), Ok("a"));
+        defmt::assert_eq!(&serial.output[..], b"\x07a\r\n");
+    }
+
+    #[test]
+    fn the_oldest_lines_are_forgotten() {
+        let mut serial = FakeSerial::new(b"a\rb\rb\r\rc\r");
+        let mut reader: LineReader<32, 2> = LineReader::new();
+        for _ in 0..5 {
+            reader.read_line(&mut serial).unwrap();
+        }
+        let mut history = reader.history();
+        defmt::assert_eq!(history.next(), Some("b"));
+        defmt::assert_eq!(history.next(), Some("c"));
+        defmt::assert_eq!(history.next(), None);
+    }
+
+    #[test]
+    fn other_escape_sequences_are_ignored() {
+        // Right arrow, and F5
+        let mut serial = FakeSerial::new(b"a\x1b[Cb\x1b[15~\r");
+        let mut reader: LineReader<32> = LineReader::new();
+        defmt::assert_eq!(reader.read_line(&mut serial), Ok("ab"));
+    }
+
+    #[test]
+    fn an_error_drops_the_line() {
+        let mut serial = FakeSerial::new(b"ab\0cd\r");
+        let mut reader: LineReader<32> = LineReader::new();
+        defmt::assert_eq!(reader.read_line(&mut serial), Err(Overrun));
+        defmt::assert_eq!(reader.read_line(&mut serial), Ok("cd"));
+    }
+
+    #[test]
+    fn poll_line_starts_afresh_after_an_error() {
+        let mut serial = FakeSerial::new(b"ab\0c");
+        let mut reader: LineReader<32> = LineReader::new();
+        defmt::assert_eq!(reader.poll_line(&mut serial), Err(Overrun));
+        defmt::assert_eq!(reader.poll_line(&mut serial), Ok(None));
+        serial.input = b"d\r";
+        defmt::assert_eq!(reader.poll_line(&mut serial), Ok(Some("cd")));
+    }
+}
@@ -0,0 +1,112 @@
+#![no_main]
+#![no_std]
+
+use core::fmt::Write;
+use cortex_m_rt::entry;
+use discovery_support::{init_log, println};
+
+// Panics are printed the same way as everything else
+#[cfg(feature = "defmt")]
+use panic_probe as _;
+#[cfg(not(any(feature = "semihosting", feature = "defmt")))]
+use panic_rtt_target as _;
+#[cfg(feature = "semihosting")]
+use panic_semihosting as _;
+
+use discovery_support::flash_log::{FlashLog, CAPACITY};
+use discovery_support::monotonic::{init_monotonic, Instant};
+use lsm303agr::{AccelOutputDataRate, Lsm303agr};
+use microbit::{
+    hal::clocks::Clocks,
+    hal::twim,
+    hal::uarte::{self, Baudrate, Parity},
+    pac::twim0::frequency::FREQUENCY_A,
+};
+
+use discovery_support::serial::{FlowControl, UartePort};
+
+#[path = "../../07-uart/src/line_reader.rs"]
+mod line_reader;
+use line_reader::LineReader;
+
+#[path = "../../07-uart/src/shell.rs"]
+#[allow(dead_code)]
+mod shell;
+use shell::{Args, Command, Error, Shell};
+
+fn log(flash_log: &mut FlashLog, mut args: Args, out: &mut dyn Write) -> Result<(), Error> {
+    match args.word()? {
+        "dump" => {
+            args.end()?;
+            write!(out, "ms,x,y,z\r\n")?;
+            for [ms, x, y, z] in flash_log.records() {
+                write!(out, "{},{},{},{}\r\n", ms, x as i32, y as i32, z as i32)?;
+            }
+        }
+        "erase" => {
+            args.end()?;
+            flash_log.erase();
+            write!(out, "log erased\r\n")?;
+        }
+        _ => return Err(Error::InvalidArgument),
+    }
+    Ok(())
+}
+
+const COMMANDS: &[Command<FlashLog>] = &[Command {
+    name: "log",
+    usage: "log dump|erase",
+    run: log,
+}];
+
+#[entry]
+fn main() -> ! {
+    init_log!();
+    let board = microbit::Board::take().unwrap();
+
+    let clocks = Clocks::new(board.CLOCK).start_lfclk();
+    init_monotonic(&clocks);
+
+    let serial = uarte::Uarte::new(
+        board.UARTE0,
+        board.uart.into(),
+        Parity::EXCLUDED,
+        Baudrate::BAUD115200,
+    );
+    let mut serial = UartePort::new(serial, FlowControl::Disabled);
+    // Samples keep coming in while we wait for a command, so typing has to be
+    // received in the background
+    serial.enable_rx_interrupt();
+
+    let i2c = twim::Twim::new(board.TWIM0, board.i2c_internal.into(), FREQUENCY_A::K100);
+    let mut sensor = Lsm303agr::new_with_i2c(i2c);
+    sensor.init().unwrap();
+    sensor.set_accel_odr(AccelOutputDataRate::Hz1).unwrap();
+
+    let mut flash_log = FlashLog::take().unwrap();
+    println!(
+        "{} records in the log, room for at least {}",
+        flash_log.records().count(),
+        CAPACITY
+    );
+
+    let shell = Shell::new(COMMANDS);
+    let mut reader: LineReader<32> = LineReader::new();
+    write!(serial, "> ").unwrap();
+    loop {
+        if sensor.accel_status().unwrap().xyz_new_data {
+            let data = sensor.accel_data().unwrap();
+            let ms = Instant::now().as_micros() / 1_000;
+            flash_log.push(&[ms as u32, data.x as u32, data.y as u32, data.z as u32]);
+        }
+
+        match reader.poll_line(&mut serial) {
+            Ok(Some(line)) => {
+                shell.run(&mut flash_log, line, &mut serial).unwrap();
+                write!(serial, "> ").unwrap();
+            }
+            Ok(None) => {}
+            Err(error) => write!(serial, "\r\nserial error: {:?}\r\n> ", error).unwrap(),
+        }
+    }
+}
@@ -0,0 +1,128 @@
+#![no_main]
+#![no_std]
+
+use cortex_m_rt::entry;
+use discovery_support::log::Debug2Format;
+use discovery_support::{init_log, println};
+
+// Panics are printed the same way as everything else
+#[cfg(feature = "defmt")]
+use panic_probe as _;
+#[cfg(not(any(feature = "semihosting", feature = "defmt")))]
+use panic_rtt_target as _;
+#[cfg(feature = "semihosting")]
+use panic_semihosting as _;
+
+use discovery_messages::{AccelSample, Button, ButtonEvent, MagSample, Message};
+use discovery_support::wire::Sender;
+use lsm303agr::{AccelOutputDataRate, Lsm303agr, MagOutputDataRate};
+use microbit::hal::prelude::*;
+
+#[cfg(feature = "v1")]
+use microbit::{
+    hal::twi,
+    hal::uart,
+    hal::uart::{Baudrate, Parity},
+    pac::twi0::frequency::FREQUENCY_A,
+};
+
+#[cfg(feature = "v2")]
+use microbit::{
+    hal::twim,
+    hal::uarte,
+    hal::uarte::{Baudrate, Parity},
+    pac::twim0::frequency::FREQUENCY_A,
+};
+
+#[cfg(feature = "v1")]
+use discovery_support::compat::Compat;
+#[cfg(feature = "v2")]
+use discovery_support::serial::{FlowControl, UartePort};
+
+#[entry]
+fn main() -> ! {
+    init_log!();
+    let board = microbit::Board::take().unwrap();
+
+    // `wire` is written for `embedded-hal-nb`, the HAL's `Uart` is still on
+    // `embedded-hal` 0.2
+    #[cfg(feature = "v1")]
+    let mut serial = {
+        Compat::new(uart::Uart::new(
+            board.UART0,
+            board.uart.into(),
+            Parity::EXCLUDED,
+            Baudrate::BAUD115200,
+        ))
+    };
+
+    #[cfg(feature = "v2")]
+    let mut serial = {
+        let serial = uarte::Uarte::new(
+            board.UARTE0,
+            board.uart.into(),
+            Parity::EXCLUDED,
+            Baudrate::BAUD115200,
+        );
+        UartePort::new(serial, FlowControl::Disabled)
+    };
+
+    #[cfg(feature = "v1")]
+    let i2c = { twi::Twi::new(board.TWI0, board.i2c.into(), FREQUENCY_A::K100) };
+
+    #[cfg(feature = "v2")]
+    let i2c = { twim::Twim::new(board.TWIM0, board.i2c_internal.into(), FREQUENCY_A::K100) };
+
+    let mut sensor = Lsm303agr::new_with_i2c(i2c);
+    sensor.init().unwrap();
+    sensor.set_accel_odr(AccelOutputDataRate::Hz10).unwrap();
+    sensor.set_mag_odr(MagOutputDataRate::Hz10).unwrap();
+    let mut sensor = sensor.into_mag_continuous().ok().unwrap();
+
+    let buttons = board.buttons;
+    let mut pressed = [false; 2];
+
+    let mut sender = Sender::new();
+    loop {
+        // The buttons are sent when they change, so the host can mark the
+        // moment in the readings
+        let now = [
+            buttons.button_a.is_low().unwrap(),
+            buttons.button_b.is_low().unwrap(),
+        ];
+        let changed = (0..2).find(|&i| now[i] != pressed[i]);
+
+        // Both sensors measure at 10 Hz, each reading is sent as soon as it's
+        // there
+        let message = if let Some(i) = changed {
+            pressed[i] = now[i];
+            let button = if i == 0 { Button::A } else { Button::B };
+            Message::Button(ButtonEvent {
+                button,
+                pressed: now[i],
+            })
+        } else if sensor.accel_status().unwrap().xyz_new_data {
+            let data = sensor.accel_data().unwrap();
+            Message::Acceleration(AccelSample {
+                x: data.x,
+                y: data.y,
+                z: data.z,
+            })
+        } else if sensor.mag_status().unwrap().xyz_new_data {
+            let data = sensor.mag_data().unwrap();
+            Message::MagneticField(MagSample {
+                x: data.x,
+                y: data.y,
+                z: data.z,
+            })
+        } else {
+            continue;
+        };
+
+        // A binary packet instead of a line of text, the host decodes it with
+        // the same `Message` type
+        if sender.send(&mut serial, &message).is_err() {
+            println!("could not send {:?}", Debug2Format(&message));
+        }
+    }
+}
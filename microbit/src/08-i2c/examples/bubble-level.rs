@@ -0,0 +1,93 @@
+#![deny(unsafe_code)]
+#![no_main]
+#![no_std]
+
+use cortex_m_rt::entry;
+use discovery_support::init_log;
+
+// Panics are printed the same way as everything else
+#[cfg(feature = "defmt")]
+use panic_probe as _;
+#[cfg(not(any(feature = "semihosting", feature = "defmt")))]
+use panic_rtt_target as _;
+#[cfg(feature = "semihosting")]
+use panic_semihosting as _;
+
+#[cfg(feature = "v1")]
+use microbit::{hal::twi, pac::twi0::frequency::FREQUENCY_A};
+
+#[cfg(feature = "v2")]
+use microbit::{hal::twim, pac::twim0::frequency::FREQUENCY_A};
+
+use lsm303agr::{AccelOutputDataRate, Lsm303agr};
+
+use discovery_support::display::{display_leds, init_display};
+
+/// How much of every new reading goes into the filtered one. Less is
+/// steadier, but slower to follow.
+const SMOOTHING: f32 = 0.1;
+/// How far the bubble moves for this much gravity along X or Y, in mg. About
+/// 6° of tilt moves it by one LED.
+const MG_PER_LED: f32 = 100.0;
+/// Closer to level than this, in mg along both X and Y, counts as level.
+const LEVEL_MG: f32 = 30.0;
+
+/// The centre LED and the four corners, shown once the board is level.
+const LEVEL: [[u8; 5]; 5] = [
+    [1, 0, 0, 0, 1],
+    [0, 0, 0, 0, 0],
+    [0, 0, 1, 0, 0],
+    [0, 0, 0, 0, 0],
+    [1, 0, 0, 0, 1],
+];
+
+/// The LED, from 0 to 4, that's `mg` of gravity away from the centre one.
+fn led_for(mg: f32) -> usize {
+    let offset = libm::roundf(mg / MG_PER_LED).clamp(-2.0, 2.0);
+    (2.0 + offset) as usize
+}
+
+#[entry]
+fn main() -> ! {
+    init_log!();
+    let board = microbit::Board::take().unwrap();
+
+    #[cfg(feature = "v1")]
+    let i2c = { twi::Twi::new(board.TWI0, board.i2c.into(), FREQUENCY_A::K100) };
+
+    #[cfg(feature = "v2")]
+    let i2c = { twim::Twim::new(board.TWIM0, board.i2c_internal.into(), FREQUENCY_A::K100) };
+
+    let mut sensor = Lsm303agr::new_with_i2c(i2c);
+    sensor.init().unwrap();
+    sensor.set_accel_odr(AccelOutputDataRate::Hz50).unwrap();
+
+    // The display refreshes itself from an interrupt, so reading the sensor
+    // doesn't make it flicker
+    init_display(board.TIMER1, board.display_pins);
+
+    let (mut x, mut y) = (0.0, 0.0);
+    loop {
+        while !sensor.accel_status().unwrap().xyz_new_data {}
+        let data = sensor.accel_data().unwrap();
+
+        // A low-pass filter: every new reading only moves the filtered one a
+        // little of the way towards it, so the jitter of the sensor, and of
+        // the hand holding the board, averages out
+        x += (data.x as f32 - x) * SMOOTHING;
+        y += (data.y as f32 - y) * SMOOTHING;
+
+        if libm::fabsf(x) < LEVEL_MG && libm::fabsf(y) < LEVEL_MG {
+            display_leds(LEVEL);
+            continue;
+        }
+
+        // The accelerometer measures gravity pointing up, so X and Y lean
+        // towards the edge that's raised, and that's where a bubble floats.
+        // X points to the right, and Y towards the bottom edge, the same way
+        // the rows go.
+        let mut leds = [[0; 5]; 5];
+        leds[led_for(y)][led_for(x)] = 1;
+        display_leds(leds);
+    }
+}
@@ -0,0 +1,88 @@
+#![no_main]
+#![no_std]
+
+use core::fmt::Write;
+use cortex_m_rt::entry;
+use discovery_support::init_log;
+
+// Panics are printed the same way as everything else
+#[cfg(feature = "defmt")]
+use panic_probe as _;
+#[cfg(not(any(feature = "semihosting", feature = "defmt")))]
+use panic_rtt_target as _;
+#[cfg(feature = "semihosting")]
+use panic_semihosting as _;
+
+use discovery_support::compat::Compat;
+use discovery_support::i2c::{device_name, i2c_scan};
+
+#[cfg(feature = "v1")]
+use microbit::{
+    hal::twi,
+    hal::uart,
+    hal::uart::{Baudrate, Parity},
+    pac::twi0::frequency::FREQUENCY_A,
+};
+
+#[cfg(feature = "v2")]
+use microbit::{
+    hal::twim,
+    hal::uarte,
+    hal::uarte::{Baudrate, Parity},
+    pac::twim0::frequency::FREQUENCY_A,
+};
+
+#[cfg(feature = "v2")]
+use discovery_support::serial::{FlowControl, UartePort};
+
+#[entry]
+fn main() -> ! {
+    init_log!();
+    let board = microbit::Board::take().unwrap();
+
+    #[cfg(feature = "v1")]
+    let mut serial = {
+        uart::Uart::new(
+            board.UART0,
+            board.uart.into(),
+            Parity::EXCLUDED,
+            Baudrate::BAUD115200,
+        )
+    };
+
+    #[cfg(feature = "v2")]
+    let mut serial = {
+        let serial = uarte::Uarte::new(
+            board.UARTE0,
+            board.uart.into(),
+            Parity::EXCLUDED,
+            Baudrate::BAUD115200,
+        );
+        UartePort::new(serial, FlowControl::Disabled)
+    };
+
+    #[cfg(feature = "v1")]
+    let i2c = { twi::Twi::new(board.TWI0, board.i2c.into(), FREQUENCY_A::K100) };
+
+    // To look for a sensor connected to the edge connector, use
+    // `board.i2c_external` instead.
+    #[cfg(feature = "v2")]
+    let i2c = { twim::Twim::new(board.TWIM0, board.i2c_internal.into(), FREQUENCY_A::K100) };
+
+    // `i2c_scan` is written for `embedded-hal` 1.0, the HAL's bus is still on
+    // 0.2
+    let mut i2c = Compat::new(i2c);
+
+    write!(serial, "Scanning the I2C bus...\r\n").unwrap();
+    let mut found = 0;
+    for address in i2c_scan(&mut i2c) {
+        let name = device_name(address).unwrap_or("unknown device");
+        write!(serial, "0x{:02X}: {}\r\n", address, name).unwrap();
+        found += 1;
+    }
+    write!(serial, "{} devices found\r\n", found).unwrap();
+
+    loop {
+        cortex_m::asm::wfi();
+    }
+}
@@ -0,0 +1,86 @@
+#![no_main]
+#![no_std]
+
+use cortex_m_rt::entry;
+use discovery_support::log::Debug2Format;
+use discovery_support::{init_log, println};
+use embedded_hal::blocking::i2c::WriteRead;
+
+// Panics are printed the same way as everything else
+#[cfg(feature = "defmt")]
+use panic_probe as _;
+#[cfg(not(any(feature = "semihosting", feature = "defmt")))]
+use panic_rtt_target as _;
+#[cfg(feature = "semihosting")]
+use panic_semihosting as _;
+
+use discovery_logic::register_map::{registers, DEFAULT_ADDRESS, WHO_AM_I_VALUE};
+use discovery_support::i2c::bus_recover;
+use microbit::hal::prelude::*;
+use microbit::hal::Timer;
+
+#[cfg(feature = "v1")]
+use microbit::{hal::twi, pac::twi0::frequency::FREQUENCY_A};
+
+#[cfg(feature = "v2")]
+use microbit::{hal::twim, pac::twim0::frequency::FREQUENCY_A};
+
+/// A smiley and a frown, for when button A on the other board is pressed.
+const SMILE: [u8; 5] = [0b00000, 0b01010, 0b00000, 0b10001, 0b01110];
+const FROWN: [u8; 5] = [0b00000, 0b01010, 0b00000, 0b01110, 0b10001];
+
+#[entry]
+fn main() -> ! {
+    init_log!();
+    let board = microbit::Board::take().unwrap();
+
+    // The v1's only bus goes to the edge connector as well
+    #[cfg(feature = "v1")]
+    let mut i2c = { twi::Twi::new(board.TWI0, board.i2c.into(), FREQUENCY_A::K100) };
+
+    #[cfg(feature = "v2")]
+    let mut i2c = { twim::Twim::new(board.TWIM0, board.i2c_external.into(), FREQUENCY_A::K100) };
+
+    let mut timer = Timer::new(board.TIMER0);
+
+    let mut who_am_i = [0];
+    i2c.write_read(DEFAULT_ADDRESS, &[registers::WHO_AM_I], &mut who_am_i)
+        .unwrap();
+    if who_am_i[0] != WHO_AM_I_VALUE {
+        println!(
+            "0x{:02X} is somebody else: 0x{:02X}",
+            DEFAULT_ADDRESS, who_am_i[0]
+        );
+        loop {
+            cortex_m::asm::wfi();
+        }
+    }
+
+    loop {
+        // The buttons and the temperature are right next to each other, so
+        // one read gets them all
+        let mut values = [0; 3];
+        let result = i2c
+            .write_read(DEFAULT_ADDRESS, &[registers::BUTTONS], &mut values)
+            .and_then(|()| {
+                let [buttons, temperature @ ..] = values;
+                let celsius = f32::from(i16::from_le_bytes(temperature)) / 4.0;
+                println!("A {}, B {}, {}°C", buttons & 1, buttons >> 1 & 1, celsius);
+
+                let face = if buttons & 1 != 0 { SMILE } else { FROWN };
+                let mut leds = [registers::LEDS, 0, 0, 0, 0, 0];
+                leds[1..].copy_from_slice(&face);
+                i2c.write(DEFAULT_ADDRESS, &leds)
+            });
+
+        if let Err(error) = result {
+            // A wire that came loose in the middle of a transfer can leave
+            // the other board holding SDA low
+            let (bus, state) = bus_recover(i2c, FREQUENCY_A::K100);
+            i2c = bus;
+            println!("{:?}, the bus was {:?}", Debug2Format(&error), state);
+        }
+
+        timer.delay_ms(500u32);
+    }
+}
@@ -0,0 +1,55 @@
+#![no_main]
+#![no_std]
+
+use cortex_m_rt::entry;
+use discovery_support::{init_log, println};
+use embedded_hal::digital::v2::InputPin;
+
+// Panics are printed the same way as everything else
+#[cfg(feature = "defmt")]
+use panic_probe as _;
+#[cfg(not(any(feature = "semihosting", feature = "defmt")))]
+use panic_rtt_target as _;
+#[cfg(feature = "semihosting")]
+use panic_semihosting as _;
+
+use discovery_logic::register_map::{RegisterMap, DEFAULT_ADDRESS};
+use discovery_support::display::{display_leds, init_display};
+use discovery_support::i2c_target::{Event, I2cTarget};
+use discovery_support::thermometer::{Thermometer, Unit};
+
+#[entry]
+fn main() -> ! {
+    init_log!();
+    let board = microbit::Board::take().unwrap();
+
+    init_display(board.TIMER1, board.display_pins);
+    let mut thermometer = Thermometer::new(board.TEMP, Unit::Celsius);
+    let buttons = board.buttons;
+
+    // The edge connector's pins 19 and 20, where the other board connects
+    let mut target = I2cTarget::new(board.TWIS0, board.i2c_external.into(), DEFAULT_ADDRESS);
+    let mut map = RegisterMap::new();
+    println!("Answering to 0x{:02X}", DEFAULT_ADDRESS);
+
+    loop {
+        match target.poll() {
+            Some(Event::Written(bytes)) => {
+                let changed = map.write(bytes);
+                if changed {
+                    display_leds(map.leds());
+                }
+            }
+            // The values are as fresh as they get when they're asked for
+            Some(Event::Read) => {
+                map.set_buttons(
+                    buttons.button_a.is_low().unwrap(),
+                    buttons.button_b.is_low().unwrap(),
+                );
+                map.set_temperature(thermometer.measure_quarters() as i16);
+                target.answer(map.to_read());
+            }
+            None => {}
+        }
+    }
+}
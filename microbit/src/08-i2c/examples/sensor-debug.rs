@@ -0,0 +1,148 @@
+#![no_main]
+#![no_std]
+
+use core::fmt::Write;
+use cortex_m_rt::entry;
+use discovery_support::init_log;
+use embedded_hal::blocking::i2c::WriteRead;
+
+// Panics are printed the same way as everything else
+#[cfg(feature = "defmt")]
+use panic_probe as _;
+#[cfg(not(any(feature = "semihosting", feature = "defmt")))]
+use panic_rtt_target as _;
+#[cfg(feature = "semihosting")]
+use panic_semihosting as _;
+
+use discovery_logic::lsm303_registers::{describe, find, Device, Register, REGISTERS};
+use lsm303agr::{AccelOutputDataRate, AccelScale, Lsm303agr, MagOutputDataRate};
+
+#[cfg(feature = "v1")]
+use microbit::{
+    hal::twi,
+    hal::uart,
+    hal::uart::{Baudrate, Parity},
+    pac::twi0::frequency::FREQUENCY_A,
+    pac::TWI0,
+};
+
+#[cfg(feature = "v2")]
+use microbit::{
+    hal::twim,
+    hal::uarte,
+    hal::uarte::{Baudrate, Parity},
+    pac::twim0::frequency::FREQUENCY_A,
+    pac::TWIM0,
+};
+
+#[cfg(feature = "v2")]
+use discovery_support::serial::{FlowControl, UartePort};
+
+#[path = "../../07-uart/src/line_reader.rs"]
+mod line_reader;
+use line_reader::LineReader;
+
+#[path = "../../07-uart/src/shell.rs"]
+#[allow(dead_code)]
+mod shell;
+use shell::{Args, Command, Error, Shell};
+
+#[cfg(feature = "v1")]
+type Bus = twi::Twi<TWI0>;
+#[cfg(feature = "v2")]
+type Bus = twim::Twim<TWIM0>;
+
+/// Print what `register` holds, or why it couldn't be read.
+fn dump(i2c: &mut Bus, register: &Register, out: &mut dyn Write) -> Result<(), Error> {
+    let mut value = [0];
+    match i2c.write_read(register.device.address(), &[register.address], &mut value) {
+        Ok(()) => describe(register, value[0], out)?,
+        Err(error) => write!(out, "{}: {:?}\r\n", register.name, error)?,
+    }
+    Ok(())
+}
+
+fn sensor_debug(i2c: &mut Bus, mut args: Args, out: &mut dyn Write) -> Result<(), Error> {
+    let device = match args.word() {
+        Ok("accel") => Some(Device::Accelerometer),
+        Ok("mag") => Some(Device::Magnetometer),
+        Ok(name) => {
+            args.end()?;
+            let register = find(name).ok_or(Error::InvalidArgument)?;
+            return dump(i2c, register, out);
+        }
+        Err(_) => None,
+    };
+    args.end()?;
+    for register in REGISTERS {
+        if device.is_none_or(|device| register.device == device) {
+            dump(i2c, register, out)?;
+        }
+    }
+    Ok(())
+}
+
+const COMMANDS: &[Command<Bus>] = &[Command {
+    name: "sensor-debug",
+    usage: "sensor-debug [accel|mag|<register>]",
+    run: sensor_debug,
+}];
+
+#[entry]
+fn main() -> ! {
+    init_log!();
+    let board = microbit::Board::take().unwrap();
+
+    #[cfg(feature = "v1")]
+    let mut serial = {
+        uart::Uart::new(
+            board.UART0,
+            board.uart.into(),
+            Parity::EXCLUDED,
+            Baudrate::BAUD115200,
+        )
+    };
+
+    #[cfg(feature = "v2")]
+    let mut serial = {
+        let serial = uarte::Uarte::new(
+            board.UARTE0,
+            board.uart.into(),
+            Parity::EXCLUDED,
+            Baudrate::BAUD115200,
+        );
+        UartePort::new(serial, FlowControl::Disabled)
+    };
+
+    #[cfg(feature = "v1")]
+    let i2c = { twi::Twi::new(board.TWI0, board.i2c.into(), FREQUENCY_A::K100) };
+
+    #[cfg(feature = "v2")]
+    let i2c = { twim::Twim::new(board.TWIM0, board.i2c_internal.into(), FREQUENCY_A::K100) };
+
+    // Set the sensor up the way your program does, and see what it was
+    // really told
+    let mut sensor = Lsm303agr::new_with_i2c(i2c);
+    sensor.init().unwrap();
+    sensor.set_accel_odr(AccelOutputDataRate::Hz50).unwrap();
+    sensor.set_accel_scale(AccelScale::G4).unwrap();
+    sensor.set_mag_odr(MagOutputDataRate::Hz10).unwrap();
+    let sensor = sensor.into_mag_continuous().ok().unwrap();
+    // The driver has nothing to read the registers with, so we take the bus
+    // back
+    let mut i2c = sensor.destroy();
+
+    let shell = Shell::new(COMMANDS);
+    let mut reader: LineReader<64> = LineReader::new();
+    loop {
+        write!(serial, "> ").unwrap();
+        let line = match reader.read_line(&mut serial) {
+            Ok(line) => line,
+            Err(error) => {
+                write!(serial, "\r\nserial error: {:?}\r\n", error).unwrap();
+                continue;
+            }
+        };
+        shell.run(&mut i2c, line, &mut serial).unwrap();
+    }
+}
@@ -0,0 +1,72 @@
+#![no_main]
+#![no_std]
+
+use core::cell::RefCell;
+use cortex_m_rt::entry;
+use discovery_support::{init_log, println};
+use embedded_hal::blocking::i2c::WriteRead;
+
+// Panics are printed the same way as everything else
+#[cfg(feature = "defmt")]
+use panic_probe as _;
+#[cfg(not(any(feature = "semihosting", feature = "defmt")))]
+use panic_rtt_target as _;
+#[cfg(feature = "semihosting")]
+use panic_semihosting as _;
+
+use discovery_support::i2c::RefCellDevice;
+use lsm303agr::{AccelOutputDataRate, Lsm303agr};
+use microbit::hal::prelude::*;
+use microbit::hal::Timer;
+
+#[cfg(feature = "v1")]
+use microbit::{hal::twi, pac::twi0::frequency::FREQUENCY_A};
+
+#[cfg(feature = "v2")]
+use microbit::{hal::twim, pac::twim0::frequency::FREQUENCY_A};
+
+/// A BME280 environmental sensor with its address pin pulled low.
+const BME280_ADDRESS: u8 = 0x76;
+const BME280_CHIP_ID: u8 = 0xD0;
+
+#[entry]
+fn main() -> ! {
+    init_log!();
+    let board = microbit::Board::take().unwrap();
+
+    // On the v1 there is a single I2C bus, the edge connector's SCL and SDA
+    // pins are connected to the LSM303AGR as well.
+    #[cfg(feature = "v1")]
+    let i2c = { twi::Twi::new(board.TWI0, board.i2c.into(), FREQUENCY_A::K100) };
+
+    // The v2 keeps the internal bus away from the edge connector, so a sensor
+    // connected there won't show up on this bus. Another device soldered onto
+    // the internal bus would work just the same though.
+    #[cfg(feature = "v2")]
+    let i2c = { twim::Twim::new(board.TWIM0, board.i2c_internal.into(), FREQUENCY_A::K100) };
+
+    let mut timer = Timer::new(board.TIMER0);
+
+    // Nobody owns the bus, each device gets a handle to it instead
+    let bus = RefCell::new(i2c);
+
+    let mut sensor = Lsm303agr::new_with_i2c(RefCellDevice::new(&bus));
+    sensor.init().unwrap();
+    sensor.set_accel_odr(AccelOutputDataRate::Hz10).unwrap();
+
+    let mut external = RefCellDevice::new(&bus);
+
+    loop {
+        while !sensor.accel_status().unwrap().xyz_new_data {}
+        let data = sensor.accel_data().unwrap();
+        println!("Acceleration: x {} y {} z {}", data.x, data.y, data.z);
+
+        let mut chip_id = [0];
+        match external.write_read(BME280_ADDRESS, &[BME280_CHIP_ID], &mut chip_id) {
+            Ok(()) => println!("BME280 chip id: 0x{:02X}", chip_id[0]),
+            Err(_) => println!("No BME280 at 0x{:02X}", BME280_ADDRESS),
+        }
+
+        timer.delay_ms(500_u16);
+    }
+}
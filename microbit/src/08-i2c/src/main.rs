@@ -3,22 +3,23 @@
 #![no_std]
 
 use cortex_m_rt::entry;
-use rtt_target::{rtt_init_print, rprintln};
+use discovery_support::{init_log, println};
+
+// Panics are printed the same way as everything else
+#[cfg(feature = "defmt")]
+use panic_probe as _;
+#[cfg(not(any(feature = "semihosting", feature = "defmt")))]
 use panic_rtt_target as _;
+#[cfg(feature = "semihosting")]
+use panic_semihosting as _;
 
 use microbit::hal::prelude::*;
 
 #[cfg(feature = "v1")]
-use microbit::{
-    hal::twi,
-    pac::twi0::frequency::FREQUENCY_A,
-};
+use microbit::{hal::twi, pac::twi0::frequency::FREQUENCY_A};
 
 #[cfg(feature = "v2")]
-use microbit::{
-    hal::twim,
-    pac::twim0::frequency::FREQUENCY_A,
-};
+use microbit::{hal::twim, pac::twim0::frequency::FREQUENCY_A};
 
 const ACCELEROMETER_ADDR: u8 = 0b0011001;
 const MAGNETOMETER_ADDR: u8 = 0b0011110;
@@ -28,10 +29,9 @@ const MAGNETOMETER_ID_REG: u8 = 0x4f;
 
 #[entry]
 fn main() -> ! {
-    rtt_init_print!();
+    init_log!();
     let board = microbit::Board::take().unwrap();
 
-
     #[cfg(feature = "v1")]
     let mut i2c = { twi::Twi::new(board.TWI0, board.i2c.into(), FREQUENCY_A::K100) };
 
@@ -42,11 +42,13 @@ fn main() -> ! {
     let mut mag = [0];
 
     // First write the address + register onto the bus, then read the chip's responses
-    i2c.write_read(ACCELEROMETER_ADDR, &[ACCELEROMETER_ID_REG], &mut acc).unwrap();
-    i2c.write_read(MAGNETOMETER_ADDR, &[MAGNETOMETER_ID_REG], &mut mag).unwrap();
+    i2c.write_read(ACCELEROMETER_ADDR, &[ACCELEROMETER_ID_REG], &mut acc)
+        .unwrap();
+    i2c.write_read(MAGNETOMETER_ADDR, &[MAGNETOMETER_ID_REG], &mut mag)
+        .unwrap();
 
-    rprintln!("The accelerometer chip's id is: {:#b}", acc[0]);
-    rprintln!("The magnetometer chip's id is: {:#b}", mag[0]);
+    println!("The accelerometer chip's id is: {:#b}", acc[0]);
+    println!("The magnetometer chip's id is: {:#b}", mag[0]);
 
     loop {}
 }
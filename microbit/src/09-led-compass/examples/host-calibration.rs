@@ -0,0 +1,135 @@
+#![deny(unsafe_code)]
+#![no_main]
+#![no_std]
+
+use cortex_m_rt::entry;
+use discovery_messages::{MagSample, Message};
+use discovery_support::settings::Settings;
+use discovery_support::wire::{self, Receiver, Sender};
+use embedded_hal_nb::serial::Read;
+use lsm303agr::{Lsm303agr, MagOutputDataRate, Measurement};
+
+// Panics are printed the same way as everything else
+#[cfg(feature = "defmt")]
+use panic_probe as _;
+#[cfg(not(feature = "defmt"))]
+use panic_rtt_target as _;
+
+use discovery_support::log::Debug2Format;
+use discovery_support::{init_log, println};
+
+// The host does the calibrating, the game on the LEDs isn't needed
+#[path = "../src/calibration.rs"]
+#[allow(dead_code)]
+mod calibration;
+use crate::calibration::{measurement_to_enu, Calibration};
+
+#[cfg(feature = "v1")]
+use microbit::{
+    hal::twi,
+    hal::uart,
+    hal::uart::{Baudrate, Parity},
+    pac::twi0::frequency::FREQUENCY_A,
+};
+
+#[cfg(feature = "v2")]
+use microbit::{
+    hal::twim,
+    hal::uarte,
+    hal::uarte::{Baudrate, Parity},
+    pac::twim0::frequency::FREQUENCY_A,
+};
+
+#[cfg(feature = "v1")]
+use discovery_support::compat::Compat;
+#[cfg(feature = "v2")]
+use discovery_support::serial::{FlowControl, UartePort};
+
+#[entry]
+fn main() -> ! {
+    init_log!();
+    let board = microbit::Board::take().unwrap();
+
+    #[cfg(feature = "v1")]
+    let mut serial = {
+        Compat::new(uart::Uart::new(
+            board.UART0,
+            board.uart.into(),
+            Parity::EXCLUDED,
+            Baudrate::BAUD115200,
+        ))
+    };
+
+    #[cfg(feature = "v2")]
+    let mut serial = {
+        let serial = uarte::Uarte::new(
+            board.UARTE0,
+            board.uart.into(),
+            Parity::EXCLUDED,
+            Baudrate::BAUD115200,
+        );
+        let mut serial = UartePort::new(serial, FlowControl::Disabled);
+        // The calibration may come in while a reading is being sent
+        serial.enable_rx_interrupt();
+        serial
+    };
+
+    #[cfg(feature = "v1")]
+    let i2c = { twi::Twi::new(board.TWI0, board.i2c.into(), FREQUENCY_A::K100) };
+
+    #[cfg(feature = "v2")]
+    let i2c = { twim::Twim::new(board.TWIM0, board.i2c_internal.into(), FREQUENCY_A::K100) };
+
+    let mut sensor = Lsm303agr::new_with_i2c(i2c);
+    sensor.init().unwrap();
+    sensor.set_mag_odr(MagOutputDataRate::Hz50).unwrap();
+    let mut sensor = sensor.into_mag_continuous().ok().unwrap();
+
+    let mut settings = Settings::take().unwrap();
+    let mut sender = Sender::new();
+    let mut receiver: Receiver<{ wire::MAX_PACKET_LEN }> = Receiver::new();
+
+    loop {
+        // Raw readings, but in the frame `Calibration` works in, so that what
+        // the host fits to them can be used as it is
+        if sensor.mag_status().unwrap().xyz_new_data {
+            let data = measurement_to_enu(sensor.mag_data().unwrap());
+            let message = Message::MagneticField(MagSample {
+                x: data.x,
+                y: data.y,
+                z: data.z,
+            });
+            if sender.send(&mut serial, &message).is_err() {
+                println!("could not send {:?}", Debug2Format(&message));
+            }
+        }
+
+        let byte = match serial.read() {
+            Ok(byte) => byte,
+            Err(nb::Error::WouldBlock) => continue,
+            Err(nb::Error::Other(_)) => {
+                println!("could not receive");
+                continue;
+            }
+        };
+        match receiver.feed(byte) {
+            Some(Ok(Message::Calibration {
+                center,
+                scale,
+                radius,
+            })) => {
+                let [x, y, z] = center;
+                let center = Measurement { x, y, z };
+                let [x, y, z] = scale;
+                let scale = Measurement { x, y, z };
+                let calibration = Calibration::new(center, scale, radius);
+                calibration.save(&mut settings);
+                println!("Saved {:?}", Debug2Format(&calibration));
+                sender.send(&mut serial, &Message::CalibrationSaved).ok();
+            }
+            Some(Ok(message)) => println!("ignoring {:?}", Debug2Format(&message)),
+            Some(Err(error)) => println!("received a bad packet: {:?}", Debug2Format(&error)),
+            None => {}
+        }
+    }
+}
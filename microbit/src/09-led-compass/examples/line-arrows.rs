@@ -0,0 +1,76 @@
+#![deny(unsafe_code)]
+#![no_main]
+#![no_std]
+
+use cortex_m_rt::entry;
+
+// Panics are printed the same way as everything else
+#[cfg(feature = "defmt")]
+use panic_probe as _;
+#[cfg(not(feature = "defmt"))]
+use panic_rtt_target as _;
+
+use discovery_support::init_log;
+
+use discovery_support::display::init_display;
+use discovery_support::graphics::Canvas;
+use embedded_graphics::{
+    pixelcolor::BinaryColor,
+    prelude::*,
+    primitives::{Line, PrimitiveStyle},
+};
+use microbit::hal::{prelude::*, Timer};
+
+/// One step towards North, North East, East, ... in display coordinates
+/// (y grows downwards).
+const HEADINGS: [(i32, i32); 8] = [
+    (0, -1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+    (-1, -1),
+];
+
+/// Draw an arrow through the centre of the display pointing along `(dx, dy)`.
+fn draw_arrow(canvas: &mut Canvas, (dx, dy): (i32, i32)) {
+    let style = PrimitiveStyle::with_stroke(BinaryColor::On, 1);
+    let centre = Point::new(2, 2);
+    let tip = centre + Point::new(2 * dx, 2 * dy);
+    let tail = centre - Point::new(2 * dx, 2 * dy);
+
+    Line::new(tail, tip)
+        .into_styled(style)
+        .draw(canvas)
+        .unwrap();
+
+    // The two barbs point backwards from the tip, 45 degrees either side of
+    // the shaft.
+    for (px, py) in [(-dy, dx), (dy, -dx)] {
+        let barb = Point::new((px - dx).clamp(-1, 1), (py - dy).clamp(-1, 1));
+        Line::new(tip, tip + barb)
+            .into_styled(style)
+            .draw(canvas)
+            .unwrap();
+    }
+}
+
+#[entry]
+fn main() -> ! {
+    init_log!();
+    let board = microbit::Board::take().unwrap();
+
+    let mut timer = Timer::new(board.TIMER0);
+    init_display(board.TIMER1, board.display_pins);
+
+    loop {
+        for heading in HEADINGS.iter() {
+            let mut canvas = Canvas::new();
+            draw_arrow(&mut canvas, *heading);
+            canvas.show();
+            timer.delay_ms(500u32);
+        }
+    }
+}
@@ -0,0 +1,58 @@
+#![deny(unsafe_code)]
+#![no_main]
+#![no_std]
+
+use cortex_m_rt::entry;
+use embedded_hal::digital::v2::InputPin;
+
+// Panics are printed the same way as everything else
+#[cfg(feature = "defmt")]
+use panic_probe as _;
+#[cfg(not(feature = "defmt"))]
+use panic_rtt_target as _;
+
+use discovery_support::{init_log, println};
+
+use discovery_logic::metal_detector::{bar_graph, bar_leds, pitch_hz, MetalDetector};
+use discovery_support::display::{display_leds, init_display};
+use discovery_support::sound::Speaker;
+use lsm303agr::{Lsm303agr, MagOutputDataRate};
+use microbit::{hal::twim, pac::twim0::frequency::FREQUENCY_A};
+
+#[entry]
+fn main() -> ! {
+    init_log!();
+    let board = microbit::Board::take().unwrap();
+
+    let i2c = twim::Twim::new(board.TWIM0, board.i2c_internal.into(), FREQUENCY_A::K100);
+    let mut sensor = Lsm303agr::new_with_i2c(i2c);
+    sensor.init().unwrap();
+    sensor.set_mag_odr(MagOutputDataRate::Hz50).unwrap();
+    let mut sensor = sensor.into_mag_continuous().ok().unwrap();
+
+    init_display(board.TIMER1, board.display_pins);
+    let mut speaker = Speaker::new(board.PWM0, board.speaker_pin, board.TIMER0);
+    let button_a = board.buttons.button_a;
+
+    let mut detector = MetalDetector::new();
+    let mut shown = None;
+    println!("Keep the board pointing the same way, press A after turning it");
+    loop {
+        while !sensor.mag_status().unwrap().xyz_new_data {}
+        let data = sensor.mag_data().unwrap();
+
+        if button_a.is_low().unwrap() {
+            detector.rebaseline();
+        }
+        let deviation = detector.update([data.x, data.y, data.z]);
+
+        // Only bother the display and the speaker when something changed
+        let leds = bar_leds(deviation);
+        if shown != Some(leds) {
+            display_leds(bar_graph(leds));
+            speaker.start_tone(pitch_hz(deviation));
+            println!("{} nT off", deviation);
+            shown = Some(leds);
+        }
+    }
+}
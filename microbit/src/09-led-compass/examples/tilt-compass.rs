@@ -0,0 +1,137 @@
+#![deny(unsafe_code)]
+#![no_main]
+#![no_std]
+
+use cortex_m_rt::entry;
+
+// Panics are printed the same way as everything else
+#[cfg(feature = "defmt")]
+use panic_probe as _;
+#[cfg(not(feature = "defmt"))]
+use panic_rtt_target as _;
+
+use discovery_support::log::Debug2Format;
+use discovery_support::{init_log, println};
+
+#[path = "../src/calibration.rs"]
+mod calibration;
+use crate::calibration::calibrated_measurement;
+use crate::calibration::saved_calibration;
+
+// The eight arrows are still in there, the needle doesn't need them
+#[path = "../src/led.rs"]
+#[allow(dead_code)]
+mod led;
+use crate::led::needle_to_led;
+
+#[path = "../src/heading.rs"]
+#[allow(dead_code)]
+mod heading;
+use crate::heading::{accel_to_cartesian, tilt_compensated_heading};
+
+use discovery_messages::{CompassHeading, Message};
+use discovery_support::settings::Settings;
+use discovery_support::wire::Sender;
+use microbit::{display::blocking::Display, hal::prelude::*, hal::Timer};
+
+#[cfg(feature = "v1")]
+use microbit::{
+    hal::twi,
+    hal::uart,
+    hal::uart::{Baudrate, Parity},
+    pac::twi0::frequency::FREQUENCY_A,
+};
+
+#[cfg(feature = "v2")]
+use microbit::{
+    hal::twim,
+    hal::uarte,
+    hal::uarte::{Baudrate, Parity},
+    pac::twim0::frequency::FREQUENCY_A,
+};
+
+#[cfg(feature = "v1")]
+use discovery_support::compat::Compat;
+#[cfg(feature = "v2")]
+use discovery_support::serial::{FlowControl, UartePort};
+
+use lsm303agr::{AccelOutputDataRate, Lsm303agr, MagOutputDataRate};
+
+#[entry]
+fn main() -> ! {
+    init_log!();
+    let board = microbit::Board::take().unwrap();
+
+    #[cfg(feature = "v1")]
+    let i2c = { twi::Twi::new(board.TWI0, board.i2c.into(), FREQUENCY_A::K100) };
+
+    #[cfg(feature = "v2")]
+    let i2c = { twim::Twim::new(board.TWIM0, board.i2c_internal.into(), FREQUENCY_A::K100) };
+
+    // The heading also goes to the serial port, for `serial-plotter`
+    #[cfg(feature = "v1")]
+    let mut serial = {
+        Compat::new(uart::Uart::new(
+            board.UART0,
+            board.uart.into(),
+            Parity::EXCLUDED,
+            Baudrate::BAUD115200,
+        ))
+    };
+
+    #[cfg(feature = "v2")]
+    let mut serial = {
+        let serial = uarte::Uarte::new(
+            board.UARTE0,
+            board.uart.into(),
+            Parity::EXCLUDED,
+            Baudrate::BAUD115200,
+        );
+        UartePort::new(serial, FlowControl::Disabled)
+    };
+
+    let mut timer = Timer::new(board.TIMER0);
+    let mut display = Display::new(board.display_pins);
+
+    let mut sensor = Lsm303agr::new_with_i2c(i2c);
+    sensor.init().unwrap();
+    sensor.set_mag_odr(MagOutputDataRate::Hz10).unwrap();
+    sensor.set_accel_odr(AccelOutputDataRate::Hz10).unwrap();
+    let mut sensor = sensor.into_mag_continuous().ok().unwrap();
+
+    // Hold button A while resetting to calibrate again
+    let recalibrate = board.buttons.button_a.is_low().unwrap();
+    let mut settings = Settings::take().unwrap();
+    let calibration = saved_calibration(
+        &mut sensor,
+        &mut display,
+        &mut timer,
+        &mut settings,
+        recalibrate,
+    );
+    println!("Calibration: {:?}", Debug2Format(&calibration));
+    println!(
+        "Hard iron offset: {:?}",
+        Debug2Format(&calibration.hard_iron_offset())
+    );
+    println!("Calibration done, entering busy loop");
+    let mut sender = Sender::new();
+    loop {
+        while !sensor.mag_status().unwrap().xyz_new_data {}
+        let mag = calibrated_measurement(sensor.mag_data().unwrap(), &calibration);
+        let accel = accel_to_cartesian(sensor.accel_data().unwrap());
+
+        let theta = tilt_compensated_heading(accel, mag);
+        println!("heading: {}", theta);
+        let message = Message::Heading(CompassHeading {
+            degrees: theta.to_degrees(),
+        });
+        if sender.send(&mut serial, &message).is_err() {
+            println!("could not send {:?}", Debug2Format(&message));
+        }
+
+        // The same way round as `heading_to_direction` picks the arrows: a
+        // `theta` of PI/2 points straight up, and PI to the right
+        display.show(&mut timer, needle_to_led(theta.to_degrees() - 90.), 100);
+    }
+}
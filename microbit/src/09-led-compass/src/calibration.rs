@@ -1,6 +1,7 @@
 //! Translated from <https://github.com/lancaster-university/codal-microbit-v2/blob/006abf5566774fbcf674c0c7df27e8a9d20013de/source/MicroBitCompassCalibrator.cpp>
 
 use core::fmt::Debug;
+use discovery_support::settings::{keys, Settings};
 use embedded_hal::blocking::delay::DelayUs;
 use embedded_hal::blocking::i2c::{Write, WriteRead};
 use libm::{fabsf, sqrtf};
@@ -36,6 +37,104 @@ impl Default for Calibration {
     }
 }
 
+impl Calibration {
+    /// Rebuild a calibration from the numbers printed with `{:?}`, so the
+    /// routine doesn't have to run on every start.
+    // Unused until you hard code a calibration in `main`
+    #[allow(dead_code)]
+    pub const fn new(center: Measurement, scale: Measurement, radius: u32) -> Calibration {
+        Calibration {
+            center,
+            scale,
+            radius,
+        }
+    }
+
+    /// The hard iron offset: the reading the magnetometer produces when there
+    /// is no field at all, in the sensor's ENU frame.
+    pub fn hard_iron_offset(&self) -> Measurement {
+        self.center
+    }
+
+    /// Keep the calibration in `settings`, for `saved_calibration` to find.
+    pub fn save(&self, settings: &mut Settings) {
+        settings
+            .set(keys::COMPASS_CALIBRATION, &self.to_bytes())
+            .unwrap();
+    }
+
+    /// The calibration as bytes, little endian, the way it's kept in the
+    /// settings.
+    fn to_bytes(&self) -> [u8; 28] {
+        let words = [
+            self.center.x,
+            self.center.y,
+            self.center.z,
+            self.scale.x,
+            self.scale.y,
+            self.scale.z,
+            self.radius as i32,
+        ];
+        let mut bytes = [0; 28];
+        for (chunk, word) in bytes.chunks_mut(4).zip(words.iter()) {
+            chunk.copy_from_slice(&word.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// The other way around, if `bytes` is the right length.
+    fn from_bytes(bytes: &[u8]) -> Option<Calibration> {
+        if bytes.len() != 28 {
+            return None;
+        }
+        let mut words = [0; 7];
+        for (word, chunk) in words.iter_mut().zip(bytes.chunks(4)) {
+            *word = i32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        }
+        let [cx, cy, cz, sx, sy, sz, radius] = words;
+        Some(Calibration {
+            center: Measurement {
+                x: cx,
+                y: cy,
+                z: cz,
+            },
+            scale: Measurement {
+                x: sx,
+                y: sy,
+                z: sz,
+            },
+            radius: radius as u32,
+        })
+    }
+}
+
+/// Use the calibration saved in `settings`, so that it only has to be done
+/// once. If there isn't one yet, or `recalibrate` is set, calibrate and save
+/// the result for next time.
+pub fn saved_calibration<I, T, E>(
+    sensor: &mut Lsm303agr<I2cInterface<I>, MagContinuous>,
+    display: &mut Display,
+    timer: &mut T,
+    settings: &mut Settings,
+    recalibrate: bool,
+) -> Calibration
+where
+    T: DelayUs<u32>,
+    I: Write<Error = E> + WriteRead<Error = E>,
+    E: Debug,
+{
+    if !recalibrate {
+        let saved = settings.get(keys::COMPASS_CALIBRATION);
+        if let Some(calibration) = saved.as_deref().and_then(Calibration::from_bytes) {
+            return calibration;
+        }
+    }
+
+    let calibration = calc_calibration(sensor, display, timer);
+    calibration.save(settings);
+    calibration
+}
+
 pub fn calc_calibration<I, T, E>(
     sensor: &mut Lsm303agr<I2cInterface<I>, MagContinuous>,
     display: &mut Display,
@@ -242,7 +341,9 @@ pub fn calibrated_measurement(measurement: Measurement, calibration: &Calibratio
     enu_to_cartesian(out)
 }
 
-fn measurement_to_enu(measurement: Measurement) -> Measurement {
+/// Turn a reading into the frame the calibration works in, ENU: east, north
+/// and up.
+pub fn measurement_to_enu(measurement: Measurement) -> Measurement {
     Measurement {
         x: -measurement.y,
         y: -measurement.x,
@@ -0,0 +1,71 @@
+//! A compass heading that stays put when the board is tilted.
+//!
+//! `atan2f(y, x)` of the magnetic field is only right as long as the board lies
+//! flat. The earth's field doesn't run parallel to the ground (in Europe it
+//! points down into it at around 65°), so as soon as the board tilts, part of
+//! that vertical component ends up on the x and y axes and the arrow swings
+//! around. The accelerometer tells us which way is down, which lets us throw
+//! the vertical part of the field away before computing the angle.
+
+use crate::led::Direction;
+use core::f32::consts::PI;
+use libm::atan2f;
+use lsm303agr::Measurement;
+
+/// Bring an accelerometer reading into the same frame as the output of
+/// `calibrated_measurement`. The two sensors share their axes, so this is the
+/// same turn `measurement_to_enu` and `enu_to_cartesian` do together.
+pub fn accel_to_cartesian(measurement: Measurement) -> Measurement {
+    Measurement {
+        x: measurement.x,
+        y: -measurement.y,
+        z: measurement.z,
+    }
+}
+
+/// The direction of north, in radians counter clockwise from the x axis of
+/// the board, just like `atan2f(mag.y, mag.x)` on a level board.
+///
+/// Both readings have to be in the same frame, see `accel_to_cartesian`.
+pub fn tilt_compensated_heading(accel: Measurement, mag: Measurement) -> f32 {
+    let (ax, ay, az) = (accel.x as f32, accel.y as f32, accel.z as f32);
+    let (mx, my, mz) = (mag.x as f32, mag.y as f32, mag.z as f32);
+
+    let gravity = ax * ax + ay * ay + az * az;
+    if gravity == 0. {
+        // Free fall, there is no "down" to compensate for
+        return atan2f(my, mx);
+    }
+
+    // Remove the part of the field that points along gravity. What is left
+    // lies flat on the ground and points north. Whether the accelerometer
+    // reports up or down doesn't matter here, the sign cancels out.
+    let vertical = (ax * mx + ay * my + az * mz) / gravity;
+    let north_x = mx - vertical * ax;
+    let north_y = my - vertical * ay;
+
+    atan2f(north_y, north_x)
+}
+
+/// Pick the arrow that is closest to `theta`.
+pub fn heading_to_direction(theta: f32) -> Direction {
+    if theta < -7. * PI / 8. {
+        Direction::West
+    } else if theta < -5. * PI / 8. {
+        Direction::SouthWest
+    } else if theta < -3. * PI / 8. {
+        Direction::South
+    } else if theta < -PI / 8. {
+        Direction::SouthEast
+    } else if theta < PI / 8. {
+        Direction::East
+    } else if theta < 3. * PI / 8. {
+        Direction::NorthEast
+    } else if theta < 5. * PI / 8. {
+        Direction::North
+    } else if theta < 7. * PI / 8. {
+        Direction::NorthWest
+    } else {
+        Direction::West
+    }
+}
@@ -1,5 +1,8 @@
+use discovery_support::image::Image5x5;
+use libm::{cosf, fabsf, roundf, sinf};
+
 #[derive(Debug)]
-pub enum Direction{
+pub enum Direction {
     North,
     NorthEast,
     East,
@@ -10,79 +13,78 @@ pub enum Direction{
     NorthWest,
 }
 
-const NORTH: [[u8; 5]; 5] = [
+const NORTH: Image5x5 = Image5x5::new([
     [0, 0, 1, 0, 0],
-    [0, 1, 1, 1, 0],   
+    [0, 1, 1, 1, 0],
     [1, 0, 1, 0, 1],
     [0, 0, 1, 0, 0],
-    [0, 0, 1, 0, 0],   
-];
+    [0, 0, 1, 0, 0],
+]);
 
-const NORTH_EAST: [[u8; 5]; 5] = [
+const NORTH_EAST: Image5x5 = Image5x5::new([
     [1, 1, 1, 0, 0],
-    [1, 1, 0, 0, 0],   
+    [1, 1, 0, 0, 0],
     [1, 0, 1, 0, 0],
     [0, 0, 0, 1, 0],
-    [0, 0, 0, 0, 1],   
-];
-
-const EAST: [[u8; 5]; 5] = [
-    [0, 0, 1, 0, 0],
-    [0, 1, 0, 0, 0],   
-    [1, 1, 1, 1, 1],
-    [0, 1, 0, 0, 0],
-    [0, 0, 1, 0, 0],   
-];
-
-const SOUTH_EAST: [[u8; 5]; 5] = [
     [0, 0, 0, 0, 1],
-    [0, 0, 0, 1, 0],   
-    [1, 0, 1, 0, 0],
-    [1, 1, 0, 0, 0],
-    [1, 1, 1, 0, 0],   
-];
-
-const SOUTH: [[u8; 5]; 5] = [
-    [0, 0, 1, 0, 0],
-    [0, 0, 1, 0, 0],   
-    [1, 0, 1, 0, 1],
-    [0, 1, 1, 1, 0],
-    [0, 0, 1, 0, 0],   
-];
-
-const SOUTH_WEST: [[u8; 5]; 5] = [
-    [1, 0, 0, 0, 0],
-    [0, 1, 0, 0, 0],   
-    [0, 0, 1, 0, 1],
-    [0, 0, 0, 1, 1],
-    [0, 0, 1, 1, 1],   
-];
-
-const WEST: [[u8; 5]; 5] = [
-    [0, 0, 1, 0, 0],
-    [0, 0, 0, 1, 0],   
-    [1, 1, 1, 1, 1],
-    [0, 0, 0, 1, 0],
-    [0, 0, 1, 0, 0],   
-];
+]);
 
-const NORTH_WEST: [[u8; 5]; 5] = [
-    [0, 0, 1, 1, 1],
-    [0, 0, 0, 1, 1],   
-    [0, 0, 1, 0, 1],
-    [0, 1, 0, 0, 0],
-    [1, 0, 0, 0, 0],   
-];
+// The other six arrows are these two turned a quarter at a time. The arrow
+// shows where north is, so it turns the opposite way to the heading: a
+// clockwise quarter turn of NORTH points the way north lies when facing west.
 
 pub fn direction_to_led(direction: Direction) -> [[u8; 5]; 5] {
-    match direction {
+    let image = match direction {
         Direction::North => NORTH,
         Direction::NorthEast => NORTH_EAST,
-        Direction::East => EAST,
-        Direction::SouthEast => SOUTH_EAST,
-        Direction::South => SOUTH,
-        Direction::SouthWest => SOUTH_WEST,
-        Direction::West => WEST,
-        Direction::NorthWest => NORTH_WEST,
+        Direction::East => NORTH.rotate90().rotate90().rotate90(),
+        Direction::SouthEast => NORTH_EAST.rotate90().rotate90().rotate90(),
+        Direction::South => NORTH.rotate90().rotate90(),
+        Direction::SouthWest => NORTH_EAST.rotate90().rotate90(),
+        Direction::West => NORTH.rotate90(),
+        Direction::NorthWest => NORTH_EAST.rotate90(),
+    };
+    image.leds()
+}
+
+/// A needle from the centre of the display towards `degrees`, clockwise from
+/// the top edge. Unlike the eight arrows it turns in finer steps: the tip can
+/// be any of the 16 LEDs around the edge.
+pub fn needle_to_led(degrees: f32) -> [[u8; 5]; 5] {
+    let (dx, dy) = (sinf(degrees.to_radians()), -cosf(degrees.to_radians()));
+    // Stretch the direction until it reaches the edge of the display. The
+    // edge is a square, not a circle, so this is how the corners get their
+    // turn.
+    let scale = 2. / fabsf(dx).max(fabsf(dy));
+    let tip = (2 + roundf(dx * scale) as i32, 2 + roundf(dy * scale) as i32);
+
+    let mut leds = [[0; 5]; 5];
+    draw_line((2, 2), tip, |x, y| leds[y as usize][x as usize] = 1);
+    leds
+}
+
+/// Call `plot` for every LED on the straight line from `from` to `to`, both
+/// ends included, as `(x, y)` with `y` growing downwards. This is Bresenham's
+/// algorithm: step along the longer axis one LED at a time, and keep track of
+/// how far off the line that puts us on the other one.
+fn draw_line(from: (i32, i32), to: (i32, i32), mut plot: impl FnMut(i32, i32)) {
+    let (mut x, mut y) = from;
+    let (dx, dy) = ((to.0 - x).abs(), -(to.1 - y).abs());
+    let (step_x, step_y) = ((to.0 - x).signum(), (to.1 - y).signum());
+    let mut error = dx + dy;
+    loop {
+        plot(x, y);
+        if (x, y) == to {
+            return;
+        }
+        let doubled = 2 * error;
+        if doubled >= dy {
+            error += dy;
+            x += step_x;
+        }
+        if doubled <= dx {
+            error += dx;
+            y += step_y;
+        }
     }
 }
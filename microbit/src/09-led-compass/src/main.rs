@@ -3,14 +3,21 @@
 #![no_std]
 
 use cortex_m_rt::entry;
+use discovery_support::{init_log, println};
+
+// Panics are printed the same way as everything else
+#[cfg(feature = "defmt")]
+use panic_probe as _;
+#[cfg(not(feature = "defmt"))]
 use panic_rtt_target as _;
-use rtt_target::{rprintln, rtt_init_print};
 
 mod calibration;
-use crate::calibration::calc_calibration;
 use crate::calibration::calibrated_measurement;
+use crate::calibration::saved_calibration;
 
-use microbit::{display::blocking::Display, hal::Timer};
+use discovery_support::log::Debug2Format;
+use discovery_support::settings::Settings;
+use microbit::{display::blocking::Display, hal::prelude::*, hal::Timer};
 
 #[cfg(feature = "v1")]
 use microbit::{hal::twi, pac::twi0::frequency::FREQUENCY_A};
@@ -22,7 +29,7 @@ use lsm303agr::{AccelOutputDataRate, Lsm303agr, MagOutputDataRate};
 
 #[entry]
 fn main() -> ! {
-    rtt_init_print!();
+    init_log!();
     let board = microbit::Board::take().unwrap();
 
     #[cfg(feature = "v1")]
@@ -40,13 +47,28 @@ fn main() -> ! {
     sensor.set_accel_odr(AccelOutputDataRate::Hz10).unwrap();
     let mut sensor = sensor.into_mag_continuous().ok().unwrap();
 
-    let calibration = calc_calibration(&mut sensor, &mut display, &mut timer);
-    rprintln!("Calibration: {:?}", calibration);
-    rprintln!("Calibration done, entering busy loop");
+    // Hold button A while resetting to calibrate again
+    let recalibrate = board.buttons.button_a.is_low().unwrap();
+    let mut settings = Settings::take().unwrap();
+    let calibration = saved_calibration(
+        &mut sensor,
+        &mut display,
+        &mut timer,
+        &mut settings,
+        recalibrate,
+    );
+    // The driver's types can't be sent the `defmt` way, they get formatted
+    // on the micro:bit as usual
+    println!("Calibration: {:?}", Debug2Format(&calibration));
+    println!(
+        "Hard iron offset: {:?}",
+        Debug2Format(&calibration.hard_iron_offset())
+    );
+    println!("Calibration done, entering busy loop");
     loop {
         while !sensor.mag_status().unwrap().xyz_new_data {}
         let mut data = sensor.mag_data().unwrap();
         data = calibrated_measurement(data, &calibration);
-        rprintln!("x: {}, y: {}, z: {}", data.x, data.y, data.z);
+        println!("x: {}, y: {}, z: {}", data.x, data.y, data.z);
     }
 }
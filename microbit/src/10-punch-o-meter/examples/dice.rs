@@ -0,0 +1,85 @@
+#![deny(unsafe_code)]
+#![no_main]
+#![no_std]
+
+use cortex_m_rt::entry;
+use discovery_support::{init_log, println};
+
+// Panics are printed the same way as everything else
+#[cfg(feature = "defmt")]
+use panic_probe as _;
+#[cfg(not(feature = "defmt"))]
+use panic_rtt_target as _;
+
+#[cfg(feature = "v1")]
+use microbit::{hal::twi, pac::twi0::frequency::FREQUENCY_A};
+
+#[cfg(feature = "v2")]
+use microbit::{hal::twim, pac::twim0::frequency::FREQUENCY_A};
+
+use lsm303agr::{AccelOutputDataRate, AccelScale, Lsm303agr};
+use microbit::hal::prelude::*;
+
+use discovery_support::delay::SysTickDelay;
+use discovery_support::display::{display_image, init_display};
+use discovery_support::gestures::{Gesture, GestureDetector};
+use discovery_support::image::DICE;
+use discovery_support::random::Random;
+
+/// How many faces the die shows while it tumbles...
+const TUMBLE_FACES: u32 = 12;
+/// ...the first one this long, in milliseconds...
+const FIRST_FACE_MS: u32 = 40;
+/// ...and every next one this much longer, as the die slows down.
+const SLOW_DOWN_MS: u32 = 15;
+
+#[entry]
+fn main() -> ! {
+    init_log!();
+    let board = microbit::Board::take().unwrap();
+
+    #[cfg(feature = "v1")]
+    let i2c = { twi::Twi::new(board.TWI0, board.i2c.into(), FREQUENCY_A::K100) };
+
+    #[cfg(feature = "v2")]
+    let i2c = { twim::Twim::new(board.TWIM0, board.i2c_internal.into(), FREQUENCY_A::K100) };
+
+    let mut sensor = Lsm303agr::new_with_i2c(i2c);
+    sensor.init().unwrap();
+    // The gesture detector expects 50 measurements per second, and shaking
+    // easily goes beyond 2g
+    sensor.set_accel_odr(AccelOutputDataRate::Hz50).unwrap();
+    sensor.set_accel_scale(AccelScale::G8).unwrap();
+
+    let mut delay = SysTickDelay::new(board.SYST);
+    init_display(board.TIMER1, board.display_pins);
+    let mut random = Random::new(board.RNG);
+    let mut gestures = GestureDetector::new();
+
+    // Start out with a six, like a die that's just been taken out of the box
+    let mut face = 5;
+    display_image(&DICE[face]);
+
+    loop {
+        while !sensor.accel_status().unwrap().xyz_new_data {}
+        gestures.update(sensor.accel_data().unwrap());
+
+        if !gestures.events().any(|gesture| gesture == Gesture::Shake) {
+            continue;
+        }
+
+        for tumble in 0..TUMBLE_FACES {
+            // Any face but the one that's up, or it wouldn't look like the
+            // die turned over
+            face = (face + 1 + random.below(5) as usize) % DICE.len();
+            display_image(&DICE[face]);
+            delay.delay_ms(FIRST_FACE_MS + tumble * SLOW_DOWN_MS);
+        }
+
+        // Where the die comes to rest mustn't depend on where it started, so
+        // that every number is as likely as the next
+        face = random.below(DICE.len() as u32) as usize;
+        display_image(&DICE[face]);
+        println!("Rolled a {}", face + 1);
+    }
+}
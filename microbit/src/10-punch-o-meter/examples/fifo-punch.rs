@@ -0,0 +1,96 @@
+#![deny(unsafe_code)]
+#![no_main]
+#![no_std]
+
+use cortex_m_rt::entry;
+use discovery_support::{init_log, println};
+
+// Panics are printed the same way as everything else
+#[cfg(feature = "defmt")]
+use panic_probe as _;
+#[cfg(not(feature = "defmt"))]
+use panic_rtt_target as _;
+
+#[cfg(feature = "v1")]
+use microbit::{hal::twi, pac::twi0::frequency::FREQUENCY_A};
+
+#[cfg(feature = "v2")]
+use microbit::{hal::twim, pac::twim0::frequency::FREQUENCY_A};
+
+use lsm303agr::{AccelOutputDataRate, AccelScale, Lsm303agr, Measurement};
+
+use microbit::hal::prelude::*;
+use microbit::hal::timer::Timer;
+use nb::Error;
+
+#[path = "../src/fifo.rs"]
+mod fifo;
+use fifo::{Fifo, FIFO_LEN};
+
+#[entry]
+fn main() -> ! {
+    const THRESHOLD: f32 = 0.5;
+
+    init_log!();
+    let board = microbit::Board::take().unwrap();
+
+    #[cfg(feature = "v1")]
+    let i2c = { twi::Twi::new(board.TWI0, board.i2c.into(), FREQUENCY_A::K400) };
+
+    #[cfg(feature = "v2")]
+    let i2c = { twim::Twim::new(board.TWIM0, board.i2c_internal.into(), FREQUENCY_A::K400) };
+
+    let mut countdown = Timer::new(board.TIMER0);
+    let mut delay = Timer::new(board.TIMER1);
+    let mut sensor = Lsm303agr::new_with_i2c(i2c);
+    sensor.init().unwrap();
+    // 400 measurements per second, the FIFO fills up in 80ms
+    sensor.set_accel_odr(AccelOutputDataRate::Hz400).unwrap();
+    sensor.set_accel_scale(AccelScale::G16).unwrap();
+
+    let mode = sensor.get_accel_mode();
+    let scale = sensor.get_accel_scale();
+    let mut fifo = Fifo::new(sensor.destroy(), mode, scale).unwrap();
+
+    let mut measurements = [Measurement { x: 0, y: 0, z: 0 }; FIFO_LEN];
+    let mut max_g = 0.;
+    let mut measuring = false;
+
+    loop {
+        let count = fifo.read(&mut measurements).unwrap();
+
+        for measurement in &measurements[..count] {
+            // x acceleration in g
+            let g_x = measurement.x as f32 / 1000.0;
+
+            if measuring {
+                if g_x > max_g {
+                    max_g = g_x;
+                }
+            } else if g_x > THRESHOLD {
+                println!("START!");
+
+                measuring = true;
+                max_g = g_x;
+                countdown.start(1_000_000_u32);
+            }
+        }
+
+        if measuring {
+            match countdown.wait() {
+                Err(Error::WouldBlock) => {}
+                Ok(_) => {
+                    println!("Max acceleration: {}g", max_g);
+
+                    max_g = 0.;
+                    measuring = false;
+                }
+                Err(Error::Other(_)) => {
+                    unreachable!()
+                }
+            }
+        }
+        // Plenty of time to spare before the FIFO overflows
+        delay.delay_ms(20_u8);
+    }
+}
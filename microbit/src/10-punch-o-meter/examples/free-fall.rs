@@ -0,0 +1,90 @@
+#![deny(unsafe_code)]
+#![no_main]
+#![no_std]
+
+use cortex_m_rt::entry;
+use discovery_support::{init_log, println};
+
+// Panics are printed the same way as everything else
+#[cfg(feature = "defmt")]
+use panic_probe as _;
+#[cfg(not(feature = "defmt"))]
+use panic_rtt_target as _;
+
+#[cfg(feature = "v1")]
+use microbit::{hal::twi, pac::twi0::frequency::FREQUENCY_A};
+
+#[cfg(feature = "v2")]
+use microbit::{hal::twim, pac::twim0::frequency::FREQUENCY_A};
+
+use lsm303agr::{AccelOutputDataRate, AccelScale, Lsm303agr};
+
+use microbit::display::blocking::Display;
+use microbit::hal::prelude::*;
+use microbit::hal::timer::Timer;
+
+#[path = "../src/accel.rs"]
+mod accel;
+
+#[path = "../src/free_fall.rs"]
+mod free_fall;
+use free_fall::{FreeFall, FreeFallConfig};
+
+const EXCLAMATION_MARK: [[u8; 5]; 5] = [
+    [0, 0, 1, 0, 0],
+    [0, 0, 1, 0, 0],
+    [0, 0, 1, 0, 0],
+    [0, 0, 0, 0, 0],
+    [0, 0, 1, 0, 0],
+];
+
+#[entry]
+fn main() -> ! {
+    const ODR: AccelOutputDataRate = AccelOutputDataRate::Hz100;
+    const SCALE: AccelScale = AccelScale::G2;
+
+    init_log!();
+    let board = microbit::Board::take().unwrap();
+
+    #[cfg(feature = "v1")]
+    let i2c = { twi::Twi::new(board.TWI0, board.i2c.into(), FREQUENCY_A::K100) };
+
+    #[cfg(feature = "v2")]
+    let i2c = { twim::Twim::new(board.TWIM0, board.i2c_internal.into(), FREQUENCY_A::K100) };
+
+    // The accelerometer's INT1 pin
+    #[cfg(feature = "v1")]
+    let int_pin = board.pins.p0_28.into_pullup_input();
+
+    #[cfg(feature = "v2")]
+    let int_pin = board.pins.p0_25.into_pullup_input();
+
+    let mut timer = Timer::new(board.TIMER0);
+    let mut display = Display::new(board.display_pins);
+
+    let mut sensor = Lsm303agr::new_with_i2c(i2c);
+    sensor.init().unwrap();
+    sensor.set_accel_odr(ODR).unwrap();
+    sensor.set_accel_scale(SCALE).unwrap();
+
+    let config = FreeFallConfig::default();
+    println!("Waiting for a fall: {:?}", config);
+    let mut free_fall = FreeFall::new(sensor.destroy(), ODR, SCALE, config).unwrap();
+
+    loop {
+        // No need to bother the accelerometer until it raises INT1
+        if int_pin.is_high().unwrap() {
+            timer.delay_ms(10_u8);
+            continue;
+        }
+
+        if free_fall.acknowledge().unwrap() {
+            println!("Falling!");
+            for _ in 0..3 {
+                display.show(&mut timer, EXCLAMATION_MARK, 200);
+                display.clear();
+                timer.delay_ms(200_u8);
+            }
+        }
+    }
+}
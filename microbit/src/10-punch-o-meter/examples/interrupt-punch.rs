@@ -0,0 +1,100 @@
+#![no_main]
+#![no_std]
+
+use cortex_m_rt::entry;
+use panic_rtt_target as _;
+use rtt_target::{rprintln, rtt_init_print};
+
+#[cfg(feature = "v1")]
+use microbit::{hal::twi, pac::twi0::frequency::FREQUENCY_A};
+
+#[cfg(feature = "v2")]
+use microbit::{hal::twim, pac::twim0::frequency::FREQUENCY_A};
+
+use lsm303agr::{AccelOutputDataRate, AccelScale, Lsm303agr};
+
+use microbit::hal::prelude::*;
+use microbit::hal::timer::Timer;
+use nb::Error;
+
+// Borrowed rather than used from `discovery-support`, whose own `GPIOTE`
+// handler, for the buttons, would clash with the one in `sampling`
+#[path = "../../../support/src/channel.rs"]
+#[allow(dead_code)]
+mod channel;
+#[path = "../src/sampling.rs"]
+mod sampling;
+#[path = "../../../support/src/shared.rs"]
+#[allow(dead_code)]
+mod shared;
+
+#[entry]
+fn main() -> ! {
+    const THRESHOLD: f32 = 0.5;
+
+    rtt_init_print!();
+    let board = microbit::Board::take().unwrap();
+
+    #[cfg(feature = "v1")]
+    let mut i2c = { twi::Twi::new(board.TWI0, board.i2c.into(), FREQUENCY_A::K100) };
+
+    #[cfg(feature = "v2")]
+    let mut i2c = { twim::Twim::new(board.TWIM0, board.i2c_internal.into(), FREQUENCY_A::K100) };
+
+    // The accelerometer's INT1 pin
+    #[cfg(feature = "v1")]
+    let int_pin = board.pins.p0_28.into_pullup_input().degrade();
+
+    #[cfg(feature = "v2")]
+    let int_pin = board.pins.p0_25.into_pullup_input().degrade();
+
+    sampling::route_data_ready(&mut i2c).unwrap();
+
+    let mut countdown = Timer::new(board.TIMER0);
+    let mut sensor = Lsm303agr::new_with_i2c(i2c);
+    sensor.init().unwrap();
+    sensor.set_accel_odr(AccelOutputDataRate::Hz50).unwrap();
+    // Allow the sensor to measure up to 16 G since human punches
+    // can actually be quite fast
+    sensor.set_accel_scale(AccelScale::G16).unwrap();
+    let mut samples = sampling::start(sensor, int_pin, board.GPIOTE);
+
+    let mut max_g = 0.;
+    let mut measuring = false;
+
+    loop {
+        // Sleep until the next interrupt, there is nothing to do without new
+        // measurements
+        cortex_m::asm::wfi();
+
+        while let Some(measurement) = samples.recv() {
+            // x acceleration in g
+            let g_x = measurement.x as f32 / 1000.0;
+
+            if measuring {
+                match countdown.wait() {
+                    Err(Error::WouldBlock) => {
+                        if g_x > max_g {
+                            max_g = g_x;
+                        }
+                    }
+                    Ok(_) => {
+                        rprintln!("Max acceleration: {}g", max_g);
+
+                        max_g = 0.;
+                        measuring = false;
+                    }
+                    Err(Error::Other(_)) => {
+                        unreachable!()
+                    }
+                }
+            } else if g_x > THRESHOLD {
+                rprintln!("START!");
+
+                measuring = true;
+                max_g = g_x;
+                countdown.start(1_000_000_u32);
+            }
+        }
+    }
+}
@@ -0,0 +1,77 @@
+#![deny(unsafe_code)]
+#![no_main]
+#![no_std]
+
+use cortex_m_rt::entry;
+use discovery_support::init_log;
+
+// Panics are printed the same way as everything else
+#[cfg(feature = "defmt")]
+use panic_probe as _;
+#[cfg(not(feature = "defmt"))]
+use panic_rtt_target as _;
+
+use discovery_support::neopixel::{NeoPixels, Rgb};
+use lsm303agr::{AccelOutputDataRate, Lsm303agr};
+use microbit::{hal::gpio::Level, hal::twim, pac::twim0::frequency::FREQUENCY_A};
+
+/// How many LEDs the strip has.
+const LEDS: usize = 8;
+/// How far apart the colors of two neighbouring LEDs are on the color wheel,
+/// so that the strip shows half of it.
+const HUE_STEP: u8 = (128 / LEDS) as u8;
+/// An acceleration of more than this, in mg, is a shake.
+const SHAKE_MG: i32 = 1_800;
+
+#[entry]
+fn main() -> ! {
+    init_log!();
+    let board = microbit::Board::take().unwrap();
+
+    let i2c = twim::Twim::new(board.TWIM0, board.i2c_internal.into(), FREQUENCY_A::K100);
+    let mut sensor = Lsm303agr::new_with_i2c(i2c);
+    sensor.init().unwrap();
+    sensor.set_accel_odr(AccelOutputDataRate::Hz50).unwrap();
+
+    // The data line of the strip goes on ring P0
+    let pin = board.pins.p0_02.into_push_pull_output(Level::Low).degrade();
+    let mut strip: NeoPixels<_, LEDS> = NeoPixels::new(board.PWM0, pin);
+    strip.clear();
+
+    let mut hue: u8 = 0;
+    let mut flash: u8 = 0;
+    loop {
+        if !sensor.accel_status().unwrap().xyz_new_data {
+            continue;
+        }
+        let data = sensor.accel_data().unwrap();
+
+        // Shaking makes the whole strip flash white, which then fades away
+        let squared = data.x * data.x + data.y * data.y + data.z * data.z;
+        if squared > SHAKE_MG * SHAKE_MG {
+            flash = 255;
+        }
+
+        // Tilting the board along X rolls a bright spot along the strip, like
+        // the bubble of a spirit level. Tilting it along Y turns the colors
+        // around the wheel, one way or the other.
+        let x = data.x.clamp(-1_000, 1_000);
+        let spot = (x + 1_000) * (LEDS as i32 - 1) / 2_000;
+        hue = hue.wrapping_add((data.y / 100) as u8);
+
+        let mut colors = [Rgb::OFF; LEDS];
+        for (index, color) in colors.iter_mut().enumerate() {
+            let distance = (index as i32 - spot).unsigned_abs();
+            let brightness = 255 >> (2 * distance).min(8);
+            let rainbow = Rgb::wheel(hue.wrapping_add(index as u8 * HUE_STEP));
+            *color = if flash > 0 {
+                Rgb::new(flash, flash, flash)
+            } else {
+                // Even dimmed down a lot, they're plenty bright close up
+                rainbow.dim(brightness as u8).dim(64)
+            };
+        }
+        flash = flash.saturating_sub(16);
+        strip.show(&colors);
+    }
+}
@@ -0,0 +1,137 @@
+#![deny(unsafe_code)]
+#![no_main]
+#![no_std]
+
+use core::fmt::Write;
+use cortex_m_rt::entry;
+use discovery_support::{init_log, println};
+
+// Panics are printed the same way as everything else
+#[cfg(feature = "defmt")]
+use panic_probe as _;
+#[cfg(not(feature = "defmt"))]
+use panic_rtt_target as _;
+
+#[cfg(feature = "v1")]
+use microbit::{hal::twi, pac::twi0::frequency::FREQUENCY_A};
+
+#[cfg(feature = "v2")]
+use microbit::{hal::twim, pac::twim0::frequency::FREQUENCY_A};
+
+use lsm303agr::{AccelOutputDataRate, AccelScale, Lsm303agr};
+
+use discovery_support::display::{display_leds, init_display};
+use discovery_support::scroll::{self, SCROLL_STEP_MS};
+use heapless::String;
+use microbit::hal::prelude::*;
+use microbit::hal::timer::Timer;
+
+const SCALES: [(AccelScale, &str); 4] = [
+    (AccelScale::G2, "2G"),
+    (AccelScale::G4, "4G"),
+    (AccelScale::G8, "8G"),
+    (AccelScale::G16, "16G"),
+];
+
+const RATES: [(AccelOutputDataRate, &str); 6] = [
+    (AccelOutputDataRate::Hz10, "10HZ"),
+    (AccelOutputDataRate::Hz50, "50HZ"),
+    (AccelOutputDataRate::Hz100, "100HZ"),
+    (AccelOutputDataRate::Hz200, "200HZ"),
+    (AccelOutputDataRate::Hz400, "400HZ"),
+    (AccelOutputDataRate::Khz1_344, "1344HZ"),
+];
+
+#[entry]
+fn main() -> ! {
+    init_log!();
+    let board = microbit::Board::take().unwrap();
+
+    #[cfg(feature = "v1")]
+    let i2c = { twi::Twi::new(board.TWI0, board.i2c.into(), FREQUENCY_A::K400) };
+
+    #[cfg(feature = "v2")]
+    let i2c = { twim::Twim::new(board.TWIM0, board.i2c_internal.into(), FREQUENCY_A::K400) };
+
+    let button_a = board.buttons.button_a;
+    let button_b = board.buttons.button_b;
+    let mut scroll_timer = Timer::new(board.TIMER0);
+    init_display(board.TIMER1, board.display_pins);
+
+    let mut scale = 3;
+    let mut rate = 1;
+    let mut sensor = Lsm303agr::new_with_i2c(i2c);
+    sensor.init().unwrap();
+    sensor.set_accel_odr(RATES[rate].0).unwrap();
+    sensor.set_accel_scale(SCALES[scale].0).unwrap();
+
+    let mut max_g: f32 = 0.;
+    let mut text: String<16> = String::new();
+    let mut scroll_step = 0;
+    let mut pressed = (false, false);
+    scroll_timer.start(SCROLL_STEP_MS * 1000);
+
+    loop {
+        if sensor.accel_status().unwrap().xyz_new_data {
+            // x acceleration in g
+            let g_x = sensor.accel_data().unwrap().x as f32 / 1000.0;
+            if g_x > max_g {
+                max_g = g_x;
+                println!("New peak: {}g", max_g);
+            }
+        }
+
+        // A picks the next scale, A and B together the next data rate, B on
+        // its own forgets the peak. We only act once all buttons have been
+        // let go, so pressing both doesn't also count as pressing one.
+        let a = button_a.is_low().unwrap();
+        let b = button_b.is_low().unwrap();
+        pressed = (pressed.0 || a, pressed.1 || b);
+
+        let mut setting = None;
+        if !a && !b {
+            match pressed {
+                (true, true) => {
+                    rate = (rate + 1) % RATES.len();
+                    sensor.set_accel_odr(RATES[rate].0).unwrap();
+                    setting = Some(RATES[rate].1);
+                }
+                (true, false) => {
+                    scale = (scale + 1) % SCALES.len();
+                    sensor.set_accel_scale(SCALES[scale].0).unwrap();
+                    setting = Some(SCALES[scale].1);
+                }
+                (false, true) => {
+                    max_g = 0.;
+                    setting = Some("0");
+                }
+                (false, false) => {}
+            }
+            pressed = (false, false);
+        }
+        if let Some(setting) = setting {
+            println!("{}", setting);
+            text.clear();
+            text.push_str(setting).unwrap();
+            scroll_step = 0;
+        }
+
+        if scroll_timer.wait().is_ok() {
+            scroll_timer.start(SCROLL_STEP_MS * 1000);
+
+            // Keep the peak scrolling across the display
+            let leds = scroll::steps(&text).nth(scroll_step);
+            match leds {
+                Some(leds) => {
+                    display_leds(leds);
+                    scroll_step += 1;
+                }
+                None => {
+                    text.clear();
+                    write!(text, "{:.1}G", max_g).unwrap();
+                    scroll_step = 0;
+                }
+            }
+        }
+    }
+}
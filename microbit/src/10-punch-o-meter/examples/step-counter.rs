@@ -0,0 +1,85 @@
+#![deny(unsafe_code)]
+#![no_main]
+#![no_std]
+
+use core::fmt::Write;
+use cortex_m_rt::entry;
+use discovery_support::{init_log, println};
+
+// Panics are printed the same way as everything else
+#[cfg(feature = "defmt")]
+use panic_probe as _;
+#[cfg(not(feature = "defmt"))]
+use panic_rtt_target as _;
+
+#[cfg(feature = "v1")]
+use microbit::{hal::twi, pac::twi0::frequency::FREQUENCY_A};
+
+#[cfg(feature = "v2")]
+use microbit::{hal::twim, pac::twim0::frequency::FREQUENCY_A};
+
+use lsm303agr::{AccelOutputDataRate, Lsm303agr};
+
+use discovery_support::display::{display_leds, init_display};
+use discovery_support::scroll::{self, SCROLL_STEP_MS};
+use heapless::String;
+
+#[path = "../src/pedometer.rs"]
+mod pedometer;
+use pedometer::{Pedometer, SAMPLE_RATE_HZ};
+
+/// Advance the scrolling text every this many measurements.
+const SAMPLES_PER_SCROLL_STEP: u32 = SCROLL_STEP_MS * SAMPLE_RATE_HZ / 1000;
+
+#[entry]
+fn main() -> ! {
+    init_log!();
+    let board = microbit::Board::take().unwrap();
+
+    #[cfg(feature = "v1")]
+    let i2c = { twi::Twi::new(board.TWI0, board.i2c.into(), FREQUENCY_A::K100) };
+
+    #[cfg(feature = "v2")]
+    let i2c = { twim::Twim::new(board.TWIM0, board.i2c_internal.into(), FREQUENCY_A::K100) };
+
+    // The display refreshes itself from an interrupt, so we never have to
+    // stop counting to show the count.
+    init_display(board.TIMER1, board.display_pins);
+
+    let mut sensor = Lsm303agr::new_with_i2c(i2c);
+    sensor.init().unwrap();
+    sensor.set_accel_odr(AccelOutputDataRate::Hz50).unwrap();
+
+    let mut pedometer = Pedometer::new();
+    let mut text: String<10> = String::new();
+    let mut scroll_step = 0;
+    let mut samples = 0;
+
+    loop {
+        while !sensor.accel_status().unwrap().xyz_new_data {}
+        if pedometer.update(sensor.accel_data().unwrap()) {
+            println!("Steps: {}", pedometer.steps());
+        }
+
+        samples += 1;
+        if samples < SAMPLES_PER_SCROLL_STEP {
+            continue;
+        }
+        samples = 0;
+
+        // Show the next step of the scrolling text. Once the count has
+        // scrolled through completely, start over with the latest count.
+        let leds = scroll::steps(&text).nth(scroll_step);
+        match leds {
+            Some(leds) => {
+                display_leds(leds);
+                scroll_step += 1;
+            }
+            None => {
+                text.clear();
+                write!(text, "{}", pedometer.steps()).unwrap();
+                scroll_step = 0;
+            }
+        }
+    }
+}
@@ -0,0 +1,100 @@
+#![deny(unsafe_code)]
+#![no_main]
+#![no_std]
+
+use cortex_m_rt::entry;
+use discovery_support::{init_log, println};
+
+// Panics are printed the same way as everything else
+#[cfg(feature = "defmt")]
+use panic_probe as _;
+#[cfg(not(feature = "defmt"))]
+use panic_rtt_target as _;
+
+#[cfg(feature = "v1")]
+use microbit::{hal::twi, pac::twi0::frequency::FREQUENCY_A};
+
+#[cfg(feature = "v2")]
+use microbit::{hal::twim, pac::twim0::frequency::FREQUENCY_A};
+
+use lsm303agr::{AccelOutputDataRate, AccelScale, Lsm303agr};
+
+use microbit::display::blocking::Display;
+use microbit::hal::timer::Timer;
+
+#[path = "../src/accel.rs"]
+mod accel;
+
+#[path = "../src/tap.rs"]
+mod tap;
+use tap::{Tap, TapConfig, TapDetector};
+
+/// The roulette from the LED roulette chapter.
+const PIXELS: [(usize, usize); 16] = [
+    (0, 0),
+    (0, 1),
+    (0, 2),
+    (0, 3),
+    (0, 4),
+    (1, 4),
+    (2, 4),
+    (3, 4),
+    (4, 4),
+    (4, 3),
+    (4, 2),
+    (4, 1),
+    (4, 0),
+    (3, 0),
+    (2, 0),
+    (1, 0),
+];
+
+#[entry]
+fn main() -> ! {
+    const ODR: AccelOutputDataRate = AccelOutputDataRate::Hz400;
+    const SCALE: AccelScale = AccelScale::G2;
+
+    init_log!();
+    let board = microbit::Board::take().unwrap();
+
+    #[cfg(feature = "v1")]
+    let i2c = { twi::Twi::new(board.TWI0, board.i2c.into(), FREQUENCY_A::K100) };
+
+    #[cfg(feature = "v2")]
+    let i2c = { twim::Twim::new(board.TWIM0, board.i2c_internal.into(), FREQUENCY_A::K100) };
+
+    let mut timer = Timer::new(board.TIMER0);
+    let mut display = Display::new(board.display_pins);
+
+    let mut sensor = Lsm303agr::new_with_i2c(i2c);
+    sensor.init().unwrap();
+    sensor.set_accel_odr(ODR).unwrap();
+    sensor.set_accel_scale(SCALE).unwrap();
+    let mut taps = TapDetector::new(sensor.destroy(), ODR, SCALE, TapConfig::default()).unwrap();
+
+    let mut current = 0;
+    let mut forwards = true;
+
+    loop {
+        // A single tap moves the LED one step, a double tap turns the
+        // roulette around
+        match taps.poll().unwrap() {
+            Some(Tap::Single) => {
+                current = if forwards {
+                    (current + 1) % PIXELS.len()
+                } else {
+                    (current + PIXELS.len() - 1) % PIXELS.len()
+                };
+            }
+            Some(Tap::Double) => {
+                println!("Turning around");
+                forwards = !forwards;
+            }
+            None => {}
+        }
+
+        let mut leds = [[0; 5]; 5];
+        leds[PIXELS[current].0][PIXELS[current].1] = 1;
+        display.show(&mut timer, leds, 10);
+    }
+}
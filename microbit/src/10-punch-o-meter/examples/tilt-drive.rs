@@ -0,0 +1,57 @@
+#![deny(unsafe_code)]
+#![no_main]
+#![no_std]
+
+use cortex_m_rt::entry;
+use discovery_support::{init_log, println};
+
+// Panics are printed the same way as everything else
+#[cfg(feature = "defmt")]
+use panic_probe as _;
+#[cfg(not(feature = "defmt"))]
+use panic_rtt_target as _;
+
+use discovery_logic::drive::{from_tilt, Ramp};
+use discovery_support::motors::{Motor, MotorDriver, MotorPins};
+use lsm303agr::{AccelOutputDataRate, Lsm303agr};
+use microbit::{hal::gpio::Level, hal::twim, pac::twim0::frequency::FREQUENCY_A};
+
+/// How much the speed of a motor changes with every reading, at 50 readings
+/// a second. From standing still to full speed takes half a second.
+const RAMP_STEP: i16 = 40;
+
+#[entry]
+fn main() -> ! {
+    init_log!();
+    let board = microbit::Board::take().unwrap();
+
+    let i2c = twim::Twim::new(board.TWIM0, board.i2c_internal.into(), FREQUENCY_A::K100);
+    let mut sensor = Lsm303agr::new_with_i2c(i2c);
+    sensor.init().unwrap();
+    sensor.set_accel_odr(AccelOutputDataRate::Hz50).unwrap();
+
+    // The pins of Kitronik's motor driver board, see `motors`
+    let pins = MotorPins {
+        left_forward: board.pins.p0_10.into_push_pull_output(Level::Low).degrade(),
+        left_back: board.pins.p0_12.into_push_pull_output(Level::Low).degrade(),
+        right_forward: board.pins.p0_02.into_push_pull_output(Level::Low).degrade(),
+        right_back: board.pins.p1_02.into_push_pull_output(Level::Low).degrade(),
+    };
+    let mut motors = MotorDriver::new(board.PWM0, pins);
+    let mut left = Ramp::new(RAMP_STEP);
+    let mut right = Ramp::new(RAMP_STEP);
+
+    loop {
+        if sensor.accel_status().unwrap().xyz_new_data {
+            let data = sensor.accel_data().unwrap();
+            // Gravity points up, towards the edge that's raised: X leans
+            // left, and Y forward, towards the edge with the USB connector
+            // going down
+            let (left_target, right_target) = from_tilt(-data.x, data.y);
+            let (left_speed, right_speed) = (left.update(left_target), right.update(right_target));
+            motors.set_speed(Motor::Left, left_speed);
+            motors.set_speed(Motor::Right, right_speed);
+            println!("left {}, right {}", left_speed, right_speed);
+        }
+    }
+}
@@ -0,0 +1,55 @@
+#![deny(unsafe_code)]
+#![no_main]
+#![no_std]
+
+use cortex_m_rt::entry;
+use discovery_support::{init_log, println};
+
+// Panics are printed the same way as everything else
+#[cfg(feature = "defmt")]
+use panic_probe as _;
+#[cfg(not(feature = "defmt"))]
+use panic_rtt_target as _;
+
+use discovery_support::servo::{Servo, MAX_ANGLE};
+use lsm303agr::{AccelOutputDataRate, Lsm303agr};
+use microbit::{hal::gpio::Level, hal::twim, pac::twim0::frequency::FREQUENCY_A};
+
+/// How much of every new reading goes into the angle. Less is smoother, but
+/// slower to follow.
+const SMOOTHING: f32 = 0.2;
+
+#[entry]
+fn main() -> ! {
+    init_log!();
+    let board = microbit::Board::take().unwrap();
+
+    let i2c = twim::Twim::new(board.TWIM0, board.i2c_internal.into(), FREQUENCY_A::K100);
+    let mut sensor = Lsm303agr::new_with_i2c(i2c);
+    sensor.init().unwrap();
+    sensor.set_accel_odr(AccelOutputDataRate::Hz50).unwrap();
+
+    // The servo's signal wire goes on ring P0
+    let pin = board.pins.p0_02.into_push_pull_output(Level::Low).degrade();
+    let mut servo = Servo::new(board.PWM0, pin);
+    // Uncomment, and adjust, if your servo doesn't get all the way to the
+    // ends, or buzzes when it gets there
+    // servo.calibrate(600, 2_400);
+
+    let mut tilt = 0.0;
+    loop {
+        if sensor.accel_status().unwrap().xyz_new_data {
+            let data = sensor.accel_data().unwrap();
+            // How far the board leans to one side or the other, from -90° to
+            // 90°. Gravity pulls along X as much as the board leans, about
+            // 1000 mg along it when the board stands on its edge.
+            let x = (data.x as f32 / 1_000.0).clamp(-1.0, 1.0);
+            let new_tilt = libm::asinf(x).to_degrees();
+            tilt += (new_tilt - tilt) * SMOOTHING;
+
+            let angle = (tilt + 90.0) as u16;
+            servo.set_angle(angle.min(MAX_ANGLE));
+            println!("tilt {}°, servo at {}°", tilt as i16, angle);
+        }
+    }
+}
@@ -0,0 +1,36 @@
+//! Helpers shared by the modules that talk to the accelerometer directly.
+
+use lsm303agr::{AccelOutputDataRate, AccelScale};
+
+/// Measurements per second.
+fn odr_hz(odr: AccelOutputDataRate) -> u32 {
+    match odr {
+        AccelOutputDataRate::Hz1 => 1,
+        AccelOutputDataRate::Hz10 => 10,
+        AccelOutputDataRate::Hz25 => 25,
+        AccelOutputDataRate::Hz50 => 50,
+        AccelOutputDataRate::Hz100 => 100,
+        AccelOutputDataRate::Hz200 => 200,
+        AccelOutputDataRate::Hz400 => 400,
+        AccelOutputDataRate::Khz1_344 => 1344,
+        AccelOutputDataRate::Khz1_620LowPower => 1620,
+        AccelOutputDataRate::Khz5_376LowPower => 5376,
+    }
+}
+
+/// The largest acceleration that can be measured, in mg.
+#[allow(dead_code)]
+pub fn full_scale_mg(scale: AccelScale) -> u32 {
+    match scale {
+        AccelScale::G2 => 2000,
+        AccelScale::G4 => 4000,
+        AccelScale::G8 => 8000,
+        AccelScale::G16 => 16000,
+    }
+}
+
+/// Convert `ms` into a number of measurements, for the registers that count
+/// time that way. `max` is the largest value the register can hold.
+pub fn ms_to_samples(ms: u16, odr: AccelOutputDataRate, max: u8) -> u8 {
+    (u32::from(ms) * odr_hz(odr) / 1000).min(u32::from(max)) as u8
+}
@@ -0,0 +1,114 @@
+//! Reading the accelerometer's FIFO in bursts.
+//!
+//! A punch is over in a few milliseconds, so at 50 Hz we are lucky if a single
+//! measurement lands anywhere near the peak. Running the accelerometer faster
+//! only helps if we keep up with it, and reading one measurement per I2C
+//! transaction doesn't. The LSM303AGR can instead collect up to 32
+//! measurements in a FIFO, which we then fetch all at once: the output
+//! registers wrap around from Z back to X and hand out the next measurement
+//! from the FIFO every time they do.
+//!
+//! The `lsm303agr` driver doesn't support the FIFO, so this talks to the
+//! accelerometer directly. Configure it with the driver first, then take the
+//! bus back with `Lsm303agr::destroy` and hand it over to `Fifo::new`.
+
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+use lsm303agr::{AccelMode, AccelScale, Measurement};
+
+/// How many measurements fit into the FIFO.
+pub const FIFO_LEN: usize = 32;
+
+const ACCEL_ADDRESS: u8 = 0x19;
+const CTRL_REG5_A: u8 = 0x24;
+const OUT_X_L_A: u8 = 0x28;
+const FIFO_CTRL_REG_A: u8 = 0x2E;
+const FIFO_SRC_REG_A: u8 = 0x2F;
+/// Setting the top bit of a register address makes the address increment
+/// after every byte.
+const AUTO_INCREMENT: u8 = 1 << 7;
+const FIFO_EN: u8 = 1 << 6;
+/// Stream mode: once the FIFO is full, the oldest measurement is dropped.
+const FM_STREAM: u8 = 0b10 << 6;
+const OVRN_FIFO: u8 = 1 << 6;
+const FSS_MASK: u8 = 0b1_1111;
+
+pub struct Fifo<I> {
+    i2c: I,
+    /// The output registers are left aligned, what to divide them by to get
+    /// the actual value.
+    resolution_factor: i16,
+    /// mg per digit.
+    scaling_factor: i32,
+}
+
+impl<I, E> Fifo<I>
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// Turn on the FIFO. `mode` and `scale` have to be what the accelerometer
+    /// was set to, they decide how the measurements are converted to mg.
+    pub fn new(mut i2c: I, mode: AccelMode, scale: AccelScale) -> Result<Fifo<I>, E> {
+        i2c.write(ACCEL_ADDRESS, &[CTRL_REG5_A, FIFO_EN])?;
+        i2c.write(ACCEL_ADDRESS, &[FIFO_CTRL_REG_A, FM_STREAM])?;
+
+        // The same conversion `Lsm303agr::accel_data` does
+        let (resolution_factor, scaling_factor) = match mode {
+            AccelMode::PowerDown => (1, 0),
+            AccelMode::HighResolution => (1 << 4, 1),
+            AccelMode::Normal => (1 << 6, 4),
+            AccelMode::LowPower => (1 << 8, 16),
+        };
+        let scaling_factor = match scale {
+            AccelScale::G2 => scaling_factor,
+            AccelScale::G4 => scaling_factor * 2,
+            AccelScale::G8 => scaling_factor * 4,
+            AccelScale::G16 => scaling_factor * 8,
+        };
+
+        Ok(Fifo {
+            i2c,
+            resolution_factor,
+            scaling_factor,
+        })
+    }
+
+    /// How many measurements are waiting in the FIFO.
+    pub fn available(&mut self) -> Result<usize, E> {
+        let mut src = [0];
+        self.i2c
+            .write_read(ACCEL_ADDRESS, &[FIFO_SRC_REG_A], &mut src)?;
+        if src[0] & OVRN_FIFO != 0 {
+            Ok(FIFO_LEN)
+        } else {
+            Ok((src[0] & FSS_MASK) as usize)
+        }
+    }
+
+    /// Take as many measurements out of the FIFO as are available and fit into
+    /// `measurements`, oldest first, in a single I2C transaction. Returns how
+    /// many there were.
+    pub fn read(&mut self, measurements: &mut [Measurement]) -> Result<usize, E> {
+        let count = self.available()?.min(measurements.len()).min(FIFO_LEN);
+        if count == 0 {
+            return Ok(0);
+        }
+
+        let mut data = [0; 6 * FIFO_LEN];
+        let data = &mut data[..6 * count];
+        self.i2c
+            .write_read(ACCEL_ADDRESS, &[OUT_X_L_A | AUTO_INCREMENT], data)?;
+
+        for (measurement, raw) in measurements.iter_mut().zip(data.chunks_exact(6)) {
+            let axis = |low: u8, high: u8| {
+                i32::from(i16::from_le_bytes([low, high]) / self.resolution_factor)
+                    * self.scaling_factor
+            };
+            *measurement = Measurement {
+                x: axis(raw[0], raw[1]),
+                y: axis(raw[2], raw[3]),
+                z: axis(raw[4], raw[5]),
+            };
+        }
+        Ok(count)
+    }
+}
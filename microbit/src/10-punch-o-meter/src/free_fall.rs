@@ -0,0 +1,105 @@
+//! Free-fall detection with the accelerometer's interrupt engine.
+//!
+//! While the board is falling, the accelerometer measures (almost) nothing on
+//! any axis. The LSM303AGR can watch for that by itself: once all three axes
+//! stay below a threshold for long enough, it raises an interrupt on its INT1
+//! pin and keeps it raised until we acknowledge it.
+//!
+//! The `lsm303agr` driver doesn't support this, so just like `fifo.rs`, this
+//! takes over the bus after the sensor has been configured.
+
+use crate::accel::ms_to_samples;
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+use lsm303agr::{AccelOutputDataRate, AccelScale};
+
+const ACCEL_ADDRESS: u8 = 0x19;
+const CTRL_REG3_A: u8 = 0x22;
+const CTRL_REG5_A: u8 = 0x24;
+const CTRL_REG6_A: u8 = 0x25;
+const INT1_CFG_A: u8 = 0x30;
+const INT1_SRC_A: u8 = 0x31;
+const INT1_THS_A: u8 = 0x32;
+const INT1_DURATION_A: u8 = 0x33;
+/// Route the interrupt generator to INT1.
+const I1_AOI1: u8 = 1 << 6;
+/// Keep INT1 raised until INT1_SRC_A is read.
+const LIR_INT1: u8 = 1 << 3;
+/// INT1 is active low.
+const H_LACTIVE: u8 = 1 << 1;
+/// Interrupt when all of the enabled events happen at once...
+const AOI: u8 = 1 << 7;
+/// ...and the enabled events are X, Y and Z being low.
+const XYZ_LOW: u8 = (1 << 4) | (1 << 2) | 1;
+/// An interrupt is pending.
+const IA: u8 = 1 << 6;
+
+/// When to consider the board to be falling.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FreeFallConfig {
+    /// All three axes have to be below this, in mg.
+    pub threshold_mg: u16,
+    /// For at least this long, in ms.
+    pub duration_ms: u16,
+}
+
+impl Default for FreeFallConfig {
+    /// Values that catch a drop from a few centimetres up without going off
+    /// when the board is merely shaken.
+    fn default() -> FreeFallConfig {
+        FreeFallConfig {
+            threshold_mg: 350,
+            duration_ms: 30,
+        }
+    }
+}
+
+pub struct FreeFall<I> {
+    i2c: I,
+}
+
+impl<I, E> FreeFall<I>
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// Set up free-fall detection. `odr` and `scale` have to be what the
+    /// accelerometer was set to, the threshold and duration registers count
+    /// in units that depend on them.
+    pub fn new(
+        mut i2c: I,
+        odr: AccelOutputDataRate,
+        scale: AccelScale,
+        config: FreeFallConfig,
+    ) -> Result<FreeFall<I>, E> {
+        let threshold_lsb_mg = match scale {
+            AccelScale::G2 => 16,
+            AccelScale::G4 => 32,
+            AccelScale::G8 => 62,
+            AccelScale::G16 => 186,
+        };
+        // Both registers are 7 bits wide
+        let threshold = (config.threshold_mg / threshold_lsb_mg).min(0x7F) as u8;
+        let duration = ms_to_samples(config.duration_ms, odr, 0x7F);
+
+        i2c.write(ACCEL_ADDRESS, &[INT1_THS_A, threshold])?;
+        i2c.write(ACCEL_ADDRESS, &[INT1_DURATION_A, duration])?;
+        i2c.write(ACCEL_ADDRESS, &[INT1_CFG_A, AOI | XYZ_LOW])?;
+        i2c.write(ACCEL_ADDRESS, &[CTRL_REG5_A, LIR_INT1])?;
+        i2c.write(ACCEL_ADDRESS, &[CTRL_REG6_A, H_LACTIVE])?;
+        i2c.write(ACCEL_ADDRESS, &[CTRL_REG3_A, I1_AOI1])?;
+
+        let mut free_fall = FreeFall { i2c };
+        // Start from a clean slate in case there is something left over
+        free_fall.acknowledge()?;
+        Ok(free_fall)
+    }
+
+    /// Whether the board has been falling since the last call, releases INT1
+    /// again.
+    pub fn acknowledge(&mut self) -> Result<bool, E> {
+        let mut src = [0];
+        self.i2c
+            .write_read(ACCEL_ADDRESS, &[INT1_SRC_A], &mut src)?;
+        Ok(src[0] & IA != 0)
+    }
+}
@@ -3,12 +3,17 @@
 #![no_std]
 
 use cortex_m_rt::entry;
-use rtt_target::rtt_init_print;
+use discovery_support::init_log;
+
+// Panics are printed the same way as everything else
+#[cfg(feature = "defmt")]
+use panic_probe as _;
+#[cfg(not(feature = "defmt"))]
 use panic_rtt_target as _;
 
 #[entry]
 fn main() -> ! {
-    rtt_init_print!();
+    init_log!();
     let board = microbit::Board::take().unwrap();
 
     loop {}
@@ -0,0 +1,108 @@
+//! Counting steps with the accelerometer.
+//!
+//! Every step shows up as a bump in the total acceleration: the foot hits the
+//! ground, the body is pushed up and then falls back down. To find those bumps
+//! we
+//!
+//! - take the magnitude of the acceleration, so it doesn't matter how the
+//!   board is held,
+//! - smooth it a little to get rid of the jitter,
+//! - subtract a slowly moving average, which takes care of the `1g` of gravity
+//!   and any offset the accelerometer has,
+//! - and count a step every time what is left goes above a threshold. Before
+//!   the next step can be counted it has to drop below a lower threshold
+//!   again, and some time has to pass.
+//!
+//! The averages are kept as fixed point numbers with 4 fractional bits, so
+//! all of this works in integers, without any floating point math.
+
+use lsm303agr::Measurement;
+
+/// This is tuned for 50 measurements per second.
+pub const SAMPLE_RATE_HZ: u32 = 50;
+
+/// Number of fractional bits of the averages.
+const FRACTION_BITS: u32 = 4;
+/// How far above the average the acceleration has to go to count as a step,
+/// in mg.
+const STEP_THRESHOLD_MG: i32 = 150;
+/// How far it has to drop before we look for the next step, in mg.
+const RESET_THRESHOLD_MG: i32 = 50;
+/// Nobody walks faster than about 4 steps per second.
+const MIN_STEP_SAMPLES: u32 = SAMPLE_RATE_HZ / 4;
+
+pub struct Pedometer {
+    /// Smoothed magnitude, averaged over roughly 2 measurements.
+    smoothed: i32,
+    /// Long term average, averaged over roughly 32 measurements.
+    baseline: i32,
+    armed: bool,
+    samples_since_step: u32,
+    steps: u32,
+}
+
+impl Pedometer {
+    pub const fn new() -> Pedometer {
+        Pedometer {
+            smoothed: 1000 << FRACTION_BITS,
+            baseline: 1000 << FRACTION_BITS,
+            armed: true,
+            samples_since_step: 0,
+            steps: 0,
+        }
+    }
+
+    /// Feed a new measurement in mg, returns whether it completed a step.
+    pub fn update(&mut self, measurement: Measurement) -> bool {
+        let magnitude = (magnitude_mg(measurement) as i32) << FRACTION_BITS;
+
+        // Exponential moving averages: move a fraction of the way towards
+        // the new value.
+        self.smoothed += (magnitude - self.smoothed) >> 1;
+        self.baseline += (magnitude - self.baseline) >> 5;
+        self.samples_since_step = self.samples_since_step.saturating_add(1);
+
+        let signal = (self.smoothed - self.baseline) >> FRACTION_BITS;
+        if self.armed && signal > STEP_THRESHOLD_MG && self.samples_since_step >= MIN_STEP_SAMPLES {
+            self.armed = false;
+            self.samples_since_step = 0;
+            self.steps += 1;
+            true
+        } else {
+            if signal < RESET_THRESHOLD_MG {
+                self.armed = true;
+            }
+            false
+        }
+    }
+
+    /// All steps counted so far.
+    pub fn steps(&self) -> u32 {
+        self.steps
+    }
+}
+
+impl Default for Pedometer {
+    fn default() -> Pedometer {
+        Pedometer::new()
+    }
+}
+
+/// The length of the acceleration vector. Up to 16g on every axis this fits
+/// into a `u32` comfortably.
+fn magnitude_mg(measurement: Measurement) -> u32 {
+    let square = |v: i32| v.unsigned_abs().pow(2);
+    isqrt(square(measurement.x) + square(measurement.y) + square(measurement.z))
+}
+
+/// Integer square root, rounded down.
+fn isqrt(n: u32) -> u32 {
+    // Newton's method, starting from a guess that is too large
+    let mut x = n;
+    let mut y = x / 2 + (x & 1);
+    while y < x {
+        x = y;
+        y = (x + n / x) / 2;
+    }
+    x
+}
@@ -0,0 +1,107 @@
+//! Interrupt driven accelerometer sampling.
+//!
+//! Instead of asking the LSM303AGR over and over whether it has a new
+//! measurement, we let it tell us: the accelerometer can drive its INT1 pin
+//! low whenever new data is ready. That pin is wired to the nRF, so a GPIOTE
+//! channel turns the falling edge into an interrupt, the interrupt handler
+//! fetches the measurement and sends it into a channel, and the main loop takes
+//! the measurements out of the channel whenever it gets around to it.
+
+use crate::channel::{Channel, Receiver, Sender};
+use crate::shared::Shared;
+use embedded_hal::blocking::i2c::Write;
+use lsm303agr::{interface::I2cInterface, mode::MagOneShot, Lsm303agr, Measurement};
+use microbit::{
+    hal::{
+        gpio::{Input, Pin, PullUp},
+        gpiote::Gpiote,
+    },
+    pac::{self, interrupt},
+};
+
+#[cfg(feature = "v1")]
+type I2c = microbit::hal::twi::Twi<pac::TWI0>;
+
+#[cfg(feature = "v2")]
+type I2c = microbit::hal::twim::Twim<pac::TWIM0>;
+
+pub type Sensor = Lsm303agr<I2cInterface<I2c>, MagOneShot>;
+
+const ACCEL_ADDRESS: u8 = 0x19;
+const CTRL_REG3_A: u8 = 0x22;
+const CTRL_REG6_A: u8 = 0x25;
+/// Data ready interrupt on INT1.
+const I1_ZYXDA: u8 = 1 << 4;
+/// Interrupt pins are active low.
+const H_LACTIVE: u8 = 1 << 1;
+
+/// Everything the interrupt handler needs.
+struct Sampler {
+    sensor: Sensor,
+    gpiote: Gpiote,
+    sender: Sender<Measurement, SAMPLES_LEN>,
+}
+
+static SAMPLER: Shared<Sampler> = Shared::new();
+static SAMPLES: Channel<Measurement, SAMPLES_LEN> = Channel::new();
+
+/// The channel has room for 31 measurements, more than half a second at 50
+/// Hz. If the main loop falls further behind than that, new measurements are
+/// dropped.
+const SAMPLES_LEN: usize = 32;
+
+/// Where the measurements come out, the oldest first.
+pub type Samples = Receiver<Measurement, SAMPLES_LEN>;
+
+/// Make the accelerometer signal new data on its INT1 pin.
+///
+/// The `lsm303agr` driver doesn't know about interrupts yet, so we write the
+/// two registers involved ourselves. Call this before handing the bus to the
+/// driver, `Lsm303agr::init` leaves these registers alone.
+pub fn route_data_ready<I: Write>(i2c: &mut I) -> Result<(), I::Error> {
+    i2c.write(ACCEL_ADDRESS, &[CTRL_REG3_A, I1_ZYXDA])?;
+    i2c.write(ACCEL_ADDRESS, &[CTRL_REG6_A, H_LACTIVE])
+}
+
+/// Hand the sensor over to the interrupt handler and start sampling.
+///
+/// `int_pin` is the pin the accelerometer's INT1 is connected to. From here
+/// on, new measurements come out of the `Samples` this returns.
+pub fn start(
+    mut sensor: Sensor,
+    int_pin: Pin<Input<PullUp>>,
+    board_gpiote: pac::GPIOTE,
+) -> Samples {
+    let (sender, receiver) = SAMPLES.split().expect("sampling has already been started");
+
+    let gpiote = Gpiote::new(board_gpiote);
+
+    let channel0 = gpiote.channel0();
+    channel0.input_pin(&int_pin).hi_to_lo().enable_interrupt();
+    channel0.reset_events();
+
+    // INT1 stays low until the measurement has been read. If one is already
+    // waiting we would never see a falling edge, so read it now.
+    sensor.accel_data().unwrap();
+
+    SAMPLER.lend(Sampler {
+        sensor,
+        gpiote,
+        sender,
+    });
+    pac::NVIC::unpend(pac::Interrupt::GPIOTE);
+    unsafe {
+        pac::NVIC::unmask(pac::Interrupt::GPIOTE);
+    }
+    receiver
+}
+
+#[interrupt]
+fn GPIOTE() {
+    SAMPLER.with(|sampler| {
+        sampler.gpiote.channel0().reset_events();
+        if let Ok(measurement) = sampler.sensor.accel_data() {
+            sampler.sender.send(measurement).ok();
+        }
+    });
+}
@@ -0,0 +1,132 @@
+//! Tap detection with the accelerometer's click engine.
+//!
+//! A tap on the board is a short spike in acceleration that is over again
+//! almost immediately. The LSM303AGR recognizes these by itself: the spike has
+//! to go above a threshold and come back below it within a time limit. For a
+//! double tap, a second one has to follow within a time window, after a
+//! latency period in which the first one is still ringing out.
+//!
+//! Like `free_fall.rs`, this takes over the bus after the sensor has been
+//! configured.
+
+use crate::accel::{full_scale_mg, ms_to_samples};
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+use lsm303agr::{AccelOutputDataRate, AccelScale};
+
+const ACCEL_ADDRESS: u8 = 0x19;
+const CLICK_CFG_A: u8 = 0x38;
+const CLICK_SRC_A: u8 = 0x39;
+const CLICK_THS_A: u8 = 0x3A;
+const TIME_LIMIT_A: u8 = 0x3B;
+const TIME_LATENCY_A: u8 = 0x3C;
+const TIME_WINDOW_A: u8 = 0x3D;
+/// Single taps on X, Y and Z.
+const XYZ_SINGLE: u8 = (1 << 4) | (1 << 2) | 1;
+/// Double taps on X, Y and Z.
+const XYZ_DOUBLE: u8 = (1 << 5) | (1 << 3) | (1 << 1);
+/// Keep the tap in CLICK_SRC_A until it has been read.
+const LIR_CLICK: u8 = 1 << 7;
+const IA: u8 = 1 << 6;
+const DCLICK: u8 = 1 << 5;
+const SCLICK: u8 = 1 << 4;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Tap {
+    Single,
+    Double,
+}
+
+/// What counts as a tap.
+#[derive(Clone, Copy, Debug)]
+pub struct TapConfig {
+    /// How hard the board has to be tapped, in mg.
+    pub threshold_mg: u16,
+    /// How quickly the acceleration has to drop again, in ms.
+    pub time_limit_ms: u16,
+    /// How long to ignore the board after a tap before the second tap of a
+    /// double tap can start, in ms.
+    pub latency_ms: u16,
+    /// How long to wait for the second tap after the latency, in ms.
+    pub window_ms: u16,
+    /// Whether to look for double taps at all.
+    pub double_tap: bool,
+}
+
+impl Default for TapConfig {
+    fn default() -> TapConfig {
+        TapConfig {
+            threshold_mg: 1200,
+            time_limit_ms: 50,
+            latency_ms: 100,
+            window_ms: 300,
+            double_tap: true,
+        }
+    }
+}
+
+pub struct TapDetector<I> {
+    i2c: I,
+}
+
+impl<I, E> TapDetector<I>
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// Set up tap detection. `odr` and `scale` have to be what the
+    /// accelerometer was set to. Taps are short, so the ODR should be at least
+    /// 100 Hz.
+    pub fn new(
+        mut i2c: I,
+        odr: AccelOutputDataRate,
+        scale: AccelScale,
+        config: TapConfig,
+    ) -> Result<TapDetector<I>, E> {
+        // The threshold is 7 bits wide and covers the whole scale
+        let threshold =
+            (u32::from(config.threshold_mg) * 128 / full_scale_mg(scale)).min(0x7F) as u8;
+        let axes = if config.double_tap {
+            XYZ_SINGLE | XYZ_DOUBLE
+        } else {
+            XYZ_SINGLE
+        };
+
+        i2c.write(ACCEL_ADDRESS, &[CLICK_THS_A, LIR_CLICK | threshold])?;
+        i2c.write(
+            ACCEL_ADDRESS,
+            &[TIME_LIMIT_A, ms_to_samples(config.time_limit_ms, odr, 0x7F)],
+        )?;
+        i2c.write(
+            ACCEL_ADDRESS,
+            &[TIME_LATENCY_A, ms_to_samples(config.latency_ms, odr, 0xFF)],
+        )?;
+        i2c.write(
+            ACCEL_ADDRESS,
+            &[TIME_WINDOW_A, ms_to_samples(config.window_ms, odr, 0xFF)],
+        )?;
+        i2c.write(ACCEL_ADDRESS, &[CLICK_CFG_A, axes])?;
+
+        let mut detector = TapDetector { i2c };
+        detector.poll()?;
+        Ok(detector)
+    }
+
+    /// The tap that happened since the last call, if any.
+    ///
+    /// The first tap of a double tap is reported as a single tap first, there
+    /// is no way to know a second one is coming.
+    pub fn poll(&mut self) -> Result<Option<Tap>, E> {
+        let mut src = [0];
+        self.i2c
+            .write_read(ACCEL_ADDRESS, &[CLICK_SRC_A], &mut src)?;
+        let src = src[0];
+        if src & IA == 0 {
+            Ok(None)
+        } else if src & DCLICK != 0 {
+            Ok(Some(Tap::Double))
+        } else if src & SCLICK != 0 {
+            Ok(Some(Tap::Single))
+        } else {
+            Ok(None)
+        }
+    }
+}
@@ -0,0 +1,252 @@
+#![no_main]
+#![no_std]
+
+// The same controls and display as the main program
+#[path = "../src/control.rs"]
+mod control;
+#[path = "../src/display.rs"]
+#[allow(dead_code)]
+mod display;
+
+#[path = "../../07-uart/src/line_reader.rs"]
+mod line_reader;
+#[path = "../../07-uart/src/shell.rs"]
+#[allow(dead_code)]
+mod shell;
+
+use core::fmt::Write;
+use cortex_m_rt::entry;
+use discovery_logic::snake::{Game, GameStatus};
+use discovery_support::scheduler::{is_due, Scheduler, Task};
+use discovery_support::scroll::{steps, SCROLL_STEP_MS};
+use discovery_support::serial::{FlowControl, UartePort};
+use discovery_support::settings::{keys, Settings};
+use heapless::String;
+use microbit::{
+    display::nonblocking::{BitImage, GreyscaleImage},
+    hal::{
+        uarte::{self, Baudrate, Parity},
+        Rng,
+    },
+    pac::UARTE0,
+    Board,
+};
+use panic_rtt_target as _;
+use rtt_target::rtt_init_print;
+
+use crate::control::{get_turn, init_buttons};
+use crate::display::{clear_display, display_image, init_display};
+use crate::line_reader::LineReader;
+use crate::shell::{Args, Command, Error, Shell};
+
+/// How long each blink of the final position takes, on and off.
+const BLINK_MS: u32 = 200;
+
+/// Where the game is at. Instead of waiting for the next step, or the end of
+/// a blink, the snake task remembers when it's due, and returns.
+#[derive(Clone, Copy)]
+enum Phase {
+    /// The snake moves on at `next_ms`.
+    Playing { next_ms: u32 },
+    /// The final position blinks, switching `left` more times.
+    GameOver { left: u8, next_ms: u32 },
+    /// The score scrolls across the display, showing step `step` of it at
+    /// `next_ms`.
+    Score { step: usize, next_ms: u32 },
+}
+
+struct Snake {
+    game: Game,
+    phase: Phase,
+    paused: bool,
+    /// What the display showed last while playing.
+    board: [[u8; 5]; 5],
+    /// What scrolls across the display once the game is over.
+    score: String<32>,
+    settings: Settings,
+}
+
+/// Everything the tasks share.
+struct App {
+    snake: Snake,
+    serial: UartePort<UARTE0>,
+    reader: LineReader<32>,
+}
+
+/// Moves the snake along. It runs far more often than the snake moves, and
+/// checks the time to see whether there's anything to do.
+fn snake_task(app: &mut App, now_ms: u32) {
+    let snake = &mut app.snake;
+    if snake.paused {
+        return;
+    }
+
+    match snake.phase {
+        Phase::Playing { next_ms } if is_due(next_ms, now_ms) => {
+            snake.game.step(get_turn(true));
+            snake.board = snake.game.game_matrix(6, 3, 9);
+            display_image(&GreyscaleImage::new(&snake.board));
+            let next_ms = now_ms.wrapping_add(snake.game.step_len_ms());
+            snake.phase = match snake.game.status {
+                GameStatus::Ongoing => Phase::Playing { next_ms },
+                _ => Phase::GameOver { left: 6, next_ms },
+            };
+        }
+        Phase::GameOver { left, next_ms } if is_due(next_ms, now_ms) => {
+            let next_ms = now_ms.wrapping_add(BLINK_MS);
+            snake.phase = match left {
+                0 => {
+                    if snake.game.is_new_high_score() {
+                        snake
+                            .settings
+                            .set(keys::HIGH_SCORE, &[snake.game.score])
+                            .unwrap();
+                    }
+                    snake.score = snake.game.score_text();
+                    Phase::Score {
+                        step: 0,
+                        next_ms: now_ms,
+                    }
+                }
+                _ => {
+                    if left % 2 == 0 {
+                        clear_display();
+                    } else {
+                        display_image(&GreyscaleImage::new(&snake.board));
+                    }
+                    Phase::GameOver {
+                        left: left - 1,
+                        next_ms,
+                    }
+                }
+            };
+        }
+        Phase::Score { step, next_ms } if is_due(next_ms, now_ms) => {
+            // Going through the steps again from the start every time is
+            // quicker than it sounds, with no more than a few characters
+            let leds = steps(&snake.score).nth(step);
+            snake.phase = match leds {
+                Some(leds) => {
+                    display_image(&BitImage::new(&leds));
+                    let next_ms = now_ms.wrapping_add(SCROLL_STEP_MS);
+                    Phase::Score {
+                        step: step + 1,
+                        next_ms,
+                    }
+                }
+                None => {
+                    clear_display();
+                    snake.game.reset();
+                    Phase::Playing { next_ms: now_ms }
+                }
+            };
+        }
+        _ => {}
+    }
+}
+
+/// Runs a command once a whole line has been typed. The serial port receives
+/// in the background, so this only has to look at what has arrived so far.
+fn shell_task(app: &mut App, _now_ms: u32) {
+    let App {
+        snake,
+        serial,
+        reader,
+    } = app;
+    match reader.poll_line(serial) {
+        Ok(Some(line)) => {
+            SHELL.run(snake, line, serial).unwrap();
+            write!(serial, "> ").unwrap();
+        }
+        Ok(None) => {}
+        Err(error) => write!(serial, "\r\nserial error: {:?}\r\n> ", error).unwrap(),
+    }
+}
+
+fn score(snake: &mut Snake, args: Args, out: &mut dyn Write) -> Result<(), Error> {
+    args.end()?;
+    write!(
+        out,
+        "score {}, best {}\r\n",
+        snake.game.score,
+        snake.game.high_score()
+    )?;
+    Ok(())
+}
+
+fn pause(snake: &mut Snake, args: Args, out: &mut dyn Write) -> Result<(), Error> {
+    args.end()?;
+    snake.paused = !snake.paused;
+    write!(
+        out,
+        "{}\r\n",
+        if snake.paused { "paused" } else { "playing" }
+    )?;
+    Ok(())
+}
+
+const SHELL: Shell<Snake> = Shell::new(&[
+    Command {
+        name: "score",
+        usage: "score",
+        run: score,
+    },
+    Command {
+        name: "pause",
+        usage: "pause",
+        run: pause,
+    },
+]);
+
+/// The snake needs checking often enough that a step is never late by much,
+/// the serial port often enough that typing doesn't feel sluggish.
+const TASKS: [Task<App>; 2] = [
+    Task {
+        period_ms: 10,
+        run: snake_task,
+    },
+    Task {
+        period_ms: 20,
+        run: shell_task,
+    },
+];
+
+#[entry]
+fn main() -> ! {
+    rtt_init_print!();
+    let mut board = Board::take().unwrap();
+    let mut rng = Rng::new(board.RNG);
+    let mut game = Game::new(rng.random_u32());
+    let settings = Settings::take().unwrap();
+    if let Some(&[high_score]) = settings.get(keys::HIGH_SCORE).as_deref() {
+        game.set_high_score(high_score);
+    }
+
+    init_buttons(board.GPIOTE, board.buttons);
+    init_display(board.TIMER1, board.display_pins);
+
+    let serial = uarte::Uarte::new(
+        board.UARTE0,
+        board.uart.into(),
+        Parity::EXCLUDED,
+        Baudrate::BAUD115200,
+    );
+    let mut serial = UartePort::new(serial, FlowControl::Disabled);
+    // Typing has to be received while the tasks are busy with something else
+    serial.enable_rx_interrupt();
+    write!(serial, "> ").unwrap();
+
+    let mut app = App {
+        snake: Snake {
+            game,
+            phase: Phase::Playing { next_ms: 0 },
+            paused: false,
+            board: [[0; 5]; 5],
+            score: String::new(),
+            settings,
+        },
+        serial,
+        reader: LineReader::new(),
+    };
+    Scheduler::new(board.TIMER0, &TASKS, &mut board.SCB).run(&mut app)
+}
@@ -1,12 +1,15 @@
 use core::cell::RefCell;
 use cortex_m::interrupt::{free, Mutex};
+use discovery_logic::snake::Turn;
+use discovery_support::shared::Shared;
 use microbit::{
     board::Buttons,
     hal::gpiote::Gpiote,
-    pac::{self, interrupt}
+    pac::{self, interrupt},
 };
-use crate::game::Turn;
-static GPIO: Mutex<RefCell<Option<Gpiote>>> = Mutex::new(RefCell::new(None));
+static GPIO: Shared<Gpiote> = Shared::new();
+// There's always a turn, `Turn::None` to begin with, so this one doesn't need
+// the `Option` of a `Shared`
 static TURN: Mutex<RefCell<Turn>> = Mutex::new(RefCell::new(Turn::None));
 
 /// Initialise the buttons and enable interrupts.
@@ -27,14 +30,11 @@ pub(crate) fn init_buttons(board_gpiote: pac::GPIOTE, board_buttons: Buttons) {
         .enable_interrupt();
     channel1.reset_events();
 
-    free(move |cs| {
-        *GPIO.borrow(cs).borrow_mut() = Some(gpiote);
-
-        unsafe {
-            pac::NVIC::unmask(pac::Interrupt::GPIOTE);
-        }
-        pac::NVIC::unpend(pac::Interrupt::GPIOTE);
-    });
+    GPIO.lend(gpiote);
+    pac::NVIC::unpend(pac::Interrupt::GPIOTE);
+    unsafe {
+        pac::NVIC::unmask(pac::Interrupt::GPIOTE);
+    }
 }
 
 /// Get the next turn (ie, the turn corresponding to the most recently pressed button).
@@ -50,21 +50,19 @@ pub fn get_turn(reset: bool) -> Turn {
 
 #[pac::interrupt]
 fn GPIOTE() {
-    free(|cs| {
-        if let Some(gpiote) = GPIO.borrow(cs).borrow().as_ref() {
-            let a_pressed = gpiote.channel0().is_event_triggered();
-            let b_pressed = gpiote.channel1().is_event_triggered();
+    GPIO.with(|gpiote| {
+        let a_pressed = gpiote.channel0().is_event_triggered();
+        let b_pressed = gpiote.channel1().is_event_triggered();
 
-            let turn = match (a_pressed, b_pressed) {
-                (true, false) => Turn::Left,
-                (false, true) => Turn::Right,
-                _ => Turn::None
-            };
+        let turn = match (a_pressed, b_pressed) {
+            (true, false) => Turn::Left,
+            (false, true) => Turn::Right,
+            _ => Turn::None,
+        };
 
-            gpiote.channel0().reset_events();
-            gpiote.channel1().reset_events();
+        gpiote.channel0().reset_events();
+        gpiote.channel1().reset_events();
 
-            *TURN.borrow(cs).borrow_mut() = turn;
-        }
+        free(|cs| *TURN.borrow(cs).borrow_mut() = turn);
     });
-}
\ No newline at end of file
+}
@@ -1,23 +1,29 @@
 #![no_main]
 #![no_std]
 
-mod game;
 mod control;
 mod display;
 
 use cortex_m_rt::entry;
+use discovery_logic::snake::{Game, GameStatus};
+#[cfg(feature = "v2")]
+use discovery_support::pcm::Pcm;
+use discovery_support::scroll::{steps, SCROLL_STEP_MS};
+use discovery_support::settings::{keys, Settings};
 use microbit::{
-    Board,
+    display::nonblocking::GreyscaleImage,
     hal::{prelude::*, Rng, Timer},
-    display::nonblocking::{BitImage, GreyscaleImage}
+    Board,
 };
-use rtt_target::rtt_init_print;
 use panic_rtt_target as _;
+use rtt_target::rtt_init_print;
 
 use crate::control::{get_turn, init_buttons};
-use crate::display::{clear_display, display_image, init_display};
-use crate::game::{Game, GameStatus};
+use crate::display::{clear_display, display_image, display_leds, init_display};
 
+/// "Wah wah wah wahhh", as 8 kHz unsigned 8 bit samples.
+#[cfg(feature = "v2")]
+const GAME_OVER: &[u8] = include_bytes!("../assets/game-over.u8");
 
 #[entry]
 fn main() -> ! {
@@ -27,28 +33,45 @@ fn main() -> ! {
     let mut rng = Rng::new(board.RNG);
     let mut game = Game::new(rng.random_u32());
 
+    let mut settings = Settings::take().unwrap();
+    if let Some(&[high_score]) = settings.get(keys::HIGH_SCORE).as_deref() {
+        game.set_high_score(high_score);
+    }
+
     init_buttons(board.GPIOTE, board.buttons);
     init_display(board.TIMER1, board.display_pins);
-
+    #[cfg(feature = "v2")]
+    let mut pcm = Pcm::new(board.PWM1, board.speaker_pin);
 
     loop {
-        loop {  // Game loop
+        loop {
+            // Game loop
             let image = GreyscaleImage::new(&game.game_matrix(6, 3, 9));
             display_image(&image);
             timer.delay_ms(game.step_len_ms());
             match game.status {
                 GameStatus::Ongoing => game.step(get_turn(true)),
                 _ => {
+                    #[cfg(feature = "v2")]
+                    pcm.play(GAME_OVER);
                     for _ in 0..3 {
                         clear_display();
                         timer.delay_ms(200u32);
                         display_image(&image);
                         timer.delay_ms(200u32);
                     }
+                    if game.is_new_high_score() {
+                        settings.set(keys::HIGH_SCORE, &[game.score]).unwrap();
+                    }
+                    // The `scroll` module would show the text on the
+                    // display of `discovery-support`, so we show its steps
+                    // on ours
+                    for leds in steps(&game.score_text()) {
+                        display_leds(leds);
+                        timer.delay_ms(SCROLL_STEP_MS);
+                    }
                     clear_display();
-                    display_image(&BitImage::new(&game.score_matrix()));
-                    timer.delay_ms(2000u32);
-                    break
+                    break;
                 }
             }
         }
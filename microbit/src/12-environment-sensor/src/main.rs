@@ -0,0 +1,70 @@
+#![deny(unsafe_code)]
+#![no_main]
+#![no_std]
+
+use core::fmt::Write;
+use cortex_m_rt::entry;
+use discovery_support::{init_log, println};
+
+// Panics are printed the same way as everything else
+#[cfg(not(feature = "semihosting"))]
+use panic_rtt_target as _;
+#[cfg(feature = "semihosting")]
+use panic_semihosting as _;
+
+use bme280::i2c::BME280;
+use discovery_support::delay::SysTickDelay;
+use discovery_support::scroll::scroll_text;
+use heapless::String;
+use microbit::{display::blocking::Display, hal::Timer};
+
+#[cfg(feature = "v1")]
+use microbit::{hal::twi, pac::twi0::frequency::FREQUENCY_A};
+
+#[cfg(feature = "v2")]
+use microbit::{hal::twim, pac::twim0::frequency::FREQUENCY_A};
+
+#[entry]
+fn main() -> ! {
+    init_log!();
+    let board = microbit::Board::take().unwrap();
+
+    // The v1 only has a single I2C bus, which is also connected to the edge
+    // connector
+    #[cfg(feature = "v1")]
+    let i2c = { twi::Twi::new(board.TWI0, board.i2c.into(), FREQUENCY_A::K100) };
+
+    #[cfg(feature = "v2")]
+    let i2c = { twim::Twim::new(board.TWIM0, board.i2c_external.into(), FREQUENCY_A::K100) };
+
+    let mut display = Display::new(board.display_pins);
+    let mut timer = Timer::new(board.TIMER1);
+
+    // The driver needs a delay of its own to wait for measurements to finish.
+    // A few milliseconds at a time only, which `SysTick` does without taking
+    // up a `TIMER`
+    let mut bme280 = BME280::new_primary(i2c, SysTickDelay::new(board.SYST));
+    bme280.init().unwrap();
+
+    let mut text: String<32> = String::new();
+    loop {
+        let measurements = bme280.measure().unwrap();
+        println!(
+            "{:.1}°C, {:.0}% relative humidity, {:.0} hPa",
+            measurements.temperature,
+            measurements.humidity,
+            measurements.pressure / 100.
+        );
+
+        text.clear();
+        write!(
+            text,
+            "{:.1}C {:.0}% {:.0}HPA",
+            measurements.temperature,
+            measurements.humidity,
+            measurements.pressure / 100.
+        )
+        .unwrap();
+        scroll_text(&mut display, &mut timer, &text);
+    }
+}
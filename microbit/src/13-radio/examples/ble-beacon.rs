@@ -0,0 +1,100 @@
+#![no_main]
+#![no_std]
+
+use cortex_m_rt::entry;
+use discovery_support::{init_log, println};
+use panic_rtt_target as _;
+
+use libm::sqrtf;
+use lsm303agr::{AccelOutputDataRate, Lsm303agr};
+use microbit::hal::{clocks::Clocks, prelude::*, Temp, Timer};
+
+#[cfg(feature = "v1")]
+use microbit::{hal::twi, pac::twi0::frequency::FREQUENCY_A};
+
+#[cfg(feature = "v2")]
+use microbit::{hal::twim, pac::twim0::frequency::FREQUENCY_A};
+
+#[path = "../src/ble.rs"]
+mod ble;
+use ble::{device_address, Beacon};
+
+/// How often to advertise. BLE allows anything between 20 ms and 10 s.
+const INTERVAL_MS: u32 = 500;
+
+/// AD structure types.
+const AD_FLAGS: u8 = 0x01;
+const AD_COMPLETE_LOCAL_NAME: u8 = 0x09;
+const AD_MANUFACTURER_DATA: u8 = 0xFF;
+/// Only BLE, no classic Bluetooth.
+const FLAGS_BR_EDR_NOT_SUPPORTED: u8 = 0x04;
+/// The company ID reserved for testing.
+const COMPANY_ID: u16 = 0xFFFF;
+const NAME: &[u8] = b"micro:bit";
+
+#[entry]
+fn main() -> ! {
+    init_log!();
+    let board = microbit::Board::take().unwrap();
+
+    #[cfg(feature = "v1")]
+    let i2c = { twi::Twi::new(board.TWI0, board.i2c.into(), FREQUENCY_A::K100) };
+
+    #[cfg(feature = "v2")]
+    let i2c = { twim::Twim::new(board.TWIM0, board.i2c_internal.into(), FREQUENCY_A::K100) };
+
+    let mut sensor = Lsm303agr::new_with_i2c(i2c);
+    sensor.init().unwrap();
+    sensor.set_accel_odr(AccelOutputDataRate::Hz10).unwrap();
+
+    let clocks = Clocks::new(board.CLOCK).enable_ext_hfosc();
+    let address = device_address(&board.FICR);
+    let mut beacon = Beacon::new(board.RADIO, &clocks, address);
+    let mut temp = Temp::new(board.TEMP);
+    let mut timer = Timer::new(board.TIMER0);
+
+    println!("Advertising as {:02X?}", address);
+
+    loop {
+        // The sensor measures in quarter degrees, we send hundredths
+        let centi_celsius = (temp.measure().to_bits() * 25) as i16;
+        let acceleration = sensor.accel_data().unwrap();
+        let (x, y, z) = (
+            acceleration.x as f32,
+            acceleration.y as f32,
+            acceleration.z as f32,
+        );
+        let magnitude_mg = sqrtf(x * x + y * y + z * z) as u16;
+
+        let company = COMPANY_ID.to_le_bytes();
+        let temperature = centi_celsius.to_le_bytes();
+        let magnitude = magnitude_mg.to_le_bytes();
+
+        let mut data = [0; ble::MAX_DATA_LEN];
+        let mut len = 0;
+        for structure in [
+            &[2, AD_FLAGS, FLAGS_BR_EDR_NOT_SUPPORTED][..],
+            &[NAME.len() as u8 + 1, AD_COMPLETE_LOCAL_NAME][..],
+            NAME,
+            &[
+                7,
+                AD_MANUFACTURER_DATA,
+                company[0],
+                company[1],
+                temperature[0],
+                temperature[1],
+                magnitude[0],
+                magnitude[1],
+            ][..],
+        ]
+        .iter()
+        {
+            data[len..][..structure.len()].copy_from_slice(structure);
+            len += structure.len();
+        }
+
+        println!("{:.2} °C, {} mg", centi_celsius as f32 / 100., magnitude_mg);
+        beacon.advertise(&data[..len]).unwrap();
+        timer.delay_ms(INTERVAL_MS);
+    }
+}
@@ -0,0 +1,90 @@
+#![no_main]
+#![no_std]
+
+use cortex_m_rt::entry;
+use discovery_logic::packet::{self, types, Packet};
+use discovery_support::{init_log, println};
+use panic_rtt_target as _;
+
+use discovery_support::display::{display_image, init_display};
+use discovery_support::esb::{Addresses, Error, Esb};
+use discovery_support::image::Image5x5;
+use microbit::hal::{clocks::Clocks, prelude::*};
+
+/// Both boards send to and listen on the same pipe.
+const PIPE: u8 = 0;
+
+const LEFT: u8 = b'L';
+const RIGHT: u8 = b'R';
+
+/// A command is a single byte.
+const PACKET_LEN: usize = packet::HEADER_LEN + 1 + packet::CRC_LEN;
+
+const ARROW_LEFT: Image5x5 = Image5x5::from_rows([0b00100, 0b01000, 0b11111, 0b01000, 0b00100]);
+const ARROW_RIGHT: Image5x5 = Image5x5::from_rows([0b00100, 0b00010, 0b11111, 0b00010, 0b00100]);
+const TICK: Image5x5 = Image5x5::from_rows([0b00000, 0b00001, 0b00010, 0b10100, 0b01000]);
+const CROSS: Image5x5 = Image5x5::from_rows([0b10001, 0b01010, 0b00100, 0b01010, 0b10001]);
+
+#[entry]
+fn main() -> ! {
+    init_log!();
+    let board = microbit::Board::take().unwrap();
+
+    let clocks = Clocks::new(board.CLOCK).enable_ext_hfosc();
+    let mut esb = Esb::new(board.RADIO, board.TIMER0, &clocks, &Addresses::default());
+    esb.listen(1 << PIPE);
+
+    // The blocking display would keep us from acknowledging in time
+    init_display(board.TIMER1, board.display_pins);
+    let buttons = board.buttons;
+    let mut pressed = (false, false);
+    let mut sequence: u8 = 0;
+
+    loop {
+        let now = (
+            buttons.button_a.is_low().unwrap(),
+            buttons.button_b.is_low().unwrap(),
+        );
+        let command = match now {
+            (true, _) if !pressed.0 => Some(LEFT),
+            (_, true) if !pressed.1 => Some(RIGHT),
+            _ => None,
+        };
+        pressed = now;
+
+        if let Some(command) = command {
+            let packet = Packet {
+                packet_type: types::COMMAND,
+                sequence,
+                payload: &[command],
+            };
+            let mut buffer = [0; PACKET_LEN];
+            let bytes = packet::encode(&packet, &mut buffer).unwrap();
+            sequence = sequence.wrapping_add(1);
+            match esb.send(PIPE, bytes) {
+                Ok(()) => display_image(&TICK),
+                Err(Error::NoAck) => {
+                    println!("The other board didn't answer");
+                    display_image(&CROSS);
+                }
+                Err(error) => println!("Send error: {:?}", error),
+            }
+        }
+
+        if let Ok((_, bytes)) = esb.receive() {
+            match packet::decode(bytes) {
+                Ok(Packet {
+                    packet_type: types::COMMAND,
+                    payload,
+                    ..
+                }) => match payload {
+                    [LEFT] => display_image(&ARROW_LEFT),
+                    [RIGHT] => display_image(&ARROW_RIGHT),
+                    _ => println!("Unknown command {:?}", payload),
+                },
+                Ok(packet) => println!("Ignoring a packet of type {}", packet.packet_type),
+                Err(error) => println!("Bad packet: {:?}", error),
+            }
+        }
+    }
+}
@@ -0,0 +1,158 @@
+#![no_main]
+#![no_std]
+
+use core::fmt::Write;
+use core::str;
+use cortex_m_rt::entry;
+use discovery_support::{init_log, println};
+use panic_rtt_target as _;
+
+use heapless::Vec;
+use microbit::hal::clocks::Clocks;
+use microbit::hal::uarte::{self, Baudrate, Parity};
+
+#[path = "../src/radio.rs"]
+mod radio;
+use radio::{Radio, MAX_PAYLOAD_LEN};
+
+use discovery_support::serial::{FlowControl, UartePort};
+
+#[path = "../../07-uart/src/line_reader.rs"]
+mod line_reader;
+use line_reader::LineReader;
+
+#[path = "../../07-uart/src/shell.rs"]
+#[allow(dead_code)]
+mod shell;
+use shell::{Args, Command, Error, Shell};
+
+fn show_settings(radio: &Radio, out: &mut dyn Write) -> Result<(), Error> {
+    write!(
+        out,
+        "group {}, channel {} ({} MHz), {} dBm\r\n",
+        radio.group(),
+        radio.channel(),
+        2400 + u32::from(radio.channel()),
+        radio.tx_power()
+    )?;
+    Ok(())
+}
+
+fn settings(radio: &mut Radio, args: Args, out: &mut dyn Write) -> Result<(), Error> {
+    args.end()?;
+    show_settings(radio, out)
+}
+
+fn group(radio: &mut Radio, mut args: Args, out: &mut dyn Write) -> Result<(), Error> {
+    let group = args.parse()?;
+    args.end()?;
+    radio.set_group(group);
+    show_settings(radio, out)
+}
+
+fn channel(radio: &mut Radio, mut args: Args, out: &mut dyn Write) -> Result<(), Error> {
+    let channel = args.parse()?;
+    args.end()?;
+    radio
+        .set_channel(channel)
+        .map_err(|_| Error::InvalidArgument)?;
+    show_settings(radio, out)
+}
+
+fn power(radio: &mut Radio, mut args: Args, out: &mut dyn Write) -> Result<(), Error> {
+    let dbm = args.parse()?;
+    args.end()?;
+    if radio.set_tx_power(dbm).is_err() {
+        write!(out, "the radio can send with")?;
+        for dbm in Radio::tx_powers() {
+            write!(out, " {}", dbm)?;
+        }
+        write!(out, " dBm\r\n")?;
+        return Err(Error::InvalidArgument);
+    }
+    show_settings(radio, out)
+}
+
+fn send(radio: &mut Radio, args: Args, out: &mut dyn Write) -> Result<(), Error> {
+    let mut message: Vec<u8, MAX_PAYLOAD_LEN> = Vec::new();
+    for (index, word) in args.rest().enumerate() {
+        if index > 0 {
+            message.push(b' ').map_err(|_| Error::InvalidArgument)?;
+        }
+        message
+            .extend_from_slice(word.as_bytes())
+            .map_err(|_| Error::InvalidArgument)?;
+    }
+    radio.send(&message).unwrap();
+    write!(out, "sent\r\n")?;
+    Ok(())
+}
+
+const COMMANDS: &[Command<Radio>] = &[
+    Command {
+        name: "settings",
+        usage: "settings",
+        run: settings,
+    },
+    Command {
+        name: "group",
+        usage: "group <0-255>",
+        run: group,
+    },
+    Command {
+        name: "channel",
+        usage: "channel <0-100>",
+        run: channel,
+    },
+    Command {
+        name: "power",
+        usage: "power <dBm>",
+        run: power,
+    },
+    Command {
+        name: "send",
+        usage: "send <message>",
+        run: send,
+    },
+];
+
+#[entry]
+fn main() -> ! {
+    init_log!();
+    let board = microbit::Board::take().unwrap();
+
+    let serial = uarte::Uarte::new(
+        board.UARTE0,
+        board.uart.into(),
+        Parity::EXCLUDED,
+        Baudrate::BAUD115200,
+    );
+    let mut serial = UartePort::new(serial, FlowControl::Disabled);
+    // The radio keeps us busy, so typing has to be received in the background
+    serial.enable_rx_interrupt();
+
+    let clocks = Clocks::new(board.CLOCK).enable_ext_hfosc();
+    let mut radio = Radio::new(board.RADIO, &clocks);
+
+    let shell = Shell::new(COMMANDS);
+    let mut reader: LineReader<64> = LineReader::new();
+    show_settings(&radio, &mut serial).unwrap();
+    write!(serial, "> ").unwrap();
+    loop {
+        if let Ok(payload) = radio.receive() {
+            match str::from_utf8(payload) {
+                Ok(text) => write!(serial, "\r\n{}\r\n> ", text).unwrap(),
+                Err(_) => println!("Ignoring a message that isn't text"),
+            }
+        }
+
+        match reader.poll_line(&mut serial) {
+            Ok(Some(line)) => {
+                shell.run(&mut radio, line, &mut serial).unwrap();
+                write!(serial, "> ").unwrap();
+            }
+            Ok(None) => {}
+            Err(error) => write!(serial, "\r\nserial error: {:?}\r\n> ", error).unwrap(),
+        }
+    }
+}
@@ -0,0 +1,55 @@
+#![deny(unsafe_code)]
+#![no_main]
+#![no_std]
+
+use cortex_m_rt::entry;
+use discovery_support::{init_log, println};
+use panic_rtt_target as _;
+
+use discovery_support::radio::{Error, Radio};
+use discovery_support::scroll::scroll_text;
+use microbit::display::blocking::Display;
+use microbit::hal::{clocks::Clocks, prelude::*, Timer};
+
+/// Set the same group with `radio.setGroup` in MakeCode, or
+/// `radio.config(group=...)` in MicroPython.
+const GROUP: u8 = 1;
+
+#[entry]
+fn main() -> ! {
+    init_log!();
+    let board = microbit::Board::take().unwrap();
+
+    let clocks = Clocks::new(board.CLOCK).enable_ext_hfosc();
+    let mut radio = Radio::new(board.RADIO, &clocks, GROUP);
+
+    let mut timer = Timer::new(board.TIMER0);
+    let mut display = Display::new(board.display_pins);
+    let buttons = board.buttons;
+    let mut pressed = (false, false);
+
+    loop {
+        let now = (
+            buttons.button_a.is_low().unwrap(),
+            buttons.button_b.is_low().unwrap(),
+        );
+        // Only send once per press
+        if now.0 && !pressed.0 {
+            radio.send_string("Hello").unwrap();
+        }
+        if now.1 && !pressed.1 {
+            radio.send_string("from Rust").unwrap();
+        }
+        pressed = now;
+
+        match radio.receive_string() {
+            Ok(text) => {
+                println!("Received {:?}", text);
+                scroll_text(&mut display, &mut timer, text);
+            }
+            Err(nb::Error::Other(Error::NotAString)) => println!("Received something else"),
+            Err(nb::Error::Other(error)) => println!("Receive error: {:?}", error),
+            Err(nb::Error::WouldBlock) => {}
+        }
+    }
+}
@@ -0,0 +1,115 @@
+#![no_main]
+#![no_std]
+
+use cortex_m_rt::entry;
+use discovery_support::{init_log, println};
+use panic_rtt_target as _;
+
+use discovery_logic::pairing::{Event, Key, MessageBytes, Nonce, Pairing};
+use discovery_support::display::{display_image, init_display};
+use discovery_support::image::Image5x5;
+use discovery_support::random::Random;
+use microbit::hal::{clocks::Clocks, prelude::*};
+
+#[path = "../src/radio.rs"]
+mod radio;
+use radio::Radio;
+
+/// Both boards need the same key, and nobody else may know it. Make up one
+/// of your own.
+const KEY: Key = *b"sixteen byte key";
+
+const TICK: Image5x5 = Image5x5::from_rows([0b00000, 0b00001, 0b00010, 0b10100, 0b01000]);
+const CROSS: Image5x5 = Image5x5::from_rows([0b10001, 0b01010, 0b00100, 0b01010, 0b10001]);
+
+fn nonce(random: &mut Random) -> Nonce {
+    let mut nonce = [0; 8];
+    random.fill(&mut nonce);
+    nonce
+}
+
+#[entry]
+fn main() -> ! {
+    init_log!();
+    let board = microbit::Board::take().unwrap();
+
+    let clocks = Clocks::new(board.CLOCK).enable_ext_hfosc();
+    let mut radio = Radio::new(board.RADIO, &clocks);
+    let mut random = Random::new(board.RNG);
+    init_display(board.TIMER1, board.display_pins);
+    display_image(&Image5x5::blank());
+
+    let buttons = board.buttons;
+    let mut pressed = (false, false);
+    let mut pairing = Pairing::new(KEY);
+    let mut presses: u32 = 0;
+    // What we sent last, to try a replay with
+    let mut last: Option<MessageBytes> = None;
+
+    println!("A pairs with the other board, B sends to it");
+    loop {
+        let now = (
+            buttons.button_a.is_low().unwrap(),
+            buttons.button_b.is_low().unwrap(),
+        );
+        if now.0 && !pressed.0 {
+            match last.take() {
+                Some(message) => {
+                    println!("Sending the last message again");
+                    radio.send(&message).unwrap();
+                }
+                None => {
+                    println!("Challenging");
+                    radio.send(&pairing.challenge(nonce(&mut random))).unwrap();
+                }
+            }
+        }
+        if now.1 && !pressed.1 {
+            presses += 1;
+            match pairing.send(&presses.to_le_bytes()) {
+                Ok(message) => {
+                    radio.send(&message).unwrap();
+                    last = Some(message);
+                }
+                Err(error) => println!("Can't send: {:?}", error),
+            }
+        }
+        pressed = now;
+
+        let bytes = match radio.receive() {
+            Ok(bytes) => bytes,
+            Err(nb::Error::WouldBlock) => continue,
+            Err(nb::Error::Other(error)) => {
+                println!("Receive error: {:?}", error);
+                continue;
+            }
+        };
+        match pairing.receive(bytes, nonce(&mut random)) {
+            Ok(Event::Reply(reply)) => {
+                println!("Challenged, answering");
+                radio.send(&reply).unwrap();
+            }
+            Ok(Event::Paired(reply)) => {
+                println!("Paired");
+                if let Some(reply) = reply {
+                    radio.send(&reply).unwrap();
+                }
+                display_image(&TICK);
+            }
+            Ok(Event::Data(payload)) => match payload {
+                &[a, b, c, d] => {
+                    println!(
+                        "The other board's B was pressed {} times",
+                        u32::from_le_bytes([a, b, c, d])
+                    );
+                    display_image(&TICK);
+                }
+                _ => println!("Received {:?}", payload),
+            },
+            Err(error) => {
+                println!("Rejected: {:?}", error);
+                display_image(&CROSS);
+            }
+        }
+    }
+}
@@ -0,0 +1,91 @@
+#![deny(unsafe_code)]
+#![no_main]
+#![no_std]
+
+use cortex_m_rt::entry;
+use discovery_support::{init_log, println};
+use panic_rtt_target as _;
+
+use discovery_support::display::{display_leds, init_display};
+use discovery_support::radio::{Radio, DEFAULT_GROUP};
+use microbit::hal::{clocks::Clocks, prelude::*, Timer};
+
+const PING: &[u8] = b"ping";
+const PING_MS: u32 = 50;
+
+/// Anything weaker is cold, anything stronger is as hot as it gets.
+const COLD_DBM: f32 = -90.0;
+const HOT_DBM: f32 = -40.0;
+/// How much of every new sample goes into the average. Less is smoother, but
+/// slower to follow.
+const SMOOTHING: f32 = 0.2;
+/// Without a ping for this long, the other board is out of range.
+const LOST_US: u32 = 1_000_000;
+
+#[entry]
+fn main() -> ! {
+    init_log!();
+    let board = microbit::Board::take().unwrap();
+
+    let clocks = Clocks::new(board.CLOCK).enable_ext_hfosc();
+    let mut radio = Radio::new(board.RADIO, &clocks, DEFAULT_GROUP);
+
+    let mut timer = Timer::new(board.TIMER0);
+    init_display(board.TIMER1, board.display_pins);
+    let button_a = board.buttons.button_a;
+
+    // Like the frames, both boards run the same program and button A turns
+    // one of them into the beacon
+    loop {
+        if button_a.is_low().unwrap() {
+            println!("Beaconing");
+            display_leds([[0, 0, 1, 0, 0]; 5]);
+            loop {
+                radio.send_datagram(PING).unwrap();
+                timer.delay_ms(PING_MS);
+            }
+        }
+
+        if let Ok(PING) = radio.receive_datagram() {
+            break;
+        }
+    }
+
+    let mut smoothed = radio.rssi() as f32;
+    let mut shown = None;
+    timer.start(LOST_US);
+    loop {
+        match radio.receive_datagram() {
+            Ok(PING) => {
+                smoothed += (radio.rssi() as f32 - smoothed) * SMOOTHING;
+                timer.start(LOST_US);
+            }
+            _ => {
+                if timer.wait().is_ok() {
+                    smoothed = COLD_DBM;
+                }
+            }
+        }
+
+        let bars = strength(smoothed);
+        if shown != Some(bars) {
+            show_bars(bars);
+            shown = Some(bars);
+        }
+    }
+}
+
+/// How many of the 5 bars to light for a signal of `dbm`.
+fn strength(dbm: f32) -> usize {
+    let bars = (dbm - COLD_DBM) / (HOT_DBM - COLD_DBM) * 5.0;
+    bars.clamp(0.0, 5.0) as usize
+}
+
+/// Fill the display from the bottom up, one row per bar.
+fn show_bars(bars: usize) {
+    let mut leds = [[0; 5]; 5];
+    for row in leds.iter_mut().rev().take(bars) {
+        *row = [1; 5];
+    }
+    display_leds(leds);
+}
@@ -0,0 +1,68 @@
+#![deny(unsafe_code)]
+#![no_main]
+#![no_std]
+
+use cortex_m_rt::entry;
+use discovery_support::{init_log, println};
+use panic_rtt_target as _;
+
+use discovery_logic::sniffer::{hexdump, Error};
+use discovery_support::monotonic::{init_monotonic, Instant};
+use discovery_support::radio::{Sniffer, DEFAULT_CHANNEL, MAX_CHANNEL};
+use microbit::hal::{clocks::Clocks, prelude::*};
+
+#[entry]
+fn main() -> ! {
+    init_log!();
+    let board = microbit::Board::take().unwrap();
+
+    let clocks = Clocks::new(board.CLOCK).enable_ext_hfosc().start_lfclk();
+    init_monotonic(&clocks);
+
+    let mut channel = DEFAULT_CHANNEL;
+    let mut sniffer = Sniffer::new(board.RADIO, &clocks, channel);
+    println!("Sniffing on channel {}, A and B to change it", channel);
+
+    let buttons = board.buttons;
+    let mut pressed = (false, false);
+
+    loop {
+        let now = (
+            buttons.button_a.is_low().unwrap(),
+            buttons.button_b.is_low().unwrap(),
+        );
+        let (down, up) = (now.0 && !pressed.0, now.1 && !pressed.1);
+        pressed = now;
+        if down || up {
+            channel = match (down, channel) {
+                (true, 0) => MAX_CHANNEL,
+                (true, channel) => channel - 1,
+                (false, MAX_CHANNEL) => 0,
+                (false, channel) => channel + 1,
+            };
+            sniffer.set_channel(channel);
+            println!("Sniffing on channel {}", channel);
+        }
+
+        // Take the time first, printing takes a while
+        let micros = Instant::now().as_micros();
+        match sniffer.receive() {
+            Ok((capture, rssi)) => {
+                println!(
+                    "{:>6}.{:06} ch {} group {} {} dBm {} bytes{}",
+                    micros / 1_000_000,
+                    micros % 1_000_000,
+                    channel,
+                    capture.group,
+                    rssi,
+                    capture.packet.len(),
+                    if capture.crc_ok { "" } else { ", bad CRC" }
+                );
+                for line in hexdump(capture.packet) {
+                    println!("    {}", line);
+                }
+            }
+            Err(nb::Error::Other(Error::TooLong(_))) | Err(nb::Error::WouldBlock) => {}
+        }
+    }
+}
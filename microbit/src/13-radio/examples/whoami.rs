@@ -0,0 +1,138 @@
+#![no_main]
+#![no_std]
+
+use core::convert::TryInto;
+use core::fmt::Write;
+use core::str;
+use cortex_m_rt::entry;
+use discovery_support::{init_log, println};
+use panic_rtt_target as _;
+
+use discovery_support::device_id::device_id;
+use heapless::Vec;
+use microbit::hal::clocks::Clocks;
+use microbit::hal::uarte::{self, Baudrate, Parity};
+
+#[path = "../src/radio.rs"]
+mod radio;
+use radio::{Radio, MAX_PAYLOAD_LEN};
+
+use discovery_support::serial::{FlowControl, UartePort};
+
+#[path = "../../07-uart/src/line_reader.rs"]
+mod line_reader;
+use line_reader::LineReader;
+
+#[path = "../../07-uart/src/shell.rs"]
+#[allow(dead_code)]
+mod shell;
+use shell::{Args, Command, Error, Shell};
+
+/// Every message starts with the ID of its sender.
+const ID_LEN: usize = 8;
+
+struct Node {
+    radio: Radio,
+    id: u64,
+}
+
+/// The radio address of the board with `id`: the lower half of the ID is the
+/// base address, and the next byte the prefix.
+fn radio_address(id: u64) -> (u32, u8) {
+    (id as u32, (id >> 32) as u8)
+}
+
+fn whoami(node: &mut Node, args: Args, out: &mut dyn Write) -> Result<(), Error> {
+    args.end()?;
+    write!(out, "{:016x}\r\n", node.id)?;
+    Ok(())
+}
+
+fn send(node: &mut Node, mut args: Args, out: &mut dyn Write) -> Result<(), Error> {
+    let to = u64::from_str_radix(args.word()?, 16).map_err(|_| Error::InvalidArgument)?;
+
+    let mut message: Vec<u8, MAX_PAYLOAD_LEN> = Vec::new();
+    message.extend_from_slice(&node.id.to_le_bytes()).unwrap();
+    for (index, word) in args.rest().enumerate() {
+        if index > 0 {
+            message.push(b' ').map_err(|_| Error::InvalidArgument)?;
+        }
+        message
+            .extend_from_slice(word.as_bytes())
+            .map_err(|_| Error::InvalidArgument)?;
+    }
+
+    // Only for as long as it takes to send, then we're back to listening on
+    // our own address
+    let (base, prefix) = radio_address(to);
+    node.radio.set_address(base, prefix);
+    node.radio.send(&message).unwrap();
+    let (base, prefix) = radio_address(node.id);
+    node.radio.set_address(base, prefix);
+
+    write!(out, "sent\r\n")?;
+    Ok(())
+}
+
+const COMMANDS: &[Command<Node>] = &[
+    Command {
+        name: "whoami",
+        usage: "whoami",
+        run: whoami,
+    },
+    Command {
+        name: "send",
+        usage: "send <id> <message>",
+        run: send,
+    },
+];
+
+#[entry]
+fn main() -> ! {
+    init_log!();
+    let board = microbit::Board::take().unwrap();
+
+    let serial = uarte::Uarte::new(
+        board.UARTE0,
+        board.uart.into(),
+        Parity::EXCLUDED,
+        Baudrate::BAUD115200,
+    );
+    let mut serial = UartePort::new(serial, FlowControl::Disabled);
+    // The radio keeps us busy, so typing has to be received in the background
+    serial.enable_rx_interrupt();
+
+    let id = device_id(&board.FICR);
+    println!("This is micro:bit {:016x}", id);
+
+    let clocks = Clocks::new(board.CLOCK).enable_ext_hfosc();
+    let mut radio = Radio::new(board.RADIO, &clocks);
+    let (base, prefix) = radio_address(id);
+    radio.set_address(base, prefix);
+    let mut node = Node { radio, id };
+
+    let shell = Shell::new(COMMANDS);
+    let mut reader: LineReader<64> = LineReader::new();
+    write!(serial, "This is micro:bit {:016x}\r\n> ", id).unwrap();
+    loop {
+        if let Ok(payload) = node.radio.receive() {
+            if payload.len() >= ID_LEN {
+                let (from, text) = payload.split_at(ID_LEN);
+                let from = u64::from_le_bytes(from.try_into().unwrap());
+                match str::from_utf8(text) {
+                    Ok(text) => write!(serial, "\r\n{:016x}: {}\r\n> ", from, text).unwrap(),
+                    Err(_) => println!("Ignoring a message from {:016x} that isn't text", from),
+                }
+            }
+        }
+
+        match reader.poll_line(&mut serial) {
+            Ok(Some(line)) => {
+                shell.run(&mut node, line, &mut serial).unwrap();
+                write!(serial, "> ").unwrap();
+            }
+            Ok(None) => {}
+            Err(error) => write!(serial, "\r\nserial error: {:?}\r\n> ", error).unwrap(),
+        }
+    }
+}
@@ -0,0 +1,146 @@
+//! Bluetooth Low Energy advertising, without a Bluetooth stack.
+//!
+//! Before two BLE devices connect, one of them *advertises*: every now and then
+//! it sends a short packet that tells everyone around who it is. Plenty of
+//! devices never do anything else, they put their data right into those
+//! packets and let anybody listen. That's all a beacon does, and the RADIO
+//! peripheral can do it with a few more registers than our own packets need.
+//!
+//! An advertising packet looks like this on air:
+//!
+//! ```text
+//! | preamble | access address | header | length | address | data          | CRC     |
+//! | 1 byte   | 0x8E89BED6     | 1 byte | 1 byte | 6 bytes | 0 - 31 bytes  | 3 bytes |
+//! ```
+//!
+//! Every advertisement is sent on all three advertising channels, since a
+//! scanner only listens on one of them at a time.
+
+use core::sync::atomic::{compiler_fence, Ordering};
+use discovery_support::device_id::{self, ADDRESS_LEN};
+use microbit::hal::clocks::{Clocks, ExternalOscillator};
+use microbit::pac::{FICR, RADIO};
+
+/// The longest advertising data that fits into a packet.
+pub const MAX_DATA_LEN: usize = 31;
+
+/// The channel index, used to whiten the data, and the frequency above 2400
+/// MHz of each advertising channel.
+const CHANNELS: [(u32, u32); 3] = [(37, 2), (38, 26), (39, 80)];
+/// Every advertisement uses the same access address.
+const ACCESS_ADDRESS_PREFIX: u32 = 0x8E;
+const ACCESS_ADDRESS_BASE: u32 = 0x89BE_D600;
+const CRC_POLY: u32 = 0x00_065B;
+const CRC_INIT: u32 = 0x55_5555;
+
+/// A non connectable advertisement from a random address.
+const ADV_NONCONN_IND: u8 = 0x02;
+const TX_ADD_RANDOM: u8 = 1 << 6;
+
+const MODE_BLE_1MBIT: u32 = 3;
+/// PCNF0: a 1 byte S0 field for the header, then an 8 bit length.
+const PCNF0_S0LEN_1: u32 = 1 << 8;
+const PCNF0_LFLEN_8: u32 = 8;
+/// PCNF1: little endian, whitening on, 3 byte base address.
+const PCNF1_WHITEEN: u32 = 1 << 25;
+const PCNF1_BALEN_3: u32 = 3 << 16;
+/// CRCCNF: 3 byte CRC that doesn't cover the access address.
+const CRCCNF_LEN_3: u32 = 3;
+const CRCCNF_SKIPADDR: u32 = 1 << 8;
+const SHORTS_READY_START: u32 = 1;
+const SHORTS_END_DISABLE: u32 = 1 << 1;
+
+#[derive(Debug)]
+pub enum Error {
+    /// The data is longer than [`MAX_DATA_LEN`].
+    TooLong,
+}
+
+pub struct Beacon {
+    radio: RADIO,
+    address: [u8; ADDRESS_LEN],
+    /// The header, the length, our address and the data.
+    buffer: [u8; 2 + ADDRESS_LEN + MAX_DATA_LEN],
+}
+
+impl Beacon {
+    /// Set up the radio for advertising from `address`. Like all radio
+    /// traffic, this needs the external crystal running.
+    pub fn new<L, LSTAT>(
+        radio: RADIO,
+        _clocks: &Clocks<ExternalOscillator, L, LSTAT>,
+        address: [u8; ADDRESS_LEN],
+    ) -> Beacon {
+        radio.mode.write(|w| unsafe { w.bits(MODE_BLE_1MBIT) });
+        radio.txpower.write(|w| unsafe { w.bits(0) });
+
+        radio
+            .pcnf0
+            .write(|w| unsafe { w.bits(PCNF0_S0LEN_1 | PCNF0_LFLEN_8) });
+        radio.pcnf1.write(|w| unsafe {
+            w.bits(PCNF1_WHITEEN | PCNF1_BALEN_3 | (ADDRESS_LEN + MAX_DATA_LEN) as u32)
+        });
+
+        radio
+            .base0
+            .write(|w| unsafe { w.bits(ACCESS_ADDRESS_BASE) });
+        radio
+            .prefix0
+            .write(|w| unsafe { w.bits(ACCESS_ADDRESS_PREFIX) });
+        radio.txaddress.write(|w| unsafe { w.bits(0) });
+
+        radio
+            .crccnf
+            .write(|w| unsafe { w.bits(CRCCNF_LEN_3 | CRCCNF_SKIPADDR) });
+        radio.crcpoly.write(|w| unsafe { w.bits(CRC_POLY) });
+        radio.crcinit.write(|w| unsafe { w.bits(CRC_INIT) });
+
+        radio
+            .shorts
+            .write(|w| unsafe { w.bits(SHORTS_READY_START | SHORTS_END_DISABLE) });
+
+        Beacon {
+            radio,
+            address,
+            buffer: [0; 2 + ADDRESS_LEN + MAX_DATA_LEN],
+        }
+    }
+
+    /// Send one advertisement carrying `data` on every advertising channel.
+    ///
+    /// `data` is a list of AD structures: each one is a length byte, a type
+    /// byte and `length - 1` bytes of whatever that type holds.
+    pub fn advertise(&mut self, data: &[u8]) -> Result<(), Error> {
+        if data.len() > MAX_DATA_LEN {
+            return Err(Error::TooLong);
+        }
+        self.buffer[0] = ADV_NONCONN_IND | TX_ADD_RANDOM;
+        self.buffer[1] = (ADDRESS_LEN + data.len()) as u8;
+        self.buffer[2..][..ADDRESS_LEN].copy_from_slice(&self.address);
+        self.buffer[2 + ADDRESS_LEN..][..data.len()].copy_from_slice(data);
+
+        for &(index, frequency) in CHANNELS.iter() {
+            self.radio.frequency.write(|w| unsafe { w.bits(frequency) });
+            self.radio.datawhiteiv.write(|w| unsafe { w.bits(index) });
+
+            compiler_fence(Ordering::Release);
+            let packet = self.buffer.as_ptr() as u32;
+            self.radio.packetptr.write(|w| unsafe { w.bits(packet) });
+            self.radio.events_disabled.reset();
+            self.radio.tasks_txen.write(|w| unsafe { w.bits(1) });
+            while self.radio.events_disabled.read().bits() == 0 {}
+            self.radio.events_disabled.reset();
+            compiler_fence(Ordering::Acquire);
+        }
+        Ok(())
+    }
+}
+
+/// The random static address every nRF chip comes with, in the order it is
+/// sent.
+pub fn device_address(ficr: &FICR) -> [u8; ADDRESS_LEN] {
+    let mut address = device_id::device_address(ficr);
+    // A random static address has its two highest bits set
+    address[ADDRESS_LEN - 1] |= 0xC0;
+    address
+}
@@ -0,0 +1,112 @@
+#![no_main]
+#![no_std]
+
+mod radio;
+
+use core::convert::TryInto;
+use cortex_m_rt::entry;
+use discovery_logic::packet::{self, types, Packet};
+use discovery_support::{init_log, println};
+use panic_rtt_target as _;
+
+use discovery_support::display::{display_image, init_display};
+use discovery_support::image::Image5x5;
+use microbit::hal::{clocks::Clocks, prelude::*, Timer};
+
+use crate::radio::{Error, Radio};
+
+/// The roulette from the LED roulette chapter.
+const PIXELS: [(usize, usize); 16] = [
+    (0, 0),
+    (0, 1),
+    (0, 2),
+    (0, 3),
+    (0, 4),
+    (1, 4),
+    (2, 4),
+    (3, 4),
+    (4, 4),
+    (4, 3),
+    (4, 2),
+    (4, 1),
+    (4, 0),
+    (3, 0),
+    (2, 0),
+    (1, 0),
+];
+
+const FRAME_MS: u32 = 100;
+
+/// A frame is five rows of five LEDs, a byte each.
+const PACKET_LEN: usize = packet::HEADER_LEN + 5 + packet::CRC_LEN;
+
+#[entry]
+fn main() -> ! {
+    init_log!();
+    let board = microbit::Board::take().unwrap();
+
+    // The radio only works with the external crystal as its clock source
+    let clocks = Clocks::new(board.CLOCK).enable_ext_hfosc();
+    let mut radio = Radio::new(board.RADIO, &clocks);
+
+    let mut timer = Timer::new(board.TIMER0);
+    init_display(board.TIMER1, board.display_pins);
+    let button_a = board.buttons.button_a;
+
+    // Both boards run the same program. They start out listening, until
+    // button A turns one of them into the sender.
+    let mut sending = false;
+    let mut current = 0;
+    let mut sequence: u8 = 0;
+
+    loop {
+        if !sending && button_a.is_low().unwrap() {
+            println!("Sending");
+            sending = true;
+        }
+
+        if sending {
+            let mut image = Image5x5::blank();
+            let (row, col) = PIXELS[current];
+            image.set(row, col, true);
+
+            let rows = image.rows();
+            let packet = Packet {
+                packet_type: types::IMAGE,
+                sequence,
+                payload: &rows,
+            };
+            let mut buffer = [0; PACKET_LEN];
+            radio
+                .send(packet::encode(&packet, &mut buffer).unwrap())
+                .unwrap();
+            sequence = sequence.wrapping_add(1);
+            display_image(&image);
+
+            current = (current + 1) % PIXELS.len();
+            timer.delay_ms(FRAME_MS);
+            continue;
+        }
+
+        match radio.receive() {
+            // The radio has checked its own CRC already. The packet's is
+            // checked on top, so a garbled frame is caught the same way as
+            // over the serial port.
+            Ok(bytes) => match packet::decode(bytes) {
+                Ok(Packet {
+                    packet_type: types::IMAGE,
+                    payload,
+                    ..
+                }) => match payload.try_into() {
+                    Ok(rows) => display_image(&Image5x5::from_rows(rows)),
+                    Err(_) => println!("Ignoring a {} byte frame", payload.len()),
+                },
+                Ok(packet) => println!("Ignoring a packet of type {}", packet.packet_type),
+                Err(error) => println!("Bad packet: {:?}", error),
+            },
+            Err(nb::Error::Other(Error::Crc)) => println!("Packet with a broken CRC"),
+            Err(nb::Error::Other(error)) => println!("Receive error: {:?}", error),
+            Err(nb::Error::WouldBlock) => {}
+        }
+    }
+}
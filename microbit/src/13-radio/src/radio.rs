@@ -0,0 +1,314 @@
+//! A minimal driver for the RADIO peripheral.
+//!
+//! Packets are sent in Nordic's own 1 Mbit/s mode. On air, every packet
+//! looks like this:
+//!
+//! ```text
+//! | preamble | address | length | payload      | CRC     |
+//! | 1 byte   | 5 bytes | 1 byte | 0 - 32 bytes | 2 bytes |
+//! ```
+//!
+//! The radio adds the preamble, the address and the CRC by itself, in memory
+//! a packet is just the length byte followed by the payload. Only radios
+//! listening on the same frequency and address pick up our packets, and
+//! thanks to the CRC they throw away anything that was garbled on the way.
+//!
+//! The registers of the nRF51 and nRF52 radios are nearly identical, so this
+//! works on both micro:bits.
+//!
+//! All boards start out on the same channel and address. A room full of them
+//! can be split into groups with [`Radio::set_group`], which changes the
+//! prefix of the address, or [`Radio::set_channel`], which moves to another
+//! frequency altogether.
+
+use core::sync::atomic::{compiler_fence, Ordering};
+use microbit::hal::clocks::{Clocks, ExternalOscillator};
+use microbit::pac::RADIO;
+
+/// The longest payload we allow.
+pub const MAX_PAYLOAD_LEN: usize = 32;
+
+/// The frequency every board starts out on, 2400 MHz plus this many MHz.
+pub const DEFAULT_CHANNEL: u8 = 42;
+/// The highest channel the radio can tune to, at 2500 MHz.
+pub const MAX_CHANNEL: u8 = 100;
+/// The prefix of the address every board starts out with.
+pub const DEFAULT_GROUP: u8 = 0x21;
+
+/// The address is made of a 4 byte base and a 1 byte prefix, the group.
+const BASE_ADDRESS: u32 = 0x6469_7363;
+
+/// The output powers the radio supports, in dBm, and what to write into
+/// TXPOWER for them. They are mostly the dBm as a byte, except for the
+/// lowest one.
+#[cfg(feature = "v1")]
+const TX_POWERS: [(i8, u8); 8] = [
+    (4, 0x04),
+    (0, 0x00),
+    (-4, 0xFC),
+    (-8, 0xF8),
+    (-12, 0xF4),
+    (-16, 0xF0),
+    (-20, 0xEC),
+    (-30, 0xD8),
+];
+#[cfg(feature = "v2")]
+const TX_POWERS: [(i8, u8); 14] = [
+    (8, 0x08),
+    (7, 0x07),
+    (6, 0x06),
+    (5, 0x05),
+    (4, 0x04),
+    (3, 0x03),
+    (2, 0x02),
+    (0, 0x00),
+    (-4, 0xFC),
+    (-8, 0xF8),
+    (-12, 0xF4),
+    (-16, 0xF0),
+    (-20, 0xEC),
+    (-40, 0xD8),
+];
+
+const CRC_POLY: u32 = 0x1_1021;
+const CRC_INIT: u32 = 0xFFFF;
+
+/// PCNF0: the length field is 8 bits, no S0 or S1 fields.
+const PCNF0_LFLEN_8: u32 = 8;
+/// PCNF1: little endian, whitening on, 4 byte base address.
+const PCNF1_WHITEEN: u32 = 1 << 25;
+const PCNF1_BALEN_4: u32 = 4 << 16;
+/// CRCCNF: 2 byte CRC that also covers the address.
+const CRCCNF_LEN_2: u32 = 2;
+/// SHORTS: start as soon as the radio is ready, turn it off once the packet
+/// is done.
+const SHORTS_READY_START: u32 = 1;
+const SHORTS_END_DISABLE: u32 = 1 << 1;
+
+#[derive(Debug)]
+pub enum Error {
+    /// The payload is longer than [`MAX_PAYLOAD_LEN`].
+    TooLong,
+    /// A packet arrived, but its CRC didn't match.
+    Crc,
+    /// There is no channel with that number.
+    InvalidChannel,
+    /// The radio can't send with that power.
+    InvalidTxPower,
+}
+
+pub struct Radio {
+    radio: RADIO,
+    /// The packet that is being sent or received, the length byte followed
+    /// by the payload.
+    buffer: [u8; MAX_PAYLOAD_LEN + 1],
+    receiving: bool,
+}
+
+impl Radio {
+    /// Set up the radio. It needs the accurate clock from the external
+    /// crystal, which is why `clocks` has to have it running.
+    pub fn new<L, LSTAT>(radio: RADIO, _clocks: &Clocks<ExternalOscillator, L, LSTAT>) -> Radio {
+        // Nordic's proprietary 1 Mbit/s mode at 0 dBm
+        radio.mode.write(|w| unsafe { w.bits(0) });
+        radio.txpower.write(|w| unsafe { w.bits(0) });
+        radio
+            .frequency
+            .write(|w| unsafe { w.bits(u32::from(DEFAULT_CHANNEL)) });
+
+        radio.pcnf0.write(|w| unsafe { w.bits(PCNF0_LFLEN_8) });
+        radio
+            .pcnf1
+            .write(|w| unsafe { w.bits(PCNF1_WHITEEN | PCNF1_BALEN_4 | MAX_PAYLOAD_LEN as u32) });
+        radio
+            .datawhiteiv
+            .write(|w| unsafe { w.bits(u32::from(DEFAULT_CHANNEL)) });
+
+        // Send on logical address 0 and only listen on it as well
+        radio.base0.write(|w| unsafe { w.bits(BASE_ADDRESS) });
+        radio
+            .prefix0
+            .write(|w| unsafe { w.bits(u32::from(DEFAULT_GROUP)) });
+        radio.txaddress.write(|w| unsafe { w.bits(0) });
+        radio.rxaddresses.write(|w| unsafe { w.bits(1) });
+
+        radio.crccnf.write(|w| unsafe { w.bits(CRCCNF_LEN_2) });
+        radio.crcpoly.write(|w| unsafe { w.bits(CRC_POLY) });
+        radio.crcinit.write(|w| unsafe { w.bits(CRC_INIT) });
+
+        radio
+            .shorts
+            .write(|w| unsafe { w.bits(SHORTS_READY_START | SHORTS_END_DISABLE) });
+
+        Radio {
+            radio,
+            buffer: [0; MAX_PAYLOAD_LEN + 1],
+            receiving: false,
+        }
+    }
+
+    /// Send to, and listen on, the address made of `base` and `prefix` from
+    /// now on, instead of the one every board starts out with.
+    // The main program doesn't need an address of its own
+    #[allow(dead_code)]
+    pub fn set_address(&mut self, base: u32, prefix: u8) {
+        self.disable();
+        self.radio.base0.write(|w| unsafe { w.bits(base) });
+        self.radio
+            .prefix0
+            .write(|w| unsafe { w.bits(u32::from(prefix)) });
+    }
+
+    /// Send `payload`, blocking until it is on its way.
+    pub fn send(&mut self, payload: &[u8]) -> Result<(), Error> {
+        if payload.len() > MAX_PAYLOAD_LEN {
+            return Err(Error::TooLong);
+        }
+        self.disable();
+
+        self.buffer[0] = payload.len() as u8;
+        self.buffer[1..=payload.len()].copy_from_slice(payload);
+        self.start(true);
+        while self.radio.events_disabled.read().bits() == 0 {}
+        self.radio.events_disabled.reset();
+        compiler_fence(Ordering::Acquire);
+        Ok(())
+    }
+
+    /// Check whether a packet has arrived, and start listening if we weren't
+    /// already.
+    ///
+    /// The payload is only valid until the next call, since listening for the
+    /// next packet starts right away.
+    pub fn receive(&mut self) -> nb::Result<&[u8], Error> {
+        if !self.receiving {
+            self.start(false);
+            self.receiving = true;
+            return Err(nb::Error::WouldBlock);
+        }
+        if self.radio.events_disabled.read().bits() == 0 {
+            return Err(nb::Error::WouldBlock);
+        }
+        self.radio.events_disabled.reset();
+        self.receiving = false;
+        // Make sure the compiler doesn't read the buffer before the radio is
+        // done writing it
+        compiler_fence(Ordering::Acquire);
+
+        if self.radio.crcstatus.read().bits() == 0 {
+            return Err(nb::Error::Other(Error::Crc));
+        }
+        // The radio only checks the length against MAXLEN, which is the same
+        // as our buffer size
+        let len = self.buffer[0] as usize;
+        Ok(&self.buffer[1..=len])
+    }
+
+    /// Turn on the transmitter or the receiver. Thanks to the shorts, the
+    /// radio turns itself off again after a single packet.
+    ///
+    /// The radio reads and writes `buffer` by itself, so `self` must not move
+    /// until it is done.
+    fn start(&mut self, transmit: bool) {
+        compiler_fence(Ordering::Release);
+        let packet = self.buffer.as_mut_ptr() as u32;
+        self.radio.packetptr.write(|w| unsafe { w.bits(packet) });
+        self.radio.events_disabled.reset();
+        if transmit {
+            self.radio.tasks_txen.write(|w| unsafe { w.bits(1) });
+        } else {
+            self.radio.tasks_rxen.write(|w| unsafe { w.bits(1) });
+        }
+    }
+
+    /// Stop listening, if we are.
+    fn disable(&mut self) {
+        if !self.receiving {
+            return;
+        }
+        self.radio.tasks_disable.write(|w| unsafe { w.bits(1) });
+        while self.radio.events_disabled.read().bits() == 0 {}
+        self.radio.events_disabled.reset();
+        self.receiving = false;
+    }
+}
+
+// Only the groups example changes the settings
+#[allow(dead_code)]
+impl Radio {
+    /// Only hear the boards in `group` from now on, and only be heard by them.
+    ///
+    /// This keeps the base of the address the way it is, and only changes the
+    /// prefix.
+    pub fn set_group(&mut self, group: u8) {
+        self.disable();
+        self.radio
+            .prefix0
+            .write(|w| unsafe { w.bits(u32::from(group)) });
+    }
+
+    /// The group the radio is in right now.
+    pub fn group(&self) -> u8 {
+        self.radio.prefix0.read().bits() as u8
+    }
+
+    /// Move to 2400 MHz plus `channel` MHz, up to [`MAX_CHANNEL`].
+    ///
+    /// Boards on neighbouring channels still hear a bit of each other, so
+    /// groups of boards that are meant to be kept apart are better off a few
+    /// channels away from each other. Wi-Fi is busy around channels 12, 37
+    /// and 62.
+    pub fn set_channel(&mut self, channel: u8) -> Result<(), Error> {
+        if channel > MAX_CHANNEL {
+            return Err(Error::InvalidChannel);
+        }
+        self.disable();
+        self.radio
+            .frequency
+            .write(|w| unsafe { w.bits(u32::from(channel)) });
+        // Both sides have to whiten with the same value, it might as well
+        // follow the channel
+        self.radio
+            .datawhiteiv
+            .write(|w| unsafe { w.bits(u32::from(channel)) });
+        Ok(())
+    }
+
+    /// The channel the radio is on right now.
+    pub fn channel(&self) -> u8 {
+        self.radio.frequency.read().bits() as u8 & 0x7F
+    }
+
+    /// Send with `dbm` of power from now on. Every board starts out with 0 dBm,
+    /// the micro:bit v1 can go up to 4 dBm, the v2 up to 8 dBm.
+    ///
+    /// Turning it down is the easiest way to keep a group of boards from
+    /// being heard across the room. Each step of 6 dBm roughly halves how far
+    /// a packet gets.
+    pub fn set_tx_power(&mut self, dbm: i8) -> Result<(), Error> {
+        let &(_, bits) = TX_POWERS
+            .iter()
+            .find(|&&(power, _)| power == dbm)
+            .ok_or(Error::InvalidTxPower)?;
+        self.disable();
+        self.radio
+            .txpower
+            .write(|w| unsafe { w.bits(u32::from(bits)) });
+        Ok(())
+    }
+
+    /// The power the radio sends with right now, in dBm.
+    pub fn tx_power(&self) -> i8 {
+        let bits = self.radio.txpower.read().bits() as u8;
+        TX_POWERS
+            .iter()
+            .find(|&&(_, power_bits)| power_bits == bits)
+            .map_or(0, |&(dbm, _)| dbm)
+    }
+
+    /// The output powers [`set_tx_power`](Radio::set_tx_power) accepts, in
+    /// dBm, from the highest to the lowest.
+    pub fn tx_powers() -> impl Iterator<Item = i8> {
+        TX_POWERS.iter().map(|&(dbm, _)| dbm)
+    }
+}
@@ -0,0 +1,10 @@
+//! This build script writes the `memory.x` for the board the chapter is
+//! built for into a directory where the linker can always find it. Which
+//! layout that is depends on the features, see `memory_x.rs`.
+
+#[path = "../../support/build/memory_x.rs"]
+mod memory_x;
+
+fn main() {
+    memory_x::write();
+}
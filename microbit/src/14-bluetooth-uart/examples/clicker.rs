@@ -0,0 +1,214 @@
+#![no_main]
+#![no_std]
+
+#[path = "../src/hid.rs"]
+#[allow(dead_code)]
+mod hid;
+#[path = "../src/radio.rs"]
+mod radio;
+#[path = "../src/timer.rs"]
+mod timer;
+
+use cortex_m::peripheral::SCB;
+use cortex_m_rt::entry;
+use discovery_support::{init_log, println};
+use panic_rtt_target as _;
+
+use discovery_support::buttons::{init_buttons, Button, ButtonEvent};
+use discovery_support::shared::Shared;
+use discovery_support::touch::{TouchEvent, TouchLogo};
+use microbit::hal::clocks::Clocks;
+use microbit::pac::{self, interrupt, FICR};
+use rubble::config::Config;
+use rubble::l2cap::{BleChannelMap, L2CAPState};
+use rubble::link::ad_structure::{AdStructure, ServiceUuids};
+use rubble::link::queue::{PacketQueue, SimpleQueue};
+use rubble::link::{AddressKind, DeviceAddress, LinkLayer, Responder};
+use rubble::security::NoSecurity;
+use rubble::time::{Duration, Timer};
+use rubble::uuid::Uuid16;
+
+use crate::hid::{keys, HidAttrs, Key, Keyboard};
+use crate::radio::{BleRadio, PacketBuffer};
+use crate::timer::BleTimer;
+
+/// What the buttons type. Change them to whatever your presentation program
+/// wants. A double click types the key of a click twice.
+const KEYMAP: &[(Button, ButtonEvent, Key)] = &[
+    (Button::A, ButtonEvent::Click, keys::LEFT_ARROW),
+    (Button::B, ButtonEvent::Click, keys::RIGHT_ARROW),
+    (Button::A, ButtonEvent::LongPress, keys::ESCAPE),
+    (Button::B, ButtonEvent::LongPress, keys::F5),
+];
+/// Touching the logo blanks the screen in most presentation programs, and
+/// touching it again brings the slide back.
+const LOGO: Key = keys::B;
+
+/// Tells the computer to show a keyboard for us.
+const APPEARANCE_KEYBOARD: [u8; 2] = [0xC1, 0x03];
+/// The type of the appearance AD structure, which rubble doesn't know.
+const APPEARANCE: u8 = 0x19;
+
+/// Ties the parts of the BLE stack together.
+pub enum AppConfig {}
+
+impl Config for AppConfig {
+    type Timer = BleTimer;
+    type Transmitter = BleRadio;
+    type ChannelMapper = BleChannelMap<HidAttrs, NoSecurity>;
+    type PacketQueue = &'static mut SimpleQueue;
+}
+
+/// The Link Layer has to react within microseconds, so it lives in the
+/// `RADIO` and `TIMER0` interrupts.
+struct LinkLayerState {
+    radio: BleRadio,
+    ll: LinkLayer<AppConfig>,
+}
+
+static LINK_LAYER: Shared<LinkLayerState> = Shared::new();
+
+/// The key for `event` on `button`, and how many times to type it.
+fn lookup(button: Button, event: ButtonEvent) -> Option<(Key, usize)> {
+    let (event, times) = match event {
+        ButtonEvent::DoubleClick => (ButtonEvent::Click, 2),
+        event => (event, 1),
+    };
+    KEYMAP
+        .iter()
+        .find(|&&(b, e, _)| b == button && e == event)
+        .map(|&(_, _, key)| (key, times))
+}
+
+#[entry]
+fn main() -> ! {
+    init_log!();
+    let board = microbit::Board::take().unwrap();
+
+    let clocks = Clocks::new(board.CLOCK).enable_ext_hfosc();
+
+    // The radio reads and writes these buffers and queues by itself, so they
+    // must never move
+    let tx_buf = cortex_m::singleton!(: PacketBuffer = [0; rubble::link::MIN_PDU_BUF]).unwrap();
+    let rx_buf = cortex_m::singleton!(: PacketBuffer = [0; rubble::link::MIN_PDU_BUF]).unwrap();
+    let tx_queue = cortex_m::singleton!(: SimpleQueue = SimpleQueue::new()).unwrap();
+    let rx_queue = cortex_m::singleton!(: SimpleQueue = SimpleQueue::new()).unwrap();
+
+    let mut radio = BleRadio::new(board.RADIO, &clocks, tx_buf, rx_buf);
+    let mut ll =
+        LinkLayer::<AppConfig>::new(device_address(&board.FICR), BleTimer::new(board.TIMER0));
+
+    let (tx, tx_consumer) = tx_queue.split();
+    let (rx_producer, rx) = rx_queue.split();
+    let responder = Responder::<AppConfig>::new(
+        tx,
+        rx,
+        L2CAPState::new(BleChannelMap::with_attributes(HidAttrs::new())),
+    );
+
+    let next_update = ll
+        .start_advertise(
+            Duration::from_millis(200),
+            &[
+                AdStructure::ServiceUuids16(ServiceUuids::from_uuids(true, &[Uuid16(0x1812)])),
+                AdStructure::Unknown {
+                    ty: APPEARANCE,
+                    data: &APPEARANCE_KEYBOARD,
+                },
+                AdStructure::CompleteLocalName("micro:bit clicker"),
+            ],
+            &mut radio,
+            tx_consumer,
+            rx_producer,
+        )
+        .unwrap();
+    ll.timer().configure_interrupt(next_update);
+
+    LINK_LAYER.lend(LinkLayerState { radio, ll });
+    unsafe {
+        pac::NVIC::unmask(pac::Interrupt::RADIO);
+        pac::NVIC::unmask(pac::Interrupt::TIMER0);
+    }
+    println!("Advertising as \"micro:bit clicker\"");
+
+    let mut buttons = init_buttons(board.GPIOTE, board.TIMER2, board.buttons);
+    let mut logo = TouchLogo::new(board.pins.p1_04, board.TIMER3);
+    let mut keyboard = Keyboard::new(responder);
+    let mut listening = false;
+
+    loop {
+        keyboard.poll();
+        if keyboard.is_listening() != listening {
+            listening = !listening;
+            println!(
+                "{}",
+                if listening {
+                    "Connected"
+                } else {
+                    "Waiting for a computer"
+                }
+            );
+        }
+
+        let mut typed = None;
+        if let Some((button, event)) = buttons.recv() {
+            typed = lookup(button, event);
+        }
+        if logo.poll_event() == Some(TouchEvent::Touched) {
+            typed = Some((LOGO, 1));
+        }
+        if let Some((key, times)) = typed {
+            for _ in 0..times {
+                if !keyboard.tap(key) {
+                    println!("Too many keys waiting to be sent, dropped one");
+                }
+            }
+        }
+    }
+}
+
+/// The random static address every nRF chip comes with.
+fn device_address(ficr: &FICR) -> DeviceAddress {
+    let mut address = [0; 6];
+    address[..4].copy_from_slice(&ficr.deviceaddr[0].read().bits().to_le_bytes());
+    address[4..].copy_from_slice(&(ficr.deviceaddr[1].read().bits() as u16).to_le_bytes());
+    // A random static address has its two highest bits set
+    address[5] |= 0xC0;
+    DeviceAddress::new(address, AddressKind::Random)
+}
+
+/// Rubble can't start advertising again once a connection has ended, so we
+/// start over instead.
+fn reset_if_disconnected(ll: &LinkLayer<AppConfig>) {
+    if !ll.is_connected() && !ll.is_advertising() {
+        println!("Disconnected, resetting");
+        SCB::sys_reset();
+    }
+}
+
+#[interrupt]
+fn RADIO() {
+    LINK_LAYER.with(|state| {
+        let now = state.ll.timer().now();
+        if let Some(cmd) = state.radio.recv_interrupt(now, &mut state.ll) {
+            state.radio.configure_receiver(cmd.radio);
+            state.ll.timer().configure_interrupt(cmd.next_update);
+            reset_if_disconnected(&state.ll);
+        }
+    });
+}
+
+#[interrupt]
+fn TIMER0() {
+    LINK_LAYER.with(|state| {
+        if !state.ll.timer().is_interrupt_pending() {
+            return;
+        }
+        state.ll.timer().clear_interrupt();
+
+        let cmd = state.ll.update_timer(&mut state.radio);
+        state.radio.configure_receiver(cmd.radio);
+        state.ll.timer().configure_interrupt(cmd.next_update);
+        reset_if_disconnected(&state.ll);
+    });
+}
@@ -0,0 +1,328 @@
+//! A keyboard over BLE, with the HID over GATT Profile.
+//!
+//! HID, the Human Interface Device class, is how USB keyboards and mice
+//! describe themselves to a computer, and HOGP carries the same thing over
+//! BLE. The computer reads the *Report Map* first: a description of what the
+//! reports the device sends look like. Ours says *keyboard*, with the boot
+//! keyboard's 8 byte report: a byte of modifier keys like Shift, a reserved
+//! byte, and up to six keys that are held down. Every time a key goes down or
+//! up, the keyboard sends a new report as a notification of the *Report*
+//! characteristic.
+//!
+//! HOGP asks for a Battery Service and a Device Information Service next to
+//! the HID Service, so those are here too.
+//!
+//! [`Keyboard`] turns a key into the reports that press and release it.
+
+use core::cmp;
+use heapless::spsc::Queue;
+use rubble::att::{
+    AttUuid, Attribute, AttributeAccessPermissions, AttributeProvider, Handle, HandleRange,
+};
+use rubble::config::Config;
+use rubble::l2cap::BleChannelMap;
+use rubble::link::Responder;
+use rubble::security::NoSecurity;
+use rubble::uuid::Uuid16;
+use rubble::Error;
+
+const HID_SERVICE: u16 = 0x1812;
+const BATTERY_SERVICE: u16 = 0x180F;
+const DEVICE_INFORMATION_SERVICE: u16 = 0x180A;
+
+const REPORT_HANDLE: u16 = 7;
+const REPORT_CCCD_HANDLE: u16 = 8;
+const CONTROL_POINT_HANDLE: u16 = 11;
+
+/// Characteristic properties.
+const READ: u8 = 0x02;
+const WRITE_WITHOUT_RESPONSE: u8 = 0x04;
+const NOTIFY: u8 = 0x10;
+
+const HID_INFORMATION_DECLARATION: [u8; 5] = declaration(READ, 3, 0x2A4A);
+const REPORT_MAP_DECLARATION: [u8; 5] = declaration(READ, 5, 0x2A4B);
+const REPORT_DECLARATION: [u8; 5] = declaration(READ | NOTIFY, REPORT_HANDLE, 0x2A4D);
+const CONTROL_POINT_DECLARATION: [u8; 5] =
+    declaration(WRITE_WITHOUT_RESPONSE, CONTROL_POINT_HANDLE, 0x2A4C);
+const BATTERY_LEVEL_DECLARATION: [u8; 5] = declaration(READ, 14, 0x2A19);
+const PNP_ID_DECLARATION: [u8; 5] = declaration(READ, 17, 0x2A50);
+
+/// HID 1.11, no country, and the computer may wake us up.
+const HID_INFORMATION: [u8; 4] = [0x11, 0x01, 0x00, 0x02];
+/// The only report has no ID, and it's an input, going to the computer.
+const REPORT_REFERENCE: [u8; 2] = [0x00, 0x01];
+/// There's no battery gauge, so we're always full.
+const BATTERY_LEVEL: [u8; 1] = [100];
+/// Who made the device, with Nordic's USB vendor ID and a product ID we made
+/// up: vendor ID source, vendor, product and version, least significant byte
+/// first.
+const PNP_ID: [u8; 7] = [0x02, 0x15, 0x19, 0xEE, 0xEE, 0x01, 0x00];
+
+#[rustfmt::skip]
+const REPORT_MAP: [u8; 43] = [
+    0x05, 0x01, // Usage Page (Generic Desktop)
+    0x09, 0x06, // Usage (Keyboard)
+    0xA1, 0x01, // Collection (Application)
+    0x05, 0x07, //   Usage Page (Keyboard/Keypad)
+    0x19, 0xE0, //   Usage Minimum (Left Control)
+    0x29, 0xE7, //   Usage Maximum (Right GUI)
+    0x15, 0x00, //   Logical Minimum (0)
+    0x25, 0x01, //   Logical Maximum (1)
+    0x75, 0x01, //   Report Size (1)
+    0x95, 0x08, //   Report Count (8)
+    0x81, 0x02, //   Input (Data, Variable, Absolute): a bit per modifier
+    0x95, 0x01, //   Report Count (1)
+    0x75, 0x08, //   Report Size (8)
+    0x81, 0x01, //   Input (Constant): the reserved byte
+    0x95, 0x06, //   Report Count (6)
+    0x75, 0x08, //   Report Size (8)
+    0x15, 0x00, //   Logical Minimum (0)
+    0x25, 0x65, //   Logical Maximum (101)
+    0x19, 0x00, //   Usage Minimum (0)
+    0x29, 0x65, //   Usage Maximum (101)
+    0x81, 0x00, //   Input (Data, Array): the keys that are down
+    0xC0,       // End Collection
+];
+
+/// What a keyboard with no key down sends.
+const RELEASED: Report = [0; 8];
+
+/// Modifier, reserved, and six keys.
+pub type Report = [u8; 8];
+
+/// A key, as a HID usage of the Keyboard/Keypad page, and the modifiers held
+/// down with it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Key {
+    pub modifiers: u8,
+    pub usage: u8,
+}
+
+impl Key {
+    pub const fn new(usage: u8) -> Key {
+        Key {
+            modifiers: 0,
+            usage,
+        }
+    }
+
+    /// The same key, with `modifiers` held down as well.
+    pub const fn with(self, modifiers: u8) -> Key {
+        Key {
+            modifiers: self.modifiers | modifiers,
+            usage: self.usage,
+        }
+    }
+
+    fn report(self) -> Report {
+        [self.modifiers, 0, self.usage, 0, 0, 0, 0, 0]
+    }
+}
+
+/// Bits of the modifier byte.
+pub mod modifiers {
+    pub const LEFT_CTRL: u8 = 0x01;
+    pub const LEFT_SHIFT: u8 = 0x02;
+    pub const LEFT_ALT: u8 = 0x04;
+    pub const LEFT_GUI: u8 = 0x08;
+}
+
+/// A few keys a presentation might want. The HID Usage Tables list all of
+/// them, in chapter 10.
+pub mod keys {
+    use super::Key;
+
+    pub const B: Key = Key::new(0x05);
+    pub const ENTER: Key = Key::new(0x28);
+    pub const ESCAPE: Key = Key::new(0x29);
+    pub const SPACE: Key = Key::new(0x2C);
+    pub const F5: Key = Key::new(0x3E);
+    pub const PAGE_UP: Key = Key::new(0x4B);
+    pub const PAGE_DOWN: Key = Key::new(0x4E);
+    pub const RIGHT_ARROW: Key = Key::new(0x4F);
+    pub const LEFT_ARROW: Key = Key::new(0x50);
+}
+
+/// A characteristic declaration: its properties, the handle of its value and
+/// its 16 bit UUID.
+const fn declaration(properties: u8, handle: u16, uuid: u16) -> [u8; 5] {
+    [
+        properties,
+        handle as u8,
+        (handle >> 8) as u8,
+        uuid as u8,
+        (uuid >> 8) as u8,
+    ]
+}
+
+const fn le(uuid: u16) -> [u8; 2] {
+    [uuid as u8, (uuid >> 8) as u8]
+}
+
+const HID_SERVICE_BYTES: [u8; 2] = le(HID_SERVICE);
+const BATTERY_SERVICE_BYTES: [u8; 2] = le(BATTERY_SERVICE);
+const DEVICE_INFORMATION_SERVICE_BYTES: [u8; 2] = le(DEVICE_INFORMATION_SERVICE);
+
+/// The attributes of the three services.
+pub struct HidAttrs {
+    attributes: [Attribute<&'static [u8]>; 17],
+    notifications_enabled: bool,
+}
+
+impl HidAttrs {
+    pub fn new() -> HidAttrs {
+        let attribute = |uuid: u16, handle: u16, value: &'static [u8]| {
+            Attribute::new(Uuid16(uuid).into(), Handle::from_raw(handle), value)
+        };
+        HidAttrs {
+            attributes: [
+                attribute(0x2800, 1, &HID_SERVICE_BYTES),
+                attribute(0x2803, 2, &HID_INFORMATION_DECLARATION),
+                attribute(0x2A4A, 3, &HID_INFORMATION),
+                attribute(0x2803, 4, &REPORT_MAP_DECLARATION),
+                attribute(0x2A4B, 5, &REPORT_MAP),
+                attribute(0x2803, 6, &REPORT_DECLARATION),
+                attribute(0x2A4D, REPORT_HANDLE, &RELEASED),
+                // The Client Characteristic Configuration, where the computer
+                // turns notifications on
+                attribute(0x2902, REPORT_CCCD_HANDLE, &[0, 0]),
+                attribute(0x2908, 9, &REPORT_REFERENCE),
+                attribute(0x2803, 10, &CONTROL_POINT_DECLARATION),
+                attribute(0x2A4C, CONTROL_POINT_HANDLE, &[0]),
+                attribute(0x2800, 12, &BATTERY_SERVICE_BYTES),
+                attribute(0x2803, 13, &BATTERY_LEVEL_DECLARATION),
+                attribute(0x2A19, 14, &BATTERY_LEVEL),
+                attribute(0x2800, 15, &DEVICE_INFORMATION_SERVICE_BYTES),
+                attribute(0x2803, 16, &PNP_ID_DECLARATION),
+                attribute(0x2A50, 17, &PNP_ID),
+            ],
+            notifications_enabled: false,
+        }
+    }
+}
+
+impl Default for HidAttrs {
+    fn default() -> HidAttrs {
+        HidAttrs::new()
+    }
+}
+
+impl AttributeProvider for HidAttrs {
+    fn for_attrs_in_range(
+        &mut self,
+        range: HandleRange,
+        mut f: impl FnMut(&Self, &Attribute<dyn AsRef<[u8]>>) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        // Handles start at 1
+        let start = usize::from(range.start().as_u16() - 1);
+        let end = cmp::min(
+            usize::from(range.end().as_u16() - 1),
+            self.attributes.len() - 1,
+        );
+        if start >= self.attributes.len() {
+            return Ok(());
+        }
+        for attribute in &self.attributes[start..=end] {
+            f(self, attribute)?;
+        }
+        Ok(())
+    }
+
+    fn is_grouping_attr(&self, uuid: AttUuid) -> bool {
+        uuid == Uuid16(0x2800)
+    }
+
+    fn group_end(&self, handle: Handle) -> Option<&Attribute<dyn AsRef<[u8]>>> {
+        match handle.as_u16() {
+            1 => Some(&self.attributes[10]),
+            12 => Some(&self.attributes[13]),
+            15 => Some(&self.attributes[16]),
+            _ => None,
+        }
+    }
+
+    fn attr_access_permissions(&self, handle: Handle) -> AttributeAccessPermissions {
+        match handle.as_u16() {
+            CONTROL_POINT_HANDLE => AttributeAccessPermissions::Writeable,
+            REPORT_CCCD_HANDLE => AttributeAccessPermissions::ReadableAndWriteable,
+            _ => AttributeAccessPermissions::Readable,
+        }
+    }
+
+    fn write_attr(&mut self, handle: Handle, data: &[u8]) -> Result<(), Error> {
+        // The control point only says when the computer goes to sleep and
+        // wakes up again, which doesn't change what we do
+        if handle.as_u16() == REPORT_CCCD_HANDLE {
+            self.notifications_enabled = data.first().is_some_and(|flags| flags & 1 != 0);
+            self.attributes[7].set_value(if self.notifications_enabled {
+                &[1, 0]
+            } else {
+                &[0, 0]
+            });
+        }
+        Ok(())
+    }
+}
+
+/// A keyboard over HOGP.
+///
+/// Like `BleSerial`, everything above the Link
+/// Layer only runs when it's asked to, so call [`poll`](Keyboard::poll)
+/// regularly, to keep the connection going and send the reports.
+pub struct Keyboard<C: Config<ChannelMapper = BleChannelMap<HidAttrs, NoSecurity>>> {
+    responder: Responder<C>,
+    /// The reports waiting for the Link Layer to have room for them.
+    reports: Queue<Report, 8>,
+}
+
+impl<C: Config<ChannelMapper = BleChannelMap<HidAttrs, NoSecurity>>> Keyboard<C> {
+    pub fn new(responder: Responder<C>) -> Keyboard<C> {
+        Keyboard {
+            responder,
+            reports: Queue::new(),
+        }
+    }
+
+    /// Whether a computer has connected, and is listening for key presses.
+    pub fn is_listening(&mut self) -> bool {
+        self.responder
+            .l2cap()
+            .channel_mapper()
+            .attribute_provider()
+            .notifications_enabled
+    }
+
+    /// Press `key` and let go of it again. Returns `false` if there are too
+    /// many key presses still waiting to be sent.
+    pub fn tap(&mut self, key: Key) -> bool {
+        if self.reports.capacity() - self.reports.len() < 2 {
+            return false;
+        }
+        self.reports.enqueue(key.report()).ok();
+        self.reports.enqueue(RELEASED).ok();
+        true
+    }
+
+    /// Answer the computer, and send whatever reports there's room for.
+    pub fn poll(&mut self) {
+        while self.responder.has_work() {
+            self.responder.process_one().unwrap();
+        }
+        if !self.is_listening() {
+            // Nobody would hear those keys, and they'd come as a surprise
+            // later
+            while self.reports.dequeue().is_some() {}
+            return;
+        }
+        while let Some(report) = self.reports.peek().copied() {
+            match self.responder.l2cap().att() {
+                Some(att) => {
+                    att.notify_raw(Handle::from_raw(REPORT_HANDLE), &report);
+                    self.reports.dequeue();
+                }
+                // The Link Layer hasn't sent the last packet yet
+                None => break,
+            }
+        }
+    }
+}
@@ -0,0 +1,153 @@
+#![no_main]
+#![no_std]
+
+mod nus;
+mod radio;
+mod timer;
+
+#[path = "../../07-uart/src/line_reader.rs"]
+mod line_reader;
+
+use cortex_m::peripheral::SCB;
+use cortex_m_rt::entry;
+use discovery_support::{init_log, println};
+use embedded_hal::serial::Write;
+use panic_rtt_target as _;
+
+use discovery_support::shared::Shared;
+use microbit::hal::clocks::Clocks;
+use microbit::pac::{self, interrupt, FICR};
+use rubble::config::Config;
+use rubble::l2cap::{BleChannelMap, L2CAPState};
+use rubble::link::ad_structure::AdStructure;
+use rubble::link::queue::{PacketQueue, SimpleQueue};
+use rubble::link::{AddressKind, DeviceAddress, LinkLayer, Responder};
+use rubble::security::NoSecurity;
+use rubble::time::{Duration, Timer};
+
+use crate::line_reader::LineReader;
+use crate::nus::{BleSerial, NusAttrs};
+use crate::radio::{BleRadio, PacketBuffer};
+use crate::timer::BleTimer;
+
+/// Ties the parts of the BLE stack together.
+pub enum AppConfig {}
+
+impl Config for AppConfig {
+    type Timer = BleTimer;
+    type Transmitter = BleRadio;
+    type ChannelMapper = BleChannelMap<NusAttrs, NoSecurity>;
+    type PacketQueue = &'static mut SimpleQueue;
+}
+
+/// The Link Layer has to react within microseconds, so it lives in the
+/// `RADIO` and `TIMER0` interrupts.
+struct LinkLayerState {
+    radio: BleRadio,
+    ll: LinkLayer<AppConfig>,
+}
+
+static LINK_LAYER: Shared<LinkLayerState> = Shared::new();
+
+#[entry]
+fn main() -> ! {
+    init_log!();
+    let board = microbit::Board::take().unwrap();
+
+    let clocks = Clocks::new(board.CLOCK).enable_ext_hfosc();
+
+    // The radio reads and writes these buffers and queues by itself, so they
+    // must never move
+    let tx_buf = cortex_m::singleton!(: PacketBuffer = [0; rubble::link::MIN_PDU_BUF]).unwrap();
+    let rx_buf = cortex_m::singleton!(: PacketBuffer = [0; rubble::link::MIN_PDU_BUF]).unwrap();
+    let tx_queue = cortex_m::singleton!(: SimpleQueue = SimpleQueue::new()).unwrap();
+    let rx_queue = cortex_m::singleton!(: SimpleQueue = SimpleQueue::new()).unwrap();
+
+    let mut radio = BleRadio::new(board.RADIO, &clocks, tx_buf, rx_buf);
+    let mut ll =
+        LinkLayer::<AppConfig>::new(device_address(&board.FICR), BleTimer::new(board.TIMER0));
+
+    let (tx, tx_consumer) = tx_queue.split();
+    let (rx_producer, rx) = rx_queue.split();
+    let responder = Responder::<AppConfig>::new(
+        tx,
+        rx,
+        L2CAPState::new(BleChannelMap::with_attributes(NusAttrs::new())),
+    );
+
+    let next_update = ll
+        .start_advertise(
+            Duration::from_millis(200),
+            &[AdStructure::CompleteLocalName("micro:bit")],
+            &mut radio,
+            tx_consumer,
+            rx_producer,
+        )
+        .unwrap();
+    ll.timer().configure_interrupt(next_update);
+
+    LINK_LAYER.lend(LinkLayerState { radio, ll });
+    unsafe {
+        pac::NVIC::unmask(pac::Interrupt::RADIO);
+        pac::NVIC::unmask(pac::Interrupt::TIMER0);
+    }
+    println!("Advertising as \"micro:bit\"");
+
+    let mut serial = BleSerial::new(responder);
+    let mut reader: LineReader<32> = LineReader::new();
+
+    loop {
+        // Receiving over Bluetooth can't fail, `BleSerial` has no errors
+        let Ok(line) = reader.read_line(&mut serial);
+        for &byte in line.as_bytes().iter().rev().chain(b"\r\n") {
+            nb::block!(serial.write(byte)).unwrap();
+        }
+        nb::block!(serial.flush()).unwrap();
+    }
+}
+
+/// The random static address every nRF chip comes with.
+fn device_address(ficr: &FICR) -> DeviceAddress {
+    let mut address = [0; 6];
+    address[..4].copy_from_slice(&ficr.deviceaddr[0].read().bits().to_le_bytes());
+    address[4..].copy_from_slice(&(ficr.deviceaddr[1].read().bits() as u16).to_le_bytes());
+    // A random static address has its two highest bits set
+    address[5] |= 0xC0;
+    DeviceAddress::new(address, AddressKind::Random)
+}
+
+/// Rubble can't start advertising again once a connection has ended, so we
+/// start over instead.
+fn reset_if_disconnected(ll: &LinkLayer<AppConfig>) {
+    if !ll.is_connected() && !ll.is_advertising() {
+        println!("Disconnected, resetting");
+        SCB::sys_reset();
+    }
+}
+
+#[interrupt]
+fn RADIO() {
+    LINK_LAYER.with(|state| {
+        let now = state.ll.timer().now();
+        if let Some(cmd) = state.radio.recv_interrupt(now, &mut state.ll) {
+            state.radio.configure_receiver(cmd.radio);
+            state.ll.timer().configure_interrupt(cmd.next_update);
+            reset_if_disconnected(&state.ll);
+        }
+    });
+}
+
+#[interrupt]
+fn TIMER0() {
+    LINK_LAYER.with(|state| {
+        if !state.ll.timer().is_interrupt_pending() {
+            return;
+        }
+        state.ll.timer().clear_interrupt();
+
+        let cmd = state.ll.update_timer(&mut state.radio);
+        state.radio.configure_receiver(cmd.radio);
+        state.ll.timer().configure_interrupt(cmd.next_update);
+        reset_if_disconnected(&state.ll);
+    });
+}
@@ -0,0 +1,259 @@
+//! The Nordic UART Service, a serial port over BLE.
+//!
+//! NUS isn't an official Bluetooth service, but it's so widespread that most
+//! BLE terminal apps on phones know it. It has two characteristics: the phone
+//! writes what is typed into *RX*, and we send our output back as
+//! notifications of *TX*.
+//!
+//! [`BleSerial`] implements the `embedded-hal` serial traits on top of it, so
+//! anything that worked with the wired UART works over BLE as well.
+
+use core::cmp;
+use core::convert::Infallible;
+use embedded_hal::serial;
+use heapless::spsc::Queue;
+use heapless::Vec;
+use rubble::att::{
+    AttUuid, Attribute, AttributeAccessPermissions, AttributeProvider, Handle, HandleRange,
+};
+use rubble::config::Config;
+use rubble::l2cap::BleChannelMap;
+use rubble::link::Responder;
+use rubble::security::NoSecurity;
+use rubble::uuid::{Uuid128, Uuid16};
+use rubble::Error;
+
+/// The UUIDs as they are sent: least significant byte first.
+const SERVICE_UUID: [u8; 16] = nus_uuid(0x01);
+const RX_UUID: [u8; 16] = nus_uuid(0x02);
+const TX_UUID: [u8; 16] = nus_uuid(0x03);
+
+const RX_HANDLE: u16 = 3;
+const TX_HANDLE: u16 = 5;
+const TX_CCCD_HANDLE: u16 = 6;
+
+/// Characteristic properties.
+const WRITE_WITHOUT_RESPONSE: u8 = 0x04;
+const WRITE: u8 = 0x08;
+const NOTIFY: u8 = 0x10;
+
+const RX_DECLARATION: [u8; 19] = declaration(WRITE | WRITE_WITHOUT_RESPONSE, RX_HANDLE, RX_UUID);
+const TX_DECLARATION: [u8; 19] = declaration(NOTIFY, TX_HANDLE, TX_UUID);
+
+/// The most a notification can carry with the default ATT MTU of 23 bytes.
+const MAX_NOTIFICATION_LEN: usize = 20;
+
+/// `6E4000xx-B5A3-F393-E0A9-E50E24DCCA9E`, in little endian.
+const fn nus_uuid(id: u8) -> [u8; 16] {
+    [
+        0x9E, 0xCA, 0xDC, 0x24, 0x0E, 0xE5, 0xA9, 0xE0, 0x93, 0xF3, 0xA3, 0xB5, id, 0x00, 0x40,
+        0x6E,
+    ]
+}
+
+/// A characteristic declaration: its properties, the handle of its value and
+/// its UUID.
+const fn declaration(properties: u8, handle: u16, uuid: [u8; 16]) -> [u8; 19] {
+    let mut declaration = [0; 19];
+    declaration[0] = properties;
+    declaration[1] = handle as u8;
+    declaration[2] = (handle >> 8) as u8;
+    let mut i = 0;
+    while i < 16 {
+        declaration[3 + i] = uuid[i];
+        i += 1;
+    }
+    declaration
+}
+
+/// 128 bit UUIDs in `Uuid128` are big endian.
+const fn big_endian(uuid: [u8; 16]) -> [u8; 16] {
+    let mut reversed = [0; 16];
+    let mut i = 0;
+    while i < 16 {
+        reversed[i] = uuid[15 - i];
+        i += 1;
+    }
+    reversed
+}
+
+/// The attributes of the service, and whatever the phone sent that hasn't
+/// been read yet.
+pub struct NusAttrs {
+    attributes: [Attribute<&'static [u8]>; 6],
+    received: Queue<u8, 64>,
+    notifications_enabled: bool,
+}
+
+impl NusAttrs {
+    pub fn new() -> NusAttrs {
+        let uuid128 = |uuid| AttUuid::Uuid128(Uuid128::from_bytes(big_endian(uuid)));
+        NusAttrs {
+            attributes: [
+                Attribute::new(Uuid16(0x2800).into(), Handle::from_raw(1), &SERVICE_UUID),
+                Attribute::new(Uuid16(0x2803).into(), Handle::from_raw(2), &RX_DECLARATION),
+                Attribute::new(uuid128(RX_UUID), Handle::from_raw(RX_HANDLE), &[]),
+                Attribute::new(Uuid16(0x2803).into(), Handle::from_raw(4), &TX_DECLARATION),
+                Attribute::new(uuid128(TX_UUID), Handle::from_raw(TX_HANDLE), &[]),
+                // The Client Characteristic Configuration, where the phone
+                // turns notifications on
+                Attribute::new(
+                    Uuid16(0x2902).into(),
+                    Handle::from_raw(TX_CCCD_HANDLE),
+                    &[0, 0],
+                ),
+            ],
+            received: Queue::new(),
+            notifications_enabled: false,
+        }
+    }
+}
+
+impl Default for NusAttrs {
+    fn default() -> NusAttrs {
+        NusAttrs::new()
+    }
+}
+
+impl AttributeProvider for NusAttrs {
+    fn for_attrs_in_range(
+        &mut self,
+        range: HandleRange,
+        mut f: impl FnMut(&Self, &Attribute<dyn AsRef<[u8]>>) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        // Handles start at 1
+        let start = usize::from(range.start().as_u16() - 1);
+        let end = cmp::min(
+            usize::from(range.end().as_u16() - 1),
+            self.attributes.len() - 1,
+        );
+        if start >= self.attributes.len() {
+            return Ok(());
+        }
+        for attribute in &self.attributes[start..=end] {
+            f(self, attribute)?;
+        }
+        Ok(())
+    }
+
+    fn is_grouping_attr(&self, uuid: AttUuid) -> bool {
+        uuid == Uuid16(0x2800)
+    }
+
+    fn group_end(&self, handle: Handle) -> Option<&Attribute<dyn AsRef<[u8]>>> {
+        match handle.as_u16() {
+            1 => Some(&self.attributes[5]),
+            _ => None,
+        }
+    }
+
+    fn attr_access_permissions(&self, handle: Handle) -> AttributeAccessPermissions {
+        match handle.as_u16() {
+            RX_HANDLE => AttributeAccessPermissions::Writeable,
+            TX_CCCD_HANDLE => AttributeAccessPermissions::ReadableAndWriteable,
+            _ => AttributeAccessPermissions::Readable,
+        }
+    }
+
+    fn write_attr(&mut self, handle: Handle, data: &[u8]) -> Result<(), Error> {
+        match handle.as_u16() {
+            RX_HANDLE => {
+                for &byte in data {
+                    // Like a UART without flow control, we lose whatever
+                    // doesn't fit
+                    self.received.enqueue(byte).ok();
+                }
+            }
+            TX_CCCD_HANDLE => {
+                self.notifications_enabled = data.first().is_some_and(|flags| flags & 1 != 0);
+                self.attributes[5].set_value(if self.notifications_enabled {
+                    &[1, 0]
+                } else {
+                    &[0, 0]
+                });
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// A serial port over NUS.
+///
+/// The Link Layer runs in interrupts, but everything above it runs whenever
+/// this is read from. Reading is what keeps the connection going, so make sure
+/// to do it regularly.
+pub struct BleSerial<C: Config<ChannelMapper = BleChannelMap<NusAttrs, NoSecurity>>> {
+    responder: Responder<C>,
+    sending: Vec<u8, MAX_NOTIFICATION_LEN>,
+}
+
+impl<C: Config<ChannelMapper = BleChannelMap<NusAttrs, NoSecurity>>> BleSerial<C> {
+    pub fn new(responder: Responder<C>) -> BleSerial<C> {
+        BleSerial {
+            responder,
+            sending: Vec::new(),
+        }
+    }
+
+    /// Answer everything the phone has sent so far.
+    fn process(&mut self) {
+        while self.responder.has_work() {
+            self.responder.process_one().unwrap();
+        }
+    }
+
+    fn with_attributes<R>(&mut self, f: impl FnOnce(&mut NusAttrs) -> R) -> R {
+        f(self.responder.l2cap().channel_mapper().attribute_provider())
+    }
+}
+
+impl<C: Config<ChannelMapper = BleChannelMap<NusAttrs, NoSecurity>>> serial::Read<u8>
+    for BleSerial<C>
+{
+    type Error = Infallible;
+
+    fn read(&mut self) -> nb::Result<u8, Infallible> {
+        self.process();
+        self.with_attributes(|attributes| attributes.received.dequeue())
+            .ok_or(nb::Error::WouldBlock)
+    }
+}
+
+impl<C: Config<ChannelMapper = BleChannelMap<NusAttrs, NoSecurity>>> serial::Write<u8>
+    for BleSerial<C>
+{
+    type Error = Infallible;
+
+    /// Bytes are collected until [`flush`](serial::Write::flush) is called, or
+    /// until there are enough for a notification.
+    fn write(&mut self, byte: u8) -> nb::Result<(), Infallible> {
+        if self.sending.is_full() {
+            self.flush()?;
+        }
+        self.sending.push(byte).ok();
+        Ok(())
+    }
+
+    fn flush(&mut self) -> nb::Result<(), Infallible> {
+        if self.sending.is_empty() {
+            return Ok(());
+        }
+        // Nobody is listening, just like a UART without a cable attached
+        if !self.with_attributes(|attributes| attributes.notifications_enabled) {
+            self.sending.clear();
+            return Ok(());
+        }
+
+        self.process();
+        match self.responder.l2cap().att() {
+            Some(att) => {
+                att.notify_raw(Handle::from_raw(TX_HANDLE), &self.sending);
+                self.sending.clear();
+                Ok(())
+            }
+            // The Link Layer hasn't sent the last packet yet
+            None => Err(nb::Error::WouldBlock),
+        }
+    }
+}
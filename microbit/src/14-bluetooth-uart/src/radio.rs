@@ -0,0 +1,289 @@
+//! The RADIO peripheral in BLE mode, as a `Transmitter` for rubble.
+//!
+//! Rubble's Link Layer decides what to send and when, this only moves packets
+//! in and out of the radio. In memory, every BLE packet starts with a 16 bit
+//! header: the first byte goes into the radio's S0 field and the second one,
+//! the payload length, into its length field.
+
+use core::cmp;
+use core::sync::atomic::{compiler_fence, Ordering};
+use microbit::hal::clocks::{Clocks, ExternalOscillator};
+use microbit::pac::{radio::state::STATE_R, RADIO};
+use rubble::config::Config;
+use rubble::link::{
+    advertising, data, Cmd, LinkLayer, RadioCmd, Transmitter, CRC_POLY, MIN_PDU_BUF,
+};
+use rubble::phy::{AdvertisingChannel, DataChannel};
+use rubble::time::{Duration, Instant};
+
+/// Big enough for the header and payload of any packet.
+pub type PacketBuffer = [u8; MIN_PDU_BUF];
+
+pub struct BleRadio {
+    radio: RADIO,
+    /// Whether we're on an advertising channel, rather than in a connection.
+    advertising: bool,
+    tx_buf: &'static mut PacketBuffer,
+    /// Only an `Option` so that we can hand the Link Layer a `&mut self` while
+    /// it looks at a received packet.
+    rx_buf: Option<&'static mut PacketBuffer>,
+}
+
+impl BleRadio {
+    /// Set up the radio for BLE. As always, the radio needs the external
+    /// crystal running.
+    pub fn new<L, LSTAT>(
+        radio: RADIO,
+        _clocks: &Clocks<ExternalOscillator, L, LSTAT>,
+        tx_buf: &'static mut PacketBuffer,
+        rx_buf: &'static mut PacketBuffer,
+    ) -> BleRadio {
+        radio.mode.write(|w| w.mode().ble_1mbit());
+        radio.txpower.write(|w| w.txpower().pos4d_bm());
+
+        let max_payload = (rx_buf.len() - 2) as u8;
+        unsafe {
+            // A 3 byte base address plus the 1 byte prefix make up the 4 byte
+            // access address
+            radio.pcnf1.write(|w| {
+                w.maxlen()
+                    .bits(max_payload)
+                    .balen()
+                    .bits(3)
+                    .whiteen()
+                    .set_bit()
+            });
+            // The CRC covers everything but the access address
+            radio.crccnf.write(|w| w.skipaddr().skip().len().three());
+            radio
+                .crcpoly
+                .write(|w| w.crcpoly().bits(CRC_POLY & 0x00FF_FFFF));
+
+            // Logical address 0 is the advertising access address. BASE0
+            // ignores its lowest byte, so the base goes into the upper 24 bits.
+            radio
+                .base0
+                .write(|w| w.bits(advertising::ACCESS_ADDRESS << 8));
+            radio
+                .prefix0
+                .write(|w| w.ap0().bits((advertising::ACCESS_ADDRESS >> 24) as u8));
+        }
+
+        radio
+            .shorts
+            .write(|w| w.ready_start().enabled().end_disable().enabled());
+
+        BleRadio {
+            radio,
+            advertising: false,
+            tx_buf,
+            rx_buf: Some(rx_buf),
+        }
+    }
+
+    fn state(&self) -> STATE_R {
+        self.radio.state.read().state()
+    }
+
+    /// Start or stop listening, as the Link Layer asked in `cmd`.
+    pub fn configure_receiver(&mut self, cmd: RadioCmd) {
+        // Let an ongoing transmission finish, unless the connection event has
+        // already been missed anyway
+        if let RadioCmd::ListenData { timeout: false, .. } = cmd {
+            while self.state().is_tx() || self.state().is_tx_ru() {}
+        }
+        compiler_fence(Ordering::Acquire);
+
+        self.radio.intenclr.write(|w| w.disabled().clear());
+        self.disable();
+
+        match cmd {
+            RadioCmd::Off => {}
+            RadioCmd::ListenAdvertising { channel } => {
+                self.prepare_advertising(channel);
+                self.point_at_rx_buf();
+                self.radio.intenset.write(|w| w.disabled().set());
+                self.radio.rxaddresses.write(|w| w.addr0().enabled());
+                self.radio
+                    .shorts
+                    .write(|w| w.ready_start().enabled().end_disable().enabled());
+
+                compiler_fence(Ordering::Release);
+                self.radio.tasks_rxen.write(|w| unsafe { w.bits(1) });
+            }
+            RadioCmd::ListenData {
+                channel,
+                access_address,
+                crc_init,
+                ..
+            } => {
+                self.prepare_data(channel, access_address, crc_init);
+                // The answer has to follow the received packet after exactly
+                // T_IFS, which the radio can take care of by itself
+                self.radio
+                    .tifs
+                    .write(|w| unsafe { w.bits(Duration::T_IFS.as_micros()) });
+                self.point_at_rx_buf();
+                self.radio.intenset.write(|w| w.disabled().set());
+                self.radio.rxaddresses.write(|w| w.addr1().enabled());
+
+                compiler_fence(Ordering::Release);
+                self.radio.tasks_rxen.write(|w| unsafe { w.bits(1) });
+
+                // Switch to sending right after the packet has been received
+                self.radio.shorts.write(|w| {
+                    w.end_disable()
+                        .enabled()
+                        .disabled_txen()
+                        .enabled()
+                        .ready_start()
+                        .enabled()
+                });
+            }
+        }
+    }
+
+    /// Handle the `RADIO` interrupt: pass a received packet to the Link
+    /// Layer and return what it wants to happen next.
+    pub fn recv_interrupt<C: Config<Transmitter = BleRadio>>(
+        &mut self,
+        timestamp: Instant,
+        ll: &mut LinkLayer<C>,
+    ) -> Option<Cmd> {
+        if self.radio.events_disabled.read().bits() == 0 {
+            return None;
+        }
+        compiler_fence(Ordering::Acquire);
+        self.radio.events_disabled.reset();
+
+        let crc_ok = self.radio.crcstatus.read().crcstatus().is_crcok();
+        let rx_buf = self.rx_buf.take().unwrap();
+
+        let cmd = if self.advertising {
+            let header = advertising::Header::parse(rx_buf);
+            let end = cmp::min(2 + usize::from(header.payload_length()), rx_buf.len());
+            ll.process_adv_packet(timestamp, self, header, &rx_buf[2..end], crc_ok)
+        } else {
+            // The radio is about to start sending the answer. Only let it do
+            // that once the Link Layer has put one into the buffer.
+            self.radio.shorts.modify(|_, w| w.ready_start().disabled());
+
+            let header = data::Header::parse(rx_buf);
+            let end = cmp::min(2 + usize::from(header.payload_length()), rx_buf.len());
+            ll.process_data_packet(timestamp, self, header, &rx_buf[2..end], crc_ok)
+        };
+
+        self.rx_buf = Some(rx_buf);
+        Some(cmd)
+    }
+
+    fn disable(&mut self) {
+        self.radio.events_disabled.reset();
+        self.radio.tasks_disable.write(|w| unsafe { w.bits(1) });
+        while self.radio.events_disabled.read().bits() == 0 {}
+        self.radio.events_disabled.reset();
+    }
+
+    fn point_at_rx_buf(&mut self) {
+        let rx_buf = self.rx_buf.as_mut().unwrap().as_mut_ptr() as u32;
+        self.radio.packetptr.write(|w| unsafe { w.bits(rx_buf) });
+    }
+
+    fn prepare_advertising(&mut self, channel: AdvertisingChannel) {
+        self.advertising = true;
+        if !self.state().is_disabled() {
+            self.disable();
+        }
+        self.configure_channel(
+            channel.freq(),
+            channel.whitening_iv(),
+            advertising::CRC_PRESET,
+        );
+    }
+
+    fn prepare_data(&mut self, channel: DataChannel, access_address: u32, crc_init: u32) {
+        self.advertising = false;
+        self.configure_channel(
+            channel.freq(),
+            channel.whitening_iv(),
+            crc_init & 0x00FF_FFFF,
+        );
+
+        // Logical address 1 is the connection's own access address
+        self.radio
+            .base1
+            .write(|w| unsafe { w.bits(access_address << 8) });
+        self.radio
+            .prefix0
+            .modify(|_, w| unsafe { w.ap1().bits((access_address >> 24) as u8) });
+    }
+
+    fn configure_channel(&mut self, freq: u16, whitening_iv: u8, crc_init: u32) {
+        unsafe {
+            self.radio
+                .pcnf0
+                .write(|w| w.s0len().bit(true).lflen().bits(8).s1len().bits(0));
+            self.radio
+                .datawhiteiv
+                .write(|w| w.datawhiteiv().bits(whitening_iv));
+            self.radio.crcinit.write(|w| w.crcinit().bits(crc_init));
+            self.radio
+                .frequency
+                .write(|w| w.frequency().bits((freq - 2400) as u8));
+        }
+    }
+
+    fn point_at_tx_buf(&mut self) {
+        let tx_buf = self.tx_buf.as_ptr() as u32;
+        self.radio.packetptr.write(|w| unsafe { w.bits(tx_buf) });
+    }
+}
+
+impl Transmitter for BleRadio {
+    fn tx_payload_buf(&mut self) -> &mut [u8] {
+        while self.state().is_tx() {}
+        compiler_fence(Ordering::Acquire);
+        &mut self.tx_buf[2..]
+    }
+
+    fn transmit_advertising(&mut self, header: advertising::Header, channel: AdvertisingChannel) {
+        self.tx_buf[0] = header.to_u16() as u8;
+        self.tx_buf[1] = header.payload_length();
+
+        self.prepare_advertising(channel);
+        self.radio
+            .txaddress
+            .write(|w| unsafe { w.txaddress().bits(0) });
+        self.point_at_tx_buf();
+        self.radio.events_disabled.reset();
+
+        // Blocks until the packet is out
+        compiler_fence(Ordering::Release);
+        self.radio.tasks_txen.write(|w| unsafe { w.bits(1) });
+        while self.radio.events_disabled.read().bits() == 0 {}
+        compiler_fence(Ordering::Acquire);
+    }
+
+    fn transmit_data(
+        &mut self,
+        _access_address: u32,
+        _crc_iv: u32,
+        header: data::Header,
+        _channel: DataChannel,
+    ) {
+        self.tx_buf[0] = header.to_u16() as u8;
+        self.tx_buf[1] = header.payload_length();
+
+        // `configure_receiver` already set up everything else, and the radio
+        // is ramping up to send. All that's left is to let it start.
+        self.radio
+            .txaddress
+            .write(|w| unsafe { w.txaddress().bits(1) });
+        self.point_at_tx_buf();
+
+        compiler_fence(Ordering::Release);
+        self.radio
+            .shorts
+            .write(|w| w.ready_start().enabled().end_disable().disabled());
+    }
+}
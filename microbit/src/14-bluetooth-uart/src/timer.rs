@@ -0,0 +1,76 @@
+//! A microsecond timer for the Link Layer.
+//!
+//! This and `radio.rs` are adapted from the `rubble-nrf5x` crate, whose last
+//! release still builds on an older nRF HAL than the microbit BSP.
+
+use microbit::pac::TIMER0;
+use rubble::link::NextUpdate;
+use rubble::time::{Instant, Timer};
+
+/// `TIMER0` running at 1 MHz. `CC[0]` is used to read the counter, `CC[1]`
+/// fires the interrupt whenever the Link Layer wants to do something next.
+pub struct BleTimer {
+    timer: TIMER0,
+    next: Instant,
+    interrupt_enabled: bool,
+}
+
+impl BleTimer {
+    pub fn new(timer: TIMER0) -> BleTimer {
+        timer.bitmode.write(|w| w.bitmode()._32bit());
+        // 16 MHz / 2^4 = 1 MHz
+        timer.prescaler.write(|w| unsafe { w.prescaler().bits(4) });
+        timer.tasks_clear.write(|w| unsafe { w.bits(1) });
+        timer.tasks_start.write(|w| unsafe { w.bits(1) });
+
+        BleTimer {
+            timer,
+            next: Instant::from_raw_micros(0),
+            interrupt_enabled: false,
+        }
+    }
+
+    /// Set up the interrupt for the Link Layer's next update.
+    pub fn configure_interrupt(&mut self, next: NextUpdate) {
+        match next {
+            NextUpdate::Keep => {
+                // Setting it again would reset an event that might already
+                // have happened
+                if !self.interrupt_enabled {
+                    self.set_interrupt(self.next);
+                }
+            }
+            NextUpdate::Disable => self.clear_interrupt(),
+            NextUpdate::At(instant) => self.set_interrupt(instant),
+        }
+    }
+
+    /// Whether the interrupt is due. The `TIMER0` handler has to check this,
+    /// since interrupts can also fire spuriously.
+    pub fn is_interrupt_pending(&self) -> bool {
+        self.timer.events_compare[1].read().bits() != 0
+    }
+
+    /// Acknowledge the interrupt, and don't fire it again until the next
+    /// `configure_interrupt`.
+    pub fn clear_interrupt(&mut self) {
+        self.timer.intenclr.write(|w| w.compare1().clear());
+        self.timer.events_compare[1].reset();
+        self.interrupt_enabled = false;
+    }
+
+    fn set_interrupt(&mut self, at: Instant) {
+        self.next = at;
+        self.timer.cc[1].write(|w| unsafe { w.bits(at.raw_micros()) });
+        self.timer.events_compare[1].reset();
+        self.timer.intenset.write(|w| w.compare1().set());
+        self.interrupt_enabled = true;
+    }
+}
+
+impl Timer for BleTimer {
+    fn now(&self) -> Instant {
+        self.timer.tasks_capture[0].write(|w| unsafe { w.bits(1) });
+        Instant::from_raw_micros(self.timer.cc[0].read().bits())
+    }
+}
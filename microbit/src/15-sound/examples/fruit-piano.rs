@@ -0,0 +1,99 @@
+#![deny(unsafe_code)]
+#![no_main]
+#![no_std]
+
+use cortex_m_rt::entry;
+use discovery_support::{init_log, println};
+use panic_rtt_target as _;
+
+use discovery_support::display::{clear_display, display_leds, init_display};
+use discovery_support::sound::{Note, Speaker};
+use discovery_support::touch::{Ring, TouchEvent, TouchLogo, TouchRings};
+
+/// The keys of the piano: the three rings, and the logo on top.
+const KEYS: usize = 4;
+const LOGO: usize = 3;
+/// A C major chord, and the C above it on the logo.
+const NOTES: [Note; KEYS] = [Note::C4, Note::E4, Note::G4, Note::C5];
+/// Every key lights up a column of its own.
+const COLUMNS: [usize; KEYS] = [0, 2, 4, 1];
+
+/// The key that was touched last, of all the ones still held.
+fn sounding(held: &[bool; KEYS], order: &[u32; KEYS]) -> Option<usize> {
+    (0..KEYS)
+        .filter(|&key| held[key])
+        .max_by_key(|&key| order[key])
+}
+
+fn show(key: Option<usize>) {
+    match key {
+        Some(key) => {
+            let mut leds = [[0; 5]; 5];
+            for row in leds.iter_mut() {
+                row[COLUMNS[key]] = 1;
+            }
+            display_leds(leds);
+        }
+        None => clear_display(),
+    }
+}
+
+#[entry]
+fn main() -> ! {
+    init_log!();
+    let board = microbit::Board::take().unwrap();
+
+    let mut speaker = Speaker::new(board.PWM0, board.speaker_pin, board.TIMER0);
+    init_display(board.TIMER1, board.display_pins);
+    // Hands off the rings and the logo while they calibrate! Plug the fruit
+    // in first though, it's part of what they measure.
+    let mut rings = TouchRings::new(
+        board.pins.p0_02.degrade(),
+        board.pins.p0_03.degrade(),
+        board.pins.p0_04.degrade(),
+        board.TIMER2,
+    );
+    let mut logo = TouchLogo::new(board.pins.p1_04, board.TIMER3);
+    for ring in Ring::ALL {
+        println!("{:?} charges in {} µs", ring, rings.baseline(ring));
+    }
+
+    // Which keys are held, and when each of them was touched, so that letting
+    // go of one goes back to the one held before it
+    let mut held = [false; KEYS];
+    let mut order = [0; KEYS];
+    let mut touches = 0;
+    let mut playing = None;
+
+    loop {
+        let mut changed = false;
+        let ring_event = rings
+            .next_event()
+            .map(|(ring, event)| (ring as usize, event));
+        let logo_event = logo.poll_event().map(|event| (LOGO, event));
+        for (key, event) in ring_event.into_iter().chain(logo_event) {
+            held[key] = event == TouchEvent::Touched;
+            if held[key] {
+                touches += 1;
+                order[key] = touches;
+            }
+            changed = true;
+        }
+        if !changed {
+            continue;
+        }
+
+        let key = sounding(&held, &order);
+        if key != playing {
+            match key {
+                Some(key) => {
+                    println!("{} Hz", NOTES[key].0);
+                    speaker.start_tone(NOTES[key].0);
+                }
+                None => speaker.stop(),
+            }
+            show(key);
+            playing = key;
+        }
+    }
+}
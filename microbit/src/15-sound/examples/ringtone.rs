@@ -0,0 +1,39 @@
+#![deny(unsafe_code)]
+#![no_main]
+#![no_std]
+
+use cortex_m_rt::entry;
+use discovery_support::{init_log, println};
+use embedded_hal::digital::v2::InputPin;
+use panic_rtt_target as _;
+
+use discovery_support::rtttl::Ringtone;
+use discovery_support::sound::Speaker;
+
+/// Paste your own here.
+const RINGTONE: &str =
+    "Entertainer:d=4,o=5,b=140:8d,8d#,8e,c6,8e,c6,8e,2c.6,8c6,8d6,8d#6,8e6,8c6,8d6,e6,8b,d6,2c6";
+
+#[entry]
+fn main() -> ! {
+    init_log!();
+    let board = microbit::Board::take().unwrap();
+
+    let ringtone = match Ringtone::parse(RINGTONE) {
+        Ok(ringtone) => ringtone,
+        Err(error) => {
+            println!("{}", RINGTONE);
+            panic!("{:?}", error);
+        }
+    };
+
+    let mut speaker = Speaker::new(board.PWM0, board.speaker_pin, board.TIMER0);
+    let button_a = board.buttons.button_a;
+    println!("Press A to play {}", ringtone.name());
+
+    loop {
+        if button_a.is_low().unwrap() {
+            speaker.play_notes(ringtone.notes());
+        }
+    }
+}
@@ -0,0 +1,29 @@
+#![deny(unsafe_code)]
+#![no_main]
+#![no_std]
+
+use cortex_m_rt::entry;
+use discovery_support::{init_log, println};
+use embedded_hal::digital::v2::InputPin;
+use panic_rtt_target as _;
+
+use discovery_support::pcm::Pcm;
+
+/// The snake game plays this when it's over.
+const GAME_OVER: &[u8] = include_bytes!("../../11-snake-game/assets/game-over.u8");
+
+#[entry]
+fn main() -> ! {
+    init_log!();
+    let board = microbit::Board::take().unwrap();
+
+    let mut pcm = Pcm::new(board.PWM0, board.speaker_pin);
+    let button_a = board.buttons.button_a;
+    println!("Press A to play {} samples", GAME_OVER.len());
+
+    loop {
+        if button_a.is_low().unwrap() {
+            pcm.play(GAME_OVER);
+        }
+    }
+}
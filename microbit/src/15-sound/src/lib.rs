@@ -0,0 +1,10 @@
+//! The scale player, as a function that other programs can run too.
+
+#![deny(unsafe_code)]
+#![no_std]
+
+// The speaker only exists on the micro:bit v2
+#[cfg(feature = "v2")]
+mod scale;
+#[cfg(feature = "v2")]
+pub use scale::run;
@@ -0,0 +1,13 @@
+#![deny(unsafe_code)]
+#![no_main]
+#![no_std]
+
+use cortex_m_rt::entry;
+use discovery_support::init_log;
+use panic_rtt_target as _;
+
+#[entry]
+fn main() -> ! {
+    init_log!();
+    sound::run(microbit::Board::take().unwrap())
+}
@@ -0,0 +1,35 @@
+use discovery_support::println;
+use embedded_hal::digital::v2::InputPin;
+use microbit::Board;
+
+use discovery_support::sound::{Millis, Note, Speaker};
+
+const BEAT_MS: Millis = 250;
+
+const SCALE: [(Note, Millis); 8] = [
+    (Note::C4, BEAT_MS),
+    (Note::D4, BEAT_MS),
+    (Note::E4, BEAT_MS),
+    (Note::F4, BEAT_MS),
+    (Note::G4, BEAT_MS),
+    (Note::A4, BEAT_MS),
+    (Note::B4, BEAT_MS),
+    (Note::C5, 2 * BEAT_MS),
+];
+
+/// Play the scale on button A, and concert pitch on button B.
+pub fn run(board: Board) -> ! {
+    let mut speaker = Speaker::new(board.PWM0, board.speaker_pin, board.TIMER0);
+    let buttons = board.buttons;
+
+    loop {
+        if buttons.button_a.is_low().unwrap() {
+            println!("Playing the scale");
+            speaker.play_melody(&SCALE);
+        }
+        if buttons.button_b.is_low().unwrap() {
+            // Concert pitch, for tuning
+            speaker.play_note(440, BEAT_MS);
+        }
+    }
+}
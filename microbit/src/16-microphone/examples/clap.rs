@@ -0,0 +1,102 @@
+#![no_main]
+#![no_std]
+
+use core::fmt::Write;
+use cortex_m_rt::entry;
+use discovery_support::{init_log, println};
+use panic_rtt_target as _;
+
+use discovery_support::clap::{ClapDetector, ClapEvent};
+use discovery_support::display::{display_leds, init_display};
+use discovery_support::microphone::Microphone;
+use microbit::hal::uarte::{self, Baudrate, Parity};
+
+use discovery_support::serial::{FlowControl, UartePort};
+
+#[path = "../../07-uart/src/line_reader.rs"]
+mod line_reader;
+use line_reader::LineReader;
+
+#[path = "../../07-uart/src/shell.rs"]
+#[allow(dead_code)]
+mod shell;
+use shell::{Args, Command, Error, Shell};
+
+fn threshold(claps: &mut ClapDetector, mut args: Args, out: &mut dyn Write) -> Result<(), Error> {
+    match args.parse() {
+        Ok(threshold) => claps.set_threshold(threshold),
+        Err(Error::MissingArgument) => {}
+        Err(error) => return Err(error),
+    }
+    args.end()?;
+    write!(out, "threshold {}\r\n", claps.threshold())?;
+    Ok(())
+}
+
+fn loudest(claps: &mut ClapDetector, args: Args, out: &mut dyn Write) -> Result<(), Error> {
+    args.end()?;
+    write!(out, "loudest {} since last asked\r\n", claps.take_loudest())?;
+    Ok(())
+}
+
+const COMMANDS: &[Command<ClapDetector>] = &[
+    Command {
+        name: "threshold",
+        usage: "threshold [<level>]",
+        run: threshold,
+    },
+    Command {
+        name: "loudest",
+        usage: "loudest",
+        run: loudest,
+    },
+];
+
+#[entry]
+fn main() -> ! {
+    init_log!();
+    let board = microbit::Board::take().unwrap();
+
+    let serial = uarte::Uarte::new(
+        board.UARTE0,
+        board.uart.into(),
+        Parity::EXCLUDED,
+        Baudrate::BAUD115200,
+    );
+    let mut serial = UartePort::new(serial, FlowControl::Disabled);
+    // The microphone keeps us busy, so typing has to be received in the
+    // background
+    serial.enable_rx_interrupt();
+
+    init_display(board.TIMER1, board.display_pins);
+    let mut mic = Microphone::new(board.SAADC, board.microphone_pins);
+
+    let mut claps = ClapDetector::default();
+    let shell = Shell::new(COMMANDS);
+    let mut reader: LineReader<32> = LineReader::new();
+    let mut lit = false;
+
+    write!(serial, "> ").unwrap();
+    mic.record(|samples| {
+        match claps.update(samples) {
+            Some(ClapEvent::Clap) => println!("Clap"),
+            Some(ClapEvent::DoubleClap) => {
+                println!("Double clap");
+                lit = !lit;
+                display_leds([[u8::from(lit); 5]; 5]);
+            }
+            None => {}
+        }
+
+        match reader.poll_line(&mut serial) {
+            Ok(Some(line)) => {
+                shell.run(&mut claps, line, &mut serial).unwrap();
+                write!(serial, "> ").unwrap();
+            }
+            Ok(None) => {}
+            Err(error) => write!(serial, "\r\nserial error: {:?}\r\n> ", error).unwrap(),
+        }
+        true
+    });
+    unreachable!()
+}
@@ -0,0 +1,93 @@
+#![deny(unsafe_code)]
+#![no_main]
+#![no_std]
+
+use cortex_m::peripheral::DWT;
+use cortex_m_rt::entry;
+use discovery_support::{init_log, println};
+use panic_rtt_target as _;
+
+use discovery_support::display::{display_leds, init_display};
+use discovery_support::fft::{self, Fft};
+use discovery_support::microphone::{Microphone, BUFFER_LEN, SAMPLE_RATE};
+
+/// Where the five columns start and end, in bins of 31.25 Hz each: 62 Hz to
+/// 125 Hz, up to 250 Hz, 500 Hz, 1 kHz and 4 kHz. Every octave gets a column
+/// of its own, apart from the last one, which is where our ears care least.
+const BANDS: [usize; 6] = [2, 4, 8, 16, 32, fft::LEN / 2];
+/// How strong a band has to be to light up one LED, and all five of them, in
+/// decibels.
+const QUIET_DB: f32 = 20.0;
+const LOUD_DB: f32 = 60.0;
+/// How many buffers go by between reports over RTT, about a second.
+const REPORT_EVERY: u32 = SAMPLE_RATE / BUFFER_LEN as u32;
+
+#[entry]
+fn main() -> ! {
+    init_log!();
+    let mut board = microbit::Board::take().unwrap();
+
+    // Counts CPU cycles, to see how long the FFT takes
+    board.DCB.enable_trace();
+    board.DWT.enable_cycle_counter();
+
+    init_display(board.TIMER1, board.display_pins);
+    let mut mic = Microphone::new(board.SAADC, board.microphone_pins);
+    let fft = Fft::new();
+    let window = hann_window();
+
+    let mut re = [0; fft::LEN];
+    let mut im = [0; fft::LEN];
+    let mut buffers = 0;
+    mic.record(|samples| {
+        let start = DWT::cycle_count();
+
+        let mean = samples.iter().map(|&s| i32::from(s)).sum::<i32>() / samples.len() as i32;
+        for ((re, &sample), &weight) in re.iter_mut().zip(samples).zip(&window) {
+            // 12 bits around the mean turn into Q15 from -0.5 to 0.5
+            let sample = (i32::from(sample) - mean) << 3;
+            *re = ((sample * i32::from(weight)) >> 15) as i16;
+        }
+        im = [0; fft::LEN];
+        fft.transform(&mut re, &mut im);
+
+        let mut levels = [0.0; 5];
+        for (level, band) in levels.iter_mut().zip(BANDS.windows(2)) {
+            let power: u32 = (band[0]..band[1]).map(|k| fft::power(&re, &im, k)).sum();
+            *level = 10.0 * libm::log10f(power.max(1) as f32);
+        }
+        show_columns(levels);
+
+        let cycles = DWT::cycle_count().wrapping_sub(start);
+        buffers += 1;
+        if buffers % REPORT_EVERY == 0 {
+            println!("{:.0?} dB, {} cycles", levels, cycles);
+        }
+        true
+    });
+    unreachable!()
+}
+
+/// The Hann window, in Q15. Cutting a recording into buffers makes jumps at
+/// the ends that the FFT would hear as every frequency at once, so the window
+/// fades every buffer in and out.
+fn hann_window() -> [i16; fft::LEN] {
+    let mut window = [0; fft::LEN];
+    for (i, weight) in window.iter_mut().enumerate() {
+        let angle = 2.0 * core::f32::consts::PI * i as f32 / fft::LEN as f32;
+        *weight = ((1.0 - libm::cosf(angle)) / 2.0 * f32::from(i16::MAX)) as i16;
+    }
+    window
+}
+
+/// Light every column from the bottom up, as high as its band is loud.
+fn show_columns(levels: [f32; 5]) {
+    let mut leds = [[0; 5]; 5];
+    for (col, &db) in levels.iter().enumerate() {
+        let height = ((db - QUIET_DB) / (LOUD_DB - QUIET_DB) * 5.0).clamp(0.0, 5.0) as usize;
+        for row in leds.iter_mut().rev().take(height) {
+            row[col] = 1;
+        }
+    }
+    display_leds(leds);
+}
@@ -0,0 +1,10 @@
+//! The sound level meter, as a function that other programs can run too.
+
+#![deny(unsafe_code)]
+#![no_std]
+
+// Only the micro:bit v2 has a microphone
+#[cfg(feature = "v2")]
+mod meter;
+#[cfg(feature = "v2")]
+pub use meter::run;
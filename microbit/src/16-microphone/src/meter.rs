@@ -0,0 +1,50 @@
+use discovery_support::println;
+use microbit::Board;
+
+use discovery_support::display::{display_leds, init_display};
+use discovery_support::microphone::{rms, Microphone, MAX_SAMPLE};
+
+/// Anything quieter doesn't light up a bar, anything louder lights them all.
+/// Both are in decibels below the loudest sound the microphone can take.
+const QUIET_DB: f32 = -50.0;
+const LOUD_DB: f32 = -10.0;
+
+/// Show how loud it is, as a bar that grows from the bottom of the display.
+pub fn run(board: Board) -> ! {
+    init_display(board.TIMER1, board.display_pins);
+    let mut mic = Microphone::new(board.SAADC, board.microphone_pins);
+
+    let mut shown = None;
+    mic.record(|samples| {
+        let db = decibels(rms(samples));
+        let bars = level(db);
+        if shown != Some(bars) {
+            println!("{:.1} dB", db);
+            show_bars(bars);
+            shown = Some(bars);
+        }
+        true
+    });
+    unreachable!()
+}
+
+/// How loud `rms` is in decibels, compared to the loudest a sine wave can be.
+fn decibels(rms: f32) -> f32 {
+    let loudest = f32::from(MAX_SAMPLE) / 2.0 / core::f32::consts::SQRT_2;
+    20.0 * libm::log10f(rms.max(1.0) / loudest)
+}
+
+/// How many of the 5 bars to light for a sound of `db`.
+fn level(db: f32) -> usize {
+    let bars = (db - QUIET_DB) / (LOUD_DB - QUIET_DB) * 5.0;
+    bars.clamp(0.0, 5.0) as usize
+}
+
+/// Fill the display from the bottom up, one row per bar.
+fn show_bars(bars: usize) {
+    let mut leds = [[0; 5]; 5];
+    for row in leds.iter_mut().rev().take(bars) {
+        *row = [1; 5];
+    }
+    display_leds(leds);
+}
@@ -0,0 +1,115 @@
+#![deny(unsafe_code)]
+#![no_main]
+#![no_std]
+
+use cortex_m_rt::entry;
+use discovery_support::{init_log, println};
+use embedded_hal::digital::v2::InputPin;
+use panic_rtt_target as _;
+
+use discovery_support::buttons::Button;
+use discovery_support::display::{display_leds, free_display, init_display};
+use discovery_support::scroll::{steps, SCROLL_STEP_MS};
+use microbit::{
+    board::{Board, Buttons},
+    hal::{prelude::*, Timer},
+    pac::TIMER0,
+};
+
+/// A chapter's program, ready to take over the board.
+struct Demo {
+    name: &'static str,
+    run: fn(Board) -> !,
+}
+
+const DEMOS: [Demo; 3] = [
+    Demo {
+        name: "Roulette",
+        run: led_roulette::run,
+    },
+    Demo {
+        name: "Scale",
+        run: sound::run,
+    },
+    Demo {
+        name: "Sound meter",
+        run: microphone::run,
+    },
+];
+
+/// How often the buttons are looked at while the menu scrolls.
+const POLL_MS: u32 = 10;
+/// Buttons bounce for a few milliseconds after they're let go.
+const DEBOUNCE_MS: u32 = 20;
+
+#[entry]
+fn main() -> ! {
+    init_log!();
+    let mut board = Board::take().unwrap();
+
+    init_display(board.TIMER1, board.display_pins);
+    let mut timer = Timer::new(board.TIMER0);
+
+    // Button A moves on to the next demo, button B starts the one showing
+    let mut selected = 0;
+    loop {
+        let demo = &DEMOS[selected];
+        println!("{}: {}", selected + 1, demo.name);
+        match scroll_menu_entry(&mut timer, &board.buttons, selected + 1, demo.name) {
+            Some(Button::A) => selected = (selected + 1) % DEMOS.len(),
+            Some(Button::B) => break,
+            // Scrolled all the way through, show it again. The rings of the
+            // edge connector aren't listened to here.
+            _ => {}
+        }
+    }
+
+    // The demo gets the board the way `Board::take` handed it out, so put
+    // back everything the menu has borrowed
+    let (display_timer, display_pins) = free_display().unwrap();
+    board.TIMER1 = display_timer;
+    board.display_pins = display_pins;
+    board.TIMER0 = timer.free();
+
+    println!("Starting {}", DEMOS[selected].name);
+    (DEMOS[selected].run)(board)
+}
+
+/// Scroll `number` and `name` across the display once, unless a button gets
+/// pressed first. Only returns once that button has been released again, so
+/// that the demo doesn't see the press as well.
+fn scroll_menu_entry(
+    timer: &mut Timer<TIMER0>,
+    buttons: &Buttons,
+    number: usize,
+    name: &str,
+) -> Option<Button> {
+    let digit = char::from_digit(number as u32, 10).unwrap_or('?');
+    let mut text = [0; 4];
+    let number = digit.encode_utf8(&mut text);
+
+    for leds in steps(number).chain(steps(name)) {
+        display_leds(leds);
+        for _ in 0..SCROLL_STEP_MS / POLL_MS {
+            if let Some(button) = pressed(buttons) {
+                while pressed(buttons).is_some() {
+                    timer.delay_ms(POLL_MS);
+                }
+                timer.delay_ms(DEBOUNCE_MS);
+                return Some(button);
+            }
+            timer.delay_ms(POLL_MS);
+        }
+    }
+    None
+}
+
+fn pressed(buttons: &Buttons) -> Option<Button> {
+    if buttons.button_a.is_low().unwrap() {
+        Some(Button::A)
+    } else if buttons.button_b.is_low().unwrap() {
+        Some(Button::B)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,57 @@
+#![no_main]
+#![no_std]
+
+use core::panic::PanicInfo;
+use core::ptr;
+use cortex_m_rt::{entry, exception, ExceptionFrame};
+use discovery_support::{init_log, println};
+
+use discovery_support::crash_log;
+use discovery_support::display::{display_leds, init_display};
+use microbit::{
+    board::Board,
+    hal::{prelude::*, Timer},
+};
+
+/// Past the end of the nRF52833's 128K of RAM, where there's nothing to read.
+const NOWHERE: usize = 0x2004_0000;
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    crash_log::record_panic(info)
+}
+
+#[exception]
+unsafe fn HardFault(frame: &ExceptionFrame) -> ! {
+    crash_log::record_fault(frame.pc())
+}
+
+#[entry]
+fn main() -> ! {
+    init_log!();
+    let board = Board::take().unwrap();
+
+    let mut delay = Timer::new(board.TIMER0);
+    init_display(board.TIMER1, board.display_pins);
+
+    if let Some(crash) = crash_log::take() {
+        println!("Crashed last time: {}", crash);
+        display_leds(crash_log::GLYPH);
+        delay.delay_ms(2_000u32);
+        display_leds([[0; 5]; 5]);
+    }
+
+    println!("Press A to panic, or B to read from where there's no memory");
+    let button_a = board.buttons.button_a;
+    let button_b = board.buttons.button_b;
+    loop {
+        if button_a.is_low().unwrap() {
+            panic!("button A was pressed");
+        }
+        if button_b.is_low().unwrap() {
+            let word = unsafe { ptr::read_volatile(NOWHERE as *const u32) };
+            println!("Read {}", word);
+        }
+        delay.delay_ms(10u32);
+    }
+}
@@ -0,0 +1,46 @@
+#![no_main]
+#![no_std]
+
+use core::ptr;
+use cortex_m_rt::{entry, exception, ExceptionFrame};
+use discovery_support::{init_log, println};
+use panic_rtt_target as _;
+
+use discovery_support::hard_fault;
+use microbit::{
+    board::Board,
+    hal::{prelude::*, Timer},
+};
+
+/// Past the end of the RAM of either micro:bit, where there's nothing to
+/// read.
+const NOWHERE: usize = 0x2004_0000;
+
+// The `hard-fault` feature of `discovery-support` would install the same
+// handler, but then `crash-log` couldn't have one of its own
+#[exception]
+unsafe fn HardFault(frame: &ExceptionFrame) -> ! {
+    hard_fault::report(frame)
+}
+
+/// A function of its own, so that `lr` says where it was called from.
+#[inline(never)]
+fn read_nowhere() -> u32 {
+    unsafe { ptr::read_volatile(NOWHERE as *const u32) }
+}
+
+#[entry]
+fn main() -> ! {
+    init_log!();
+    let board = Board::take().unwrap();
+    let mut delay = Timer::new(board.TIMER0);
+
+    println!("Press A to read from where there's no memory");
+    let button_a = board.buttons.button_a;
+    loop {
+        if button_a.is_low().unwrap() {
+            println!("Read {}", read_nowhere());
+        }
+        delay.delay_ms(10u32);
+    }
+}
@@ -0,0 +1,142 @@
+#![no_main]
+#![no_std]
+
+use core::cell::Cell;
+use core::sync::atomic::{AtomicBool, Ordering};
+use cortex_m::interrupt::{free, Mutex};
+use cortex_m_rt::entry;
+use discovery_support::{init_log, println};
+use panic_rtt_target as _;
+
+use discovery_support::display::{display_leds, init_display};
+use discovery_support::shared::Shared;
+use discovery_support::watchdog::{Feeder, Watchdog};
+use microbit::{
+    board::Board,
+    hal::{
+        prelude::*,
+        timer::{Periodic, Timer},
+    },
+    pac::{self, interrupt, TIMER0},
+};
+
+/// Without being fed for this long, the watchdog resets the chip.
+const TIMEOUT_MS: u32 = 2_000;
+const BLINK_MS: u32 = 250;
+/// How long the main loop takes per round.
+const LOOP_MS: u32 = 100;
+
+const HEART: [[u8; 5]; 5] = [
+    [0, 1, 0, 1, 0],
+    [1, 1, 1, 1, 1],
+    [1, 1, 1, 1, 1],
+    [0, 1, 1, 1, 0],
+    [0, 0, 1, 0, 0],
+];
+const CROSS: [[u8; 5]; 5] = [
+    [1, 0, 0, 0, 1],
+    [0, 1, 0, 1, 0],
+    [0, 0, 1, 0, 0],
+    [0, 1, 0, 1, 0],
+    [1, 0, 0, 0, 1],
+];
+
+/// The second task, besides the main loop: it makes the heart beat, in the
+/// `TIMER0` interrupt, and feeds the watchdog while it's at it.
+struct Blinker {
+    timer: Timer<TIMER0, Periodic>,
+    feeder: Feeder,
+    on: bool,
+}
+
+static BLINKER: Shared<Blinker> = Shared::new();
+/// Makes the blinker stop doing its job, as if it was waiting for something
+/// that never comes.
+static BLINKER_STUCK: AtomicBool = AtomicBool::new(false);
+
+/// A very simple lock, which whoever holds it has to let go of before anybody
+/// else can take it.
+static LOCKED: Mutex<Cell<bool>> = Mutex::new(Cell::new(false));
+
+#[entry]
+fn main() -> ! {
+    init_log!();
+    let board = Board::take().unwrap();
+
+    let mut delay = Timer::new(board.TIMER2);
+    init_display(board.TIMER1, board.display_pins);
+
+    if Watchdog::caused_reset() {
+        println!("The watchdog had to step in, starting over");
+        display_leds(CROSS);
+        delay.delay_ms(1_000u32);
+    }
+
+    let (_watchdog, [mut feeder, blinker_feeder]) = Watchdog::start::<2>(TIMEOUT_MS).unwrap();
+
+    let mut timer = Timer::periodic(board.TIMER0);
+    timer.enable_interrupt();
+    // The timer ticks at 1MHz
+    timer.start(BLINK_MS * 1_000);
+    BLINKER.lend(Blinker {
+        timer,
+        feeder: blinker_feeder,
+        on: false,
+    });
+    unsafe { pac::NVIC::unmask(pac::Interrupt::TIMER0) }
+
+    println!("Press A to get the main loop stuck, or B to get the blinker stuck");
+    let button_a = board.buttons.button_a;
+    let button_b = board.buttons.button_b;
+    loop {
+        if button_a.is_low().unwrap() {
+            println!("Taking the lock twice...");
+            lock();
+            // Some code much further away that doesn't know the lock is
+            // already taken
+            lock();
+            unreachable!();
+        }
+        if button_b.is_low().unwrap() && !BLINKER_STUCK.load(Ordering::Relaxed) {
+            println!("The blinker is stuck, even though the main loop isn't...");
+            BLINKER_STUCK.store(true, Ordering::Relaxed);
+        }
+
+        feeder.feed();
+        delay.delay_ms(LOOP_MS);
+    }
+}
+
+/// Take the lock, waiting for as long as somebody else holds it.
+fn lock() {
+    loop {
+        let taken = free(|cs| {
+            let locked = LOCKED.borrow(cs);
+            if locked.get() {
+                false
+            } else {
+                locked.set(true);
+                true
+            }
+        });
+        if taken {
+            return;
+        }
+    }
+}
+
+#[interrupt]
+fn TIMER0() {
+    BLINKER.with(|blinker| {
+        // Clears the event, so that the interrupt doesn't fire again
+        // straight away
+        blinker.timer.wait().ok();
+        if BLINKER_STUCK.load(Ordering::Relaxed) {
+            return;
+        }
+
+        blinker.on = !blinker.on;
+        display_leds(if blinker.on { HEART } else { [[0; 5]; 5] });
+        blinker.feeder.feed();
+    });
+}
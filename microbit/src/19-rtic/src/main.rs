@@ -0,0 +1,166 @@
+#![no_main]
+#![no_std]
+
+use panic_rtt_target as _;
+
+#[rtic::app(device = microbit::pac, peripherals = true)]
+mod app {
+    use core::fmt::Write;
+    use embedded_hal::serial::{Read, Write as _};
+    use microbit::{
+        board::Board,
+        display::nonblocking::{BitImage, Display},
+        hal::{
+            gpiote::Gpiote,
+            uarte::{Baudrate, Parity, Uarte, UarteRx, UarteTx},
+        },
+        pac::{TIMER1, UARTE0},
+    };
+    use rtt_target::{rprintln, rtt_init_print};
+
+    /// How many columns of the display can light up.
+    const MAX_LEVEL: usize = 5;
+
+    /// Everything more than one task needs. They have to `lock` it to get at
+    /// it, which makes sure no other task that uses it can run in the
+    /// meantime.
+    #[shared]
+    struct Shared {
+        display: Display<TIMER1>,
+        tx: UarteTx<UARTE0>,
+        /// How many columns are lit, changed by the buttons as well as over
+        /// the serial port.
+        level: usize,
+    }
+
+    /// Everything only one task needs, it can use it without a lock.
+    #[local]
+    struct Local {
+        gpiote: Gpiote,
+        rx: UarteRx<UARTE0>,
+    }
+
+    // The DMA buffers of the UARTE have to live forever, `init` gets to
+    // hand out its local resources as `&'static mut`
+    #[init(local = [tx_buf: [u8; 32] = [0; 32], rx_buf: [u8; 1] = [0; 1]])]
+    fn init(cx: init::Context) -> (Shared, Local, init::Monotonics) {
+        rtt_init_print!();
+        let board = Board::new(cx.device, cx.core);
+
+        let display = Display::new(board.TIMER1, board.display_pins);
+
+        let gpiote = Gpiote::new(board.GPIOTE);
+        gpiote
+            .channel0()
+            .input_pin(&board.buttons.button_a.degrade())
+            .hi_to_lo()
+            .enable_interrupt();
+        gpiote
+            .channel1()
+            .input_pin(&board.buttons.button_b.degrade())
+            .hi_to_lo()
+            .enable_interrupt();
+
+        let serial = Uarte::new(
+            board.UARTE0,
+            board.uart.into(),
+            Parity::EXCLUDED,
+            Baudrate::BAUD115200,
+        );
+        let (mut tx, mut rx) = serial.split(cx.local.tx_buf, cx.local.rx_buf).unwrap();
+        // Raise the UARTE0 interrupt whenever a byte has arrived, and start
+        // waiting for the first one
+        unsafe { &*UARTE0::ptr() }
+            .intenset
+            .write(|w| w.endrx().set());
+        rx.read().ok();
+
+        write!(tx, "Press A or B, or type + or -\r\n").unwrap();
+        nb::block!(tx.flush()).unwrap();
+
+        (
+            Shared {
+                display,
+                tx,
+                level: 0,
+            },
+            Local { gpiote, rx },
+            init::Monotonics(),
+        )
+    }
+
+    /// Runs whenever no task has anything to do.
+    #[idle]
+    fn idle(_: idle::Context) -> ! {
+        loop {
+            cortex_m::asm::wfi();
+        }
+    }
+
+    /// Light up the next row of LEDs. The display flickers as soon as this
+    /// has to wait, so it gets the highest priority.
+    #[task(binds = TIMER1, priority = 3, shared = [display])]
+    fn refresh(mut cx: refresh::Context) {
+        cx.shared
+            .display
+            .lock(|display| display.handle_display_event());
+    }
+
+    /// Button A turns the level down, button B turns it up.
+    #[task(binds = GPIOTE, priority = 2, local = [gpiote], shared = [display, tx, level])]
+    fn buttons(cx: buttons::Context) {
+        let gpiote = cx.local.gpiote;
+        let down = gpiote.channel0().is_event_triggered();
+        let up = gpiote.channel1().is_event_triggered();
+        gpiote.reset_events();
+
+        (cx.shared.level, cx.shared.display, cx.shared.tx).lock(|level, display, tx| {
+            change_level(level, up, down, display);
+            write!(tx, "level {}\r\n", level).ok();
+            nb::block!(tx.flush()).ok();
+        });
+    }
+
+    /// Echo whatever is typed, and turn the level up on `+` and down on `-`.
+    #[task(binds = UARTE0_UART0, priority = 1, local = [rx], shared = [display, tx, level])]
+    fn serial(mut cx: serial::Context) {
+        let byte = match cx.local.rx.read() {
+            Ok(byte) => byte,
+            Err(nb::Error::WouldBlock) => return,
+            Err(nb::Error::Other(error)) => {
+                rprintln!("Receive error: {:?}", error);
+                cx.local.rx.read().ok();
+                return;
+            }
+        };
+        // Start waiting for the next byte
+        cx.local.rx.read().ok();
+
+        cx.shared.tx.lock(|tx| {
+            nb::block!(tx.write(byte)).ok();
+            nb::block!(tx.flush()).ok();
+        });
+        if byte == b'+' || byte == b'-' {
+            (cx.shared.level, cx.shared.display).lock(|level, display| {
+                change_level(level, byte == b'+', byte == b'-', display);
+            });
+        }
+    }
+
+    fn change_level(level: &mut usize, up: bool, down: bool, display: &mut Display<TIMER1>) {
+        if up && *level < MAX_LEVEL {
+            *level += 1;
+        }
+        if down && *level > 0 {
+            *level -= 1;
+        }
+
+        let mut leds = [[0; 5]; 5];
+        for row in leds.iter_mut() {
+            for led in row.iter_mut().take(*level) {
+                *led = 1;
+            }
+        }
+        display.show(&BitImage::new(&leds));
+    }
+}
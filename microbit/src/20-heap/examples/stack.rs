@@ -0,0 +1,107 @@
+#![no_main]
+#![no_std]
+
+use core::fmt::Write;
+use core::hint::black_box;
+use cortex_m_rt::entry;
+use discovery_support::init_log;
+use discovery_support::stackcheck::{stack_size, stack_used};
+use heapless::Vec;
+use panic_rtt_target as _;
+
+#[cfg(feature = "v1")]
+use microbit::hal::uart::{self, Baudrate, Parity};
+
+#[cfg(feature = "v2")]
+use microbit::hal::uarte::{self, Baudrate, Parity};
+
+/// Calls itself `levels` deep. Every level keeps a buffer on the stack, and
+/// uses it after the call below it returns, so the compiler can't turn the
+/// recursion into a loop.
+#[inline(never)]
+fn recurse(levels: u32) -> u32 {
+    let mut scratch = [levels as u8; 32];
+    black_box(&mut scratch);
+    if levels == 0 {
+        return 0;
+    }
+    recurse(levels - 1) + scratch[0] as u32
+}
+
+/// Fills a `heapless::Vec` that lives on the stack, like any other local
+/// variable, all `N` bytes of it.
+#[inline(never)]
+fn fill<const N: usize>() -> usize {
+    let mut buffer: Vec<u8, N> = Vec::new();
+    while buffer.push(0xff).is_ok() {}
+    black_box(&mut buffer).len()
+}
+
+#[entry]
+fn main() -> ! {
+    init_log!();
+    let board = microbit::Board::take().unwrap();
+
+    #[cfg(feature = "v1")]
+    let mut serial = uart::Uart::new(
+        board.UART0,
+        board.uart.into(),
+        Parity::EXCLUDED,
+        Baudrate::BAUD115200,
+    );
+
+    #[cfg(feature = "v2")]
+    let mut serial = uarte::Uarte::new(
+        board.UARTE0,
+        board.uart.into(),
+        Parity::EXCLUDED,
+        Baudrate::BAUD115200,
+    );
+
+    write!(serial, "{} bytes of stack\r\n", stack_size()).unwrap();
+    write!(serial, "{} used before we get going\r\n", stack_used()).unwrap();
+
+    // The high-water mark only ever goes up, so measure from the shallowest
+    // to the deepest
+    for &levels in &[10, 20, 40, 80] {
+        let before = stack_used();
+        recurse(levels);
+        let used = stack_used();
+        write!(
+            serial,
+            "{} levels deep: {} used, {} more, {} per level\r\n",
+            levels,
+            used,
+            used - before,
+            (used - before) / levels as usize
+        )
+        .unwrap();
+    }
+
+    // A `heapless` buffer takes all of its room on the stack, however little
+    // of it is in use. That's what to compare with the stack that's left.
+    let before = stack_used();
+    fill::<1024>();
+    let used = stack_used();
+    write!(
+        serial,
+        "A 1024 byte buffer: {} used, {} more\r\n",
+        used,
+        used - before
+    )
+    .unwrap();
+
+    // With enough levels, the stack runs into the statics, and then anything
+    // can happen. There's no check for that, but the measurement shows how
+    // close it got.
+    write!(
+        serial,
+        "{} bytes of stack were never used\r\n",
+        stack_size() - stack_used()
+    )
+    .unwrap();
+
+    loop {
+        cortex_m::asm::wfi();
+    }
+}
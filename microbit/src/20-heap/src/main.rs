@@ -0,0 +1,126 @@
+#![no_main]
+#![no_std]
+
+// `alloc` comes with Rust, like `core`, but only gets linked in when asked for
+extern crate alloc;
+
+use alloc::{boxed::Box, format, string::String, vec::Vec};
+use core::mem::MaybeUninit;
+use core::panic::PanicInfo;
+use core::ptr::addr_of_mut;
+use cortex_m_rt::entry;
+use discovery_support::{init_log, println};
+use embedded_alloc::LlffHeap as Heap;
+use embedded_hal::digital::v2::InputPin;
+
+/// How much of the RAM the heap gets, in bytes. Everything else is left for
+/// the statics and the stack.
+const HEAP_SIZE: usize = 4096;
+
+/// Every `Box`, `Vec` and `String` gets its memory from here.
+#[global_allocator]
+static HEAP: Heap = Heap::empty();
+
+/// How much of the heap is taken, and how much is left.
+#[derive(Clone, Copy, Debug)]
+struct HeapStats {
+    used: usize,
+    free: usize,
+}
+
+fn heap_stats() -> HeapStats {
+    HeapStats {
+        used: HEAP.used(),
+        free: HEAP.free(),
+    }
+}
+
+/// Counting the bytes from the start is easy to get wrong, so this prints
+/// how many the heap has handed out since `before`.
+fn report(what: &str, before: HeapStats) -> HeapStats {
+    let now = heap_stats();
+    println!(
+        "{}: {} bytes, {} used in total, {} free",
+        what,
+        now.used as isize - before.used as isize,
+        now.used,
+        now.free
+    );
+    now
+}
+
+/// The allocator runs out of memory when it can't find a free block that is
+/// big enough. The allocation then fails with a panic, so this is where that
+/// ends up, along with every other panic. Either way, the heap statistics
+/// help finding out what happened.
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    println!("{}", info);
+    let stats = heap_stats();
+    println!("Heap: {} bytes used, {} free", stats.used, stats.free);
+    loop {
+        cortex_m::asm::wfi();
+    }
+}
+
+#[entry]
+fn main() -> ! {
+    init_log!();
+    let board = microbit::Board::take().unwrap();
+
+    // The heap has to be set up before anything uses it. Its memory is a
+    // static like any other, so the linker makes sure that it fits.
+    {
+        static mut HEAP_MEM: [MaybeUninit<u8>; HEAP_SIZE] = [MaybeUninit::uninit(); HEAP_SIZE];
+        unsafe { HEAP.init(addr_of_mut!(HEAP_MEM) as usize, HEAP_SIZE) }
+    }
+    let empty = heap_stats();
+    assert_eq!(empty.used, 0);
+    assert_eq!(empty.free, HEAP_SIZE);
+
+    // A `Vec` grows as needed, and doesn't need to know how big it's going to
+    // be, unlike a `heapless::Vec`. Every time it's full, it asks for a block
+    // twice as big and moves over, so it often takes more than it uses.
+    let mut squares = Vec::new();
+    for i in 0..100u32 {
+        squares.push(i * i);
+    }
+    let stats = report("100 squares", empty);
+    assert!(stats.used >= squares.capacity() * 4);
+    squares.shrink_to_fit();
+    let stats = report("shrunk to fit", stats);
+
+    let text: String = format!("The sum of the squares is {}", squares.iter().sum::<u32>());
+    println!("{}", text);
+    let stats = report("the text", stats);
+
+    let boxed = Box::new([0u8; 100]);
+    let stats = report("a boxed array", stats);
+    assert!(stats.used >= boxed.len());
+
+    // Once everything has been dropped, the heap is empty again. If it isn't,
+    // something is still holding on to memory.
+    drop(boxed);
+    drop(text);
+    drop(squares);
+    report("all dropped", stats);
+    assert_eq!(heap_stats().used, 0);
+
+    // Running out of memory doesn't have to end in a panic. `try_reserve`
+    // asks for the memory up front, and says so when there isn't enough.
+    let mut big: Vec<u8> = Vec::new();
+    match big.try_reserve(2 * HEAP_SIZE) {
+        Ok(()) => println!("Reserved {} bytes", big.capacity()),
+        Err(_) => println!("There's no room for {} bytes", 2 * HEAP_SIZE),
+    }
+
+    println!("Press button A to run out of memory");
+    while board.buttons.button_a.is_high().unwrap() {}
+
+    // A plain `push` has no way to say that there's no room, so this
+    // eventually ends up in the panic handler
+    let mut hog: Vec<u32> = Vec::new();
+    loop {
+        hog.push(hog.len() as u32);
+    }
+}
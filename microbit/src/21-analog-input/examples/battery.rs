@@ -0,0 +1,69 @@
+#![deny(unsafe_code)]
+#![no_main]
+#![no_std]
+
+use cortex_m_rt::entry;
+use discovery_logic::battery::{bars, LowBattery, BARS};
+use discovery_support::analog::vdd_millivolts;
+use discovery_support::delay::LowPowerDelay;
+use discovery_support::display::{display_greyscale, init_display, MAX_BRIGHTNESS};
+use discovery_support::{init_log, println};
+use microbit::{
+    board::Board,
+    hal::{clocks::Clocks, prelude::*},
+};
+use panic_rtt_target as _;
+
+/// How bright the outline of the battery is. The bars are as bright as the
+/// LEDs go.
+const OUTLINE: u8 = 2;
+/// How long the icon stays on, or off while it blinks.
+const BLINK_MS: u32 = 500;
+/// Measure the supply every so many blinks.
+const MEASURE_EVERY: u32 = 4;
+
+/// A battery standing up, with its terminal at the top, and `filled` of its
+/// [`BARS`] rows lit from the bottom up.
+fn battery_icon(filled: u32) -> [[u8; 5]; 5] {
+    let mut leds = [[0; 5]; 5];
+    leds[0][2] = OUTLINE;
+    for bar in 0..BARS {
+        let row = 4 - bar as usize;
+        if bar < filled {
+            leds[row][1..4].copy_from_slice(&[MAX_BRIGHTNESS; 3]);
+        } else {
+            leds[row][1] = OUTLINE;
+            leds[row][3] = OUTLINE;
+        }
+    }
+    leds
+}
+
+#[entry]
+fn main() -> ! {
+    init_log!();
+
+    let mut board = Board::take().unwrap();
+    let clocks = Clocks::new(board.CLOCK).start_lfclk();
+    let mut delay = LowPowerDelay::new(board.RTC0, &clocks, &mut board.SCB);
+    init_display(board.TIMER1, board.display_pins);
+
+    let mut saadc = board.SAADC;
+    let mut battery = LowBattery::default();
+    let mut icon = battery_icon(BARS);
+    let mut count = 0;
+    loop {
+        if count % MEASURE_EVERY == 0 {
+            let mv = vdd_millivolts(&mut saadc);
+            let low = if battery.update(mv) { ", low" } else { "" };
+            println!("{} mV, {} of {} bars{}", mv, bars(mv), BARS, low);
+            icon = battery_icon(bars(mv));
+        }
+
+        // While the batteries are low, every other blink is dark
+        let dark = battery.is_low() && count % 2 == 1;
+        display_greyscale(if dark { [[0; 5]; 5] } else { icon });
+        count = count.wrapping_add(1);
+        delay.delay_ms(BLINK_MS);
+    }
+}
@@ -0,0 +1,68 @@
+#![deny(unsafe_code)]
+#![no_main]
+#![no_std]
+
+use cortex_m_rt::entry;
+use discovery_support::analog::{AnalogPin, FULL_SCALE_MV, MAX_VALUE};
+use discovery_support::delay::LowPowerDelay;
+use discovery_support::display::{init_display, FrameBuffer, MAX_BRIGHTNESS};
+use discovery_support::{init_log, println};
+use microbit::{
+    board::Board,
+    hal::{clocks::Clocks, prelude::*},
+};
+use panic_rtt_target as _;
+
+/// How many times the bar is measured before it's drawn again.
+const OVERSAMPLING: u32 = 16;
+/// The offset drifts with the temperature, so calibrate again every minute.
+const CALIBRATE_EVERY: u32 = 60 * 20;
+
+/// Turn `value` into a bar that grows from the left, one column at a time.
+/// The column at the tip of the bar lights up as far as it's filled, so that
+/// the bar moves smoothly instead of jumping from column to column.
+fn draw_bar(frame: &mut FrameBuffer, value: u16) {
+    let steps = 5 * u32::from(MAX_BRIGHTNESS);
+    let level = u32::from(value) * steps / u32::from(MAX_VALUE);
+    for col in 0..5 {
+        let lit = level.saturating_sub(col as u32 * u32::from(MAX_BRIGHTNESS));
+        let brightness = lit.min(u32::from(MAX_BRIGHTNESS)) as u8;
+        for row in 0..5 {
+            frame.set(row, col, brightness);
+        }
+    }
+    frame.commit();
+}
+
+#[entry]
+fn main() -> ! {
+    init_log!();
+
+    let mut board = Board::take().unwrap();
+    let clocks = Clocks::new(board.CLOCK).start_lfclk();
+    let mut delay = LowPowerDelay::new(board.RTC0, &clocks, &mut board.SCB);
+    init_display(board.TIMER1, board.display_pins);
+
+    // The potentiometer's wiper goes on ring P0, which is pin P0.02
+    let mut pot = AnalogPin::new(board.SAADC, board.pins.p0_02);
+    pot.set_oversampling(OVERSAMPLING);
+
+    let mut frame = FrameBuffer::new();
+    let mut count = 0;
+    loop {
+        let value = pot.read();
+        println!(
+            "{:4} ({} mV)",
+            value,
+            u32::from(value) * FULL_SCALE_MV / u32::from(MAX_VALUE)
+        );
+        draw_bar(&mut frame, value);
+
+        count += 1;
+        if count == CALIBRATE_EVERY {
+            pot.calibrate();
+            count = 0;
+        }
+        delay.delay_ms(50u32);
+    }
+}
@@ -0,0 +1,174 @@
+#![deny(unsafe_code)]
+#![no_main]
+#![no_std]
+
+use core::cell::RefCell;
+use core::f32::consts::PI;
+use core::fmt::Write;
+use cortex_m_rt::entry;
+use discovery_support::{init_log, println};
+use panic_rtt_target as _;
+
+// The compass chapter's calibration. Its lints are that chapter's business,
+// there's no need to hear about them twice.
+#[path = "../../09-led-compass/src/calibration.rs"]
+#[allow(dead_code, clippy::assign_op_pattern, clippy::needless_return)]
+mod calibration;
+use crate::calibration::{calibrated_measurement, saved_calibration};
+
+// `heading` names its directions after the arrows of the LED compass
+#[path = "../../09-led-compass/src/led.rs"]
+#[allow(dead_code)]
+mod led;
+
+#[path = "../../09-led-compass/src/heading.rs"]
+#[allow(dead_code)]
+mod heading;
+use crate::heading::{accel_to_cartesian, tilt_compensated_heading};
+
+use discovery_support::i2c::RefCellDevice;
+use discovery_support::settings::Settings;
+use embedded_graphics::{
+    // The ASCII fonts have no degree sign
+    mono_font::{iso_8859_1::FONT_10X20, iso_8859_1::FONT_6X10, MonoTextStyle},
+    pixelcolor::BinaryColor,
+    prelude::*,
+    primitives::{Circle, Line, PrimitiveStyle},
+    text::Text,
+};
+use heapless::String;
+use lsm303agr::{AccelOutputDataRate, Lsm303agr, MagOutputDataRate};
+use microbit::{display::blocking::Display, hal::prelude::*, hal::Temp, hal::Timer};
+use ssd1306::{prelude::*, I2CDisplayInterface, Ssd1306};
+
+#[cfg(feature = "v1")]
+use microbit::{hal::twi, pac::twi0::frequency::FREQUENCY_A};
+
+#[cfg(feature = "v2")]
+use microbit::{hal::twim, pac, pac::twim0::frequency::FREQUENCY_A};
+
+/// Where the compass rose goes on the 128x64 screen, and how big it is.
+const ROSE_CENTER: Point = Point::new(96, 32);
+const ROSE_RADIUS: i32 = 28;
+
+/// The name of the direction closest to `degrees` clockwise from north.
+fn direction_name(degrees: u32) -> &'static str {
+    const NAMES: [&str; 8] = ["N", "NE", "E", "SE", "S", "SW", "W", "NW"];
+    NAMES[((degrees + 22) / 45 % 8) as usize]
+}
+
+/// Draw the heading and the temperature as text on the left, and a compass
+/// rose with a needle pointing north on the right.
+fn draw<D>(screen: &mut D, degrees: u32, celsius: f32) -> Result<(), D::Error>
+where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    let big = MonoTextStyle::new(&FONT_10X20, BinaryColor::On);
+    let small = MonoTextStyle::new(&FONT_6X10, BinaryColor::On);
+    let mut text: String<16> = String::new();
+
+    write!(text, "{}\u{b0}", degrees).unwrap();
+    Text::new(&text, Point::new(0, 20), big).draw(screen)?;
+    Text::new(direction_name(degrees), Point::new(0, 40), big).draw(screen)?;
+    text.clear();
+    write!(text, "{:.1}\u{b0}C", celsius).unwrap();
+    Text::new(&text, Point::new(0, 60), small).draw(screen)?;
+
+    let outline = PrimitiveStyle::with_stroke(BinaryColor::On, 1);
+    Circle::with_center(ROSE_CENTER, 2 * ROSE_RADIUS as u32 + 1)
+        .into_styled(outline)
+        .draw(screen)?;
+    // Heading clockwise from north means that north is that far counter
+    // clockwise from straight ahead, the top of the screen. The screen's y
+    // axis points down.
+    let north = -(degrees as f32) * PI / 180.;
+    let tip = ROSE_CENTER
+        + Point::new(
+            (libm::sinf(north) * (ROSE_RADIUS - 3) as f32) as i32,
+            -(libm::cosf(north) * (ROSE_RADIUS - 3) as f32) as i32,
+        );
+    Line::new(ROSE_CENTER, tip)
+        .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 2))
+        .draw(screen)?;
+    Ok(())
+}
+
+#[entry]
+fn main() -> ! {
+    init_log!();
+    let board = microbit::Board::take().unwrap();
+
+    // The v1 only has a single I2C bus, which connects the sensor and the
+    // edge connector alike, so the sensor and the screen share it
+    #[cfg(feature = "v1")]
+    let bus = RefCell::new(twi::Twi::new(
+        board.TWI0,
+        board.i2c.into(),
+        FREQUENCY_A::K100,
+    ));
+    #[cfg(feature = "v1")]
+    let sensor_i2c = RefCellDevice::new(&bus);
+
+    // The v2 has a bus of its own for the edge connector. `Board` only hands
+    // out `TWIM0`, which talks to the sensor, so nobody else can be using
+    // `TWIM1`.
+    #[cfg(feature = "v2")]
+    let sensor_i2c = twim::Twim::new(board.TWIM0, board.i2c_internal.into(), FREQUENCY_A::K100);
+    #[cfg(feature = "v2")]
+    #[allow(unsafe_code)]
+    let twim1 = unsafe { pac::Peripherals::steal() }.TWIM1;
+    #[cfg(feature = "v2")]
+    let bus = RefCell::new(twim::Twim::new(
+        twim1,
+        board.i2c_external.into(),
+        FREQUENCY_A::K100,
+    ));
+
+    // Anything else on the edge connector's bus, like the BME280 from the
+    // environment sensor chapter, can get a `RefCellDevice` of its own
+    let interface = I2CDisplayInterface::new(RefCellDevice::new(&bus));
+    let mut screen = Ssd1306::new(interface, DisplaySize128x64, DisplayRotation::Rotate0)
+        .into_buffered_graphics_mode();
+    screen.init().unwrap();
+
+    let mut timer = Timer::new(board.TIMER0);
+    let mut display = Display::new(board.display_pins);
+    let mut temp = Temp::new(board.TEMP);
+
+    let mut sensor = Lsm303agr::new_with_i2c(sensor_i2c);
+    sensor.init().unwrap();
+    sensor.set_mag_odr(MagOutputDataRate::Hz10).unwrap();
+    sensor.set_accel_odr(AccelOutputDataRate::Hz10).unwrap();
+    let mut sensor = sensor.into_mag_continuous().ok().unwrap();
+
+    // Hold button A while resetting to calibrate again. That happens on the
+    // LED matrix, like in the compass chapter.
+    let recalibrate = board.buttons.button_a.is_low().unwrap();
+    let mut settings = Settings::take().unwrap();
+    let calibration = saved_calibration(
+        &mut sensor,
+        &mut display,
+        &mut timer,
+        &mut settings,
+        recalibrate,
+    );
+
+    loop {
+        while !sensor.mag_status().unwrap().xyz_new_data {}
+        let mag = calibrated_measurement(sensor.mag_data().unwrap(), &calibration);
+        let accel = accel_to_cartesian(sensor.accel_data().unwrap());
+
+        // `theta` goes counter clockwise from the board's x axis to north. A
+        // compass heading goes clockwise from north to where the top edge of
+        // the board points, the y axis, a quarter turn on from x.
+        let theta = tilt_compensated_heading(accel, mag);
+        let degrees = ((theta.to_degrees() - 90.) as i32).rem_euclid(360) as u32;
+        // The TEMP peripheral counts in quarter degrees
+        let celsius = temp.measure().to_bits() as f32 / 4.;
+        println!("heading {}°, {:.2}°C", degrees, celsius);
+
+        screen.clear_buffer();
+        draw(&mut screen, degrees, celsius).unwrap();
+        screen.flush().unwrap();
+    }
+}
@@ -0,0 +1,133 @@
+#![deny(unsafe_code)]
+#![no_main]
+#![no_std]
+
+use core::fmt::Write;
+use cortex_m_rt::entry;
+use discovery_support::init_log;
+use panic_rtt_target as _;
+
+use discovery_support::buttons::{init_buttons, Button, ButtonEvent, ButtonEvents};
+use discovery_support::display::{display_leds, init_display};
+use discovery_support::scheduler::{Scheduler, Task};
+use discovery_support::scroll::{steps, SCROLL_STEP_MS};
+use discovery_support::thermometer::{Temperature, Thermometer, Unit};
+use heapless::String;
+use microbit::board::Board;
+
+#[cfg(feature = "v1")]
+use microbit::{
+    hal::uart::{self, Baudrate, Parity, Uart},
+    pac::UART0,
+};
+
+#[cfg(feature = "v2")]
+use microbit::{
+    hal::uarte::{self, Baudrate, Parity, Uarte},
+    pac::UARTE0,
+};
+
+#[cfg(feature = "v1")]
+type Serial = Uart<UART0>;
+#[cfg(feature = "v2")]
+type Serial = Uarte<UARTE0>;
+
+struct App {
+    thermometer: Thermometer,
+    serial: Serial,
+    buttons: ButtonEvents,
+    /// The last temperature measured.
+    latest: Option<Temperature>,
+    /// What's scrolling across the display, and how far along it is.
+    text: String<16>,
+    step: usize,
+}
+
+/// Measure, and send the temperature over the serial port.
+fn measure_task(app: &mut App, now_ms: u32) {
+    let temperature = app.thermometer.measure();
+    write!(app.serial, "{:>8} ms: {}\r\n", now_ms, temperature).unwrap();
+    app.latest = Some(temperature);
+}
+
+/// Scroll the text along by one step. Once it's all gone past, start over
+/// with the latest temperature.
+fn scroll_task(app: &mut App, _now_ms: u32) {
+    let leds = steps(&app.text).nth(app.step);
+    match leds {
+        Some(leds) => {
+            display_leds(leds);
+            app.step += 1;
+        }
+        None => {
+            app.text.clear();
+            if let Some(temperature) = app.latest {
+                write!(app.text, "{:#}", temperature).unwrap();
+            }
+            app.step = 0;
+        }
+    }
+}
+
+/// Button A switches between Celsius and Fahrenheit.
+fn button_task(app: &mut App, _now_ms: u32) {
+    while let Some(event) = app.buttons.recv() {
+        if event == (Button::A, ButtonEvent::Click) {
+            let unit = match app.thermometer.unit() {
+                Unit::Celsius => Unit::Fahrenheit,
+                Unit::Fahrenheit => Unit::Celsius,
+            };
+            app.thermometer.set_unit(unit);
+        }
+    }
+}
+
+const TASKS: [Task<App>; 3] = [
+    Task {
+        period_ms: 1_000,
+        run: measure_task,
+    },
+    Task {
+        period_ms: SCROLL_STEP_MS,
+        run: scroll_task,
+    },
+    Task {
+        period_ms: 20,
+        run: button_task,
+    },
+];
+
+#[entry]
+fn main() -> ! {
+    init_log!();
+    let mut board = Board::take().unwrap();
+
+    #[cfg(feature = "v1")]
+    let serial = uart::Uart::new(
+        board.UART0,
+        board.uart.into(),
+        Parity::EXCLUDED,
+        Baudrate::BAUD115200,
+    );
+
+    #[cfg(feature = "v2")]
+    let serial = uarte::Uarte::new(
+        board.UARTE0,
+        board.uart.into(),
+        Parity::EXCLUDED,
+        Baudrate::BAUD115200,
+    );
+
+    init_display(board.TIMER1, board.display_pins);
+    let buttons = init_buttons(board.GPIOTE, board.TIMER2, board.buttons);
+
+    let mut app = App {
+        thermometer: Thermometer::new(board.TEMP, Unit::Celsius),
+        serial,
+        buttons,
+        latest: None,
+        text: String::new(),
+        step: 0,
+    };
+    Scheduler::new(board.TIMER0, &TASKS, &mut board.SCB).run(&mut app)
+}
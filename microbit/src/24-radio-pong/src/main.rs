@@ -0,0 +1,156 @@
+#![deny(unsafe_code)]
+#![no_main]
+#![no_std]
+
+use cortex_m_rt::entry;
+use discovery_support::{init_log, println};
+use panic_rtt_target as _;
+
+use discovery_logic::pong::{Message, Pong};
+use discovery_support::display::{display_image, display_leds, init_display};
+use discovery_support::esb::{Addresses, Esb};
+use discovery_support::image::Image5x5;
+use discovery_support::random::Random;
+use microbit::hal::{clocks::Clocks, prelude::*, Timer};
+
+/// Both boards send to and listen on the same pipe. Not pipe 0, so that the
+/// `esb-remote` example of the radio chapter doesn't get in the way.
+const PIPE: u8 = 1;
+/// How long the ball takes from one LED to the next.
+const STEP_MS: u32 = 200;
+/// How many steps the score stays on the display after a point.
+const SCORE_STEPS: u32 = 10;
+
+const CROSS: Image5x5 = Image5x5::from_rows([0b10001, 0b01010, 0b00100, 0b01010, 0b10001]);
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// No game going on. Press a button to start one and serve. `alone` once
+    /// nobody answered that.
+    Idle {
+        alone: bool,
+    },
+    Playing,
+    /// The score is on the display for `left` more steps.
+    Scoring {
+        left: u32,
+    },
+}
+
+#[entry]
+fn main() -> ! {
+    init_log!();
+    let board = microbit::Board::take().unwrap();
+
+    let clocks = Clocks::new(board.CLOCK).enable_ext_hfosc();
+    let mut esb = Esb::new(board.RADIO, board.TIMER0, &clocks, &Addresses::default());
+    esb.listen(1 << PIPE);
+
+    // The blocking display would keep us from acknowledging in time
+    init_display(board.TIMER1, board.display_pins);
+    let mut timer = Timer::periodic(board.TIMER2);
+    timer.start(STEP_MS * 1_000);
+    let mut random = Random::new(board.RNG);
+    let buttons = board.buttons;
+    let mut pressed = (false, false);
+
+    let mut pong = Pong::new();
+    let mut state = State::Idle { alone: false };
+    // The ticket of the game we started, until the ball has left our half
+    let mut our_serve: Option<u32> = None;
+
+    loop {
+        let now = (
+            buttons.button_a.is_low().unwrap(),
+            buttons.button_b.is_low().unwrap(),
+        );
+        let (left, right) = (now.0 && !pressed.0, now.1 && !pressed.1);
+        pressed = now;
+
+        match state {
+            State::Idle { .. } if left || right => {
+                let ticket = random.next_u32();
+                match esb.send(PIPE, &Message::Serve { ticket }.encode()) {
+                    Ok(()) => {
+                        pong.reset();
+                        pong.serve(random.below(3) as i8 - 1);
+                        our_serve = Some(ticket);
+                        state = State::Playing;
+                    }
+                    Err(error) => {
+                        println!("Nobody to play with: {:?}", error);
+                        state = State::Idle { alone: true };
+                    }
+                }
+            }
+            State::Playing => {
+                if left {
+                    pong.move_paddle_left();
+                }
+                if right {
+                    pong.move_paddle_right();
+                }
+            }
+            _ => {}
+        }
+
+        if timer.wait().is_ok() {
+            match state {
+                State::Playing => {
+                    if let Some(message) = pong.step() {
+                        our_serve = None;
+                        let sent = esb.send(PIPE, &message.encode());
+                        match message {
+                            Message::Point => state = State::Scoring { left: SCORE_STEPS },
+                            // Nobody caught it on the other side, so the top
+                            // edge is a wall for now
+                            Message::Ball { .. } if sent.is_err() => pong.bounce_back(message),
+                            _ => {}
+                        }
+                    }
+                }
+                State::Scoring { left: 0 } if pong.is_over() => {
+                    println!("Game over, {} to {}", pong.score, pong.their_score);
+                    state = State::Idle { alone: false };
+                }
+                State::Scoring { left: 0 } => {
+                    if pong.serves_next() {
+                        pong.serve(random.below(3) as i8 - 1);
+                    }
+                    state = State::Playing;
+                }
+                State::Scoring { left } => state = State::Scoring { left: left - 1 },
+                State::Idle { .. } => {}
+            }
+        }
+
+        if let Ok((_, payload)) = esb.receive() {
+            match Message::decode(payload) {
+                Some(Message::Serve { ticket }) => {
+                    match our_serve {
+                        // We both started a game at once, and ours wins
+                        Some(ours) if ours > ticket => {}
+                        Some(_) => {
+                            pong.drop_ball();
+                            our_serve = None;
+                        }
+                        None => pong.reset(),
+                    }
+                    state = State::Playing;
+                }
+                Some(Message::Point) => {
+                    pong.receive(Message::Point);
+                    state = State::Scoring { left: SCORE_STEPS };
+                }
+                Some(message) => pong.receive(message),
+                None => println!("Unknown message {:?}", payload),
+            }
+        }
+
+        match state {
+            State::Idle { alone: true } => display_image(&CROSS),
+            State::Scoring { .. } => display_leds(pong.score_leds()),
+            _ => display_leds(pong.leds()),
+        }
+    }
+}
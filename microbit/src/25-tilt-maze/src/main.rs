@@ -0,0 +1,140 @@
+#![deny(unsafe_code)]
+#![no_main]
+#![no_std]
+
+use cortex_m_rt::entry;
+use discovery_support::{init_log, println};
+use panic_rtt_target as _;
+
+#[cfg(feature = "v1")]
+use microbit::{hal::twi, pac::twi0::frequency::FREQUENCY_A};
+
+#[cfg(feature = "v2")]
+use microbit::{hal::twim, pac::twim0::frequency::FREQUENCY_A};
+
+use discovery_logic::maze::{Maze, Roll, Tile, LEVELS, SCREEN};
+use discovery_support::display::{display_greyscale, display_image, init_display, MAX_BRIGHTNESS};
+use discovery_support::image::Image5x5;
+#[cfg(feature = "v2")]
+use discovery_support::power::enter_motion_sleep;
+#[cfg(feature = "v2")]
+use lsm303agr::AccelScale;
+use lsm303agr::{AccelOutputDataRate, Lsm303agr};
+use microbit::hal::{prelude::*, Timer};
+
+/// The ball rolls on by one tile every this many measurements, 160 ms at
+/// 50 Hz.
+const STEP_SAMPLES: u32 = 8;
+/// The goal blinks on and off every this many measurements.
+const BLINK_SAMPLES: u32 = 12;
+/// Tilting the board by less than this, in mg, doesn't get the ball rolling.
+/// That's about 12°.
+const TILT_MG: i32 = 200;
+/// Lying flat for this many measurements, 30 s at 50 Hz, sends the micro:bit
+/// v2 to sleep until it's picked up again.
+#[cfg(feature = "v2")]
+const IDLE_SAMPLES: u32 = 1_500;
+/// How bright the walls are. Dimmer than the ball, so it stands out.
+const WALL_BRIGHTNESS: u8 = 2;
+
+const TICK: Image5x5 = Image5x5::from_rows([0b00000, 0b00001, 0b00010, 0b10100, 0b01000]);
+
+/// Which way the ball rolls along an axis with `mg` of gravity on it. The
+/// accelerometer measures gravity pointing up, towards the edge that's
+/// raised, and the ball rolls the other way, downhill.
+fn downhill(mg: i32) -> i8 {
+    if mg > TILT_MG {
+        -1
+    } else if mg < -TILT_MG {
+        1
+    } else {
+        0
+    }
+}
+
+/// The screen the ball is on, with the goal lit if `blink` says so.
+fn draw(maze: &Maze, blink: bool) {
+    let mut leds = [[0; SCREEN]; SCREEN];
+    for (leds, tiles) in leds.iter_mut().zip(maze.view().iter()) {
+        for (led, tile) in leds.iter_mut().zip(tiles.iter()) {
+            *led = match tile {
+                Tile::Wall => WALL_BRIGHTNESS,
+                Tile::Goal if blink => MAX_BRIGHTNESS,
+                _ => 0,
+            };
+        }
+    }
+    let (row, col) = maze.ball_in_view();
+    leds[row][col] = MAX_BRIGHTNESS;
+    display_greyscale(leds);
+}
+
+#[entry]
+fn main() -> ! {
+    init_log!();
+    let board = microbit::Board::take().unwrap();
+
+    #[cfg(feature = "v1")]
+    let i2c = { twi::Twi::new(board.TWI0, board.i2c.into(), FREQUENCY_A::K100) };
+
+    #[cfg(feature = "v2")]
+    let i2c = { twim::Twim::new(board.TWIM0, board.i2c_internal.into(), FREQUENCY_A::K100) };
+
+    let mut sensor = Lsm303agr::new_with_i2c(i2c);
+    sensor.init().unwrap();
+    sensor.set_accel_odr(AccelOutputDataRate::Hz50).unwrap();
+
+    // The accelerometer's INT1 pin
+    #[cfg(feature = "v2")]
+    let int_pin = board.pins.p0_25.into_pullup_input().degrade();
+    #[cfg(feature = "v2")]
+    let mut idle: u32 = 0;
+
+    let mut timer = Timer::new(board.TIMER0);
+    init_display(board.TIMER1, board.display_pins);
+
+    let mut level = 0;
+    let mut maze = Maze::new(&LEVELS[level]);
+    let mut samples: u32 = 0;
+    println!("Level {}", level + 1);
+
+    loop {
+        while !sensor.accel_status().unwrap().xyz_new_data {}
+        let data = sensor.accel_data().unwrap();
+        samples = samples.wrapping_add(1);
+
+        #[cfg(feature = "v2")]
+        {
+            let flat = downhill(data.x) == 0 && downhill(data.y) == 0;
+            idle = if flat { idle + 1 } else { 0 };
+            if idle == IDLE_SAMPLES {
+                println!("Sleeping until the board is picked up");
+                // The driver gets the bus back afterwards, and sets the
+                // accelerometer up from scratch
+                let mut i2c = sensor.destroy();
+                enter_motion_sleep(&mut i2c, &int_pin, AccelScale::G2).unwrap();
+                sensor = Lsm303agr::new_with_i2c(i2c);
+                sensor.init().unwrap();
+                sensor.set_accel_odr(AccelOutputDataRate::Hz50).unwrap();
+                idle = 0;
+                continue;
+            }
+        }
+
+        // X points to the right, and Y towards the bottom edge, the same way
+        // the rows go
+        if samples.is_multiple_of(STEP_SAMPLES)
+            && maze.roll(downhill(data.y), downhill(data.x)) == Roll::Goal
+        {
+            display_image(&TICK);
+            timer.delay_ms(1_000u32);
+
+            // After the last level, it's back to the first one
+            level = (level + 1) % LEVELS.len();
+            maze = Maze::new(&LEVELS[level]);
+            println!("Level {}", level + 1);
+        }
+
+        draw(&maze, (samples / BLINK_SAMPLES).is_multiple_of(2));
+    }
+}
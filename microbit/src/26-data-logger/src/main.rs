@@ -0,0 +1,283 @@
+#![no_main]
+#![no_std]
+
+use core::fmt::Write;
+use cortex_m_rt::entry;
+use embedded_hal::digital::v2::InputPin;
+use panic_rtt_target as _;
+use rtt_target::rtt_init_print;
+
+use discovery_logic::data_log::{rows, Sample, CSV_HEADER, RATES_HZ};
+use discovery_support::display::{display_leds, init_display};
+use discovery_support::flash_log::{FlashLog, CAPACITY};
+use discovery_support::monotonic::{init_monotonic, Instant};
+use discovery_support::serial::{FlowControl, UartePort};
+use discovery_support::settings::{keys, Settings};
+use lsm303agr::{AccelOutputDataRate, Lsm303agr};
+use microbit::{
+    hal::clocks::Clocks,
+    hal::twim,
+    hal::uarte::{self, Baudrate, Parity},
+    pac::twim0::frequency::FREQUENCY_A,
+};
+
+#[path = "../../07-uart/src/line_reader.rs"]
+mod line_reader;
+use line_reader::LineReader;
+
+#[path = "../../07-uart/src/shell.rs"]
+#[allow(dead_code)]
+mod shell;
+use shell::{Args, Command, Error, Shell};
+
+/// The rate before anybody chose one.
+const DEFAULT_RATE_HZ: u32 = 10;
+/// How often button A is looked at, slow enough that it's done bouncing.
+const BUTTON_POLL_MS: u64 = 10;
+
+fn now_ms() -> u64 {
+    Instant::now().as_micros() / 1_000
+}
+
+fn odr(rate_hz: u32) -> AccelOutputDataRate {
+    match rate_hz {
+        1 => AccelOutputDataRate::Hz1,
+        10 => AccelOutputDataRate::Hz10,
+        25 => AccelOutputDataRate::Hz25,
+        _ => AccelOutputDataRate::Hz50,
+    }
+}
+
+struct Logger {
+    flash_log: FlashLog,
+    settings: Settings,
+    rate_hz: u32,
+    /// When the recording started, if it's recording.
+    started_ms: Option<u64>,
+}
+
+impl Logger {
+    /// Pick up the rate, and the recording if there was one going on when
+    /// the power went.
+    fn new(flash_log: FlashLog, settings: Settings) -> Logger {
+        let saved = settings.get(keys::DATA_LOGGER);
+        let (rate_hz, recording) = match saved.as_deref() {
+            Some(&[rate_hz, recording]) if RATES_HZ.contains(&u32::from(rate_hz)) => {
+                (u32::from(rate_hz), recording != 0)
+            }
+            _ => (DEFAULT_RATE_HZ, false),
+        };
+        Logger {
+            flash_log,
+            settings,
+            rate_hz,
+            started_ms: if recording { Some(now_ms()) } else { None },
+        }
+    }
+
+    fn start(&mut self) {
+        self.started_ms = Some(now_ms());
+        self.save();
+    }
+
+    fn stop(&mut self) {
+        self.started_ms = None;
+        self.save();
+    }
+
+    fn save(&mut self) {
+        let value = [self.rate_hz as u8, self.started_ms.is_some() as u8];
+        self.settings.set(keys::DATA_LOGGER, &value).unwrap();
+    }
+
+    fn status(&self, out: &mut dyn Write) -> Result<(), Error> {
+        let state = if self.started_ms.is_some() {
+            "recording"
+        } else {
+            "stopped"
+        };
+        write!(
+            out,
+            "{} at {} Hz, {} samples logged, room for {} s\r\n",
+            state,
+            self.rate_hz,
+            self.flash_log.records().count(),
+            CAPACITY as u32 / self.rate_hz
+        )?;
+        Ok(())
+    }
+}
+
+fn rate(logger: &mut Logger, mut args: Args, out: &mut dyn Write) -> Result<(), Error> {
+    let rate_hz = args.parse()?;
+    args.end()?;
+    if !RATES_HZ.contains(&rate_hz) {
+        return Err(Error::InvalidArgument);
+    }
+    logger.rate_hz = rate_hz;
+    logger.save();
+    logger.status(out)
+}
+
+fn start(logger: &mut Logger, args: Args, out: &mut dyn Write) -> Result<(), Error> {
+    args.end()?;
+    logger.start();
+    logger.status(out)
+}
+
+fn stop(logger: &mut Logger, args: Args, out: &mut dyn Write) -> Result<(), Error> {
+    args.end()?;
+    logger.stop();
+    logger.status(out)
+}
+
+fn status(logger: &mut Logger, args: Args, out: &mut dyn Write) -> Result<(), Error> {
+    args.end()?;
+    logger.status(out)
+}
+
+fn dump(logger: &mut Logger, args: Args, out: &mut dyn Write) -> Result<(), Error> {
+    args.end()?;
+    write!(out, "{}\r\n", CSV_HEADER)?;
+    for row in rows(logger.flash_log.records()) {
+        write!(out, "{}\r\n", row)?;
+    }
+    Ok(())
+}
+
+fn erase(logger: &mut Logger, args: Args, out: &mut dyn Write) -> Result<(), Error> {
+    args.end()?;
+    logger.flash_log.erase();
+    logger.status(out)
+}
+
+const COMMANDS: &[Command<Logger>] = &[
+    Command {
+        name: "rate",
+        usage: "rate 1|10|25|50",
+        run: rate,
+    },
+    Command {
+        name: "start",
+        usage: "start",
+        run: start,
+    },
+    Command {
+        name: "stop",
+        usage: "stop",
+        run: stop,
+    },
+    Command {
+        name: "status",
+        usage: "status",
+        run: status,
+    },
+    Command {
+        name: "dump",
+        usage: "dump",
+        run: dump,
+    },
+    Command {
+        name: "erase",
+        usage: "erase",
+        run: erase,
+    },
+];
+
+/// While recording, the top left LED blinks once a second.
+fn blink(logger: &Logger, now: u64) -> bool {
+    logger
+        .started_ms
+        .is_some_and(|started_ms| (now - started_ms) % 1_000 < 100)
+}
+
+#[entry]
+fn main() -> ! {
+    rtt_init_print!();
+    let board = microbit::Board::take().unwrap();
+
+    let clocks = Clocks::new(board.CLOCK).start_lfclk();
+    init_monotonic(&clocks);
+    init_display(board.TIMER1, board.display_pins);
+
+    let serial = uarte::Uarte::new(
+        board.UARTE0,
+        board.uart.into(),
+        Parity::EXCLUDED,
+        Baudrate::BAUD115200,
+    );
+    let mut serial = UartePort::new(serial, FlowControl::Disabled);
+    // Samples keep coming in while we wait for a command
+    serial.enable_rx_interrupt();
+
+    let i2c = twim::Twim::new(board.TWIM0, board.i2c_internal.into(), FREQUENCY_A::K100);
+    let mut sensor = Lsm303agr::new_with_i2c(i2c);
+    sensor.init().unwrap();
+
+    let mut logger = Logger::new(FlashLog::take().unwrap(), Settings::take().unwrap());
+    let mut sensor_rate_hz = 0;
+    let button_a = board.buttons.button_a;
+
+    let shell = Shell::new(COMMANDS);
+    let mut reader: LineReader<32> = LineReader::new();
+    write!(serial, "Press A to start or stop recording\r\n").unwrap();
+    logger.status(&mut serial).unwrap();
+    write!(serial, "> ").unwrap();
+
+    let mut pressed = false;
+    let mut lit = false;
+    let mut next_poll_ms = 0;
+    loop {
+        if sensor_rate_hz != logger.rate_hz {
+            sensor.set_accel_odr(odr(logger.rate_hz)).unwrap();
+            sensor_rate_hz = logger.rate_hz;
+        }
+
+        let now = now_ms();
+        if sensor.accel_status().unwrap().xyz_new_data {
+            // Read it either way, or the status keeps saying there's new data
+            let data = sensor.accel_data().unwrap();
+            if let Some(started_ms) = logger.started_ms {
+                let sample = Sample {
+                    ms: (now - started_ms) as u32,
+                    x: data.x as i16,
+                    y: data.y as i16,
+                    z: data.z as i16,
+                };
+                logger.flash_log.push(&sample.to_record());
+            }
+        }
+
+        if blink(&logger, now) != lit {
+            lit = !lit;
+            let mut leds = [[0; 5]; 5];
+            leds[0][0] = lit as u8;
+            display_leds(leds);
+        }
+
+        if now >= next_poll_ms {
+            next_poll_ms = now + BUTTON_POLL_MS;
+            let now_pressed = button_a.is_low().unwrap();
+            if now_pressed && !pressed {
+                if logger.started_ms.is_some() {
+                    logger.stop();
+                } else {
+                    logger.start();
+                }
+                write!(serial, "\r\n").unwrap();
+                logger.status(&mut serial).unwrap();
+                write!(serial, "> ").unwrap();
+            }
+            pressed = now_pressed;
+        }
+
+        match reader.poll_line(&mut serial) {
+            Ok(Some(line)) => {
+                shell.run(&mut logger, line, &mut serial).unwrap();
+                write!(serial, "> ").unwrap();
+            }
+            Ok(None) => {}
+            Err(error) => write!(serial, "\r\nserial error: {:?}\r\n> ", error).unwrap(),
+        }
+    }
+}
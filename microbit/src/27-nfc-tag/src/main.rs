@@ -0,0 +1,66 @@
+#![no_main]
+#![no_std]
+
+use cortex_m_rt::entry;
+use panic_rtt_target as _;
+use rtt_target::{rprintln, rtt_init_print};
+
+use discovery_logic::ndef::uri_message;
+use discovery_logic::type2_tag::{Response, Type2Tag};
+use discovery_support::display::{display_leds, init_display};
+use discovery_support::nfc::{enable_nfc_pins, uid, Event, NfcTag};
+use microbit::{hal::clocks::Clocks, pac};
+
+/// Where a phone goes when it's tapped on the antenna.
+const URL: &str = "https://docs.rust-embedded.org/discovery/microbit/";
+
+/// Shown while a reader is close.
+const WAVES: [[u8; 5]; 5] = [
+    [0, 0, 1, 0, 0],
+    [0, 1, 0, 1, 0],
+    [1, 0, 1, 0, 1],
+    [0, 1, 0, 1, 0],
+    [0, 0, 1, 0, 0],
+];
+
+#[entry]
+fn main() -> ! {
+    rtt_init_print!();
+    // Before anything else, as it may have to reset the micro:bit
+    enable_nfc_pins();
+
+    let board = microbit::Board::take().unwrap();
+    // The NFCT needs the crystal oscillator
+    let _clocks = Clocks::new(board.CLOCK).enable_ext_hfosc();
+    init_display(board.TIMER1, board.display_pins);
+
+    let uid = uid(&board.FICR);
+    let message = uri_message(URL).unwrap();
+    let memory = Type2Tag::new(uid, &message).unwrap();
+    // `Board::take` doesn't hand out the NFCT, so nothing else is using it
+    let nfct = unsafe { pac::Peripherals::steal() }.NFCT;
+    let mut tag = NfcTag::new(nfct, uid);
+
+    rprintln!("Tap a phone on the antenna to open {}", URL);
+    loop {
+        // Nothing in here can take long, the reader wants its answer within
+        // about 300 µs
+        let command = match tag.poll() {
+            Some(Event::Command(command)) => command,
+            Some(Event::FieldDetected) => {
+                display_leds(WAVES);
+                continue;
+            }
+            Some(Event::FieldLost) => {
+                display_leds([[0; 5]; 5]);
+                continue;
+            }
+            Some(Event::Selected) | None => continue,
+        };
+        match memory.respond(command) {
+            Response::Data(data) => tag.respond(&data),
+            Response::Nak => tag.nak(),
+            Response::Sleep => tag.sleep(),
+        }
+    }
+}
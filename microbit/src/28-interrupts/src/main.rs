@@ -0,0 +1,84 @@
+#![no_main]
+#![no_std]
+
+use cortex_m::peripheral::NVIC;
+use cortex_m_rt::entry;
+use discovery_support::shared::Shared;
+use embedded_hal::digital::v2::{OutputPin, StatefulOutputPin};
+use panic_rtt_target as _;
+use rtt_target::{rprintln, rtt_init_print};
+
+use microbit::{
+    hal::gpio::{Output, Pin, PushPull},
+    hal::gpiote::Gpiote,
+    pac::{self, interrupt},
+};
+
+/// What the interrupt handler works with.
+struct Context {
+    gpiote: Gpiote,
+    /// The row of the top left LED. Its column stays low, so the LED is lit
+    /// whenever this is high.
+    led: Pin<Output<PushPull>>,
+    presses: u32,
+}
+
+/// `main` sets it up, and the interrupt handler takes it from there.
+static CONTEXT: Shared<Context> = Shared::new();
+
+#[entry]
+fn main() -> ! {
+    rtt_init_print!();
+    let board = microbit::Board::take().unwrap();
+
+    let mut col1 = board.display_pins.col1;
+    col1.set_low().unwrap();
+    let led = board.display_pins.row1.degrade();
+
+    // Channel 0 watches button A, and signals an event when the pin goes from
+    // high to low, which is what pressing the button does. The event makes
+    // the GPIOTE ask for its interrupt.
+    let gpiote = Gpiote::new(board.GPIOTE);
+    gpiote
+        .channel0()
+        .input_pin(&board.buttons.button_a.degrade())
+        .hi_to_lo()
+        .enable_interrupt();
+
+    CONTEXT.lend(Context {
+        gpiote,
+        led,
+        presses: 0,
+    });
+    // Only now that the handler finds what it needs can the NVIC let the
+    // interrupt through
+    unsafe { NVIC::unmask(pac::Interrupt::GPIOTE) };
+
+    rprintln!("Press button A");
+    let mut reported = 0;
+    loop {
+        // Sleep until an interrupt has run
+        cortex_m::asm::wfi();
+        let presses = CONTEXT.with(|context| context.presses).unwrap();
+        if presses != reported {
+            rprintln!("{} presses", presses);
+            reported = presses;
+        }
+    }
+}
+
+#[interrupt]
+fn GPIOTE() {
+    CONTEXT.with(|context| {
+        // Otherwise the event is still there when the handler returns, and
+        // the interrupt runs again, and again
+        context.gpiote.channel0().reset_events();
+
+        if context.led.is_set_high().unwrap() {
+            context.led.set_low().unwrap();
+        } else {
+            context.led.set_high().unwrap();
+        }
+        context.presses += 1;
+    });
+}
@@ -0,0 +1,12 @@
+//! This build script writes the `memory.x` for the bootloader into a
+//! directory where the linker can always find it. It's a layout of its own,
+//! at the start of the flash, see `memory_x.rs`.
+
+#[path = "../../support/build/memory_x.rs"]
+// Only the bootloader's layout, not the one from the features
+#[allow(dead_code)]
+mod memory_x;
+
+fn main() {
+    memory_x::write_bootloader();
+}
@@ -0,0 +1,160 @@
+#![no_main]
+#![no_std]
+
+use cortex_m::peripheral::SCB;
+use cortex_m_rt::entry;
+use embedded_hal::digital::v2::{InputPin, OutputPin};
+use embedded_hal::serial::Read;
+use panic_rtt_target as _;
+use rtt_target::{rprintln, rtt_init_print};
+
+use microbit::{
+    hal::uarte::{self, Baudrate, Parity},
+    pac::{self, NVMC},
+};
+
+use discovery_logic::dfu::{
+    ImageFlash, Request, Response, Updater, MAX_REQUEST_LEN, MAX_RESPONSE_LEN,
+};
+use discovery_logic::packet::{self, types, Packet, CRC_LEN, HEADER_LEN};
+use discovery_support::serial::{FlowControl, UartePort};
+use discovery_support::{flash, flash_layout};
+
+/// Where the program goes, right after the bootloader. `memory_x.rs` links
+/// the programs built with the `dfu` feature to start there.
+const PROGRAM_START: usize = 32 * 1024;
+/// How much room it has, up to the pages `discovery-support` keeps at the
+/// end of the flash, the same room `memory_x.rs` gives it.
+const PROGRAM_LEN: usize = flash_layout::v2::PROGRAM_END - PROGRAM_START;
+
+/// The packet the longest request comes in.
+const PACKET_LEN: usize = HEADER_LEN + MAX_REQUEST_LEN + CRC_LEN;
+/// COBS adds a byte to anything up to 254 bytes long.
+const FRAME_LEN: usize = PACKET_LEN + 1;
+/// The packet the longest response goes out in.
+const RESPONSE_PACKET_LEN: usize = HEADER_LEN + MAX_RESPONSE_LEN + CRC_LEN;
+
+/// The program's part of the flash.
+struct ProgramFlash {
+    nvmc: NVMC,
+}
+
+impl ImageFlash for ProgramFlash {
+    fn erase_page(&mut self, offset: usize) {
+        flash::erase_page(&self.nvmc, PROGRAM_START + offset);
+    }
+
+    fn write(&mut self, offset: usize, words: &[u32]) {
+        flash::write_words(&self.nvmc, PROGRAM_START + offset, words);
+    }
+
+    fn read(&self, offset: usize) -> u32 {
+        flash::read_word(PROGRAM_START + offset)
+    }
+}
+
+#[entry]
+fn main() -> ! {
+    rtt_init_print!();
+    let board = microbit::Board::take().unwrap();
+
+    // Holding A while the micro:bit starts asks for an update, even if there
+    // is a program. Without one, the first word of its part of the flash is
+    // still erased, see `dfu`.
+    let update = board.buttons.button_a.is_low().unwrap();
+    if !update && flash::read_word(PROGRAM_START) != flash::ERASED {
+        start_program();
+    }
+
+    // The LED in the middle says we're waiting
+    let mut display = board.display_pins;
+    display.row3.set_high().unwrap();
+    display.col3.set_low().unwrap();
+
+    let serial = uarte::Uarte::new(
+        board.UARTE0,
+        board.uart.into(),
+        Parity::EXCLUDED,
+        Baudrate::BAUD115200,
+    );
+    let mut serial = UartePort::new(serial, FlowControl::Disabled);
+    serial.enable_rx_interrupt();
+
+    // `Board::take` doesn't hand out the NVMC, so nothing else is using it
+    let nvmc = unsafe { pac::Peripherals::steal() }.NVMC;
+    let mut program = ProgramFlash { nvmc };
+    let mut updater = Updater::new(PROGRAM_LEN as u32, flash::PAGE_SIZE as u32);
+
+    rprintln!("Waiting for a program, run `dfu-sender` on the computer");
+    let mut frame = [0; FRAME_LEN];
+    let mut len = 0;
+    loop {
+        // A byte that got garbled ends the frame, which then won't decode
+        let byte = nb::block!(serial.read()).unwrap_or(0);
+        if byte != 0 {
+            // A frame that's too long is no request, but the zero at its end
+            // still is the end of it
+            if len < frame.len() {
+                frame[len] = byte;
+            }
+            len += 1;
+            continue;
+        }
+
+        // Anything garbled is left out, the computer asks again when there's
+        // no answer
+        let received = &mut frame[..len.min(FRAME_LEN)];
+        len = 0;
+        let packet = match cobs::decode_in_place(received) {
+            Ok(decoded) => packet::decode(&received[..decoded]),
+            Err(_) => continue,
+        };
+        let (sequence, request) = match packet {
+            Ok(Packet {
+                packet_type: types::DFU,
+                sequence,
+                payload,
+            }) => match Request::decode(payload) {
+                Some(request) => (sequence, request),
+                None => continue,
+            },
+            _ => continue,
+        };
+
+        let response = updater.handle(&request, &mut program);
+        if let Response::Failed(failure) = response {
+            rprintln!("Update failed: {:?}", failure);
+        }
+        let payload = response.encode();
+        let packet = Packet {
+            packet_type: types::DFU,
+            sequence,
+            payload: &payload,
+        };
+        let mut buf = [0; RESPONSE_PACKET_LEN];
+        let packet = packet::encode(&packet, &mut buf).unwrap();
+        let mut out = [0; RESPONSE_PACKET_LEN + 2];
+        let encoded = cobs::encode(packet, &mut out);
+        // `out` is one longer than the encoding, its last byte stays zero
+        serial.write_all(&out[..=encoded]).unwrap();
+
+        if response == Response::Done {
+            rprintln!("Done, starting the new program");
+            // The program should find the chip the way it is after a reset,
+            // not with our UARTE running and its interrupt unmasked, so it's
+            // started by the check at the top after one
+            SCB::sys_reset();
+        }
+    }
+}
+
+/// Start the program as if the chip had started there: with the stack
+/// pointer and the reset vector from the start of its vector table.
+fn start_program() -> ! {
+    let vector_table = PROGRAM_START as *const u32;
+    unsafe {
+        // The interrupts go to the program's handlers from now on
+        (*SCB::PTR).vtor.write(vector_table as u32);
+        cortex_m::asm::bootload(vector_table)
+    }
+}
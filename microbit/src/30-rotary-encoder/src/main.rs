@@ -0,0 +1,128 @@
+#![no_main]
+#![no_std]
+
+use cortex_m_rt::entry;
+use discovery_logic::knob::Knob;
+use discovery_support::delay::LowPowerDelay;
+use discovery_support::display::{display_greyscale, init_display, MAX_BRIGHTNESS};
+use discovery_support::sound::{Note, Speaker, MAX_VOLUME};
+use discovery_support::{init_log, println};
+use embedded_hal::digital::v2::InputPin;
+use microbit::{
+    board::Board,
+    hal::{
+        clocks::Clocks,
+        prelude::*,
+        qdec::{Qdec, SamplePeriod},
+    },
+    pac,
+};
+use panic_rtt_target as _;
+
+/// How many counts the encoder makes from one click to the next. Most have
+/// a click for every full cycle of A and B, which is four counts, but some
+/// click twice or four times as often.
+const COUNTS_PER_DETENT: i32 = 4;
+/// How often the counts are read.
+const POLL_MS: u32 = 10;
+/// How long the beep at every new volume is.
+const BEEP_MS: u32 = 40;
+
+/// What turning the knob changes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Mode {
+    Brightness,
+    Volume,
+}
+
+/// In brightness mode all the LEDs light up as bright as they're set to, in
+/// volume mode as many rows as it takes to show the volume, from the bottom,
+/// with the one at the top of the bar half as bright if it's an odd volume.
+fn draw(mode: Mode, brightness: u8, volume: u8) {
+    let mut leds = [[brightness; 5]; 5];
+    if mode == Mode::Volume {
+        for (row, leds) in leds.iter_mut().rev().enumerate() {
+            let lit = volume.saturating_sub(row as u8 * 2).min(2);
+            *leds = [brightness * lit / 2; 5];
+        }
+    }
+    display_greyscale(leds);
+}
+
+#[entry]
+fn main() -> ! {
+    init_log!();
+
+    let mut board = Board::take().unwrap();
+    let clocks = Clocks::new(board.CLOCK).start_lfclk();
+    let mut delay = LowPowerDelay::new(board.RTC0, &clocks, &mut board.SCB);
+    init_display(board.TIMER1, board.display_pins);
+    let mut speaker = Speaker::new(board.PWM0, board.speaker_pin, board.TIMER0);
+
+    // A goes on ring P0 and B on ring P1, the common pin on GND. The pull-ups
+    // keep A and B high while their switches are open.
+    // `Board::take` doesn't hand out the QDEC, so nothing else is using it
+    let qdec = unsafe { pac::Peripherals::steal() }.QDEC;
+    let encoder = Qdec::new(
+        qdec,
+        board.pins.p0_02.into_pullup_input().degrade(),
+        board.pins.p0_03.into_pullup_input().degrade(),
+        None,
+        SamplePeriod::_1024us,
+    );
+    // The switches bounce when they open or close, which would count as the
+    // knob going back and forth. The filter only takes a level that stays
+    // the same for a few samples in a row.
+    encoder.debounce(true);
+    encoder.enable();
+
+    let mut brightness = Knob::new(1, i32::from(MAX_BRIGHTNESS), COUNTS_PER_DETENT);
+    brightness.set_value(i32::from(MAX_BRIGHTNESS));
+    let mut volume = Knob::new(0, i32::from(MAX_VOLUME), COUNTS_PER_DETENT);
+    volume.set_value(i32::from(MAX_VOLUME / 2));
+    speaker.set_volume(volume.value() as u8);
+
+    let button_a = board.buttons.button_a;
+    let mut was_pressed = false;
+    let mut mode = Mode::Brightness;
+    let mut position: i32 = 0;
+    draw(mode, brightness.value() as u8, volume.value() as u8);
+    loop {
+        let pressed = button_a.is_low().unwrap();
+        let switched = pressed && !was_pressed;
+        was_pressed = pressed;
+        if switched {
+            mode = match mode {
+                Mode::Brightness => Mode::Volume,
+                Mode::Volume => Mode::Brightness,
+            };
+            println!("Turning the {:?}", mode);
+        }
+
+        // The QDEC adds up the counts in hardware, so none get lost while we
+        // sleep, and reading them starts it at 0 again
+        let counts = i32::from(encoder.read());
+        position += counts;
+        let changed = match mode {
+            Mode::Brightness => brightness.turn(counts),
+            Mode::Volume => volume.turn(counts),
+        };
+        if changed {
+            println!(
+                "position {}, brightness {}, volume {}",
+                position,
+                brightness.value(),
+                volume.value()
+            );
+        }
+        if changed || switched {
+            draw(mode, brightness.value() as u8, volume.value() as u8);
+        }
+        if changed && mode == Mode::Volume {
+            speaker.set_volume(volume.value() as u8);
+            speaker.play_note(Note::A4.0, BEEP_MS);
+        }
+
+        delay.delay_ms(POLL_MS);
+    }
+}
@@ -0,0 +1,181 @@
+#![no_main]
+#![no_std]
+
+use cortex_m_rt::entry;
+use discovery_logic::clock::TimeOfDay;
+use discovery_logic::temperature_log::{line, FILE_NAME, HEADER};
+use discovery_support::delay::LowPowerDelay;
+use discovery_support::display::{display_leds, init_display};
+use discovery_support::monotonic::{self, init_monotonic};
+use discovery_support::thermometer::{Thermometer, Unit};
+use discovery_support::{init_log, println};
+use embedded_sdmmc::{
+    BlockDevice, Directory, Error, File, Mode, SdCard, TimeSource, Timestamp, Volume, VolumeIdx,
+    VolumeManager,
+};
+use microbit::{
+    board::Board,
+    hal::{
+        clocks::Clocks,
+        gpio::Level,
+        prelude::*,
+        spim::{self, Frequency, Spim},
+        timer::Timer,
+    },
+    pac,
+};
+use panic_rtt_target as _;
+
+/// How often the temperature is measured.
+const INTERVAL_MS: u32 = 1_000;
+
+/// A dot in a corner goes on and off with every line that made it onto the
+/// card.
+const WRITTEN: [[u8; 5]; 5] = [
+    [0, 0, 0, 0, 1],
+    [0, 0, 0, 0, 0],
+    [0, 0, 0, 0, 0],
+    [0, 0, 0, 0, 0],
+    [0, 0, 0, 0, 0],
+];
+/// The card isn't there, or something went wrong writing to it.
+const FAILED: [[u8; 5]; 5] = [
+    [1, 0, 0, 0, 1],
+    [0, 1, 0, 1, 0],
+    [0, 0, 1, 0, 0],
+    [0, 1, 0, 1, 0],
+    [1, 0, 0, 0, 1],
+];
+
+/// Tells the FAT driver when a file was written. The micro:bit has no
+/// calendar, and its clock starts at midnight, so everything is written on
+/// the first of January 2024, some time after the micro:bit started.
+struct Uptime;
+
+impl TimeSource for Uptime {
+    fn get_timestamp(&self) -> Timestamp {
+        let time = TimeOfDay::from_secs((monotonic::now().as_micros() / 1_000_000) as u32);
+        Timestamp {
+            year_since_1970: (2024 - 1970) as u8,
+            zero_indexed_month: 0,
+            zero_indexed_day: 0,
+            hours: time.hours() as u8,
+            minutes: time.minutes() as u8,
+            seconds: time.seconds() as u8,
+        }
+    }
+}
+
+/// Add `line` to the end of the log on the card, and start the log with the
+/// header if it's new.
+///
+/// Everything is opened again for every line, and closed after it, so that
+/// nothing about the card is kept from one line to the next. A card that
+/// was taken out and put back in, or swapped for another one, is found again
+/// like the first one was.
+fn append<D, T>(volumes: &mut VolumeManager<D, T>, line: &str) -> Result<(), Error<D::Error>>
+where
+    D: BlockDevice,
+    T: TimeSource,
+{
+    let mut volume = volumes.get_volume(VolumeIdx(0))?;
+    let root = volumes.open_root_dir(&volume)?;
+    let result = append_in(volumes, &mut volume, &root, line);
+    // The manager only has room for a few open directories and files, so
+    // they have to be closed even when something went wrong
+    volumes.close_dir(&volume, root);
+    result
+}
+
+fn append_in<D, T>(
+    volumes: &mut VolumeManager<D, T>,
+    volume: &mut Volume,
+    dir: &Directory,
+    line: &str,
+) -> Result<(), Error<D::Error>>
+where
+    D: BlockDevice,
+    T: TimeSource,
+{
+    let mut file =
+        volumes.open_file_in_dir(volume, dir, FILE_NAME, Mode::ReadWriteCreateOrAppend)?;
+    let mut result = Ok(());
+    if file.length() == 0 {
+        result = write_all(volumes, volume, &mut file, HEADER.as_bytes());
+    }
+    let result = result.and_then(|_| write_all(volumes, volume, &mut file, line.as_bytes()));
+    volumes.close_file(volume, file).and(result)
+}
+
+/// Write all of `bytes` to the end of `file`. What doesn't fit on the card
+/// anymore is left out, which counts as an error.
+fn write_all<D, T>(
+    volumes: &mut VolumeManager<D, T>,
+    volume: &mut Volume,
+    file: &mut File,
+    bytes: &[u8],
+) -> Result<(), Error<D::Error>>
+where
+    D: BlockDevice,
+    T: TimeSource,
+{
+    if volumes.write(volume, file, bytes)? < bytes.len() {
+        return Err(Error::NotEnoughSpace);
+    }
+    Ok(())
+}
+
+#[entry]
+fn main() -> ! {
+    init_log!();
+
+    let mut board = Board::take().unwrap();
+    let clocks = Clocks::new(board.CLOCK).start_lfclk();
+    init_monotonic(&clocks);
+    let mut delay = LowPowerDelay::new(board.RTC0, &clocks, &mut board.SCB);
+    init_display(board.TIMER1, board.display_pins);
+    let mut thermometer = Thermometer::new(board.TEMP, Unit::Celsius);
+
+    // The card's SCK, MOSI and MISO go on pins 13, 15 and 14 of the edge
+    // connector, where the micro:bit's own examples have them, and CS on 16
+    let pins = spim::Pins {
+        sck: board.pins.p0_17.into_push_pull_output(Level::Low).degrade(),
+        mosi: Some(board.pins.p0_13.into_push_pull_output(Level::Low).degrade()),
+        miso: Some(board.pins.p0_01.into_floating_input().degrade()),
+    };
+    let cs = board.pins.p1_02.into_push_pull_output(Level::High);
+    // `Board::take` doesn't hand out the SPIM2, so nothing else is using it
+    let spim2 = unsafe { pac::Peripherals::steal() }.SPIM2;
+    // A card has to be started at 400 kHz at most, and the driver can't
+    // change the clock after that, so it stays slow
+    let spim = Spim::new(spim2, pins, Frequency::K250, spim::MODE_0, 0xff);
+    let card = SdCard::new(spim, cs, Timer::new(board.TIMER0));
+    let mut volumes = VolumeManager::new(card, Uptime);
+
+    let mut written = 0;
+    loop {
+        let temperature = thermometer.measure();
+        let ms = monotonic::now().as_micros() / 1_000;
+        let time = TimeOfDay::from_secs((ms / 1_000) as u32);
+        let line = line(ms, time, temperature.hundredths());
+        match append(&mut volumes, &line) {
+            Ok(()) => {
+                written += 1;
+                println!("{}", line.trim_end());
+                display_leds(if written % 2 == 0 {
+                    [[0; 5]; 5]
+                } else {
+                    WRITTEN
+                });
+            }
+            Err(e) => {
+                println!("Couldn't write to the card: {:?}", e);
+                display_leds(FAILED);
+                // It may have been taken out, and a card that's put back in
+                // has to be started again
+                volumes.device().mark_card_uninit();
+            }
+        }
+        delay.delay_ms(INTERVAL_MS);
+    }
+}
@@ -0,0 +1,94 @@
+//! What a program was built from, and when, for the program to tell, written
+//! by the chapters' build scripts.
+//!
+//! Once a few dozen boards have been flashed with a few dozen versions of a
+//! program, there's no telling which one is on which board, unless the
+//! program says so itself. So the build script hands `rustc` a couple of
+//! environment variables, which `discovery_support::build_info!` puts into
+//! the program with `env!`:
+//!
+//! - `BUILD_GIT_HASH`, the commit the source is at, with `-dirty` if it had
+//!   changes that weren't committed,
+//! - and `BUILD_TIME`, when the build script ran, in UTC.
+//!
+//! The time comes from `SOURCE_DATE_EPOCH` instead, if it's set, like for
+//! any other reproducible build.
+
+use std::env;
+use std::path::Path;
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// What `git` prints for `args`, if there is a `git` and it's happy.
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8(output.stdout).ok()?.trim().to_string())
+}
+
+/// Seconds since 1970 as `YYYY-MM-DD HH:MM UTC`.
+fn utc(seconds: u64) -> String {
+    let (days, seconds) = (seconds / 86_400, seconds % 86_400);
+    // Days to a date, counting in 400 year eras that start on March 1st, so
+    // that the leap day comes at the end of a year
+    let days = days as i64 + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+    let month = if month_from_march < 10 {
+        month_from_march + 3
+    } else {
+        month_from_march - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        seconds / 3_600,
+        seconds % 3_600 / 60
+    )
+}
+
+/// Tell `rustc` the commit and the time, for `build_info!`.
+pub fn write() {
+    let hash = match git(&["rev-parse", "--short", "HEAD"]) {
+        Some(hash) => match git(&["status", "--porcelain", "--untracked-files=no"]) {
+            Some(changes) if !changes.is_empty() => format!("{}-dirty", hash),
+            _ => hash,
+        },
+        None => "unknown".to_string(),
+    };
+    println!("cargo:rustc-env=BUILD_GIT_HASH={}", hash);
+
+    let seconds = match env::var("SOURCE_DATE_EPOCH") {
+        Ok(epoch) => epoch.parse().expect("SOURCE_DATE_EPOCH isn't a number"),
+        Err(_) => SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs(),
+    };
+    println!("cargo:rustc-env=BUILD_TIME={}", utc(seconds));
+
+    // A new commit moves HEAD, or the branch it points to, and every change
+    // that's staged touches the index. Without these, the hash would only be
+    // updated when something else makes Cargo run the build script again.
+    // A file that doesn't exist, like the branch's after `git pack-refs`,
+    // would have Cargo run the build script every time.
+    let branch = git(&["symbolic-ref", "-q", "HEAD"]);
+    let paths = ["HEAD", "index"].iter().copied().chain(branch.as_deref());
+    for path in paths.filter_map(|path| git(&["rev-parse", "--git-path", path])) {
+        if Path::new(&path).exists() {
+            println!("cargo:rerun-if-changed={}", path);
+        }
+    }
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+    println!("cargo:rerun-if-changed=../../support/build/build_info.rs");
+}
@@ -0,0 +1,44 @@
+//! The `device.x` linker script for the chapters with programs built on
+//! `embassy-nrf`, next to the ones built on the `microbit` crate, written by
+//! their build scripts.
+//!
+//! `cortex-m-rt`'s linker script includes a `device.x`, which names the
+//! handler of every interrupt in the vector table, so that the ones a program
+//! doesn't have fall back to `DefaultHandler`. The PAC the `microbit` crate
+//! is built on and the one `embassy-nrf` is built on bring one each, and the
+//! linker takes whichever it comes across first. But the two don't name all of
+//! the nRF52833's interrupts the same way: where one has `UARTE0_UART0`, the
+//! other has `UARTE0`, so the vector table of the other one points to
+//! handlers that don't exist. This `device.x` names them both ways, and goes
+//! where the linker looks first, in `OUT_DIR` next to `memory.x`.
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+/// The interrupts of the nRF52833, by the names of both PACs.
+const INTERRUPTS: &str = "\
+    AAR_CCM CCM_AAR CLOCK_POWER COMP_LPCOMP ECB EGU0_SWI0 EGU1_SWI1 EGU2_SWI2 EGU3_SWI3 \
+    EGU4_SWI4 EGU5_SWI5 FPU GPIOTE I2S MWU NFCT PDM POWER_CLOCK PWM0 PWM1 PWM2 PWM3 QDEC \
+    RADIO RNG RTC0 RTC1 RTC2 SAADC SPI2 SPIM0_SPIS0_TWIM0_TWIS0_SPI0_TWI0 \
+    SPIM1_SPIS1_TWIM1_TWIS1_SPI1_TWI1 SPIM2_SPIS2_SPI2 SPIM3 SWI0_EGU0 SWI1_EGU1 SWI2_EGU2 \
+    SWI3_EGU3 SWI4_EGU4 SWI5_EGU5 TEMP TIMER0 TIMER1 TIMER2 TIMER3 TIMER4 TWISPI0 TWISPI1 \
+    UARTE0 UARTE0_UART0 UARTE1 USBD WDT";
+
+/// Write the `device.x` if the chapter is built with its `embassy` feature.
+/// Without it, the one of the `microbit` crate's PAC is all there is.
+pub fn write() {
+    println!("cargo:rerun-if-changed=../../support/build/device_x.rs");
+    let device_x = PathBuf::from(env::var_os("OUT_DIR").unwrap()).join("device.x");
+    if env::var_os("CARGO_FEATURE_EMBASSY").is_none() {
+        fs::remove_file(device_x).ok();
+        return;
+    }
+
+    let provides: String = INTERRUPTS
+        .split_whitespace()
+        .map(|name| format!("PROVIDE({} = DefaultHandler);\n", name))
+        .collect();
+    // The build script puts `OUT_DIR` on the linker's search path already
+    fs::write(device_x, provides).unwrap();
+}
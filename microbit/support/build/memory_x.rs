@@ -0,0 +1,160 @@
+//! The `memory.x` linker script for whichever board a chapter is built for,
+//! written by the chapters' build scripts.
+//!
+//! The linker has to know where the flash and the RAM are, and how big they
+//! are, and that's different for every chip: the micro:bit v1's nRF51822 has
+//! 256K of flash and 16K of RAM, the v2's nRF52833 512K and 128K. A softdevice,
+//! Nordic's Bluetooth stack, sits at the start of the flash and the RAM, and
+//! takes some of each. So instead of one `memory.x` for all of them, the build
+//! script picks the layout from the features the chapter is built with:
+//! `v1`, `v2`, and `s113` or `s140` on top of `v2`, for the SoftDevice of
+//! that name. With `dfu` on top of `v2` instead, the program goes behind the
+//! serial bootloader, which takes the first [`BOOTLOADER_LEN`] bytes, and
+//! which has a layout of its own, see [`write_bootloader`].
+//!
+//! Every layout leaves out the end of the flash, where `discovery-support`
+//! keeps its settings and, on the v2, its log and the crash log. Where those are is in
+//! `support/src/flash_layout.rs`, which the support crate uses too.
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+// The build scripts only need some of it
+#[allow(dead_code)]
+#[path = "../src/flash_layout.rs"]
+mod flash_layout;
+
+const K: u32 = 1024;
+/// How much of the flash the serial bootloader takes, from the start.
+const BOOTLOADER_LEN: u32 = 32 * K;
+/// Where the part of the v2's flash ends that any of its layouts may use.
+const V2_PROGRAM_END: u32 = flash_layout::v2::PROGRAM_END as u32;
+
+/// Where a program may go, and how much room it has.
+struct Layout {
+    board: &'static str,
+    flash_origin: u32,
+    flash_length: u32,
+    ram_origin: u32,
+    ram_length: u32,
+}
+
+impl Layout {
+    /// The layout for the features the chapter is being built with.
+    fn from_features() -> Layout {
+        let v1 = env::var_os("CARGO_FEATURE_V1").is_some();
+        let s113 = env::var_os("CARGO_FEATURE_S113").is_some();
+        let s140 = env::var_os("CARGO_FEATURE_S140").is_some();
+        if env::var_os("CARGO_FEATURE_DFU").is_some() {
+            if v1 || s113 || s140 {
+                panic!("the serial bootloader is for the micro:bit v2 without a softdevice");
+            }
+            return Layout {
+                board: "micro:bit v2, nRF52833 behind the serial bootloader",
+                flash_origin: BOOTLOADER_LEN,
+                flash_length: V2_PROGRAM_END - BOOTLOADER_LEN,
+                ram_origin: 0x2000_0000,
+                ram_length: 128 * K,
+            };
+        }
+        match (v1, s113, s140) {
+            (_, true, true) => panic!("pick one softdevice, `s113` or `s140`"),
+            (true, true, _) | (true, _, true) => {
+                panic!("there is no softdevice layout for the micro:bit v1")
+            }
+            (true, false, false) => Layout {
+                board: "micro:bit v1, nRF51822",
+                flash_origin: 0,
+                flash_length: flash_layout::v1::PROGRAM_END as u32,
+                ram_origin: 0x2000_0000,
+                ram_length: 16 * K,
+            },
+            (false, false, false) => Layout {
+                board: "micro:bit v2, nRF52833",
+                flash_origin: 0,
+                flash_length: V2_PROGRAM_END,
+                ram_origin: 0x2000_0000,
+                ram_length: 128 * K,
+            },
+            // S113 7.x takes the first 112K of the flash. How much RAM it
+            // needs depends on how it's set up, it says so when it's enabled
+            // with less, 8K is plenty for a single connection.
+            (false, true, false) => Layout {
+                board: "micro:bit v2, nRF52833 with the S113 softdevice",
+                flash_origin: 112 * K,
+                flash_length: V2_PROGRAM_END - 112 * K,
+                ram_origin: 0x2000_0000 + 8 * K,
+                ram_length: (128 - 8) * K,
+            },
+            // S140 7.x can be central as well, and takes 156K of the flash.
+            // It wants more RAM for every connection it's set up for, 16K
+            // leaves room for a few.
+            (false, false, true) => Layout {
+                board: "micro:bit v2, nRF52833 with the S140 softdevice",
+                flash_origin: 156 * K,
+                flash_length: V2_PROGRAM_END - 156 * K,
+                ram_origin: 0x2000_0000 + 16 * K,
+                ram_length: (128 - 16) * K,
+            },
+        }
+    }
+
+    fn memory_x(&self) -> String {
+        format!(
+            "/* Written by the build script, for the {} */\n\
+             MEMORY\n\
+             {{\n  \
+               FLASH : ORIGIN = {:#010x}, LENGTH = {}K\n  \
+               RAM : ORIGIN = {:#010x}, LENGTH = {}K\n\
+             }}\n",
+            self.board,
+            self.flash_origin,
+            self.flash_length / K,
+            self.ram_origin,
+            self.ram_length / K,
+        )
+    }
+}
+
+/// Put the `memory.x` for the chapter's features in the output directory, and
+/// point the linker there.
+pub fn write() {
+    write_layout(Layout::from_features());
+}
+
+/// Put the `memory.x` for the serial bootloader in the output directory
+/// instead. It starts where the chip does, and keeps to the first
+/// [`BOOTLOADER_LEN`] bytes, the program it loads goes after those. It needs
+/// all of the RAM while it runs, but none of it once it's started the
+/// program.
+// Only the bootloader's build script calls it, the others all call `write`
+#[allow(dead_code)]
+pub fn write_bootloader() {
+    // There's no bootloader for the v1, and nothing that gets linked, the
+    // chapter only has to build
+    if env::var_os("CARGO_FEATURE_V1").is_some() {
+        return write();
+    }
+    write_layout(Layout {
+        board: "micro:bit v2, nRF52833, the serial bootloader",
+        flash_origin: 0,
+        flash_length: BOOTLOADER_LEN,
+        ram_origin: 0x2000_0000,
+        ram_length: 128 * K,
+    });
+}
+
+fn write_layout(layout: Layout) {
+    let out = PathBuf::from(env::var_os("OUT_DIR").unwrap());
+    fs::write(out.join("memory.x"), layout.memory_x()).unwrap();
+    println!("cargo:rustc-link-search={}", out.display());
+
+    // Cargo runs the build script again whenever the features change, but
+    // would run it for every change to the crate as well, unless told which
+    // files it depends on. The pages the support crate keeps are part of the
+    // layout, too.
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=../../support/build/memory_x.rs");
+    println!("cargo:rerun-if-changed=../../support/src/flash_layout.rs");
+}
@@ -0,0 +1,180 @@
+//! Analog input on the big rings of the edge connector, P0, P1 and P2.
+//!
+//! Each of the rings is wired to one of the `SAADC`'s analog inputs, so
+//! instead of only high or low, it can tell any voltage between 0 V and the
+//! 3.3 V of the board. [`AnalogPin`] measures one of them at a time.
+//!
+//! A single measurement is a little noisy. *Oversampling* takes 2, 4, up to
+//! 256 of them in a row, and averages them into one, at the cost of taking
+//! that many times longer. The `SAADC` also has a small offset of its own,
+//! which *calibration* measures, and then takes out of every measurement.
+//! It drifts with the temperature, so it's worth calibrating again every now
+//! and then.
+//!
+//! Besides the pins, the `SAADC` can measure the chip's own supply voltage,
+//! VDD, which [`vdd_millivolts`] does. On USB, that's the 3.3 V the board's
+//! regulator makes. On batteries, it's whatever voltage the batteries have
+//! left, and it drops as they run down.
+
+use core::sync::atomic::{compiler_fence, Ordering};
+use microbit::hal::gpio::p0::{P0_02, P0_03, P0_04};
+use microbit::pac::saadc::ch::pselp::PSELP_A;
+use microbit::pac::SAADC;
+
+/// The largest a measurement gets, with 12 bits.
+pub const MAX_VALUE: u16 = 4095;
+/// The voltage of a measurement of [`MAX_VALUE`], in millivolts.
+pub const FULL_SCALE_MV: u32 = 3_300;
+/// The same for [`vdd_millivolts`], which measures against the `SAADC`'s
+/// internal reference instead of VDD itself.
+const VDD_FULL_SCALE_MV: u32 = 3_600;
+
+/// One of the rings of the edge connector, and which of the `SAADC`'s inputs
+/// it's wired to.
+pub trait Ring {
+    const INPUT: PSELP_A;
+}
+
+/// Ring P0
+impl<MODE> Ring for P0_02<MODE> {
+    const INPUT: PSELP_A = PSELP_A::ANALOGINPUT0;
+}
+
+/// Ring P1
+impl<MODE> Ring for P0_03<MODE> {
+    const INPUT: PSELP_A = PSELP_A::ANALOGINPUT1;
+}
+
+/// Ring P2
+impl<MODE> Ring for P0_04<MODE> {
+    const INPUT: PSELP_A = PSELP_A::ANALOGINPUT2;
+}
+
+pub struct AnalogPin<P: Ring> {
+    saadc: SAADC,
+    pin: P,
+}
+
+impl<P: Ring> AnalogPin<P> {
+    /// Set up the `SAADC` to measure `pin`, and calibrate it.
+    pub fn new(saadc: SAADC, pin: P) -> AnalogPin<P> {
+        saadc.enable.write(|w| w.enable().enabled());
+        saadc.resolution.write(|w| w.val()._12bit());
+        saadc.ch[0].pselp.write(|w| w.pselp().variant(P::INPUT));
+        saadc.ch[0].pseln.write(|w| w.pseln().nc());
+        // A quarter of the input, against a quarter of the supply voltage,
+        // makes 0 to 3.3 V the whole range
+        saadc.ch[0].config.write(|w| {
+            w.gain().gain1_4();
+            w.refsel().vdd1_4();
+            w.tacq()._10us();
+            w.mode().se();
+            w.resp().bypass();
+            w.resn().bypass();
+            // Oversampling takes all of its measurements on one `SAMPLE`
+            // task, instead of needing one task for each
+            w.burst().enabled()
+        });
+        // One measurement at a time, until `set_oversampling` says otherwise
+        saadc.oversample.write(|w| w.oversample().bypass());
+        // No timer: every measurement is started by hand
+        saadc.samplerate.write(|w| w.mode().task());
+        saadc.result.maxcnt.write(|w| unsafe { w.maxcnt().bits(1) });
+
+        let mut analog = AnalogPin { saadc, pin };
+        analog.calibrate();
+        analog
+    }
+
+    /// Average every measurement out of `samples` of them, which has to be a
+    /// power of two from 1 to 256.
+    pub fn set_oversampling(&mut self, samples: u32) {
+        assert!(
+            samples.is_power_of_two() && samples <= 256,
+            "can't oversample {} times",
+            samples
+        );
+        self.saadc
+            .oversample
+            .write(|w| unsafe { w.oversample().bits(samples.trailing_zeros() as u8) });
+    }
+
+    /// Measure the `SAADC`'s own offset, so that it can take it out of every
+    /// measurement. This takes a few milliseconds.
+    pub fn calibrate(&mut self) {
+        self.saadc.events_calibratedone.reset();
+        self.saadc
+            .tasks_calibrateoffset
+            .write(|w| unsafe { w.bits(1) });
+        while self.saadc.events_calibratedone.read().bits() == 0 {}
+        self.saadc.events_calibratedone.reset();
+    }
+
+    /// Measure the voltage on the ring, from 0 to [`MAX_VALUE`].
+    pub fn read(&mut self) -> u16 {
+        // Calibration, or noise, can push a ring at 0 V a little below 0
+        sample(&self.saadc).clamp(0, MAX_VALUE as i16) as u16
+    }
+
+    /// Measure the voltage on the ring, in millivolts.
+    pub fn read_mv(&mut self) -> u32 {
+        u32::from(self.read()) * FULL_SCALE_MV / u32::from(MAX_VALUE)
+    }
+
+    /// Switch the `SAADC` off, and give back it and the pin.
+    pub fn free(self) -> (SAADC, P) {
+        self.saadc.enable.write(|w| w.enable().disabled());
+        (self.saadc, self.pin)
+    }
+}
+
+/// Measure the supply voltage, VDD, in millivolts.
+///
+/// It can't be measured against itself, like the rings are, so the `SAADC`
+/// measures a sixth of it against its internal 0.6 V reference instead. It
+/// takes 8 measurements and averages them, which is plenty for a battery that
+/// takes hours to go down by a few millivolts. The `SAADC` is switched off
+/// again afterwards.
+pub fn vdd_millivolts(saadc: &mut SAADC) -> u32 {
+    saadc.enable.write(|w| w.enable().enabled());
+    saadc.resolution.write(|w| w.val()._12bit());
+    saadc.ch[0].pselp.write(|w| w.pselp().vdd());
+    saadc.ch[0].pseln.write(|w| w.pseln().nc());
+    saadc.ch[0].config.write(|w| {
+        w.gain().gain1_6();
+        w.refsel().internal();
+        w.tacq()._10us();
+        w.mode().se();
+        w.resp().bypass();
+        w.resn().bypass();
+        w.burst().enabled()
+    });
+    saadc.oversample.write(|w| w.oversample().over8x());
+    saadc.samplerate.write(|w| w.mode().task());
+    saadc.result.maxcnt.write(|w| unsafe { w.maxcnt().bits(1) });
+
+    let sample = sample(saadc).max(0) as u32;
+    saadc.enable.write(|w| w.enable().disabled());
+    sample * VDD_FULL_SCALE_MV / (u32::from(MAX_VALUE) + 1)
+}
+
+/// Take a single measurement with channel 0, the way it's been set up.
+fn sample(saadc: &SAADC) -> i16 {
+    let mut sample: i16 = 0;
+    let ptr = &mut sample as *mut i16 as u32;
+    saadc.result.ptr.write(|w| unsafe { w.bits(ptr) });
+    saadc.events_started.reset();
+    saadc.events_end.reset();
+    // EasyDMA writes to `sample` behind the compiler's back, which
+    // mustn't move anything across that
+    compiler_fence(Ordering::SeqCst);
+    saadc.tasks_start.write(|w| unsafe { w.bits(1) });
+    while saadc.events_started.read().bits() == 0 {}
+    saadc.tasks_sample.write(|w| unsafe { w.bits(1) });
+    while saadc.events_end.read().bits() == 0 {}
+    compiler_fence(Ordering::SeqCst);
+    saadc.events_stopped.reset();
+    saadc.tasks_stop.write(|w| unsafe { w.bits(1) });
+    while saadc.events_stopped.read().bits() == 0 {}
+    sample
+}
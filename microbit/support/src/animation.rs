@@ -0,0 +1,194 @@
+//! Frame based animations for the LED matrix.
+//!
+//! An [`Animation`] is a list of [`Frame`]s, each with its own duration, that
+//! is either played once or looped forever. Frames are shown through the
+//! interrupt driven [`display`](crate::display), so they may use the full
+//! range of brightnesses.
+
+use crate::display::display_greyscale;
+use embedded_hal_02::blocking::delay::DelayMs;
+
+/// A single picture of an animation and how long it stays on screen.
+#[derive(Clone, Copy, Debug)]
+pub struct Frame {
+    pub leds: [[u8; 5]; 5],
+    pub duration_ms: u32,
+}
+
+impl Frame {
+    pub const fn new(leds: [[u8; 5]; 5], duration_ms: u32) -> Frame {
+        Frame { leds, duration_ms }
+    }
+}
+
+/// What happens after the last frame has been shown.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+    /// Stop after the last frame.
+    OneShot,
+    /// Start again from the first frame.
+    Loop,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Animation<'a> {
+    frames: &'a [Frame],
+    mode: Mode,
+}
+
+impl<'a> Animation<'a> {
+    pub const fn new(frames: &'a [Frame], mode: Mode) -> Animation<'a> {
+        Animation { frames, mode }
+    }
+
+    /// The same frames, played with a different `mode`.
+    pub const fn with_mode(self, mode: Mode) -> Animation<'a> {
+        Animation { mode, ..self }
+    }
+
+    pub fn frames(&self) -> &'a [Frame] {
+        self.frames
+    }
+
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// An iterator over the frames in the order they should be shown. For a
+    /// looping animation it never ends.
+    pub fn player(&self) -> Player<'a> {
+        Player {
+            animation: *self,
+            next: 0,
+        }
+    }
+}
+
+/// Steps through the frames of an [`Animation`].
+///
+/// This is what to use when `main` has other things to do than wait for the
+/// next frame: show the frame, note when it is due to be replaced and carry
+/// on.
+pub struct Player<'a> {
+    animation: Animation<'a>,
+    next: usize,
+}
+
+impl<'a> Iterator for Player<'a> {
+    type Item = &'a Frame;
+
+    fn next(&mut self) -> Option<&'a Frame> {
+        let frames = self.animation.frames;
+        if self.next == frames.len() {
+            if self.animation.mode == Mode::OneShot || frames.is_empty() {
+                return None;
+            }
+            self.next = 0;
+        }
+        let frame = &frames[self.next];
+        self.next += 1;
+        Some(frame)
+    }
+}
+
+/// Play `animation` on the display, using `timer` to wait between frames.
+///
+/// This returns once a one shot animation has finished and never returns for
+/// a looping one.
+pub fn play<D: DelayMs<u32>>(animation: &Animation, timer: &mut D) {
+    for frame in animation.player() {
+        display_greyscale(frame.leds);
+        timer.delay_ms(frame.duration_ms);
+    }
+}
+
+/// A heart growing and shrinking.
+pub const HEART_BEAT: Animation<'static> = Animation::new(
+    &[
+        Frame::new(
+            [
+                [0, 9, 0, 9, 0],
+                [9, 9, 9, 9, 9],
+                [9, 9, 9, 9, 9],
+                [0, 9, 9, 9, 0],
+                [0, 0, 9, 0, 0],
+            ],
+            400,
+        ),
+        Frame::new(
+            [
+                [0, 0, 0, 0, 0],
+                [0, 9, 0, 9, 0],
+                [0, 9, 9, 9, 0],
+                [0, 0, 9, 0, 0],
+                [0, 0, 0, 0, 0],
+            ],
+            400,
+        ),
+    ],
+    Mode::Loop,
+);
+
+/// A bar spinning around the centre LED.
+pub const SPINNER: Animation<'static> = Animation::new(
+    &[
+        Frame::new(
+            [
+                [0, 0, 9, 0, 0],
+                [0, 0, 9, 0, 0],
+                [0, 0, 9, 0, 0],
+                [0, 0, 9, 0, 0],
+                [0, 0, 9, 0, 0],
+            ],
+            100,
+        ),
+        Frame::new(
+            [
+                [0, 0, 0, 0, 9],
+                [0, 0, 0, 9, 0],
+                [0, 0, 9, 0, 0],
+                [0, 9, 0, 0, 0],
+                [9, 0, 0, 0, 0],
+            ],
+            100,
+        ),
+        Frame::new(
+            [
+                [0, 0, 0, 0, 0],
+                [0, 0, 0, 0, 0],
+                [9, 9, 9, 9, 9],
+                [0, 0, 0, 0, 0],
+                [0, 0, 0, 0, 0],
+            ],
+            100,
+        ),
+        Frame::new(
+            [
+                [9, 0, 0, 0, 0],
+                [0, 9, 0, 0, 0],
+                [0, 0, 9, 0, 0],
+                [0, 0, 0, 9, 0],
+                [0, 0, 0, 0, 9],
+            ],
+            100,
+        ),
+    ],
+    Mode::Loop,
+);
+
+/// The display filling up from left to right and then emptying again.
+pub const WIPE: Animation<'static> = Animation::new(
+    &[
+        Frame::new([[9, 0, 0, 0, 0]; 5], 80),
+        Frame::new([[9, 9, 0, 0, 0]; 5], 80),
+        Frame::new([[9, 9, 9, 0, 0]; 5], 80),
+        Frame::new([[9, 9, 9, 9, 0]; 5], 80),
+        Frame::new([[9, 9, 9, 9, 9]; 5], 80),
+        Frame::new([[0, 9, 9, 9, 9]; 5], 80),
+        Frame::new([[0, 0, 9, 9, 9]; 5], 80),
+        Frame::new([[0, 0, 0, 9, 9]; 5], 80),
+        Frame::new([[0, 0, 0, 0, 9]; 5], 80),
+        Frame::new([[0, 0, 0, 0, 0]; 5], 80),
+    ],
+    Mode::OneShot,
+);
@@ -0,0 +1,58 @@
+//! Which program is on the board, from which commit, built when.
+//!
+//! A chapter whose build script calls `build_info::write()` from
+//! `support/build/build_info.rs` gets the commit and the time as environment
+//! variables while it's compiled. [`build_info!`](crate::build_info!) picks
+//! them up with `env!`, in the chapter's crate rather than this one, along
+//! with the ones Cargo sets itself, and puts them all into a [`BuildInfo`].
+//! A chapter whose build script doesn't call it fails to compile with a
+//! message about `BUILD_GIT_HASH`.
+
+use core::fmt;
+
+/// Which board the program was built for.
+#[cfg(feature = "v1")]
+pub const BOARD: &str = "micro:bit v1";
+#[cfg(feature = "v2")]
+pub const BOARD: &str = "micro:bit v2";
+
+#[derive(Clone, Copy, Debug)]
+pub struct BuildInfo {
+    /// The chapter's package, like `uart`.
+    pub chapter: &'static str,
+    pub version: &'static str,
+    /// The program in it, the chapter's own or one of its examples.
+    pub program: &'static str,
+    /// The short hash of the commit, with `-dirty` if there were changes
+    /// that weren't committed yet.
+    pub git_hash: &'static str,
+    /// When the build script ran, like `2024-03-01 14:05 UTC`.
+    pub built: &'static str,
+    pub board: &'static str,
+}
+
+/// The [`BuildInfo`] of the program that uses it.
+#[macro_export]
+macro_rules! build_info {
+    () => {
+        $crate::build_info::BuildInfo {
+            chapter: env!("CARGO_PKG_NAME"),
+            version: env!("CARGO_PKG_VERSION"),
+            program: env!("CARGO_CRATE_NAME"),
+            git_hash: env!("BUILD_GIT_HASH"),
+            built: env!("BUILD_TIME"),
+            board: $crate::build_info::BOARD,
+        }
+    };
+}
+
+impl fmt::Display for BuildInfo {
+    /// All of it on one line, for a banner.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} from {} {}, git {}, built {} for the {}",
+            self.program, self.chapter, self.version, self.git_hash, self.built, self.board
+        )
+    }
+}
@@ -0,0 +1,384 @@
+//! Clicks, double clicks and long presses on buttons A and B.
+//!
+//! A button doesn't go from up to down cleanly: its contacts bounce for a few
+//! milliseconds, and the pin flips back and forth many times before it
+//! settles. So the `GPIOTE` interrupt only notices that *something* happened
+//! on a pin, and wakes up `TIMER2`. From then on, the timer looks at both
+//! buttons every [`TICK_MS`], and only takes a new state seriously once it has
+//! stayed the same for [`DEBOUNCE_MS`]. When both buttons are idle again, the
+//! timer goes back to sleep.
+//!
+//! The time between pressing and releasing, and between one click and the
+//! next, tells the events apart:
+//!
+//! - held for [`LONG_PRESS_MS`] is a [`ButtonEvent::LongPress`], right away,
+//!   without waiting for the release,
+//! - released and pressed again within [`DOUBLE_CLICK_MS`] is a
+//!   [`ButtonEvent::DoubleClick`],
+//! - anything else is a [`ButtonEvent::Click`]. It only comes once
+//!   [`DOUBLE_CLICK_MS`] have passed without a second click.
+//!
+//! The events go into a [`Channel`], the timer's interrupt handler sends them
+//! and the main loop receives them from the [`ButtonEvents`] that
+//! [`init_buttons`] returns.
+//!
+//! On the micro:bit v2, [`init_rings`] adds the big rings P0, P1 and P2 of
+//! the edge connector, for switches, motion sensors and the like. They are
+//! debounced the same way, and their events go into the same channel, but they
+//! only tell when an input becomes active, [`ButtonEvent::Pressed`], and when
+//! it stops being active, [`ButtonEvent::Released`].
+//!
+//! The `GPIOTE` and `TIMER2` handlers only come with the `buttons-interrupt`
+//! feature, so that a program can have the `GPIOTE` interrupt to itself, as
+//! the interrupts chapter does. If it wants the buttons too, its handlers call
+//! [`on_gpiote_interrupt`] and [`on_timer2_interrupt`].
+
+use crate::channel::{Channel, Receiver, Sender};
+use crate::shared::Shared;
+use crate::softdevice;
+#[cfg(feature = "buttons-interrupt")]
+use crate::weak_interrupt::weak_interrupt;
+use embedded_hal_02::digital::v2::InputPin;
+use embedded_hal_02::timer::{Cancel, CountDown};
+use microbit::board::Buttons;
+use microbit::hal::gpio::{Disconnected, Floating, Input, Pin, PullDown, PullUp};
+use microbit::hal::gpiote::Gpiote;
+use microbit::hal::timer::{Periodic, Timer};
+use microbit::pac::{self, GPIOTE, TIMER2};
+
+/// How often the buttons are looked at while something is going on.
+pub const TICK_MS: u32 = 10;
+/// How long a button has to stay up or down before we believe it.
+pub const DEBOUNCE_MS: u32 = 20;
+pub const LONG_PRESS_MS: u32 = 600;
+pub const DOUBLE_CLICK_MS: u32 = 300;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Button {
+    A,
+    B,
+    /// The rings of the edge connector, once [`init_rings`] has set them up.
+    P0,
+    P1,
+    P2,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ButtonEvent {
+    Click,
+    DoubleClick,
+    LongPress,
+    /// A ring became active.
+    Pressed,
+    /// A ring stopped being active.
+    Released,
+}
+
+/// How a ring is pulled while nothing drives it, which also decides what
+/// makes it active.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pull {
+    /// Pulled high, and active while something pulls it low, like a switch
+    /// to GND. That's how the buttons are wired.
+    Up,
+    /// Pulled low, and active while something drives it high, like the
+    /// output of a motion sensor.
+    Down,
+}
+
+/// Where a button is in telling the events apart. The milliseconds count up
+/// from the last time the button went down or up.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum State {
+    Idle,
+    Pressed {
+        ms: u32,
+    },
+    /// The long press has already been reported, it only needs releasing.
+    LongPressed,
+    /// Released after a short press, waiting to see if a second one comes.
+    Released {
+        ms: u32,
+    },
+    /// Pressed for the second time, the release makes it a double click.
+    PressedAgain,
+}
+
+/// Whether a pin is down, or active, as far as we believe.
+#[derive(Default)]
+struct Debouncer {
+    down: bool,
+    /// How long the pin has disagreed with `down`.
+    changing_ms: u32,
+}
+
+impl Debouncer {
+    /// Move on by one tick, with the pin reading `down`, and tell if that
+    /// changed what we believe.
+    fn tick(&mut self, down: bool) -> bool {
+        let changed = if down == self.down {
+            self.changing_ms = 0;
+            false
+        } else {
+            self.changing_ms += TICK_MS;
+            self.changing_ms >= DEBOUNCE_MS
+        };
+        if changed {
+            self.down = down;
+            self.changing_ms = 0;
+        }
+        changed
+    }
+
+    /// Whether the pin has agreed with us since the last tick.
+    fn is_settled(&self) -> bool {
+        self.changing_ms == 0
+    }
+}
+
+struct Debounced {
+    pin: Pin<Input<Floating>>,
+    debouncer: Debouncer,
+    state: State,
+}
+
+impl Debounced {
+    fn new(pin: Pin<Input<Floating>>) -> Debounced {
+        Debounced {
+            pin,
+            debouncer: Debouncer::default(),
+            state: State::Idle,
+        }
+    }
+
+    /// Move on by one tick, and tell if that made an event.
+    fn tick(&mut self) -> Option<ButtonEvent> {
+        // The buttons pull their pins low while they're pressed
+        let changed = self.debouncer.tick(self.pin.is_low().unwrap());
+
+        let (state, event) = match (self.state, changed) {
+            (State::Idle, true) => (State::Pressed { ms: 0 }, None),
+            (State::Pressed { .. }, true) => (State::Released { ms: 0 }, None),
+            (State::Pressed { ms }, false) if ms + TICK_MS >= LONG_PRESS_MS => {
+                (State::LongPressed, Some(ButtonEvent::LongPress))
+            }
+            (State::Pressed { ms }, false) => (State::Pressed { ms: ms + TICK_MS }, None),
+            (State::LongPressed, true) => (State::Idle, None),
+            (State::Released { .. }, true) => (State::PressedAgain, None),
+            (State::Released { ms }, false) if ms + TICK_MS >= DOUBLE_CLICK_MS => {
+                (State::Idle, Some(ButtonEvent::Click))
+            }
+            (State::Released { ms }, false) => (State::Released { ms: ms + TICK_MS }, None),
+            (State::PressedAgain, true) => (State::Idle, Some(ButtonEvent::DoubleClick)),
+            (state, _) => (state, None),
+        };
+        self.state = state;
+        event
+    }
+
+    /// Whether there's nothing left to wait for, so that the timer can stop.
+    fn is_idle(&self) -> bool {
+        self.state == State::Idle && !self.debouncer.down && self.debouncer.is_settled()
+    }
+}
+
+// Only `init_rings` sets up rings, which the micro:bit v1 doesn't have
+#[cfg_attr(not(feature = "v2"), allow(dead_code))]
+enum RingPin {
+    PullUp(Pin<Input<PullUp>>),
+    PullDown(Pin<Input<PullDown>>),
+}
+
+/// A ring of the edge connector.
+struct Ring {
+    pin: RingPin,
+    debouncer: Debouncer,
+}
+
+impl Ring {
+    #[cfg_attr(not(feature = "v2"), allow(dead_code))]
+    fn new(pin: Pin<Disconnected>, pull: Pull) -> Ring {
+        let pin = match pull {
+            Pull::Up => RingPin::PullUp(pin.into_pullup_input()),
+            Pull::Down => RingPin::PullDown(pin.into_pulldown_input()),
+        };
+        Ring {
+            pin,
+            debouncer: Debouncer::default(),
+        }
+    }
+
+    fn tick(&mut self) -> Option<ButtonEvent> {
+        let active = match &self.pin {
+            RingPin::PullUp(pin) => pin.is_low().unwrap(),
+            RingPin::PullDown(pin) => pin.is_high().unwrap(),
+        };
+        if !self.debouncer.tick(active) {
+            None
+        } else if self.debouncer.down {
+            Some(ButtonEvent::Pressed)
+        } else {
+            Some(ButtonEvent::Released)
+        }
+    }
+}
+
+struct Driver {
+    gpiote: Gpiote,
+    timer: Timer<TIMER2, Periodic>,
+    /// Whether `timer` is ticking.
+    ticking: bool,
+    a: Debounced,
+    b: Debounced,
+    rings: [Option<Ring>; 3],
+    events: Sender<(Button, ButtonEvent), EVENTS_LEN>,
+}
+
+/// The rings, in the order of `Driver::rings`.
+const RINGS: [Button; 3] = [Button::P0, Button::P1, Button::P2];
+
+/// A channel of size N holds at most N - 1 events.
+const EVENTS_LEN: usize = 8;
+
+/// Where the events come out, the oldest first.
+///
+/// There's room for 7 events. If the main loop doesn't look often enough, any
+/// more are dropped.
+pub type ButtonEvents = Receiver<(Button, ButtonEvent), EVENTS_LEN>;
+
+static DRIVER: Shared<Driver> = Shared::new();
+static EVENTS: Channel<(Button, ButtonEvent), EVENTS_LEN> = Channel::new();
+
+/// Take over the buttons, and start listening to them. Events come out of the
+/// [`ButtonEvents`] this returns.
+///
+/// There's only one `GPIOTE`, so this can only be called once.
+pub fn init_buttons(
+    board_gpiote: GPIOTE,
+    board_timer: TIMER2,
+    board_buttons: Buttons,
+) -> ButtonEvents {
+    let (sender, receiver) = EVENTS.split().expect("init_buttons was called before");
+    let gpiote = Gpiote::new(board_gpiote);
+    let a = board_buttons.button_a.degrade();
+    let b = board_buttons.button_b.degrade();
+
+    // Both edges, since releasing a button matters as much as pressing it
+    let channel0 = gpiote.channel0();
+    channel0.input_pin(&a).toggle().enable_interrupt();
+    channel0.reset_events();
+    let channel1 = gpiote.channel1();
+    channel1.input_pin(&b).toggle().enable_interrupt();
+    channel1.reset_events();
+
+    let mut timer = Timer::periodic(board_timer);
+    timer.enable_interrupt();
+
+    DRIVER.lend(Driver {
+        gpiote,
+        timer,
+        ticking: false,
+        a: Debounced::new(a),
+        b: Debounced::new(b),
+        rings: [None, None, None],
+        events: sender,
+    });
+    unsafe {
+        softdevice::unmask(pac::Interrupt::GPIOTE);
+        softdevice::unmask(pac::Interrupt::TIMER2);
+    }
+    pac::NVIC::unpend(pac::Interrupt::GPIOTE);
+    receiver
+}
+
+/// Listen to the rings P0, P1 and P2 of the edge connector as well, all
+/// pulled the same way. Their events come out of the same [`ButtonEvents`] as
+/// those of the buttons, so [`init_buttons`] has to come first.
+///
+/// Each ring gets a `GPIOTE` channel of its own. The nRF51 only has 4 of
+/// those, and the buttons take 2 already, so this is for the micro:bit v2
+/// only.
+#[cfg(feature = "v2")]
+pub fn init_rings(p0: Pin<Disconnected>, p1: Pin<Disconnected>, p2: Pin<Disconnected>, pull: Pull) {
+    let rings = [
+        Ring::new(p0, pull),
+        Ring::new(p1, pull),
+        Ring::new(p2, pull),
+    ];
+    DRIVER
+        .with(|driver| {
+            let channels = [
+                driver.gpiote.channel2(),
+                driver.gpiote.channel3(),
+                driver.gpiote.channel4(),
+            ];
+            for (channel, ring) in channels.iter().zip(rings.iter()) {
+                match &ring.pin {
+                    RingPin::PullUp(pin) => {
+                        channel.input_pin(pin).toggle().enable_interrupt();
+                    }
+                    RingPin::PullDown(pin) => {
+                        channel.input_pin(pin).toggle().enable_interrupt();
+                    }
+                }
+                channel.reset_events();
+            }
+            // The rings start out inactive, whatever they are now. If one of
+            // them already is active, the first tick says so.
+            driver.rings = rings.map(Some);
+            if !driver.ticking {
+                driver.timer.start(TICK_MS * 1_000);
+                driver.ticking = true;
+            }
+        })
+        .expect("init_buttons has to come before init_rings");
+}
+
+#[cfg(feature = "buttons-interrupt")]
+weak_interrupt!(GPIOTE => on_gpiote_interrupt);
+#[cfg(feature = "buttons-interrupt")]
+weak_interrupt!(TIMER2 => on_timer2_interrupt);
+
+/// Start the timer looking at the buttons, what the `GPIOTE` handler does.
+pub fn on_gpiote_interrupt() {
+    DRIVER.with(|driver| {
+        // The rings' channels, if any, all wake the timer just the same
+        driver.gpiote.reset_events();
+        if !driver.ticking {
+            // The timer ticks at 1 MHz
+            driver.timer.start(TICK_MS * 1_000);
+            driver.ticking = true;
+        }
+    });
+}
+
+/// Look at the buttons once more, what the `TIMER2` handler does.
+pub fn on_timer2_interrupt() {
+    DRIVER.with(|driver| {
+        // Clears the event, so that the interrupt doesn't fire again straight
+        // away
+        driver.timer.wait().ok();
+
+        if let Some(event) = driver.a.tick() {
+            driver.events.send((Button::A, event)).ok();
+        }
+        if let Some(event) = driver.b.tick() {
+            driver.events.send((Button::B, event)).ok();
+        }
+        for (ring, button) in driver.rings.iter_mut().zip(RINGS) {
+            if let Some(event) = ring.as_mut().and_then(Ring::tick) {
+                driver.events.send((button, event)).ok();
+            }
+        }
+
+        let rings_settled = driver
+            .rings
+            .iter()
+            .flatten()
+            .all(|ring| ring.debouncer.is_settled());
+        if driver.a.is_idle() && driver.b.is_idle() && rings_settled {
+            driver.timer.cancel().ok();
+            driver.ticking = false;
+        }
+    });
+}
@@ -0,0 +1,138 @@
+//! Passing events from an interrupt handler to the main loop.
+//!
+//! An interrupt handler that notices something, a button press, a received
+//! byte, new data from a sensor, usually can't deal with it right away. It
+//! puts it in a queue instead, and the main loop takes it out when it gets
+//! around to it. With a `Mutex<RefCell<Queue>>` both sides have to enter a
+//! critical section each time, and every chapter ends up writing the same few
+//! lines again.
+//!
+//! A [`Channel`] is a `heapless::spsc::Queue` in a `static`, which is
+//! [`split`](Channel::split) once into a [`Sender`] for the interrupt handler
+//! and a [`Receiver`] for the main loop. With exactly one of each, neither
+//! side needs a critical section: the sender only ever moves the end of the
+//! queue, the receiver only its start.
+//!
+//! Sending also executes `sev`, which sets the event that `wfe` waits for. So
+//! a main loop that found the queue empty and went to sleep with
+//! [`idle::sleep`](crate::idle::sleep) or [`Receiver::wait`] doesn't miss an
+//! event that was sent just before it got there.
+
+use core::cell::UnsafeCell;
+use core::sync::atomic::{AtomicBool, Ordering};
+use cortex_m::{asm, interrupt};
+use heapless::spsc::{Consumer, Producer, Queue};
+
+/// A queue with room for `N` - 1 events of type `T`.
+pub struct Channel<T, const N: usize> {
+    queue: UnsafeCell<Queue<T, N>>,
+    split: AtomicBool,
+}
+
+// Nothing gets at the queue but the two ends, and there's only one of each
+unsafe impl<T: Send, const N: usize> Sync for Channel<T, N> {}
+
+impl<T, const N: usize> Channel<T, N> {
+    pub const fn new() -> Channel<T, N> {
+        Channel {
+            queue: UnsafeCell::new(Queue::new()),
+            split: AtomicBool::new(false),
+        }
+    }
+
+    /// The two ends of the channel, or `None` if they have been handed out
+    /// already.
+    pub fn split(&'static self) -> Option<(Sender<T, N>, Receiver<T, N>)> {
+        // The nRF51 can't swap atomically, so the check and the store are
+        // kept together by a critical section instead
+        let split_before = interrupt::free(|_| {
+            let split_before = self.split.load(Ordering::Relaxed);
+            self.split.store(true, Ordering::Relaxed);
+            split_before
+        });
+        if split_before {
+            return None;
+        }
+        let (producer, consumer) = unsafe { &mut *self.queue.get() }.split();
+        Some((Sender { producer }, Receiver { consumer }))
+    }
+}
+
+impl<T, const N: usize> Default for Channel<T, N> {
+    fn default() -> Self {
+        Channel::new()
+    }
+}
+
+/// The end of a [`Channel`] that goes to the interrupt handler.
+pub struct Sender<T: 'static, const N: usize> {
+    producer: Producer<'static, T, N>,
+}
+
+impl<T, const N: usize> Sender<T, N> {
+    /// Put `value` at the end of the queue, and wake up the main loop. If the
+    /// queue is full, `value` is handed back instead.
+    pub fn send(&mut self, value: T) -> Result<(), T> {
+        self.producer.enqueue(value)?;
+        asm::sev();
+        Ok(())
+    }
+
+    /// Whether there's room for another event.
+    pub fn is_ready(&self) -> bool {
+        self.producer.ready()
+    }
+
+    /// How many events are waiting for the receiver.
+    pub fn len(&self) -> usize {
+        self.producer.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.producer.len() == 0
+    }
+
+    /// How many events fit into the queue.
+    pub fn capacity(&self) -> usize {
+        self.producer.capacity()
+    }
+}
+
+/// The end of a [`Channel`] that goes to the main loop.
+pub struct Receiver<T: 'static, const N: usize> {
+    consumer: Consumer<'static, T, N>,
+}
+
+impl<T, const N: usize> Receiver<T, N> {
+    /// The oldest event in the queue, if there is one.
+    pub fn recv(&mut self) -> Option<T> {
+        self.consumer.dequeue()
+    }
+
+    /// Sleep until there is an event, and return it.
+    ///
+    /// Any other interrupt wakes the CPU as well, this just goes back to sleep
+    /// until the event is there.
+    pub fn wait(&mut self) -> T {
+        loop {
+            if let Some(value) = self.recv() {
+                return value;
+            }
+            asm::wfe();
+        }
+    }
+
+    /// How many events are waiting.
+    pub fn len(&self) -> usize {
+        self.consumer.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        !self.consumer.ready()
+    }
+
+    /// How many events fit into the queue.
+    pub fn capacity(&self) -> usize {
+        self.consumer.capacity()
+    }
+}
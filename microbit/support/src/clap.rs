@@ -0,0 +1,120 @@
+//! Hearing claps with the [`microphone`](crate::microphone).
+//!
+//! A clap is short and much louder than anything around it. So for every
+//! buffer of samples we look at its *envelope*: how far the loudest sample
+//! gets from the average. A clap is when that goes over a threshold.
+//!
+//! The sound of a clap bounces around the room for a little while, and
+//! without care every echo would count as another clap. After a clap, the
+//! envelope has to drop back under the threshold, and [`DEBOUNCE_MS`] have to
+//! pass, before the next one counts.
+
+use crate::microphone::{BUFFER_LEN, SAMPLE_RATE};
+
+/// How long a buffer of samples lasts.
+const BUFFER_MS: u32 = BUFFER_LEN as u32 * 1000 / SAMPLE_RATE;
+/// The shortest time between two claps.
+pub const DEBOUNCE_MS: u32 = 150;
+/// Two claps closer together than this make a double clap.
+pub const DOUBLE_CLAP_MS: u32 = 600;
+/// Where to start. Every room is different, so it's worth tuning.
+pub const DEFAULT_THRESHOLD: u16 = 1000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClapEvent {
+    Clap,
+    /// A clap right after another one. The first one has already been a
+    /// [`ClapEvent::Clap`].
+    DoubleClap,
+}
+
+pub struct ClapDetector {
+    threshold: u16,
+    /// Whether the envelope has been under the threshold since the last clap.
+    quiet: bool,
+    /// How much longer the last clap keeps the next one from counting.
+    debounce_ms: u32,
+    /// How long ago the last clap was, while it can still become a double
+    /// clap.
+    since_clap_ms: Option<u32>,
+    /// The loudest envelope since `take_loudest` was last called.
+    loudest: u16,
+}
+
+impl ClapDetector {
+    pub fn new(threshold: u16) -> ClapDetector {
+        ClapDetector {
+            threshold,
+            quiet: true,
+            debounce_ms: 0,
+            since_clap_ms: None,
+            loudest: 0,
+        }
+    }
+
+    pub fn threshold(&self) -> u16 {
+        self.threshold
+    }
+
+    pub fn set_threshold(&mut self, threshold: u16) {
+        self.threshold = threshold;
+    }
+
+    /// The loudest envelope seen since the last call, to help pick a
+    /// threshold.
+    pub fn take_loudest(&mut self) -> u16 {
+        core::mem::take(&mut self.loudest)
+    }
+
+    /// Look at the next buffer from
+    /// [`Microphone::record`](crate::microphone::Microphone::record), and
+    /// tell if it has a clap in it.
+    pub fn update(&mut self, samples: &[i16]) -> Option<ClapEvent> {
+        let level = envelope(samples);
+        self.loudest = self.loudest.max(level);
+
+        self.debounce_ms = self.debounce_ms.saturating_sub(BUFFER_MS);
+        self.since_clap_ms = self
+            .since_clap_ms
+            .map(|ms| ms + BUFFER_MS)
+            .filter(|&ms| ms <= DOUBLE_CLAP_MS);
+
+        if level <= self.threshold {
+            self.quiet = true;
+            return None;
+        }
+        if !self.quiet || self.debounce_ms > 0 {
+            return None;
+        }
+
+        self.quiet = false;
+        self.debounce_ms = DEBOUNCE_MS;
+        match self.since_clap_ms.take() {
+            Some(_) => Some(ClapEvent::DoubleClap),
+            None => {
+                self.since_clap_ms = Some(0);
+                Some(ClapEvent::Clap)
+            }
+        }
+    }
+}
+
+impl Default for ClapDetector {
+    fn default() -> ClapDetector {
+        ClapDetector::new(DEFAULT_THRESHOLD)
+    }
+}
+
+/// How far the loudest of `samples` is from their average.
+fn envelope(samples: &[i16]) -> u16 {
+    if samples.is_empty() {
+        return 0;
+    }
+    let sum: i32 = samples.iter().map(|&s| i32::from(s)).sum();
+    let mean = sum / samples.len() as i32;
+    samples
+        .iter()
+        .map(|&s| (i32::from(s) - mean).unsigned_abs() as u16)
+        .max()
+        .unwrap_or(0)
+}
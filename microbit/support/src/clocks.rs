@@ -0,0 +1,38 @@
+//! Starting the clocks from the crystal.
+//!
+//! Out of reset, the nRF runs from two oscillators inside the chip: a 16 MHz
+//! and a 32.768 kHz one, both made of a resistor and a capacitor. They start
+//! instantly and need little power, but they are only accurate to a few
+//! percent, and drift with the temperature. That's enough for blinking LEDs,
+//! but a UART that is 2% off is about to garble bytes, and the radio doesn't
+//! work at all: it has to hit a channel only 1 MHz wide.
+//!
+//! The micro:bit has a crystal for the 16 MHz clock, accurate to 50 ppm or
+//! better, but none for the 32.768 kHz one. [`init_clocks`] starts the crystal,
+//! then has the chip divide the 32.768 kHz clock down from it, so that the
+//! RTCs count just as accurately.
+//!
+//! The `CLOCK` peripheral is consumed, and the HAL's `Clocks` it comes back as
+//! says in its type which clocks are running. The helpers that need them,
+//! like [`Radio::new`](crate::radio::Radio::new) for the crystal, and
+//! [`init_monotonic`](crate::monotonic::init_monotonic) for the low frequency
+//! clock, take it as proof.
+//!
+//! The crystal takes around 250 µA while it runs. Programs that only sleep
+//! with [`LowPowerDelay`](crate::delay::LowPowerDelay) are better off with the
+//! internal oscillator, and just call `Clocks::start_lfclk`.
+
+use microbit::hal::clocks::{self, ExternalOscillator, LfOscStarted, LfOscSynthesized};
+use microbit::pac::CLOCK;
+
+/// The clocks once [`init_clocks`] has started them.
+pub type Clocks = clocks::Clocks<ExternalOscillator, LfOscSynthesized, LfOscStarted>;
+
+/// Start the crystal, and the low frequency clock divided down from it. Both
+/// are running once this returns, which takes about half a millisecond.
+pub fn init_clocks(clock: CLOCK) -> Clocks {
+    clocks::Clocks::new(clock)
+        .enable_ext_hfosc()
+        .set_lfclk_src_synth()
+        .start_lfclk()
+}
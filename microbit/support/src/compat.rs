@@ -0,0 +1,181 @@
+//! `embedded-hal` 1.0 for the HAL's peripherals.
+//!
+//! Drivers written these days, and the helpers in this crate that take a bus
+//! or a serial port, use the traits of `embedded-hal` 1.0 and
+//! `embedded-hal-nb`. The HALs behind the `microbit` crates still implement
+//! `embedded-hal` 0.2 though, so their `Twim`, `Timer` and pins don't fit.
+//! Wrapping one in a [`Compat`] gives it the 1.0 traits on top, which do the
+//! same by calling the 0.2 ones:
+//!
+//! - pins get `OutputPin` and `InputPin`,
+//! - timers get `DelayNs`,
+//! - `Twi` and `Twim` get `I2c`,
+//! - `Uart` gets the serial `Read` and `Write` of `embedded-hal-nb`.
+//!
+//! Timers counting down and the ADC have nothing to switch to, 1.0 left
+//! their traits out. Those stay on 0.2.
+
+use core::fmt::Debug;
+use embedded_hal::delay::DelayNs;
+use embedded_hal::{digital, i2c};
+use embedded_hal_02::blocking::delay::DelayUs;
+use embedded_hal_02::blocking::i2c::{Read, Write, WriteRead};
+use embedded_hal_02::digital::v2::{InputPin, OutputPin};
+use embedded_hal_02::serial;
+use embedded_hal_nb::serial as serial_nb;
+
+/// A peripheral of the HAL, with the traits of `embedded-hal` 1.0.
+pub struct Compat<T> {
+    inner: T,
+}
+
+impl<T> Compat<T> {
+    pub fn new(inner: T) -> Compat<T> {
+        Compat { inner }
+    }
+
+    /// The peripheral itself, for what only the HAL has a method for.
+    pub fn inner(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+/// An error of an `embedded-hal` 0.2 peripheral. 1.0 wants to know what kind
+/// of error it is, and 0.2 doesn't say, so it's always some other kind.
+#[derive(Debug)]
+pub struct CompatError<E>(pub E);
+
+impl<E: Debug> i2c::Error for CompatError<E> {
+    fn kind(&self) -> i2c::ErrorKind {
+        i2c::ErrorKind::Other
+    }
+}
+
+// A pin can be both an input and an output, and would need to get its error
+// type from both. The HAL's pins can't fail anyway.
+impl<T> digital::ErrorType for Compat<T> {
+    type Error = digital::ErrorKind;
+}
+
+impl<T: OutputPin> digital::OutputPin for Compat<T> {
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.inner.set_low().map_err(|_| digital::ErrorKind::Other)
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.inner.set_high().map_err(|_| digital::ErrorKind::Other)
+    }
+}
+
+impl<T: InputPin> digital::InputPin for Compat<T> {
+    fn is_high(&mut self) -> Result<bool, Self::Error> {
+        self.inner.is_high().map_err(|_| digital::ErrorKind::Other)
+    }
+
+    fn is_low(&mut self) -> Result<bool, Self::Error> {
+        self.inner.is_low().map_err(|_| digital::ErrorKind::Other)
+    }
+}
+
+impl<T: DelayUs<u32>> DelayNs for Compat<T> {
+    /// Rounded up to whole microseconds, the shortest delay 0.2 knows.
+    fn delay_ns(&mut self, ns: u32) {
+        self.inner.delay_us(ns.div_ceil(1_000));
+    }
+
+    fn delay_us(&mut self, us: u32) {
+        self.inner.delay_us(us);
+    }
+
+    fn delay_ms(&mut self, ms: u32) {
+        // A second at a time, so that the microseconds don't overflow
+        for _ in 0..ms / 1_000 {
+            self.inner.delay_us(1_000_000);
+        }
+        self.inner.delay_us(ms % 1_000 * 1_000);
+    }
+}
+
+impl<T: Write> i2c::ErrorType for Compat<T>
+where
+    T::Error: Debug,
+{
+    type Error = CompatError<T::Error>;
+}
+
+impl<T, E> i2c::I2c for Compat<T>
+where
+    T: Read<Error = E> + Write<Error = E> + WriteRead<Error = E>,
+    E: Debug,
+{
+    fn read(&mut self, address: u8, read: &mut [u8]) -> Result<(), Self::Error> {
+        self.inner.read(address, read).map_err(CompatError)
+    }
+
+    fn write(&mut self, address: u8, write: &[u8]) -> Result<(), Self::Error> {
+        self.inner.write(address, write).map_err(CompatError)
+    }
+
+    fn write_read(
+        &mut self,
+        address: u8,
+        write: &[u8],
+        read: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        self.inner
+            .write_read(address, write, read)
+            .map_err(CompatError)
+    }
+
+    /// 0.2 can only do a write followed by a read in one go. Any other
+    /// sequence of operations is done one operation at a time, with a stop
+    /// after each, which most devices don't mind.
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [i2c::Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        if let [i2c::Operation::Write(write), i2c::Operation::Read(read)] = operations {
+            return self.write_read(address, write, read);
+        }
+        for operation in operations {
+            match operation {
+                i2c::Operation::Read(read) => self.read(address, read)?,
+                i2c::Operation::Write(write) => self.write(address, write)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+// Same as with the pins: a port both reads and writes, and the v1's `Uart`
+// has a different error type for each. It can't fail anyway.
+impl<T> serial_nb::ErrorType for Compat<T> {
+    type Error = serial_nb::ErrorKind;
+}
+
+impl<T: serial::Write<u8>> serial_nb::Write for Compat<T> {
+    fn write(&mut self, byte: u8) -> nb::Result<(), Self::Error> {
+        self.inner
+            .write(byte)
+            .map_err(|error| error.map(|_| serial_nb::ErrorKind::Other))
+    }
+
+    fn flush(&mut self) -> nb::Result<(), Self::Error> {
+        self.inner
+            .flush()
+            .map_err(|error| error.map(|_| serial_nb::ErrorKind::Other))
+    }
+}
+
+impl<T: serial::Read<u8>> serial_nb::Read for Compat<T> {
+    fn read(&mut self) -> nb::Result<u8, Self::Error> {
+        self.inner
+            .read()
+            .map_err(|error| error.map(|_| serial_nb::ErrorKind::Other))
+    }
+}
@@ -0,0 +1,197 @@
+//! Remembering why the program crashed, for after the reset.
+//!
+//! With a debugger attached, `panic-rtt-target` prints the panic message, and
+//! that's the end of it. A micro:bit running from a battery just stops, and
+//! nobody ever finds out why. So instead, the program's own panic handler
+//! calls [`record_panic`], which writes the message and where it came from
+//! into a page of flash, and resets the chip. [`record_fault`] does the same
+//! for a HardFault, which is what the CPU raises when it can't go on, say on
+//! a read from an address where there's nothing to read.
+//!
+//! After the reset, [`take`] finds the crash in the flash, hands it back, and
+//! erases the page again, so that every crash is only reported once. The
+//! program can print it and show [`GLYPH`] on the display before it starts
+//! over, and a crash that happened out of sight at least leaves a trace.
+//!
+//! Writing to the flash in the middle of a panic is only safe because nothing
+//! else is running anymore: interrupts are switched off first, and the
+//! `NVMC` is taken from whoever had it, since they are never getting it back.
+//!
+//! The crash is kept in the page right below the ones of the
+//! [`flash_log`](crate::flash_log), which `memory.x` leaves out of the
+//! program's way, see [`flash_layout`].
+
+use crate::flash::{self, ERASED};
+use crate::flash_layout;
+use core::convert::TryInto;
+use core::fmt::{self, Write};
+use core::panic::PanicInfo;
+use cortex_m::interrupt;
+use microbit::pac::{self, SCB};
+
+/// How much of the panic message is kept, in bytes.
+pub const MAX_MESSAGE_LEN: usize = 128;
+
+/// A sad face, to show after a crash.
+pub const GLYPH: [[u8; 5]; 5] = [
+    [0, 0, 0, 0, 0],
+    [0, 1, 0, 1, 0],
+    [0, 0, 0, 0, 0],
+    [0, 1, 1, 1, 0],
+    [1, 0, 0, 0, 1],
+];
+
+const PAGE_ADDRESS: usize = flash_layout::v2::CRASH_LOG_ADDRESS;
+/// The first word of a page that holds a crash, "Crs1".
+const MAGIC: u32 = 0x3173_7243;
+
+/// The magic word, the kind of crash, the address, the length of the message,
+/// the message, and a checksum of all that.
+const HEADER_WORDS: usize = 4;
+const MESSAGE_WORDS: usize = MAX_MESSAGE_LEN / 4;
+const RECORD_WORDS: usize = HEADER_WORDS + MESSAGE_WORDS + 1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    Panic,
+    HardFault,
+}
+
+/// What [`take`] found from before the reset.
+pub struct Crash {
+    pub kind: Kind,
+    /// For a HardFault, the instruction the CPU got stuck on. For a panic,
+    /// where the panic handler was called from, which is somewhere in `core`.
+    /// The message says more about where the panic came from.
+    pub pc: u32,
+    message: [u8; MAX_MESSAGE_LEN],
+    len: usize,
+}
+
+impl Crash {
+    /// The panic message, with the file and line it came from. A message that
+    /// was too long is cut off.
+    pub fn message(&self) -> &str {
+        let message = &self.message[..self.len];
+        match core::str::from_utf8(message) {
+            Ok(text) => text,
+            // Cut off in the middle of a character
+            Err(error) => core::str::from_utf8(&message[..error.valid_up_to()]).unwrap(),
+        }
+    }
+}
+
+impl fmt::Display for Crash {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            Kind::Panic => write!(f, "{} (pc {:#010x})", self.message(), self.pc),
+            Kind::HardFault => write!(f, "HardFault (pc {:#010x})", self.pc),
+        }
+    }
+}
+
+/// The crash from before the last reset, if there was one.
+///
+/// The page is erased once it has been read, so the next reset doesn't report
+/// the same crash again. `Board::take` doesn't hand out the `NVMC`, so we go
+/// behind its back, just like `Board::take` itself.
+pub fn take() -> Option<Crash> {
+    let mut record = [0; RECORD_WORDS];
+    for (offset, word) in record.iter_mut().enumerate() {
+        *word = flash::read_word(PAGE_ADDRESS + offset * 4);
+    }
+    if record[0] == ERASED {
+        return None;
+    }
+    // Erased whether it's a crash or not, anything else is garbage
+    flash::erase_page(&unsafe { pac::Peripherals::steal() }.NVMC, PAGE_ADDRESS);
+
+    let (data, sum) = record.split_at(RECORD_WORDS - 1);
+    if data[0] != MAGIC || sum[0] != checksum(data) {
+        return None;
+    }
+    let kind = match data[1] {
+        0 => Kind::Panic,
+        _ => Kind::HardFault,
+    };
+    let len = (data[3] as usize).min(MAX_MESSAGE_LEN);
+    let mut message = [0; MAX_MESSAGE_LEN];
+    for (bytes, word) in message.chunks_mut(4).zip(&data[HEADER_WORDS..]) {
+        bytes.copy_from_slice(&word.to_le_bytes());
+    }
+    Some(Crash {
+        kind,
+        pc: data[2],
+        message,
+        len,
+    })
+}
+
+/// Write `info` to the flash and reset. Meant to be called from the program's
+/// own `#[panic_handler]`, in place of a crate like `panic-rtt-target`.
+#[inline(always)]
+pub fn record_panic(info: &PanicInfo) -> ! {
+    interrupt::disable();
+    let pc = cortex_m::register::lr::read();
+    let mut message = Message {
+        bytes: [0; MAX_MESSAGE_LEN],
+        len: 0,
+    };
+    write!(message, "{}", info).ok();
+    store(Kind::Panic, pc, &message)
+}
+
+/// Write `pc`, the address the CPU got stuck on, to the flash and reset.
+/// Meant to be called from the program's own `HardFault` handler, with the
+/// `pc()` of the `ExceptionFrame` it gets.
+pub fn record_fault(pc: u32) -> ! {
+    interrupt::disable();
+    let message = Message {
+        bytes: [0; MAX_MESSAGE_LEN],
+        len: 0,
+    };
+    store(Kind::HardFault, pc, &message)
+}
+
+fn store(kind: Kind, pc: u32, message: &Message) -> ! {
+    let mut record = [0; RECORD_WORDS];
+    record[..HEADER_WORDS].copy_from_slice(&[MAGIC, kind as u32, pc, message.len as u32]);
+    for (word, bytes) in record[HEADER_WORDS..]
+        .iter_mut()
+        .zip(message.bytes.chunks(4))
+    {
+        *word = u32::from_le_bytes(bytes.try_into().unwrap());
+    }
+    record[RECORD_WORDS - 1] = checksum(&record[..RECORD_WORDS - 1]);
+
+    let nvmc = unsafe { pac::Peripherals::steal() }.NVMC;
+    flash::erase_page(&nvmc, PAGE_ADDRESS);
+    flash::write_words(&nvmc, PAGE_ADDRESS, &record);
+    SCB::sys_reset()
+}
+
+/// Collects as much of the panic message as fits.
+struct Message {
+    bytes: [u8; MAX_MESSAGE_LEN],
+    len: usize,
+}
+
+impl Write for Message {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let count = s.len().min(MAX_MESSAGE_LEN - self.len);
+        self.bytes[self.len..][..count].copy_from_slice(&s.as_bytes()[..count]);
+        self.len += count;
+        Ok(())
+    }
+}
+
+fn checksum(words: &[u32]) -> u32 {
+    let sum = words.iter().fold(0x4372_7368, |sum: u32, &word| {
+        (sum.rotate_left(5) ^ word).wrapping_mul(0x0100_0193)
+    });
+    if sum == ERASED {
+        0
+    } else {
+        sum
+    }
+}
@@ -0,0 +1,227 @@
+//! Delays that let the CPU sleep.
+//!
+//! `Timer::delay_ms` keeps the CPU spinning in a loop, asking the timer over
+//! and over whether it's done yet. On USB power nobody notices, but on
+//! batteries a CPU that runs flat out doing nothing drains them for no
+//! reason. The timers themselves aren't cheap either: they run from the
+//! 16 MHz clock.
+//!
+//! [`LowPowerDelay`] uses an `RTC` (real time counter) instead, which counts
+//! the 32.768 kHz low frequency clock and needs next to no power. The CPU
+//! sets a compare register to the time the delay is up, and then waits for
+//! an event with `wfe`, which stops it until something happens. Any
+//! interrupt wakes it early, such as the one refreshing the display, so it
+//! checks whether the delay is over and goes back to sleep if not.
+//!
+//! With a tick of about 30.5 µs, delays are rounded up to whole ticks.
+//!
+//! For waits of a few micro- or milliseconds, like the ones sensor drivers
+//! need between a command and its result, sleeping isn't worth the trouble.
+//! [`SysTickDelay`] spins on the Cortex-M's own `SysTick` timer instead, which
+//! counts the CPU's clock cycles. Every Cortex-M has exactly one, and nothing
+//! else in this book uses it, so the `TIMER`s stay free for the display and
+//! everything else that needs one.
+
+use cortex_m::asm;
+use cortex_m::peripheral::syst::SystClkSource;
+use cortex_m::peripheral::{NVIC, SCB, SYST};
+use embedded_hal::delay::DelayNs;
+use embedded_hal_02::blocking::delay::{DelayMs, DelayUs};
+use microbit::hal::clocks::{Clocks, LfOscStarted};
+use microbit::hal::rtc::{self, Rtc, RtcCompareReg, RtcInterrupt};
+
+const TICKS_PER_SECOND: u64 = 32_768;
+/// The counter has 24 bits.
+const COUNTER_MASK: u32 = (1 << 24) - 1;
+/// Longer delays are split up, so that the compare value is never further
+/// ahead than the counter can count.
+const MAX_TICKS: u32 = 1 << 23;
+/// A compare value only 1 tick ahead of the counter might be missed.
+const MIN_TICKS: u32 = 2;
+
+pub struct LowPowerDelay<R: rtc::Instance> {
+    rtc: Rtc<R>,
+}
+
+impl<R: rtc::Instance> LowPowerDelay<R> {
+    /// Take over `rtc`. It counts the low frequency clock, so that has to be
+    /// started first, which `_clocks` proves.
+    ///
+    /// The RTC's interrupt stays masked: we only need it to send the event
+    /// that wakes up `wfe`, which `SEVONPEND` in `scb` makes it do.
+    pub fn new<H, L>(rtc: R, _clocks: &Clocks<H, L, LfOscStarted>, scb: &mut SCB) -> Self {
+        // No prescaler, so the RTC ticks at the full 32.768 kHz
+        let mut rtc = Rtc::new(rtc, 0).unwrap();
+        rtc.enable_interrupt(RtcInterrupt::Compare0, None);
+        rtc.enable_counter();
+        scb.set_sevonpend();
+        LowPowerDelay { rtc }
+    }
+
+    /// Sleep for `ticks` of the RTC.
+    fn sleep(&mut self, mut ticks: u32) {
+        while ticks > 0 {
+            let chunk = ticks.min(MAX_TICKS);
+            let target = (self.rtc.get_counter() + chunk.max(MIN_TICKS)) & COUNTER_MASK;
+            self.rtc.reset_event(RtcInterrupt::Compare0);
+            self.rtc
+                .set_compare(RtcCompareReg::Compare0, target)
+                .unwrap();
+
+            while !self.rtc.is_event_triggered(RtcInterrupt::Compare0) {
+                asm::wfe();
+            }
+            self.rtc.reset_event(RtcInterrupt::Compare0);
+            // An interrupt that is already pending can't send another event,
+            // so the next delay would never wake up
+            NVIC::unpend(R::INTERRUPT);
+
+            ticks -= chunk;
+        }
+    }
+}
+
+/// How many ticks of the RTC there are in `amount` of `per_second`, rounded
+/// up.
+fn ticks(amount: u32, per_second: u64) -> u32 {
+    let ticks = (u64::from(amount) * TICKS_PER_SECOND).div_ceil(per_second);
+    ticks.min(u64::from(u32::MAX)) as u32
+}
+
+impl<R: rtc::Instance> DelayMs<u32> for LowPowerDelay<R> {
+    fn delay_ms(&mut self, ms: u32) {
+        self.sleep(ticks(ms, 1_000));
+    }
+}
+
+impl<R: rtc::Instance> DelayMs<u16> for LowPowerDelay<R> {
+    fn delay_ms(&mut self, ms: u16) {
+        DelayMs::delay_ms(self, u32::from(ms));
+    }
+}
+
+impl<R: rtc::Instance> DelayMs<u8> for LowPowerDelay<R> {
+    fn delay_ms(&mut self, ms: u8) {
+        DelayMs::delay_ms(self, u32::from(ms));
+    }
+}
+
+impl<R: rtc::Instance> DelayUs<u32> for LowPowerDelay<R> {
+    fn delay_us(&mut self, us: u32) {
+        self.sleep(ticks(us, 1_000_000));
+    }
+}
+
+// For drivers on `embedded-hal` 1.0
+impl<R: rtc::Instance> DelayNs for LowPowerDelay<R> {
+    fn delay_ns(&mut self, ns: u32) {
+        self.sleep(ticks(ns, 1_000_000_000));
+    }
+
+    fn delay_us(&mut self, us: u32) {
+        self.sleep(ticks(us, 1_000_000));
+    }
+
+    fn delay_ms(&mut self, ms: u32) {
+        self.sleep(ticks(ms, 1_000));
+    }
+}
+
+/// How fast the CPU runs, and so `SysTick` counts.
+#[cfg(feature = "v2")]
+pub const CPU_HZ: u32 = 64_000_000;
+#[cfg(feature = "v1")]
+pub const CPU_HZ: u32 = 16_000_000;
+
+/// `SysTick` counts down from a 24 bit reload value.
+const MAX_CYCLES: u64 = 1 << 24;
+
+/// Busy waits, counted by `SysTick`.
+pub struct SysTickDelay {
+    syst: SYST,
+}
+
+impl SysTickDelay {
+    pub fn new(mut syst: SYST) -> SysTickDelay {
+        syst.set_clock_source(SystClkSource::Core);
+        syst.disable_interrupt();
+        SysTickDelay { syst }
+    }
+
+    /// Hand `SysTick` back.
+    pub fn free(self) -> SYST {
+        self.syst
+    }
+
+    /// Spin for `cycles` of the CPU's clock.
+    fn spin(&mut self, mut cycles: u64) {
+        while cycles > 0 {
+            let chunk = cycles.min(MAX_CYCLES);
+            // The counter goes from the reload value down to 0, and a reload
+            // value of 0 would stop it
+            self.syst.set_reload((chunk as u32 - 1).max(1));
+            self.syst.clear_current();
+            self.syst.enable_counter();
+            while !self.syst.has_wrapped() {}
+            self.syst.disable_counter();
+            cycles -= chunk;
+        }
+    }
+}
+
+/// How many cycles of the CPU there are in `amount` of `per_second`, rounded
+/// up.
+fn cycles(amount: u32, per_second: u64) -> u64 {
+    (u64::from(amount) * u64::from(CPU_HZ)).div_ceil(per_second)
+}
+
+impl DelayMs<u32> for SysTickDelay {
+    fn delay_ms(&mut self, ms: u32) {
+        self.spin(cycles(ms, 1_000));
+    }
+}
+
+impl DelayMs<u16> for SysTickDelay {
+    fn delay_ms(&mut self, ms: u16) {
+        DelayMs::delay_ms(self, u32::from(ms));
+    }
+}
+
+impl DelayMs<u8> for SysTickDelay {
+    fn delay_ms(&mut self, ms: u8) {
+        DelayMs::delay_ms(self, u32::from(ms));
+    }
+}
+
+impl DelayUs<u32> for SysTickDelay {
+    fn delay_us(&mut self, us: u32) {
+        self.spin(cycles(us, 1_000_000));
+    }
+}
+
+impl DelayUs<u16> for SysTickDelay {
+    fn delay_us(&mut self, us: u16) {
+        DelayUs::delay_us(self, u32::from(us));
+    }
+}
+
+impl DelayUs<u8> for SysTickDelay {
+    fn delay_us(&mut self, us: u8) {
+        DelayUs::delay_us(self, u32::from(us));
+    }
+}
+
+// For drivers on `embedded-hal` 1.0
+impl DelayNs for SysTickDelay {
+    fn delay_ns(&mut self, ns: u32) {
+        self.spin(cycles(ns, 1_000_000_000));
+    }
+
+    fn delay_us(&mut self, us: u32) {
+        self.spin(cycles(us, 1_000_000));
+    }
+
+    fn delay_ms(&mut self, ms: u32) {
+        self.spin(cycles(ms, 1_000));
+    }
+}
@@ -0,0 +1,32 @@
+//! What tells one micro:bit apart from all the others.
+//!
+//! Nordic writes a few things about every chip into its `FICR`, the factory
+//! information configuration registers, before it leaves the factory. Two of
+//! them are random numbers, picked anew for every chip:
+//!
+//! - `DEVICEID`, 64 bits meant to identify the chip.
+//! - `DEVICEADDR`, 48 bits meant to be its Bluetooth address.
+//!
+//! Being random, they aren't *guaranteed* to be different on two chips, but
+//! with 64 bits the odds of two micro:bits on the same desk sharing an ID are
+//! about as good as winning the lottery twice in a row.
+
+use microbit::pac::FICR;
+
+/// How many bytes [`device_address`] has.
+pub const ADDRESS_LEN: usize = 6;
+
+/// The 64 bit ID of this chip.
+pub fn device_id(ficr: &FICR) -> u64 {
+    let low = ficr.deviceid[0].read().bits();
+    let high = ficr.deviceid[1].read().bits();
+    u64::from(high) << 32 | u64::from(low)
+}
+
+/// The 48 bit address of this chip, lowest byte first, the way Bluetooth
+/// sends it.
+pub fn device_address(ficr: &FICR) -> [u8; ADDRESS_LEN] {
+    let low = ficr.deviceaddr[0].read().bits().to_le_bytes();
+    let high = ficr.deviceaddr[1].read().bits().to_le_bytes();
+    [low[0], low[1], low[2], low[3], high[0], high[1]]
+}
@@ -0,0 +1,404 @@
+//! Interrupt driven 5x5 LED matrix.
+//!
+//! The blocking `Display::show` keeps the CPU busy for the whole time a frame
+//! is on screen. Here the matrix is refreshed from the `TIMER1` interrupt
+//! instead, so `main` only has to hand over a new frame whenever the picture
+//! should change and is free to do other work in the meantime.
+//!
+//! Because the driver gets to switch the LEDs many times per frame it can also
+//! dim them: every LED takes a brightness between 0 (off) and
+//! [`MAX_BRIGHTNESS`], using the same timings as MicroPython.
+//!
+//! Drawing straight into an array that is being shown can put half finished
+//! pictures on screen. Draw into a [`FrameBuffer`] instead: nothing drawn into
+//! it becomes visible until [`FrameBuffer::commit`] swaps the whole frame in
+//! at once.
+//!
+//! On the micro:bit v2 the LEDs can also be read backwards: [`light_level`]
+//! uses them as tiny photodiodes to measure how bright the room is.
+//!
+//! The matrix lights one of its rows at a time, for 6 ms each, which makes
+//! [`DEFAULT_REFRESH_HZ`] refreshes a second. [`set_refresh_rate`] makes that
+//! faster, for less flicker on camera, or slower, for fewer interrupts. And
+//! [`set_duty_cycle`] leaves some of the frames dark: that dims the whole
+//! display without losing any of the brightness levels, and the interrupt has
+//! less to do while it's dark.
+//!
+//! [`pause_display`] goes dark for good and stops the timer too, so that
+//! there are no interrupts at all, until [`resume_display`] picks up where it
+//! left off.
+//!
+//! The `TIMER1` handler comes with the `display-interrupt` feature. A program
+//! that needs `TIMER1` for something else leaves the feature out, and one with
+//! a handler of its own calls [`on_interrupt`] from it.
+
+use crate::shared::Shared;
+use crate::softdevice;
+#[cfg(feature = "display-interrupt")]
+use crate::weak_interrupt::weak_interrupt;
+use cortex_m::interrupt::free;
+use microbit::display::nonblocking::{BitImage, Display, GreyscaleImage, MicrobitFrame};
+use microbit::gpio::DisplayPins;
+use microbit::pac::{self, TIMER1};
+use tiny_led_matrix::{Frame, Render};
+
+/// Brightness of a fully lit LED.
+pub const MAX_BRIGHTNESS: u8 = tiny_led_matrix::MAX_BRIGHTNESS;
+
+/// `tiny-led-matrix` lights a row for 375 ticks of the timer, and the
+/// `microbit` crate makes a tick 16 µs, with a prescaler of 8.
+const ROW_TICKS: u32 = 375;
+const DEFAULT_PRESCALER: u32 = 8;
+/// The timers count 16 MHz divided by 2 to the power of the prescaler, which
+/// goes up to 9.
+const TIMER_HZ: u32 = 16_000_000;
+const MAX_PRESCALER: u32 = 9;
+
+/// How often the whole display is refreshed, until [`set_refresh_rate`]
+/// changes it. The micro:bit v2 has 5 rows in its matrix, the v1 only 3, so
+/// that's about 33 Hz on the v2 and 55 Hz on the v1.
+pub const DEFAULT_REFRESH_HZ: u32 = refresh_hz(DEFAULT_PRESCALER);
+
+const fn refresh_hz(prescaler: u32) -> u32 {
+    (TIMER_HZ >> prescaler) / (ROW_TICKS * MicrobitFrame::ROWS as u32)
+}
+
+struct Driver {
+    display: Display<TIMER1>,
+    /// What's on the display while it's lit.
+    frame: MicrobitFrame,
+    /// Lit for `on` out of every `period` frames.
+    on: u8,
+    period: u8,
+    /// How many rows into the period the display is.
+    row: u32,
+    lit: bool,
+    /// Stop the timer at the start of the next row, once the LEDs are off.
+    paused: bool,
+}
+
+impl Driver {
+    fn show(&mut self, frame: &MicrobitFrame) {
+        self.frame = *frame;
+        if self.lit {
+            self.display.show_frame(frame);
+        }
+    }
+
+    /// Move on to the next row, and go dark or light up again when a frame
+    /// begins where the duty cycle says so.
+    fn next_row(&mut self) {
+        let rows = MicrobitFrame::ROWS as u32;
+        self.row = (self.row + 1) % (rows * u32::from(self.period));
+        let lit = self.row < rows * u32::from(self.on);
+        if lit != self.lit {
+            self.set_lit(lit);
+        }
+    }
+
+    fn set_lit(&mut self, lit: bool) {
+        self.lit = lit;
+        if lit {
+            self.display.show_frame(&self.frame);
+        } else {
+            self.display.clear();
+        }
+    }
+}
+
+static DISPLAY: Shared<Driver> = Shared::new();
+
+/// Take ownership of the LED matrix and start refreshing it from `TIMER1`.
+pub fn init_display(board_timer: TIMER1, board_display: DisplayPins) {
+    let driver = Driver {
+        display: Display::new(board_timer, board_display),
+        frame: MicrobitFrame::default(),
+        on: 1,
+        period: 1,
+        row: 0,
+        lit: true,
+        paused: false,
+    };
+
+    DISPLAY.lend(driver);
+    unsafe { softdevice::unmask(pac::Interrupt::TIMER1) }
+}
+
+/// Stop refreshing the LED matrix, and give back what [`init_display`] took,
+/// so that somebody else can set the display up again.
+pub fn free_display() -> Option<(TIMER1, DisplayPins)> {
+    pac::NVIC::mask(pac::Interrupt::TIMER1);
+    DISPLAY.take().map(|driver| driver.display.free())
+}
+
+/// Refresh the display at least `hz` times a second, or as close to that as
+/// it gets, and return how often it really does.
+///
+/// The timer can only be slowed down by powers of two, so the rates that can
+/// be had are [`DEFAULT_REFRESH_HZ`] doubled or halved a few times. Each
+/// refresh takes as many interrupts as before, so twice the rate is twice the
+/// time spent in them. Below about 25 Hz the display starts to flicker.
+pub fn set_refresh_rate(hz: u32) -> u32 {
+    // The slowest rate that is still fast enough
+    let prescaler = (0..=MAX_PRESCALER)
+        .rev()
+        .find(|&prescaler| refresh_hz(prescaler) >= hz)
+        .unwrap_or(0);
+
+    free(|_| {
+        // The timer belongs to the display driver, which can't run while we
+        // are in a critical section. The prescaler can only be changed while
+        // the timer is stopped.
+        let timer = unsafe { &*TIMER1::ptr() };
+        timer.tasks_stop.write(|w| unsafe { w.bits(1) });
+        timer
+            .prescaler
+            .write(|w| unsafe { w.prescaler().bits(prescaler as u8) });
+        timer.tasks_clear.write(|w| unsafe { w.bits(1) });
+        timer.tasks_start.write(|w| unsafe { w.bits(1) });
+    });
+    refresh_hz(prescaler)
+}
+
+/// Only light the display for `on` out of every `period` frames, and leave it
+/// dark for the rest. `on` equal to `period` is always lit, which is how it
+/// starts out.
+///
+/// Every frame that is lit is as bright as ever, so the display is dimmed to
+/// `on / period` of its brightness, on top of the brightness of every LED.
+/// That many frames in a row are lit, then it's dark for the others, so a long
+/// `period` at a low refresh rate flickers visibly.
+pub fn set_duty_cycle(on: u8, period: u8) {
+    assert!(
+        period > 0 && on <= period,
+        "duty cycle of {} in {}",
+        on,
+        period
+    );
+    DISPLAY.with(|driver| {
+        driver.on = on;
+        driver.period = period;
+        // Start over with the first frame of the period, which is lit
+        // unless none of them are
+        driver.row = 0;
+        driver.set_lit(on > 0);
+    });
+}
+
+/// Switch the display off, and stop its timer as well, within a row's time.
+/// Whatever is shown in the meantime is kept for [`resume_display`].
+pub fn pause_display() {
+    DISPLAY.with(|driver| {
+        driver.set_lit(false);
+        driver.paused = true;
+    });
+}
+
+/// Light the display up again after [`pause_display`], with the latest frame
+/// it was given.
+pub fn resume_display() {
+    DISPLAY.with(|driver| {
+        if !driver.paused {
+            return;
+        }
+        driver.paused = false;
+        let lit = driver.row < MicrobitFrame::ROWS as u32 * u32::from(driver.on);
+        driver.set_lit(lit);
+        // In case it hasn't got to stopping yet, starting it again does
+        // no harm
+        let timer = unsafe { &*TIMER1::ptr() };
+        timer.tasks_start.write(|w| unsafe { w.bits(1) });
+    });
+}
+
+/// Display an image.
+pub fn display_image(image: &impl Render) {
+    let mut frame = MicrobitFrame::default();
+    frame.set(image);
+    display_frame(&frame);
+}
+
+/// Display a matrix of on (1) / off (0) LEDs, laid out the same way as the
+/// `leds` arrays passed to the blocking display.
+pub fn display_leds(leds: [[u8; 5]; 5]) {
+    display_image(&BitImage::new(&leds));
+}
+
+/// Display a matrix of LED brightnesses, each between 0 and [`MAX_BRIGHTNESS`].
+pub fn display_greyscale(leds: [[u8; 5]; 5]) {
+    display_image(&GreyscaleImage::new(&leds));
+}
+
+/// Display a frame that has already been prepared with [`MicrobitFrame::set`].
+///
+/// Only the swap itself happens with interrupts disabled, which keeps the
+/// critical section as short as possible.
+pub fn display_frame(frame: &MicrobitFrame) {
+    DISPLAY.with(|driver| {
+        driver.show(frame);
+    });
+}
+
+/// A back buffer for the display.
+///
+/// Changes made to the buffer are invisible until they are committed, at which
+/// point the complete frame replaces whatever was on screen.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FrameBuffer {
+    leds: [[u8; 5]; 5],
+}
+
+impl FrameBuffer {
+    pub const fn new() -> FrameBuffer {
+        FrameBuffer { leds: [[0; 5]; 5] }
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> u8 {
+        self.leds[row][col]
+    }
+
+    /// Set the brightness of a single LED, between 0 and [`MAX_BRIGHTNESS`].
+    pub fn set(&mut self, row: usize, col: usize, brightness: u8) {
+        self.leds[row][col] = brightness;
+    }
+
+    pub fn leds(&self) -> &[[u8; 5]; 5] {
+        &self.leds
+    }
+
+    pub fn leds_mut(&mut self) -> &mut [[u8; 5]; 5] {
+        &mut self.leds
+    }
+
+    /// Turn off every LED in the buffer.
+    pub fn clear(&mut self) {
+        self.leds = [[0; 5]; 5];
+    }
+
+    /// Present the buffer on the display.
+    pub fn commit(&self) {
+        let mut frame = MicrobitFrame::default();
+        frame.set(&GreyscaleImage::new(&self.leds));
+        display_frame(&frame);
+    }
+}
+
+/// Dim every lit LED in `leds` by one brightness level.
+///
+/// Calling this once per animation step leaves a fading trail behind anything
+/// that moves across the matrix.
+pub fn fade(leds: &mut [[u8; 5]; 5]) {
+    for led in leds.iter_mut().flatten() {
+        *led = led.saturating_sub(1);
+    }
+}
+
+/// Clear the display (turn off all LEDs).
+pub fn clear_display() {
+    display_frame(&MicrobitFrame::default());
+}
+
+/// Row pins, all on port 0.
+#[cfg(feature = "v2")]
+const ROWS: [usize; 5] = [21, 22, 15, 24, 19];
+
+/// Column pins that double as analog inputs, with their SAADC channel.
+#[cfg(feature = "v2")]
+const ANALOG_COLS: [(usize, u8); 3] = [(28, 4), (31, 7), (30, 6)];
+
+/// How long the columns are left floating before they are sampled, 2ms at
+/// 64MHz.
+#[cfg(feature = "v2")]
+const INTEGRATION_CYCLES: u32 = 128_000;
+
+#[cfg(feature = "v2")]
+mod channel {
+    use embedded_hal_02::adc::Channel;
+    use microbit::hal::saadc::Saadc;
+
+    /// Stand in for a column pin that is still owned by the display.
+    pub struct Ain<const N: u8>;
+
+    impl<const N: u8> Channel<Saadc> for Ain<N> {
+        type ID = u8;
+
+        fn channel() -> u8 {
+            N
+        }
+    }
+}
+
+/// Measure the ambient light with the LED matrix, from 0 (dark) to 255 (bright).
+///
+/// An LED that is reverse biased behaves like a small capacitor that light
+/// slowly discharges. The rows are pulled low and the columns high to charge
+/// them, then the columns are left floating for a couple of milliseconds and
+/// their voltage read back with the SAADC: the more light, the lower it has
+/// dropped.
+///
+/// Interrupts are disabled while this runs, so the display stops refreshing
+/// for a moment and picks up where it was afterwards.
+#[cfg(feature = "v2")]
+pub fn light_level(saadc: &mut microbit::hal::saadc::Saadc) -> u8 {
+    use embedded_hal_02::adc::OneShot;
+
+    free(|_| {
+        // The pins belong to the display driver, which can't run while we are
+        // in a critical section, so borrowing its registers here is fine.
+        let p0 = unsafe { &*pac::P0::ptr() };
+        let saved_out = p0.out.read().bits();
+
+        let rows = ROWS.iter().fold(0, |mask, &row| mask | 1 << row);
+        let cols = ANALOG_COLS
+            .iter()
+            .fold(0, |mask, &(col, _)| mask | 1 << col);
+        p0.outclr.write(|w| unsafe { w.bits(rows) });
+        p0.outset.write(|w| unsafe { w.bits(cols) });
+        cortex_m::asm::delay(1_000);
+
+        for &(col, _) in ANALOG_COLS.iter() {
+            p0.pin_cnf[col].write(|w| w.dir().input().input().disconnect());
+        }
+        cortex_m::asm::delay(INTEGRATION_CYCLES);
+
+        let mut total: u32 = 0;
+        for &(_, ain) in ANALOG_COLS.iter() {
+            let sample = match ain {
+                4 => saadc.read(&mut channel::Ain::<4>),
+                6 => saadc.read(&mut channel::Ain::<6>),
+                _ => saadc.read(&mut channel::Ain::<7>),
+            };
+            total += sample.unwrap_or(0).max(0) as u32;
+        }
+
+        for &(col, _) in ANALOG_COLS.iter() {
+            p0.pin_cnf[col].write(|w| w.dir().output().input().disconnect());
+        }
+        p0.out.write(|w| unsafe { w.bits(saved_out) });
+
+        let average = total / ANALOG_COLS.len() as u32;
+        let full_scale = (1 << 14) - 1;
+        (255 - average.min(full_scale) * 255 / full_scale) as u8
+    })
+}
+
+#[cfg(feature = "display-interrupt")]
+weak_interrupt!(TIMER1 => on_interrupt);
+
+/// Light the next row, or the next step of this one. That's what the
+/// `TIMER1` handler does, for a program with a handler of its own.
+pub fn on_interrupt() {
+    DISPLAY.with(|driver| {
+        // The first compare event starts a new row, `handle_display_event`
+        // clears it
+        let timer = unsafe { &*TIMER1::ptr() };
+        let new_row = timer.events_compare[0].read().bits() != 0;
+        driver.display.handle_display_event();
+        if new_row && driver.paused {
+            // That row started out dark, and so will the others
+            timer.tasks_stop.write(|w| unsafe { w.bits(1) });
+        } else if new_row {
+            driver.next_row();
+        }
+    });
+}
@@ -0,0 +1,339 @@
+//! A reliable radio link, in the style of Nordic's Enhanced ShockBurst (ESB).
+//!
+//! The radio on its own sends packets and hopes for the best. ESB adds what it
+//! takes to know that they arrived: the receiver answers every packet with an
+//! *acknowledgement*, and the sender sends the packet again until one comes
+//! back, up to a number of *retransmissions*. Every packet also carries a 2 bit
+//! ID, so that the receiver can tell when the same packet arrives twice
+//! because an acknowledgement got lost.
+//!
+//! Packets are sent to one of 8 *pipes*, logical addresses made up of a base
+//! address and a one byte prefix. Pipe 0 has a base address of its own, pipes
+//! 1 to 7 share the other one. Every board listens on the pipes it is
+//! interested in, so two games next to each other don't get in each other's
+//! way.
+//!
+//! ```text
+//! | length | ID     | ACK   | payload      | CRC     |
+//! | 6 bits | 2 bits | 1 bit | 0 - 32 bytes | 2 bytes |
+//! ```
+//!
+//! In memory, the length goes into the first byte and the ID and the ACK bit,
+//! which asks for an acknowledgement, into the second one. Acknowledgements
+//! are packets without a payload, with the ID of the packet they answer.
+//!
+//! The receiving board sends the acknowledgement from [`Esb::receive`], so it
+//! has to call it often: the sender only waits for half a millisecond. Don't
+//! use the blocking display on that board, or call `receive` from the `RADIO`
+//! interrupt.
+
+use core::convert::Infallible;
+use core::sync::atomic::{compiler_fence, Ordering};
+use embedded_hal_02::timer::CountDown;
+use microbit::hal::clocks::{Clocks, ExternalOscillator};
+use microbit::hal::timer::{Instance, OneShot, Timer};
+use microbit::pac::RADIO;
+
+pub const MAX_PAYLOAD_LEN: usize = 32;
+/// How many pipes there are to send to and listen on.
+pub const PIPES: usize = 8;
+/// How often a packet is sent again before giving up, on top of the first
+/// time. The same as on Nordic's nRF24 chips.
+pub const DEFAULT_RETRANSMITS: u8 = 3;
+
+const HEADER_LEN: usize = 2;
+const ACK_REQUESTED: u8 = 1;
+
+/// How long to wait for an acknowledgement to start, in µs. Turning the
+/// radio around from receiving to sending takes the other side about 140 µs.
+const ACK_TIMEOUT: u32 = 500;
+
+/// 2402 MHz, the default channel of the nRF24 chips.
+const FREQUENCY: u32 = 2;
+
+const MODE_NRF_2MBIT: u32 = 1;
+const PCNF0_LFLEN_6: u32 = 6;
+const PCNF0_S1LEN_3: u32 = 3 << 16;
+const PCNF1_ENDIAN_BIG: u32 = 1 << 24;
+const PCNF1_BALEN_4: u32 = 4 << 16;
+const CRCCNF_LEN_2: u32 = 2;
+const CRC_POLY: u32 = 0x1_1021;
+const CRC_INIT: u32 = 0xFFFF;
+const SHORTS_READY_START: u32 = 1;
+const SHORTS_END_DISABLE: u32 = 1 << 1;
+const SHORTS_DISABLED_RXEN: u32 = 1 << 3;
+
+#[derive(Debug)]
+pub enum Error {
+    /// The payload doesn't fit into a packet.
+    TooLong,
+    /// No acknowledgement came back, not even after all retransmissions.
+    NoAck,
+}
+
+/// The addresses of the pipes.
+#[derive(Clone, Copy, Debug)]
+pub struct Addresses {
+    /// The base address of pipe 0.
+    pub base0: u32,
+    /// The base address of pipes 1 to 7.
+    pub base1: u32,
+    /// The prefix of every pipe.
+    pub prefixes: [u8; PIPES],
+}
+
+impl Default for Addresses {
+    /// The same addresses the nRF24 chips start out with.
+    fn default() -> Addresses {
+        Addresses {
+            base0: 0xE7E7_E7E7,
+            base1: 0xC2C2_C2C2,
+            prefixes: [0xE7, 0xC2, 0xC3, 0xC4, 0xC5, 0xC6, 0xC7, 0xC8],
+        }
+    }
+}
+
+pub struct Esb<T: Instance> {
+    radio: RADIO,
+    timer: Timer<T, OneShot>,
+    buffer: [u8; HEADER_LEN + MAX_PAYLOAD_LEN],
+    ack: [u8; HEADER_LEN],
+    retransmits: u8,
+    /// One bit for every pipe we listen on.
+    listening: u32,
+    /// The ID of the next packet sent to each pipe.
+    next_ids: [u8; PIPES],
+    /// The ID and CRC of the last packet received on each pipe, to spot
+    /// the same packet coming in again.
+    last_received: [Option<(u8, u32)>; PIPES],
+    receiving: bool,
+}
+
+impl<T: Instance> Esb<T> {
+    /// Set up the radio, listening on pipe 0. `timer` measures how long to
+    /// wait for acknowledgements, and `clocks` proves that the external
+    /// crystal is running, which the radio needs.
+    pub fn new<L, LSTAT>(
+        radio: RADIO,
+        timer: T,
+        _clocks: &Clocks<ExternalOscillator, L, LSTAT>,
+        addresses: &Addresses,
+    ) -> Esb<T> {
+        radio.mode.write(|w| unsafe { w.bits(MODE_NRF_2MBIT) });
+        radio.txpower.write(|w| unsafe { w.bits(0) });
+        radio.frequency.write(|w| unsafe { w.bits(FREQUENCY) });
+
+        radio
+            .pcnf0
+            .write(|w| unsafe { w.bits(PCNF0_LFLEN_6 | PCNF0_S1LEN_3) });
+        radio.pcnf1.write(|w| unsafe {
+            w.bits(PCNF1_ENDIAN_BIG | PCNF1_BALEN_4 | MAX_PAYLOAD_LEN as u32)
+        });
+
+        radio.base0.write(|w| unsafe { w.bits(addresses.base0) });
+        radio.base1.write(|w| unsafe { w.bits(addresses.base1) });
+        let prefixes = addresses.prefixes;
+        radio.prefix0.write(|w| unsafe {
+            w.bits(u32::from_le_bytes([
+                prefixes[0],
+                prefixes[1],
+                prefixes[2],
+                prefixes[3],
+            ]))
+        });
+        radio.prefix1.write(|w| unsafe {
+            w.bits(u32::from_le_bytes([
+                prefixes[4],
+                prefixes[5],
+                prefixes[6],
+                prefixes[7],
+            ]))
+        });
+
+        // Unlike MakeCode's packets, the CRC covers the address as well
+        radio.crccnf.write(|w| unsafe { w.bits(CRCCNF_LEN_2) });
+        radio.crcpoly.write(|w| unsafe { w.bits(CRC_POLY) });
+        radio.crcinit.write(|w| unsafe { w.bits(CRC_INIT) });
+
+        Esb {
+            radio,
+            timer: Timer::one_shot(timer),
+            buffer: [0; HEADER_LEN + MAX_PAYLOAD_LEN],
+            ack: [0; HEADER_LEN],
+            retransmits: DEFAULT_RETRANSMITS,
+            listening: 1,
+            next_ids: [0; PIPES],
+            last_received: [None; PIPES],
+            receiving: false,
+        }
+    }
+
+    /// Listen on the pipes whose bits are set in `pipes`, bit 0 for pipe 0.
+    pub fn listen(&mut self, pipes: u8) {
+        self.disable();
+        self.listening = pipes.into();
+    }
+
+    /// How often a packet is sent again when no acknowledgement comes back.
+    pub fn set_retransmits(&mut self, retransmits: u8) {
+        self.retransmits = retransmits;
+    }
+
+    /// Send `payload` to `pipe` and wait until it has been acknowledged.
+    ///
+    /// # Panics
+    ///
+    /// If there is no such pipe.
+    pub fn send(&mut self, pipe: u8, payload: &[u8]) -> Result<(), Error> {
+        let pipe = usize::from(pipe);
+        assert!(pipe < PIPES, "there are only {} pipes", PIPES);
+        if payload.len() > MAX_PAYLOAD_LEN {
+            return Err(Error::TooLong);
+        }
+        self.disable();
+
+        // Every packet gets a new ID, even when the last one was never
+        // acknowledged. Otherwise it might get mistaken for that one.
+        let id = self.next_ids[pipe];
+        self.next_ids[pipe] = (id + 1) % 4;
+
+        self.buffer[0] = payload.len() as u8;
+        self.buffer[1] = id << 1 | ACK_REQUESTED;
+        self.buffer[HEADER_LEN..][..payload.len()].copy_from_slice(payload);
+        self.radio
+            .txaddress
+            .write(|w| unsafe { w.bits(pipe as u32) });
+        self.radio
+            .rxaddresses
+            .write(|w| unsafe { w.bits(1 << pipe) });
+
+        let attempts = u32::from(self.retransmits) + 1;
+        if (0..attempts).any(|_| self.transmit(id)) {
+            Ok(())
+        } else {
+            Err(Error::NoAck)
+        }
+    }
+
+    /// Check whether a packet has arrived on one of the pipes we listen on,
+    /// and start listening if we weren't already. Returns the pipe along with
+    /// the payload. Packets that have already been received once are
+    /// acknowledged again, but skipped.
+    ///
+    /// The payload is only valid until the next call, since listening for the
+    /// next packet starts right away.
+    pub fn receive(&mut self) -> nb::Result<(u8, &[u8]), Infallible> {
+        if !self.receiving {
+            self.start_receiving();
+            return Err(nb::Error::WouldBlock);
+        }
+        if self.radio.events_disabled.read().bits() == 0 {
+            return Err(nb::Error::WouldBlock);
+        }
+        self.radio.events_disabled.reset();
+        self.receiving = false;
+        compiler_fence(Ordering::Acquire);
+
+        // Without an acknowledgement the sender tries again, so there's no
+        // need to report a broken packet
+        if self.radio.crcstatus.read().bits() == 0 {
+            self.start_receiving();
+            return Err(nb::Error::WouldBlock);
+        }
+        let pipe = self.radio.rxmatch.read().bits() as usize;
+        let crc = self.radio.rxcrc.read().bits();
+        let id = self.buffer[1] >> 1;
+
+        if self.buffer[1] & ACK_REQUESTED != 0 {
+            self.acknowledge(pipe, id);
+        }
+        if self.last_received[pipe] == Some((id, crc)) {
+            self.start_receiving();
+            return Err(nb::Error::WouldBlock);
+        }
+        self.last_received[pipe] = Some((id, crc));
+
+        let len = usize::from(self.buffer[0]).min(MAX_PAYLOAD_LEN);
+        Ok((pipe as u8, &self.buffer[HEADER_LEN..HEADER_LEN + len]))
+    }
+
+    /// Send the packet in the buffer once, and wait for its acknowledgement.
+    fn transmit(&mut self, id: u8) -> bool {
+        // Start listening for the acknowledgement as soon as the packet is out
+        self.radio.shorts.write(|w| unsafe {
+            w.bits(SHORTS_READY_START | SHORTS_END_DISABLE | SHORTS_DISABLED_RXEN)
+        });
+        let packet = self.buffer.as_ptr() as u32;
+        self.start(packet, true);
+        self.wait_until_disabled();
+
+        // The radio is already ramping up to receive, which takes long
+        // enough to tell it where to put the acknowledgement
+        self.radio
+            .shorts
+            .write(|w| unsafe { w.bits(SHORTS_READY_START | SHORTS_END_DISABLE) });
+        let ack = self.ack.as_mut_ptr() as u32;
+        self.radio.packetptr.write(|w| unsafe { w.bits(ack) });
+        self.radio.events_address.reset();
+
+        self.timer.start(ACK_TIMEOUT);
+        while self.radio.events_address.read().bits() == 0 {
+            if self.timer.wait().is_ok() {
+                self.radio.tasks_disable.write(|w| unsafe { w.bits(1) });
+                self.wait_until_disabled();
+                return false;
+            }
+        }
+        self.wait_until_disabled();
+        self.radio.crcstatus.read().bits() != 0 && self.ack[1] >> 1 == id
+    }
+
+    fn acknowledge(&mut self, pipe: usize, id: u8) {
+        self.ack = [0, id << 1];
+        self.radio
+            .txaddress
+            .write(|w| unsafe { w.bits(pipe as u32) });
+        let ack = self.ack.as_ptr() as u32;
+        self.start(ack, true);
+        self.wait_until_disabled();
+    }
+
+    fn start_receiving(&mut self) {
+        let listening = self.listening;
+        self.radio
+            .rxaddresses
+            .write(|w| unsafe { w.bits(listening) });
+        self.radio
+            .shorts
+            .write(|w| unsafe { w.bits(SHORTS_READY_START | SHORTS_END_DISABLE) });
+        let packet = self.buffer.as_mut_ptr() as u32;
+        self.start(packet, false);
+        self.receiving = true;
+    }
+
+    fn start(&mut self, packet: u32, transmit: bool) {
+        compiler_fence(Ordering::Release);
+        self.radio.packetptr.write(|w| unsafe { w.bits(packet) });
+        self.radio.events_disabled.reset();
+        if transmit {
+            self.radio.tasks_txen.write(|w| unsafe { w.bits(1) });
+        } else {
+            self.radio.tasks_rxen.write(|w| unsafe { w.bits(1) });
+        }
+    }
+
+    fn wait_until_disabled(&mut self) {
+        while self.radio.events_disabled.read().bits() == 0 {}
+        self.radio.events_disabled.reset();
+        compiler_fence(Ordering::Acquire);
+    }
+
+    fn disable(&mut self) {
+        if !self.receiving {
+            return;
+        }
+        self.radio.tasks_disable.write(|w| unsafe { w.bits(1) });
+        self.wait_until_disabled();
+        self.receiving = false;
+    }
+}
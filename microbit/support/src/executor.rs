@@ -0,0 +1,60 @@
+//! Running an `async fn` without an async runtime.
+//!
+//! An `async fn` doesn't do anything when it's called. What it returns is a
+//! *future*, which somebody has to *poll*, and each poll runs it up to the
+//! next `.await` that has to wait. Whatever it waits for, say a byte on the
+//! serial port, keeps the *waker* that came with the poll, and calls `wake`
+//! on it once the byte is there, usually from an interrupt handler. Only then
+//! is it worth polling again.
+//!
+//! [`block_on`] is about the smallest thing that does that: it polls a single
+//! future, and sleeps with `wfe` until the future is woken. Embassy's
+//! executor works the same way, but with many futures at once, and timers
+//! and drivers for everything that go with it.
+
+use core::future::Future;
+use core::pin::pin;
+use core::ptr;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+use cortex_m::asm;
+
+/// Whether the future was woken since it was last polled. There's only ever
+/// one future, so a single flag does.
+static WOKEN: AtomicBool = AtomicBool::new(false);
+
+static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, wake, wake, drop);
+
+fn clone(_: *const ()) -> RawWaker {
+    RawWaker::new(ptr::null(), &VTABLE)
+}
+
+fn wake(_: *const ()) {
+    WOKEN.store(true, Ordering::Release);
+    // In case it's called from the main program, which no interrupt would
+    // wake up
+    asm::sev();
+}
+
+fn drop(_: *const ()) {}
+
+/// Run `future` until it's done, and return what it returns.
+///
+/// Between polls the CPU sleeps, until the future's waker is called.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    // The vtable doesn't need the data pointer, and its functions do what
+    // wakers are supposed to
+    let waker = unsafe { Waker::from_raw(clone(ptr::null())) };
+    let mut context = Context::from_waker(&waker);
+    loop {
+        // Cleared before polling, so that a wake during the poll isn't lost
+        WOKEN.store(false, Ordering::Relaxed);
+        if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+            return output;
+        }
+        while !WOKEN.load(Ordering::Acquire) {
+            asm::wfe();
+        }
+    }
+}
@@ -0,0 +1,23 @@
+//! A friendlier error for building without a board feature.
+//!
+//! Every chapter builds for either micro:bit, picked with `--features v1` or
+//! `--features v2`. Without either, there's no `microbit` crate at all, and
+//! that ends in a long list of errors about it that don't say why. This one
+//! comes first and does. The HAL already has errors of its own for the wrong
+//! `--target`, and for both features at once.
+//!
+//! The SoftDevice features only make sense on the micro:bit v2, and only one
+//! SoftDevice fits on a chip. The modules that only make sense on the v2 say
+//! so themselves when they're used on the v1, see `v2_only`.
+
+#[cfg(not(any(feature = "v1", feature = "v2")))]
+compile_error!(
+    "pick a board: build with `--features v2 --target thumbv7em-none-eabihf` for the micro:bit v2, \
+     or `--features v1 --target thumbv6m-none-eabi` for the micro:bit v1"
+);
+
+#[cfg(all(feature = "v1", any(feature = "s113", feature = "s140")))]
+compile_error!("the `s113` and `s140` SoftDevice features are for the micro:bit v2 only");
+
+#[cfg(all(feature = "s113", feature = "s140"))]
+compile_error!("pick one SoftDevice: `s113` or `s140`, not both");
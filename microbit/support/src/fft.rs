@@ -0,0 +1,89 @@
+//! A fixed point fast Fourier transform.
+//!
+//! The Fourier transform splits a signal into the frequencies it's made of:
+//! out of [`LEN`] samples it makes [`LEN`] *bins*, and bin `k` tells how much
+//! of the frequency `k * sample rate / LEN` there is in the signal. The fast
+//! way to do that is the radix-2 FFT, which halves the problem again and
+//! again until only pairs of samples are left, and then puts the answer back
+//! together in `log2(LEN)` passes over the samples.
+//!
+//! The nRF52833 has a floating point unit, but the samples come in as
+//! integers, and integer math is quick and exact enough here. All numbers are
+//! Q15: an `i16` that stands for itself divided by 32768, so from -1 up to
+//! just under 1. Every pass halves the numbers to keep them in that range, so
+//! the result is the Fourier transform divided by [`LEN`].
+
+/// How many samples go into one transform. It has to be a power of 2.
+pub const LEN: usize = 256;
+const PASSES: u32 = LEN.trailing_zeros();
+
+/// Sines are slow to work out on the spot, so this keeps a table of them.
+pub struct Fft {
+    /// The sine of a whole turn split into [`LEN`] steps, in Q15.
+    sines: [i16; LEN],
+}
+
+impl Fft {
+    pub fn new() -> Fft {
+        let mut sines = [0; LEN];
+        for (k, sine) in sines.iter_mut().enumerate() {
+            let angle = 2.0 * core::f32::consts::PI * k as f32 / LEN as f32;
+            *sine = (libm::sinf(angle) * f32::from(i16::MAX)) as i16;
+        }
+        Fft { sines }
+    }
+
+    /// Transform the signal in `re` and `im`, its real and imaginary parts,
+    /// in place. For samples, `im` starts out all zeros.
+    pub fn transform(&self, re: &mut [i16; LEN], im: &mut [i16; LEN]) {
+        // Put every sample where the halving will want it: at the index with
+        // its bits the other way around
+        for i in 0..LEN {
+            let j = i.reverse_bits() >> (usize::BITS - PASSES);
+            if i < j {
+                re.swap(i, j);
+                im.swap(i, j);
+            }
+        }
+
+        // Every pass combines pairs of transforms of `half` samples each
+        let mut half = 1;
+        while half < LEN {
+            let step = LEN / (2 * half);
+            for start in (0..LEN).step_by(2 * half) {
+                for k in 0..half {
+                    // e^(-2πik / 2half) = cos - i sin
+                    let cos = self.sines[(k * step + LEN / 4) % LEN];
+                    let sin = -self.sines[k * step];
+                    let (a, b) = (start + k, start + k + half);
+
+                    let t_re = mul(re[b], cos) - mul(im[b], sin);
+                    let t_im = mul(re[b], sin) + mul(im[b], cos);
+                    let (u_re, u_im) = (i32::from(re[a]), i32::from(im[a]));
+                    re[a] = ((u_re + t_re) >> 1) as i16;
+                    im[a] = ((u_im + t_im) >> 1) as i16;
+                    re[b] = ((u_re - t_re) >> 1) as i16;
+                    im[b] = ((u_im - t_im) >> 1) as i16;
+                }
+            }
+            half *= 2;
+        }
+    }
+}
+
+impl Default for Fft {
+    fn default() -> Fft {
+        Fft::new()
+    }
+}
+
+/// Multiply two Q15 numbers, rounding to the nearest.
+fn mul(a: i16, b: i16) -> i32 {
+    (i32::from(a) * i32::from(b) + (1 << 14)) >> 15
+}
+
+/// How strong bin `k` is: the square of its magnitude.
+pub fn power(re: &[i16; LEN], im: &[i16; LEN], k: usize) -> u32 {
+    let (re, im) = (i32::from(re[k]), i32::from(im[k]));
+    (re * re + im * im) as u32
+}
@@ -0,0 +1,58 @@
+//! Reading, erasing and writing flash through the `NVMC`, for the modules
+//! that keep things in it.
+//!
+//! Reading is easy, since the flash is mapped into memory just like RAM.
+//! Writing has to be switched on in the `NVMC` first, and can only turn 1s
+//! into 0s, one word at a time. Erasing turns a whole page back into 1s.
+//! While the flash is busy the CPU can't fetch any code from it, so it just
+//! stops for a moment.
+
+use core::ptr;
+use microbit::pac::NVMC;
+
+#[cfg(feature = "v2")]
+pub const PAGE_SIZE: usize = crate::flash_layout::v2::PAGE_SIZE;
+#[cfg(feature = "v1")]
+pub const PAGE_SIZE: usize = crate::flash_layout::v1::PAGE_SIZE;
+
+/// What a word of erased flash reads as.
+pub const ERASED: u32 = 0xffff_ffff;
+
+pub fn read_word(address: usize) -> u32 {
+    unsafe { ptr::read_volatile(address as *const u32) }
+}
+
+/// Erase the page starting at `address`.
+pub fn erase_page(nvmc: &NVMC, address: usize) {
+    nvmc.config.write(|w| w.wen().een());
+    nvmc.erasepage()
+        .write(|w| unsafe { w.bits(address as u32) });
+    wait_ready(nvmc);
+    nvmc.config.write(|w| w.wen().ren());
+}
+
+/// Erase the UICR, the user information configuration registers, which
+/// the chip reads its settings from when it starts. It lives in a page of
+/// flash of its own, apart from the rest.
+#[cfg(feature = "v2")]
+pub fn erase_uicr(nvmc: &NVMC) {
+    nvmc.config.write(|w| w.wen().een());
+    nvmc.eraseuicr.write(|w| w.eraseuicr().erase());
+    wait_ready(nvmc);
+    nvmc.config.write(|w| w.wen().ren());
+}
+
+/// Write `words` to the flash starting at `address`, which has to be
+/// erased.
+pub fn write_words(nvmc: &NVMC, address: usize, words: &[u32]) {
+    nvmc.config.write(|w| w.wen().wen());
+    for (index, &word) in words.iter().enumerate() {
+        unsafe { ptr::write_volatile((address + index * 4) as *mut u32, word) };
+        wait_ready(nvmc);
+    }
+    nvmc.config.write(|w| w.wen().ren());
+}
+
+fn wait_ready(nvmc: &NVMC) {
+    while nvmc.ready.read().ready().is_busy() {}
+}
@@ -0,0 +1,38 @@
+//! Where in the flash everything goes: the program at the start, and the
+//! pages that `discovery-support` keeps for itself at the end.
+//!
+//! From the end of the flash down, that's the page of the
+//! [`settings`](crate::settings), and on the v2, the pages of the
+//! `flash_log` below it, and the page of the `crash_log` below those. The
+//! program gets everything below `PROGRAM_END`. The chapters' build scripts include this file too,
+//! through `support/build/memory_x.rs`, and hand the linker no more than
+//! that, so a module that gets a page here can't end up with code in it.
+//!
+//! Both boards are in here, since the build script only finds out which one
+//! it's for while it runs.
+
+/// The micro:bit v1's nRF51822, with 256K of flash in pages of 1K.
+pub mod v1 {
+    pub const FLASH_END: usize = 256 * 1024;
+    pub const PAGE_SIZE: usize = 1024;
+    /// The page of the settings, the last one.
+    pub const SETTINGS_ADDRESS: usize = FLASH_END - PAGE_SIZE;
+    /// Where the program's part of the flash ends.
+    pub const PROGRAM_END: usize = SETTINGS_ADDRESS;
+}
+
+/// The micro:bit v2's nRF52833, with 512K of flash in pages of 4K.
+pub mod v2 {
+    pub const FLASH_END: usize = 512 * 1024;
+    pub const PAGE_SIZE: usize = 4096;
+    /// The page of the settings, the last one.
+    pub const SETTINGS_ADDRESS: usize = FLASH_END - PAGE_SIZE;
+    /// How many pages the flash log is spread over.
+    pub const FLASH_LOG_PAGES: usize = 8;
+    /// The first page of the flash log, which goes up to the settings.
+    pub const FLASH_LOG_ADDRESS: usize = SETTINGS_ADDRESS - FLASH_LOG_PAGES * PAGE_SIZE;
+    /// The page of the crash log, right below the flash log.
+    pub const CRASH_LOG_ADDRESS: usize = FLASH_LOG_ADDRESS - PAGE_SIZE;
+    /// Where the program's part of the flash ends.
+    pub const PROGRAM_END: usize = CRASH_LOG_ADDRESS;
+}
@@ -0,0 +1,193 @@
+//! A log of fixed-size records in flash, which keeps the newest ones when it
+//! runs out of room.
+//!
+//! The log is spread over [`PAGES`] pages, used one after the other as a
+//! ring. Records are appended to the current page until it's full, then the
+//! next page is erased, and the records go there, until the ring comes back
+//! around and erases the oldest page. That way every page gets erased just as
+//! often as the others, instead of the first one wearing out while the rest
+//! are hardly ever touched. Each page starts with a sequence number that goes
+//! up by one with every new page, which is how [`FlashLog::take`] finds the
+//! newest page again after a reset.
+//!
+//! A record is [`RECORD_WORDS`] words, followed by a checksum of them. The
+//! checksum is written last, so a record that lost power halfway through being
+//! written doesn't add up, and gets skipped.
+//!
+//! The log lives in flash that only the v2 has to spare: the 32K below the
+//! page of [`settings`](crate::settings), which `memory.x` leaves out of the
+//! program's way, see [`flash_layout`].
+
+use crate::flash::{self, ERASED, PAGE_SIZE};
+use crate::flash_layout;
+use core::sync::atomic::{AtomicBool, Ordering};
+use microbit::pac::{self, NVMC};
+
+/// How many words of data a record holds.
+pub const RECORD_WORDS: usize = 4;
+pub type Record = [u32; RECORD_WORDS];
+
+/// How many pages the log is spread over.
+pub const PAGES: usize = flash_layout::v2::FLASH_LOG_PAGES;
+/// The first of the pages, right below the settings page.
+const START_ADDRESS: usize = flash_layout::v2::FLASH_LOG_ADDRESS;
+
+/// The data of a record and its checksum.
+const SLOT_WORDS: usize = RECORD_WORDS + 1;
+/// Every page starts with its sequence number, the records come after.
+const SLOTS_PER_PAGE: usize = (PAGE_SIZE / 4 - 1) / SLOT_WORDS;
+
+/// How many records fit into the log. One page is always about to be
+/// erased, so only the ones in the others are sure to still be there.
+pub const CAPACITY: usize = (PAGES - 1) * SLOTS_PER_PAGE;
+
+/// Whether [`FlashLog::take`] has handed out the `NVMC` yet.
+static TAKEN: AtomicBool = AtomicBool::new(false);
+
+pub struct FlashLog {
+    nvmc: NVMC,
+    /// The page records are appended to.
+    page: usize,
+    /// The sequence number of that page.
+    sequence: u32,
+    /// The slot in that page the next record goes into.
+    slot: usize,
+}
+
+impl FlashLog {
+    /// Take the `NVMC`, the first time this is called, and find where the
+    /// log left off before the reset. Without any log in the flash, an empty
+    /// one is started.
+    ///
+    /// `Board::take` doesn't hand out the `NVMC`, so we go behind its back,
+    /// just like `Board::take` itself.
+    pub fn take() -> Option<FlashLog> {
+        if TAKEN.swap(true, Ordering::Relaxed) {
+            return None;
+        }
+        let nvmc = unsafe { pac::Peripherals::steal() }.NVMC;
+
+        let newest = (0..PAGES)
+            .filter_map(|page| Some((page_sequence(page)?, page)))
+            .max();
+        let mut log = FlashLog {
+            nvmc,
+            page: 0,
+            sequence: 0,
+            slot: 0,
+        };
+        match newest {
+            Some((sequence, page)) => {
+                log.page = page;
+                log.sequence = sequence;
+                // A slot that has been written at all, even if it didn't
+                // work out, can't be written again. The ones after the last
+                // of them are free.
+                log.slot = (0..SLOTS_PER_PAGE)
+                    .rev()
+                    .find(|&slot| !slot_is_erased(page, slot))
+                    .map_or(0, |slot| slot + 1);
+            }
+            None => log.start_page(0, 0),
+        }
+        Some(log)
+    }
+
+    /// Append `record` to the log, after erasing the oldest page if there's
+    /// no room left in the current one.
+    pub fn push(&mut self, record: &Record) {
+        if self.slot == SLOTS_PER_PAGE {
+            self.start_page((self.page + 1) % PAGES, self.sequence + 1);
+        }
+
+        let mut words = [0; SLOT_WORDS];
+        words[..RECORD_WORDS].copy_from_slice(record);
+        words[RECORD_WORDS] = checksum(record);
+        flash::write_words(&self.nvmc, slot_address(self.page, self.slot), &words);
+        self.slot += 1;
+    }
+
+    /// All records in the log, from the oldest to the newest.
+    pub fn records(&self) -> impl Iterator<Item = Record> {
+        let (current, newest) = (self.page, self.sequence);
+        // The page after the current one is the oldest, unless the ring
+        // hasn't come around yet, and it's still erased
+        (1..=PAGES)
+            .map(move |step| (current + step) % PAGES)
+            .filter(move |&page| page_sequence(page).is_some_and(|sequence| sequence <= newest))
+            .flat_map(|page| (0..SLOTS_PER_PAGE).filter_map(move |slot| read_record(page, slot)))
+    }
+
+    /// Throw away all records.
+    ///
+    /// The log starts over on the page after the current one rather than on
+    /// the first, so that erasing it over and over again doesn't wear out
+    /// the first page either.
+    pub fn erase(&mut self) {
+        for page in 0..PAGES {
+            if !page_is_erased(page) {
+                flash::erase_page(&self.nvmc, page_address(page));
+            }
+        }
+        self.start_page((self.page + 1) % PAGES, self.sequence + 1);
+    }
+
+    /// Erase `page`, and make it the current one.
+    fn start_page(&mut self, page: usize, sequence: u32) {
+        if !page_is_erased(page) {
+            flash::erase_page(&self.nvmc, page_address(page));
+        }
+        flash::write_words(&self.nvmc, page_address(page), &[sequence]);
+        self.page = page;
+        self.sequence = sequence;
+        self.slot = 0;
+    }
+}
+
+fn page_address(page: usize) -> usize {
+    START_ADDRESS + page * PAGE_SIZE
+}
+
+fn slot_address(page: usize, slot: usize) -> usize {
+    page_address(page) + (1 + slot * SLOT_WORDS) * 4
+}
+
+/// The sequence number of `page`, or `None` if it isn't part of the log.
+fn page_sequence(page: usize) -> Option<u32> {
+    match flash::read_word(page_address(page)) {
+        ERASED => None,
+        sequence => Some(sequence),
+    }
+}
+
+fn page_is_erased(page: usize) -> bool {
+    (0..PAGE_SIZE / 4).all(|index| flash::read_word(page_address(page) + index * 4) == ERASED)
+}
+
+fn slot_is_erased(page: usize, slot: usize) -> bool {
+    (0..SLOT_WORDS).all(|index| flash::read_word(slot_address(page, slot) + index * 4) == ERASED)
+}
+
+/// The record in `slot` of `page`, if there is one and it adds up.
+fn read_record(page: usize, slot: usize) -> Option<Record> {
+    let address = slot_address(page, slot);
+    let record: Record = core::array::from_fn(|index| flash::read_word(address + index * 4));
+    if flash::read_word(address + RECORD_WORDS * 4) != checksum(&record) {
+        return None;
+    }
+    Some(record)
+}
+
+/// Mixes every bit of the record into the checksum, so that a record cut
+/// short by a power cut, or a bit that has flipped, doesn't add up anymore.
+/// It's never [`ERASED`], so a record can't be mistaken for a free slot.
+fn checksum(record: &Record) -> u32 {
+    let sum = record.iter().fold(0x4c6f_6731, |sum: u32, &word| {
+        (sum.rotate_left(5) ^ word).wrapping_mul(0x0100_0193)
+    });
+    if sum == ERASED {
+        0
+    } else {
+        sum
+    }
+}
@@ -0,0 +1,100 @@
+//! A 5x5 pixel font covering printable ASCII.
+//!
+//! Each glyph is stored as five rows, top first. Within a row bit 4 is the
+//! leftmost column and bit 0 the rightmost, so `0b10001` lights both edges.
+//! Lower case letters are shown using their upper case glyph and anything
+//! without a glyph is shown as `?`.
+
+/// The first character in [`FONT`].
+const FIRST: char = ' ';
+
+/// Glyphs for `' '` up to and including `'_'`.
+const FONT: [[u8; 5]; 64] = [
+    [0b00000, 0b00000, 0b00000, 0b00000, 0b00000], // ' '
+    [0b00100, 0b00100, 0b00100, 0b00000, 0b00100], // '!'
+    [0b01010, 0b01010, 0b00000, 0b00000, 0b00000], // '"'
+    [0b01010, 0b11111, 0b01010, 0b11111, 0b01010], // '#'
+    [0b01111, 0b10100, 0b01110, 0b00101, 0b11110], // '$'
+    [0b11001, 0b11010, 0b00100, 0b01011, 0b10011], // '%'
+    [0b01100, 0b10010, 0b01100, 0b10010, 0b01101], // '&'
+    [0b00100, 0b00100, 0b00000, 0b00000, 0b00000], // '\''
+    [0b00010, 0b00100, 0b00100, 0b00100, 0b00010], // '('
+    [0b01000, 0b00100, 0b00100, 0b00100, 0b01000], // ')'
+    [0b00000, 0b01010, 0b00100, 0b01010, 0b00000], // '*'
+    [0b00000, 0b00100, 0b01110, 0b00100, 0b00000], // '+'
+    [0b00000, 0b00000, 0b00000, 0b00100, 0b01000], // ','
+    [0b00000, 0b00000, 0b01110, 0b00000, 0b00000], // '-'
+    [0b00000, 0b00000, 0b00000, 0b00000, 0b00100], // '.'
+    [0b00001, 0b00010, 0b00100, 0b01000, 0b10000], // '/'
+    [0b01100, 0b10010, 0b10010, 0b10010, 0b01100], // '0'
+    [0b00100, 0b01100, 0b00100, 0b00100, 0b01110], // '1'
+    [0b11100, 0b00010, 0b01100, 0b10000, 0b11110], // '2'
+    [0b11110, 0b00010, 0b00100, 0b10010, 0b01100], // '3'
+    [0b00110, 0b01010, 0b10010, 0b11111, 0b00010], // '4'
+    [0b11111, 0b10000, 0b11110, 0b00001, 0b11110], // '5'
+    [0b00010, 0b00100, 0b01110, 0b10001, 0b01110], // '6'
+    [0b11111, 0b00010, 0b00100, 0b01000, 0b10000], // '7'
+    [0b01110, 0b10001, 0b01110, 0b10001, 0b01110], // '8'
+    [0b01110, 0b10001, 0b01110, 0b00100, 0b01000], // '9'
+    [0b00000, 0b00100, 0b00000, 0b00100, 0b00000], // ':'
+    [0b00000, 0b00100, 0b00000, 0b00100, 0b01000], // ';'
+    [0b00010, 0b00100, 0b01000, 0b00100, 0b00010], // '<'
+    [0b00000, 0b01110, 0b00000, 0b01110, 0b00000], // '='
+    [0b01000, 0b00100, 0b00010, 0b00100, 0b01000], // '>'
+    [0b01110, 0b00001, 0b00110, 0b00000, 0b00100], // '?'
+    [0b01110, 0b10001, 0b10111, 0b10000, 0b01111], // '@'
+    [0b01100, 0b10010, 0b11110, 0b10010, 0b10010], // 'A'
+    [0b11100, 0b10010, 0b11100, 0b10010, 0b11100], // 'B'
+    [0b01110, 0b10000, 0b10000, 0b10000, 0b01110], // 'C'
+    [0b11100, 0b10010, 0b10010, 0b10010, 0b11100], // 'D'
+    [0b11110, 0b10000, 0b11100, 0b10000, 0b11110], // 'E'
+    [0b11110, 0b10000, 0b11100, 0b10000, 0b10000], // 'F'
+    [0b01110, 0b10000, 0b10011, 0b10001, 0b01110], // 'G'
+    [0b10010, 0b10010, 0b11110, 0b10010, 0b10010], // 'H'
+    [0b01110, 0b00100, 0b00100, 0b00100, 0b01110], // 'I'
+    [0b00111, 0b00001, 0b00001, 0b10001, 0b01110], // 'J'
+    [0b10010, 0b10100, 0b11000, 0b10100, 0b10010], // 'K'
+    [0b10000, 0b10000, 0b10000, 0b10000, 0b11110], // 'L'
+    [0b10001, 0b11011, 0b10101, 0b10001, 0b10001], // 'M'
+    [0b10001, 0b11001, 0b10101, 0b10011, 0b10001], // 'N'
+    [0b01100, 0b10010, 0b10010, 0b10010, 0b01100], // 'O'
+    [0b11100, 0b10010, 0b11100, 0b10000, 0b10000], // 'P'
+    [0b01100, 0b10010, 0b10010, 0b01100, 0b00011], // 'Q'
+    [0b11100, 0b10010, 0b11100, 0b10100, 0b10010], // 'R'
+    [0b01110, 0b10000, 0b01100, 0b00010, 0b11100], // 'S'
+    [0b11111, 0b00100, 0b00100, 0b00100, 0b00100], // 'T'
+    [0b10010, 0b10010, 0b10010, 0b10010, 0b01100], // 'U'
+    [0b10001, 0b10001, 0b10001, 0b01010, 0b00100], // 'V'
+    [0b10001, 0b10001, 0b10101, 0b11011, 0b10001], // 'W'
+    [0b10010, 0b10010, 0b01100, 0b10010, 0b10010], // 'X'
+    [0b10001, 0b01010, 0b00100, 0b00100, 0b00100], // 'Y'
+    [0b11110, 0b00100, 0b01000, 0b10000, 0b11110], // 'Z'
+    [0b01110, 0b01000, 0b01000, 0b01000, 0b01110], // '['
+    [0b10000, 0b01000, 0b00100, 0b00010, 0b00001], // '\\'
+    [0b01110, 0b00010, 0b00010, 0b00010, 0b01110], // ']'
+    [0b00100, 0b01010, 0b00000, 0b00000, 0b00000], // '^'
+    [0b00000, 0b00000, 0b00000, 0b00000, 0b11111], // '_'
+];
+
+/// Look up the rows of the glyph used to draw `c`.
+pub fn glyph(c: char) -> [u8; 5] {
+    let c = c.to_ascii_uppercase();
+    let index = (c as u32).wrapping_sub(FIRST as u32) as usize;
+    match FONT.get(index) {
+        Some(glyph) => *glyph,
+        None => FONT['?' as usize - FIRST as usize],
+    }
+}
+
+/// Render `c` as a matrix of on (1) / off (0) LEDs, ready to be shown on the
+/// display.
+pub fn character(c: char) -> [[u8; 5]; 5] {
+    let rows = glyph(c);
+    let mut leds = [[0; 5]; 5];
+    for (row, bits) in leds.iter_mut().zip(rows.iter()) {
+        for (col, led) in row.iter_mut().enumerate() {
+            *led = (bits >> (4 - col)) & 1;
+        }
+    }
+    leds
+}
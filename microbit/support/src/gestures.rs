@@ -0,0 +1,174 @@
+//! Recognizing what is being done with the board from accelerometer data.
+//!
+//! Games usually don't care about accelerations, they want to know whether
+//! the board has been shaken or turned over. [`GestureDetector`] is fed every
+//! accelerometer measurement and turns them into [`Gesture`]s, which the
+//! program picks up with [`GestureDetector::events`] whenever it's ready.
+//!
+//! All time limits are counted in measurements and tuned for an output data
+//! rate of 50 Hz.
+
+use heapless::spsc::Queue;
+use lsm303agr::Measurement;
+
+/// Which way the board is lying, or standing.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Orientation {
+    /// Flat, with the LEDs pointing up.
+    FaceUp,
+    /// Flat, with the LEDs pointing down.
+    FaceDown,
+    /// Standing on its top or bottom edge.
+    Landscape,
+    /// Standing on its left or right edge.
+    Portrait,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Gesture {
+    /// The board has settled in a new orientation.
+    Orientation(Orientation),
+    /// The board has been shaken.
+    Shake,
+    /// The board has been turned over, from face up to face down or the other
+    /// way around.
+    Flip,
+}
+
+/// How long the board has to stay in an orientation before it counts.
+const STABLE_SAMPLES: u32 = 5;
+/// How strongly gravity has to pull along an axis for the board to be
+/// oriented along it, in mg.
+const ORIENTATION_MG: i32 = 700;
+/// Anything above this is the board being moved rather than lying still, in
+/// mg.
+const STILL_MAX_MG: i32 = 1300;
+/// A shake is several jolts harder than this, in mg...
+const JOLT_MG: i32 = 2000;
+const SHAKE_JOLTS: u32 = 3;
+/// ...within this many measurements.
+const SHAKE_SAMPLES: u32 = 25;
+/// How long the board may spend on its edge while being turned over.
+const FLIP_SAMPLES: u32 = 50;
+
+pub struct GestureDetector {
+    events: Queue<Gesture, 8>,
+    orientation: Option<Orientation>,
+    candidate: Option<Orientation>,
+    candidate_samples: u32,
+    /// The last face up or face down orientation, and how long ago the board
+    /// left it.
+    last_face: Option<(Orientation, u32)>,
+    jolts: u32,
+    shake_samples: u32,
+}
+
+impl GestureDetector {
+    pub const fn new() -> GestureDetector {
+        GestureDetector {
+            events: Queue::new(),
+            orientation: None,
+            candidate: None,
+            candidate_samples: 0,
+            last_face: None,
+            jolts: 0,
+            shake_samples: 0,
+        }
+    }
+
+    /// Feed a new accelerometer measurement, in mg.
+    pub fn update(&mut self, measurement: Measurement) {
+        let (x, y, z) = (measurement.x, measurement.y, measurement.z);
+        let magnitude_squared = x * x + y * y + z * z;
+
+        self.detect_shake(magnitude_squared);
+        if magnitude_squared <= STILL_MAX_MG * STILL_MAX_MG {
+            self.detect_orientation(x, y, z);
+        }
+        if let Some((face, since)) = self.last_face.as_mut() {
+            if self.orientation == Some(*face) {
+                *since = 0;
+            } else {
+                *since = since.saturating_add(1);
+            }
+        }
+    }
+
+    /// Every gesture recognized since the last call, oldest first.
+    pub fn events(&mut self) -> impl Iterator<Item = Gesture> + '_ {
+        core::iter::from_fn(move || self.events.dequeue())
+    }
+
+    fn detect_shake(&mut self, magnitude_squared: i32) {
+        if self.jolts > 0 {
+            self.shake_samples += 1;
+            if self.shake_samples > SHAKE_SAMPLES {
+                self.jolts = 0;
+            }
+        }
+        if magnitude_squared > JOLT_MG * JOLT_MG {
+            if self.jolts == 0 {
+                self.shake_samples = 0;
+            }
+            self.jolts += 1;
+            if self.jolts == SHAKE_JOLTS {
+                self.push(Gesture::Shake);
+            }
+        }
+    }
+
+    fn detect_orientation(&mut self, x: i32, y: i32, z: i32) {
+        // The LSM303AGR sits on the back of the board, so its Z axis points
+        // away from the LEDs
+        let candidate = if z < -ORIENTATION_MG {
+            Some(Orientation::FaceUp)
+        } else if z > ORIENTATION_MG {
+            Some(Orientation::FaceDown)
+        } else if y.abs() > ORIENTATION_MG {
+            Some(Orientation::Landscape)
+        } else if x.abs() > ORIENTATION_MG {
+            Some(Orientation::Portrait)
+        } else {
+            None
+        };
+
+        if candidate != self.candidate {
+            self.candidate = candidate;
+            self.candidate_samples = 0;
+        }
+        self.candidate_samples = self.candidate_samples.saturating_add(1);
+
+        let orientation = match candidate {
+            Some(orientation) if self.candidate_samples == STABLE_SAMPLES => orientation,
+            _ => return,
+        };
+        if self.orientation == Some(orientation) {
+            return;
+        }
+        self.orientation = Some(orientation);
+        self.push(Gesture::Orientation(orientation));
+
+        if let Orientation::FaceUp | Orientation::FaceDown = orientation {
+            if let Some((face, since)) = self.last_face {
+                if face != orientation && since <= FLIP_SAMPLES {
+                    self.push(Gesture::Flip);
+                }
+            }
+            self.last_face = Some((orientation, 0));
+        }
+    }
+
+    fn push(&mut self, gesture: Gesture) {
+        // If nobody is listening, forgetting the gesture is the best we can
+        // do
+        self.events.enqueue(gesture).ok();
+    }
+}
+
+impl Default for GestureDetector {
+    fn default() -> GestureDetector {
+        GestureDetector::new()
+    }
+}
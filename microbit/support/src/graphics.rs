@@ -0,0 +1,58 @@
+//! [`embedded-graphics`](embedded_graphics) support for the LED matrix.
+//!
+//! Draw onto a [`Canvas`] using any of the `embedded-graphics` primitives,
+//! then put it on the display with [`Canvas::show`]. The canvas is 5x5
+//! pixels with the origin in the top left corner, matching the `leds[row][col]`
+//! layout used everywhere else.
+
+use crate::display::display_leds;
+use core::convert::{Infallible, TryFrom};
+use embedded_graphics::pixelcolor::BinaryColor;
+use embedded_graphics::prelude::*;
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Canvas {
+    leds: [[u8; 5]; 5],
+}
+
+impl Canvas {
+    pub const fn new() -> Canvas {
+        Canvas { leds: [[0; 5]; 5] }
+    }
+
+    /// The pixels drawn so far, as on (1) / off (0) LEDs.
+    pub fn leds(&self) -> [[u8; 5]; 5] {
+        self.leds
+    }
+
+    /// Show the canvas on the interrupt driven display.
+    pub fn show(&self) {
+        display_leds(self.leds);
+    }
+}
+
+impl OriginDimensions for Canvas {
+    fn size(&self) -> Size {
+        Size::new(5, 5)
+    }
+}
+
+impl DrawTarget for Canvas {
+    type Color = BinaryColor;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(point, color) in pixels {
+            // Anything drawn outside of the matrix is simply clipped
+            if let (Ok(col @ 0..=4), Ok(row @ 0..=4)) =
+                (usize::try_from(point.x), usize::try_from(point.y))
+            {
+                self.leds[row][col] = color.is_on() as u8;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,285 @@
+//! A serial link between two micro:bits over a single wire.
+//!
+//! A serial port usually takes two wires, one for each direction. Two boards
+//! can share one instead, as long as they take turns: that's *half duplex*.
+//! The catch is that both can drive the wire. If one drove it high while the
+//! other drove it low, that would be a short, so neither ever drives it high.
+//! The pin is *open drain*: it pulls the wire low for a 0, and lets go for a
+//! 1, and a pull-up resistor takes the wire back up when nobody pulls it
+//! down. A wire that somebody pulls low stays low, whatever the other end
+//! does, and nothing gets hurt.
+//!
+//! The UARTE's receiver is connected to the pin all the time. Its
+//! transmitter only gets the pin while there's something to send, which
+//! changes the pin's role at runtime: the pins of a UARTE can only be changed
+//! while it's disabled, so every [`HalfDuplex::send`] disables it, connects
+//! the transmitter, sends, and takes the transmitter off the pin again. In
+//! between, a board can't hold the wire low by mistake, even if its program
+//! stops in the middle of a byte.
+//!
+//! Since the receiver stays on the pin while sending, a board hears its own
+//! bytes come back. That's how it notices a *collision*, both boards sending
+//! at once: the wire carries the AND of the two, which isn't what either of
+//! them sent, so the echo doesn't match. It can't be prevented entirely, but
+//! nobody starts sending while the wire is busy, and whoever notices a
+//! collision waits for a random while before trying again, so the two don't
+//! collide again and again.
+//!
+//! What goes over the wire are the [`packet`]s of
+//! the other links, with their length and CRC. Their sequence number pairs a
+//! response with its request.
+//!
+//! Connect ring 0 of the two boards with a wire, and GND with GND. The pins'
+//! pull-ups are weak, about 13 kΩ, so the wire takes a moment to go back up
+//! after a 0: a short wire is fine up to 115200 baud, a long one wants less.
+
+use core::sync::atomic::{compiler_fence, Ordering};
+use discovery_logic::packet::{
+    self, types, DecodeError, Packet, CRC_LEN, HEADER_LEN, MAX_PAYLOAD_LEN,
+};
+use embedded_hal_02::timer::CountDown;
+use microbit::hal::gpio::{Level, OpenDrain, OpenDrainConfig, Output, Pin, Port};
+use microbit::hal::timer::{self, OneShot, Timer};
+use microbit::hal::uarte::{Instance, Parity};
+use microbit::pac::{p0, P0, P1};
+
+use crate::serial::SerialConfig;
+
+/// The longest packet, with the longest payload.
+const PACKET_LEN: usize = HEADER_LEN + MAX_PAYLOAD_LEN + CRC_LEN;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The other board was sending, so nothing was sent.
+    Busy,
+    /// What came back while sending wasn't what was sent, the other board
+    /// must have started sending too. Try again after a random while.
+    Collision,
+    /// Nothing arrived in time, or only part of a packet.
+    Timeout,
+    /// A byte arrived without its stop bit, or broke off in the middle.
+    Framing,
+    /// The payload doesn't fit into a packet.
+    TooLong,
+    /// A whole packet arrived, but its CRC or length is wrong.
+    Packet(DecodeError),
+}
+
+/// One end of a single wire serial link.
+pub struct HalfDuplex<U: Instance, T: timer::Instance> {
+    uarte: U,
+    timer: Timer<T, OneShot>,
+    pin: Pin<Output<OpenDrain>>,
+    /// How long a byte takes on the wire, in µs, with its start and stop bit.
+    byte_us: u32,
+    /// EasyDMA can only get at RAM, so packets are put together in here.
+    tx: [u8; PACKET_LEN],
+    rx: [u8; PACKET_LEN],
+}
+
+impl<U: Instance, T: timer::Instance> HalfDuplex<U, T> {
+    /// A link on `pin` at `bps` baud, with `timer` for the timeouts.
+    ///
+    /// # Panics
+    ///
+    /// If the UARTE can't run at `bps`.
+    pub fn new<MODE>(uarte: U, timer: T, pin: Pin<MODE>, bps: u32) -> HalfDuplex<U, T> {
+        let baudrate = SerialConfig::baudrate_from_bps(bps).expect("not a baud rate the UARTE has");
+        let pin = pin.into_open_drain_output(OpenDrainConfig::Standard0Disconnect1, Level::High);
+        // The HAL leaves the input of an output disconnected, and no pull-up
+        // either, but both ends have to hear the wire, and it has to go back
+        // up by itself
+        port(&pin).pin_cnf[pin.pin() as usize].modify(|_, w| {
+            w.input().connect();
+            w.pull().pullup()
+        });
+
+        uarte.enable.write(|w| w.enable().disabled());
+        uarte.psel.txd.write(|w| w.connect().disconnected());
+        uarte.psel.cts.write(|w| w.connect().disconnected());
+        uarte.psel.rts.write(|w| w.connect().disconnected());
+        uarte.psel.rxd.write(|w| {
+            unsafe { w.bits(pin.psel_bits()) };
+            w.connect().connected()
+        });
+        uarte.baudrate.write(|w| w.baudrate().variant(baudrate));
+        uarte
+            .config
+            .write(|w| w.hwfc().disabled().parity().variant(Parity::EXCLUDED));
+        uarte.enable.write(|w| w.enable().enabled());
+
+        HalfDuplex {
+            uarte,
+            timer: Timer::one_shot(timer),
+            pin,
+            byte_us: 10 * 1_000_000 / bps + 1,
+            tx: [0; PACKET_LEN],
+            rx: [0; PACKET_LEN],
+        }
+    }
+
+    /// Send `packet`, unless the wire is busy, and check that it went out
+    /// the way it was sent.
+    pub fn send(&mut self, packet: &Packet) -> Result<(), Error> {
+        let len = packet::encode(packet, &mut self.tx)
+            .map_err(|_| Error::TooLong)?
+            .len();
+
+        // Nobody starts in the middle of somebody else's packet: the wire has
+        // to stay high for a whole byte first, longer than the pause between
+        // two bytes of a packet
+        self.timer.start(self.byte_us);
+        while self.timer.wait().is_err() {
+            if self.line_is_low() {
+                return Err(Error::Busy);
+            }
+        }
+
+        self.connect_transmitter(true);
+        self.start_rx(0, len);
+        let tx = &self.tx[..len];
+        self.uarte.events_endtx.reset();
+        self.uarte
+            .txd
+            .ptr
+            .write(|w| unsafe { w.ptr().bits(tx.as_ptr() as u32) });
+        self.uarte
+            .txd
+            .maxcnt
+            .write(|w| unsafe { w.maxcnt().bits(len as _) });
+        compiler_fence(Ordering::SeqCst);
+        self.uarte.tasks_starttx.write(|w| unsafe { w.bits(1) });
+
+        // The last byte of the echo comes back right after it's been sent,
+        // a couple of bytes is plenty of slack
+        let echoed = self.wait_rx((len as u32 + 2) * self.byte_us);
+        self.uarte.tasks_stoptx.write(|w| unsafe { w.bits(1) });
+        while self.uarte.events_txstopped.read().bits() == 0 {}
+        self.uarte.events_txstopped.reset();
+        self.uarte.events_txstarted.reset();
+        self.uarte.events_endtx.reset();
+        self.stop_rx();
+        self.connect_transmitter(false);
+
+        // Whatever went wrong with the echo, the cause is the same
+        match echoed {
+            Ok(()) if self.rx[..len] == self.tx[..len] => Ok(()),
+            _ => Err(Error::Collision),
+        }
+    }
+
+    /// Wait for the next packet, for up to `timeout_us`.
+    pub fn receive(&mut self, timeout_us: u32) -> Result<Packet<'_>, Error> {
+        // The header says how long the rest is, the receiver keeps going
+        // while the second half gets set up
+        self.start_rx(0, HEADER_LEN);
+        let header = self.wait_rx(timeout_us);
+        let result = header.and_then(|()| {
+            let rest = usize::from(self.rx[1]) + CRC_LEN;
+            self.start_rx(HEADER_LEN, rest);
+            self.wait_rx((rest as u32 + 2) * self.byte_us)
+                .map(|()| HEADER_LEN + rest)
+        });
+        self.stop_rx();
+        let len = result?;
+        packet::decode(&self.rx[..len]).map_err(Error::Packet)
+    }
+
+    /// Send `request`, and wait for the [`RESPONSE`](types::RESPONSE) with
+    /// the same sequence number, for up to `timeout_us` for every packet.
+    /// Any other packets are left out.
+    pub fn request(&mut self, request: &Packet, timeout_us: u32) -> Result<Packet<'_>, Error> {
+        self.send(request)?;
+        loop {
+            let response = self.receive(timeout_us)?;
+            if response.packet_type != types::RESPONSE || response.sequence != request.sequence {
+                continue;
+            }
+            // The packet borrows the buffer, and can't leave the loop like
+            // that, so it's decoded again
+            let len = HEADER_LEN + response.payload.len() + CRC_LEN;
+            return packet::decode(&self.rx[..len]).map_err(Error::Packet);
+        }
+    }
+
+    /// Stop using the UARTE, and give back what the link was made from.
+    pub fn free(self) -> (U, T, Pin<Output<OpenDrain>>) {
+        self.uarte.enable.write(|w| w.enable().disabled());
+        self.uarte.psel.rxd.write(|w| w.connect().disconnected());
+        (self.uarte, self.timer.free(), self.pin)
+    }
+
+    fn line_is_low(&self) -> bool {
+        port(&self.pin).in_.read().bits() & 1 << self.pin.pin() == 0
+    }
+
+    /// Put the transmitter on the pin, or take it off.
+    fn connect_transmitter(&mut self, connect: bool) {
+        // The pins can only be changed while the UARTE is disabled
+        self.uarte.enable.write(|w| w.enable().disabled());
+        if connect {
+            let psel = self.pin.psel_bits();
+            self.uarte.psel.txd.write(|w| {
+                unsafe { w.bits(psel) };
+                w.connect().connected()
+            });
+        } else {
+            self.uarte.psel.txd.write(|w| w.connect().disconnected());
+        }
+        self.uarte.enable.write(|w| w.enable().enabled());
+    }
+
+    /// Receive the next `len` bytes into `rx` from `start` on.
+    fn start_rx(&mut self, start: usize, len: usize) {
+        let buf = &mut self.rx[start..start + len];
+        self.uarte.events_endrx.reset();
+        self.uarte
+            .rxd
+            .ptr
+            .write(|w| unsafe { w.ptr().bits(buf.as_mut_ptr() as u32) });
+        self.uarte
+            .rxd
+            .maxcnt
+            .write(|w| unsafe { w.maxcnt().bits(len as _) });
+        compiler_fence(Ordering::SeqCst);
+        self.uarte.tasks_startrx.write(|w| unsafe { w.bits(1) });
+    }
+
+    /// Wait for up to `timeout_us` for the bytes `start_rx` asked for.
+    fn wait_rx(&mut self, timeout_us: u32) -> Result<(), Error> {
+        self.timer.start(timeout_us);
+        while self.uarte.events_endrx.read().bits() == 0 {
+            if self.uarte.events_error.read().bits() != 0 {
+                return Err(Error::Framing);
+            }
+            if self.timer.wait().is_ok() {
+                return Err(Error::Timeout);
+            }
+        }
+        compiler_fence(Ordering::SeqCst);
+        Ok(())
+    }
+
+    /// Stop receiving, and forget whatever went wrong.
+    fn stop_rx(&mut self) {
+        self.uarte.events_rxto.reset();
+        self.uarte.tasks_stoprx.write(|w| unsafe { w.bits(1) });
+        while self.uarte.events_rxto.read().bits() == 0 {}
+        self.uarte.events_rxto.reset();
+        self.uarte.events_endrx.reset();
+        self.uarte.events_rxstarted.reset();
+        self.uarte.events_error.reset();
+        let errors = self.uarte.errorsrc.read().bits();
+        self.uarte.errorsrc.write(|w| unsafe { w.bits(errors) });
+    }
+}
+
+/// The GPIO port `pin` is on.
+fn port<MODE>(pin: &Pin<MODE>) -> &'static p0::RegisterBlock {
+    let port = match pin.port() {
+        Port::Port0 => P0::ptr(),
+        Port::Port1 => P1::ptr(),
+    };
+    // Only the pin's own bits are ever touched
+    unsafe { &*port }
+}
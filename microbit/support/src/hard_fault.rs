@@ -0,0 +1,119 @@
+//! Saying what went wrong after a HardFault, and showing that it happened.
+//!
+//! A HardFault is what the CPU raises when it can't carry on: a read from an
+//! address where there's no memory, a jump to somewhere that isn't code, a
+//! stack that ran out. `cortex-m-rt`'s own handler then loops forever, and the
+//! board looks as if it had died. [`report`] prints everything there is to
+//! know instead, and then keeps blinking the whole matrix, three quick
+//! flashes and a pause, so that there's something to see without a debugger
+//! too. That's not the sad face of a panic, so the two don't get mixed up.
+//!
+//! On entry to the handler, the CPU has *stacked* eight registers of the code
+//! that faulted, `r0` to `r3`, `r12`, `lr`, `pc` and `xpsr`, and the handler
+//! gets them as an [`ExceptionFrame`]. `pc` is the instruction that faulted,
+//! or the one after it, and `lr` usually is where the function it's in was
+//! called from. On the micro:bit v2, the fault status registers say why, see
+//! [`discovery_logic::fault_status`].
+//!
+//! The program itself has no idea what the functions at those addresses are
+//! called, that's only in the ELF file on the computer. The last line of the
+//! report is a command for the `symbolize` tool in the `tools` directory,
+//! which looks them up in there.
+//!
+//! A program only has one `HardFault` handler, so the one that calls
+//! [`report`] only comes with the `hard-fault` feature. A program with a
+//! handler of its own can call [`report`] from it.
+
+use crate::println;
+use cortex_m::interrupt;
+#[cfg(feature = "hard-fault")]
+use cortex_m_rt::exception;
+use cortex_m_rt::ExceptionFrame;
+use microbit::{
+    board::Board,
+    display::blocking::Display,
+    hal::{prelude::*, Timer},
+    pac,
+};
+
+/// All the LEDs.
+const ALL: [[u8; 5]; 5] = [[1; 5]; 5];
+/// How many flashes there are before every pause.
+const FLASHES: u32 = 3;
+
+#[cfg(feature = "hard-fault")]
+#[exception]
+unsafe fn HardFault(frame: &ExceptionFrame) -> ! {
+    report(frame)
+}
+
+/// Print the registers in `frame`, and why the CPU faulted, then blink the
+/// matrix forever. Meant to be called from a `HardFault` handler.
+pub fn report(frame: &ExceptionFrame) -> ! {
+    interrupt::disable();
+
+    println!("HardFault at pc {:#010x}", frame.pc());
+    println!(
+        "  r0  {:#010x}  r1 {:#010x}  r2 {:#010x}  r3   {:#010x}",
+        frame.r0(),
+        frame.r1(),
+        frame.r2(),
+        frame.r3()
+    );
+    println!(
+        "  r12 {:#010x}  lr {:#010x}  pc {:#010x}  xpsr {:#010x}",
+        frame.r12(),
+        frame.lr(),
+        frame.pc(),
+        frame.xpsr()
+    );
+    #[cfg(feature = "v2")]
+    print_status();
+    println!(
+        "  symbolize: cargo run --manifest-path ../../tools/symbolize/Cargo.toml -- <elf> {:#x} {:#x}",
+        frame.pc(),
+        frame.lr()
+    );
+
+    blink()
+}
+
+/// Print the fault status registers, with what their bits mean.
+#[cfg(feature = "v2")]
+fn print_status() {
+    use discovery_logic::fault_status::{bfar_valid, cfsr_flags, hfsr_flags, mmfar_valid};
+
+    // Only read here, and nothing else runs anymore
+    let scb = unsafe { &*pac::SCB::PTR };
+    let (cfsr, hfsr) = (scb.cfsr.read(), scb.hfsr.read());
+    println!("  CFSR {:#010x}  HFSR {:#010x}", cfsr, hfsr);
+    for flag in cfsr_flags(cfsr).chain(hfsr_flags(hfsr)) {
+        println!("    {}: {}", flag.name, flag.meaning);
+    }
+    if bfar_valid(cfsr) {
+        println!("  BFAR {:#010x}", scb.bfar.read());
+    }
+    if mmfar_valid(cfsr) {
+        println!("  MMFAR {:#010x}", scb.mmfar.read());
+    }
+}
+
+/// Three quick flashes of all the LEDs, and a pause, over and over.
+fn blink() -> ! {
+    // Whoever owned the peripherals before isn't going to run again, so it
+    // is safe to take them back and start from scratch. The display can't
+    // use its interrupt anymore, so it's the blocking one.
+    let board = Board::new(unsafe { pac::Peripherals::steal() }, unsafe {
+        pac::CorePeripherals::steal()
+    });
+    let mut timer = Timer::new(board.TIMER0);
+    let mut display = Display::new(board.display_pins);
+    loop {
+        for _ in 0..FLASHES {
+            display.show(&mut timer, ALL, 100);
+            display.clear();
+            timer.delay_ms(100u32);
+        }
+        timer.delay_ms(1_000u32);
+    }
+}
@@ -0,0 +1,244 @@
+//! Helpers for working with an I2C bus.
+//!
+//! When a sensor doesn't answer, the first question is whether it is on the
+//! bus at all, and at which address. `i2c_scan` asks every address in turn:
+//! a device that is there acknowledges its address, for every other address
+//! the transfer fails.
+//!
+//! A driver usually wants to own the bus it talks over, which is a problem as
+//! soon as there is a second device on the same bus. `RefCellDevice` and
+//! `CriticalSectionDevice` each give every driver its own handle to a bus that
+//! is shared between them. They work like the types of the same name in
+//! `embedded-hal-bus`, but for `embedded-hal` 0.2, which the HALs and the
+//! drivers used in this book still implement. Drivers on 1.0 can share a bus
+//! wrapped in a [`Compat`](crate::compat::Compat) with `embedded-hal-bus`
+//! itself.
+//!
+//! A loose wire on the edge connector can leave a device holding SDA low, and
+//! then every transfer after that fails, see [`bus_recover`].
+
+use crate::delay::CPU_HZ;
+use core::cell::RefCell;
+use cortex_m::asm;
+use cortex_m::interrupt::{free, Mutex};
+use embedded_hal::i2c::I2c;
+use embedded_hal_02::blocking::i2c::{Read, Write, WriteRead};
+use embedded_hal_02::digital::v2::{InputPin, OutputPin};
+use microbit::hal::gpio::{Disconnected, Level, OpenDrainConfig, Pin};
+
+#[cfg(feature = "v1")]
+use microbit::hal::twi::{Frequency, Instance, Pins, Twi as Bus};
+#[cfg(feature = "v2")]
+use microbit::hal::twim::{Frequency, Instance, Pins, Twim as Bus};
+
+/// The lowest and highest 7 bit address that isn't reserved by the I2C
+/// specification.
+pub const FIRST_ADDRESS: u8 = 0x08;
+pub const LAST_ADDRESS: u8 = 0x77;
+
+/// All addresses between [`FIRST_ADDRESS`] and [`LAST_ADDRESS`] that a device
+/// answers to, lowest first.
+///
+/// Each address is probed by reading a single byte from it. That's harmless
+/// for the devices found on the micro:bit, but keep in mind that some devices
+/// do react to being read from. The HAL's bus has to be wrapped in a
+/// [`Compat`](crate::compat::Compat) for this.
+pub fn i2c_scan<I: I2c>(i2c: &mut I) -> impl Iterator<Item = u8> + '_ {
+    (FIRST_ADDRESS..=LAST_ADDRESS).filter(move |&address| i2c.read(address, &mut [0]).is_ok())
+}
+
+/// What is most likely behind `address` on one of the micro:bit's own buses.
+pub fn device_name(address: u8) -> Option<&'static str> {
+    match address {
+        0x0E => Some("MAG3110 magnetometer (v1)"),
+        0x19 => Some("LSM303AGR accelerometer"),
+        0x1D => Some("MMA8653 accelerometer (v1)"),
+        0x1E => Some("LSM303AGR magnetometer"),
+        0x70 => Some("interface MCU (v2)"),
+        _ => None,
+    }
+}
+
+/// How many clock pulses [`bus_recover`] sends at most. A device in the middle
+/// of sending a byte lets go of SDA after 9: the rest of the byte, and the
+/// acknowledge bit.
+const RECOVERY_PULSES: usize = 9;
+/// Half a clock at 100 kHz.
+const HALF_CLOCK_CYCLES: u32 = CPU_HZ / 200_000;
+
+/// What [`bus_recover`] found.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum BusState {
+    /// Nothing held SDA low, the problem is somewhere else.
+    Idle,
+    /// A device held SDA low, and let go of it.
+    Released,
+    /// SDA is still low after all the clock pulses. Something holds it there
+    /// for good, like a wire to GND.
+    Stuck,
+}
+
+/// Get a bus going again that a device holds SDA low on.
+///
+/// That happens when a transfer stops halfway through a byte the device
+/// sends, because a wire came loose, or the micro:bit was reset. The device
+/// is still waiting for the clock pulses for the rest of its byte, and until
+/// it gets them, it holds SDA low whenever one of its bits is a 0. No START
+/// gets through that, so every transfer fails, with nothing wrong with the
+/// wiring anymore.
+///
+/// This takes the pins away from the peripheral, and if SDA is low, pulses
+/// SCL by hand until the device lets go. A STOP after that tells every device
+/// that the transfer is over, and then the peripheral gets its pins back, set
+/// up just like before. Call it when a transfer fails, and try again.
+pub fn bus_recover<T: Instance>(bus: Bus<T>, frequency: Frequency) -> (Bus<T>, BusState) {
+    let peripheral = bus.free();
+    // While it's disabled, the pins do what the GPIO registers say
+    peripheral.enable.write(|w| w.enable().disabled());
+    let (scl, sda) = pin_bits(&peripheral);
+    // The peripheral was the only one using these pins, and it's off now
+    let mut scl = unsafe { Pin::<Disconnected>::from_psel_bits(scl) }
+        .into_open_drain_output(OpenDrainConfig::Standard0Disconnect1, Level::High);
+    let sda = unsafe { Pin::<Disconnected>::from_psel_bits(sda) }.into_pullup_input();
+    asm::delay(HALF_CLOCK_CYCLES);
+
+    let state = if sda.is_high().unwrap() {
+        BusState::Idle
+    } else {
+        for _ in 0..RECOVERY_PULSES {
+            scl.set_low().unwrap();
+            asm::delay(HALF_CLOCK_CYCLES);
+            scl.set_high().unwrap();
+            asm::delay(HALF_CLOCK_CYCLES);
+            if sda.is_high().unwrap() {
+                break;
+            }
+        }
+        if sda.is_high().unwrap() {
+            BusState::Released
+        } else {
+            BusState::Stuck
+        }
+    };
+
+    // A STOP is SDA going high while SCL is high
+    scl.set_low().unwrap();
+    let mut sda = sda.into_open_drain_output(OpenDrainConfig::Standard0Disconnect1, Level::Low);
+    asm::delay(HALF_CLOCK_CYCLES);
+    scl.set_high().unwrap();
+    asm::delay(HALF_CLOCK_CYCLES);
+    sda.set_high().unwrap();
+    asm::delay(HALF_CLOCK_CYCLES);
+
+    let pins = Pins {
+        scl: scl.into_floating_input(),
+        sda: sda.into_floating_input(),
+    };
+    (Bus::new(peripheral, pins, frequency), state)
+}
+
+/// The pins `peripheral` uses.
+#[cfg(feature = "v1")]
+fn pin_bits<T: Instance>(peripheral: &T) -> (u32, u32) {
+    (
+        peripheral.pselscl.read().bits(),
+        peripheral.pselsda.read().bits(),
+    )
+}
+
+/// The pins `peripheral` uses, with their ports, but without the bit that
+/// says they are connected.
+#[cfg(feature = "v2")]
+fn pin_bits<T: Instance>(peripheral: &T) -> (u32, u32) {
+    const PIN_AND_PORT: u32 = 0x3F;
+    (
+        peripheral.psel.scl.read().bits() & PIN_AND_PORT,
+        peripheral.psel.sda.read().bits() & PIN_AND_PORT,
+    )
+}
+
+/// A handle to a bus shared through a `RefCell`.
+///
+/// This is the cheapest way to share a bus, but all handles have to be used
+/// from the same context: if an interrupt handler uses the bus while the main
+/// loop is in the middle of a transfer, the `RefCell` is already borrowed and
+/// the program panics. Use [`CriticalSectionDevice`] for that.
+pub struct RefCellDevice<'b, I> {
+    bus: &'b RefCell<I>,
+}
+
+impl<'b, I> RefCellDevice<'b, I> {
+    pub fn new(bus: &'b RefCell<I>) -> RefCellDevice<'b, I> {
+        RefCellDevice { bus }
+    }
+}
+
+impl<I: Read> Read for RefCellDevice<'_, I> {
+    type Error = I::Error;
+
+    fn read(&mut self, address: u8, buffer: &mut [u8]) -> Result<(), I::Error> {
+        self.bus.borrow_mut().read(address, buffer)
+    }
+}
+
+impl<I: Write> Write for RefCellDevice<'_, I> {
+    type Error = I::Error;
+
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), I::Error> {
+        self.bus.borrow_mut().write(address, bytes)
+    }
+}
+
+impl<I: WriteRead> WriteRead for RefCellDevice<'_, I> {
+    type Error = I::Error;
+
+    fn write_read(&mut self, address: u8, bytes: &[u8], buffer: &mut [u8]) -> Result<(), I::Error> {
+        self.bus.borrow_mut().write_read(address, bytes, buffer)
+    }
+}
+
+/// A handle to a bus shared through a critical section, so it can also be used
+/// from interrupt handlers.
+///
+/// Every transfer runs with interrupts disabled. On a 100 kHz bus, a transfer
+/// of a few bytes takes a couple of hundred microseconds, make sure nothing
+/// else has to react faster than that.
+pub struct CriticalSectionDevice<'b, I> {
+    bus: &'b Mutex<RefCell<I>>,
+}
+
+impl<'b, I> CriticalSectionDevice<'b, I> {
+    pub fn new(bus: &'b Mutex<RefCell<I>>) -> CriticalSectionDevice<'b, I> {
+        CriticalSectionDevice { bus }
+    }
+}
+
+impl<I: Read> Read for CriticalSectionDevice<'_, I> {
+    type Error = I::Error;
+
+    fn read(&mut self, address: u8, buffer: &mut [u8]) -> Result<(), I::Error> {
+        free(|cs| self.bus.borrow(cs).borrow_mut().read(address, buffer))
+    }
+}
+
+impl<I: Write> Write for CriticalSectionDevice<'_, I> {
+    type Error = I::Error;
+
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), I::Error> {
+        free(|cs| self.bus.borrow(cs).borrow_mut().write(address, bytes))
+    }
+}
+
+impl<I: WriteRead> WriteRead for CriticalSectionDevice<'_, I> {
+    type Error = I::Error;
+
+    fn write_read(&mut self, address: u8, bytes: &[u8], buffer: &mut [u8]) -> Result<(), I::Error> {
+        free(|cs| {
+            self.bus
+                .borrow(cs)
+                .borrow_mut()
+                .write_read(address, bytes, buffer)
+        })
+    }
+}
@@ -0,0 +1,146 @@
+//! Being the device on an I2C bus, instead of the controller.
+//!
+//! The `TWIS` peripheral answers to an address of its own, and moves the
+//! bytes by EasyDMA like the `TWIM` does. What it can't do is know what to
+//! answer, so whenever a controller addresses us, it holds the clock line low
+//! until the CPU has a buffer ready. The controller waits that long, that's
+//! what I2C calls *clock stretching*, as long as [`I2cTarget::poll`] gets
+//! called often enough. A millisecond or so is fine for any controller.
+//!
+//! A write that is followed by a read without a stop in between, what
+//! `write_read` does, is handed over before the read starts, so that the
+//! read can depend on it.
+//!
+//! The HAL's `Twis` does the same with blocking calls, but those wait for the
+//! end of the transaction, which a `write_read` only gets to once its read
+//! has been answered. It also only configures pins on port 0, and SDA on the
+//! edge connector is P1.00.
+
+use core::sync::atomic::{compiler_fence, Ordering};
+use microbit::hal::gpio::{Floating, Input, Pin, Port};
+use microbit::hal::twis::Pins;
+use microbit::pac::{P0, P1, TWIS0};
+
+/// The longest write we take in, and the longest read we answer. Whatever a
+/// controller writes past that is refused, and it reads `0xFF` past it.
+pub const BUFFER_LEN: usize = 32;
+
+/// What a controller reads once we run out of bytes.
+const OVER_READ_CHARACTER: u8 = 0xFF;
+
+/// What a controller did.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Event<'a> {
+    /// It wrote these bytes.
+    Written(&'a [u8]),
+    /// It wants to read. Answer with [`I2cTarget::answer`].
+    Read,
+}
+
+pub struct I2cTarget {
+    twis: TWIS0,
+    rx: [u8; BUFFER_LEN],
+    tx: [u8; BUFFER_LEN],
+    /// Whether `rx` is being written to, and has to be handed over once the
+    /// write is done.
+    writing: bool,
+}
+
+impl I2cTarget {
+    /// Answer to `address` on the bus with `pins`.
+    pub fn new(twis: TWIS0, pins: Pins, address: u8) -> I2cTarget {
+        configure(&pins.scl);
+        configure(&pins.sda);
+        twis.psel
+            .scl
+            .write(|w| unsafe { w.bits(pins.scl.psel_bits()) });
+        twis.psel
+            .sda
+            .write(|w| unsafe { w.bits(pins.sda.psel_bits()) });
+        twis.address[0].write(|w| unsafe { w.address().bits(address) });
+        twis.config.write(|w| w.address0().enabled());
+        twis.orc
+            .write(|w| unsafe { w.orc().bits(OVER_READ_CHARACTER) });
+        // Hold the bus as soon as a write or a read comes in
+        twis.shorts
+            .write(|w| w.write_suspend().enabled().read_suspend().enabled());
+        twis.enable.write(|w| w.enable().enabled());
+
+        I2cTarget {
+            twis,
+            rx: [0; BUFFER_LEN],
+            tx: [0; BUFFER_LEN],
+            writing: false,
+        }
+    }
+
+    /// See what the controller is up to. Anything but `None` has to be dealt
+    /// with right away, the bus is held until then.
+    pub fn poll(&mut self) -> Option<Event<'_>> {
+        let twis = &self.twis;
+        let ended = twis.events_read.read().bits() != 0
+            || twis.events_write.read().bits() != 0
+            || twis.events_stopped.read().bits() != 0;
+        if self.writing && ended {
+            // A write is done once anything else happens on the bus
+            self.writing = false;
+            twis.events_stopped.reset();
+            compiler_fence(Ordering::Acquire);
+            let amount = twis.rxd.amount.read().bits() as usize;
+            return Some(Event::Written(&self.rx[..amount]));
+        }
+        twis.events_stopped.reset();
+
+        if twis.events_write.read().bits() != 0 {
+            twis.events_write.reset();
+            self.writing = true;
+            compiler_fence(Ordering::Release);
+            let rx = self.rx.as_mut_ptr() as u32;
+            twis.rxd.ptr.write(|w| unsafe { w.ptr().bits(rx) });
+            twis.rxd
+                .maxcnt
+                .write(|w| unsafe { w.maxcnt().bits(BUFFER_LEN as _) });
+            twis.tasks_preparerx.write(|w| unsafe { w.bits(1) });
+            twis.tasks_resume.write(|w| unsafe { w.bits(1) });
+            return None;
+        }
+        if twis.events_read.read().bits() != 0 {
+            twis.events_read.reset();
+            return Some(Event::Read);
+        }
+        None
+    }
+
+    /// Let the controller read `bytes`, up to [`BUFFER_LEN`] of them.
+    pub fn answer(&mut self, bytes: &[u8]) {
+        let len = bytes.len().min(BUFFER_LEN);
+        self.tx[..len].copy_from_slice(&bytes[..len]);
+        compiler_fence(Ordering::Release);
+        let tx = self.tx.as_ptr() as u32;
+        self.twis.txd.ptr.write(|w| unsafe { w.ptr().bits(tx) });
+        self.twis
+            .txd
+            .maxcnt
+            .write(|w| unsafe { w.maxcnt().bits(len as _) });
+        self.twis.tasks_preparetx.write(|w| unsafe { w.bits(1) });
+        self.twis.tasks_resume.write(|w| unsafe { w.bits(1) });
+    }
+}
+
+/// Connect the input, pull the line up and only ever drive it low, like an
+/// I2C bus needs.
+fn configure(pin: &Pin<Input<Floating>>) {
+    let port = match pin.port() {
+        Port::Port0 => P0::ptr(),
+        Port::Port1 => P1::ptr(),
+    };
+    // Safe, since the pin is ours and we only touch its own register
+    unsafe { &(*port).pin_cnf[pin.pin() as usize] }.write(|w| {
+        w.dir().input();
+        w.input().connect();
+        w.pull().pullup();
+        w.drive().s0d1();
+        w.sense().disabled();
+        w
+    });
+}
@@ -0,0 +1,51 @@
+//! Sleeping in the main loop while there's nothing to do.
+//!
+//! A main loop that keeps asking a timer whether it's done yet keeps the CPU
+//! running flat out, even though it has nothing to do most of the time. `wfe`,
+//! *wait for event*, stops the CPU until something happens instead. Any
+//! interrupt that runs counts, like the ones of the display or the buttons.
+//!
+//! With `SEVONPEND` set, which [`init`] does, so does any interrupt that only
+//! becomes *pending* because it is masked in the NVIC. That's how a timer can
+//! wake the CPU without an interrupt handler, see [`wake_on_timer`]. It also
+//! means that nothing gets lost between checking for work and going to sleep:
+//! the event is remembered, and the next `wfe` returns straight away.
+//!
+//! So a main loop checks everything it is waiting for, and then calls
+//! [`sleep`] at the end of every round.
+
+use cortex_m::asm;
+use cortex_m::peripheral::{NVIC, SCB};
+use embedded_hal_02::timer::CountDown;
+use microbit::hal::timer::{self, Timer};
+
+/// Let interrupts that are masked in the NVIC wake up [`sleep`] too.
+pub fn init(scb: &mut SCB) {
+    scb.set_sevonpend();
+}
+
+/// Sleep until an interrupt happens, or until one becomes pending.
+///
+/// Every interrupt wakes the CPU, not only the one a loop is waiting for, so
+/// after waking up it has to check again.
+pub fn sleep() {
+    asm::wfe();
+}
+
+/// Make `timer` wake up [`sleep`] whenever it fires. Its interrupt stays masked
+/// in the NVIC, so no handler is needed, but [`timer_fired`] has to be used
+/// instead of `wait`.
+pub fn wake_on_timer<T: timer::Instance, U>(timer: &mut Timer<T, U>) {
+    timer.enable_interrupt();
+}
+
+/// Whether `timer` has fired, like `timer.wait().is_ok()`.
+pub fn timer_fired<T: timer::Instance, U>(timer: &mut Timer<T, U>) -> bool {
+    if timer.wait().is_err() {
+        return false;
+    }
+    // An interrupt that is already pending can't become pending again, so the
+    // next time the timer fires wouldn't wake anybody up
+    NVIC::unpend(T::INTERRUPT);
+    true
+}
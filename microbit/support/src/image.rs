@@ -0,0 +1,158 @@
+//! An on/off 5x5 bitmap that can be transformed and combined.
+
+use crate::display::MAX_BRIGHTNESS;
+use core::ops::{BitAnd, BitOr, Not};
+use tiny_led_matrix::Render;
+
+/// A 5x5 black and white image.
+///
+/// Rows are stored top first with bit 4 as the leftmost column, the same
+/// layout as the glyphs in [`font5x5`](crate::font5x5).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Image5x5([u8; 5]);
+
+impl Image5x5 {
+    /// Build an image from an array of LEDs, any non-zero value counts as lit.
+    pub const fn new(leds: [[u8; 5]; 5]) -> Image5x5 {
+        let mut rows = [0; 5];
+        let mut row = 0;
+        while row < 5 {
+            let mut col = 0;
+            while col < 5 {
+                if leds[row][col] != 0 {
+                    rows[row] |= 1 << (4 - col);
+                }
+                col += 1;
+            }
+            row += 1;
+        }
+        Image5x5(rows)
+    }
+
+    /// Build an image from five row bitmasks, e.g. a [`font5x5::glyph`](crate::font5x5::glyph).
+    pub const fn from_rows(rows: [u8; 5]) -> Image5x5 {
+        Image5x5([
+            rows[0] & 0x1f,
+            rows[1] & 0x1f,
+            rows[2] & 0x1f,
+            rows[3] & 0x1f,
+            rows[4] & 0x1f,
+        ])
+    }
+
+    pub const fn blank() -> Image5x5 {
+        Image5x5([0; 5])
+    }
+
+    fn from_fn(f: impl Fn(usize, usize) -> bool) -> Image5x5 {
+        let mut image = Image5x5::blank();
+        for row in 0..5 {
+            for col in 0..5 {
+                image.set(row, col, f(row, col));
+            }
+        }
+        image
+    }
+
+    pub fn is_lit(&self, row: usize, col: usize) -> bool {
+        self.0[row] & (1 << (4 - col)) != 0
+    }
+
+    pub fn set(&mut self, row: usize, col: usize, lit: bool) {
+        if lit {
+            self.0[row] |= 1 << (4 - col);
+        } else {
+            self.0[row] &= !(1 << (4 - col));
+        }
+    }
+
+    /// The image as on (1) / off (0) LEDs, ready for the blocking display.
+    pub fn leds(&self) -> [[u8; 5]; 5] {
+        let mut leds = [[0; 5]; 5];
+        for (row, line) in leds.iter_mut().enumerate() {
+            for (col, led) in line.iter_mut().enumerate() {
+                *led = self.is_lit(row, col) as u8;
+            }
+        }
+        leds
+    }
+
+    /// The five row bitmasks, in the same layout [`Image5x5::from_rows`] takes.
+    pub const fn rows(&self) -> [u8; 5] {
+        self.0
+    }
+
+    /// Rotate the image by 90 degrees clockwise.
+    pub fn rotate90(&self) -> Image5x5 {
+        Image5x5::from_fn(|row, col| self.is_lit(4 - col, row))
+    }
+
+    /// Mirror the image left to right.
+    pub fn flip_horizontal(&self) -> Image5x5 {
+        Image5x5::from_fn(|row, col| self.is_lit(row, 4 - col))
+    }
+
+    /// Mirror the image top to bottom.
+    pub fn flip_vertical(&self) -> Image5x5 {
+        Image5x5::from_fn(|row, col| self.is_lit(4 - row, col))
+    }
+
+    /// Swap lit and unlit LEDs.
+    pub fn invert(&self) -> Image5x5 {
+        Image5x5::from_rows([!self.0[0], !self.0[1], !self.0[2], !self.0[3], !self.0[4]])
+    }
+
+    /// Every LED that is lit in either image.
+    pub fn or(&self, other: &Image5x5) -> Image5x5 {
+        Image5x5::from_fn(|row, col| self.is_lit(row, col) || other.is_lit(row, col))
+    }
+
+    /// Only the LEDs that are lit in both images.
+    pub fn and(&self, other: &Image5x5) -> Image5x5 {
+        Image5x5::from_fn(|row, col| self.is_lit(row, col) && other.is_lit(row, col))
+    }
+}
+
+/// The six faces of a die, `DICE[0]` with one pip up to `DICE[5]` with six.
+pub const DICE: [Image5x5; 6] = [
+    Image5x5::from_rows([0b00000, 0b00000, 0b00100, 0b00000, 0b00000]),
+    Image5x5::from_rows([0b10000, 0b00000, 0b00000, 0b00000, 0b00001]),
+    Image5x5::from_rows([0b10000, 0b00000, 0b00100, 0b00000, 0b00001]),
+    Image5x5::from_rows([0b10001, 0b00000, 0b00000, 0b00000, 0b10001]),
+    Image5x5::from_rows([0b10001, 0b00000, 0b00100, 0b00000, 0b10001]),
+    Image5x5::from_rows([0b10001, 0b00000, 0b10001, 0b00000, 0b10001]),
+];
+
+impl BitOr for Image5x5 {
+    type Output = Image5x5;
+
+    fn bitor(self, other: Image5x5) -> Image5x5 {
+        self.or(&other)
+    }
+}
+
+impl BitAnd for Image5x5 {
+    type Output = Image5x5;
+
+    fn bitand(self, other: Image5x5) -> Image5x5 {
+        self.and(&other)
+    }
+}
+
+impl Not for Image5x5 {
+    type Output = Image5x5;
+
+    fn not(self) -> Image5x5 {
+        self.invert()
+    }
+}
+
+impl Render for Image5x5 {
+    fn brightness_at(&self, x: usize, y: usize) -> u8 {
+        if self.is_lit(y, x) {
+            MAX_BRIGHTNESS
+        } else {
+            0
+        }
+    }
+}
@@ -0,0 +1,180 @@
+//! Helpers shared between the micro:bit chapters.
+//!
+//! Everything in here builds on top of the `microbit` BSP selected by the `v1`
+//! or `v2` feature, in the same way the chapter crates do.
+
+#![no_std]
+
+// The `SAADC` is the nRF52's, the nRF51 has an older, simpler `ADC`
+#[cfg(feature = "v2")]
+pub mod analog;
+pub mod animation;
+pub mod build_info;
+pub mod buttons;
+pub mod channel;
+// Claps are heard with the microphone, which only the micro:bit v2 has
+#[cfg(feature = "v2")]
+pub mod clap;
+pub mod clocks;
+pub mod compat;
+// Kept in flash only the micro:bit v2 has to spare, next to the flash log
+#[cfg(feature = "v2")]
+pub mod crash_log;
+pub mod delay;
+pub mod device_id;
+pub mod display;
+pub mod esb;
+pub mod executor;
+// Nothing but a check that a board feature has been picked
+mod features;
+pub mod fft;
+// The raw flash access behind `settings`, `flash_log` and `nfc`, and the
+// serial bootloader's
+pub mod flash;
+pub mod flash_layout;
+// The log lives in flash only the micro:bit v2 has to spare
+#[cfg(feature = "v2")]
+pub mod flash_log;
+pub mod font5x5;
+pub mod gestures;
+pub mod graphics;
+// Both ends share one wire through the nRF52's UARTE
+#[cfg(feature = "v2")]
+pub mod half_duplex;
+pub mod hard_fault;
+pub mod i2c;
+// The nRF51's TWI can only be the controller, the TWIS is the nRF52's
+#[cfg(feature = "v2")]
+pub mod i2c_target;
+pub mod idle;
+pub mod image;
+pub mod log;
+// Only the micro:bit v2 has a microphone
+#[cfg(feature = "v2")]
+pub mod microphone;
+pub mod monotonic;
+// The nRF51 has no `PWM` peripheral
+#[cfg(feature = "v2")]
+pub mod motors;
+// Driven by the `PWM` peripheral, which the nRF51 doesn't have
+#[cfg(feature = "v2")]
+pub mod neopixel;
+// The NFCT is new on the nRF52
+#[cfg(feature = "v2")]
+pub mod nfc;
+// The speaker only exists on the micro:bit v2
+#[cfg(feature = "v2")]
+pub mod pcm;
+// System OFF is the nRF52's, the v1 would need its own version
+#[cfg(feature = "v2")]
+pub mod power;
+pub mod ppi_blink;
+pub mod profiling;
+// The nRF51's PPI channels can't fork to a second task
+#[cfg(feature = "v2")]
+pub mod pulse_capture;
+pub mod radio;
+pub mod random;
+// Ringtones are played on the speaker, which only the micro:bit v2 has
+#[cfg(feature = "v2")]
+pub mod rtttl;
+pub mod scheduler;
+pub mod scroll;
+// The UARTE is the nRF52's, the nRF51 only has the older UART without
+// EasyDMA
+#[cfg(feature = "v2")]
+pub mod serial;
+// The nRF51 has no `PWM` peripheral
+#[cfg(feature = "v2")]
+pub mod servo;
+pub mod settings;
+pub mod shared;
+pub mod softdevice;
+// Painting the stack at boot takes the one hook that runs before `main`, so
+// only programs that want to measure it get it
+#[cfg(feature = "stackcheck")]
+pub mod stackcheck;
+// The speaker only exists on the micro:bit v2
+#[cfg(feature = "v2")]
+pub mod sound;
+pub mod thermometer;
+// The touch logo is new on the micro:bit v2
+#[cfg(feature = "v2")]
+pub mod touch;
+pub mod watchdog;
+// The interrupt handlers of `display`, `buttons` and the others, which a
+// program's own handlers take the place of
+#[cfg(any(
+    feature = "display-interrupt",
+    feature = "buttons-interrupt",
+    feature = "monotonic-interrupt",
+    feature = "random-interrupt",
+    all(feature = "serial-interrupt", feature = "v2")
+))]
+mod weak_interrupt;
+pub mod wire;
+
+// On the v1, the modules above that only the v2 has are stand-ins that say
+// so when they're used
+#[cfg(feature = "v1")]
+mod v2_only;
+#[cfg(feature = "v1")]
+v2_only::v2_only! {
+    analog
+        "`discovery_support::analog` needs the micro:bit v2 (feature \"v2\")"
+        { Ring, AnalogPin }
+    clap
+        "`discovery_support::clap` needs the micro:bit v2 (feature \"v2\")"
+        { ClapEvent, ClapDetector }
+    crash_log
+        "`discovery_support::crash_log` needs the micro:bit v2 (feature \"v2\")"
+        { Kind, Crash }
+    flash_log
+        "`discovery_support::flash_log` needs the micro:bit v2 (feature \"v2\")"
+        { Record, FlashLog }
+    half_duplex
+        "`discovery_support::half_duplex` needs the micro:bit v2 (feature \"v2\")"
+        { Error, HalfDuplex }
+    i2c_target
+        "`discovery_support::i2c_target` needs the micro:bit v2 (feature \"v2\")"
+        { Event, I2cTarget }
+    microphone
+        "`discovery_support::microphone` needs the micro:bit v2 (feature \"v2\")"
+        { Microphone }
+    motors
+        "`discovery_support::motors` needs the micro:bit v2 (feature \"v2\")"
+        { Motor, MotorPins, MotorDriver }
+    neopixel
+        "`discovery_support::neopixel` needs the micro:bit v2 (feature \"v2\")"
+        { Rgb, NeoPixels }
+    nfc
+        "`discovery_support::nfc` needs the micro:bit v2 (feature \"v2\")"
+        { Event, NfcTag }
+    pcm
+        "`discovery_support::pcm` needs the micro:bit v2 (feature \"v2\")"
+        { Pcm }
+    power
+        "`discovery_support::power` needs the micro:bit v2 (feature \"v2\")"
+        { Power }
+    pulse_capture
+        "`discovery_support::pulse_capture` needs the micro:bit v2 (feature \"v2\")"
+        { PulseCapture }
+    rtttl
+        "`discovery_support::rtttl` needs the micro:bit v2 (feature \"v2\")"
+        { Error, Ringtone }
+    serial
+        "`discovery_support::serial` needs the micro:bit v2 (feature \"v2\")"
+        {
+            Buffers, SerialConfig, SerialStats, FlowControl, IdleTimeout,
+            UartePort, IoError, AsyncPort
+        }
+    servo
+        "`discovery_support::servo` needs the micro:bit v2 (feature \"v2\")"
+        { Servo }
+    sound
+        "`discovery_support::sound` needs the micro:bit v2 (feature \"v2\")"
+        { Millis, Note, Speaker }
+    touch
+        "`discovery_support::touch` needs the micro:bit v2 (feature \"v2\")"
+        { TouchEvent, TouchLogo, Ring, TouchRings }
+}
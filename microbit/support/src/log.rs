@@ -0,0 +1,257 @@
+//! Printing to the debugger, over RTT or over semihosting, to the serial
+//! port, or nowhere at all.
+//!
+//! RTT is what the chapters use by default: the micro:bit leaves the text in
+//! a buffer in RAM, and the debugger picks it up from there whenever it gets
+//! round to it. Some debug probes don't get on with it, though. With the
+//! `semihosting` feature the text goes to the debugger by *semihosting*
+//! instead: the CPU stops at a breakpoint, and the debugger reads the text
+//! and lets it carry on. That's much slower, and without a debugger attached
+//! the CPU stays stopped, but just about every probe can do it.
+//!
+//! Without a debugger, neither gets the text anywhere. With the `serial-log`
+//! feature it goes out of the serial port of the UART chapter instead, at
+//! 115200 baud, for any serial terminal to show. That port belongs to the log
+//! then, so it's no good for programs that talk over it themselves. And with
+//! `no-log`, nothing gets printed at all, which also leaves out the code that
+//! formats the text.
+//!
+//! Formatting the text takes the micro:bit a while, and so does sending every
+//! character of it, which a program that prints every measurement it makes
+//! notices. With the `defmt` feature, [`println!`](crate::println) is
+//! `defmt::println!`, which leaves the format strings in the ELF file and
+//! only sends which one it is and the values, in binary, over RTT. The
+//! debugger puts the text back together. Whatever is printed has to
+//! implement `defmt::Format` then, or be wrapped in `defmt::Debug2Format`,
+//! and the program needs `defmt` as a dependency of its own, which is where
+//! the macro looks for it.
+//!
+//! A program picks none of these: it calls [`init_log!`](crate::init_log)
+//! and [`println!`](crate::println), and leaves the choice to whoever builds
+//! it. If several features are on, `no-log` wins over `serial-log`, that over
+//! `defmt`, and that over `semihosting`.
+//!
+//! Plenty of other `no_std` crates, sensor drivers for one, say what they're
+//! up to with the macros of the `log` crate, `log::info!` and the like. That
+//! only goes somewhere once a program has registered a logger with it, and
+//! [`init_log!`](crate::init_log) registers one that prints the same way,
+//! with the level and the module it came from in front. Only [`Level::Info`]
+//! and above get printed, unless [`set_level`] says otherwise.
+
+pub use ::log::{Level, LevelFilter};
+use core::fmt;
+
+// RTT has to be set up by the program itself, with a macro that uses these
+#[doc(hidden)]
+pub use rtt_target;
+
+// The same goes for the macro `println!` is with the `defmt` feature
+#[cfg(feature = "defmt")]
+#[doc(hidden)]
+pub use defmt;
+
+// Sets RTT up itself, there's nothing for `init_log!` to do
+#[cfg(feature = "defmt")]
+use defmt_rtt as _;
+
+// The serial port is the nRF52's UARTE
+#[cfg(all(feature = "serial-log", not(feature = "v2")))]
+compile_error!(
+    "the `serial-log` feature only works on the micro:bit v2, its serial port is a UARTE"
+);
+
+/// Get ready to print. RTT is set up either way, so that a panic handler
+/// that prints over it still can, and this has to come before anything is
+/// printed. Semihosting needs nothing.
+#[cfg(not(any(feature = "semihosting", feature = "defmt")))]
+#[macro_export]
+macro_rules! init_log {
+    () => {{
+        $crate::log::rtt_target::rtt_init_print!();
+        $crate::log::init();
+    }};
+}
+
+/// Get ready to print. RTT is set up either way, so that a panic handler
+/// that prints over it still can, and this has to come before anything is
+/// printed. Semihosting needs nothing, and `defmt` has set RTT up already.
+#[cfg(any(feature = "semihosting", feature = "defmt"))]
+#[macro_export]
+macro_rules! init_log {
+    () => {
+        $crate::log::init()
+    };
+}
+
+/// Print a line, like `rprintln!`, to wherever the features say.
+#[cfg(not(all(
+    feature = "defmt",
+    not(any(feature = "serial-log", feature = "no-log"))
+)))]
+#[macro_export]
+macro_rules! println {
+    ($($arg:tt)*) => {
+        $crate::log::print(format_args!($($arg)*))
+    };
+}
+
+/// Print a line, like `defmt::println!`, which it is.
+#[cfg(all(
+    feature = "defmt",
+    not(any(feature = "serial-log", feature = "no-log"))
+))]
+#[macro_export]
+macro_rules! println {
+    ($($arg:tt)*) => {
+        $crate::log::defmt::println!($($arg)*)
+    };
+}
+
+/// Prints `T` the way its `Debug` implementation does, with `{:?}`, for the
+/// types that don't implement `defmt::Format`. With the `defmt` feature that
+/// means formatting the text on the micro:bit after all, like
+/// `defmt::Debug2Format`, so it's best kept for what gets printed now and
+/// then.
+pub struct Debug2Format<'t, T: fmt::Debug + ?Sized>(pub &'t T);
+
+impl<T: fmt::Debug + ?Sized> fmt::Debug for Debug2Format<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl<T: fmt::Debug + ?Sized> defmt::Format for Debug2Format<'_, T> {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(f, "{}", defmt::Debug2Format(self.0))
+    }
+}
+
+#[doc(hidden)]
+pub fn init() {
+    #[cfg(all(feature = "serial-log", feature = "v2", not(feature = "no-log")))]
+    serial_log::init();
+
+    // With nothing to print to, the records aren't even made
+    let level = if cfg!(feature = "no-log") {
+        LevelFilter::Off
+    } else {
+        LevelFilter::Info
+    };
+    // The `log` crate only has a safe way of setting the logger on chips
+    // that can swap a pointer atomically, which the nRF51 can't. Nothing can
+    // get in the way of setting it in a critical section, on a single core.
+    critical_section::with(|_| unsafe {
+        // It was set already if this is the second call, which is fine
+        let _ = ::log::set_logger_racy(&LOGGER);
+        ::log::set_max_level_racy(level);
+    });
+}
+
+/// Print what's logged through the `log` crate at `level` and above, or
+/// nothing with [`LevelFilter::Off`]. Call it after [`init_log!`](crate::init_log).
+pub fn set_level(level: LevelFilter) {
+    critical_section::with(|_| unsafe { ::log::set_max_level_racy(level) });
+}
+
+/// Prints what other crates log, the way [`print`] does.
+struct Logger;
+
+static LOGGER: Logger = Logger;
+
+impl ::log::Log for Logger {
+    fn enabled(&self, metadata: &::log::Metadata) -> bool {
+        metadata.level() <= ::log::max_level()
+    }
+
+    fn log(&self, record: &::log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        // Something logged from an interrupt handler would otherwise end up
+        // in the middle of a line that's only half printed
+        critical_section::with(|_| {
+            print(format_args!(
+                "{:<5} {}: {}",
+                record.level(),
+                record.target(),
+                record.args()
+            ))
+        });
+    }
+
+    fn flush(&self) {}
+}
+
+#[doc(hidden)]
+pub fn print(args: fmt::Arguments) {
+    #[cfg(feature = "no-log")]
+    let _ = args;
+    #[cfg(all(feature = "serial-log", feature = "v2", not(feature = "no-log")))]
+    serial_log::print(args);
+    // What other crates log is text already
+    #[cfg(all(
+        feature = "defmt",
+        not(any(feature = "serial-log", feature = "no-log"))
+    ))]
+    defmt::println!("{}", defmt::Display2Format(&args));
+    #[cfg(all(
+        feature = "semihosting",
+        not(any(feature = "defmt", feature = "serial-log", feature = "no-log"))
+    ))]
+    cortex_m_semihosting::hprintln!("{}", args);
+    #[cfg(not(any(
+        feature = "semihosting",
+        feature = "defmt",
+        feature = "serial-log",
+        feature = "no-log"
+    )))]
+    rtt_target::rprintln!("{}", args);
+}
+
+#[cfg(all(feature = "serial-log", feature = "v2", not(feature = "no-log")))]
+mod serial_log {
+    use crate::serial::{FlowControl, UartePort};
+    use crate::shared::Shared;
+    use core::fmt::{self, Write};
+    use microbit::hal::gpio::{p0, p1, Level};
+    use microbit::hal::uarte::{Baudrate, Parity, Pins, Uarte};
+    use microbit::pac::{self, UARTE0};
+
+    static PORT: Shared<UartePort<UARTE0>> = Shared::new();
+
+    pub fn init() {
+        // The program takes the peripherals with `Board::take`, after this.
+        // The log only needs the UARTE and its two pins, which the board sets
+        // up the same way, and as long as the program doesn't use them too,
+        // neither gets in the other's way.
+        let peripherals = unsafe { pac::Peripherals::steal() };
+        let pins = Pins {
+            txd: p0::Parts::new(peripherals.P0)
+                .p0_06
+                .into_push_pull_output(Level::High)
+                .degrade(),
+            rxd: p1::Parts::new(peripherals.P1)
+                .p1_08
+                .into_floating_input()
+                .degrade(),
+            cts: None,
+            rts: None,
+        };
+        let uarte = Uarte::new(
+            peripherals.UARTE0,
+            pins,
+            Parity::EXCLUDED,
+            Baudrate::BAUD115200,
+        );
+        let port = UartePort::new(uarte, FlowControl::Disabled);
+        PORT.lend(port);
+    }
+
+    pub fn print(args: fmt::Arguments) {
+        PORT.with(|port| {
+            // If the serial port fails, there's nowhere left to say so
+            write!(port, "{}\r\n", args).ok();
+        });
+    }
+}
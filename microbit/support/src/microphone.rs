@@ -0,0 +1,154 @@
+//! The microphone of the micro:bit v2.
+//!
+//! Many boards have a digital PDM microphone, but the one on the micro:bit is
+//! analog: it puts out a voltage that follows the sound, on pin P0.05, and
+//! pin P0.20 switches it on. So instead of the `PDM` peripheral, we record
+//! with the `SAADC` (successive approximation analog to digital converter),
+//! which measures that voltage.
+//!
+//! The `SAADC` can take samples at a fixed rate by itself, and writes them
+//! straight to RAM with EasyDMA. When a buffer is full, it moves on to the
+//! next one while we work on the first. [`Microphone::record`] hands over
+//! every full buffer as it comes in.
+//!
+//! When it's quiet, the microphone's output stays at a steady level, and
+//! sound moves it up and down around that. [`rms`] tells how loud a buffer
+//! is.
+
+use core::sync::atomic::{compiler_fence, Ordering};
+use embedded_hal_02::digital::v2::OutputPin;
+use microbit::gpio::MicrophonePins;
+use microbit::pac::SAADC;
+
+/// Samples per second.
+pub const SAMPLE_RATE: u32 = 8_000;
+/// Samples per buffer, 32 ms worth.
+pub const BUFFER_LEN: usize = 256;
+/// The largest a sample gets, with 12 bits.
+pub const MAX_SAMPLE: i16 = 4095;
+
+/// The `SAADC` runs at 16 MHz, so this gives us `SAMPLE_RATE`.
+const SAMPLE_RATE_CC: u16 = (16_000_000 / SAMPLE_RATE) as u16;
+
+pub struct Microphone {
+    saadc: SAADC,
+    pins: MicrophonePins,
+    buffers: [[i16; BUFFER_LEN]; 2],
+}
+
+impl Microphone {
+    /// Switch the microphone on and set up the `SAADC` for it.
+    pub fn new(saadc: SAADC, mut pins: MicrophonePins) -> Microphone {
+        pins.mic_run.set_high().unwrap();
+
+        saadc.enable.write(|w| w.enable().enabled());
+        saadc.resolution.write(|w| w.val()._12bit());
+        saadc.ch[0].pselp.write(|w| w.pselp().analog_input3());
+        saadc.ch[0].pseln.write(|w| w.pseln().nc());
+        // The microphone's signal is tiny, so amplify it 4 times
+        saadc.ch[0].config.write(|w| {
+            w.gain().gain4();
+            w.refsel().vdd1_4();
+            w.tacq()._10us();
+            w.mode().se();
+            w.resp().bypass();
+            w.resn().bypass();
+            w.burst().disabled()
+        });
+        saadc
+            .samplerate
+            .write(|w| unsafe { w.cc().bits(SAMPLE_RATE_CC).mode().timers() });
+        saadc
+            .result
+            .maxcnt
+            .write(|w| unsafe { w.maxcnt().bits(BUFFER_LEN as u16) });
+
+        // Measure the SAADC's own offset, so that it doesn't end up in every
+        // sample
+        saadc.events_calibratedone.reset();
+        saadc.tasks_calibrateoffset.write(|w| unsafe { w.bits(1) });
+        while saadc.events_calibratedone.read().bits() == 0 {}
+        saadc.events_calibratedone.reset();
+
+        Microphone {
+            saadc,
+            pins,
+            buffers: [[0; BUFFER_LEN]; 2],
+        }
+    }
+
+    /// Record, and call `f` with every buffer of [`BUFFER_LEN`] samples,
+    /// until it returns `false`.
+    ///
+    /// The next buffer is recorded while `f` runs. If `f` takes longer than
+    /// that, recording pauses until it's done, and the sound in between is
+    /// lost.
+    pub fn record(&mut self, mut f: impl FnMut(&[i16]) -> bool) {
+        let buffers = [
+            self.buffers[0].as_mut_ptr() as u32,
+            self.buffers[1].as_mut_ptr() as u32,
+        ];
+        self.saadc.events_started.reset();
+        self.saadc.events_end.reset();
+        self.saadc
+            .result
+            .ptr
+            .write(|w| unsafe { w.bits(buffers[0]) });
+        compiler_fence(Ordering::Release);
+        self.saadc.tasks_start.write(|w| unsafe { w.bits(1) });
+        self.wait_until_started(buffers[1]);
+        // Starts the timer that takes the samples from now on
+        self.saadc.tasks_sample.write(|w| unsafe { w.bits(1) });
+
+        for filling in (0..2).cycle() {
+            while self.saadc.events_end.read().bits() == 0 {}
+            self.saadc.events_end.reset();
+            compiler_fence(Ordering::Acquire);
+            self.saadc.tasks_start.write(|w| unsafe { w.bits(1) });
+            // The buffer we're done with comes after the one being filled
+            self.wait_until_started(buffers[filling]);
+
+            if !f(&self.buffers[filling]) {
+                break;
+            }
+        }
+
+        self.saadc.events_stopped.reset();
+        self.saadc.tasks_stop.write(|w| unsafe { w.bits(1) });
+        while self.saadc.events_stopped.read().bits() == 0 {}
+        self.saadc.events_stopped.reset();
+    }
+
+    /// Once recording into one buffer has started, the `SAADC` only needs to
+    /// know where the next one is.
+    fn wait_until_started(&mut self, next: u32) {
+        while self.saadc.events_started.read().bits() == 0 {}
+        self.saadc.events_started.reset();
+        self.saadc.result.ptr.write(|w| unsafe { w.bits(next) });
+    }
+
+    /// Switch the microphone off, and give back the `SAADC` and the pins.
+    pub fn free(mut self) -> (SAADC, MicrophonePins) {
+        self.pins.mic_run.set_low().unwrap();
+        self.saadc.enable.write(|w| w.enable().disabled());
+        (self.saadc, self.pins)
+    }
+}
+
+/// The root mean square of `samples`: how far they are from their average,
+/// on average. The bigger it is, the louder the sound.
+pub fn rms(samples: &[i16]) -> f32 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    let len = samples.len() as f32;
+    let mean = samples.iter().map(|&s| f32::from(s)).sum::<f32>() / len;
+    let squares: f32 = samples
+        .iter()
+        .map(|&s| {
+            let s = f32::from(s) - mean;
+            s * s
+        })
+        .sum();
+    libm::sqrtf(squares / len)
+}
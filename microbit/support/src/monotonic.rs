@@ -0,0 +1,212 @@
+//! A clock that counts microseconds since start up, and never wraps around.
+//!
+//! The timers count at 1 MHz, but their 32 bits run out after about 71
+//! minutes, and then they start over from 0. Anything that compares two
+//! timestamps across that moment gets nonsense. The `RTC` runs out even
+//! sooner: it only has 24 bits, which at 32.768 kHz last for 512 seconds.
+//!
+//! So [`init_monotonic`] takes over `RTC1`, and counts how often it has run
+//! out in its `OVRFLW` interrupt. Together with the 24 bits of the counter
+//! itself, that makes a 64 bit count of ticks, which won't run out for
+//! millions of years. [`now`] turns it into microseconds.
+//!
+//! One tick of the RTC is about 30.5 µs, so that's as precise as the clock
+//! gets. It runs from the low frequency clock, which has to be started first,
+//! but needs next to no power, and keeps going while the CPU sleeps.
+//!
+//! The `RTC1` handler that counts the overflows is only there with the
+//! `monotonic-interrupt` feature. Other crates take `RTC1` for their clocks
+//! too, like Embassy's time driver, and a program can't have two handlers for
+//! it.
+
+use crate::shared::Shared;
+use crate::softdevice;
+#[cfg(feature = "monotonic-interrupt")]
+use crate::weak_interrupt::weak_interrupt;
+use core::ops::{Add, AddAssign, Sub};
+use microbit::hal::clocks::{Clocks, LfOscStarted};
+use microbit::hal::rtc::{Rtc, RtcInterrupt};
+use microbit::pac::{self, RTC1};
+
+/// A tick is 1_000_000 / 32_768 µs, which is 15_625 / 512 µs.
+const MICROS_PER_512_TICKS: u64 = 15_625;
+const COUNTER_BITS: u32 = 24;
+
+/// A point in time, in microseconds since [`init_monotonic`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Instant {
+    micros: u64,
+}
+
+impl Instant {
+    /// The time right now. Before [`init_monotonic`] it's always 0.
+    pub fn now() -> Instant {
+        now()
+    }
+
+    pub fn from_micros(micros: u64) -> Instant {
+        Instant { micros }
+    }
+
+    /// How long it has been since the clock started.
+    pub fn as_micros(self) -> u64 {
+        self.micros
+    }
+
+    /// How much later than `earlier` this is, or 0 if it is earlier itself.
+    pub fn duration_since(self, earlier: Instant) -> Duration {
+        Duration::from_micros(self.micros.saturating_sub(earlier.micros))
+    }
+
+    /// How much time has passed since this instant.
+    pub fn elapsed(self) -> Duration {
+        now().duration_since(self)
+    }
+}
+
+/// The time between two [`Instant`]s.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Duration {
+    micros: u64,
+}
+
+impl Duration {
+    pub const fn from_micros(micros: u64) -> Duration {
+        Duration { micros }
+    }
+
+    pub const fn from_millis(millis: u64) -> Duration {
+        Duration::from_micros(millis * 1_000)
+    }
+
+    pub const fn from_secs(secs: u64) -> Duration {
+        Duration::from_micros(secs * 1_000_000)
+    }
+
+    pub fn as_micros(self) -> u64 {
+        self.micros
+    }
+
+    /// The whole milliseconds, rounded down.
+    pub fn as_millis(self) -> u64 {
+        self.micros / 1_000
+    }
+
+    /// The whole seconds, rounded down.
+    pub fn as_secs(self) -> u64 {
+        self.micros / 1_000_000
+    }
+}
+
+impl Add<Duration> for Instant {
+    type Output = Instant;
+
+    fn add(self, duration: Duration) -> Instant {
+        Instant::from_micros(self.micros + duration.micros)
+    }
+}
+
+impl AddAssign<Duration> for Instant {
+    fn add_assign(&mut self, duration: Duration) {
+        *self = *self + duration;
+    }
+}
+
+impl Sub<Duration> for Instant {
+    type Output = Instant;
+
+    /// Going back further than the start of the clock ends up at 0.
+    fn sub(self, duration: Duration) -> Instant {
+        Instant::from_micros(self.micros.saturating_sub(duration.micros))
+    }
+}
+
+impl Sub<Instant> for Instant {
+    type Output = Duration;
+
+    fn sub(self, earlier: Instant) -> Duration {
+        self.duration_since(earlier)
+    }
+}
+
+impl Add for Duration {
+    type Output = Duration;
+
+    fn add(self, other: Duration) -> Duration {
+        Duration::from_micros(self.micros + other.micros)
+    }
+}
+
+impl Sub for Duration {
+    type Output = Duration;
+
+    /// Never less than 0.
+    fn sub(self, other: Duration) -> Duration {
+        Duration::from_micros(self.micros.saturating_sub(other.micros))
+    }
+}
+
+struct Clock {
+    rtc: Rtc<RTC1>,
+    /// How often the counter has run out.
+    overflows: u64,
+}
+
+static CLOCK: Shared<Clock> = Shared::new();
+
+/// Take over `RTC1`, and start counting from 0. It counts the low frequency
+/// clock, which `_clocks` proves has been started. Only the first call does
+/// anything, the clock can't be started over.
+///
+/// `Board::take` doesn't hand out `RTC1`, so we go behind its back, just like
+/// `Board::take` itself.
+pub fn init_monotonic<H, L>(_clocks: &Clocks<H, L, LfOscStarted>) {
+    if CLOCK.with(|_| ()).is_some() {
+        return;
+    }
+    let board_rtc = unsafe { pac::Peripherals::steal() }.RTC1;
+
+    // No prescaler, so the RTC ticks at the full 32.768 kHz
+    let mut rtc = Rtc::new(board_rtc, 0).unwrap();
+    rtc.clear_counter();
+    rtc.reset_event(RtcInterrupt::Overflow);
+    rtc.enable_event(RtcInterrupt::Overflow);
+    rtc.enable_interrupt(RtcInterrupt::Overflow, None);
+    rtc.enable_counter();
+
+    CLOCK.lend(Clock { rtc, overflows: 0 });
+    unsafe { softdevice::unmask(pac::Interrupt::RTC1) }
+}
+
+/// The time right now. Before [`init_monotonic`] it's always 0.
+pub fn now() -> Instant {
+    let ticks = CLOCK
+        .with(|clock| {
+            let mut overflows = clock.overflows;
+            let mut counter = clock.rtc.get_counter();
+            // The interrupt can't count an overflow while we're in here. If one
+            // happened, the counter may have been read just before or just after
+            // it, so read it again to be sure it's the one after.
+            if clock.rtc.is_event_triggered(RtcInterrupt::Overflow) {
+                overflows += 1;
+                counter = clock.rtc.get_counter();
+            }
+            (overflows << COUNTER_BITS) | u64::from(counter)
+        })
+        .unwrap_or(0);
+    Instant::from_micros(ticks * MICROS_PER_512_TICKS / 512)
+}
+
+#[cfg(feature = "monotonic-interrupt")]
+weak_interrupt!(RTC1 => on_interrupt);
+
+/// Count an overflow, what the `RTC1` handler does, for a program that has a
+/// handler of its own.
+pub fn on_interrupt() {
+    CLOCK.with(|clock| {
+        if clock.rtc.is_event_triggered(RtcInterrupt::Overflow) {
+            clock.rtc.reset_event(RtcInterrupt::Overflow);
+            clock.overflows += 1;
+        }
+    });
+}
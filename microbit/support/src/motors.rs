@@ -0,0 +1,118 @@
+//! DC motors on a motor driver board, like the ones that the micro:bit plugs
+//! into to make a robot.
+//!
+//! A DC motor turns one way when the current goes through it one way, and the
+//! other way the other way around. The *H-bridge* on the board switches that
+//! for each motor, from two pins of the micro:bit: with the first one high
+//! and the second low, the motor goes forward, the other way around it goes
+//! back, and with both low it runs free. How fast it goes depends on how much
+//! of the time its pin is high, so each pin gets a channel of its own of the
+//! `PWM` peripheral, which has four of them, just enough for two motors.
+//!
+//! Kitronik's motor driver board, and most of the ones like it, have the
+//! first motor on pins 8 and 12 of the edge connector, and the second one on
+//! pins 0 and 16. Other boards want other pins, which is why
+//! [`MotorDriver::new`] takes them.
+//!
+//! Speeds go from `-MAX_SPEED`, backwards as fast as it goes, to
+//! [`MAX_SPEED`], which is
+//! [`discovery_logic::drive::MAX_SPEED`],
+//! so the speeds that module works out fit as they are.
+
+pub use discovery_logic::drive::MAX_SPEED;
+use microbit::hal::gpio::{Output, Pin, PushPull};
+use microbit::hal::pwm::{self, Channel, Prescaler, Pwm};
+
+/// One of the two motors.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Motor {
+    /// The one on pins 8 and 12, on the left of a robot that's looking away
+    /// from you.
+    Left,
+    /// The one on pins 0 and 16.
+    Right,
+}
+
+impl Motor {
+    /// The channels of the forward and the backward pin.
+    fn channels(self) -> (Channel, Channel) {
+        match self {
+            Motor::Left => (Channel::C0, Channel::C1),
+            Motor::Right => (Channel::C2, Channel::C3),
+        }
+    }
+}
+
+/// The pins of the two motors: for each of them, the one that's high to go
+/// forward, and the one that's high to go back.
+pub struct MotorPins {
+    pub left_forward: Pin<Output<PushPull>>,
+    pub left_back: Pin<Output<PushPull>>,
+    pub right_forward: Pin<Output<PushPull>>,
+    pub right_back: Pin<Output<PushPull>>,
+}
+
+pub struct MotorDriver<T: pwm::Instance> {
+    pwm: Pwm<T>,
+}
+
+impl<T: pwm::Instance> MotorDriver<T> {
+    /// Take over `pins`, with both motors standing still.
+    pub fn new(pwm: T, pins: MotorPins) -> MotorDriver<T> {
+        let pwm = Pwm::new(pwm);
+        // At 16 MHz, a period of `MAX_SPEED` ticks is 16 kHz, too high to
+        // hear, which a lower one would be, as a whine from the motors
+        pwm.set_prescaler(Prescaler::Div1);
+        pwm.set_max_duty(MAX_SPEED as u16);
+        pwm.set_output_pin(Channel::C0, pins.left_forward);
+        pwm.set_output_pin(Channel::C1, pins.left_back);
+        pwm.set_output_pin(Channel::C2, pins.right_forward);
+        pwm.set_output_pin(Channel::C3, pins.right_back);
+
+        let mut motors = MotorDriver { pwm };
+        motors.stop();
+        motors
+    }
+
+    /// Run `motor` at `speed`, forward if it's positive, back if it's
+    /// negative. Anything beyond [`MAX_SPEED`] is as fast as it goes.
+    ///
+    /// This changes the speed right away. Going from standing still to full
+    /// speed at once can draw enough current to reset the micro:bit, see
+    /// [`Ramp`](discovery_logic::drive::Ramp).
+    pub fn set_speed(&mut self, motor: Motor, speed: i16) {
+        let duty = speed.unsigned_abs().min(MAX_SPEED as u16);
+        let (forward, back) = motor.channels();
+        let (forward_duty, back_duty) = if speed >= 0 { (duty, 0) } else { (0, duty) };
+        // High for the duty, and low for the rest of the period, see `servo`
+        self.pwm.set_duty_off(forward, forward_duty);
+        self.pwm.set_duty_off(back, back_duty);
+    }
+
+    /// Let both motors run free, until they stand still.
+    pub fn stop(&mut self) {
+        self.set_speed(Motor::Left, 0);
+        self.set_speed(Motor::Right, 0);
+    }
+
+    /// Stop `motor` as quickly as it stops: with both of its pins high, the
+    /// H-bridge shorts the motor, which brakes it.
+    pub fn brake(&mut self, motor: Motor) {
+        let (forward, back) = motor.channels();
+        self.pwm.set_duty_off(forward, MAX_SPEED as u16);
+        self.pwm.set_duty_off(back, MAX_SPEED as u16);
+    }
+
+    /// Stop the motors, and give back the `PWM` peripheral and the pins.
+    pub fn free(mut self) -> (T, MotorPins) {
+        self.stop();
+        self.pwm.disable();
+        let pins = MotorPins {
+            left_forward: self.pwm.clear_output_pin(Channel::C0).unwrap(),
+            left_back: self.pwm.clear_output_pin(Channel::C1).unwrap(),
+            right_forward: self.pwm.clear_output_pin(Channel::C2).unwrap(),
+            right_back: self.pwm.clear_output_pin(Channel::C3).unwrap(),
+        };
+        (self.pwm.free(), pins)
+    }
+}
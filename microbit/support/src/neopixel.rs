@@ -0,0 +1,157 @@
+//! WS2812 LEDs, better known as NeoPixels, on the edge connector.
+//!
+//! A strip of WS2812s hangs off a single wire. Every LED takes the first 24
+//! bits that come along it, 8 each for green, red and blue, and passes on
+//! the rest to the next LED. A pause of more than 50 µs tells them all to
+//! show what they got, and start over.
+//!
+//! Every bit takes 1.25 µs, and starts with the wire high: for 0.4 µs for a
+//! 0, for 0.8 µs for a 1. That's too quick to get right by switching the pin
+//! from code, an interrupt at the wrong moment would mess it up. The `PWM`
+//! peripheral can do it though: at 16 MHz, a period of 20 ticks is exactly
+//! 1.25 µs, and a duty cycle of 6 or 13 ticks makes a 0 or a 1. It reads one
+//! duty cycle per period from RAM with EasyDMA, so [`NeoPixels::show`] only
+//! has to write down the bits, and the `PWM` plays them out without a single
+//! tick of jitter.
+
+use core::sync::atomic::{compiler_fence, Ordering};
+use microbit::hal::gpio::{Output, Pin, PushPull};
+use microbit::hal::pwm;
+
+/// 16 MHz / 20 = 800 kHz, a period of 1.25 µs for every bit.
+const COUNTERTOP: u16 = 20;
+/// The pin goes high at the start of every period, and low once the counter
+/// gets to the duty cycle: bit 15 makes the first edge a falling one.
+const FALLING_FIRST: u16 = 0x8000;
+/// 6 ticks high, 0.375 µs.
+const ZERO: u16 = FALLING_FIRST | 6;
+/// 13 ticks high, 0.8125 µs.
+const ONE: u16 = FALLING_FIRST | 13;
+/// Low for the whole period.
+const LOW: u16 = FALLING_FIRST;
+/// 64 periods low, 80 µs, tells the LEDs to show what they got.
+const RESET_LEN: usize = 64;
+const BITS_PER_LED: usize = 24;
+
+/// The color of one LED, every part from 0 (off) to 255 (as bright as it
+/// gets).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Rgb {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Rgb {
+    pub const OFF: Rgb = Rgb::new(0, 0, 0);
+
+    pub const fn new(r: u8, g: u8, b: u8) -> Rgb {
+        Rgb { r, g, b }
+    }
+
+    /// The color `hue` of the way around the color wheel, from red at 0 via
+    /// green at 85 and blue at 170, back to red.
+    pub fn wheel(hue: u8) -> Rgb {
+        let step = hue % 85 * 3;
+        match hue / 85 {
+            0 => Rgb::new(255 - step, step, 0),
+            1 => Rgb::new(0, 255 - step, step),
+            _ => Rgb::new(step, 0, 255 - step),
+        }
+    }
+
+    /// The same color, `brightness` / 255 as bright.
+    pub fn dim(self, brightness: u8) -> Rgb {
+        let dim = |part: u8| (u16::from(part) * u16::from(brightness) / 255) as u8;
+        Rgb::new(dim(self.r), dim(self.g), dim(self.b))
+    }
+}
+
+/// What EasyDMA plays. The bits of the LEDs and the pause after them have to
+/// follow one another in RAM, which `repr(C)` makes sure of.
+#[repr(C)]
+struct Sequence<const N: usize> {
+    leds: [[u16; BITS_PER_LED]; N],
+    reset: [u16; RESET_LEN],
+}
+
+/// A strip of `N` LEDs.
+pub struct NeoPixels<T: pwm::Instance, const N: usize> {
+    pwm: T,
+    _pin: Pin<Output<PushPull>>,
+    sequence: Sequence<N>,
+}
+
+impl<T: pwm::Instance, const N: usize> NeoPixels<T, N> {
+    /// Take over `pin`, which the data line of the strip is connected to.
+    /// The LEDs keep whatever they showed before, until the first
+    /// [`show`](NeoPixels::show).
+    pub fn new(pwm: T, pin: Pin<Output<PushPull>>) -> NeoPixels<T, N> {
+        pwm.psel.out[0].write(|w| unsafe { w.bits(pin.psel_bits()) });
+        pwm.mode.write(|w| w.updown().up());
+        pwm.prescaler.write(|w| w.prescaler().div_1());
+        pwm.countertop
+            .write(|w| unsafe { w.countertop().bits(COUNTERTOP) });
+        // One duty cycle, for all channels, every period
+        pwm.decoder.write(|w| {
+            w.load().common();
+            w.mode().refresh_count()
+        });
+        pwm.seq0.refresh.write(|w| unsafe { w.cnt().bits(0) });
+        pwm.seq0.enddelay.write(|w| unsafe { w.bits(0) });
+        pwm.loop_.write(|w| w.cnt().disabled());
+
+        NeoPixels {
+            pwm,
+            _pin: pin,
+            sequence: Sequence {
+                leds: [[LOW; BITS_PER_LED]; N],
+                reset: [LOW; RESET_LEN],
+            },
+        }
+    }
+
+    /// Send `colors` down the strip, the first one to the LED closest to the
+    /// micro:bit, and wait until they're showing. That takes 30 µs per LED.
+    pub fn show(&mut self, colors: &[Rgb; N]) {
+        for (bits, color) in self.sequence.leds.iter_mut().zip(colors) {
+            // Green goes first, and the highest bit of each color first
+            let word = u32::from(color.g) << 16 | u32::from(color.r) << 8 | u32::from(color.b);
+            for (index, bit) in bits.iter_mut().enumerate() {
+                *bit = if word & 1 << (BITS_PER_LED - 1 - index) != 0 {
+                    ONE
+                } else {
+                    ZERO
+                };
+            }
+        }
+
+        let len = N * BITS_PER_LED + RESET_LEN;
+        let ptr = &self.sequence as *const Sequence<N> as u32;
+        self.pwm.seq0.ptr.write(|w| unsafe { w.bits(ptr) });
+        self.pwm
+            .seq0
+            .cnt
+            .write(|w| unsafe { w.cnt().bits(len as u16) });
+        self.pwm.events_seqend[0].reset();
+        self.pwm.events_stopped.reset();
+
+        // EasyDMA reads the bits behind the compiler's back, they had better
+        // all be written by now
+        compiler_fence(Ordering::Release);
+        self.pwm.enable.write(|w| w.enable().enabled());
+        self.pwm.tasks_seqstart[0].write(|w| unsafe { w.bits(1) });
+        while self.pwm.events_seqend[0].read().bits() == 0 {}
+        compiler_fence(Ordering::Acquire);
+
+        // The last duty cycle keeps playing until we stop, and it's a low one
+        self.pwm.tasks_stop.write(|w| unsafe { w.bits(1) });
+        while self.pwm.events_stopped.read().bits() == 0 {}
+        self.pwm.enable.write(|w| w.enable().disabled());
+    }
+
+    /// Switch all the LEDs off.
+    pub fn clear(&mut self) {
+        self.show(&[Rgb::OFF; N]);
+    }
+}
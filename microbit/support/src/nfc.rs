@@ -0,0 +1,240 @@
+//! Being an NFC tag, with the nRF52833's `NFCT` peripheral.
+//!
+//! A reader, like a phone, powers the tag with its 13.56 MHz field, and
+//! talks to it by switching that field off for a moment now and then. The tag
+//! answers by changing how much of the field its antenna soaks up, which the
+//! reader notices. The `NFCT` does all of that, and even the anti-collision,
+//! with which a reader picks one tag out of all those in its field. What the
+//! CPU gets is the commands that come after that, and it has to answer each
+//! one within about 300 µs. So [`NfcTag::poll`] goes in a tight loop, with
+//! nothing slow in between.
+//!
+//! The antenna goes to P0.09 and P0.10, pins 9 and 8 of the edge connector.
+//! Whether those are NFC pins or ordinary GPIOs is up to the `NFCPINS`
+//! register in the UICR, which the chip only reads when it starts, see
+//! [`enable_nfc_pins`].
+
+use crate::flash::{self, ERASED};
+use core::sync::atomic::{compiler_fence, Ordering};
+use cortex_m::peripheral::SCB;
+use microbit::pac::{self, FICR, NFCT, UICR};
+
+/// How many bytes a UID has. Shorter and longer ones exist too, but 7 is
+/// what Type 2 tags use.
+pub const UID_LEN: usize = 7;
+/// The longest command we take in, and the longest answer, CRC included.
+pub const BUFFER_LEN: usize = 32;
+
+/// The UICR registers, up to `REGOUT0`, the last one there is.
+const UICR_WORDS: usize = 0x308 / 4;
+const NFCPINS_WORD: usize = 0x20c / 4;
+
+/// A NAK, a command that is refused, is just 4 bits.
+const NAK: u8 = 0x0;
+
+/// What a reader did.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Event<'a> {
+    /// One came close enough to power us.
+    FieldDetected,
+    /// It picked us out, and commands are about to follow.
+    Selected,
+    /// It sent this command, without the CRC. Answer it right away with
+    /// [`NfcTag::respond`], [`NfcTag::nak`] or [`NfcTag::sleep`].
+    Command(&'a [u8]),
+    /// It's gone.
+    FieldLost,
+}
+
+/// Make P0.09 and P0.10 the antenna pins, if they aren't already.
+///
+/// A program that uses them as GPIOs writes a 0 into `NFCPINS`, and once it's
+/// there, the only way to get the 1 back is to erase the whole UICR. So
+/// everything else in the UICR is copied to RAM first, and written back once
+/// it has been erased, `APPROTECT` and the reset pin included. The chip only
+/// reads the UICR when it starts, so this resets the micro:bit, and doesn't
+/// return if it had anything to do. Call it first thing in `main`.
+pub fn enable_nfc_pins() {
+    let uicr = unsafe { &*UICR::ptr() };
+    if uicr.nfcpins.read().protect().is_nfc() {
+        return;
+    }
+
+    let start = UICR::ptr() as usize;
+    let mut words = [ERASED; UICR_WORDS];
+    for (offset, word) in words.iter_mut().enumerate() {
+        *word = flash::read_word(start + offset * 4);
+    }
+    // An erased `NFCPINS` is what makes them NFC pins
+    words[NFCPINS_WORD] = ERASED;
+
+    // `Board::take` doesn't hand out the `NVMC`, and it's too early for
+    // anything else to be using it
+    let nvmc = unsafe { pac::Peripherals::steal() }.NVMC;
+    flash::erase_uicr(&nvmc);
+    for (offset, &word) in words.iter().enumerate() {
+        if word != ERASED {
+            flash::write_words(&nvmc, start + offset * 4, &[word]);
+        }
+    }
+    SCB::sys_reset();
+}
+
+/// The UID Nordic picked for this chip. It starts with `0x5F`, the number
+/// every tag made by Nordic starts with.
+pub fn uid(ficr: &FICR) -> [u8; UID_LEN] {
+    let first = ficr.nfc.tagheader0.read().bits().to_le_bytes();
+    let second = ficr.nfc.tagheader1.read().bits().to_le_bytes();
+    [
+        first[0], first[1], first[2], first[3], second[0], second[1], second[2],
+    ]
+}
+
+pub struct NfcTag {
+    nfct: NFCT,
+    /// Where a command is received, and its answer sent from.
+    buffer: [u8; BUFFER_LEN],
+}
+
+impl NfcTag {
+    /// Answer to readers as a Type 2 tag with `uid`.
+    ///
+    /// The `NFCT` only works with the crystal oscillator running, see
+    /// `Clocks::enable_ext_hfosc`.
+    pub fn new(nfct: NFCT, uid: [u8; UID_LEN]) -> NfcTag {
+        nfct.nfcid1_2nd_last.write(|w| unsafe {
+            w.nfcid1_t().bits(uid[0]);
+            w.nfcid1_u().bits(uid[1]);
+            w.nfcid1_v().bits(uid[2])
+        });
+        nfct.nfcid1_last.write(|w| unsafe {
+            w.nfcid1_w().bits(uid[3]);
+            w.nfcid1_x().bits(uid[4]);
+            w.nfcid1_y().bits(uid[5]);
+            w.nfcid1_z().bits(uid[6])
+        });
+        // A 7 byte UID, and the kind of anti-collision Type 2 tags use
+        nfct.sensres
+            .write(|w| w.nfcidsize().nfcid1double().bitframesdd().sdd00100());
+        // Type 2 tags don't speak any of the protocols on top of NFC-A
+        nfct.selres.write(|w| unsafe { w.bits(0) });
+        // Answer in the time slots the reader expects an answer in
+        nfct.framedelaymode
+            .write(|w| w.framedelaymode().window_grid());
+        // Start anti-collision as soon as a reader shows up, and go back to
+        // waiting for one once it's gone
+        nfct.shorts.write(|w| {
+            w.fielddetected_activate()
+                .enabled()
+                .fieldlost_sense()
+                .enabled()
+        });
+        nfct.tasks_sense.write(|w| unsafe { w.bits(1) });
+
+        NfcTag {
+            nfct,
+            buffer: [0; BUFFER_LEN],
+        }
+    }
+
+    /// See what the reader is up to.
+    pub fn poll(&mut self) -> Option<Event<'_>> {
+        let nfct = &self.nfct;
+        if nfct.events_fieldlost.read().bits() != 0 {
+            nfct.events_fieldlost.reset();
+            return Some(Event::FieldLost);
+        }
+        if nfct.events_fielddetected.read().bits() != 0 {
+            nfct.events_fielddetected.reset();
+            return Some(Event::FieldDetected);
+        }
+        if nfct.events_selected.read().bits() != 0 {
+            nfct.events_selected.reset();
+            self.receive();
+            return Some(Event::Selected);
+        }
+        if nfct.events_txframeend.read().bits() != 0 {
+            nfct.events_txframeend.reset();
+            self.receive();
+            return None;
+        }
+        if nfct.events_rxframeend.read().bits() != 0 {
+            nfct.events_rxframeend.reset();
+            nfct.events_rxerror.reset();
+            compiler_fence(Ordering::Acquire);
+            let status = nfct.framestatus.rx.read();
+            // Its bits are cleared by writing 1s
+            nfct.framestatus
+                .rx
+                .write(|w| unsafe { w.bits(status.bits()) });
+            // That includes the CRC
+            let len = nfct.rxd.amount.read().rxdatabytes().bits() as usize;
+            if status.bits() != 0 || !(2..=BUFFER_LEN).contains(&len) {
+                // Garbled, wait for the reader to try again
+                self.receive();
+                return None;
+            }
+            return Some(Event::Command(&self.buffer[..len - 2]));
+        }
+        None
+    }
+
+    /// Answer the last command with `bytes`, up to [`BUFFER_LEN`] - 2 of
+    /// them, and a CRC.
+    pub fn respond(&mut self, bytes: &[u8]) {
+        let len = bytes.len().min(BUFFER_LEN - 2);
+        self.buffer[..len].copy_from_slice(&bytes[..len]);
+        self.transmit(len, 0, true);
+    }
+
+    /// Refuse the last command.
+    pub fn nak(&mut self) {
+        self.buffer[0] = NAK;
+        self.transmit(0, 4, false);
+    }
+
+    /// Don't answer the last command, and don't answer anything else either,
+    /// until the reader wakes us up again.
+    pub fn sleep(&mut self) {
+        self.nfct.tasks_gosleep.write(|w| unsafe { w.bits(1) });
+    }
+
+    /// Send `bytes` whole bytes of `buffer`, or the lowest `bits` of its
+    /// first byte.
+    fn transmit(&mut self, bytes: usize, bits: u8, crc: bool) {
+        let nfct = &self.nfct;
+        nfct.txd.frameconfig.write(|w| {
+            w.parity().parity();
+            w.discardmode().discard_end();
+            w.sof().so_f();
+            if crc {
+                w.crcmodetx().crc16tx()
+            } else {
+                w.crcmodetx().no_crctx()
+            }
+        });
+        nfct.txd.amount.write(|w| unsafe {
+            w.txdatabytes().bits(bytes as _);
+            w.txdatabits().bits(bits)
+        });
+        self.point_at_buffer();
+        compiler_fence(Ordering::Release);
+        self.nfct.tasks_starttx.write(|w| unsafe { w.bits(1) });
+    }
+
+    fn receive(&mut self) {
+        self.point_at_buffer();
+        compiler_fence(Ordering::Release);
+        self.nfct.tasks_enablerxdata.write(|w| unsafe { w.bits(1) });
+    }
+
+    fn point_at_buffer(&mut self) {
+        let buffer = self.buffer.as_mut_ptr() as u32;
+        self.nfct
+            .packetptr
+            .write(|w| unsafe { w.ptr().bits(buffer) });
+        self.nfct
+            .maxlen
+            .write(|w| unsafe { w.maxlen().bits(BUFFER_LEN as _) });
+    }
+}
@@ -0,0 +1,124 @@
+//! Playing recorded sounds on the speaker of the micro:bit v2.
+//!
+//! The [`sound`](crate::sound) module plays one frequency at a time. Sound
+//! effects need more than that: they are recorded as PCM, a list of samples
+//! that each say how far out the speaker should be at that moment. We can
+//! play those with the `PWM` peripheral as well, by running it much faster
+//! than the speaker can follow. The speaker then settles wherever the duty
+//! cycle puts it, so one duty cycle per sample plays the sound.
+//!
+//! The `PWM` peripheral reads its duty cycles from RAM by itself, with what
+//! Nordic calls EasyDMA, and it can go from one *sequence* of them straight
+//! to the next. While one sequence plays, we fill the other one with the
+//! samples that come after it: *double buffering*. The CPU only has to step
+//! in every [`CHUNK_LEN`] samples, and sounds can stay in flash, where
+//! EasyDMA can't read them.
+//!
+//! Sounds are unsigned 8 bit samples at [`SAMPLE_RATE`] with the speaker at
+//! rest at 128, what Audacity exports as raw "Unsigned 8-bit PCM".
+
+use core::sync::atomic::{compiler_fence, Ordering};
+use microbit::hal::gpio::p0::P0_00;
+use microbit::hal::gpio::{Disconnected, Level, Output, Pin, PushPull};
+use microbit::hal::pwm;
+
+/// Samples per second.
+pub const SAMPLE_RATE: u32 = 8_000;
+/// How many samples go into one sequence.
+pub const CHUNK_LEN: usize = 256;
+
+/// 16 MHz / 250 = 64 kHz, well above what anybody can hear.
+const COUNTERTOP: u16 = 250;
+/// 64 kHz / 8 = 8 kHz, so every sample lasts for 8 PWM periods.
+const PERIODS_PER_SAMPLE: u32 = 8;
+const REST: u8 = 128;
+
+pub struct Pcm<T: pwm::Instance> {
+    pwm: T,
+    _pin: Pin<Output<PushPull>>,
+    buffers: [[u16; CHUNK_LEN]; 2],
+}
+
+impl<T: pwm::Instance> Pcm<T> {
+    /// Take over the speaker pin.
+    pub fn new(pwm: T, speaker_pin: P0_00<Disconnected>) -> Pcm<T> {
+        let pin = speaker_pin.into_push_pull_output(Level::Low).degrade();
+
+        pwm.psel.out[0].write(|w| unsafe { w.bits(pin.psel_bits()) });
+        pwm.mode.write(|w| w.updown().up());
+        pwm.prescaler.write(|w| w.prescaler().div_1());
+        pwm.countertop
+            .write(|w| unsafe { w.countertop().bits(COUNTERTOP) });
+        // One duty cycle for all channels at a time, repeated for as many
+        // periods as a sample lasts
+        pwm.decoder.write(|w| {
+            w.load().common();
+            w.mode().refresh_count()
+        });
+        for seq in [&pwm.seq0, &pwm.seq1] {
+            seq.refresh
+                .write(|w| unsafe { w.cnt().bits(PERIODS_PER_SAMPLE - 1) });
+            seq.enddelay.write(|w| unsafe { w.bits(0) });
+            seq.cnt.write(|w| unsafe { w.cnt().bits(CHUNK_LEN as u16) });
+        }
+        // Go back and forth between the sequences for as long as it takes
+        pwm.loop_.write(|w| unsafe { w.cnt().bits(u16::MAX) });
+
+        Pcm {
+            pwm,
+            _pin: pin,
+            buffers: [[0; CHUNK_LEN]; 2],
+        }
+    }
+
+    /// Play `sound`, and wait until it's over.
+    pub fn play(&mut self, sound: &[u8]) {
+        let mut chunks = sound.chunks(CHUNK_LEN);
+        let len = chunks.len();
+        if len == 0 {
+            return;
+        }
+        fill(&mut self.buffers[0], chunks.next());
+        fill(&mut self.buffers[1], chunks.next());
+
+        let buffers = [
+            self.buffers[0].as_ptr() as u32,
+            self.buffers[1].as_ptr() as u32,
+        ];
+        self.pwm.seq0.ptr.write(|w| unsafe { w.bits(buffers[0]) });
+        self.pwm.seq1.ptr.write(|w| unsafe { w.bits(buffers[1]) });
+        self.pwm.events_seqend[0].reset();
+        self.pwm.events_seqend[1].reset();
+        self.pwm.events_stopped.reset();
+
+        compiler_fence(Ordering::Release);
+        self.pwm.enable.write(|w| w.enable().enabled());
+        self.pwm.tasks_seqstart[0].write(|w| unsafe { w.bits(1) });
+
+        // Whenever a sequence is over, the other one is playing, and this one
+        // gets the chunk after that
+        for playing in (0..2).cycle().take(len) {
+            while self.pwm.events_seqend[playing].read().bits() == 0 {}
+            self.pwm.events_seqend[playing].reset();
+            compiler_fence(Ordering::Acquire);
+            fill(&mut self.buffers[playing], chunks.next());
+            compiler_fence(Ordering::Release);
+        }
+
+        // The sequence playing now only has silence left
+        self.pwm.tasks_stop.write(|w| unsafe { w.bits(1) });
+        while self.pwm.events_stopped.read().bits() == 0 {}
+        self.pwm.events_stopped.reset();
+        self.pwm.enable.write(|w| w.enable().disabled());
+    }
+}
+
+/// Turn `samples` into duty cycles, and fill up the rest of `buffer` with the
+/// speaker at rest.
+fn fill(buffer: &mut [u16; CHUNK_LEN], samples: Option<&[u8]>) {
+    let samples = samples.unwrap_or_default();
+    let rest = core::iter::repeat(&REST);
+    for (duty, &sample) in buffer.iter_mut().zip(samples.iter().chain(rest)) {
+        *duty = u16::from(sample) * COUNTERTOP / 255;
+    }
+}
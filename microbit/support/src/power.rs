@@ -0,0 +1,220 @@
+//! Switching the nRF52833 off, and waking it up again.
+//!
+//! In *System OFF*, the deepest sleep there is, the chip draws less than a
+//! microamp: the CPU, the clocks, even the RAM are switched off. The only way
+//! back is a reset, and one of the things that can cause one is a pin that
+//! has been set up to *sense* a level, like the pin of a button being
+//! pressed. The program then starts over from the very beginning.
+//!
+//! Everything in RAM is gone by then, but the `GPREGRET` register of the
+//! `POWER` peripheral keeps its value, so a program can leave itself a byte
+//! of notes about where it was.
+//!
+//! [`enter_motion_sleep`] sleeps more lightly, in System ON, so that the
+//! program carries on right where it was. Until the board is picked up, the
+//! CPU waits with `wfe`, the display is switched off, and the accelerometer
+//! measures just often enough to notice.
+//!
+//! With a debugger attached, the chip only pretends to be off, so that the
+//! debugger can keep talking to it, and it keeps drawing power. Power the
+//! board from a battery to see the real thing.
+
+use crate::display::{pause_display, resume_display};
+use core::sync::atomic::{compiler_fence, AtomicBool, Ordering};
+use cortex_m::peripheral::{NVIC, SCB};
+use embedded_hal_02::blocking::i2c::{Write, WriteRead};
+use embedded_hal_02::digital::v2::InputPin;
+use lsm303agr::AccelScale;
+use microbit::hal::gpio::{Input, Pin, Port, PullUp};
+use microbit::pac::{self, Interrupt, POWER};
+
+const ACCEL_ADDRESS: u8 = 0x19;
+const CTRL_REG1_A: u8 = 0x20;
+const CTRL_REG2_A: u8 = 0x21;
+const CTRL_REG3_A: u8 = 0x22;
+const CTRL_REG5_A: u8 = 0x24;
+const CTRL_REG6_A: u8 = 0x25;
+const REFERENCE_A: u8 = 0x26;
+const INT1_CFG_A: u8 = 0x30;
+const INT1_SRC_A: u8 = 0x31;
+const INT1_THS_A: u8 = 0x32;
+const INT1_DURATION_A: u8 = 0x33;
+/// The output data rate is in the top 4 bits of CTRL_REG1_A, and 10 Hz is 2.
+const ODR_MASK: u8 = 0xf0;
+const ODR_10HZ: u8 = 2 << 4;
+/// Take gravity out of what the interrupt generator sees, with the high-pass
+/// filter.
+const HPIS1: u8 = 1;
+/// Route the interrupt generator to INT1.
+const I1_AOI1: u8 = 1 << 6;
+/// Keep INT1 raised until INT1_SRC_A is read.
+const LIR_INT1: u8 = 1 << 3;
+/// INT1 is active low.
+const H_LACTIVE: u8 = 1 << 1;
+/// Interrupt when any of X, Y or Z goes above the threshold.
+const XYZ_HIGH: u8 = (1 << 5) | (1 << 3) | (1 << 1);
+/// The `SEVONPEND` bit of the SCB's `SCR`: an interrupt becoming pending
+/// wakes up `wfe`, even while the NVIC keeps it masked.
+const SEVONPEND: u32 = 1 << 4;
+
+/// How much the board has to move to wake up, in mg on top of gravity.
+pub const MOTION_WAKE_MG: u16 = 100;
+
+pub struct Power {
+    power: POWER,
+    /// Whether the last reset was the chip waking up from System OFF.
+    woke_up: bool,
+}
+
+/// Whether [`Power::take`] has handed out the `POWER` peripheral yet.
+static TAKEN: AtomicBool = AtomicBool::new(false);
+
+impl Power {
+    /// Take the `POWER` peripheral, the first time this is called.
+    ///
+    /// `Board::take` takes all the peripherals, but doesn't hand this one out,
+    /// so we go behind its back, just like `Board::take` itself: only one
+    /// `Power` can ever exist.
+    pub fn take() -> Option<Power> {
+        if TAKEN.swap(true, Ordering::SeqCst) {
+            return None;
+        }
+        let power = unsafe { pac::Peripherals::steal() }.POWER;
+        Some(Power::new(power))
+    }
+
+    fn new(power: POWER) -> Power {
+        let woke_up = power.resetreas.read().off().is_detected();
+        // The reasons add up over every reset until they're cleared, by
+        // writing 1 to them
+        power.resetreas.write(|w| unsafe { w.bits(0xffff_ffff) });
+        Power { power, woke_up }
+    }
+
+    /// Whether the program is running because a sensing pin woke the chip
+    /// up, rather than because it was switched on or reset.
+    pub fn woke_up(&self) -> bool {
+        self.woke_up
+    }
+
+    /// The byte that was kept with [`Power::retain`].
+    pub fn retained(&self) -> u8 {
+        self.power.gpregret.read().gpregret().bits()
+    }
+
+    /// Keep `value` safe through System OFF. Switching the power off and on
+    /// again clears it.
+    pub fn retain(&mut self, value: u8) {
+        self.power
+            .gpregret
+            .write(|w| unsafe { w.gpregret().bits(value) });
+    }
+
+    /// Wake the chip up from System OFF once `pin` is low, like the pin of a
+    /// pressed button. `pin` has to be an input.
+    pub fn wake_on_low<MODE>(&mut self, pin: &Pin<MODE>) {
+        let port = match pin.port() {
+            Port::Port0 => unsafe { &*pac::P0::ptr() },
+            Port::Port1 => unsafe { &*pac::P1::ptr() },
+        };
+        port.pin_cnf[pin.pin() as usize].modify(|_, w| w.sense().low());
+    }
+
+    /// Enter System OFF. Whatever the pins are doing, they keep doing, so
+    /// switch off anything that draws power first, like the LEDs.
+    pub fn system_off(self) -> ! {
+        compiler_fence(Ordering::SeqCst);
+        self.power.systemoff.write(|w| w.systemoff().enter());
+        // Switching off can take a moment, don't run anything else meanwhile
+        loop {
+            cortex_m::asm::wfe();
+        }
+    }
+}
+
+/// Sleep until the board is moved, with the display switched off.
+///
+/// `i2c` is the bus to the LSM303AGR and `int_pin` the accelerometer's INT1,
+/// P0.25. While the program sleeps, the accelerometer measures at 10 Hz, with
+/// its high-pass filter taking out gravity, and pulls INT1 low once any axis
+/// changes by more than [`MOTION_WAKE_MG`]. `scale` has to be what the
+/// accelerometer is set to, the threshold counts in units that depend on it.
+///
+/// Waking up puts the accelerometer back the way it was, and lights the
+/// display again, if `init_display` had set it up. Other interrupts wake the
+/// CPU too, and run, but it goes straight back to sleep after them.
+pub fn enter_motion_sleep<I, E>(
+    i2c: &mut I,
+    int_pin: &Pin<Input<PullUp>>,
+    scale: AccelScale,
+) -> Result<(), E>
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+{
+    let threshold_lsb_mg = match scale {
+        AccelScale::G2 => 16,
+        AccelScale::G4 => 32,
+        AccelScale::G8 => 62,
+        AccelScale::G16 => 186,
+    };
+    let threshold = (MOTION_WAKE_MG / threshold_lsb_mg).min(0x7F) as u8;
+
+    let mut ctrl_reg1 = [0];
+    i2c.write_read(ACCEL_ADDRESS, &[CTRL_REG1_A], &mut ctrl_reg1)?;
+    let [ctrl_reg1] = ctrl_reg1;
+    let sleepy_reg1 = ctrl_reg1 & !ODR_MASK | ODR_10HZ;
+    i2c.write(ACCEL_ADDRESS, &[CTRL_REG1_A, sleepy_reg1])?;
+    i2c.write(ACCEL_ADDRESS, &[CTRL_REG2_A, HPIS1])?;
+    i2c.write(ACCEL_ADDRESS, &[INT1_THS_A, threshold])?;
+    i2c.write(ACCEL_ADDRESS, &[INT1_DURATION_A, 0])?;
+    // Reading the reference register starts the filter over from the board
+    // the way it's lying right now
+    i2c.write_read(ACCEL_ADDRESS, &[REFERENCE_A], &mut [0])?;
+    i2c.write(ACCEL_ADDRESS, &[INT1_CFG_A, XYZ_HIGH])?;
+    i2c.write(ACCEL_ADDRESS, &[CTRL_REG5_A, LIR_INT1])?;
+    i2c.write(ACCEL_ADDRESS, &[CTRL_REG6_A, H_LACTIVE])?;
+    i2c.write(ACCEL_ADDRESS, &[CTRL_REG3_A, I1_AOI1])?;
+    // In case the interrupt went off before the filter had settled
+    i2c.write_read(ACCEL_ADDRESS, &[INT1_SRC_A], &mut [0])?;
+
+    pause_display();
+    // INT1 going low sets the `GPIOTE`'s PORT event, and the event makes its
+    // interrupt pending. The buttons module may have a handler for it, which
+    // clears the event. If not, `SEVONPEND` wakes us up all the same.
+    let port = match int_pin.port() {
+        Port::Port0 => unsafe { &*pac::P0::ptr() },
+        Port::Port1 => unsafe { &*pac::P1::ptr() },
+    };
+    let gpiote = unsafe { &*pac::GPIOTE::ptr() };
+    let scb = unsafe { &*SCB::PTR };
+    // An interrupt that is pending already can't become pending again, so a
+    // left over PORT event would let the CPU sleep right through the motion
+    gpiote.events_port.reset();
+    if !NVIC::is_enabled(Interrupt::GPIOTE) {
+        NVIC::unpend(Interrupt::GPIOTE);
+    }
+    port.pin_cnf[int_pin.pin() as usize].modify(|_, w| w.sense().low());
+    gpiote.intenset.write(|w| w.port().set());
+    unsafe { scb.scr.modify(|scr| scr | SEVONPEND) };
+
+    while int_pin.is_high().unwrap() {
+        cortex_m::asm::wfe();
+    }
+
+    unsafe { scb.scr.modify(|scr| scr & !SEVONPEND) };
+    gpiote.intenclr.write(|w| w.port().clear());
+    gpiote.events_port.reset();
+    port.pin_cnf[int_pin.pin() as usize].modify(|_, w| w.sense().disabled());
+    if !NVIC::is_enabled(Interrupt::GPIOTE) {
+        NVIC::unpend(Interrupt::GPIOTE);
+    }
+    resume_display();
+
+    i2c.write(ACCEL_ADDRESS, &[CTRL_REG3_A, 0])?;
+    i2c.write(ACCEL_ADDRESS, &[INT1_CFG_A, 0])?;
+    i2c.write(ACCEL_ADDRESS, &[CTRL_REG2_A, 0])?;
+    i2c.write(ACCEL_ADDRESS, &[CTRL_REG5_A, 0])?;
+    i2c.write(ACCEL_ADDRESS, &[CTRL_REG6_A, 0])?;
+    i2c.write_read(ACCEL_ADDRESS, &[INT1_SRC_A], &mut [0])?;
+    i2c.write(ACCEL_ADDRESS, &[CTRL_REG1_A, ctrl_reg1])
+}
@@ -0,0 +1,70 @@
+//! Blinking a pin without any help from the CPU.
+//!
+//! The peripherals of the nRF can talk to each other directly: every *event*
+//! one of them raises, like a timer reaching its compare value, can trigger a
+//! *task* of another one, like flipping a pin. The `PPI`, programmable
+//! peripheral interconnect, is the switchboard that connects the two.
+//!
+//! [`ppi_blink`] connects a periodic timer to a `GPIOTE` channel that toggles
+//! a pin. Once that's set up, the pin keeps blinking at exactly the right
+//! time, whatever the CPU is doing, even while it sleeps or is halted by the
+//! debugger.
+
+use embedded_hal_02::timer::{Cancel, CountDown};
+use microbit::hal::gpio::{Output, Pin, PushPull};
+use microbit::hal::gpiote::{GpioteChannel, TaskOutPolarity};
+use microbit::hal::ppi::ConfigurablePpi;
+use microbit::hal::timer::{self, Periodic, Timer};
+
+/// A pin blinking on its own. Dropping it leaves it blinking, [`stop`]
+/// stops it.
+///
+/// [`stop`]: PpiBlink::stop
+pub struct PpiBlink<T: timer::Instance, P: ConfigurablePpi> {
+    timer: Timer<T, Periodic>,
+    ppi: P,
+}
+
+/// Toggle `pin` every `half_period_us` microseconds, so that it goes through
+/// a whole period of high and low every `2 * half_period_us`.
+///
+/// `timer` does the timing, `channel` of the `GPIOTE` takes over the pin, and
+/// `ppi` connects the two.
+pub fn ppi_blink<T, P>(
+    timer: T,
+    mut ppi: P,
+    channel: &GpioteChannel,
+    pin: Pin<Output<PushPull>>,
+    half_period_us: u32,
+) -> PpiBlink<T, P>
+where
+    T: timer::Instance,
+    P: ConfigurablePpi,
+{
+    channel
+        .output_pin(pin)
+        .task_out_polarity(TaskOutPolarity::Toggle)
+        .init_low();
+
+    let mut timer = Timer::periodic(timer);
+    ppi.set_event_endpoint(timer.event_compare_cc0());
+    ppi.set_task_endpoint(channel.task_out());
+    ppi.enable();
+    // The timer ticks at 1 MHz, and starts over by itself every time it gets
+    // to the compare value
+    timer.start(half_period_us);
+
+    PpiBlink { timer, ppi }
+}
+
+impl<T: timer::Instance, P: ConfigurablePpi> PpiBlink<T, P> {
+    /// Stop blinking, and give back the timer and the PPI channel. The pin
+    /// stays the way it was at that moment, and stays with the `GPIOTE`
+    /// channel.
+    pub fn stop(self) -> (T, P) {
+        let PpiBlink { mut timer, mut ppi } = self;
+        ppi.disable();
+        timer.cancel().ok();
+        (timer.free(), ppi)
+    }
+}
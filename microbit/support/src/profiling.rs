@@ -0,0 +1,103 @@
+//! Marking parts of the program on a pin, for a power profiler or a logic
+//! analyzer.
+//!
+//! A power profiler, like Nordic's Power Profiler Kit, draws how much current
+//! the board takes over time. The curve has bumps and plateaus, but it
+//! doesn't say which part of the program each one was. Most profilers and
+//! every logic analyzer can record a digital input next to the current,
+//! though, and that's what a [`Marker`] is for: it drives one pin high while
+//! a [`Region`] of the program runs, and low again when it's over. One of the
+//! big rings of the edge connector is easy to clip a wire to, ring 2 is
+//! `p0_04` on the micro:bit v2 and `p0_01` on the v1.
+//!
+//! ```ignore
+//! let marker = Marker::new(board.pins.p0_04.degrade());
+//! loop {
+//!     idle::sleep();
+//!     let _awake = marker.region();
+//!     // Everything up to the end of the loop shows as high
+//! }
+//! ```
+//!
+//! Setting the pin and clearing it again take a single write each, so the
+//! marker hardly changes what it measures. A pin that only drives the input
+//! of the profiler draws nothing worth mentioning either.
+//!
+//! The regions of one marker shouldn't overlap: the first one to end clears
+//! the pin, whether the other is still running or not. Give the parts that
+//! nest, like an interrupt handler, a marker on a pin of their own instead.
+
+#[cfg(feature = "v2")]
+use microbit::hal::gpio::Port;
+use microbit::hal::gpio::{Level, Pin};
+#[cfg(feature = "v1")]
+use microbit::pac::{gpio::RegisterBlock, GPIO};
+#[cfg(feature = "v2")]
+use microbit::pac::{p0::RegisterBlock, P0, P1};
+
+/// Drives a pin high around the regions of the program it marks.
+///
+/// Copies of it drive the same pin, so one can go to an interrupt handler.
+#[derive(Clone, Copy)]
+pub struct Marker {
+    port: &'static RegisterBlock,
+    mask: u32,
+}
+
+// The `OUTSET` and `OUTCLR` registers only change the bits that are written
+// as 1, so writing them from everywhere at once is fine
+unsafe impl Send for Marker {}
+unsafe impl Sync for Marker {}
+
+impl Marker {
+    /// A marker on `pin`, which is made an output, low until a region starts.
+    pub fn new<MODE>(pin: Pin<MODE>) -> Marker {
+        let pin = pin.into_push_pull_output(Level::Low);
+        #[cfg(feature = "v2")]
+        let port = match pin.port() {
+            Port::Port0 => P0::ptr(),
+            Port::Port1 => P1::ptr(),
+        };
+        #[cfg(feature = "v1")]
+        let port = GPIO::ptr();
+        Marker {
+            // The pin is ours, and only its own bit is ever written
+            port: unsafe { &*port },
+            mask: 1 << pin.pin(),
+        }
+    }
+
+    /// Drive the pin high until the [`Region`] is dropped.
+    pub fn region(&self) -> Region<'_> {
+        self.port.outset.write(|w| unsafe { w.bits(self.mask) });
+        Region { marker: self }
+    }
+
+    /// Run `f` as a region of its own.
+    pub fn measure<R>(&self, f: impl FnOnce() -> R) -> R {
+        let _region = self.region();
+        f()
+    }
+
+    /// A blip on the pin, for something that happens at an instant rather
+    /// than taking a while, like an interrupt arriving. It's as short as the
+    /// pin can go, a few tens of nanoseconds, which a logic analyzer catches
+    /// but a slow profiler may not.
+    pub fn pulse(&self) {
+        drop(self.region());
+    }
+}
+
+/// A region of the program, marked by a [`Marker`]'s pin being high. It ends
+/// when it's dropped.
+#[must_use = "the region ends as soon as it's dropped"]
+pub struct Region<'a> {
+    marker: &'a Marker,
+}
+
+impl Drop for Region<'_> {
+    fn drop(&mut self) {
+        let marker = self.marker;
+        marker.port.outclr.write(|w| unsafe { w.bits(marker.mask) });
+    }
+}
@@ -0,0 +1,108 @@
+//! Timing the edges of a signal with a timer, instead of the CPU.
+//!
+//! A `GPIOTE` channel can watch a pin for edges both ways, and its event can
+//! trigger a timer's capture task through the `PPI`, the same switchboard
+//! that [`ppi_blink`](crate::ppi_blink) uses the other way around. The timer
+//! copies its count into a `CC` register the moment the edge comes in, so the
+//! time is exact, however long the CPU takes to get around to reading it.
+//! The PPI channel *forks* to the timer's clear task as well, so the count it
+//! captures is how long the signal stayed at its last level.
+//!
+//! That leaves one thing for the CPU: looking at the captured count before
+//! the next edge overwrites it. [`PulseCapture::poll`] notices when it was
+//! too late, and leaves those phases out.
+
+use embedded_hal_02::digital::v2::InputPin;
+use microbit::hal::gpio::{Floating, Input, Pin};
+use microbit::hal::gpiote::GpioteChannel;
+use microbit::hal::ppi::ConfigurablePpi;
+use microbit::hal::timer;
+
+/// The timer counts the 16 MHz clock without dividing it down, a tick is
+/// 62.5ns. Its 32 bits last for over 4 minutes.
+pub const TICKS_PER_SECOND: u32 = 16_000_000;
+
+/// Watches a pin, and times how long it stays high or low.
+pub struct PulseCapture<'a, T: timer::Instance, P: ConfigurablePpi> {
+    timer: T,
+    ppi: P,
+    channel: GpioteChannel<'a>,
+    pin: Pin<Input<Floating>>,
+    /// Whether there was an edge since the timer started. The count up to
+    /// the first one says nothing about the signal.
+    started: bool,
+}
+
+impl<'a, T: timer::Instance, P: ConfigurablePpi> PulseCapture<'a, T, P> {
+    /// Time the edges on `pin`, with `timer`, `channel` of the `GPIOTE`, and
+    /// `ppi` to connect the two.
+    pub fn new(
+        timer: T,
+        mut ppi: P,
+        channel: GpioteChannel<'a>,
+        pin: Pin<Input<Floating>>,
+    ) -> PulseCapture<'a, T, P> {
+        channel.input_pin(&pin).toggle();
+        channel.reset_events();
+
+        let regs = timer.as_timer0();
+        regs.tasks_stop.write(|w| unsafe { w.bits(1) });
+        regs.mode.write(|w| w.mode().timer());
+        regs.bitmode.write(|w| w.bitmode()._32bit());
+        regs.prescaler.write(|w| unsafe { w.prescaler().bits(0) });
+        // Nothing compares with CC[0], it only gets captured into
+        regs.shorts.reset();
+
+        ppi.set_event_endpoint(channel.event());
+        ppi.set_task_endpoint(&regs.tasks_capture[0]);
+        ppi.set_fork_task_endpoint(&regs.tasks_clear);
+        ppi.enable();
+
+        regs.tasks_clear.write(|w| unsafe { w.bits(1) });
+        regs.tasks_start.write(|w| unsafe { w.bits(1) });
+
+        PulseCapture {
+            timer,
+            ppi,
+            channel,
+            pin,
+            started: false,
+        }
+    }
+
+    /// The phase that ended with the last edge, if there was one since the
+    /// last call: whether the signal was high, and for how many ticks.
+    ///
+    /// If another edge came in while it was looking, there's no telling which
+    /// one the timer captured, and the phase is left out.
+    pub fn poll(&mut self) -> Option<(bool, u32)> {
+        if !self.channel.is_event_triggered() {
+            return None;
+        }
+        // Whatever edge comes in from here on sets the event again
+        self.channel.reset_events();
+        // The signal is low now if the phase that just ended was high
+        let high = self.pin.is_low().unwrap();
+        let ticks = self.timer.as_timer0().cc[0].read().bits();
+        if self.channel.is_event_triggered() || !self.started {
+            self.started = true;
+            return None;
+        }
+        Some((high, ticks))
+    }
+
+    /// Stop timing, and give back the timer, the PPI channel and the pin.
+    /// The `GPIOTE` channel keeps watching the pin until it's set up for
+    /// something else.
+    pub fn stop(self) -> (T, P, Pin<Input<Floating>>) {
+        let PulseCapture {
+            timer,
+            mut ppi,
+            pin,
+            ..
+        } = self;
+        ppi.disable();
+        timer.as_timer0().tasks_stop.write(|w| unsafe { w.bits(1) });
+        (timer, ppi, pin)
+    }
+}
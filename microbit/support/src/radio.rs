@@ -0,0 +1,363 @@
+//! Talking to micro:bits running MakeCode or MicroPython over the radio.
+//!
+//! Both of them build on the radio protocol of the micro:bit runtime. It uses
+//! the same packets as the radio chapter of the book, just with fixed
+//! settings: channel 7 (2407 MHz), 1 Mbit/s and the base address
+//! `"ubit"`. Micro:bits only hear each other when they are in the same
+//! *group*, which becomes the address prefix.
+//!
+//! Every packet starts with a 3 byte header after the length byte:
+//!
+//! ```text
+//! | length | version | group  | protocol | payload      |
+//! | 1 byte | 1       | 1 byte | 1        | 0 - 29 bytes |
+//! ```
+//!
+//! MakeCode and MicroPython both use protocol 1, *datagrams*, which carry
+//! whatever the program sends. MicroPython's `radio.send` and `radio.receive`
+//! use the payload for their text as it is. MakeCode adds a header of its own
+//! to say what kind of value follows, [`Radio::send_string`] and
+//! [`Radio::receive_string`] take care of that for strings.
+//!
+//! MicroPython's `radio.send_bytes` and `radio.receive_bytes` skip the header
+//! altogether, so they don't mix with datagrams.
+//!
+//! A [`Sniffer`] listens to every group at once instead, and hands over
+//! whatever it hears, garbled or not.
+
+use core::sync::atomic::{compiler_fence, Ordering};
+use discovery_logic::sniffer::{self, Capture, CAPTURE_LEN};
+use microbit::hal::clocks::{Clocks, ExternalOscillator};
+use microbit::pac::RADIO;
+
+/// The longest datagram payload that every micro:bit accepts.
+pub const MAX_PAYLOAD_LEN: usize = MAX_PACKET_LEN - HEADER_LEN;
+/// The longest string MakeCode sends or accepts.
+pub const MAX_STRING_LEN: usize = 19;
+/// MakeCode's default group is chosen per project, MicroPython's is 0.
+pub const DEFAULT_GROUP: u8 = 0;
+/// The highest channel the radio can tune to, at 2500 MHz.
+pub const MAX_CHANNEL: u8 = 100;
+/// The channel the micro:bit runtime uses, unless told otherwise.
+pub const DEFAULT_CHANNEL: u8 = FREQUENCY as u8;
+
+/// Everything after the length byte, including the header.
+const MAX_PACKET_LEN: usize = 32;
+const HEADER_LEN: usize = 3;
+const VERSION: u8 = 1;
+const PROTOCOL_DATAGRAM: u8 = 1;
+
+const FREQUENCY: u32 = 7;
+const BASE_ADDRESS: u32 = 0x7562_6974;
+const DATA_WHITENING_IV: u32 = 0x18;
+const CRC_POLY: u32 = 0x1_1021;
+const CRC_INIT: u32 = 0xFFFF;
+
+const PCNF0_LFLEN_8: u32 = 8;
+const PCNF1_WHITEEN: u32 = 1 << 25;
+const PCNF1_BALEN_4: u32 = 4 << 16;
+const PCNF1_BALEN_3: u32 = 3 << 16;
+const PCNF1_STATLEN_SHIFT: u32 = 8;
+const CRCCNF_LEN_2: u32 = 2;
+const SHORTS_READY_START: u32 = 1;
+const SHORTS_END_DISABLE: u32 = 1 << 1;
+const SHORTS_ADDRESS_RSSISTART: u32 = 1 << 4;
+const RSSISAMPLE_MASK: u32 = 0x7F;
+
+/// MakeCode's header: a packet type, the sender's uptime and serial number.
+const MAKECODE_HEADER_LEN: usize = 9;
+const MAKECODE_STRING: u8 = 2;
+const MAKECODE_LAST_TYPE: u8 = 5;
+
+#[derive(Debug)]
+pub enum Error {
+    /// The payload doesn't fit into a packet.
+    TooLong,
+    /// A packet arrived, but its CRC didn't match.
+    Crc,
+    /// A datagram arrived, but it doesn't hold a string.
+    NotAString,
+}
+
+pub struct Radio {
+    radio: RADIO,
+    buffer: [u8; MAX_PACKET_LEN + 1],
+    receiving: bool,
+}
+
+impl Radio {
+    /// Set up the radio the same way the micro:bit runtime does, listening to
+    /// `group`. `clocks` proves that the external crystal is running, which
+    /// the radio needs.
+    pub fn new<L, LSTAT>(
+        radio: RADIO,
+        _clocks: &Clocks<ExternalOscillator, L, LSTAT>,
+        group: u8,
+    ) -> Radio {
+        radio.mode.write(|w| unsafe { w.bits(0) });
+        radio.txpower.write(|w| unsafe { w.bits(0) });
+        radio.frequency.write(|w| unsafe { w.bits(FREQUENCY) });
+
+        radio.pcnf0.write(|w| unsafe { w.bits(PCNF0_LFLEN_8) });
+        radio
+            .pcnf1
+            .write(|w| unsafe { w.bits(PCNF1_WHITEEN | PCNF1_BALEN_4 | MAX_PACKET_LEN as u32) });
+        radio
+            .datawhiteiv
+            .write(|w| unsafe { w.bits(DATA_WHITENING_IV) });
+
+        radio.base0.write(|w| unsafe { w.bits(BASE_ADDRESS) });
+        radio.txaddress.write(|w| unsafe { w.bits(0) });
+        radio.rxaddresses.write(|w| unsafe { w.bits(1) });
+
+        radio.crccnf.write(|w| unsafe { w.bits(CRCCNF_LEN_2) });
+        radio.crcpoly.write(|w| unsafe { w.bits(CRC_POLY) });
+        radio.crcinit.write(|w| unsafe { w.bits(CRC_INIT) });
+
+        // Measure the signal strength of every packet as it comes in
+        radio.shorts.write(|w| unsafe {
+            w.bits(SHORTS_READY_START | SHORTS_END_DISABLE | SHORTS_ADDRESS_RSSISTART)
+        });
+
+        let mut radio = Radio {
+            radio,
+            buffer: [0; MAX_PACKET_LEN + 1],
+            receiving: false,
+        };
+        radio.set_group(group);
+        radio
+    }
+
+    /// Switch to another group, like MakeCode's `radio.setGroup`.
+    pub fn set_group(&mut self, group: u8) {
+        self.disable();
+        self.radio
+            .prefix0
+            .write(|w| unsafe { w.bits(group as u32) });
+    }
+
+    /// Send `payload` as a datagram, like MicroPython's `radio.send` does with
+    /// its text.
+    pub fn send_datagram(&mut self, payload: &[u8]) -> Result<(), Error> {
+        if payload.len() > MAX_PAYLOAD_LEN {
+            return Err(Error::TooLong);
+        }
+        self.disable();
+
+        // The runtime puts the group into the header as well, but never looks
+        // at it. MicroPython only accepts 0 there.
+        self.buffer[..=HEADER_LEN].copy_from_slice(&[
+            (HEADER_LEN + payload.len()) as u8,
+            VERSION,
+            0,
+            PROTOCOL_DATAGRAM,
+        ]);
+        self.buffer[HEADER_LEN + 1..][..payload.len()].copy_from_slice(payload);
+        self.start(true);
+        while self.radio.events_disabled.read().bits() == 0 {}
+        self.radio.events_disabled.reset();
+        compiler_fence(Ordering::Acquire);
+        Ok(())
+    }
+
+    /// Check whether a datagram has arrived, and start listening if we
+    /// weren't already. Packets of other protocols are skipped.
+    ///
+    /// The payload is only valid until the next call, since listening for the
+    /// next packet starts right away.
+    pub fn receive_datagram(&mut self) -> nb::Result<&[u8], Error> {
+        if !self.receiving {
+            self.start(false);
+            self.receiving = true;
+            return Err(nb::Error::WouldBlock);
+        }
+        if self.radio.events_disabled.read().bits() == 0 {
+            return Err(nb::Error::WouldBlock);
+        }
+        self.radio.events_disabled.reset();
+        self.receiving = false;
+        compiler_fence(Ordering::Acquire);
+
+        if self.radio.crcstatus.read().bits() == 0 {
+            return Err(nb::Error::Other(Error::Crc));
+        }
+        let len = self.buffer[0] as usize;
+        if len < HEADER_LEN || self.buffer[HEADER_LEN] != PROTOCOL_DATAGRAM {
+            return Err(nb::Error::WouldBlock);
+        }
+        Ok(&self.buffer[HEADER_LEN + 1..=len])
+    }
+
+    /// Send `text` the way MakeCode's `radio.sendString` does. Like MakeCode,
+    /// only the first [`MAX_STRING_LEN`] bytes are sent.
+    pub fn send_string(&mut self, text: &str) -> Result<(), Error> {
+        let text = &text.as_bytes()[..text.len().min(MAX_STRING_LEN)];
+
+        // We don't know our uptime or serial number, MakeCode only shows
+        // them to programs that ask for them
+        let mut payload = [0; MAKECODE_HEADER_LEN + 1 + MAX_STRING_LEN];
+        payload[0] = MAKECODE_STRING;
+        payload[MAKECODE_HEADER_LEN] = text.len() as u8;
+        payload[MAKECODE_HEADER_LEN + 1..][..text.len()].copy_from_slice(text);
+        self.send_datagram(&payload[..MAKECODE_HEADER_LEN + 1 + text.len()])
+    }
+
+    /// Check whether a string has arrived, either from MakeCode's
+    /// `radio.sendString` or MicroPython's `radio.send`.
+    ///
+    /// Text that isn't valid UTF-8 is reported as [`Error::NotAString`], just
+    /// like numbers and other values sent by MakeCode.
+    pub fn receive_string(&mut self) -> nb::Result<&str, Error> {
+        let payload = self.receive_datagram()?;
+        let text = match payload.first() {
+            Some(&MAKECODE_STRING) if payload.len() > MAKECODE_HEADER_LEN => {
+                let len = payload[MAKECODE_HEADER_LEN] as usize;
+                payload[MAKECODE_HEADER_LEN + 1..]
+                    .get(..len)
+                    .ok_or(Error::NotAString)?
+            }
+            // Numbers and the other values MakeCode sends
+            Some(&kind) if kind <= MAKECODE_LAST_TYPE => {
+                return Err(nb::Error::Other(Error::NotAString))
+            }
+            // MicroPython sends nothing but the text
+            _ => payload,
+        };
+        core::str::from_utf8(text).map_err(|_| nb::Error::Other(Error::NotAString))
+    }
+
+    /// How strong the last packet received was, in dBm. The closer to 0, the
+    /// stronger: around -40 dBm from a board right next to this one, down to
+    /// about -90 dBm where packets stop arriving.
+    pub fn rssi(&self) -> i16 {
+        -((self.radio.rssisample.read().bits() & RSSISAMPLE_MASK) as i16)
+    }
+
+    fn start(&mut self, transmit: bool) {
+        compiler_fence(Ordering::Release);
+        let packet = self.buffer.as_mut_ptr() as u32;
+        self.radio.packetptr.write(|w| unsafe { w.bits(packet) });
+        self.radio.events_disabled.reset();
+        if transmit {
+            self.radio.tasks_txen.write(|w| unsafe { w.bits(1) });
+        } else {
+            self.radio.tasks_rxen.write(|w| unsafe { w.bits(1) });
+        }
+    }
+
+    fn disable(&mut self) {
+        if !self.receiving {
+            return;
+        }
+        self.radio.tasks_disable.write(|w| unsafe { w.bits(1) });
+        while self.radio.events_disabled.read().bits() == 0 {}
+        self.radio.events_disabled.reset();
+        self.receiving = false;
+    }
+}
+
+/// Listens to everything the micro:bit runtime sends on a channel, in any
+/// group.
+///
+/// The group is the last byte of the address, so the sniffer listens on the
+/// four bytes before it, and the group turns into the first byte of what it
+/// captures. The radio can't find the length, undo the whitening or check the
+/// CRC from there, so it always captures [`CAPTURE_LEN`] bytes, and
+/// [`discovery_logic::sniffer::decode`] does the rest.
+pub struct Sniffer {
+    radio: RADIO,
+    buffer: [u8; CAPTURE_LEN],
+    receiving: bool,
+}
+
+impl Sniffer {
+    /// Set up the radio to sniff on `channel`, 2400 MHz plus this many MHz.
+    pub fn new<L, LSTAT>(
+        radio: RADIO,
+        _clocks: &Clocks<ExternalOscillator, L, LSTAT>,
+        channel: u8,
+    ) -> Sniffer {
+        radio.mode.write(|w| unsafe { w.bits(0) });
+
+        // No length field, no whitening and no CRC, just a fixed number of
+        // bytes after the address
+        radio.pcnf0.write(|w| unsafe { w.bits(0) });
+        radio.pcnf1.write(|w| unsafe {
+            w.bits(PCNF1_BALEN_3 | (CAPTURE_LEN as u32) << PCNF1_STATLEN_SHIFT | CAPTURE_LEN as u32)
+        });
+        radio.crccnf.write(|w| unsafe { w.bits(0) });
+
+        // The three bytes of the base address that go over the air last, and
+        // then the one before the group
+        radio.base0.write(|w| unsafe { w.bits(BASE_ADDRESS << 8) });
+        radio
+            .prefix0
+            .write(|w| unsafe { w.bits(BASE_ADDRESS >> 24) });
+        radio.rxaddresses.write(|w| unsafe { w.bits(1) });
+
+        radio.shorts.write(|w| unsafe {
+            w.bits(SHORTS_READY_START | SHORTS_END_DISABLE | SHORTS_ADDRESS_RSSISTART)
+        });
+
+        let mut sniffer = Sniffer {
+            radio,
+            buffer: [0; CAPTURE_LEN],
+            receiving: false,
+        };
+        sniffer.set_channel(channel);
+        sniffer
+    }
+
+    /// Move to another channel, up to [`MAX_CHANNEL`].
+    pub fn set_channel(&mut self, channel: u8) {
+        self.disable();
+        let channel = channel.min(MAX_CHANNEL);
+        self.radio
+            .frequency
+            .write(|w| unsafe { w.bits(u32::from(channel)) });
+    }
+
+    /// Check whether something has arrived, and start listening if we weren't
+    /// already. Along with the capture comes how strong it was, in dBm, like
+    /// [`Radio::rssi`]. Captures that are too long to be a packet are noise
+    /// that happened to look like the address.
+    ///
+    /// The capture is only valid until the next call, since listening starts
+    /// again right away.
+    pub fn receive(&mut self) -> nb::Result<(Capture<'_>, i16), sniffer::Error> {
+        if !self.receiving {
+            self.start();
+            return Err(nb::Error::WouldBlock);
+        }
+        if self.radio.events_disabled.read().bits() == 0 {
+            return Err(nb::Error::WouldBlock);
+        }
+        self.radio.events_disabled.reset();
+        self.receiving = false;
+        compiler_fence(Ordering::Acquire);
+
+        let rssi = -((self.radio.rssisample.read().bits() & RSSISAMPLE_MASK) as i16);
+        let address = BASE_ADDRESS.to_le_bytes();
+        let capture = sniffer::decode(address, DATA_WHITENING_IV as u8, &mut self.buffer)?;
+        Ok((capture, rssi))
+    }
+
+    fn start(&mut self) {
+        compiler_fence(Ordering::Release);
+        let buffer = self.buffer.as_mut_ptr() as u32;
+        self.radio.packetptr.write(|w| unsafe { w.bits(buffer) });
+        self.radio.events_disabled.reset();
+        self.radio.tasks_rxen.write(|w| unsafe { w.bits(1) });
+        self.receiving = true;
+    }
+
+    fn disable(&mut self) {
+        if !self.receiving {
+            return;
+        }
+        self.radio.tasks_disable.write(|w| unsafe { w.bits(1) });
+        while self.radio.events_disabled.read().bits() == 0 {}
+        self.radio.events_disabled.reset();
+        self.receiving = false;
+    }
+}
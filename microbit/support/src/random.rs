@@ -0,0 +1,182 @@
+//! Random numbers from the `RNG` peripheral.
+//!
+//! The `RNG` makes its numbers out of thermal noise, one byte at a time. They
+//! are truly random, but not necessarily *evenly* random: a few more 1 bits
+//! than 0 bits, say. Its *bias correction* takes out that kind of lopsidedness,
+//! at the cost of making each byte take longer, around 120 µs instead of 30.
+//! [`Random`] always turns it on.
+//!
+//! There are two ways of waiting for the bytes:
+//!
+//! - [`Random::new`] starts the `RNG` whenever a number is asked for, and
+//!   waits for as many bytes as it takes,
+//! - [`Random::new_interrupt_fed`] keeps the `RNG` going in the background,
+//!   and the `RNG` interrupt puts its bytes by, up to [`POOL_LEN`] of them.
+//!   A program that only needs a random number every so often never has to
+//!   wait for one. The `RNG` handler comes with the `random-interrupt`
+//!   feature, or from a program calling [`on_interrupt`] in its own.
+//!
+//! Reach for [`Random::below`] rather than `%` to pick one of `n` things. A
+//! random byte `% 6` is a 4 slightly more often than a 5, for example, since
+//! 256 isn't a multiple of 6. `below` throws away the numbers that would tip
+//! the balance and asks for new ones.
+//!
+//! Dice, food for the snake, which LED lights up next: games get all of their
+//! randomness from here.
+
+use crate::softdevice;
+#[cfg(feature = "random-interrupt")]
+use crate::weak_interrupt::weak_interrupt;
+use core::cell::RefCell;
+use cortex_m::interrupt::{free, Mutex};
+use heapless::spsc::Queue;
+use microbit::pac::{self, RNG};
+
+/// How many bytes the `RNG` interrupt puts by, at most.
+pub const POOL_LEN: usize = 32;
+
+/// The bytes the `RNG` interrupt has put by. A `Queue` of size N holds at
+/// most N - 1 of them.
+///
+/// It starts out empty rather than missing, so it doesn't need the `Option`
+/// of a [`Shared`](crate::shared::Shared).
+static POOL: Mutex<RefCell<Queue<u8, { POOL_LEN + 1 }>>> = Mutex::new(RefCell::new(Queue::new()));
+
+pub struct Random {
+    rng: RNG,
+    /// Whether the `RNG` interrupt fills the pool.
+    interrupt_fed: bool,
+}
+
+impl Random {
+    /// Hand out random numbers, waiting for the `RNG` every time.
+    pub fn new(rng: RNG) -> Random {
+        rng.config.write(|w| w.dercen().enabled());
+        Random {
+            rng,
+            interrupt_fed: false,
+        }
+    }
+
+    /// Hand out random numbers from the pool the `RNG` interrupt keeps
+    /// filling. A number has to wait only once the pool has run dry.
+    pub fn new_interrupt_fed(rng: RNG) -> Random {
+        let random = Random {
+            interrupt_fed: true,
+            ..Random::new(rng)
+        };
+        random.rng.events_valrdy.reset();
+        random.rng.intenset.write(|w| w.valrdy().set());
+        random.rng.tasks_start.write(|w| unsafe { w.bits(1) });
+        unsafe { softdevice::unmask(pac::Interrupt::RNG) }
+        random
+    }
+
+    /// A random byte.
+    pub fn next_u8(&mut self) -> u8 {
+        if self.interrupt_fed {
+            loop {
+                let byte = free(|cs| POOL.borrow(cs).borrow_mut().dequeue());
+                // There's room in the pool again, so the `RNG` has to keep
+                // going. Starting it while it's already going doesn't hurt.
+                self.rng.tasks_start.write(|w| unsafe { w.bits(1) });
+                match byte {
+                    Some(byte) => return byte,
+                    // The next byte comes with an interrupt, which wakes us up
+                    None => cortex_m::asm::wfi(),
+                }
+            }
+        } else {
+            self.rng.tasks_start.write(|w| unsafe { w.bits(1) });
+            while self.rng.events_valrdy.read().bits() == 0 {}
+            self.rng.events_valrdy.reset();
+            let byte = self.rng.value.read().value().bits();
+            self.rng.tasks_stop.write(|w| unsafe { w.bits(1) });
+            byte
+        }
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let mut bytes = [0; 4];
+        self.fill(&mut bytes);
+        u32::from_le_bytes(bytes)
+    }
+
+    /// Fill `bytes` with random bytes.
+    pub fn fill(&mut self, bytes: &mut [u8]) {
+        for byte in bytes {
+            *byte = self.next_u8();
+        }
+    }
+
+    /// A random number from 0 up to, but not including, `n`, every one of
+    /// them as likely as the next. Picking one of a few things only takes one
+    /// byte most of the time.
+    ///
+    /// Panics if `n` is 0.
+    pub fn below(&mut self, n: u32) -> u32 {
+        assert!(n > 0, "there's no number below 0 to pick");
+        if n <= 256 {
+            // The largest multiple of `n` that a byte can reach, anything from
+            // there on would make the smaller numbers more likely
+            let limit = 256 / n * n;
+            loop {
+                let byte = u32::from(self.next_u8());
+                if byte < limit {
+                    return byte % n;
+                }
+            }
+        } else {
+            let limit = u32::MAX / n * n;
+            loop {
+                let number = self.next_u32();
+                if number < limit {
+                    return number % n;
+                }
+            }
+        }
+    }
+
+    /// A random number from `low` up to, but not including, `high`.
+    ///
+    /// Panics unless `low` is less than `high`.
+    pub fn between(&mut self, low: u32, high: u32) -> u32 {
+        assert!(low < high, "the range {}..{} is empty", low, high);
+        low + self.below(high - low)
+    }
+
+    /// `true` `percent` times out of a hundred.
+    pub fn chance(&mut self, percent: u32) -> bool {
+        self.below(100) < percent
+    }
+
+    /// Stop the `RNG`, and give it back, along with anything that's left in
+    /// the pool.
+    pub fn free(self) -> RNG {
+        pac::NVIC::mask(pac::Interrupt::RNG);
+        self.rng.intenclr.write(|w| w.valrdy().clear());
+        self.rng.tasks_stop.write(|w| unsafe { w.bits(1) });
+        free(|cs| while POOL.borrow(cs).borrow_mut().dequeue().is_some() {});
+        self.rng
+    }
+}
+
+#[cfg(feature = "random-interrupt")]
+weak_interrupt!(RNG => on_interrupt);
+
+/// Put the byte the `RNG` just made by, what the `RNG` handler does.
+pub fn on_interrupt() {
+    // Only `Random::new_interrupt_fed` unmasks this interrupt, once it has
+    // set up the `RNG`
+    let rng = unsafe { &*RNG::ptr() };
+    rng.events_valrdy.reset();
+    let byte = rng.value.read().value().bits();
+    free(|cs| {
+        let mut pool = POOL.borrow(cs).borrow_mut();
+        pool.enqueue(byte).ok();
+        // Nowhere to put the next byte, stop until `next_u8` makes room
+        if pool.is_full() {
+            rng.tasks_stop.write(|w| unsafe { w.bits(1) });
+        }
+    });
+}
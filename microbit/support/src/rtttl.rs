@@ -0,0 +1,240 @@
+//! Ringtones in the Ring Tone Text Transfer Language (RTTTL).
+//!
+//! Nokia phones used this format for their ringtones, and plenty of them can
+//! still be found online. A ringtone is a line of text with three sections,
+//! separated by colons:
+//!
+//! ```text
+//! Beep:d=4,o=5,b=120:8c,8e,8g,2c6
+//! ```
+//!
+//! 1. The name.
+//! 2. Defaults for the notes, separated by commas: their duration `d`, as a
+//!    fraction of a whole note, their octave `o`, and the tempo `b` in beats
+//!    (quarter notes) per minute. Any of them can be left out.
+//! 3. The notes, separated by commas. Every note is a letter from `a` to
+//!    `g`, or `p` for a pause, followed by `#` to make it sharp. In front of
+//!    the letter can go a duration, and after it an octave, when they differ
+//!    from the defaults. A `.` makes the note half as long again.
+//!
+//! [`Ringtone::parse`] checks the whole ringtone before it can be played, and
+//! every error says where in the text the problem is.
+
+use crate::sound::{Millis, Note};
+use core::ops::RangeInclusive;
+
+/// What a ringtone without defaults plays like.
+const NO_DEFAULTS: Defaults = Defaults {
+    duration: 4,
+    octave: 6,
+    bpm: 63,
+};
+
+const DURATIONS: [u32; 6] = [1, 2, 4, 8, 16, 32];
+const OCTAVES: RangeInclusive<u32> = 4..=7;
+const BPMS: RangeInclusive<u32> = 1..=900;
+
+/// The frequencies of the 12 notes from C7 to B7 in Hz. Every octave below
+/// halves them.
+const OCTAVE_7: [u32; 12] = [
+    2093, 2217, 2349, 2489, 2637, 2794, 2960, 3136, 3322, 3520, 3729, 3951,
+];
+
+/// In every error, `at` is where the broken default or note starts, in bytes
+/// from the start of the ringtone.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The ringtone doesn't have three sections.
+    MissingSection,
+    /// A default doesn't look like `d=4`, `o=5` or `b=120`.
+    BadDefault { at: usize },
+    /// A duration isn't one of 1, 2, 4, 8, 16 or 32.
+    BadDuration { at: usize },
+    /// An octave isn't between 4 and 7.
+    BadOctave { at: usize },
+    /// The tempo isn't between 1 and 900 beats per minute.
+    BadTempo { at: usize },
+    /// A note isn't a letter from `a` to `g` or `p`, or there's something
+    /// left over after it.
+    BadNote { at: usize },
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Defaults {
+    duration: u32,
+    octave: u32,
+    bpm: u32,
+}
+
+/// A ringtone that has been checked and is ready to play.
+#[derive(Clone, Copy, Debug)]
+pub struct Ringtone<'a> {
+    name: &'a str,
+    defaults: Defaults,
+    notes: &'a str,
+    /// Where the notes start in the whole text.
+    notes_at: usize,
+}
+
+impl<'a> Ringtone<'a> {
+    pub fn parse(text: &'a str) -> Result<Ringtone<'a>, Error> {
+        let mut sections = text.splitn(3, ':');
+        let name = sections.next().unwrap_or_default();
+        let defaults = sections.next().ok_or(Error::MissingSection)?;
+        let notes = sections.next().ok_or(Error::MissingSection)?;
+
+        let defaults_at = name.len() + 1;
+        let ringtone = Ringtone {
+            name: name.trim(),
+            defaults: parse_defaults(defaults, defaults_at)?,
+            notes,
+            notes_at: defaults_at + defaults.len() + 1,
+        };
+        for note in ringtone.parse_notes() {
+            note?;
+        }
+        Ok(ringtone)
+    }
+
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    /// The notes, ready for [`Speaker::play_notes`](crate::sound::Speaker::play_notes).
+    pub fn notes(&self) -> impl Iterator<Item = (Note, Millis)> + 'a {
+        self.parse_notes()
+            .map(|note| note.expect("notes are checked by `Ringtone::parse`"))
+    }
+
+    fn parse_notes(&self) -> impl Iterator<Item = Result<(Note, Millis), Error>> + 'a {
+        let defaults = self.defaults;
+        fields(self.notes, self.notes_at).map(move |(at, note)| parse_note(note, at, defaults))
+    }
+}
+
+/// Split `section`, which starts at `at` in the whole ringtone, at its commas.
+/// Every field comes with where it starts, after any whitespace.
+fn fields(section: &str, at: usize) -> impl Iterator<Item = (usize, &str)> {
+    section.split(',').scan(at, |next, field| {
+        let trimmed = field.trim_start();
+        let at = *next + field.len() - trimmed.len();
+        *next += field.len() + 1;
+        Some((at, trimmed.trim_end()))
+    })
+}
+
+/// Split the digits off the start of `text`.
+fn digits(text: &str) -> (&str, &str) {
+    let end = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+    text.split_at(end)
+}
+
+/// Parse `number`, if it is one and within `valid`.
+fn number_in(number: &str, valid: impl Fn(u32) -> bool) -> Option<u32> {
+    number.parse().ok().filter(|&n| valid(n))
+}
+
+fn parse_defaults(section: &str, at: usize) -> Result<Defaults, Error> {
+    let mut defaults = NO_DEFAULTS;
+    for (at, field) in fields(section, at).filter(|(_, field)| !field.is_empty()) {
+        let (key, value) = field.split_once('=').ok_or(Error::BadDefault { at })?;
+        let value = value.trim();
+        match key.trim() {
+            "d" => {
+                defaults.duration =
+                    number_in(value, |d| DURATIONS.contains(&d)).ok_or(Error::BadDuration { at })?
+            }
+            "o" => {
+                defaults.octave =
+                    number_in(value, |o| OCTAVES.contains(&o)).ok_or(Error::BadOctave { at })?
+            }
+            "b" => {
+                defaults.bpm =
+                    number_in(value, |b| BPMS.contains(&b)).ok_or(Error::BadTempo { at })?
+            }
+            _ => return Err(Error::BadDefault { at }),
+        }
+    }
+    Ok(defaults)
+}
+
+fn parse_note(note: &str, at: usize, defaults: Defaults) -> Result<(Note, Millis), Error> {
+    let (duration, rest) = digits(note);
+    let duration = if duration.is_empty() {
+        defaults.duration
+    } else {
+        number_in(duration, |d| DURATIONS.contains(&d)).ok_or(Error::BadDuration { at })?
+    };
+
+    let mut chars = rest.chars();
+    // Some ringtones use the German `h` for B
+    let semitone = match chars.next().map(|c| c.to_ascii_lowercase()) {
+        Some('c') => Some(0),
+        Some('d') => Some(2),
+        Some('e') => Some(4),
+        Some('f') => Some(5),
+        Some('g') => Some(7),
+        Some('a') => Some(9),
+        Some('b') | Some('h') => Some(11),
+        Some('p') => None,
+        _ => return Err(Error::BadNote { at }),
+    };
+    let mut rest = chars.as_str();
+
+    let sharp = rest.starts_with('#');
+    if sharp {
+        if semitone.is_none() {
+            return Err(Error::BadNote { at });
+        }
+        rest = &rest[1..];
+    }
+
+    // The dot goes after the octave, but plenty of ringtones have it in
+    // front of it instead
+    let mut dotted = rest.starts_with('.');
+    if dotted {
+        rest = &rest[1..];
+    }
+    let (octave, rest) = digits(rest);
+    let octave = if octave.is_empty() {
+        defaults.octave
+    } else {
+        number_in(octave, |o| OCTAVES.contains(&o)).ok_or(Error::BadOctave { at })?
+    };
+    let rest = match rest.strip_prefix('.') {
+        Some(rest) if !dotted => {
+            dotted = true;
+            rest
+        }
+        _ => rest,
+    };
+    if !rest.is_empty() {
+        return Err(Error::BadNote { at });
+    }
+
+    // A whole note lasts four beats
+    let mut millis = 4 * 60_000 / (defaults.bpm * duration);
+    if dotted {
+        millis += millis / 2;
+    }
+    let freq = match semitone {
+        Some(semitone) => frequency(semitone + u32::from(sharp), octave),
+        None => 0,
+    };
+    Ok((Note(freq), millis))
+}
+
+/// The frequency of the note `semitone` steps above C in `octave`, rounded
+/// to the nearest Hz. B sharp is C of the next octave.
+fn frequency(semitone: u32, octave: u32) -> u32 {
+    let octave = octave + semitone / 12;
+    let freq = OCTAVE_7[(semitone % 12) as usize];
+    if octave >= 7 {
+        freq << (octave - 7)
+    } else {
+        let shift = 7 - octave;
+        (freq + (1 << (shift - 1))) >> shift
+    }
+}
@@ -0,0 +1,116 @@
+//! A tiny cooperative scheduler: a few tasks, each run every so often, one
+//! after the other.
+//!
+//! A main loop that does more than one thing soon turns into a tangle of
+//! "is it time for this yet?" checks. [`Scheduler`] untangles it: every
+//! [`Task`] is a plain function with its own period, and the scheduler calls
+//! each of them whenever it's due, all driven by a single timer that ticks
+//! once every millisecond.
+//!
+//! *Cooperative* means that a task is never interrupted by another task. Each
+//! one runs until it returns, and only then does the next one get a turn. That
+//! makes sharing data between tasks easy, they all get a `&mut` to the same
+//! context, no locks needed. The price is that every task has to do its bit
+//! and return quickly. A task that waits for something, say with a `delay` or
+//! a blocking read, holds up all the others. Instead, it has to remember where
+//! it was, return, and carry on the next time it's run.
+//!
+//! Interrupts still interrupt, of course, so a display or a serial port that is
+//! driven by interrupts keeps going whatever the tasks are doing.
+
+use crate::idle;
+use cortex_m::peripheral::SCB;
+use embedded_hal_02::timer::CountDown;
+use microbit::hal::timer::{self, Periodic, Timer};
+
+/// How often the timer ticks, in milliseconds. Task periods are counted in
+/// ticks, so this is also the shortest period a task can have.
+const TICK_MS: u32 = 1;
+
+/// One of the things the scheduler takes turns doing.
+///
+/// `C` is whatever all the tasks share: usually a struct holding the
+/// peripherals and the state of the whole program.
+pub struct Task<C> {
+    /// How often `run` is called, in milliseconds.
+    pub period_ms: u32,
+    /// Does the task's work. It gets the time, in milliseconds since the
+    /// scheduler started, which it can use to time things that don't fit its
+    /// period, like a delay that changes while the program runs.
+    pub run: fn(context: &mut C, now_ms: u32),
+}
+
+/// Runs `N` tasks, each of them every `period_ms`, in the order they were
+/// given.
+pub struct Scheduler<'t, T: timer::Instance, C, const N: usize> {
+    timer: Timer<T, Periodic>,
+    tasks: &'t [Task<C>; N],
+    /// When each task is due next, in milliseconds since the start.
+    due: [u32; N],
+    now_ms: u32,
+}
+
+impl<'t, T: timer::Instance, C, const N: usize> Scheduler<'t, T, C, N> {
+    /// Schedule `tasks`, ticking along with `timer`. Every one of them is due
+    /// straight away, so they all get to run once as soon as [`run`] starts.
+    ///
+    /// The CPU sleeps whenever no task is due. The timer wakes it up without
+    /// an interrupt handler, which needs the `SEVONPEND` bit of the `SCB`,
+    /// see the [`idle`] module.
+    ///
+    /// [`run`]: Scheduler::run
+    pub fn new(timer: T, tasks: &'t [Task<C>; N], scb: &mut SCB) -> Self {
+        idle::init(scb);
+        let mut timer = Timer::periodic(timer);
+        idle::wake_on_timer(&mut timer);
+        Scheduler {
+            timer,
+            tasks,
+            due: [0; N],
+            now_ms: 0,
+        }
+    }
+
+    /// Run the tasks forever, passing `context` to each of them.
+    pub fn run(mut self, context: &mut C) -> ! {
+        // The timer ticks at 1 MHz, and starts over by itself every time it
+        // gets to the compare value
+        self.timer.start(TICK_MS * 1_000);
+
+        loop {
+            for (task, due) in self.tasks.iter().zip(self.due.iter_mut()) {
+                // The clock wraps around after about 49 days. Comparing the
+                // difference instead of the times themselves keeps working
+                // across that, as long as no period is longer than half of
+                // it.
+                if is_due(*due, self.now_ms) {
+                    (task.run)(context, self.now_ms);
+                    *due = due.wrapping_add(task.period_ms);
+                    // A task that missed its turn, because the ones before it
+                    // took too long, runs once, not once for every turn it
+                    // missed
+                    if is_due(*due, self.now_ms) {
+                        *due = self.now_ms.wrapping_add(task.period_ms);
+                    }
+                }
+            }
+
+            // Wait for the next tick. Every interrupt wakes us up, not only
+            // the timer's, so check that it was the timer.
+            //
+            // If the tasks took longer than a tick, the timer has fired more
+            // than once in the meantime, but that only counts as one tick, so
+            // the clock falls behind. A good reason to keep tasks short.
+            while !idle::timer_fired(&mut self.timer) {
+                idle::sleep();
+            }
+            self.now_ms = self.now_ms.wrapping_add(TICK_MS);
+        }
+    }
+}
+
+/// Whether something that's `due` at that time is due at `now_ms`, for tasks
+/// that time things themselves.
+pub fn is_due(due: u32, now_ms: u32) -> bool {
+    now_ms.wrapping_sub(due) as i32 >= 0
+}
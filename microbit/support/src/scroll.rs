@@ -0,0 +1,67 @@
+//! Scrolling text across the LED matrix.
+
+use crate::display::{clear_display, display_leds};
+use crate::font5x5;
+use embedded_hal_02::blocking::delay::{DelayMs, DelayUs};
+use microbit::display::blocking::Display;
+
+/// How long each step of the scroll stays on screen.
+pub const SCROLL_STEP_MS: u32 = 120;
+
+/// Scroll `text` from right to left across the display, one column per step.
+///
+/// The text starts just off the right edge and scrolls until the last
+/// character has left the display again, so this blocks for roughly
+/// `6 * text.len() * SCROLL_STEP_MS` milliseconds.
+pub fn scroll_text<D: DelayUs<u32>>(display: &mut Display, timer: &mut D, text: &str) {
+    for leds in steps(text) {
+        display.show(timer, leds, SCROLL_STEP_MS);
+    }
+    display.clear();
+}
+
+/// Like [`scroll_text`], but for the interrupt driven display set up with
+/// [`init_display`](crate::display::init_display).
+///
+/// The display is left blank afterwards.
+pub fn scroll_text_nonblocking<D: DelayMs<u32>>(timer: &mut D, text: &str) {
+    for leds in steps(text) {
+        display_leds(leds);
+        timer.delay_ms(SCROLL_STEP_MS);
+    }
+    clear_display();
+}
+
+/// Every picture shown while scrolling `text`, one step at a time.
+///
+/// For programs that cannot afford to block while the text scrolls: show the
+/// next one every [`SCROLL_STEP_MS`] milliseconds in between their other work.
+pub fn steps(text: &str) -> impl Iterator<Item = [[u8; 5]; 5]> + '_ {
+    columns(text).scan([[0; 5]; 5], |leds, column| {
+        for (row, lit) in leds.iter_mut().zip(column.iter()) {
+            row.rotate_left(1);
+            row[4] = *lit;
+        }
+        Some(*leds)
+    })
+}
+
+/// All columns making up `text`, left to right, one blank column after every
+/// character and followed by a blank screen's worth of columns so the text
+/// scrolls out completely.
+fn columns(text: &str) -> impl Iterator<Item = [u8; 5]> + '_ {
+    text.chars()
+        .flat_map(|c| {
+            let rows = font5x5::glyph(c);
+            (0..6).map(move |col| {
+                let mut column = [0; 5];
+                if col < 5 {
+                    for (lit, bits) in column.iter_mut().zip(rows.iter()) {
+                        *lit = (bits >> (4 - col)) & 1;
+                    }
+                }
+                column
+            })
+        })
+        .chain(core::iter::repeat_n([0; 5], 5))
+}
@@ -0,0 +1,1247 @@
+//! A serial port on the nRF52's UARTE, for the micro:bit v2.
+//!
+//! [`UartePort`] wraps the HAL's `Uarte`, so that it can be used through the
+//! `embedded-hal` 0.2 traits the same way as the micro:bit v1's `Uart`, and
+//! through those of `embedded-hal-nb` and `embedded-io` too. On top of that
+//! it does hardware flow control, notices when the line goes quiet, and
+//! receives in the background, by interrupt or DMA.
+//!
+//! Serial adapters without RTS and CTS can use XON/XOFF flow control
+//! instead, with [`FlowControl::Software`].
+//!
+//! A byte that arrives garbled, because the two ends disagree about the baud
+//! rate or the parity, or one that gets lost because nobody picked it up in
+//! time, is counted, see [`UartePort::stats`].
+//!
+//! [`AsyncPort`] is a port to `.await`, for running on an executor like
+//! [`block_on`](crate::executor::block_on).
+//!
+//! The nRF52833 has two UARTEs, and both can be ports at the same time. UARTE0
+//! is usually the one connected to the interface chip, and UARTE1 can go to
+//! any other pins.
+//!
+//! Receiving in the background takes the UARTEs' interrupt handlers, which
+//! come with the `serial-interrupt` feature. A program with handlers of its
+//! own calls [`on_uarte0_interrupt`] or [`on_uarte1_interrupt`] from them.
+
+use crate::channel::{Channel, Receiver, Sender};
+use crate::shared::Shared;
+use crate::softdevice;
+#[cfg(feature = "serial-interrupt")]
+use crate::weak_interrupt::weak_interrupt;
+use core::fmt;
+use core::future::poll_fn;
+use core::hint;
+use core::ptr::addr_of_mut;
+use core::sync::atomic::{compiler_fence, AtomicBool, AtomicU32, AtomicU8, AtomicUsize, Ordering};
+use core::task::{Poll, Waker};
+use cortex_m::interrupt::free;
+use embedded_hal_02::blocking::serial as bserial;
+use embedded_hal_02::digital::v2::OutputPin;
+use embedded_hal_02::serial;
+use embedded_hal_02::timer::{Cancel, CountDown};
+use embedded_hal_nb::serial as serial_nb;
+use microbit::hal::gpio::{Floating, Input, Output, Pin, PushPull};
+use microbit::hal::ppi::ConfigurablePpi;
+use microbit::hal::timer::{self, Timer};
+use microbit::hal::uarte::{Baudrate, Error, Instance, Parity, Uarte, UarteRx, UarteTx};
+use microbit::pac::{self, uarte0::RegisterBlock, UARTE0, UARTE1};
+
+/// The nRF52 series can't send more than 255 bytes in one go.
+const DMA_CHUNK_LEN: usize = 255;
+
+/// With software flow control `write_all` sends smaller chunks, so that it
+/// stops soon after an XOFF arrives.
+const XOFF_CHUNK_LEN: usize = 16;
+
+/// Asks the other end to stop sending, and to go on again. These are Ctrl-S
+/// and Ctrl-Q, which terminals have understood that way for a long time.
+pub const XOFF: u8 = 0x13;
+pub const XON: u8 = 0x11;
+
+/// How far we are with asking the other end to stop. Only the interrupt
+/// moves away from `FLOWING`, and only the main program moves back to it.
+const FLOWING: u8 = 0;
+/// The queue is filling up, but the transmitter was busy.
+const XOFF_PENDING: u8 = 1;
+const XOFF_SENT: u8 = 2;
+
+/// The two buffers EasyDMA takes turns filling with `enable_rx_dma`.
+const RX_CHUNK_LEN: usize = 16;
+
+/// What the interrupt counted, see [`SerialStats`].
+struct ErrorCounts {
+    overruns: AtomicU32,
+    parity_errors: AtomicU32,
+    framing_errors: AtomicU32,
+    breaks: AtomicU32,
+    dropped: AtomicU32,
+}
+
+impl ErrorCounts {
+    const fn new() -> Self {
+        ErrorCounts {
+            overruns: AtomicU32::new(0),
+            parity_errors: AtomicU32::new(0),
+            framing_errors: AtomicU32::new(0),
+            breaks: AtomicU32::new(0),
+            dropped: AtomicU32::new(0),
+        }
+    }
+
+    fn all(&self) -> [&AtomicU32; 5] {
+        [
+            &self.overruns,
+            &self.parity_errors,
+            &self.framing_errors,
+            &self.breaks,
+            &self.dropped,
+        ]
+    }
+}
+
+/// Where a future leaves its waker, for the interrupt to wake it with.
+struct WakerSlot(Shared<Waker>);
+
+impl WakerSlot {
+    const fn new() -> Self {
+        WakerSlot(Shared::new())
+    }
+
+    fn register(&self, waker: &Waker) {
+        // If the interrupt wakes the old waker in between, the future is just
+        // polled once more than it needs to be
+        if self.0.with(|old| old.will_wake(waker)) != Some(true) {
+            self.0.lend(waker.clone());
+        }
+    }
+
+    fn wake(&self) {
+        if let Some(waker) = self.0.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// What a port and the interrupt of its UARTE share. UARTE0 and UARTE1 each
+/// have their own, so that both can be ports at the same time.
+struct PortShared {
+    /// Whether the port does XON/XOFF flow control.
+    software_flow: AtomicBool,
+    /// The other end sent XOFF, and hasn't sent XON since.
+    tx_paused: AtomicBool,
+    /// The main program is using the transmitter. While it is, the interrupt
+    /// can't send an XOFF itself and leaves it to the main program instead.
+    tx_busy: AtomicBool,
+    /// One of `FLOWING`, `XOFF_PENDING` and `XOFF_SENT`.
+    rx_flow: AtomicU8,
+    /// Which of the `rx_chunks` EasyDMA is filling right now.
+    rx_filling: AtomicUsize,
+    /// Where the interrupt sends the bytes it receives, the sending end of
+    /// the channel in the port's `Buffers`.
+    rx_sender: Shared<&'static mut dyn RxSender>,
+    errors: ErrorCounts,
+    /// The [`AsyncPort`] futures waiting for bytes to arrive, and for a write
+    /// to finish or the other end to send XON.
+    rx_waker: WakerSlot,
+    tx_waker: WakerSlot,
+}
+
+impl PortShared {
+    const fn new() -> Self {
+        PortShared {
+            software_flow: AtomicBool::new(false),
+            tx_paused: AtomicBool::new(false),
+            tx_busy: AtomicBool::new(false),
+            rx_flow: AtomicU8::new(FLOWING),
+            rx_filling: AtomicUsize::new(0),
+            rx_sender: Shared::new(),
+            errors: ErrorCounts::new(),
+            rx_waker: WakerSlot::new(),
+            tx_waker: WakerSlot::new(),
+        }
+    }
+}
+
+static SHARED: [PortShared; 2] = [PortShared::new(), PortShared::new()];
+
+/// The memory EasyDMA works with for a UARTE, which has to stay put.
+struct DmaBuffers {
+    /// The HAL's `UarteRx` only ever receives a single byte at a time.
+    rx: [u8; 1],
+    /// EasyDMA can only read from RAM, so `write_all` copies each chunk in
+    /// here first.
+    tx: [u8; DMA_CHUNK_LEN],
+    /// Where an XON or XOFF is sent from, it can't share `tx` with a
+    /// `write_all` that's in the middle of a chunk.
+    control: [u8; 1],
+    rx_chunks: [[u8; RX_CHUNK_LEN]; 2],
+}
+
+impl DmaBuffers {
+    const fn new() -> Self {
+        DmaBuffers {
+            rx: [0; 1],
+            tx: [0; DMA_CHUNK_LEN],
+            control: [0; 1],
+            rx_chunks: [[0; RX_CHUNK_LEN]; 2],
+        }
+    }
+}
+
+static mut DMA_BUFFERS: [DmaBuffers; 2] = [DmaBuffers::new(), DmaBuffers::new()];
+
+/// One of the UARTEs, along with what belongs to it.
+#[derive(Clone, Copy)]
+struct Port {
+    uarte: &'static RegisterBlock,
+    index: usize,
+}
+
+impl Port {
+    fn of<T: Instance>() -> Port {
+        let index = if T::ptr() == UARTE1::ptr() { 1 } else { 0 };
+        Port {
+            uarte: unsafe { &*T::ptr() },
+            index,
+        }
+    }
+
+    fn shared(self) -> &'static PortShared {
+        &SHARED[self.index]
+    }
+
+    /// Only one part of the buffers may be borrowed at a time, and only by
+    /// whoever owns that part right now, the port or the interrupt.
+    fn buffers(self) -> *mut DmaBuffers {
+        unsafe { addr_of_mut!(DMA_BUFFERS[self.index]) }
+    }
+
+    fn interrupt(self) -> pac::Interrupt {
+        match self.index {
+            0 => pac::Interrupt::UARTE0_UART0,
+            _ => pac::Interrupt::UARTE1,
+        }
+    }
+}
+
+/// What `UartePort::new` uses, the buffers of a port that doesn't bring its
+/// own.
+static mut BUFFERS: Buffers = Buffers::new();
+
+/// The ends of a `Channel` of any size, so that `PortShared` and the port can
+/// hold the ones of whichever `Buffers` the port got.
+trait RxSender: Send {
+    fn send(&mut self, byte: u8) -> Result<(), u8>;
+    fn len(&self) -> usize;
+    fn capacity(&self) -> usize;
+}
+
+impl<const N: usize> RxSender for Sender<u8, N> {
+    fn send(&mut self, byte: u8) -> Result<(), u8> {
+        Sender::send(self, byte)
+    }
+
+    fn len(&self) -> usize {
+        Sender::len(self)
+    }
+
+    fn capacity(&self) -> usize {
+        Sender::capacity(self)
+    }
+}
+
+trait RxReceiver: Send {
+    fn recv(&mut self) -> Option<u8>;
+    fn len(&self) -> usize;
+    fn capacity(&self) -> usize;
+}
+
+impl<const N: usize> RxReceiver for Receiver<u8, N> {
+    fn recv(&mut self) -> Option<u8> {
+        Receiver::recv(self)
+    }
+
+    fn len(&self) -> usize {
+        Receiver::len(self)
+    }
+
+    fn capacity(&self) -> usize {
+        Receiver::capacity(self)
+    }
+}
+
+/// The memory a [`UartePort`] works with: room for `TX` bytes that have been
+/// written but not sent yet, and for `RX` - 1 bytes that have been received
+/// in the background but not read yet.
+///
+/// With the default of a single byte, every write is sent straight away. A
+/// bigger `TX` collects the bytes until it's full or the port is flushed, and
+/// sends them with a single DMA transfer, which takes less of the CPU for long
+/// replies. A bigger `RX` lets the main loop be busy for longer without losing
+/// anything, at 115200 baud the default lasts about 22 ms. `TX` can't be 0.
+pub struct Buffers<const TX: usize = 1, const RX: usize = 256> {
+    tx: [u8; TX],
+    rx: Channel<u8, RX>,
+    /// The two ends of `rx`, once a port has split it.
+    rx_sender: Option<Sender<u8, RX>>,
+    rx_receiver: Option<Receiver<u8, RX>>,
+}
+
+impl<const TX: usize, const RX: usize> Buffers<TX, RX> {
+    /// Empty buffers. The UARTE can only get at memory that stays put, so
+    /// these have to live in a `static`, for example with
+    /// `cortex_m::singleton!(: Buffers<64, 1024> = Buffers::new())`.
+    pub const fn new() -> Self {
+        Buffers {
+            tx: [0; TX],
+            rx: Channel::new(),
+            rx_sender: None,
+            rx_receiver: None,
+        }
+    }
+}
+
+impl<const TX: usize, const RX: usize> Default for Buffers<TX, RX> {
+    fn default() -> Self {
+        Buffers::new()
+    }
+}
+
+/// Line settings of a serial port.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SerialConfig {
+    pub baudrate: Baudrate,
+    pub parity: Parity,
+}
+
+impl SerialConfig {
+    /// Look up the `Baudrate` for a speed in bits per second, if the UARTE
+    /// supports it.
+    pub fn baudrate_from_bps(bps: u32) -> Option<Baudrate> {
+        let baudrate = match bps {
+            1200 => Baudrate::BAUD1200,
+            2400 => Baudrate::BAUD2400,
+            4800 => Baudrate::BAUD4800,
+            9600 => Baudrate::BAUD9600,
+            14400 => Baudrate::BAUD14400,
+            19200 => Baudrate::BAUD19200,
+            28800 => Baudrate::BAUD28800,
+            31250 => Baudrate::BAUD31250,
+            38400 => Baudrate::BAUD38400,
+            56000 => Baudrate::BAUD56000,
+            57600 => Baudrate::BAUD57600,
+            76800 => Baudrate::BAUD76800,
+            115200 => Baudrate::BAUD115200,
+            230400 => Baudrate::BAUD230400,
+            250000 => Baudrate::BAUD250000,
+            460800 => Baudrate::BAUD460800,
+            921600 => Baudrate::BAUD921600,
+            1000000 => Baudrate::BAUD1M,
+            _ => return None,
+        };
+        Some(baudrate)
+    }
+}
+
+/// 115200 baud without parity, the settings used throughout this chapter.
+impl Default for SerialConfig {
+    fn default() -> Self {
+        SerialConfig {
+            baudrate: Baudrate::BAUD115200,
+            parity: Parity::EXCLUDED,
+        }
+    }
+}
+
+/// What went wrong while receiving in the background, since the port was
+/// made.
+///
+/// The UARTE only reports errors with an interrupt, so nothing is counted
+/// while the port receives one `read` at a time.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SerialStats {
+    /// Bytes that arrived while the UARTE's own buffer was still full, and
+    /// were lost.
+    pub overruns: u32,
+    /// Bytes whose parity bit was wrong.
+    pub parity_errors: u32,
+    /// Bytes that didn't end with a stop bit, usually because the other end
+    /// uses a different baud rate.
+    pub framing_errors: u32,
+    /// Times the line was held low for longer than a whole byte, which is
+    /// how some adapters get the other end's attention, and what a
+    /// disconnected wire looks like.
+    pub breaks: u32,
+    /// Bytes that arrived while the queue was full, and were dropped.
+    pub dropped: u32,
+}
+
+/// Whether the two ends of the line tell each other when they are ready to
+/// receive.
+///
+/// With hardware flow control the UARTE doesn't send while the other end keeps
+/// `cts` high, and raises `rts` itself when it can't take any more bytes, so
+/// nothing is lost at high baud rates. Both wires have to be connected to the
+/// opposite signals on the other side.
+///
+/// Software flow control needs no extra wires, the two ends send each other
+/// [`XOFF`] and [`XON`] bytes instead. The port stops writing when it receives
+/// XOFF, and goes on after XON. It sends XOFF itself once the bytes received
+/// in the background fill three quarters of the queue, and XON once `read`
+/// has taken it down to a quarter. That only works while receiving in the
+/// background, with `enable_rx_interrupt` or `enable_rx_dma`, and XON and
+/// XOFF can't be sent as data any more.
+pub enum FlowControl {
+    Disabled,
+    Enabled {
+        cts: Pin<Input<Floating>>,
+        rts: Pin<Output<PushPull>>,
+    },
+    Software,
+}
+
+/// What `read_until_idle` needs to notice that the line has gone quiet: a
+/// timer, and two PPI channels to connect it to the UARTE.
+pub struct IdleTimeout<I: timer::Instance, A, B> {
+    timer: Timer<I>,
+    restart: A,
+    stop: B,
+    quiet_us: u32,
+}
+
+impl<I: timer::Instance, A: ConfigurablePpi, B: ConfigurablePpi> IdleTimeout<I, A, B> {
+    /// Treat `quiet_us` microseconds without a new byte as the end of a
+    /// message.
+    pub fn new(timer: Timer<I>, restart: A, stop: B, quiet_us: u32) -> Self {
+        IdleTimeout {
+            timer,
+            restart,
+            stop,
+            quiet_us,
+        }
+    }
+}
+
+/// Where received bytes come from.
+#[derive(Clone, Copy, PartialEq)]
+enum RxMode {
+    /// Straight from the hardware, one `read` at a time.
+    Polled,
+    /// From `rx_queue`, filled one byte at a time by the interrupt.
+    Interrupt,
+    /// From `rx_queue`, filled one chunk at a time by the interrupt.
+    Dma,
+}
+
+pub struct UartePort<T: Instance> {
+    tx: UarteTx<T>,
+    rx: UarteRx<T>,
+    rx_mode: RxMode,
+    /// Bytes received in the background that have not been read yet.
+    rx_queue: &'static mut dyn RxReceiver,
+}
+
+impl<T: Instance> UartePort<T> {
+    /// A port with the default [`Buffers`]. Those exist just once, so there
+    /// can't be more than one port made like this.
+    pub fn new(serial: Uarte<T>, flow_control: FlowControl) -> UartePort<T> {
+        Self::with_buffers(serial, flow_control, unsafe { &mut *addr_of_mut!(BUFFERS) })
+    }
+
+    /// A port with `buffers` of a size of its own. Each `Buffers` can only be
+    /// used by one port.
+    pub fn with_buffers<const TX: usize, const RX: usize>(
+        serial: Uarte<T>,
+        flow_control: FlowControl,
+        buffers: &'static mut Buffers<TX, RX>,
+    ) -> UartePort<T> {
+        let port = Port::of::<T>();
+        let shared = port.shared();
+        shared.software_flow.store(
+            matches!(flow_control, FlowControl::Software),
+            Ordering::Relaxed,
+        );
+        shared.tx_paused.store(false, Ordering::Relaxed);
+        shared.rx_flow.store(FLOWING, Ordering::Relaxed);
+        for count in shared.errors.all() {
+            count.store(0, Ordering::Relaxed);
+        }
+        if let FlowControl::Enabled { cts, mut rts } = flow_control {
+            let uarte = port.uarte;
+            // Not ready to receive until the UARTE takes over the pin
+            rts.set_high().unwrap();
+            // The pins can only be changed while the UARTE is disabled
+            uarte.enable.write(|w| w.enable().disabled());
+            uarte.psel.cts.write(|w| {
+                unsafe { w.bits(cts.psel_bits()) };
+                w.connect().connected()
+            });
+            uarte.psel.rts.write(|w| {
+                unsafe { w.bits(rts.psel_bits()) };
+                w.connect().connected()
+            });
+            uarte.config.modify(|_, w| w.hwfc().enabled());
+            uarte.enable.write(|w| w.enable().enabled());
+        }
+
+        let Buffers {
+            tx: tx_buf,
+            rx,
+            rx_sender,
+            rx_receiver,
+        } = buffers;
+        let rx: &'static Channel<u8, RX> = rx;
+        let (sender, receiver) = rx.split().expect("the buffers already belong to a port");
+        let rx_sender: &'static mut dyn RxSender = rx_sender.insert(sender);
+        shared.rx_sender.lend(rx_sender);
+        let (tx, rx) = serial
+            .split(tx_buf, unsafe { &mut (*port.buffers()).rx })
+            .unwrap();
+        UartePort {
+            tx,
+            rx,
+            rx_mode: RxMode::Polled,
+            rx_queue: rx_receiver.insert(receiver),
+        }
+    }
+
+    /// How many bytes received in the background are waiting to be read.
+    pub fn rx_available(&self) -> usize {
+        self.rx_queue.len()
+    }
+
+    /// What got garbled or lost on the way in so far.
+    pub fn stats(&self) -> SerialStats {
+        let errors = &Port::of::<T>().shared().errors;
+        SerialStats {
+            overruns: errors.overruns.load(Ordering::Relaxed),
+            parity_errors: errors.parity_errors.load(Ordering::Relaxed),
+            framing_errors: errors.framing_errors.load(Ordering::Relaxed),
+            breaks: errors.breaks.load(Ordering::Relaxed),
+            dropped: errors.dropped.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Switch to different line settings.
+    ///
+    /// Anything still waiting to be sent goes out with the old settings
+    /// first. A byte that is only partially received when this is called is
+    /// lost. The other end has to be switched over as well, of course.
+    pub fn reconfigure(&mut self, config: SerialConfig) {
+        nb::block!(serial::Write::flush(&mut self.tx)).ok();
+        self.release_tx();
+
+        let port = Port::of::<T>();
+        let uarte = port.uarte;
+        free(|_| {
+            // Stop receiving, whether that happens in the background or not
+            uarte
+                .intenclr
+                .write(|w| w.endrx().clear().rxstarted().clear().error().clear());
+            uarte.shorts.modify(|_, w| w.endrx_startrx().disabled());
+            stop_rx(uarte);
+
+            // The settings can only be changed while the UARTE is disabled.
+            // Leave hardware flow control the way it was.
+            uarte.enable.write(|w| w.enable().disabled());
+            uarte
+                .config
+                .modify(|_, w| w.parity().variant(config.parity));
+            uarte
+                .baudrate
+                .write(|w| w.baudrate().variant(config.baudrate));
+            uarte.enable.write(|w| w.enable().enabled());
+
+            // Don't let the interrupt pick up what was flushed above
+            pac::NVIC::unpend(port.interrupt());
+            match self.rx_mode {
+                RxMode::Polled => {}
+                RxMode::Interrupt => start_interrupt_rx(port),
+                RxMode::Dma => start_dma_rx(port),
+            }
+        });
+    }
+
+    /// Receive into `buf` until the line has been quiet for the time set in
+    /// `idle`, or `buf` is full, and return how many bytes arrived.
+    ///
+    /// This is meant for messages that don't end in a known terminator. The
+    /// clock only starts once this is called, so if nothing arrives at all it
+    /// returns 0 after one idle period instead of waiting forever. `buf` can be
+    /// at most 255 bytes long.
+    ///
+    /// Can't be used while the UARTE interrupt receives in the background.
+    pub fn read_until_idle<I, A, B>(
+        &mut self,
+        buf: &mut [u8],
+        idle: &mut IdleTimeout<I, A, B>,
+    ) -> Result<usize, Error>
+    where
+        I: timer::Instance,
+        A: ConfigurablePpi,
+        B: ConfigurablePpi,
+    {
+        assert!(
+            self.rx_mode == RxMode::Polled,
+            "read_until_idle while receiving in the background"
+        );
+        if buf.is_empty() {
+            return Err(Error::RxBufferTooSmall);
+        }
+        if buf.len() > DMA_CHUNK_LEN {
+            return Err(Error::RxBufferTooLong);
+        }
+
+        let uarte = Port::of::<T>().uarte;
+        // A half finished single byte `read` would get in the way
+        stop_rx(uarte);
+
+        uarte
+            .rxd
+            .ptr
+            .write(|w| unsafe { w.ptr().bits(buf.as_mut_ptr() as u32) });
+        uarte
+            .rxd
+            .maxcnt
+            .write(|w| unsafe { w.maxcnt().bits(buf.len() as _) });
+        uarte.events_endrx.reset();
+        uarte.events_rxto.reset();
+
+        // Every received byte restarts the timer, and if it ever runs out the
+        // reception is stopped, all without the CPU having to do anything
+        idle.restart.set_event_endpoint(&uarte.events_rxdrdy);
+        idle.restart.set_task_endpoint(idle.timer.task_clear());
+        idle.stop.set_event_endpoint(idle.timer.event_compare_cc0());
+        idle.stop.set_task_endpoint(&uarte.tasks_stoprx);
+        idle.restart.enable();
+        idle.stop.enable();
+
+        compiler_fence(Ordering::SeqCst);
+        uarte.tasks_startrx.write(|w| unsafe { w.bits(1) });
+        idle.timer.start(idle.quiet_us);
+
+        loop {
+            if uarte.events_rxto.read().bits() != 0 {
+                // Stopped by the timer, collect what is left in the FIFO
+                uarte.tasks_flushrx.write(|w| unsafe { w.bits(1) });
+                while uarte.events_endrx.read().bits() == 0 {}
+                break;
+            }
+            if uarte.events_endrx.read().bits() != 0 {
+                // The buffer is full, stop receiving ourselves
+                uarte.tasks_stoprx.write(|w| unsafe { w.bits(1) });
+                while uarte.events_rxto.read().bits() == 0 {}
+                break;
+            }
+        }
+
+        idle.restart.disable();
+        idle.stop.disable();
+        idle.timer.cancel().ok();
+        compiler_fence(Ordering::SeqCst);
+
+        let count = uarte.rxd.amount.read().bits() as usize;
+        uarte.events_rxto.reset();
+        uarte.events_endrx.reset();
+        uarte.events_rxstarted.reset();
+        Ok(count)
+    }
+
+    /// Send all of `bytes`, letting EasyDMA move up to 255 of them at a time.
+    ///
+    /// The CPU only has to get involved once per chunk instead of once per
+    /// byte.
+    pub fn write_all(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        // Anything still sitting in the single byte buffer goes out first
+        nb::block!(serial::Write::flush(self))?;
+
+        let port = Port::of::<T>();
+        let shared = port.shared();
+        let software_flow = shared.software_flow.load(Ordering::Relaxed);
+        let chunk_len = if software_flow {
+            XOFF_CHUNK_LEN
+        } else {
+            DMA_CHUNK_LEN
+        };
+        shared.tx_busy.store(true, Ordering::Relaxed);
+        let result = bytes.chunks(chunk_len).try_for_each(|chunk| {
+            while software_flow && shared.tx_paused.load(Ordering::Relaxed) {
+                hint::spin_loop();
+            }
+            let buf = unsafe { &mut (*port.buffers()).tx };
+            buf[..chunk.len()].copy_from_slice(chunk);
+            dma_write(port.uarte, &buf[..chunk.len()])
+        });
+        self.release_tx();
+        result
+    }
+
+    /// Let the interrupt have the transmitter again, and send the XOFF it
+    /// couldn't send while it was busy.
+    fn release_tx(&mut self) {
+        let shared = Port::of::<T>().shared();
+        shared.tx_busy.store(false, Ordering::Relaxed);
+        if shared.rx_flow.load(Ordering::Relaxed) == XOFF_PENDING {
+            self.send_control(XOFF);
+            shared.rx_flow.store(XOFF_SENT, Ordering::Relaxed);
+        }
+    }
+
+    /// Send XON or XOFF right away, after whatever has been written already,
+    /// even if the other end has sent XOFF.
+    fn send_control(&mut self, byte: u8) {
+        let port = Port::of::<T>();
+        port.shared().tx_busy.store(true, Ordering::Relaxed);
+        nb::block!(serial::Write::flush(&mut self.tx)).ok();
+        let buf = unsafe { &mut (*port.buffers()).control };
+        buf[0] = byte;
+        dma_write(port.uarte, buf).ok();
+        port.shared().tx_busy.store(false, Ordering::Relaxed);
+    }
+
+    /// Tell the other end to go on sending, once `read` has made enough room.
+    fn resume_rx(&mut self) {
+        let shared = Port::of::<T>().shared();
+        let flow = shared.rx_flow.load(Ordering::Relaxed);
+        if flow == FLOWING || self.rx_queue.len() > self.rx_queue.capacity() / 4 {
+            return;
+        }
+        if flow == XOFF_SENT {
+            self.send_control(XON);
+        }
+        shared.rx_flow.store(FLOWING, Ordering::Relaxed);
+    }
+}
+
+/// Send `buf`, which has to be in RAM, and wait until it has gone out.
+fn dma_write(uarte: &RegisterBlock, buf: &[u8]) -> Result<(), Error> {
+    start_tx(uarte, buf);
+    while !tx_done(uarte) {}
+    finish_tx(uarte)
+}
+
+/// Start sending `buf`, which has to be in RAM and stay put until
+/// [`tx_done`].
+fn start_tx(uarte: &RegisterBlock, buf: &[u8]) {
+    // Keeps the transmitter from being touched before `tx_busy` is set
+    compiler_fence(Ordering::SeqCst);
+    uarte.events_endtx.reset();
+    uarte.events_txstopped.reset();
+    uarte
+        .txd
+        .ptr
+        .write(|w| unsafe { w.ptr().bits(buf.as_ptr() as u32) });
+    uarte
+        .txd
+        .maxcnt
+        .write(|w| unsafe { w.maxcnt().bits(buf.len() as _) });
+
+    compiler_fence(Ordering::SeqCst);
+    uarte.tasks_starttx.write(|w| unsafe { w.bits(1) });
+}
+
+fn tx_done(uarte: &RegisterBlock) -> bool {
+    uarte.events_endtx.read().bits() != 0 || uarte.events_txstopped.read().bits() != 0
+}
+
+/// Clean up after a transfer that is done, and tell whether all of it went
+/// out.
+fn finish_tx(uarte: &RegisterBlock) -> Result<(), Error> {
+    compiler_fence(Ordering::SeqCst);
+    let stopped_early = uarte.events_txstopped.read().bits() != 0;
+
+    // Switch the transmitter off again and leave the events the way the
+    // single byte writes expect to find them
+    uarte.tasks_stoptx.write(|w| unsafe { w.bits(1) });
+    while uarte.events_txstopped.read().bits() == 0 {}
+    uarte.events_txstarted.reset();
+    uarte.events_endtx.reset();
+    uarte.events_txstopped.reset();
+
+    if stopped_early {
+        Err(Error::Transmit)
+    } else {
+        Ok(())
+    }
+}
+
+impl<T: Instance> UartePort<T> {
+    /// Receive in the background from now on.
+    ///
+    /// Every byte that arrives is pushed into a ring buffer by the UARTE's
+    /// interrupt, and `read` takes bytes out of that buffer instead of asking
+    /// the hardware. Nothing gets lost while the main loop is busy, as long as
+    /// it catches up before the buffer fills.
+    pub fn enable_rx_interrupt(&mut self) {
+        let port = Port::of::<T>();
+        start_interrupt_rx(port);
+
+        self.rx_mode = RxMode::Interrupt;
+        unsafe { softdevice::unmask(port.interrupt()) }
+    }
+
+    /// Receive in the background using two DMA buffers in turn.
+    ///
+    /// While EasyDMA fills one buffer the interrupt copies the other one into
+    /// the queue `read` takes bytes from, and the hardware moves on to the next
+    /// buffer by itself, so reception never pauses. The interrupt only runs
+    /// once every 16 bytes, which keeps up even at 1 Mbaud.
+    ///
+    /// Bytes only become readable once a whole buffer has been filled, which
+    /// suits a steady stream of data. For typing at a terminal
+    /// `enable_rx_interrupt` is the better choice.
+    pub fn enable_rx_dma(&mut self) {
+        let port = Port::of::<T>();
+        start_dma_rx(port);
+
+        self.rx_mode = RxMode::Dma;
+        unsafe { softdevice::unmask(port.interrupt()) }
+    }
+}
+
+/// Stop a reception that is in progress, if there is one, and put the events
+/// back the way they were before it started.
+fn stop_rx(uarte: &RegisterBlock) {
+    if uarte.events_rxstarted.read().bits() != 0 {
+        uarte.tasks_stoprx.write(|w| unsafe { w.bits(1) });
+        while uarte.events_rxto.read().bits() == 0 {}
+        uarte.tasks_flushrx.write(|w| unsafe { w.bits(1) });
+        while uarte.events_endrx.read().bits() == 0 {}
+        uarte.events_rxto.reset();
+        uarte.events_endrx.reset();
+        uarte.events_rxstarted.reset();
+    }
+}
+
+fn start_dma_rx(port: Port) {
+    let uarte = port.uarte;
+    port.shared().rx_filling.store(0, Ordering::Relaxed);
+    let chunks = unsafe { &mut (*port.buffers()).rx_chunks };
+    uarte
+        .rxd
+        .ptr
+        .write(|w| unsafe { w.ptr().bits(chunks[0].as_mut_ptr() as u32) });
+    uarte
+        .rxd
+        .maxcnt
+        .write(|w| unsafe { w.maxcnt().bits(RX_CHUNK_LEN as _) });
+    // As soon as one buffer is full, start filling the one RXD.PTR points to by
+    // then
+    uarte.shorts.modify(|_, w| w.endrx_startrx().enabled());
+    uarte.events_endrx.reset();
+    uarte.events_rxstarted.reset();
+    uarte.events_error.reset();
+    uarte
+        .intenset
+        .write(|w| w.endrx().set().rxstarted().set().error().set());
+
+    compiler_fence(Ordering::SeqCst);
+    uarte.tasks_startrx.write(|w| unsafe { w.bits(1) });
+}
+
+fn start_interrupt_rx(port: Port) {
+    let uarte = port.uarte;
+    let buf = unsafe { addr_of_mut!((*port.buffers()).rx) };
+    uarte.rxd.ptr.write(|w| unsafe { w.ptr().bits(buf as u32) });
+    uarte.rxd.maxcnt.write(|w| unsafe { w.maxcnt().bits(1) });
+    // Start listening for the next byte as soon as one has been received,
+    // without waiting for the interrupt handler
+    uarte.shorts.modify(|_, w| w.endrx_startrx().enabled());
+    uarte.events_endrx.reset();
+    uarte.events_error.reset();
+    uarte.intenset.write(|w| w.endrx().set().error().set());
+
+    compiler_fence(Ordering::SeqCst);
+    uarte.tasks_startrx.write(|w| unsafe { w.bits(1) });
+}
+
+impl<T: Instance> fmt::Write for UartePort<T> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.write_all(s.as_bytes()).map_err(|_| fmt::Error)
+    }
+}
+
+impl<T: Instance> serial::Write<u8> for UartePort<T> {
+    type Error = Error;
+
+    fn write(&mut self, b: u8) -> nb::Result<(), Self::Error> {
+        let shared = Port::of::<T>().shared();
+        if shared.software_flow.load(Ordering::Relaxed) {
+            if shared.rx_flow.load(Ordering::Relaxed) == XOFF_PENDING {
+                serial::Write::flush(self)?;
+            }
+            if shared.tx_paused.load(Ordering::Relaxed) {
+                return Err(nb::Error::WouldBlock);
+            }
+        }
+        shared.tx_busy.store(true, Ordering::Relaxed);
+        self.tx.write(b)
+    }
+
+    fn flush(&mut self) -> nb::Result<(), Self::Error> {
+        let shared = Port::of::<T>().shared();
+        if shared.software_flow.load(Ordering::Relaxed) && shared.tx_paused.load(Ordering::Relaxed)
+        {
+            return Err(nb::Error::WouldBlock);
+        }
+        let result = self.tx.flush();
+        if !matches!(result, Err(nb::Error::WouldBlock)) {
+            self.release_tx();
+        }
+        result
+    }
+}
+
+impl<T: Instance> bserial::write::Default<u8> for UartePort<T> {}
+
+impl<T: Instance> serial::Read<u8> for UartePort<T> {
+    type Error = Error;
+
+    fn read(&mut self) -> nb::Result<u8, Self::Error> {
+        if self.rx_mode != RxMode::Polled {
+            let byte = self.rx_queue.recv().ok_or(nb::Error::WouldBlock)?;
+            self.resume_rx();
+            Ok(byte)
+        } else {
+            self.rx.read()
+        }
+    }
+}
+
+/// `embedded_io` and `embedded_hal_nb` want an error type that implements
+/// their `Error` traits, which the HAL's error type doesn't.
+#[derive(Debug)]
+pub struct IoError(pub Error);
+
+impl embedded_io::Error for IoError {
+    fn kind(&self) -> embedded_io::ErrorKind {
+        embedded_io::ErrorKind::Other
+    }
+}
+
+impl serial_nb::Error for IoError {
+    fn kind(&self) -> serial_nb::ErrorKind {
+        serial_nb::ErrorKind::Other
+    }
+}
+
+impl<T: Instance> serial_nb::ErrorType for UartePort<T> {
+    type Error = IoError;
+}
+
+impl<T: Instance> serial_nb::Write for UartePort<T> {
+    fn write(&mut self, byte: u8) -> nb::Result<(), Self::Error> {
+        serial::Write::write(self, byte).map_err(|error| error.map(IoError))
+    }
+
+    fn flush(&mut self) -> nb::Result<(), Self::Error> {
+        serial::Write::flush(self).map_err(|error| error.map(IoError))
+    }
+}
+
+impl<T: Instance> serial_nb::Read for UartePort<T> {
+    fn read(&mut self) -> nb::Result<u8, Self::Error> {
+        serial::Read::read(self).map_err(|error| error.map(IoError))
+    }
+}
+
+impl<T: Instance> embedded_io::ErrorType for UartePort<T> {
+    type Error = IoError;
+}
+
+impl<T: Instance> embedded_io::Read for UartePort<T> {
+    /// Wait for the first byte, then return it together with any others that
+    /// have already arrived, up to the size of `buf`.
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        buf[0] = nb::block!(serial::Read::read(self)).map_err(IoError)?;
+        let mut count = 1;
+        while count < buf.len() {
+            match serial::Read::read(self) {
+                Ok(byte) => buf[count] = byte,
+                Err(nb::Error::WouldBlock) => break,
+                Err(nb::Error::Other(error)) => return Err(IoError(error)),
+            }
+            count += 1;
+        }
+        Ok(count)
+    }
+}
+
+impl<T: Instance> embedded_io::Write for UartePort<T> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.write_all(buf).map_err(IoError)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        nb::block!(serial::Write::flush(self)).map_err(IoError)
+    }
+}
+
+/// A [`UartePort`] whose reads and writes are `async`.
+///
+/// Where the port spins until a byte arrives, or a write has gone out, the
+/// futures of an `AsyncPort` return `Pending`, and the UARTE's interrupt wakes
+/// them once there's something to do. Meanwhile the executor can sleep, or
+/// get on with another future. Reads and writes each leave their waker in a
+/// slot of their own.
+///
+/// It receives in the background, with `enable_rx_interrupt` unless the port
+/// already did with `enable_rx_dma`.
+pub struct AsyncPort<T: Instance> {
+    port: UartePort<T>,
+}
+
+impl<T: Instance> AsyncPort<T> {
+    pub fn new(mut port: UartePort<T>) -> AsyncPort<T> {
+        if port.rx_mode == RxMode::Polled {
+            port.enable_rx_interrupt();
+        }
+        AsyncPort { port }
+    }
+
+    /// The blocking port again, for whatever `AsyncPort` can't do, like
+    /// `reconfigure`.
+    pub fn into_inner(self) -> UartePort<T> {
+        self.port
+    }
+
+    /// Wait for the first byte, then return it together with any others that
+    /// have already arrived, up to the size of `buf`.
+    pub async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let shared = Port::of::<T>().shared();
+        let port = &self.port;
+        poll_fn(|cx| {
+            // Before looking, so that a byte that arrives in between still
+            // wakes us
+            shared.rx_waker.register(cx.waker());
+            if port.rx_available() > 0 {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        })
+        .await;
+        embedded_io::Read::read(&mut self.port, buf).map_err(|IoError(error)| error)
+    }
+
+    /// Send all of `bytes`, letting EasyDMA move up to 255 of them at a time,
+    /// and wait until they have gone out.
+    ///
+    /// The transmitter stays busy until the future is done, so don't drop it
+    /// before then.
+    pub async fn write_all(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        // Anything still sitting in the single byte buffer goes out first
+        nb::block!(serial::Write::flush(&mut self.port))?;
+
+        let port = Port::of::<T>();
+        let shared = port.shared();
+        let software_flow = shared.software_flow.load(Ordering::Relaxed);
+        let chunk_len = if software_flow {
+            XOFF_CHUNK_LEN
+        } else {
+            DMA_CHUNK_LEN
+        };
+        shared.tx_busy.store(true, Ordering::Relaxed);
+        let mut result = Ok(());
+        for chunk in bytes.chunks(chunk_len) {
+            poll_fn(|cx| {
+                shared.tx_waker.register(cx.waker());
+                if software_flow && shared.tx_paused.load(Ordering::Relaxed) {
+                    Poll::Pending
+                } else {
+                    Poll::Ready(())
+                }
+            })
+            .await;
+
+            let buf = unsafe { &mut (*port.buffers()).tx };
+            buf[..chunk.len()].copy_from_slice(chunk);
+            start_tx(port.uarte, &buf[..chunk.len()]);
+            poll_fn(|cx| {
+                shared.tx_waker.register(cx.waker());
+                // An event that is already there fires the interrupt right
+                // away
+                port.uarte
+                    .intenset
+                    .write(|w| w.endtx().set().txstopped().set());
+                if tx_done(port.uarte) {
+                    Poll::Ready(())
+                } else {
+                    Poll::Pending
+                }
+            })
+            .await;
+            port.uarte
+                .intenclr
+                .write(|w| w.endtx().clear().txstopped().clear());
+
+            result = finish_tx(port.uarte);
+            if result.is_err() {
+                break;
+            }
+        }
+        self.port.release_tx();
+        result
+    }
+}
+
+#[cfg(feature = "serial-interrupt")]
+weak_interrupt!(UARTE0_UART0 => on_uarte0_interrupt);
+#[cfg(feature = "serial-interrupt")]
+weak_interrupt!(UARTE1 => on_uarte1_interrupt);
+
+/// What the `UARTE0_UART0` handler does: take in what UARTE0 received, and
+/// count its errors.
+pub fn on_uarte0_interrupt() {
+    on_interrupt(Port::of::<UARTE0>());
+}
+
+/// The same for UARTE1, what the `UARTE1` handler does.
+pub fn on_uarte1_interrupt() {
+    on_interrupt(Port::of::<UARTE1>());
+}
+
+fn on_interrupt(port: Port) {
+    let uarte = port.uarte;
+
+    if uarte.events_error.read().bits() != 0 {
+        uarte.events_error.reset();
+        count_errors(port);
+    }
+
+    // Only an `AsyncPort` write turns these on, and the events stay for it to
+    // clean up after
+    if uarte.inten.read().endtx().is_enabled() && tx_done(uarte) {
+        uarte
+            .intenclr
+            .write(|w| w.endtx().clear().txstopped().clear());
+        port.shared().tx_waker.wake();
+    }
+
+    if uarte.inten.read().rxstarted().is_enabled() {
+        dma_rx_event(port);
+    } else if uarte.events_endrx.read().bits() != 0 {
+        uarte.events_endrx.reset();
+        compiler_fence(Ordering::SeqCst);
+
+        let byte = unsafe { (*port.buffers()).rx[0] };
+        let shared = port.shared();
+        let filling_up = shared
+            .rx_sender
+            .with(|sender| receive(shared, &mut **sender, &[byte]))
+            .unwrap_or(false);
+        shared.rx_waker.wake();
+        if filling_up {
+            pause_rx(port);
+        }
+    }
+}
+
+/// Count what went wrong with the bytes received since the last ERROR event.
+/// The byte itself still arrives, garbled or not.
+fn count_errors(port: Port) {
+    let source = port.uarte.errorsrc.read();
+    // Its bits are cleared by writing 1s
+    port.uarte
+        .errorsrc
+        .write(|w| unsafe { w.bits(source.bits()) });
+
+    let errors = &port.shared().errors;
+    let counts = [
+        (source.overrun().is_present(), &errors.overruns),
+        (source.parity().is_present(), &errors.parity_errors),
+        (source.framing().is_present(), &errors.framing_errors),
+        (source.break_().is_present(), &errors.breaks),
+    ];
+    for (present, count) in counts {
+        if present {
+            count.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Put received bytes into the queue, and tell whether it's time to ask the
+/// other end to stop. With software flow control XON and XOFF are taken out
+/// on the way.
+fn receive(shared: &PortShared, sender: &mut dyn RxSender, bytes: &[u8]) -> bool {
+    let software_flow = shared.software_flow.load(Ordering::Relaxed);
+    for &byte in bytes {
+        match byte {
+            XOFF if software_flow => shared.tx_paused.store(true, Ordering::Relaxed),
+            XON if software_flow => {
+                shared.tx_paused.store(false, Ordering::Relaxed);
+                shared.tx_waker.wake();
+            }
+            // If the buffer is full the byte is dropped, there is nowhere
+            // else to put it
+            _ => {
+                if sender.send(byte).is_err() {
+                    shared.errors.dropped.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        }
+    }
+    software_flow && sender.len() >= sender.capacity() / 4 * 3
+}
+
+/// Ask the other end to stop sending, unless that has happened already.
+fn pause_rx(port: Port) {
+    let shared = port.shared();
+    if shared.rx_flow.load(Ordering::Relaxed) != FLOWING {
+        return;
+    }
+    if shared.tx_busy.load(Ordering::Relaxed) {
+        // The main program sends it once it's done with the transmitter
+        shared.rx_flow.store(XOFF_PENDING, Ordering::Relaxed);
+    } else {
+        let buf = unsafe { &mut (*port.buffers()).control };
+        buf[0] = XOFF;
+        dma_write(port.uarte, buf).ok();
+        shared.rx_flow.store(XOFF_SENT, Ordering::Relaxed);
+    }
+}
+
+fn dma_rx_event(port: Port) {
+    let uarte = port.uarte;
+    let shared = port.shared();
+    let chunks = unsafe { &mut (*port.buffers()).rx_chunks };
+
+    // ENDRX always comes before the RXSTARTED of the following buffer, so
+    // handle it first in case both are pending
+    if uarte.events_endrx.read().bits() != 0 {
+        uarte.events_endrx.reset();
+        compiler_fence(Ordering::SeqCst);
+
+        let filled = shared.rx_filling.load(Ordering::Relaxed);
+        let count = uarte.rxd.amount.read().bits() as usize;
+        let filling_up = shared
+            .rx_sender
+            .with(|sender| receive(shared, &mut **sender, &chunks[filled][..count]))
+            .unwrap_or(false);
+        shared.rx_filling.store(filled ^ 1, Ordering::Relaxed);
+        shared.rx_waker.wake();
+        if filling_up {
+            pause_rx(port);
+        }
+    }
+
+    if uarte.events_rxstarted.read().bits() != 0 {
+        uarte.events_rxstarted.reset();
+        // RXD.PTR has been latched for the buffer that is being filled now,
+        // so it can already be pointed at the next one
+        let next = shared.rx_filling.load(Ordering::Relaxed) ^ 1;
+        uarte
+            .rxd
+            .ptr
+            .write(|w| unsafe { w.ptr().bits(chunks[next].as_mut_ptr() as u32) });
+    }
+}
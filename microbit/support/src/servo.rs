@@ -0,0 +1,96 @@
+//! Hobby servos on the edge connector.
+//!
+//! A servo has three wires: power, ground, and a signal that tells it where
+//! to turn. The signal is a pulse every 20 ms, 50 times a second, and the
+//! length of the pulse is the position: 1 ms for all the way to one side,
+//! 2 ms for all the way to the other, 1.5 ms for the middle. The `PWM`
+//! peripheral makes those pulses by itself, so the servo holds its position
+//! without the CPU doing anything.
+//!
+//! Not every servo sticks to 1 and 2 ms. Many turn further with pulses a bit
+//! shorter and longer than that, and some buzz at the ends because they
+//! can't get all the way there. [`Servo::calibrate`] sets the pulse lengths
+//! that the ends of the range stand for.
+
+use microbit::hal::gpio::{Output, Pin, PushPull};
+use microbit::hal::pwm::{self, Channel, Prescaler, Pwm};
+
+/// The time between two pulses, in µs.
+const PERIOD_US: u16 = 20_000;
+/// The pulse lengths of the two ends, in µs, unless calibrated otherwise.
+pub const DEFAULT_MIN_US: u16 = 1_000;
+pub const DEFAULT_MAX_US: u16 = 2_000;
+/// How far a servo turns, from one end to the other.
+pub const MAX_ANGLE: u16 = 180;
+
+pub struct Servo<T: pwm::Instance> {
+    pwm: Pwm<T>,
+    min_us: u16,
+    max_us: u16,
+}
+
+impl<T: pwm::Instance> Servo<T> {
+    /// Start sending pulses on `pin`, to turn the servo to the middle.
+    pub fn new(pwm: T, pin: Pin<Output<PushPull>>) -> Servo<T> {
+        let pwm = Pwm::new(pwm);
+        // At 1 MHz the counter counts microseconds, and 20 ms fit into its 15
+        // bits
+        pwm.set_prescaler(Prescaler::Div16);
+        pwm.set_max_duty(PERIOD_US);
+        pwm.set_output_pin(Channel::C0, pin);
+
+        let mut servo = Servo {
+            pwm,
+            min_us: DEFAULT_MIN_US,
+            max_us: DEFAULT_MAX_US,
+        };
+        servo.set_angle(MAX_ANGLE / 2);
+        servo
+    }
+
+    /// Make `min_us` the pulse length for an angle of 0, and `max_us` for
+    /// [`MAX_ANGLE`].
+    ///
+    /// Panics unless `min_us` is less than `max_us`, and `max_us` fits into a
+    /// period.
+    pub fn calibrate(&mut self, min_us: u16, max_us: u16) {
+        assert!(
+            min_us < max_us && max_us < PERIOD_US,
+            "{} to {} µs isn't a range of pulses",
+            min_us,
+            max_us
+        );
+        self.min_us = min_us;
+        self.max_us = max_us;
+    }
+
+    /// The pulse lengths of the two ends, as last calibrated.
+    pub fn calibration(&self) -> (u16, u16) {
+        (self.min_us, self.max_us)
+    }
+
+    /// Turn to `degrees`, from 0 at one end to [`MAX_ANGLE`] at the other.
+    /// Anything further is as far as it goes.
+    pub fn set_angle(&mut self, degrees: u16) {
+        let degrees = u32::from(degrees.min(MAX_ANGLE));
+        let range = u32::from(self.max_us - self.min_us);
+        let pulse_us = u32::from(self.min_us) + range * degrees / u32::from(MAX_ANGLE);
+        self.set_pulse_us(pulse_us as u16);
+    }
+
+    /// Send pulses of `pulse_us` µs, whatever the calibration. Handy to find
+    /// out how far a servo will go.
+    pub fn set_pulse_us(&mut self, pulse_us: u16) {
+        // The pin goes high at the start of every period, and low once the
+        // counter gets to the duty. That's what the HAL calls the duty *off*.
+        self.pwm.set_duty_off(Channel::C0, pulse_us.min(PERIOD_US));
+    }
+
+    /// Stop the pulses, so that the servo goes limp, and give back the `PWM`
+    /// peripheral and the pin.
+    pub fn free(mut self) -> (T, Pin<Output<PushPull>>) {
+        self.pwm.disable();
+        let pin = self.pwm.clear_output_pin(Channel::C0).unwrap();
+        (self.pwm.free(), pin)
+    }
+}
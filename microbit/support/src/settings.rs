@@ -0,0 +1,274 @@
+//! Small settings that survive a reset, kept in the last page of flash.
+//!
+//! Flash is written through the `NVMC`, the non-volatile memory controller,
+//! one word at a time, and a write can only turn 1s into 0s. The only way to
+//! get the 1s back is to erase a whole page, which also takes a while and
+//! wears the flash out a little bit every time.
+//!
+//! So a setting is never changed where it is. [`Settings::set`] *appends* a
+//! new record with the key and the value to the end of the page, and
+//! [`Settings::get`] goes for the last record with that key. Only once the
+//! page is full, the newest record of every key is copied to RAM, the page is
+//! erased, and they are written back.
+//!
+//! Every record carries a checksum. If anything on the page doesn't add up,
+//! say because the power went out halfway through a write, [`Settings::take`]
+//! erases the whole page: all settings are back to their defaults, which beats
+//! believing garbage.
+//!
+//! The program itself is kept at the start of the flash, and the `memory.x`
+//! of every chapter leaves the last page out of its way, see
+//! [`flash_layout`](crate::flash_layout).
+
+use crate::flash::{self, ERASED, PAGE_SIZE};
+use core::cell::Cell;
+use cortex_m::interrupt::{free, Mutex};
+use heapless::Vec;
+use microbit::pac::{self, NVMC};
+
+/// Who uses which key. Keys say what a value is, so two programs that use
+/// the same key for different things would read each other's garbage.
+pub mod keys {
+    use super::Key;
+
+    /// The compass calibration of chapter 9.
+    pub const COMPASS_CALIBRATION: Key = 1;
+    /// The baud rate of the serial port, in bits per second.
+    pub const SERIAL_BAUD: Key = 2;
+    /// The best score of a game.
+    pub const HIGH_SCORE: Key = 3;
+    /// The best time of the reaction game, in milliseconds.
+    pub const REACTION_TIME: Key = 4;
+    /// The rate of the data logger of chapter 26, and whether it's recording.
+    pub const DATA_LOGGER: Key = 5;
+    /// The last command typed into the shell of chapter 7.
+    pub const SHELL_LAST_COMMAND: Key = 6;
+}
+
+/// What a setting is stored under. 255 is what erased flash reads as, so it
+/// can't be used.
+pub type Key = u8;
+
+/// How long a value can be, in bytes.
+pub const MAX_VALUE_LEN: usize = 32;
+/// How many different keys fit. Compacting the page has to keep one value of
+/// every key in RAM.
+pub const MAX_KEYS: usize = 16;
+
+#[cfg(feature = "v2")]
+const PAGE_ADDRESS: usize = crate::flash_layout::v2::SETTINGS_ADDRESS;
+#[cfg(feature = "v1")]
+const PAGE_ADDRESS: usize = crate::flash_layout::v1::SETTINGS_ADDRESS;
+
+const PAGE_WORDS: usize = PAGE_SIZE / 4;
+/// The first word of a page that holds settings, "Set1".
+const MAGIC: u32 = 0x3174_6553;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The value is longer than [`MAX_VALUE_LEN`].
+    TooLong,
+    /// The key is 255, which isn't allowed.
+    InvalidKey,
+    /// There are already [`MAX_KEYS`] other keys.
+    TooManyKeys,
+}
+
+/// Whether [`Settings::take`] has handed out the `NVMC` yet.
+static TAKEN: Mutex<Cell<bool>> = Mutex::new(Cell::new(false));
+
+pub struct Settings {
+    nvmc: NVMC,
+    /// Where the next record goes, in words from the start of the page.
+    end: usize,
+}
+
+impl Settings {
+    /// Take the `NVMC`, the first time this is called, and check the settings
+    /// page. If it doesn't hold valid settings, it is erased.
+    ///
+    /// `Board::take` doesn't hand out the `NVMC`, so we go behind its back,
+    /// just like `Board::take` itself.
+    pub fn take() -> Option<Settings> {
+        if free(|cs| TAKEN.borrow(cs).replace(true)) {
+            return None;
+        }
+        let nvmc = unsafe { pac::Peripherals::steal() }.NVMC;
+
+        let mut settings = Settings { nvmc, end: 1 };
+        match settings.check() {
+            Some(end) => settings.end = end,
+            None => settings.erase(),
+        }
+        Some(settings)
+    }
+
+    /// The value last stored under `key`, if there is one.
+    pub fn get(&self, key: Key) -> Option<Vec<u8, MAX_VALUE_LEN>> {
+        let mut found = None;
+        let mut offset = 1;
+        while offset < self.end {
+            let (record_key, len) = split_header(read_word(offset));
+            if record_key == key {
+                found = Some((offset + 1, len));
+            }
+            offset += record_words(len);
+        }
+
+        let (offset, len) = found?;
+        Some(read_value(offset, len))
+    }
+
+    /// Store `value` under `key`, replacing whatever was stored under it
+    /// before.
+    pub fn set(&mut self, key: Key, value: &[u8]) -> Result<(), Error> {
+        if value.len() > MAX_VALUE_LEN {
+            return Err(Error::TooLong);
+        }
+        if key == 0xff {
+            return Err(Error::InvalidKey);
+        }
+        let old = self.get(key);
+        // Writing what's already there anyway would only wear out the flash
+        if old.as_deref() == Some(value) {
+            return Ok(());
+        }
+        if old.is_none() && self.keys().is_full() {
+            return Err(Error::TooManyKeys);
+        }
+
+        if self.end + record_words(value.len()) > PAGE_WORDS {
+            self.compact(key);
+        }
+        self.append(key, value);
+        Ok(())
+    }
+
+    /// Forget all settings.
+    pub fn clear(&mut self) {
+        self.erase();
+    }
+
+    /// Walk through the records, and return where the first free word is, or
+    /// `None` if the page doesn't hold valid settings.
+    fn check(&self) -> Option<usize> {
+        if read_word(0) != MAGIC {
+            return None;
+        }
+
+        let mut offset = 1;
+        while offset < PAGE_WORDS {
+            let header = read_word(offset);
+            if header == ERASED {
+                break;
+            }
+            let (key, len) = split_header(header);
+            if key == 0xff || len > MAX_VALUE_LEN || offset + record_words(len) > PAGE_WORDS {
+                return None;
+            }
+            if header != make_header(key, &read_value(offset + 1, len)) {
+                return None;
+            }
+            offset += record_words(len);
+        }
+
+        // Everything after the last record has to be erased, or the next
+        // record written there would come out garbled
+        if (offset..PAGE_WORDS).any(|offset| read_word(offset) != ERASED) {
+            return None;
+        }
+        Some(offset)
+    }
+
+    /// Every key that has a value.
+    fn keys(&self) -> Vec<Key, MAX_KEYS> {
+        let mut keys = Vec::new();
+        let mut offset = 1;
+        while offset < self.end {
+            let (key, len) = split_header(read_word(offset));
+            if !keys.contains(&key) {
+                // `set` never lets there be more than `MAX_KEYS`
+                keys.push(key).unwrap();
+            }
+            offset += record_words(len);
+        }
+        keys
+    }
+
+    /// Make room by keeping only the newest value of every key, except
+    /// `new_key`, which is about to get a new one.
+    fn compact(&mut self, new_key: Key) {
+        let mut newest: Vec<(Key, Vec<u8, MAX_VALUE_LEN>), MAX_KEYS> = Vec::new();
+        for key in self.keys() {
+            if key != new_key {
+                newest.push((key, self.get(key).unwrap())).unwrap();
+            }
+        }
+
+        self.erase();
+        for (key, value) in newest.iter() {
+            self.append(*key, value);
+        }
+    }
+
+    fn append(&mut self, key: Key, value: &[u8]) {
+        let mut words: Vec<u32, { 1 + MAX_VALUE_LEN / 4 }> = Vec::new();
+        words.push(make_header(key, value)).unwrap();
+        for chunk in value.chunks(4) {
+            let mut bytes = [0xff; 4];
+            bytes[..chunk.len()].copy_from_slice(chunk);
+            words.push(u32::from_le_bytes(bytes)).unwrap();
+        }
+
+        flash::write_words(&self.nvmc, PAGE_ADDRESS + self.end * 4, &words);
+        self.end += words.len();
+    }
+
+    fn erase(&mut self) {
+        flash::erase_page(&self.nvmc, PAGE_ADDRESS);
+        flash::write_words(&self.nvmc, PAGE_ADDRESS, &[MAGIC]);
+        self.end = 1;
+    }
+}
+
+/// Read the word `offset` words into the page.
+fn read_word(offset: usize) -> u32 {
+    flash::read_word(PAGE_ADDRESS + offset * 4)
+}
+
+/// Read `len` bytes of a value, starting `offset` words into the page. `len`
+/// can't be more than [`MAX_VALUE_LEN`].
+fn read_value(offset: usize, len: usize) -> Vec<u8, MAX_VALUE_LEN> {
+    (0..len)
+        .map(|index| read_word(offset + index / 4).to_le_bytes()[index % 4])
+        .collect()
+}
+
+/// How many words a record with a value of `len` bytes takes, header
+/// included.
+fn record_words(len: usize) -> usize {
+    1 + len.div_ceil(4)
+}
+
+/// A record header holds the key in the lowest byte, the length of the value
+/// in the next one, and a checksum of both and the value in the top half.
+fn make_header(key: Key, value: &[u8]) -> u32 {
+    let len = value.len() as u8;
+    let checksum = fletcher16([key, len].iter().chain(value));
+    u32::from(key) | u32::from(len) << 8 | u32::from(checksum) << 16
+}
+
+fn split_header(header: u32) -> (Key, usize) {
+    (header as u8, usize::from((header >> 8) as u8))
+}
+
+/// A checksum that, unlike a plain sum, also notices bytes that have swapped
+/// places.
+fn fletcher16<'a>(bytes: impl Iterator<Item = &'a u8>) -> u16 {
+    let (mut low, mut high) = (0u16, 0u16);
+    for &byte in bytes {
+        low = (low + u16::from(byte)) % 255;
+        high = (high + low) % 255;
+    }
+    high << 8 | low
+}
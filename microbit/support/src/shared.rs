@@ -0,0 +1,60 @@
+//! Data shared between the main program and interrupt handlers.
+//!
+//! An interrupt handler can't be handed anything, it can only get at
+//! `static`s. Those always exist, even before the program has set up what's
+//! going to go in them, and both the handler and the main program can get at
+//! them at any time, even while the other one is halfway through changing
+//! them. So the value goes in an `Option`, which is `None` until it's set up,
+//! inside a `RefCell`, so that it can be changed, inside a `Mutex`, so that
+//! only code in a critical section can get at it.
+//!
+//! `Mutex<RefCell<Option<T>>>` is a mouthful, and getting at the value takes
+//! a few lines every time. [`Shared`] does the same with two methods: the
+//! main program calls [`lend`](Shared::lend) once it has set the value up,
+//! and from then on everybody uses [`with`](Shared::with). Whoever is done
+//! with it can [`take`](Shared::take) it back.
+
+use core::cell::RefCell;
+use critical_section::Mutex;
+
+pub struct Shared<T> {
+    value: Mutex<RefCell<Option<T>>>,
+}
+
+impl<T> Shared<T> {
+    /// Nothing has been lent yet.
+    pub const fn new() -> Shared<T> {
+        Shared {
+            value: Mutex::new(RefCell::new(None)),
+        }
+    }
+
+    /// Hand over `value`, usually once it has been set up and before the
+    /// interrupts that use it are unmasked. Whatever was lent before is
+    /// dropped.
+    pub fn lend(&self, value: T) {
+        critical_section::with(|cs| *self.value.borrow_ref_mut(cs) = Some(value));
+    }
+
+    /// Take back what was lent, if anything, so that nobody else can get at
+    /// it any more.
+    pub fn take(&self) -> Option<T> {
+        critical_section::with(|cs| self.value.borrow_ref_mut(cs).take())
+    }
+
+    /// Run `f` with the value, in a critical section, so that no interrupt
+    /// handler can get in the way. Returns what `f` returns, or `None` if
+    /// nothing has been lent yet.
+    ///
+    /// Interrupts have to wait until `f` is done, so it had better be quick.
+    /// Calling `with` again from inside `f`, on the same `Shared`, panics.
+    pub fn with<R>(&self, f: impl FnOnce(&mut T) -> R) -> Option<R> {
+        critical_section::with(|cs| self.value.borrow_ref_mut(cs).as_mut().map(f))
+    }
+}
+
+impl<T> Default for Shared<T> {
+    fn default() -> Shared<T> {
+        Shared::new()
+    }
+}
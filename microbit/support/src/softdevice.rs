@@ -0,0 +1,95 @@
+//! Sharing the chip with a SoftDevice, Nordic's Bluetooth stack.
+//!
+//! A SoftDevice isn't a library but a program of its own, flashed next to
+//! ours. It keeps the start of the flash and the RAM, some peripherals, and
+//! the most urgent interrupt priorities to itself, because a Bluetooth
+//! connection falls apart when the radio isn't served on time. Whatever
+//! breaks one of its rules makes it call its fault handler, which for
+//! `nrf-softdevice` ends in a panic, or it ends in a hard fault right away.
+//! The `s113` and `s140` features keep to those rules:
+//!
+//! - The chapter's build script links the program behind the SoftDevice, see
+//!   `support/build/memory_x.rs`. The SoftDevice's vector table stays at the
+//!   start of the flash: it takes the interrupts of its own peripherals, and
+//!   *forwards* all others to the vector table at the start of our program,
+//!   so the `#[interrupt]` handlers in here don't need to change.
+//! - Every interrupt this crate unmasks gets a priority the SoftDevice leaves
+//!   to the application first. Out of reset they're all at 0, the most
+//!   urgent, which is the SoftDevice's own. It keeps 0, 1 and 4, the
+//!   priorities `nrf-softdevice` calls `P0`, `P1` and `P4`.
+//! - The interrupts of the peripherals it owns aren't unmasked at all.
+//!   [`unmask`] panics instead, which says why, unlike the hard fault.
+//!
+//! That's enough for the display, the buttons, the serial port, the
+//! monotonic clock, and the sensors, whose I2C bus doesn't use an interrupt.
+//! Not everything in here can work next to a SoftDevice, though. While it's
+//! enabled, the flash can only be written by asking it, so the settings and
+//! the logs in flash can't be written. And [`random`](crate::random),
+//! [`thermometer`](crate::thermometer), [`clocks`](crate::clocks) and
+//! [`radio`](crate::radio) use peripherals that are the SoftDevice's, and
+//! have to be replaced by its own calls, like `sd_rand_application_vector_get`
+//! for random numbers.
+//!
+//! Without either feature, [`unmask`] is just `NVIC::unmask`.
+
+use microbit::pac::{self, Interrupt};
+
+/// The priorities the SoftDevice keeps to itself.
+#[cfg(any(feature = "s113", feature = "s140"))]
+pub const RESERVED_PRIORITIES: [u8; 3] = [0, 1, 4];
+
+/// The priority `interrupt` gets next to the SoftDevice, or `None` if it's
+/// one of the SoftDevice's own.
+///
+/// Smaller numbers are more urgent. The display and the serial port get the
+/// most urgent of those left, 2: the display flickers when a row stays on
+/// too long, and a byte that arrives before the last one was picked up is
+/// lost. The buttons and the clock can wait a little, at 3. Everything else
+/// goes below all of them, at 5, which leaves 6 and 7 for the program's own
+/// background work.
+#[cfg(any(feature = "s113", feature = "s140"))]
+pub fn priority(interrupt: Interrupt) -> Option<u8> {
+    match interrupt {
+        // These are the SoftDevice's interrupts. `SWI2_EGU2` tells the
+        // program about Bluetooth events, and `nrf-softdevice` handles it.
+        Interrupt::POWER_CLOCK
+        | Interrupt::RADIO
+        | Interrupt::TIMER0
+        | Interrupt::RTC0
+        | Interrupt::TEMP
+        | Interrupt::RNG
+        | Interrupt::ECB
+        | Interrupt::CCM_AAR
+        | Interrupt::SWI2_EGU2
+        | Interrupt::SWI5_EGU5
+        | Interrupt::MWU => None,
+        Interrupt::TIMER1 | Interrupt::UARTE0_UART0 | Interrupt::UARTE1 => Some(2),
+        Interrupt::GPIOTE | Interrupt::TIMER2 | Interrupt::RTC1 => Some(3),
+        _ => Some(5),
+    }
+}
+
+/// Unmask `interrupt`, at a priority the SoftDevice allows, if there is one.
+///
+/// # Safety
+///
+/// Like `NVIC::unmask`, this can break critical sections that only mask
+/// interrupts.
+///
+/// # Panics
+///
+/// With `s113` or `s140`, if `interrupt` belongs to the SoftDevice.
+pub unsafe fn unmask(interrupt: Interrupt) {
+    #[cfg(any(feature = "s113", feature = "s140"))]
+    {
+        let level = match priority(interrupt) {
+            Some(level) => level,
+            None => panic!("{:?} belongs to the SoftDevice", interrupt),
+        };
+        // Only the top `NVIC_PRIO_BITS` of a priority byte exist
+        let mut core = cortex_m::Peripherals::steal();
+        core.NVIC
+            .set_priority(interrupt, level << (8 - pac::NVIC_PRIO_BITS));
+    }
+    pac::NVIC::unmask(interrupt)
+}
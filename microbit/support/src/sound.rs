@@ -0,0 +1,145 @@
+//! Tones and melodies on the speaker of the micro:bit v2.
+//!
+//! The speaker hangs off pin P0.00. Switching that pin on and off at the
+//! frequency of a note plays the note, and the `PWM` peripheral can do the
+//! switching for us: we only tell it how long a period is, and that the pin
+//! should be on for half of it.
+//!
+//! On for half of the period is as loud as the speaker gets. On for less of
+//! it is quieter, so that's how [`Speaker::set_volume`] turns it down.
+
+use embedded_hal_02::blocking::delay::DelayMs;
+use microbit::hal::gpio::p0::P0_00;
+use microbit::hal::gpio::{Disconnected, Level};
+use microbit::hal::pwm::{Channel, Prescaler, Pwm};
+use microbit::hal::time::Hertz;
+use microbit::hal::timer::{self, Timer};
+use microbit::pac::PWM0;
+
+/// A duration in milliseconds.
+pub type Millis = u32;
+
+/// The pause after every note of a melody, so that two of the same note
+/// don't blur into one.
+const NOTE_GAP_MS: Millis = 10;
+
+/// The loudest volume, and the one a speaker starts out with.
+pub const MAX_VOLUME: u8 = 10;
+
+/// A note, by its frequency in Hz.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Note(pub u32);
+
+impl Note {
+    /// Silence.
+    pub const REST: Note = Note(0);
+
+    pub const C4: Note = Note(262);
+    pub const D4: Note = Note(294);
+    pub const E4: Note = Note(330);
+    pub const F4: Note = Note(349);
+    pub const G4: Note = Note(392);
+    pub const A4: Note = Note(440);
+    pub const B4: Note = Note(494);
+    pub const C5: Note = Note(523);
+    pub const D5: Note = Note(587);
+    pub const E5: Note = Note(659);
+    pub const F5: Note = Note(698);
+    pub const G5: Note = Note(784);
+    pub const A5: Note = Note(880);
+    pub const B5: Note = Note(988);
+    pub const C6: Note = Note(1047);
+}
+
+pub struct Speaker<T: timer::Instance> {
+    pwm: Pwm<PWM0>,
+    timer: Timer<T>,
+    volume: u8,
+    playing: bool,
+}
+
+impl<T: timer::Instance> Speaker<T> {
+    /// Take over the speaker pin. `timer` times how long every note lasts.
+    pub fn new(pwm: PWM0, speaker_pin: P0_00<Disconnected>, timer: T) -> Speaker<T> {
+        let pin = speaker_pin.into_push_pull_output(Level::Low).degrade();
+        let pwm = Pwm::new(pwm);
+        // At 2 MHz, the period of anything from 62 Hz upwards fits into the
+        // 15 bit counter
+        pwm.set_prescaler(Prescaler::Div8);
+        pwm.set_output_pin(Channel::C0, pin);
+        pwm.disable();
+
+        Speaker {
+            pwm,
+            timer: Timer::new(timer),
+            volume: MAX_VOLUME,
+            playing: false,
+        }
+    }
+
+    /// Play everything from now on at `volume`, from 0 for silence up to
+    /// [`MAX_VOLUME`]. A tone that's playing changes right away.
+    ///
+    /// The ear hears loudness about logarithmically, so the time the pin is
+    /// on goes with the square of the volume, which sounds more even than
+    /// going straight down.
+    pub fn set_volume(&mut self, volume: u8) {
+        self.volume = volume.min(MAX_VOLUME);
+        if self.playing {
+            self.pwm.set_duty_on_common(self.duty());
+        }
+    }
+
+    pub fn volume(&self) -> u8 {
+        self.volume
+    }
+
+    /// How long the pin is on in every period, for the volume.
+    fn duty(&self) -> u16 {
+        let half = u32::from(self.pwm.max_duty() / 2);
+        let volume = u32::from(self.volume);
+        let max = u32::from(MAX_VOLUME);
+        (half * volume * volume / (max * max)) as u16
+    }
+
+    /// Start playing a tone of `freq` Hz, until [`Speaker::stop`]. A
+    /// frequency of 0 is silence.
+    pub fn start_tone(&mut self, freq: u32) {
+        if freq == 0 {
+            self.stop();
+            return;
+        }
+        self.pwm.set_period(Hertz(freq));
+        self.pwm.enable();
+        self.pwm.set_duty_on_common(self.duty());
+        self.playing = true;
+    }
+
+    /// Stop playing.
+    pub fn stop(&mut self) {
+        self.pwm.disable();
+        self.playing = false;
+    }
+
+    /// Play a tone of `freq` Hz for `duration`, and wait until it's over. A
+    /// frequency of 0 is a rest.
+    pub fn play_note(&mut self, freq: u32, duration: Millis) {
+        self.start_tone(freq);
+        self.timer.delay_ms(duration);
+        self.stop();
+    }
+
+    /// Play one note after the other, each for as long as it says.
+    pub fn play_melody(&mut self, melody: &[(Note, Millis)]) {
+        self.play_notes(melody.iter().copied());
+    }
+
+    /// Like [`Speaker::play_melody`], for notes that aren't in a slice, such
+    /// as the ones of a [`Ringtone`](crate::rtttl::Ringtone).
+    pub fn play_notes(&mut self, notes: impl Iterator<Item = (Note, Millis)>) {
+        for (Note(freq), duration) in notes {
+            self.play_note(freq, duration.saturating_sub(NOTE_GAP_MS));
+            self.timer.delay_ms(duration.min(NOTE_GAP_MS));
+        }
+    }
+}
@@ -0,0 +1,80 @@
+//! How much stack a program uses, found out by *painting* it.
+//!
+//! Right after reset, before anything else runs, every word of RAM that the
+//! stack can grow into gets filled with a pattern. Whatever the program puts
+//! on the stack overwrites the pattern, and nothing ever paints it back. So
+//! later on, the lowest word that doesn't hold the pattern anymore is as far
+//! as the stack has ever got, its *high-water mark*.
+//!
+//! That's a measurement, not a guarantee: it only shows what the program has
+//! done so far. Let it run through everything it does, the deepest calls and
+//! the busiest interrupts, before trusting the number. And a word the stack
+//! took and that happened to hold the pattern anyway counts as unused, which
+//! is why the pattern is an unlikely value.
+//!
+//! Painting needs a hook that runs before `main`, which only one crate in a
+//! program can have, so this module only exists with the `stackcheck` feature.
+
+use core::arch::global_asm;
+use core::ptr::{addr_of, read_volatile};
+
+/// What the unused stack is painted with.
+const PAINT: u32 = 0xcdcd_cdcd;
+
+extern "C" {
+    // Both are set up by `cortex-m-rt`'s linker script. The stack starts at
+    // the top of RAM and grows down towards the statics, which end at
+    // `_stack_end`.
+    static _stack_start: u32;
+    static _stack_end: u32;
+}
+
+// `cortex-m-rt` calls `__pre_init` first thing after reset, before the
+// statics have been set up, so it can't be written in Rust. It paints from
+// the bottom of the stack up to where the stack pointer is now, which is the
+// top, since nothing has run yet.
+global_asm!(
+    ".section .text.__pre_init, \"ax\"
+     .global __pre_init
+     .type __pre_init, %function
+     .thumb_func
+     __pre_init:
+         ldr r0, =_stack_end
+         mov r1, sp
+         ldr r2, ={paint}
+     0:
+         cmp r0, r1
+         bhs 1f
+         str r2, [r0]
+         adds r0, #4
+         b 0b
+     1:
+         bx lr
+     .ltorg",
+    paint = const PAINT,
+);
+
+fn top() -> usize {
+    addr_of!(_stack_start) as usize
+}
+
+fn bottom() -> usize {
+    addr_of!(_stack_end) as usize
+}
+
+/// How big the stack can get, in bytes: all the RAM the statics don't take.
+pub fn stack_size() -> usize {
+    top() - bottom()
+}
+
+/// The most stack the program has used since reset, in bytes.
+pub fn stack_used() -> usize {
+    let mut address = bottom();
+    // Everything from the bottom up to the first word that has been
+    // overwritten was never used. None of it is in use right now either, so
+    // reading it doesn't get in anybody's way.
+    while address < top() && unsafe { read_volatile(address as *const u32) } == PAINT {
+        address += 4;
+    }
+    top() - address
+}
@@ -0,0 +1,123 @@
+//! The temperature of the chip, from the `TEMP` peripheral.
+//!
+//! `TEMP` measures the temperature of the nRF chip itself, in steps of a
+//! quarter of a degree Celsius. The chip warms itself up a little as it
+//! works, so that's usually a few degrees above the room, but it follows
+//! the room well enough to see a window being opened, or a hand warming the
+//! board up.
+
+use core::fmt;
+use microbit::pac::TEMP;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Unit {
+    Celsius,
+    Fahrenheit,
+}
+
+impl Unit {
+    /// The letter after the degree sign.
+    pub fn letter(self) -> char {
+        match self {
+            Unit::Celsius => 'C',
+            Unit::Fahrenheit => 'F',
+        }
+    }
+}
+
+/// A measured temperature.
+///
+/// It prints like `21.75°C`. The LED matrix's font has no degree sign, so
+/// `{:#}` leaves it out: `21.75C`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Temperature {
+    hundredths: i32,
+    unit: Unit,
+}
+
+impl Temperature {
+    /// The temperature in hundredths of a degree, of [`Temperature::unit`].
+    pub fn hundredths(self) -> i32 {
+        self.hundredths
+    }
+
+    pub fn unit(self) -> Unit {
+        self.unit
+    }
+
+    /// The temperature in degrees, of [`Temperature::unit`].
+    pub fn degrees(self) -> f32 {
+        self.hundredths as f32 / 100.
+    }
+}
+
+impl fmt::Display for Temperature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.hundredths < 0 { "-" } else { "" };
+        let hundredths = self.hundredths.unsigned_abs();
+        let degree = if f.alternate() { "" } else { "°" };
+        write!(
+            f,
+            "{}{}.{:02}{}{}",
+            sign,
+            hundredths / 100,
+            hundredths % 100,
+            degree,
+            self.unit.letter()
+        )
+    }
+}
+
+pub struct Thermometer {
+    temp: TEMP,
+    unit: Unit,
+}
+
+impl Thermometer {
+    /// Measure in `unit`, until [`Thermometer::set_unit`] says otherwise.
+    pub fn new(temp: TEMP, unit: Unit) -> Thermometer {
+        Thermometer { temp, unit }
+    }
+
+    pub fn unit(&self) -> Unit {
+        self.unit
+    }
+
+    pub fn set_unit(&mut self, unit: Unit) {
+        self.unit = unit;
+    }
+
+    /// Measure the temperature, in the raw quarters of a degree Celsius the
+    /// `TEMP` peripheral counts in. That takes around 36 µs on the nRF52, and
+    /// 50 µs on the nRF51.
+    pub fn measure_quarters(&mut self) -> i32 {
+        self.temp.events_datardy.reset();
+        self.temp.tasks_start.write(|w| unsafe { w.bits(1) });
+        while self.temp.events_datardy.read().bits() == 0 {}
+        self.temp.events_datardy.reset();
+        // The register holds a signed number, which the PAC only knows as
+        // bits
+        let quarters = self.temp.temp.read().bits() as i32;
+        self.temp.tasks_stop.write(|w| unsafe { w.bits(1) });
+        quarters
+    }
+
+    /// Measure the temperature, in the unit that's set.
+    pub fn measure(&mut self) -> Temperature {
+        let quarters = self.measure_quarters();
+        let hundredths = match self.unit {
+            Unit::Celsius => quarters * 25,
+            // °F = °C × 9 / 5 + 32, and the quarters make that × 25 × 9 / 5
+            Unit::Fahrenheit => quarters * 45 + 3_200,
+        };
+        Temperature {
+            hundredths,
+            unit: self.unit,
+        }
+    }
+
+    /// Give back the `TEMP` peripheral.
+    pub fn free(self) -> TEMP {
+        self.temp
+    }
+}
@@ -0,0 +1,223 @@
+//! Touch sensing, on the logo on the front of the micro:bit v2 and on the
+//! rings of its edge connector.
+//!
+//! The logo is a patch of copper on pin P1.04, with a very large resistor
+//! pulling it up to the supply. Every piece of metal can hold a little bit of
+//! charge, and it takes the resistor a while to fill the logo up: the more
+//! charge the logo can hold, the longer. A finger on the logo adds to that,
+//! so touching it makes the pin take noticeably longer to go high.
+//!
+//! To measure that, we empty the logo by driving the pin low, let go of it,
+//! and time how long it takes to read high again. [`TouchLogo::new`] measures
+//! the untouched logo first, so don't touch it while that happens. Anything
+//! well above that counts as a touch.
+//!
+//! The rings P0, P1 and P2 have the same very large pull up resistors, so
+//! [`TouchRings`] measures them the same way. Each of them gets a calibration
+//! of its own: whatever is plugged into one ring, a wire, a banana, changes
+//! how much charge that ring holds, but not the others.
+
+use embedded_hal_02::digital::v2::InputPin;
+use embedded_hal_02::timer::CountDown;
+use microbit::hal::gpio::p1::P1_04;
+use microbit::hal::gpio::{Disconnected, Floating, Input, Level, Pin};
+use microbit::hal::timer::{self, Timer};
+
+/// A finger on the logo slows it down to at least this many times the
+/// untouched time, in tenths.
+const TOUCHED_TENTHS: u32 = 15;
+/// Once touched, the logo is let go when it's faster again than this many
+/// tenths of the untouched time. Somewhere between the two, it stays as it
+/// was, so that a finger resting on the edge doesn't flicker.
+const RELEASED_TENTHS: u32 = 12;
+/// How long to wait for the pin to go high, in µs, before giving up.
+const TIMEOUT_US: u32 = 5_000;
+/// How many measurements to average when calibrating.
+const CALIBRATION_ROUNDS: u32 = 16;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TouchEvent {
+    Touched,
+    Released,
+}
+
+/// One pin that reacts to touch, and how it feels untouched.
+struct Pad {
+    /// Only `None` for a moment during `charge_time`, while the pin drives the
+    /// pad low.
+    pin: Option<Pin<Input<Floating>>>,
+    /// How long the untouched pad takes, in µs.
+    baseline: u32,
+    touched: bool,
+}
+
+impl Pad {
+    fn new(pin: Pin<Disconnected>) -> Pad {
+        Pad {
+            pin: Some(pin.into_floating_input()),
+            baseline: 0,
+            touched: false,
+        }
+    }
+
+    /// How long the pad takes to charge, in µs.
+    fn charge_time<T: timer::Instance>(&mut self, timer: &mut Timer<T>) -> u32 {
+        let pin = self.pin.take().unwrap();
+        let pin = pin.into_push_pull_output(Level::Low);
+        // Give the charge a moment to drain away
+        timer.delay(10);
+        let pin = pin.into_floating_input();
+
+        timer.start(TIMEOUT_US);
+        let time = loop {
+            if pin.is_high().unwrap() {
+                break timer.read();
+            }
+            if timer.wait().is_ok() {
+                break TIMEOUT_US;
+            }
+        };
+        self.pin = Some(pin);
+        time
+    }
+
+    /// Measure the pad while nobody touches it.
+    fn calibrate<T: timer::Instance>(&mut self, timer: &mut Timer<T>) {
+        let total: u32 = (0..CALIBRATION_ROUNDS)
+            .map(|_| self.charge_time(timer))
+            .sum();
+        // A baseline of 0 would make everything a touch
+        self.baseline = (total / CALIBRATION_ROUNDS).max(1);
+        self.touched = false;
+    }
+
+    fn is_touched<T: timer::Instance>(&mut self, timer: &mut Timer<T>) -> bool {
+        let time = self.charge_time(timer);
+        let tenths = if self.touched {
+            RELEASED_TENTHS
+        } else {
+            TOUCHED_TENTHS
+        };
+        self.touched = time * 10 >= self.baseline * tenths;
+        self.touched
+    }
+
+    fn poll_event<T: timer::Instance>(&mut self, timer: &mut Timer<T>) -> Option<TouchEvent> {
+        let was_touched = self.touched;
+        match (was_touched, self.is_touched(timer)) {
+            (false, true) => Some(TouchEvent::Touched),
+            (true, false) => Some(TouchEvent::Released),
+            _ => None,
+        }
+    }
+}
+
+pub struct TouchLogo<T: timer::Instance> {
+    pad: Pad,
+    timer: Timer<T>,
+}
+
+impl<T: timer::Instance> TouchLogo<T> {
+    /// Take over the logo and measure it while nobody touches it. `timer`
+    /// does the timing.
+    pub fn new(logo_pin: P1_04<Disconnected>, timer: T) -> TouchLogo<T> {
+        let mut logo = TouchLogo {
+            pad: Pad::new(logo_pin.degrade()),
+            timer: Timer::new(timer),
+        };
+        logo.pad.calibrate(&mut logo.timer);
+        logo
+    }
+
+    /// How long the logo takes to charge, in µs.
+    pub fn charge_time(&mut self) -> u32 {
+        self.pad.charge_time(&mut self.timer)
+    }
+
+    /// Whether somebody is touching the logo right now.
+    pub fn is_touched(&mut self) -> bool {
+        self.pad.is_touched(&mut self.timer)
+    }
+
+    /// Measure the logo, and tell if it has been touched or let go since
+    /// last time.
+    pub fn poll_event(&mut self) -> Option<TouchEvent> {
+        self.pad.poll_event(&mut self.timer)
+    }
+}
+
+/// One of the big rings of the edge connector.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Ring {
+    P0,
+    P1,
+    P2,
+}
+
+impl Ring {
+    pub const ALL: [Ring; 3] = [Ring::P0, Ring::P1, Ring::P2];
+}
+
+/// The rings P0, P1 and P2, as touch pads.
+pub struct TouchRings<T: timer::Instance> {
+    pads: [Pad; 3],
+    timer: Timer<T>,
+}
+
+impl<T: timer::Instance> TouchRings<T> {
+    /// Take over the rings, `board.pins.p0_02`, `p0_03` and `p0_04`, and
+    /// measure each of them while nobody touches it. `timer` does the
+    /// timing.
+    pub fn new(
+        p0: Pin<Disconnected>,
+        p1: Pin<Disconnected>,
+        p2: Pin<Disconnected>,
+        timer: T,
+    ) -> TouchRings<T> {
+        let mut rings = TouchRings {
+            pads: [Pad::new(p0), Pad::new(p1), Pad::new(p2)],
+            timer: Timer::new(timer),
+        };
+        for ring in Ring::ALL {
+            rings.calibrate(ring);
+        }
+        rings
+    }
+
+    /// Measure `ring` again while nobody touches it, say after plugging
+    /// something new into it.
+    pub fn calibrate(&mut self, ring: Ring) {
+        self.pads[ring as usize].calibrate(&mut self.timer);
+    }
+
+    /// How long `ring` takes to charge untouched, in µs, as it was measured
+    /// when calibrating.
+    pub fn baseline(&self, ring: Ring) -> u32 {
+        self.pads[ring as usize].baseline
+    }
+
+    /// How long `ring` takes to charge right now, in µs.
+    pub fn charge_time(&mut self, ring: Ring) -> u32 {
+        self.pads[ring as usize].charge_time(&mut self.timer)
+    }
+
+    /// Whether somebody is touching `ring` right now.
+    pub fn is_touched(&mut self, ring: Ring) -> bool {
+        self.pads[ring as usize].is_touched(&mut self.timer)
+    }
+
+    /// Measure `ring`, and tell if it has been touched or let go since
+    /// last time.
+    pub fn poll_event(&mut self, ring: Ring) -> Option<TouchEvent> {
+        self.pads[ring as usize].poll_event(&mut self.timer)
+    }
+
+    /// Measure the rings, until one of them has been touched or let go since
+    /// last time. The ones after it wait for the next call.
+    pub fn next_event(&mut self) -> Option<(Ring, TouchEvent)> {
+        Ring::ALL
+            .iter()
+            .copied()
+            .find_map(|ring| self.poll_event(ring).map(|event| (ring, event)))
+    }
+}
@@ -0,0 +1,46 @@
+//! Stand-ins on the micro:bit v1 for the modules only the v2 has.
+//!
+//! Without them, a program for the v1 that uses, say, the speaker only gets
+//! told that there's no `sound` in `discovery_support`, as if it had never
+//! existed. So on the v1, `discovery_support::sound` is still there, but
+//! every type in it is one that can't be used for anything, other than an
+//! error that says what to do:
+//!
+//! ```text
+//! error[E0277]: `discovery_support::sound` needs the micro:bit v2 (feature "v2")
+//! ```
+//!
+//! A `compile_error!` in the stand-in would go off while the support crate
+//! itself is built, so it's a trait that nothing implements instead, and the
+//! error only comes when a type that needs it is used. A module that's used
+//! through its functions or constants alone still has nothing in it.
+
+/// A stand-in for each module, `name "message" { types }`: the message its
+/// error should have, and the types in it. Those take up to two generic
+/// parameters, so that a type that has them still gets to the error.
+macro_rules! v2_only {
+    ($($module:ident $message:literal { $($item:ident),* })*) => {
+        $(
+            #[doc(hidden)]
+            pub mod $module {
+                use core::marker::PhantomData;
+
+                #[diagnostic::on_unimplemented(
+                    message = $message,
+                    label = "only the micro:bit v2 has this",
+                    note = "build with `--features v2 --target thumbv7em-none-eabihf`"
+                )]
+                pub trait V2Module {}
+
+                pub struct Module;
+
+                pub struct V2Only<M: V2Module, T>(PhantomData<(M, T)>);
+
+                $(
+                    pub type $item<A = (), B = ()> = V2Only<Module, (A, B)>;
+                )*
+            }
+        )*
+    };
+}
+pub(crate) use v2_only;
@@ -0,0 +1,112 @@
+//! The watchdog, which resets the chip unless it's fed in time.
+//!
+//! Once started, the `WDT` counts down from its timeout, and when it gets to
+//! 0 it resets the whole chip. Writing a magic value to one of its *reload
+//! request* registers, feeding it, starts the countdown over. Nothing can
+//! stop it again, short of a reset.
+//!
+//! A program that feeds the watchdog in its main loop gets reset whenever
+//! that loop gets stuck. With more than one thing going on, say the main loop
+//! and an interrupt, each of them can get a [`Feeder`] of its own. The
+//! countdown only starts over once *every* feeder has been fed, so any one of
+//! them getting stuck is enough for a reset.
+
+use core::cell::Cell;
+use cortex_m::interrupt::{free, Mutex};
+use microbit::pac::{self, WDT};
+
+/// The watchdog has 8 reload request registers.
+pub const MAX_FEEDERS: usize = 8;
+/// What has to be written to a reload request register to feed it.
+const RELOAD: u32 = 0x6e52_4635;
+/// The watchdog counts the 32.768 kHz low frequency clock.
+const TICKS_PER_SECOND: u64 = 32_768;
+/// The shortest timeout the watchdog can do is 15 ticks.
+const MIN_TICKS: u32 = 15;
+
+/// Whether [`Watchdog::start`] has handed out the `WDT` peripheral yet. The
+/// micro:bit v1 can't swap an `AtomicBool`, so this takes a critical section
+/// instead.
+static TAKEN: Mutex<Cell<bool>> = Mutex::new(Cell::new(false));
+
+/// The running watchdog. Holding on to it makes sure nobody else can
+/// configure it again.
+pub struct Watchdog {
+    _wdt: WDT,
+}
+
+/// One of the reload requests of the watchdog.
+///
+/// It doesn't borrow the [`Watchdog`], so it can be moved wherever the
+/// feeding needs to happen, like into an interrupt handler.
+pub struct Feeder {
+    index: usize,
+}
+
+impl Watchdog {
+    /// Start the watchdog, so that it resets the chip unless all `N` feeders
+    /// it returns are fed every `timeout_ms`. That only works the first time
+    /// this is called.
+    ///
+    /// `Board::take` takes all the peripherals, but doesn't hand out the
+    /// `WDT`, so we go behind its back, just like `Board::take` itself.
+    ///
+    /// The watchdog keeps counting while the CPU sleeps, but pauses while a
+    /// debugger has halted it, so stepping through the program doesn't reset
+    /// it.
+    ///
+    /// A watchdog that is already running can't be changed, only fed. Not
+    /// every kind of reset stops it, so after one of those the old settings
+    /// stay, and the program had better ask for the same ones.
+    pub fn start<const N: usize>(timeout_ms: u32) -> Option<(Watchdog, [Feeder; N])> {
+        assert!(
+            N >= 1 && N <= MAX_FEEDERS,
+            "the watchdog has 1 to 8 feeders"
+        );
+        if free(|cs| TAKEN.borrow(cs).replace(true)) {
+            return None;
+        }
+        let wdt = unsafe { pac::Peripherals::steal() }.WDT;
+
+        if !wdt.runstatus.read().runstatus().bit_is_set() {
+            let ticks = u64::from(timeout_ms) * TICKS_PER_SECOND / 1_000;
+            let ticks = ticks.clamp(u64::from(MIN_TICKS), u64::from(u32::MAX)) as u32;
+            wdt.crv.write(|w| unsafe { w.bits(ticks) });
+            wdt.rren.write(|w| unsafe { w.bits((1 << N) - 1) });
+            wdt.config.write(|w| w.sleep().run().halt().pause());
+            wdt.tasks_start.write(|w| unsafe { w.bits(1) });
+        }
+
+        let feeders = core::array::from_fn(|index| Feeder { index });
+        Some((Watchdog { _wdt: wdt }, feeders))
+    }
+
+    /// Whether the last reset was the watchdog running out.
+    ///
+    /// The reason is cleared once read, so only the first call after a reset
+    /// can tell.
+    pub fn caused_reset() -> bool {
+        // `Board` doesn't hand out the POWER peripheral. Looking at, and
+        // clearing, this one bit doesn't get in the way of anybody else.
+        let power = unsafe { &*pac::POWER::ptr() };
+        let dog = power.resetreas.read().dog().is_detected();
+        power.resetreas.write(|w| w.dog().detected());
+        dog
+    }
+}
+
+impl Feeder {
+    /// Feed the watchdog. The countdown starts over once all feeders have
+    /// been fed.
+    pub fn feed(&mut self) {
+        let wdt = unsafe { &*WDT::ptr() };
+        wdt.rr[self.index].write(|w| unsafe { w.bits(RELOAD) });
+    }
+
+    /// Whether this feeder has been fed since the countdown last started
+    /// over.
+    pub fn is_fed(&self) -> bool {
+        let wdt = unsafe { &*WDT::ptr() };
+        wdt.reqstatus.read().bits() & (1 << self.index) == 0
+    }
+}
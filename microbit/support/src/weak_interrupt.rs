@@ -0,0 +1,36 @@
+//! Interrupt handlers that a program's own handlers take the place of.
+//!
+//! A handler made with `#[interrupt]` is an ordinary symbol named after its
+//! interrupt, and a program with two of them doesn't link. That happens more
+//! easily than it seems: `cargo build --workspace` builds this crate only
+//! once, with the features of all chapters together, so a chapter that has a
+//! `TIMER1` handler of its own would get the display's next to it.
+//!
+//! So the handlers behind the `*-interrupt` features are *weak* symbols
+//! instead, which the linker only takes when there's no other symbol of the
+//! same name. Rust has no stable way to make one, which is why it's written in
+//! assembly: a weak `$interrupt` that jumps straight to `$handler`. The jump
+//! goes through a register, since the Cortex-M0 of the micro:bit v1 can't
+//! branch far.
+
+macro_rules! weak_interrupt {
+    ($interrupt:ident => $handler:path) => {
+        core::arch::global_asm!(
+            concat!(
+                ".section .text.",
+                stringify!($interrupt),
+                ",\"ax\",%progbits"
+            ),
+            concat!(".weak ", stringify!($interrupt)),
+            concat!(".type ", stringify!($interrupt), ",%function"),
+            ".thumb_func",
+            concat!(stringify!($interrupt), ":"),
+            "ldr r0, ={handler}",
+            "bx r0",
+            ".ltorg",
+            handler = sym $handler,
+        );
+    };
+}
+
+pub(crate) use weak_interrupt;
@@ -0,0 +1,190 @@
+//! Structured messages over a serial port.
+//!
+//! Each message is serialized with `postcard` and sent as the payload of a
+//! packet from `discovery_logic::packet`, which gives it a sequence number
+//! and a CRC. The packet is then COBS encoded. COBS gets rid of every zero
+//! byte in the data, which leaves zero free to mark the end of a packet: if
+//! the receiver ever loses track, it only has to wait for the next zero to be
+//! back in sync.
+
+use discovery_logic::packet::{self, types, DecodeError, Packet};
+use embedded_hal_nb::serial::{Read, Write};
+use heapless::Vec;
+use serde::{de::DeserializeOwned, Serialize};
+
+/// The largest encoded packet `send` can produce, including the COBS overhead
+/// and the terminating zero.
+pub const MAX_PACKET_LEN: usize = 64;
+
+/// How long a serialized message can be. COBS adds a byte to packets this
+/// short, and the zero at the end is another one.
+const MAX_PAYLOAD_LEN: usize = MAX_PACKET_LEN - 2 - packet::HEADER_LEN - packet::CRC_LEN;
+
+/// Why a message could not be sent or received.
+#[derive(Debug)]
+pub enum Error<E> {
+    /// The message doesn't fit into a packet.
+    TooLong,
+    /// The bytes up to the zero are not COBS encoded.
+    Framing,
+    /// The packet got garbled on the way.
+    Packet(DecodeError),
+    /// The packet is intact, but it's not a message.
+    UnexpectedType(u8),
+    /// The packet is intact, but its payload isn't a valid message.
+    Corrupt,
+    /// The serial port reported an error.
+    Serial(E),
+}
+
+impl<E> Error<E> {
+    /// The same error, for a different serial port error type.
+    fn map_serial<F>(self, op: impl FnOnce(E) -> F) -> Error<F> {
+        match self {
+            Error::TooLong => Error::TooLong,
+            Error::Framing => Error::Framing,
+            Error::Packet(error) => Error::Packet(error),
+            Error::UnexpectedType(packet_type) => Error::UnexpectedType(packet_type),
+            Error::Corrupt => Error::Corrupt,
+            Error::Serial(error) => Error::Serial(op(error)),
+        }
+    }
+}
+
+/// Numbers the messages it sends.
+pub struct Sender {
+    sequence: u8,
+}
+
+impl Sender {
+    pub const fn new() -> Sender {
+        Sender { sequence: 0 }
+    }
+
+    /// Encode `message` and write it to `serial`, blocking until every byte
+    /// has been handed over.
+    pub fn send<S, M>(&mut self, serial: &mut S, message: &M) -> Result<(), Error<S::Error>>
+    where
+        S: Write,
+        M: Serialize,
+    {
+        let mut payload = [0; MAX_PAYLOAD_LEN];
+        let payload = postcard::to_slice(message, &mut payload).map_err(|_| Error::TooLong)?;
+        let packet = Packet {
+            packet_type: types::MESSAGE,
+            sequence: self.sequence,
+            payload,
+        };
+        let mut buf = [0; MAX_PAYLOAD_LEN + packet::HEADER_LEN + packet::CRC_LEN];
+        let packet = packet::encode(&packet, &mut buf).map_err(|_| Error::TooLong)?;
+        let mut frame = [0; MAX_PACKET_LEN];
+        let len = cobs::try_encode(packet, &mut frame).map_err(|_| Error::TooLong)?;
+        self.sequence = self.sequence.wrapping_add(1);
+
+        // `frame` is one longer than any encoding, its last byte stays zero
+        for &byte in frame[..=len].iter() {
+            nb::block!(serial.write(byte)).map_err(Error::Serial)?;
+        }
+        nb::block!(serial.flush()).map_err(Error::Serial)
+    }
+}
+
+impl Default for Sender {
+    fn default() -> Self {
+        Sender::new()
+    }
+}
+
+/// Collects incoming bytes until a complete message has arrived.
+///
+/// `N` is the size of the buffer for a single packet.
+pub struct Receiver<const N: usize> {
+    frame: Vec<u8, N>,
+    overflowed: bool,
+    /// The sequence number of the last packet, to notice the ones in between
+    /// that went missing.
+    last_sequence: Option<u8>,
+    lost: u32,
+}
+
+impl<const N: usize> Receiver<N> {
+    pub const fn new() -> Receiver<N> {
+        Receiver {
+            frame: Vec::new(),
+            overflowed: false,
+            last_sequence: None,
+            lost: 0,
+        }
+    }
+
+    /// How many packets never arrived, going by the gaps in the sequence
+    /// numbers.
+    pub fn lost(&self) -> u32 {
+        self.lost
+    }
+
+    /// Hand a received byte to the receiver. Once the byte turns out to be the
+    /// end of a packet, the decoded message is returned.
+    ///
+    /// A packet that isn't a valid message is reported once and then skipped,
+    /// the next packet is received normally.
+    pub fn feed<M: DeserializeOwned>(&mut self, byte: u8) -> Option<Result<M, Error<()>>> {
+        if byte != 0 {
+            if self.frame.push(byte).is_err() {
+                self.overflowed = true;
+            }
+            return None;
+        }
+        // Two zeros in a row are a gap, not an empty packet
+        if self.frame.is_empty() && !self.overflowed {
+            return None;
+        }
+
+        let result = if self.overflowed {
+            Err(Error::TooLong)
+        } else {
+            self.decode()
+        };
+        self.frame.clear();
+        self.overflowed = false;
+        Some(result)
+    }
+
+    fn decode<M: DeserializeOwned>(&mut self) -> Result<M, Error<()>> {
+        let len = cobs::decode_in_place(&mut self.frame).map_err(|_| Error::Framing)?;
+        let packet = packet::decode(&self.frame[..len]).map_err(Error::Packet)?;
+
+        // The same number twice is a packet sent again, not 255 lost ones
+        if let Some(last) = self.last_sequence.filter(|&last| last != packet.sequence) {
+            let gap = packet.sequence.wrapping_sub(last).wrapping_sub(1);
+            self.lost += u32::from(gap);
+        }
+        self.last_sequence = Some(packet.sequence);
+
+        if packet.packet_type != types::MESSAGE {
+            return Err(Error::UnexpectedType(packet.packet_type));
+        }
+        postcard::from_bytes(packet.payload).map_err(|_| Error::Corrupt)
+    }
+
+    /// Read from `serial` until a whole message has arrived.
+    pub fn receive<S, M>(&mut self, serial: &mut S) -> Result<M, Error<S::Error>>
+    where
+        S: Read,
+        M: DeserializeOwned,
+    {
+        loop {
+            let byte = nb::block!(serial.read()).map_err(Error::Serial)?;
+            if let Some(result) = self.feed(byte) {
+                // `feed` never sees the serial port, so can't have its errors
+                return result.map_err(|error| error.map_serial(|()| unreachable!()));
+            }
+        }
+    }
+}
+
+impl<const N: usize> Default for Receiver<N> {
+    fn default() -> Self {
+        Receiver::new()
+    }
+}
@@ -0,0 +1,521 @@
+//! Calibrates the compass from the LED compass chapter on the computer.
+//!
+//! The `host-calibration` example streams the magnetometer's raw readings as
+//! `discovery_messages::Message`s, over `discovery_support::wire`. Turn the micro:bit around in every direction while this collects
+//! them. Ideally they would lie on a sphere around zero. The hard iron offset
+//! shifts that sphere, and the soft iron effects squash it into an
+//! ellipsoid. So an ellipsoid is fitted to the readings, its axes along the
+//! sensor's, and the calibration that turns it back into a sphere around zero
+//! is sent to the micro:bit, which keeps it in its settings.
+//!
+//! ```text
+//! compass-calibrator /dev/ttyACM0
+//! compass-calibrator --samples 1000 --dry-run /dev/ttyACM0
+//! ```
+
+use std::env;
+use std::io::{self, Write};
+use std::process;
+use std::time::{Duration, Instant};
+
+use discovery_logic::packet::{self, types, Packet};
+use discovery_messages::{MagSample, Message};
+use serialport::SerialPort;
+
+const USAGE: &str =
+    "usage: compass-calibrator [--baud <rate>] [--samples <count>] [--dry-run] <port>";
+
+/// How many characters wide and high each view of the point cloud is.
+const PLOT_WIDTH: usize = 31;
+const PLOT_HEIGHT: usize = 15;
+
+struct Options {
+    port: String,
+    baud: u32,
+    samples: usize,
+    dry_run: bool,
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut args = env::args().skip(1);
+    let mut baud = 115_200;
+    let mut samples = 300;
+    let mut dry_run = false;
+    let mut port = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--baud" => {
+                let value = args.next().ok_or("--baud needs a value")?;
+                baud = value
+                    .parse()
+                    .map_err(|_| format!("invalid baud rate: {}", value))?;
+            }
+            "--samples" => {
+                let value = args.next().ok_or("--samples needs a value")?;
+                samples = value
+                    .parse()
+                    .map_err(|_| format!("invalid number of samples: {}", value))?;
+            }
+            "--dry-run" => dry_run = true,
+            "-h" | "--help" => return Err(USAGE.to_string()),
+            _ if port.is_none() => port = Some(arg),
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+
+    Ok(Options {
+        port: port.ok_or(USAGE)?,
+        baud,
+        samples,
+        dry_run,
+    })
+}
+
+/// Sends and receives messages the way `discovery_support::wire` does:
+/// serialized with `postcard`, in a packet from `discovery_logic::packet`,
+/// COBS encoded, and ended with a zero byte.
+struct Link {
+    port: Box<dyn SerialPort>,
+    /// What has arrived of the packet that's coming in.
+    frame: Vec<u8>,
+    pending: Vec<u8>,
+    sequence: u8,
+}
+
+impl Link {
+    fn new(port: Box<dyn SerialPort>) -> Link {
+        Link {
+            port,
+            frame: Vec::new(),
+            pending: Vec::new(),
+            sequence: 0,
+        }
+    }
+
+    /// The next message, or `None` if there was none before `deadline`.
+    fn receive(&mut self, deadline: Instant) -> io::Result<Option<Message>> {
+        loop {
+            while !self.pending.is_empty() {
+                let byte = self.pending.remove(0);
+                if byte != 0 {
+                    self.frame.push(byte);
+                    continue;
+                }
+                if self.frame.is_empty() {
+                    continue;
+                }
+                let message = decode_frame(&self.frame);
+                self.frame.clear();
+                match message {
+                    Ok(message) => return Ok(Some(message)),
+                    Err(e) => eprintln!("skipped a packet: {}", e),
+                }
+            }
+
+            if Instant::now() > deadline {
+                return Ok(None);
+            }
+            let mut buf = [0; 64];
+            match self.port.read(&mut buf) {
+                Ok(n) => self.pending.extend_from_slice(&buf[..n]),
+                Err(e) if e.kind() == io::ErrorKind::TimedOut => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    fn send(&mut self, message: &Message) -> io::Result<()> {
+        let invalid = |e: String| io::Error::new(io::ErrorKind::InvalidInput, e);
+        let mut payload = [0; packet::MAX_PAYLOAD_LEN];
+        let payload =
+            postcard::to_slice(message, &mut payload).map_err(|e| invalid(e.to_string()))?;
+        let packet = Packet {
+            packet_type: types::MESSAGE,
+            sequence: self.sequence,
+            payload,
+        };
+        let mut buf = [0; packet::HEADER_LEN + packet::MAX_PAYLOAD_LEN + packet::CRC_LEN];
+        let packet = packet::encode(&packet, &mut buf).map_err(|e| invalid(format!("{:?}", e)))?;
+        self.sequence = self.sequence.wrapping_add(1);
+
+        let mut frame = cobs::encode_vec(packet);
+        frame.push(0);
+        self.port.write_all(&frame)?;
+        self.port.flush()
+    }
+}
+
+/// Take a packet apart: COBS on the outside, then the packet with its CRC,
+/// then `postcard`.
+fn decode_frame(frame: &[u8]) -> Result<Message, String> {
+    let bytes = cobs::decode_vec(frame).map_err(|_| "not COBS encoded".to_string())?;
+    let packet = packet::decode(&bytes).map_err(|e| format!("{:?}", e))?;
+    if packet.packet_type != types::MESSAGE {
+        return Err(format!("unexpected packet type {}", packet.packet_type));
+    }
+    postcard::from_bytes(packet.payload).map_err(|_| "not a valid message".to_string())
+}
+
+type Point = [f64; 3];
+
+/// An ellipsoid with its axes along x, y and z.
+struct Ellipsoid {
+    center: Point,
+    axes: Point,
+}
+
+/// Fit an ellipsoid to `points`, by least squares.
+///
+/// Such an ellipsoid is every point where `a x² + b y² + c z² + d x + e y +
+/// f z = 1`, which is linear in the six unknowns. The points are moved
+/// around zero and scaled down first, the squares of some 50000 nT would
+/// leave the equations in poor shape otherwise.
+fn fit(points: &[Point]) -> Result<Ellipsoid, String> {
+    let mut mean = [0.0; 3];
+    for point in points {
+        for axis in 0..3 {
+            mean[axis] += point[axis] / points.len() as f64;
+        }
+    }
+    let size = points
+        .iter()
+        .flat_map(|point| (0..3).map(move |axis| (point[axis] - mean[axis]).abs()))
+        .fold(0.0, f64::max);
+    if size == 0.0 {
+        return Err("all the readings are the same".to_string());
+    }
+
+    // The normal equations, Mᵀ M u = Mᵀ 1
+    let mut lhs = [[0.0; 6]; 6];
+    let mut rhs = [0.0; 6];
+    for point in points {
+        let [x, y, z] = [0, 1, 2].map(|axis| (point[axis] - mean[axis]) / size);
+        let row = [x * x, y * y, z * z, x, y, z];
+        for i in 0..6 {
+            for j in 0..6 {
+                lhs[i][j] += row[i] * row[j];
+            }
+            rhs[i] += row[i];
+        }
+    }
+    let u = solve(lhs, rhs).ok_or("the readings don't cover enough directions")?;
+    let [a, b, c, d, e, f] = u;
+    if a <= 0.0 || b <= 0.0 || c <= 0.0 {
+        return Err("the readings don't cover enough directions".to_string());
+    }
+
+    let center = [-d / (2.0 * a), -e / (2.0 * b), -f / (2.0 * c)];
+    let g = 1.0 + d * d / (4.0 * a) + e * e / (4.0 * b) + f * f / (4.0 * c);
+    let axes = [(g / a).sqrt(), (g / b).sqrt(), (g / c).sqrt()];
+    Ok(Ellipsoid {
+        center: [0, 1, 2].map(|axis| mean[axis] + center[axis] * size),
+        axes: axes.map(|axis| axis * size),
+    })
+}
+
+/// Solve `lhs u = rhs` by Gaussian elimination, or `None` if there is no
+/// single solution.
+fn solve(mut lhs: [[f64; 6]; 6], mut rhs: [f64; 6]) -> Option<[f64; 6]> {
+    for column in 0..6 {
+        let pivot =
+            (column..6).max_by(|&i, &j| lhs[i][column].abs().total_cmp(&lhs[j][column].abs()))?;
+        if lhs[pivot][column].abs() < 1e-12 {
+            return None;
+        }
+        lhs.swap(column, pivot);
+        rhs.swap(column, pivot);
+        let pivot_row = lhs[column];
+        for row in column + 1..6 {
+            let factor = lhs[row][column] / pivot_row[column];
+            for (value, pivot) in lhs[row][column..].iter_mut().zip(&pivot_row[column..]) {
+                *value -= factor * pivot;
+            }
+            rhs[row] -= factor * rhs[column];
+        }
+    }
+
+    let mut u = [0.0; 6];
+    for row in (0..6).rev() {
+        let known: f64 = (row + 1..6).map(|k| lhs[row][k] * u[k]).sum();
+        u[row] = (rhs[row] - known) / lhs[row][row];
+    }
+    Some(u)
+}
+
+/// The three views of the point cloud, from the top, the front and the side,
+/// with `+` where the center of the fitted ellipsoid is.
+fn plot(points: &[Point], center: Point) -> String {
+    let range = points
+        .iter()
+        .flat_map(|point| (0..3).map(move |axis| (point[axis] - center[axis]).abs()))
+        .fold(1.0, f64::max);
+
+    let views = [(0, 1, "x/y"), (0, 2, "x/z"), (1, 2, "y/z")];
+    let mut grids = vec![vec![vec![' '; PLOT_WIDTH]; PLOT_HEIGHT]; views.len()];
+    for (grid, &(across, up, _)) in grids.iter_mut().zip(views.iter()) {
+        let cell = |value: f64, center: f64, cells: usize| {
+            let offset = (value - center) / range;
+            (((offset + 1.0) / 2.0 * (cells - 1) as f64).round() as usize).min(cells - 1)
+        };
+        for point in points {
+            let column = cell(point[across], center[across], PLOT_WIDTH);
+            let row = PLOT_HEIGHT - 1 - cell(point[up], center[up], PLOT_HEIGHT);
+            grid[row][column] = '·';
+        }
+        grid[PLOT_HEIGHT / 2][PLOT_WIDTH / 2] = '+';
+    }
+
+    let mut out = String::new();
+    for &(_, _, name) in views.iter() {
+        out += &format!("{:<width$}   ", name, width = PLOT_WIDTH + 2);
+    }
+    out += "\n";
+    for row in 0..PLOT_HEIGHT {
+        for grid in grids.iter() {
+            out += &format!("|{}|   ", grid[row].iter().collect::<String>());
+        }
+        out += "\n";
+    }
+    out
+}
+
+fn run(options: Options) -> Result<(), String> {
+    let Options {
+        port: name,
+        baud,
+        samples,
+        dry_run,
+    } = options;
+
+    let port = serialport::new(&name, baud)
+        .timeout(Duration::from_millis(100))
+        .open()
+        .map_err(|e| format!("could not open {}: {}", name, e))?;
+    let mut link = Link::new(port);
+    let failed = |e: io::Error| format!("talking to {} failed: {}", name, e);
+
+    eprintln!("turn the micro:bit around in every direction");
+    let mut points = Vec::new();
+    while points.len() < samples {
+        let deadline = Instant::now() + Duration::from_secs(2);
+        match link.receive(deadline).map_err(failed)? {
+            Some(Message::MagneticField(MagSample { x, y, z })) => {
+                points.push([f64::from(x), f64::from(y), f64::from(z)]);
+                eprint!("\r{}/{} readings", points.len(), samples);
+            }
+            Some(_) => {}
+            None => return Err("no readings, is host-calibration running?".to_string()),
+        }
+    }
+    eprintln!();
+
+    let ellipsoid = fit(&points)?;
+    println!("{}", plot(&points, ellipsoid.center));
+
+    // What `Calibration` makes of the ellipsoid: it subtracts the center and
+    // multiplies every axis by its scale, so every axis has to be scaled to
+    // the same radius. The geometric mean of the axes keeps the volume.
+    let radius = ellipsoid.axes.iter().product::<f64>().cbrt();
+    let center = ellipsoid.center.map(|axis| axis.round() as i32);
+    let scale = ellipsoid
+        .axes
+        .map(|axis| (1024.0 * radius / axis).round() as i32);
+    let radius = radius.round() as u32;
+    println!("hard iron offset: {:?}", center);
+    println!("scale, in 1024ths: {:?}", scale);
+    println!("radius: {}", radius);
+
+    // How far off the readings still are once calibrated
+    let distances: Vec<f64> = points
+        .iter()
+        .map(|point| {
+            (0..3)
+                .map(|axis| {
+                    (point[axis] - f64::from(center[axis])) * f64::from(scale[axis]) / 1024.0
+                })
+                .map(|value| value * value)
+                .sum::<f64>()
+                .sqrt()
+        })
+        .collect();
+    let min = distances.iter().cloned().fold(f64::INFINITY, f64::min);
+    let max = distances.iter().cloned().fold(0.0, f64::max);
+    println!(
+        "calibrated readings lie between {:.0} and {:.0}, {:.1}% around the radius",
+        min,
+        max,
+        (max - min) / 2.0 / f64::from(radius) * 100.0
+    );
+
+    if dry_run {
+        return Ok(());
+    }
+    link.send(&Message::Calibration {
+        center,
+        scale,
+        radius,
+    })
+    .map_err(failed)?;
+    // The readings keep coming in, the answer is somewhere among them
+    let deadline = Instant::now() + Duration::from_secs(2);
+    loop {
+        match link.receive(deadline).map_err(failed)? {
+            Some(Message::CalibrationSaved) => break,
+            Some(_) => {}
+            None => return Err("the micro:bit didn't confirm the calibration".to_string()),
+        }
+    }
+    println!("saved on the micro:bit");
+    io::stdout().flush().map_err(|e| e.to_string())
+}
+
+fn main() {
+    if let Err(message) = parse_args().and_then(run) {
+        eprintln!("{}", message);
+        process::exit(2);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    const CENTER: Point = [1200.0, -3400.0, 2500.0];
+    const AXES: Point = [48000.0, 52000.0, 45000.0];
+
+    /// Points all over the ellipsoid with [`CENTER`] and [`AXES`].
+    fn ellipsoid_points() -> Vec<Point> {
+        let mut points = Vec::new();
+        for i in 1..10 {
+            let polar = PI * i as f64 / 10.0;
+            for j in 0..20 {
+                let azimuth = 2.0 * PI * j as f64 / 20.0;
+                let unit = [
+                    polar.sin() * azimuth.cos(),
+                    polar.sin() * azimuth.sin(),
+                    polar.cos(),
+                ];
+                points.push([0, 1, 2].map(|axis| CENTER[axis] + AXES[axis] * unit[axis]));
+            }
+        }
+        points
+    }
+
+    fn assert_close(actual: Point, expected: Point) {
+        for axis in 0..3 {
+            assert!(
+                (actual[axis] - expected[axis]).abs() < 1e-3,
+                "{:?} isn't {:?}",
+                actual,
+                expected
+            );
+        }
+    }
+
+    /// The frame `Link::send` would send for `message`.
+    fn frame(packet_type: u8, message: &Message) -> Vec<u8> {
+        let mut payload = [0; packet::MAX_PAYLOAD_LEN];
+        let payload = postcard::to_slice(message, &mut payload).unwrap();
+        let packet = Packet {
+            packet_type,
+            sequence: 0,
+            payload,
+        };
+        let mut buf = [0; packet::HEADER_LEN + packet::MAX_PAYLOAD_LEN + packet::CRC_LEN];
+        cobs::encode_vec(packet::encode(&packet, &mut buf).unwrap())
+    }
+
+    #[test]
+    fn fit_finds_the_center_and_the_axes() {
+        let ellipsoid = fit(&ellipsoid_points()).unwrap();
+        assert_close(ellipsoid.center, CENTER);
+        assert_close(ellipsoid.axes, AXES);
+    }
+
+    #[test]
+    fn fit_doesnt_mind_where_the_points_start() {
+        let mut points = ellipsoid_points();
+        points.rotate_left(37);
+        let ellipsoid = fit(&points).unwrap();
+        assert_close(ellipsoid.center, CENTER);
+    }
+
+    #[test]
+    fn fit_needs_readings_that_differ() {
+        assert!(fit(&[CENTER; 50]).is_err());
+    }
+
+    #[test]
+    fn fit_needs_readings_in_every_direction() {
+        // Turned around the z axis only
+        let flat: Vec<Point> = ellipsoid_points()
+            .into_iter()
+            .map(|[x, y, _]| [x, y, CENTER[2]])
+            .collect();
+        assert!(fit(&flat).is_err());
+    }
+
+    #[test]
+    fn solve_finds_the_solution() {
+        let mut lhs = [[0.0; 6]; 6];
+        for (i, row) in lhs.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = if i == j {
+                    4.0
+                } else {
+                    1.0 / (1 + i + j) as f64
+                };
+            }
+        }
+        let expected = [1.0, -2.0, 3.0, -4.0, 5.0, -6.0];
+        let mut rhs = [0.0; 6];
+        for i in 0..6 {
+            rhs[i] = (0..6).map(|j| lhs[i][j] * expected[j]).sum();
+        }
+
+        let u = solve(lhs, rhs).unwrap();
+        for i in 0..6 {
+            assert!((u[i] - expected[i]).abs() < 1e-9, "{:?}", u);
+        }
+    }
+
+    #[test]
+    fn solve_swaps_rows_for_a_zero_pivot() {
+        let mut lhs = [[0.0; 6]; 6];
+        for i in 0..6 {
+            // Every row has its one in the next column
+            lhs[i][(i + 1) % 6] = 1.0;
+        }
+        let rhs = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+        assert_eq!(solve(lhs, rhs), Some([6.0, 1.0, 2.0, 3.0, 4.0, 5.0]));
+    }
+
+    #[test]
+    fn solve_gives_up_on_a_singular_matrix() {
+        let mut lhs = [[0.0; 6]; 6];
+        for (i, row) in lhs.iter_mut().enumerate() {
+            row[i] = 1.0;
+        }
+        // The last row is the sum of the first two
+        lhs[5] = [1.0, 1.0, 0.0, 0.0, 0.0, 0.0];
+        assert_eq!(solve(lhs, [1.0; 6]), None);
+    }
+
+    #[test]
+    fn decodes_what_the_micro_bit_sends() {
+        let sample = Message::MagneticField(MagSample { x: 1, y: -2, z: 3 });
+        let decoded = decode_frame(&frame(types::MESSAGE, &sample)).unwrap();
+        assert_eq!(decoded, sample);
+    }
+
+    #[test]
+    fn rejects_other_packets() {
+        let sample = Message::MagneticField(MagSample { x: 1, y: -2, z: 3 });
+        assert!(decode_frame(&frame(types::DFU, &sample)).is_err());
+        let mut garbled = frame(types::MESSAGE, &sample);
+        garbled[3] ^= 0x10;
+        assert!(decode_frame(&garbled).is_err());
+    }
+}
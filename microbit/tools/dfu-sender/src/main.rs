@@ -0,0 +1,287 @@
+//! Sends a new program to the serial bootloader, the one from the chapter of
+//! that name.
+//!
+//! The program has to be built with the `dfu` feature, which links it to
+//! start behind the bootloader, and turned into a plain binary, with nothing
+//! in it but the bytes that go into the flash:
+//!
+//! ```text
+//! cargo objcopy --release --features v2,dfu --target thumbv7em-none-eabihf -- -O binary roulette.bin
+//! dfu-sender /dev/ttyACM0 roulette.bin
+//! ```
+//!
+//! It speaks `discovery_logic::dfu`, in packets from `discovery_logic::packet`,
+//! COBS encoded and ended with a zero byte. Every request waits for its
+//! answer, and is sent again if none comes. The exit code is non-zero if the
+//! update didn't go through.
+
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::process;
+use std::time::{Duration, Instant};
+
+use discovery_logic::dfu::{crc32, Request, Response, CHUNK_LEN};
+use discovery_logic::packet::{self, types, Packet};
+use serialport::SerialPort;
+
+const USAGE: &str = "usage: dfu-sender [--baud <rate>] [--timeout <ms>] <port> <program.bin>";
+
+/// Where the bootloader puts the program, see
+/// `src/29-serial-bootloader/src/main.rs`.
+const PROGRAM_START: u32 = 32 * 1024;
+/// How often a request is sent before giving up.
+const ATTEMPTS: usize = 5;
+
+struct Options {
+    port: String,
+    baud: u32,
+    timeout: Duration,
+    program: String,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut baud = 115_200;
+    // Erasing a page takes the bootloader up to 85ms, on top of the bytes
+    let mut timeout = Duration::from_secs(1);
+    let mut port = None;
+    let mut program = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--baud" => {
+                let value = args.next().ok_or("--baud needs a value")?;
+                baud = value
+                    .parse()
+                    .map_err(|_| format!("invalid baud rate: {}", value))?;
+            }
+            "--timeout" => {
+                let value = args.next().ok_or("--timeout needs a value")?;
+                let ms = value
+                    .parse()
+                    .map_err(|_| format!("invalid timeout: {}", value))?;
+                timeout = Duration::from_millis(ms);
+            }
+            "-h" | "--help" => return Err(USAGE.to_string()),
+            _ if port.is_none() => port = Some(arg),
+            _ if program.is_none() => program = Some(arg),
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+
+    Ok(Options {
+        port: port.ok_or(USAGE)?,
+        baud,
+        timeout,
+        program: program.ok_or(USAGE)?,
+    })
+}
+
+/// Check that `image` looks like a program for behind the bootloader, and
+/// say what's wrong with it if it doesn't.
+fn check_image(image: &[u8]) -> Result<(), String> {
+    if image.starts_with(b"\x7fELF") {
+        return Err(
+            "that's an ELF file, turn it into a binary with `cargo objcopy ... -- -O binary` first"
+                .to_string(),
+        );
+    }
+    if image.len() < 8 {
+        return Err("that's too short to be a program".to_string());
+    }
+    // The second word of the vector table is the reset vector, which points
+    // into the program itself if it's been linked for behind the bootloader
+    let reset = u32::from_le_bytes([image[4], image[5], image[6], image[7]]);
+    let end = PROGRAM_START + image.len() as u32;
+    if !(PROGRAM_START..end).contains(&reset) {
+        return Err(format!(
+            "its reset vector is {:#x}, not in {:#x}..{:#x}, was it built with the `dfu` feature?",
+            reset, PROGRAM_START, end
+        ));
+    }
+    Ok(())
+}
+
+/// Sends requests and waits for their responses, the same way
+/// `discovery_support::wire` frames its messages.
+struct Link {
+    port: Box<dyn SerialPort>,
+    timeout: Duration,
+    sequence: u8,
+}
+
+impl Link {
+    /// Send `request` until the bootloader answers it.
+    fn request(&mut self, request: &Request) -> Result<Response, String> {
+        self.sequence = self.sequence.wrapping_add(1);
+        let payload = request.encode();
+        let packet = Packet {
+            packet_type: types::DFU,
+            sequence: self.sequence,
+            payload: &payload,
+        };
+        let mut buf = [0; packet::HEADER_LEN + packet::MAX_PAYLOAD_LEN + packet::CRC_LEN];
+        let packet = packet::encode(&packet, &mut buf).map_err(|e| format!("{:?}", e))?;
+        let mut frame = cobs::encode_vec(packet);
+        frame.push(0);
+
+        for _ in 0..ATTEMPTS {
+            // A zero first ends whatever the bootloader has of a frame that
+            // broke off
+            self.port
+                .write_all(&[0])
+                .and_then(|_| self.port.write_all(&frame))
+                .and_then(|_| self.port.flush())
+                .map_err(|e| format!("writing failed: {}", e))?;
+            if let Some(response) = self.response()? {
+                return Ok(response);
+            }
+        }
+        Err(format!("no answer after {} attempts", ATTEMPTS))
+    }
+
+    /// The response to the last request, or `None` if it doesn't come in
+    /// time. Anything else that arrives is left out.
+    fn response(&mut self) -> Result<Option<Response>, String> {
+        let deadline = Instant::now() + self.timeout;
+        let mut frame = Vec::new();
+        let mut byte = [0];
+        while Instant::now() < deadline {
+            match self.port.read(&mut byte) {
+                Ok(1) if byte[0] != 0 => frame.push(byte[0]),
+                Ok(1) => {
+                    if let Some(response) = self.decode(&frame) {
+                        return Ok(Some(response));
+                    }
+                    frame.clear();
+                }
+                Ok(_) => {}
+                Err(e) if e.kind() == io::ErrorKind::TimedOut => {}
+                Err(e) => return Err(format!("reading failed: {}", e)),
+            }
+        }
+        Ok(None)
+    }
+
+    fn decode(&self, frame: &[u8]) -> Option<Response> {
+        let bytes = cobs::decode_vec(frame).ok()?;
+        let packet = packet::decode(&bytes).ok()?;
+        if packet.packet_type != types::DFU || packet.sequence != self.sequence {
+            return None;
+        }
+        Response::decode(packet.payload)
+    }
+}
+
+fn run(options: Options) -> Result<(), String> {
+    let image = fs::read(&options.program)
+        .map_err(|e| format!("could not read {}: {}", options.program, e))?;
+    check_image(&image).map_err(|e| format!("{}: {}", options.program, e))?;
+
+    let port = serialport::new(&options.port, options.baud)
+        .timeout(Duration::from_millis(100))
+        .open()
+        .map_err(|e| format!("could not open {}: {}", options.port, e))?;
+    let mut link = Link {
+        port,
+        timeout: options.timeout,
+        sequence: 0,
+    };
+
+    let len = image.len() as u32;
+    let start = Request::Start {
+        len,
+        crc: crc32(&image),
+    };
+    let mut response = link.request(&start)?;
+    loop {
+        match response {
+            Response::Next(offset) if offset == len => {
+                println!("\rsent {} bytes, checking", len);
+                response = link.request(&Request::Finish)?;
+            }
+            Response::Next(offset) if offset < len => {
+                let end = (offset as usize + CHUNK_LEN).min(image.len());
+                print!("\rsent {} of {} bytes", offset, len);
+                io::stdout().flush().unwrap();
+                let chunk = Request::Chunk {
+                    offset,
+                    data: &image[offset as usize..end],
+                };
+                response = link.request(&chunk)?;
+            }
+            Response::Next(offset) => {
+                return Err(format!("the bootloader asked for offset {}", offset))
+            }
+            Response::Done => {
+                println!("done, the micro:bit starts the new program");
+                return Ok(());
+            }
+            Response::Failed(failure) => return Err(format!("the update failed: {:?}", failure)),
+        }
+    }
+}
+
+fn main() {
+    if let Err(message) = parse_args(env::args().skip(1)).and_then(run) {
+        eprintln!("{}", message);
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> impl Iterator<Item = String> + '_ {
+        line.split_whitespace().map(String::from)
+    }
+
+    /// The start of a program with its reset vector at `reset`, padded to
+    /// `len` bytes.
+    fn image(reset: u32, len: usize) -> Vec<u8> {
+        let mut image = vec![0; len];
+        image[..4].copy_from_slice(&0x2002_0000u32.to_le_bytes());
+        image[4..8].copy_from_slice(&reset.to_le_bytes());
+        image
+    }
+
+    #[test]
+    fn takes_the_port_and_the_program() {
+        let options = parse_args(args("--timeout 200 /dev/ttyACM0 roulette.bin")).unwrap();
+        assert_eq!(options.port, "/dev/ttyACM0");
+        assert_eq!(options.program, "roulette.bin");
+        assert_eq!(options.baud, 115_200);
+        assert_eq!(options.timeout, Duration::from_millis(200));
+    }
+
+    #[test]
+    fn rejects_bad_arguments() {
+        assert!(parse_args(args("/dev/ttyACM0")).is_err());
+        assert!(parse_args(args("/dev/ttyACM0 a.bin b.bin")).is_err());
+        assert!(parse_args(args("--baud /dev/ttyACM0 a.bin")).is_err());
+    }
+
+    #[test]
+    fn takes_a_program_linked_behind_the_bootloader() {
+        assert_eq!(check_image(&image(PROGRAM_START + 0x101, 1024)), Ok(()));
+    }
+
+    #[test]
+    fn rejects_a_program_linked_for_the_start_of_the_flash() {
+        let error = check_image(&image(0x101, 1024)).unwrap_err();
+        assert!(error.contains("`dfu` feature"), "{}", error);
+    }
+
+    #[test]
+    fn rejects_a_reset_vector_past_the_end_of_the_program() {
+        assert!(check_image(&image(PROGRAM_START + 2048, 1024)).is_err());
+    }
+
+    #[test]
+    fn rejects_elf_files_and_short_ones() {
+        let error = check_image(b"\x7fELF\x01\x01\x01\0").unwrap_err();
+        assert!(error.contains("objcopy"), "{}", error);
+        assert!(check_image(&[0; 4]).is_err());
+    }
+}
@@ -0,0 +1,275 @@
+//! Checks the "reverse a string" firmware from the UART chapter.
+//!
+//! Every line given on the command line, or read from stdin if there are
+//! none, is sent to the micro:bit followed by a carriage return. The reply has
+//! to be the same line reversed. The exit code is non-zero if any reply is
+//! wrong or doesn't arrive in time, so this can be used from scripts and CI.
+//!
+//! ```text
+//! serial-client /dev/ttyACM0 hello world
+//! serial-client --baud 9600 /dev/ttyACM0 < lines.txt
+//! ```
+//!
+//! With `--bench`, it's the other end of the UART chapter's `bench` example
+//! instead: both send each other a test pattern as fast as they can, for the
+//! given number of seconds, and check what arrives. This side prints what it
+//! measured once a second, the micro:bit does the same over RTT. The exit
+//! code is non-zero if anything arrived wrong.
+//!
+//! ```text
+//! serial-client --bench 10 /dev/ttyACM0
+//! ```
+
+use discovery_logic::prbs::{Checker, Prbs};
+use std::env;
+use std::io::{self, BufRead, Write};
+use std::process;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const USAGE: &str =
+    "usage: serial-client [--baud <rate>] [--timeout <ms>] <port> [line...]\n       \
+                     serial-client [--baud <rate>] --bench <seconds> <port>";
+
+struct Options {
+    port: String,
+    baud: u32,
+    timeout: Duration,
+    /// How long to benchmark for, instead of sending lines.
+    bench: Option<Duration>,
+    lines: Vec<String>,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut baud = 115_200;
+    let mut timeout = Duration::from_secs(2);
+    let mut bench = None;
+    let mut port = None;
+    let mut lines = Vec::new();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--baud" => {
+                let value = args.next().ok_or("--baud needs a value")?;
+                baud = value
+                    .parse()
+                    .map_err(|_| format!("invalid baud rate: {}", value))?;
+            }
+            "--timeout" => {
+                let value = args.next().ok_or("--timeout needs a value")?;
+                let ms = value
+                    .parse()
+                    .map_err(|_| format!("invalid timeout: {}", value))?;
+                timeout = Duration::from_millis(ms);
+            }
+            "--bench" => {
+                let value = args.next().ok_or("--bench needs a value")?;
+                let seconds = value
+                    .parse()
+                    .map_err(|_| format!("invalid number of seconds: {}", value))?;
+                bench = Some(Duration::from_secs(seconds));
+            }
+            "-h" | "--help" => return Err(USAGE.to_string()),
+            _ if port.is_none() => port = Some(arg),
+            _ => lines.push(arg),
+        }
+    }
+
+    Ok(Options {
+        port: port.ok_or(USAGE)?,
+        baud,
+        timeout,
+        bench,
+        lines,
+    })
+}
+
+/// Read until the firmware has finished its reply, which ends with `"\n\r"`.
+fn read_reply(port: &mut (impl io::Read + ?Sized), timeout: Duration) -> io::Result<String> {
+    let deadline = Instant::now() + timeout;
+    let mut reply = Vec::new();
+    let mut byte = [0];
+
+    while !reply.ends_with(b"\n\r") {
+        if Instant::now() > deadline {
+            return Err(io::Error::new(io::ErrorKind::TimedOut, "no complete reply"));
+        }
+        match port.read(&mut byte) {
+            Ok(1) => reply.push(byte[0]),
+            Ok(_) => {}
+            Err(e) if e.kind() == io::ErrorKind::TimedOut => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    // The firmware sends the carriage return we typed back first, since it
+    // is the last byte of the line
+    let reply = String::from_utf8_lossy(&reply);
+    Ok(reply.trim_matches(|c| c == '\r' || c == '\n').to_string())
+}
+
+/// Send the test pattern for `duration`, and check the one that comes back.
+fn bench(
+    mut port: Box<dyn serialport::SerialPort>,
+    name: &str,
+    duration: Duration,
+) -> Result<bool, String> {
+    let mut writer = port
+        .try_clone()
+        .map_err(|e| format!("could not clone {}: {}", name, e))?;
+    let end = Instant::now() + duration;
+    // Sending blocks for as long as the bytes take, so it gets a thread of
+    // its own, and reading never falls behind because of it
+    let sender = thread::spawn(move || {
+        let mut prbs = Prbs::new(0xbe7c);
+        let mut chunk = [0; 64];
+        let mut sent = 0u64;
+        while Instant::now() < end {
+            prbs.fill(&mut chunk);
+            writer.write_all(&chunk)?;
+            sent += chunk.len() as u64;
+        }
+        Ok::<_, io::Error>(sent)
+    });
+
+    let mut checker = Checker::new();
+    let mut buffer = [0; 256];
+    let (mut received, mut wrong) = (0, 0);
+    let mut report = Instant::now() + Duration::from_secs(1);
+    while Instant::now() < end {
+        match port.read(&mut buffer) {
+            Ok(count) => {
+                for &byte in &buffer[..count] {
+                    checker.check(byte);
+                }
+                received += count;
+            }
+            Err(e) if e.kind() == io::ErrorKind::TimedOut => {}
+            Err(e) => return Err(format!("reading from {} failed: {}", name, e)),
+        }
+        if Instant::now() >= report {
+            println!("rx {} B/s, {} wrong", received, checker.errors() - wrong);
+            received = 0;
+            wrong = checker.errors();
+            report += Duration::from_secs(1);
+        }
+    }
+
+    let sent = sender
+        .join()
+        .unwrap()
+        .map_err(|e| format!("writing to {} failed: {}", name, e))?;
+    println!(
+        "sent {} bytes, received {}, {} of them wrong",
+        sent,
+        checker.received(),
+        checker.errors()
+    );
+    Ok(checker.errors() == 0)
+}
+
+fn run(options: Options) -> Result<bool, String> {
+    let Options {
+        port: name,
+        baud,
+        timeout,
+        bench: bench_duration,
+        lines,
+    } = options;
+
+    let mut port = serialport::new(&name, baud)
+        .timeout(Duration::from_millis(100))
+        .open()
+        .map_err(|e| format!("could not open {}: {}", name, e))?;
+
+    if let Some(duration) = bench_duration {
+        return bench(port, &name, duration);
+    }
+
+    let lines: Box<dyn Iterator<Item = String>> = if lines.is_empty() {
+        Box::new(io::stdin().lock().lines().map_while(Result::ok))
+    } else {
+        Box::new(lines.into_iter())
+    };
+
+    let mut all_ok = true;
+    for line in lines {
+        port.write_all(line.as_bytes())
+            .and_then(|_| port.write_all(b"\r"))
+            .and_then(|_| port.flush())
+            .map_err(|e| format!("writing to {} failed: {}", name, e))?;
+
+        let expected: String = line.chars().rev().collect();
+        match read_reply(&mut *port, timeout) {
+            Ok(reply) if reply == expected => println!("ok   {:?}", line),
+            Ok(reply) => {
+                println!("FAIL {:?}: expected {:?}, got {:?}", line, expected, reply);
+                all_ok = false;
+            }
+            Err(e) => {
+                println!("FAIL {:?}: {}", line, e);
+                all_ok = false;
+            }
+        }
+    }
+    Ok(all_ok)
+}
+
+fn main() {
+    let result = parse_args(env::args().skip(1)).and_then(run);
+    match result {
+        Ok(true) => {}
+        Ok(false) => process::exit(1),
+        Err(message) => {
+            eprintln!("{}", message);
+            process::exit(2);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> impl Iterator<Item = String> + '_ {
+        line.split_whitespace().map(String::from)
+    }
+
+    #[test]
+    fn the_rest_of_the_arguments_are_lines() {
+        let options = parse_args(args("--baud 9600 /dev/ttyACM0 hello world")).unwrap();
+        assert_eq!(options.port, "/dev/ttyACM0");
+        assert_eq!(options.baud, 9600);
+        assert_eq!(options.lines, ["hello", "world"]);
+        assert_eq!(options.bench, None);
+    }
+
+    #[test]
+    fn bench_takes_seconds() {
+        let options = parse_args(args("--bench 10 --timeout 500 /dev/ttyACM0")).unwrap();
+        assert_eq!(options.bench, Some(Duration::from_secs(10)));
+        assert_eq!(options.timeout, Duration::from_millis(500));
+    }
+
+    #[test]
+    fn rejects_bad_arguments() {
+        assert!(parse_args(args("")).is_err());
+        assert!(parse_args(args("--baud fast /dev/ttyACM0")).is_err());
+        assert!(parse_args(args("/dev/ttyACM0 --bench")).is_err());
+    }
+
+    #[test]
+    fn the_reply_leaves_out_the_line_endings() {
+        // The carriage return typed at the end of the line comes back first
+        let mut port: &[u8] = b"\rolleh\n\r";
+        let reply = read_reply(&mut port, Duration::from_secs(1)).unwrap();
+        assert_eq!(reply, "olleh");
+    }
+
+    #[test]
+    fn a_reply_that_doesnt_end_times_out() {
+        let mut port: &[u8] = b"\rolle";
+        let error = read_reply(&mut port, Duration::from_millis(10)).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::TimedOut);
+    }
+}
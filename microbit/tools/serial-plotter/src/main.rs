@@ -0,0 +1,378 @@
+//! Plots the sensor readings a micro:bit streams over its serial port.
+//!
+//! The micro:bit sends `discovery_messages::Message`s the way
+//! `discovery_support::wire` does: serialized with `postcard`, in a packet
+//! from `discovery_logic::packet`, COBS encoded, and ended with a zero byte.
+//! Every reading becomes a line with its values and a strip chart, with `x`,
+//! `y` and `z` marking where each axis is, or a line of CSV to be plotted with
+//! a spreadsheet or anything else.
+//!
+//! ```text
+//! serial-plotter /dev/ttyACM0
+//! serial-plotter --only acceleration /dev/ttyACM0
+//! serial-plotter --csv /dev/ttyACM0 > readings.csv
+//! ```
+
+use std::env;
+use std::io::{self, Write};
+use std::process;
+use std::time::{Duration, Instant};
+
+use discovery_logic::packet::{self, types};
+use discovery_messages::{AccelSample, Button, ButtonEvent, CompassHeading, MagSample, Message};
+
+const USAGE: &str = "usage: serial-plotter [--baud <rate>] [--csv] [--only <channel>] <port>\n\
+     channels: acceleration, magnetic-field, temperature, heading, button-a, button-b";
+
+/// How many characters wide the strip chart is.
+const WIDTH: usize = 61;
+
+/// Each kind of reading, plotted on a scale of its own.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Channel {
+    Acceleration,
+    MagneticField,
+    Temperature,
+    Heading,
+    ButtonA,
+    ButtonB,
+}
+
+impl Channel {
+    const ALL: [Channel; 6] = [
+        Channel::Acceleration,
+        Channel::MagneticField,
+        Channel::Temperature,
+        Channel::Heading,
+        Channel::ButtonA,
+        Channel::ButtonB,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Channel::Acceleration => "acceleration",
+            Channel::MagneticField => "magnetic-field",
+            Channel::Temperature => "temperature",
+            Channel::Heading => "heading",
+            Channel::ButtonA => "button-a",
+            Channel::ButtonB => "button-b",
+        }
+    }
+}
+
+/// A reading, with the values of its axes. The temperature and the heading
+/// have just one, and a button is 1 while it's held down and 0 once it's let
+/// go of.
+fn reading(message: &Message) -> Option<(Channel, Vec<i32>)> {
+    match *message {
+        Message::Acceleration(AccelSample { x, y, z }) => {
+            Some((Channel::Acceleration, vec![x, y, z]))
+        }
+        Message::MagneticField(MagSample { x, y, z }) => {
+            Some((Channel::MagneticField, vec![x, y, z]))
+        }
+        Message::Temperature(t) => Some((Channel::Temperature, vec![i32::from(t)])),
+        Message::Heading(CompassHeading { degrees }) => {
+            Some((Channel::Heading, vec![degrees.round() as i32]))
+        }
+        Message::Button(ButtonEvent { button, pressed }) => {
+            let channel = match button {
+                Button::A => Channel::ButtonA,
+                Button::B => Channel::ButtonB,
+            };
+            Some((channel, vec![i32::from(pressed)]))
+        }
+        // Not readings
+        _ => None,
+    }
+}
+
+struct Options {
+    port: String,
+    baud: u32,
+    csv: bool,
+    only: Option<Channel>,
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut args = env::args().skip(1);
+    let mut baud = 115_200;
+    let mut csv = false;
+    let mut only = None;
+    let mut port = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--baud" => {
+                let value = args.next().ok_or("--baud needs a value")?;
+                baud = value
+                    .parse()
+                    .map_err(|_| format!("invalid baud rate: {}", value))?;
+            }
+            "--csv" => csv = true,
+            "--only" => {
+                let value = args.next().ok_or("--only needs a value")?;
+                let channel = Channel::ALL
+                    .iter()
+                    .find(|channel| channel.name() == value)
+                    .ok_or_else(|| format!("unknown channel: {}\n{}", value, USAGE))?;
+                only = Some(*channel);
+            }
+            "-h" | "--help" => return Err(USAGE.to_string()),
+            _ if port.is_none() => port = Some(arg),
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+
+    Ok(Options {
+        port: port.ok_or(USAGE)?,
+        baud,
+        csv,
+        only,
+    })
+}
+
+/// Draws readings as strip charts, one line each.
+///
+/// Every channel scales to the largest value it has had so far, so the chart
+/// starts out jumpy and settles down once the readings have covered their
+/// range.
+struct Plot {
+    range: [i32; Channel::ALL.len()],
+}
+
+impl Plot {
+    fn new() -> Plot {
+        Plot {
+            range: [1; Channel::ALL.len()],
+        }
+    }
+
+    fn line(&mut self, channel: Channel, values: &[i32]) -> String {
+        let range = &mut self.range[channel as usize];
+        for value in values {
+            *range = (*range).max(value.saturating_abs());
+        }
+        let range = *range;
+
+        let mut chart = vec![' '; WIDTH];
+        chart[WIDTH / 2] = '|';
+        for (value, mark) in values.iter().zip(['x', 'y', 'z']) {
+            // -range is the left edge, +range the right one
+            let offset = (i64::from(*value) + i64::from(range)) * (WIDTH as i64 - 1)
+                / (2 * i64::from(range));
+            chart[offset.clamp(0, WIDTH as i64 - 1) as usize] =
+                if values.len() == 1 { '*' } else { mark };
+        }
+
+        let numbers: Vec<String> = values.iter().map(|value| format!("{:>7}", value)).collect();
+        format!(
+            "{:<14} {:<23} [{}] ±{}",
+            channel.name(),
+            numbers.join(" "),
+            chart.into_iter().collect::<String>(),
+            range
+        )
+    }
+}
+
+/// A reading as `seconds,channel,x,y,z`. The channels with a single value
+/// leave `y` and `z` empty.
+fn csv_line(elapsed: Duration, channel: Channel, values: &[i32]) -> String {
+    let mut values: Vec<String> = values.iter().map(i32::to_string).collect();
+    values.resize(3, String::new());
+    format!(
+        "{:.3},{},{}",
+        elapsed.as_secs_f64(),
+        channel.name(),
+        values.join(",")
+    )
+}
+
+/// Take a packet apart the way `discovery_support::wire` put it together:
+/// COBS on the outside, then the packet with its CRC, then `postcard`.
+fn decode_frame(frame: &[u8]) -> Result<Message, String> {
+    let bytes = cobs::decode_vec(frame).map_err(|_| "not COBS encoded".to_string())?;
+    let packet = packet::decode(&bytes).map_err(|e| format!("{:?}", e))?;
+    if packet.packet_type != types::MESSAGE {
+        return Err(format!("unexpected packet type {}", packet.packet_type));
+    }
+    postcard::from_bytes(packet.payload).map_err(|_| "not a valid message".to_string())
+}
+
+fn run(options: Options) -> Result<(), String> {
+    let Options {
+        port: name,
+        baud,
+        csv,
+        only,
+    } = options;
+
+    let mut port = serialport::new(&name, baud)
+        .timeout(Duration::from_millis(100))
+        .open()
+        .map_err(|e| format!("could not open {}: {}", name, e))?;
+
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    if csv {
+        writeln!(out, "seconds,channel,x,y,z").map_err(|e| e.to_string())?;
+    }
+
+    let start = Instant::now();
+    let mut plot = Plot::new();
+    let mut frame = Vec::new();
+    let mut buf = [0; 64];
+
+    loop {
+        let n = match port.read(&mut buf) {
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::TimedOut => continue,
+            Err(e) => return Err(format!("reading from {} failed: {}", name, e)),
+        };
+
+        // A read can end in the middle of a packet, or hold several of them
+        for &byte in &buf[..n] {
+            if byte != 0 {
+                frame.push(byte);
+                continue;
+            }
+            if frame.is_empty() {
+                continue;
+            }
+            let message = decode_frame(&frame);
+            frame.clear();
+
+            let message = match message {
+                Ok(message) => message,
+                Err(e) => {
+                    eprintln!("skipped a packet: {}", e);
+                    continue;
+                }
+            };
+            let selected =
+                reading(&message).filter(|(channel, _)| only.is_none_or(|only| only == *channel));
+            if let Some((channel, values)) = selected {
+                let line = if csv {
+                    csv_line(start.elapsed(), channel, &values)
+                } else {
+                    plot.line(channel, &values)
+                };
+                // Flushed right away, so that the readings show up as they
+                // come in, even through a pipe
+                writeln!(out, "{}", line)
+                    .and_then(|_| out.flush())
+                    .map_err(|e| e.to_string())?;
+            }
+        }
+    }
+}
+
+fn main() {
+    if let Err(message) = parse_args().and_then(run) {
+        eprintln!("{}", message);
+        process::exit(2);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use discovery_logic::packet::Packet;
+
+    /// The frame `discovery_support::wire` would send for `message`, without
+    /// the zero at its end.
+    fn frame(packet_type: u8, message: &Message) -> Vec<u8> {
+        let mut payload = [0; packet::MAX_PAYLOAD_LEN];
+        let payload = postcard::to_slice(message, &mut payload).unwrap();
+        let packet = Packet {
+            packet_type,
+            sequence: 7,
+            payload,
+        };
+        let mut buf = [0; packet::HEADER_LEN + packet::MAX_PAYLOAD_LEN + packet::CRC_LEN];
+        cobs::encode_vec(packet::encode(&packet, &mut buf).unwrap())
+    }
+
+    #[test]
+    fn readings_have_their_axes() {
+        let accel = Message::Acceleration(AccelSample { x: 1, y: -2, z: 3 });
+        assert_eq!(
+            reading(&accel),
+            Some((Channel::Acceleration, vec![1, -2, 3]))
+        );
+        let heading = Message::Heading(CompassHeading { degrees: -89.6 });
+        assert_eq!(reading(&heading), Some((Channel::Heading, vec![-90])));
+        assert_eq!(
+            reading(&Message::Temperature(-5)),
+            Some((Channel::Temperature, vec![-5]))
+        );
+    }
+
+    #[test]
+    fn a_button_is_one_while_its_held_down() {
+        let event = |button, pressed| Message::Button(ButtonEvent { button, pressed });
+        assert_eq!(
+            reading(&event(Button::A, true)),
+            Some((Channel::ButtonA, vec![1]))
+        );
+        assert_eq!(
+            reading(&event(Button::B, false)),
+            Some((Channel::ButtonB, vec![0]))
+        );
+    }
+
+    #[test]
+    fn other_messages_arent_readings() {
+        assert_eq!(reading(&Message::Ping), None);
+    }
+
+    #[test]
+    fn csv_leaves_missing_axes_empty() {
+        let elapsed = Duration::from_millis(1_250);
+        assert_eq!(
+            csv_line(elapsed, Channel::Acceleration, &[1, -2, 3]),
+            "1.250,acceleration,1,-2,3"
+        );
+        assert_eq!(
+            csv_line(elapsed, Channel::Heading, &[90]),
+            "1.250,heading,90,,"
+        );
+    }
+
+    #[test]
+    fn the_chart_spans_the_range() {
+        let mut plot = Plot::new();
+        let line = plot.line(Channel::Acceleration, &[-100, 0, 100]);
+        let chart = &line[line.find('[').unwrap() + 1..line.find(']').unwrap()];
+        let chart: Vec<char> = chart.chars().collect();
+        assert_eq!(chart.len(), WIDTH);
+        assert_eq!(chart[0], 'x');
+        assert_eq!(chart[WIDTH / 2], 'y');
+        assert_eq!(chart[WIDTH - 1], 'z');
+        assert!(line.ends_with("±100"));
+    }
+
+    #[test]
+    fn each_channel_keeps_its_own_range() {
+        let mut plot = Plot::new();
+        plot.line(Channel::Acceleration, &[1000, 0, 0]);
+        assert!(plot
+            .line(Channel::Acceleration, &[10, 0, 0])
+            .ends_with("±1000"));
+        assert!(plot.line(Channel::Temperature, &[10]).ends_with("±10"));
+    }
+
+    #[test]
+    fn decodes_what_the_micro_bit_sends() {
+        let accel = Message::Acceleration(AccelSample { x: 1, y: -2, z: 3 });
+        assert_eq!(decode_frame(&frame(types::MESSAGE, &accel)), Ok(accel));
+    }
+
+    #[test]
+    fn rejects_other_packets() {
+        let accel = Message::Acceleration(AccelSample { x: 1, y: -2, z: 3 });
+        assert!(decode_frame(&frame(types::DFU, &accel)).is_err());
+        assert!(decode_frame(&[]).is_err());
+    }
+}
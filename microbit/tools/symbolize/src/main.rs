@@ -0,0 +1,122 @@
+//! Looks up addresses in a program, the ones a HardFault report prints, and
+//! says which function and line of source they're in.
+//!
+//! The report of `discovery_support::hard_fault` ends with the command for
+//! the program counter and the link register:
+//!
+//! ```text
+//! symbolize ../../target/thumbv7em-none-eabihf/debug/crash-log 0x1a2c 0x1b05
+//! ```
+//!
+//! Without any addresses, it reads the lines of a report, or anything else,
+//! from standard input, and prints them with every address in them that's in
+//! the program looked up below them. The program has to be built with debug
+//! information, as the chapters are, in release builds too.
+
+use std::borrow::Cow;
+use std::env;
+use std::io::{self, BufRead};
+use std::process;
+
+use addr2line::Loader;
+
+const USAGE: &str = "usage: symbolize <elf> [<address>...]";
+
+/// Where `address` is, as `function at file:line`, with the functions it was
+/// inlined into after it, or `None` if it isn't in the program.
+///
+/// An odd address is one with the Thumb bit set, a return address like the
+/// one in `lr`. That's the instruction after the call, which may be on the
+/// next line already, so it's the call itself that's looked up.
+fn describe(loader: &Loader, address: u64) -> Result<Option<String>, String> {
+    let probe = if address & 1 != 0 {
+        (address & !1) - 1
+    } else {
+        address
+    };
+    let symbol = loader.find_symbol(probe);
+    let mut frames = loader
+        .find_frames(probe)
+        .map_err(|e| format!("reading the debug information failed: {}", e))?;
+
+    let mut description = String::new();
+    let mut outermost = None;
+    while let Some(frame) = frames.next().map_err(|e| e.to_string())? {
+        let function = frame.function.as_ref();
+        let name = function
+            .and_then(|function| function.demangle().ok())
+            .unwrap_or(Cow::Borrowed("??"));
+        outermost = function
+            .and_then(|function| function.raw_name().ok())
+            .map(Cow::into_owned);
+        if !description.is_empty() {
+            description.push_str("\n      inlined into ");
+        }
+        description.push_str(&name);
+        if let Some(location) = frame.location {
+            description.push_str(&format!(
+                " at {}:{}",
+                location.file.unwrap_or("??"),
+                location.line.unwrap_or(0)
+            ));
+        }
+    }
+
+    // The linker leaves the debug information of the functions it threw
+    // away in the file, at address 0 and up, where it covers the functions
+    // that are really there. The symbol table only has those, so when the
+    // two don't agree, it's the symbol table that's right.
+    if description.is_empty() || outermost.as_deref() != symbol {
+        return Ok(
+            symbol.map(|name| addr2line::demangle_auto(Cow::Borrowed(name), None).into_owned())
+        );
+    }
+    Ok(Some(description))
+}
+
+fn parse_address(text: &str) -> Option<u64> {
+    let digits = text
+        .strip_prefix("0x")
+        .or_else(|| text.strip_prefix("0X"))?;
+    u64::from_str_radix(digits, 16).ok()
+}
+
+fn run(mut args: impl Iterator<Item = String>) -> Result<(), String> {
+    let elf = args.next().ok_or(USAGE)?;
+    if elf == "-h" || elf == "--help" {
+        return Err(USAGE.to_string());
+    }
+    let loader = Loader::new(&elf).map_err(|e| format!("could not read {}: {}", elf, e))?;
+
+    let addresses: Vec<String> = args.collect();
+    if !addresses.is_empty() {
+        for text in addresses {
+            let address = parse_address(&text).ok_or(format!("not an address: {}", text))?;
+            match describe(&loader, address)? {
+                Some(description) => println!("{:#010x}: {}", address, description),
+                None => println!("{:#010x}: not in {}", address, elf),
+            }
+        }
+        return Ok(());
+    }
+
+    for line in io::stdin().lock().lines() {
+        let line = line.map_err(|e| format!("reading failed: {}", e))?;
+        println!("{}", line);
+        let words = line.split(|c: char| !c.is_ascii_alphanumeric());
+        for address in words.filter_map(parse_address) {
+            // Registers that hold data, not code, mostly aren't in the program
+            if let Some(description) = describe(&loader, address)? {
+                println!("    {:#010x}: {}", address, description);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn main() {
+    if let Err(message) = run(env::args().skip(1)) {
+        eprintln!("{}", message);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,332 @@
+//! Builds, flashes and sizes the chapters' programs, so that none of that
+//! needs `--features`, `--target` or a separate flashing tool.
+//!
+//! The board follows from the chip: the v2's nRF52833 (the default) builds
+//! with the `v2` feature for `thumbv7em-none-eabihf`, the v1's nRF51822 with
+//! `v1` for `thumbv6m-none-eabi`. A chapter can be named by its directory in
+//! `src`, or just by its number.
+//!
+//! ```text
+//! cargo xtask flash 07-uart
+//! cargo xtask flash 08 --example i2c-scan --chip nRF51822_xxAA
+//! cargo xtask build-all
+//! cargo xtask size 11 --release
+//! ```
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command, Stdio};
+
+use object::{elf, Object, ObjectSection, SectionFlags, SectionKind};
+use probe_rs::flashing::{self, FormatKind};
+use probe_rs::{Session, SessionConfig};
+use serde_json::Value;
+
+const USAGE: &str = "\
+usage: cargo xtask flash <chapter> [--chip <chip>] [--example <name>] [--release]
+       cargo xtask build-all [--chip <chip>]
+       cargo xtask size <chapter> [--chip <chip>] [--example <name>] [--release]";
+
+/// Where the RAM starts on both boards, everything below it is flash.
+const RAM_START: u64 = 0x2000_0000;
+
+/// The board a chip is on, and how its programs are built.
+struct Board {
+    chip: String,
+    feature: &'static str,
+    target: &'static str,
+}
+
+impl Board {
+    fn from_chip(chip: &str) -> Result<Board, String> {
+        let (feature, target) = match chip {
+            "nRF52833_xxAA" => ("v2", "thumbv7em-none-eabihf"),
+            "nRF51822_xxAA" => ("v1", "thumbv6m-none-eabi"),
+            _ => {
+                return Err(format!(
+                    "unknown chip {}, the micro:bits have an nRF52833_xxAA (v2) or an nRF51822_xxAA (v1)",
+                    chip
+                ))
+            }
+        };
+        Ok(Board {
+            chip: chip.to_string(),
+            feature,
+            target,
+        })
+    }
+}
+
+enum Task {
+    Flash(String),
+    BuildAll,
+    Size(String),
+}
+
+struct Options {
+    task: Task,
+    board: Board,
+    example: Option<String>,
+    release: bool,
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut args = env::args().skip(1);
+    let mut chip = "nRF52833_xxAA".to_string();
+    let mut example = None;
+    let mut release = false;
+    let mut positional = Vec::new();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--chip" => chip = args.next().ok_or("--chip needs a value")?,
+            "--example" => example = Some(args.next().ok_or("--example needs a value")?),
+            "--release" => release = true,
+            "-h" | "--help" => return Err(USAGE.to_string()),
+            _ => positional.push(arg),
+        }
+    }
+
+    let mut positional = positional.into_iter();
+    let task = match (positional.next().as_deref(), positional.next()) {
+        (Some("flash"), Some(chapter)) => Task::Flash(chapter),
+        (Some("build-all"), None) => Task::BuildAll,
+        (Some("size"), Some(chapter)) => Task::Size(chapter),
+        _ => return Err(USAGE.to_string()),
+    };
+    if positional.next().is_some() {
+        return Err(USAGE.to_string());
+    }
+
+    Ok(Options {
+        task,
+        board: Board::from_chip(&chip)?,
+        example,
+        release,
+    })
+}
+
+/// The `microbit` directory, which this crate sits in.
+fn root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .unwrap()
+        .to_path_buf()
+}
+
+/// Every chapter with a Cargo project, in order.
+fn chapters() -> Result<Vec<PathBuf>, String> {
+    let entries = fs::read_dir(root().join("src")).map_err(|e| format!("reading src: {}", e))?;
+    let mut chapters: Vec<PathBuf> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.join("Cargo.toml").is_file())
+        .collect();
+    chapters.sort();
+    Ok(chapters)
+}
+
+/// The chapter called `name`, `07-uart` or just `07`.
+fn find_chapter(name: &str) -> Result<PathBuf, String> {
+    chapters()?
+        .into_iter()
+        .find(|path| {
+            let dir = path.file_name().unwrap().to_string_lossy();
+            dir == name || dir.split('-').next() == Some(name)
+        })
+        .ok_or_else(|| format!("there is no chapter {} with a Cargo project", name))
+}
+
+fn has_feature(chapter: &Path, feature: &str) -> bool {
+    let manifest = fs::read_to_string(chapter.join("Cargo.toml")).unwrap_or_default();
+    let prefix = format!("{} =", feature);
+    manifest.lines().any(|line| line.starts_with(&prefix))
+}
+
+/// Build the chapter for `board`, with `targets` saying what to build, and
+/// return the programs that came out of it.
+fn build(
+    chapter: &Path,
+    board: &Board,
+    targets: &[&str],
+    release: bool,
+) -> Result<Vec<PathBuf>, String> {
+    let mut cargo = Command::new(env::var("CARGO").unwrap_or_else(|_| "cargo".to_string()));
+    // The chapter's `.cargo/config.toml` has the linker arguments, so cargo
+    // has to run in its directory
+    cargo
+        .current_dir(chapter)
+        .args(["build", "--message-format=json-render-diagnostics"])
+        .args(["--features", board.feature, "--target", board.target])
+        .args(targets)
+        .stdout(Stdio::piped());
+    if release {
+        cargo.arg("--release");
+    }
+
+    let output = cargo
+        .output()
+        .map_err(|e| format!("could not run cargo: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("building {} failed", chapter.display()));
+    }
+
+    // Cargo tells about every crate it has built on a line of its own, the
+    // programs are the ones with an executable
+    let executables = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .filter(|message| message["reason"] == "compiler-artifact")
+        .filter_map(|message| message["executable"].as_str().map(PathBuf::from))
+        .collect();
+    Ok(executables)
+}
+
+/// Build the chapter's program, or `example`, and return where it is.
+fn build_one(
+    chapter: &Path,
+    board: &Board,
+    example: Option<&str>,
+    release: bool,
+) -> Result<PathBuf, String> {
+    let targets = match example {
+        Some(example) => vec!["--example", example],
+        None => vec!["--bins"],
+    };
+    let mut executables = build(chapter, board, &targets, release)?;
+    match executables.len() {
+        1 => Ok(executables.remove(0)),
+        0 => Err(format!(
+            "{} has no program for the micro:bit {}, try --example",
+            chapter.display(),
+            board.feature
+        )),
+        _ => Err(format!(
+            "{} has more than one program, pick one with --example",
+            chapter.display()
+        )),
+    }
+}
+
+fn flash(program: &Path, board: &Board) -> Result<(), String> {
+    let mut session = Session::auto_attach(board.chip.as_str(), SessionConfig::default())
+        .map_err(|e| format!("could not attach to the micro:bit: {}", e))?;
+    println!("flashing {}", program.display());
+    flashing::download_file(&mut session, program, FormatKind::Elf)
+        .map_err(|e| format!("flashing failed: {}", e))?;
+    session
+        .core(0)
+        .and_then(|mut core| core.reset())
+        .map_err(|e| format!("could not reset the micro:bit: {}", e))?;
+    Ok(())
+}
+
+fn build_all(board: &Board) -> Result<bool, String> {
+    let mut failed = Vec::new();
+    for chapter in chapters()? {
+        let name = chapter.file_name().unwrap().to_string_lossy().into_owned();
+        // The setup chapter has no board features, it only checks the tools
+        if !has_feature(&chapter, board.feature) {
+            println!("skip {}", name);
+            continue;
+        }
+        match build(&chapter, board, &["--bins", "--examples"], false) {
+            Ok(_) => println!("ok   {}", name),
+            Err(_) => {
+                println!("FAIL {}", name);
+                failed.push(name);
+            }
+        }
+    }
+    if !failed.is_empty() {
+        println!("{} failed: {}", failed.len(), failed.join(", "));
+    }
+    Ok(failed.is_empty())
+}
+
+/// Print how much flash and RAM the program takes, section by section.
+///
+/// `.data` is in both: it's copied from the flash into the RAM at startup.
+fn size(program: &Path) -> Result<(), String> {
+    let data = fs::read(program).map_err(|e| format!("reading {}: {}", program.display(), e))?;
+    let file = object::File::parse(&*data)
+        .map_err(|e| format!("{} is not an ELF file: {}", program.display(), e))?;
+
+    let (mut flash, mut ram) = (0, 0);
+    println!("{:<16} {:>10} {:>8}", "section", "address", "size");
+    for section in file.sections() {
+        let allocated = match section.flags() {
+            SectionFlags::Elf { sh_flags } => sh_flags & u64::from(elf::SHF_ALLOC) != 0,
+            _ => false,
+        };
+        if !allocated || section.size() == 0 {
+            // Debug information and the like, which never gets flashed
+            continue;
+        }
+        let in_ram = section.address() >= RAM_START;
+        let loaded = !matches!(
+            section.kind(),
+            SectionKind::UninitializedData | SectionKind::UninitializedTls
+        );
+        if loaded {
+            flash += section.size();
+        }
+        if in_ram {
+            ram += section.size();
+        }
+        println!(
+            "{:<16} {:#010x} {:>8}",
+            section.name().unwrap_or("?"),
+            section.address(),
+            section.size()
+        );
+    }
+    println!("flash: {} bytes", flash);
+    println!("RAM:   {} bytes", ram);
+    Ok(())
+}
+
+fn run(options: Options) -> Result<bool, String> {
+    let Options {
+        task,
+        board,
+        example,
+        release,
+    } = options;
+
+    match task {
+        Task::Flash(chapter) => {
+            let program = build_one(
+                &find_chapter(&chapter)?,
+                &board,
+                example.as_deref(),
+                release,
+            )?;
+            flash(&program, &board)?;
+        }
+        Task::BuildAll => return build_all(&board),
+        Task::Size(chapter) => {
+            let program = build_one(
+                &find_chapter(&chapter)?,
+                &board,
+                example.as_deref(),
+                release,
+            )?;
+            size(&program)?;
+        }
+    }
+    Ok(true)
+}
+
+fn main() {
+    let result = parse_args().and_then(run);
+    match result {
+        Ok(true) => {}
+        Ok(false) => process::exit(1),
+        Err(message) => {
+            eprintln!("{}", message);
+            process::exit(2);
+        }
+    }
+}